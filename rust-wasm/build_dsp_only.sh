@@ -0,0 +1,14 @@
+#!/bin/bash
+# Checks that the DSP-only module subset builds on its own, without the wasm or
+# native hosts.
+# Run before pushing changes to lib.rs or the feature gates in Cargo.toml.
+
+echo "Building DSP-only module subset..."
+cargo build --lib --no-default-features --features dsp-only
+
+if [ $? -eq 0 ]; then
+    echo ""
+    echo "Build successful!"
+else
+    exit 1
+fi
@@ -0,0 +1,20 @@
+// Decodes arbitrary bytes as a sample import. The target node doesn't exist,
+// so a decoded file ends in an error too; only panics and aborts count.
+#![no_main]
+
+use audio_processor::audio_engine::{AudioEngine, JobRequest, JobStatus};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut engine = AudioEngine::new(48_000.0, 1);
+    engine.init(48_000.0, 1);
+    let request = JobRequest::ImportSample {
+        node_id: "00000000-0000-0000-0000-000000000000".to_string(),
+    };
+    let Ok(job) = engine.start_job(request, data.to_vec()) else {
+        return;
+    };
+    while let Ok(JobStatus::Running { .. }) = engine.poll_job(job) {
+        std::thread::yield_now();
+    }
+});
@@ -0,0 +1,23 @@
+// Decodes arbitrary bytes as an impulse response and swaps it into the plate
+// reverb's convolver, resampling when the file's rate differs.
+#![no_main]
+
+use audio_processor::audio_engine::{AudioEngine, JobRequest, JobStatus};
+use libfuzzer_sys::fuzz_target;
+
+/// The plate reverb in the default effect stack.
+const PLATE_EFFECT_ID: usize = 10_003;
+
+fuzz_target!(|data: &[u8]| {
+    let mut engine = AudioEngine::new(48_000.0, 1);
+    engine.init(48_000.0, 1);
+    let request = JobRequest::ImportImpulse {
+        effect_id: PLATE_EFFECT_ID,
+    };
+    let Ok(job) = engine.start_job(request, data.to_vec()) else {
+        return;
+    };
+    while let Ok(JobStatus::Running { .. }) = engine.poll_job(job) {
+        std::thread::yield_now();
+    }
+});
@@ -0,0 +1,24 @@
+// Decodes arbitrary bytes as a wavetable import. The first two bytes pick the
+// cycle length, the rest is the file.
+#![no_main]
+
+use audio_processor::audio_engine::{AudioEngine, JobRequest, JobStatus};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let [low, high, wav @ ..] = data else {
+        return;
+    };
+    let mut engine = AudioEngine::new(48_000.0, 1);
+    engine.init(48_000.0, 1);
+    let request = JobRequest::ImportWavetable {
+        node_id: "00000000-0000-0000-0000-000000000000".to_string(),
+        base_size: u16::from_le_bytes([*low, *high]) as usize,
+    };
+    let Ok(job) = engine.start_job(request, wav.to_vec()) else {
+        return;
+    };
+    while let Ok(JobStatus::Running { .. }) = engine.poll_job(job) {
+        std::thread::yield_now();
+    }
+});
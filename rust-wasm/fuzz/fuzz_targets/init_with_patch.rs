@@ -0,0 +1,19 @@
+// Loads arbitrary text as a patch, then renders a block from whatever loaded.
+// The wasm engine only builds for wasm32; it parses patches into the same
+// `PatchFile`, so its patch parsing is fuzzed here.
+#![no_main]
+
+use audio_processor::audio_engine::AudioEngine;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(patch_json) = std::str::from_utf8(data) else {
+        return;
+    };
+    let mut engine = AudioEngine::new(48_000.0, 1);
+    engine.init(48_000.0, 1);
+    if engine.init_with_patch(patch_json).is_ok() {
+        let (mut left, mut right) = (vec![0.0; 128], vec![0.0; 128]);
+        engine.process_audio(&[1.0], &[440.0], &[1.0], &[1.0], &[], 1.0, &mut left, &mut right);
+    }
+});
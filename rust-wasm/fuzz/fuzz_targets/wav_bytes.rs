@@ -0,0 +1,11 @@
+// Decodes arbitrary bytes with the in-memory WAV reader behind the wasm
+// engine's sample, sample zone and impulse response imports. The wasm engine
+// only builds for wasm32, so its imports are fuzzed through this reader.
+#![no_main]
+
+use audio_processor::nodes::SampleData;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = SampleData::from_wav_bytes(data);
+});
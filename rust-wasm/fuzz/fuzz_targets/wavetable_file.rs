@@ -0,0 +1,21 @@
+// Decodes arbitrary bytes as the `.wt` or WAV wavetable the wasm engine's
+// `import_wavetable` reads. The first two bytes pick the cycle length, the
+// rest is the file.
+#![no_main]
+
+use audio_processor::audio_engine::MAX_WAVETABLE_BASE_SIZE;
+use audio_processor::nodes::morph_wavetable::read_wavetable_file;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let [low, high, file @ ..] = data else {
+        return;
+    };
+    let base_size = u16::from_le_bytes([*low, *high]) as usize;
+    if base_size > MAX_WAVETABLE_BASE_SIZE {
+        return;
+    }
+    if let Ok(wavetable) = read_wavetable_file(file, base_size) {
+        assert!(wavetable.cycle_size > 0 && wavetable.samples.len() >= wavetable.cycle_size);
+    }
+});
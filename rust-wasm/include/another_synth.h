@@ -0,0 +1,48 @@
+/*
+ * C interface to the native synth engine. Build the library with
+ * `cargo build --release --no-default-features --features c-api` and link
+ * against the resulting cdylib. See src/audio_engine/ffi.rs for details.
+ *
+ * An engine is not thread-safe: send notes and render from one thread, or
+ * guard the calls with a lock.
+ */
+#ifndef ANOTHER_SYNTH_H
+#define ANOTHER_SYNTH_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct AudioEngine AudioEngine;
+
+/* Message of the last failed call on this thread, or NULL. */
+const char *synth_last_error(void);
+
+/* 0 voices or a block size of 0 pick the defaults. */
+AudioEngine *synth_engine_new(float sample_rate, size_t num_voices, size_t block_size);
+void synth_engine_free(AudioEngine *engine);
+
+/* Loads a patch file (UTF-8 JSON). Returns the voice count, or -1 on error. */
+int32_t synth_engine_load_patch(AudioEngine *engine, const char *patch_json);
+
+/* MIDI note number, velocity 0..1. Returns the voice index, or -1. */
+int32_t synth_engine_note_on(AudioEngine *engine, uint8_t note, float velocity);
+bool synth_engine_note_off(AudioEngine *engine, uint8_t note);
+void synth_engine_all_notes_off(AudioEngine *engine);
+/* 0 = oldest, 1 = quietest, 2 = same note, 3 = release first. */
+void synth_engine_set_steal_mode(AudioEngine *engine, uint8_t mode);
+void synth_engine_set_tempo(AudioEngine *engine, double bpm);
+
+/* Renders `frames` frames into `left` and `right`; call from the audio callback. */
+bool synth_engine_process(AudioEngine *engine, float master_gain, float *left, float *right,
+                          size_t frames);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ANOTHER_SYNTH_H */
@@ -0,0 +1,532 @@
+// src/audio_engine/api.rs
+//
+// Object forms of the wasm engine's positional update calls. Every
+// `update_*_params` method takes one of these structs as a plain object with
+// camelCase keys, e.g. `update_envelope_params(id, { attack: 0.01, ... })`,
+// and the positional `update_*` methods wrap the same code, so both stay in
+// step. `api_schema` describes each object as JSON Schema for hosts that
+// validate or generate bindings; the engine serves it from `get_api_schema`.
+// Calls that already take a struct (oscillators, noise, LFOs) or a single
+// value keep only their original form.
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+use serde_json::{json, Map, Value};
+
+use super::patch_loader::filter_type_from_i32;
+use crate::biquad::FilterType;
+use crate::nodes::{AutoWahDirection, EqBandType, FilterSlope, LooperSpeed, SaturationCharacter};
+
+/// `update_*_params` calls addressing a master effect by its numeric id; the
+/// others take a voice node's UUID string.
+pub const EFFECT_UPDATE_METHODS: &[&str] = &[
+    "update_auto_wah_params",
+    "update_binaural_params",
+    "update_bitcrusher_params",
+    "update_chorus_params",
+    "update_compressor_params",
+    "update_convolver_params",
+    "update_delay_params",
+    "update_eq_band_dynamics_params",
+    "update_eq_band_params",
+    "update_eq_params",
+    "update_exciter_params",
+    "update_looper_params",
+    "update_multiband_band_params",
+    "update_multiband_params",
+    "update_noise_gate_params",
+    "update_parallel_params",
+    "update_reverb_params",
+    "update_saturation_params",
+    "update_saturation_tone_params",
+    "update_stereo_enhancer_params",
+];
+
+/// JSON Schema of an update field type.
+pub trait ApiType {
+    /// Whether the field must be present.
+    const REQUIRED: bool = true;
+    fn schema() -> Value;
+}
+
+macro_rules! api_type {
+    ($($ty:ty => $schema:tt,)*) => {
+        $(
+            impl ApiType for $ty {
+                fn schema() -> Value {
+                    json!($schema)
+                }
+            }
+        )*
+    };
+}
+
+api_type! {
+    f32 => { "type": "number" },
+    bool => { "type": "boolean" },
+    u8 => { "type": "integer", "minimum": 0, "maximum": 255 },
+    u32 => { "type": "integer", "minimum": 0 },
+    usize => { "type": "integer", "minimum": 0 },
+    Vec<f32> => { "type": "array", "items": { "type": "number" } },
+    SaturationCharacter => { "type": "string", "enum": ["soft", "tape", "tube", "diode"] },
+    AutoWahDirection => { "type": "string", "enum": ["up", "down"] },
+    LooperSpeed => { "type": "string", "enum": ["half", "normal", "double"] },
+    EqBandType => {
+        "type": "string",
+        "enum": ["peak", "lowShelf", "highShelf", "lowCut", "highCut", "notch", "bandPass"]
+    },
+    FilterSlope => {
+        "type": "integer",
+        "enum": [0, 1],
+        "description": "0 = 12 dB/oct, 1 = 24 dB/oct"
+    },
+    FilterType => {
+        "type": "integer",
+        "minimum": 0,
+        "maximum": 8,
+        "description": "0 = low-pass, 1 = low shelf, 2 = peaking, 3 = high shelf, 4 = notch, \
+                        5 = high-pass, 6 = ladder, 7 = comb, 8 = band-pass"
+    },
+}
+
+impl<T: ApiType> ApiType for Option<T> {
+    const REQUIRED: bool = false;
+
+    fn schema() -> Value {
+        T::schema()
+    }
+}
+
+/// Filter types travel as their patch-file numbers.
+fn deserialize_filter_type<'de, D>(deserializer: D) -> Result<FilterType, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    filter_type_from_i32(i32::deserialize(deserializer)?).map_err(D::Error::custom)
+}
+
+fn camel_case(name: &str) -> String {
+    let mut parts = name.split('_');
+    let mut out = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            out.extend(first.to_uppercase());
+            out.push_str(chars.as_str());
+        }
+    }
+    out
+}
+
+fn object_schema(title: &str, doc: &[&str], fields: &[(&str, Value, bool)]) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for (name, schema, is_required) in fields {
+        let key = camel_case(name);
+        if *is_required {
+            required.push(Value::String(key.clone()));
+        }
+        properties.insert(key, schema.clone());
+    }
+    let description: Vec<&str> = doc.iter().map(|line| line.trim()).collect();
+    json!({
+        "title": title,
+        "description": description.join(" "),
+        "type": "object",
+        "properties": properties,
+        "required": required
+    })
+}
+
+macro_rules! update_params {
+    ($(
+        $(#[doc = $doc:literal])*
+        $name:ident for [$($method:literal),+ $(,)?] {
+            $($(#[$field_meta:meta])* $field:ident: $ty:ty,)*
+        }
+    )*) => {
+        $(
+            $(#[doc = $doc])*
+            #[derive(Debug, Clone, Deserialize)]
+            #[serde(rename_all = "camelCase")]
+            pub struct $name {
+                $($(#[$field_meta])* pub $field: $ty,)*
+            }
+        )*
+
+        /// JSON Schema of the object every `update_*_params` call takes, keyed
+        /// by method name.
+        pub fn api_schema() -> Value {
+            let mut methods = Map::new();
+            $(
+                let schema = object_schema(
+                    stringify!($name),
+                    &[$($doc),*],
+                    &[$((
+                        stringify!($field),
+                        <$ty as ApiType>::schema(),
+                        <$ty as ApiType>::REQUIRED,
+                    )),*],
+                );
+                $(methods.insert($method.to_string(), schema.clone());)+
+            )*
+            Value::Object(methods)
+        }
+    };
+}
+
+update_params! {
+    /// Velocity sensitivity and random velocity spread of a velocity node.
+    VelocityUpdate for ["update_velocity_params"] {
+        sensitivity: f32,
+        randomize: f32,
+    }
+
+    /// ADSR times (seconds), sustain level and segment curves of an envelope.
+    EnvelopeUpdate for ["update_envelope_params"] {
+        attack: f32,
+        decay: f32,
+        sustain: f32,
+        release: f32,
+        attack_curve: f32,
+        decay_curve: f32,
+        release_curve: f32,
+        active: bool,
+    }
+
+    /// Time, feedback and wet level of a master delay.
+    DelayUpdate for ["update_delay_params"] {
+        delay_ms: f32,
+        feedback: f32,
+        wet_mix: f32,
+        enabled: bool,
+    }
+
+    /// Drive and mix of a saturation.
+    SaturationUpdate for ["update_saturation_params", "update_voice_saturation_params"] {
+        drive: f32,
+        mix: f32,
+        active: bool,
+    }
+
+    /// Transfer curve, pre/post tilt EQ (dB) and auto-gain of a saturation.
+    SaturationToneUpdate for [
+        "update_saturation_tone_params",
+        "update_voice_saturation_tone_params",
+    ] {
+        character: SaturationCharacter,
+        pre_tilt_db: f32,
+        post_tilt_db: f32,
+        auto_gain: bool,
+    }
+
+    /// Bit depth, sample-rate reduction and mix of a bitcrusher.
+    BitcrusherUpdate for ["update_bitcrusher_params", "update_voice_bitcrusher_params"] {
+        bits: u8,
+        downsample_factor: usize,
+        mix: f32,
+        active: bool,
+    }
+
+    /// Haas delays, width and comb compensation of a stereo enhancer.
+    StereoEnhancerUpdate for [
+        "update_stereo_enhancer_params",
+        "update_voice_stereo_enhancer_params",
+    ] {
+        active: bool,
+        delay_left_ms: f32,
+        delay_right_ms: f32,
+        width: f32,
+        comb_compensation: f32,
+        mix: f32,
+    }
+
+    /// Source direction (degrees) and mix of a binaural panner.
+    BinauralUpdate for ["update_binaural_params", "update_voice_binaural_params"] {
+        active: bool,
+        azimuth: f32,
+        elevation: f32,
+        mix: f32,
+    }
+
+    /// Envelope follower and band-pass sweep of an auto-wah.
+    AutoWahUpdate for ["update_auto_wah_params", "update_voice_auto_wah_params"] {
+        active: bool,
+        sensitivity: f32,
+        frequency: f32,
+        range: f32,
+        q: f32,
+        direction: AutoWahDirection,
+        mix: f32,
+    }
+
+    /// High-pass corner, harmonic amount and mix of the exciter insert.
+    ExciterUpdate for ["update_exciter_params"] {
+        active: bool,
+        frequency: f32,
+        amount: f32,
+        mix: f32,
+    }
+
+    /// Threshold, hysteresis, timing (ms) and range of a noise gate.
+    NoiseGateUpdate for ["update_noise_gate_params", "update_voice_noise_gate_params"] {
+        active: bool,
+        threshold_db: f32,
+        hysteresis_db: f32,
+        attack_ms: f32,
+        hold_ms: f32,
+        release_ms: f32,
+        range_db: f32,
+    }
+
+    /// Crossover frequencies of a multiband container; 1 to 3 crossovers give 2 to 4 bands.
+    MultibandUpdate for ["update_multiband_params"] {
+        active: bool,
+        crossovers: Vec<f32>,
+    }
+
+    /// Linear gain, mute and solo of one band of a multiband container.
+    MultibandBandUpdate for ["update_multiband_band_params"] {
+        band: usize,
+        gain: f32,
+        muted: bool,
+        soloed: bool,
+    }
+
+    /// Balance of a parallel container.
+    ParallelUpdate for ["update_parallel_params"] {
+        active: bool,
+        blend: f32,
+    }
+
+    /// Band count of a parametric EQ; added bands start as flat peaks.
+    EqUpdate for ["update_eq_params"] {
+        active: bool,
+        band_count: usize,
+    }
+
+    /// Shape, frequency (Hz), Q and gain (dB) of one parametric EQ band.
+    EqBandUpdate for ["update_eq_band_params"] {
+        band: usize,
+        band_type: EqBandType,
+        frequency: f32,
+        q: f32,
+        gain_db: f32,
+    }
+
+    /// Dynamic mode of one parametric EQ band: threshold (dBFS), ratio and attack and release
+    /// (ms) of the gain reduction; off when `enabled` is false.
+    EqBandDynamicsUpdate for ["update_eq_band_dynamics_params"] {
+        band: usize,
+        enabled: bool,
+        threshold_db: f32,
+        ratio: f32,
+        attack_ms: f32,
+        release_ms: f32,
+    }
+
+    /// Playback speed, direction, overdub feedback and level of the looper.
+    LooperUpdate for ["update_looper_params"] {
+        active: bool,
+        speed: LooperSpeed,
+        reverse: bool,
+        feedback: f32,
+        level: f32,
+    }
+
+    /// Wet level of a convolution reverb.
+    ConvolverUpdate for ["update_convolver_params"] {
+        wet_mix: f32,
+        enabled: bool,
+    }
+
+    /// Room size, damping, levels and width of the Freeverb.
+    ReverbUpdate for ["update_reverb_params"] {
+        active: bool,
+        room_size: f32,
+        damp: f32,
+        wet: f32,
+        dry: f32,
+        width: f32,
+    }
+
+    /// Threshold, ratio, timing (ms), makeup gain and mix of the compressor.
+    CompressorUpdate for ["update_compressor_params"] {
+        active: bool,
+        threshold_db: f32,
+        ratio: f32,
+        attack_ms: f32,
+        release_ms: f32,
+        makeup_gain_db: f32,
+        mix: f32,
+    }
+
+    /// Delay, modulation depth and rate, feedback and mix of the chorus.
+    ChorusUpdate for ["update_chorus_params"] {
+        active: bool,
+        base_delay_ms: f32,
+        depth_ms: f32,
+        lfo_rate_hz: f32,
+        feedback: f32,
+        feedback_filter: f32,
+        mix: f32,
+        stereo_phase_offset_deg: f32,
+    }
+
+    /// Shared settings of a dual filter (routing 0 = serial, 1 = parallel, 2 = split).
+    DualFilterUpdate for ["update_dual_filter_params"] {
+        active: bool,
+        routing: u8,
+        balance: f32,
+        spacing: f32,
+        cutoff: f32,
+        key_tracking: f32,
+    }
+
+    /// Vowel (0 = A, 1 = E, 2 = I, 3 = O, 4 = U), formant shift (-1 to 1), resonance and mix of
+    /// a formant filter.
+    FormantFilterUpdate for ["update_formant_filter_params"] {
+        active: bool,
+        vowel: u8,
+        gender: f32,
+        resonance: f32,
+        mix: f32,
+    }
+
+    /// Gate length and delay (ms), clock division and multiplication and probability of a gate
+    /// tool; a length of 0 follows the incoming gate.
+    GateToolUpdate for ["update_gate_tool_params"] {
+        active: bool,
+        length_ms: f32,
+        delay_ms: f32,
+        division: u32,
+        multiplication: u32,
+        probability: f32,
+    }
+
+    /// Division (beats) and pulse width of a clock node.
+    ClockUpdate for ["update_clock_params"] {
+        active: bool,
+        division_beats: f32,
+        pulse_width: f32,
+    }
+
+    /// Probability, mode (0 = route, 1 = hold, 2 = attenuate), attenuation, seed and randomness (0
+    /// = per voice, 1 = global) of a Chance node.
+    ChanceUpdate for ["update_chance_params"] {
+        active: bool,
+        probability: f32,
+        mode: u8,
+        attenuation: f32,
+        seed: u32,
+        randomness: u8,
+    }
+
+    /// Waveform (0 = sine, 1 = half sine, 2 = abs sine, 3 = triangle, 4 = square, 5 = saw),
+    /// ratio or fixed frequency (Hz), detune (cents, plus optional octaves and semitones),
+    /// feedback and level of an FM operator.
+    FmOperatorUpdate for ["update_fm_operator_params"] {
+        waveform: u8,
+        fixed: bool,
+        ratio: f32,
+        fixed_frequency: f32,
+        detune_oct: Option<f32>,
+        detune_semi: Option<f32>,
+        detune: f32,
+        feedback: f32,
+        level: f32,
+        active: bool,
+    }
+
+    /// One filter of a dual filter (slot 0 = A, 1 = B).
+    DualFilterSlotUpdate for ["update_dual_filter_slot_params"] {
+        slot: usize,
+        #[serde(deserialize_with = "deserialize_filter_type")]
+        filter_type: FilterType,
+        filter_slope: FilterSlope,
+        resonance: f32,
+        gain: f32,
+    }
+
+    /// Glide time (seconds) of a glide node.
+    GlideUpdate for ["update_glide_params"] {
+        glide_time: f32,
+        active: bool,
+    }
+
+    /// Pitch, gain, loop region and crossfade (ms) and trigger mode of a
+    /// sampler, and its playback mode (0 = repitch, 1 = stretch) with the
+    /// stretch grain size (ms) and quality (overlapping grains, 2 to 8).
+    SamplerUpdate for ["update_sampler_params"] {
+        frequency: f32,
+        gain: f32,
+        loop_mode: u8,
+        loop_start: f32,
+        loop_end: f32,
+        loop_crossfade: Option<f32>,
+        root_note: f32,
+        trigger_mode: u8,
+        active: bool,
+        playback_mode: Option<u8>,
+        grain_size: Option<f32>,
+        stretch_quality: Option<u8>,
+    }
+
+    /// Cutoff, resonance, gain, key tracking, comb settings, type and slope of a filter.
+    FilterUpdate for ["update_filters_params"] {
+        cutoff: f32,
+        resonance: f32,
+        gain: f32,
+        key_tracking: f32,
+        comb_frequency: f32,
+        comb_dampening: f32,
+        oversampling: Option<u32>,
+        #[serde(deserialize_with = "deserialize_filter_type")]
+        filter_type: FilterType,
+        filter_slope: FilterSlope,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_matches_deserialized_fields() {
+        let schema = api_schema();
+        let envelope = &schema["update_envelope_params"];
+        assert_eq!(envelope["title"], "EnvelopeUpdate");
+        assert_eq!(envelope["properties"]["attackCurve"]["type"], "number");
+        assert_eq!(envelope["required"].as_array().unwrap().len(), 8);
+        assert_eq!(
+            schema["update_voice_noise_gate_params"],
+            schema["update_noise_gate_params"]
+        );
+        // Oversampling may be left out to keep the filter's current factor.
+        assert_eq!(
+            schema["update_filters_params"]["required"]
+                .as_array()
+                .unwrap()
+                .len(),
+            8
+        );
+
+        let update: FilterUpdate = serde_json::from_value(json!({
+            "cutoff": 1000.0,
+            "resonance": 0.5,
+            "gain": 0.5,
+            "keyTracking": 1.0,
+            "combFrequency": 220.0,
+            "combDampening": 0.5,
+            "filterType": 5,
+            "filterSlope": 1,
+        }))
+        .unwrap();
+        assert_eq!(update.filter_type, FilterType::HighPass);
+        assert_eq!(update.filter_slope, FilterSlope::Db24);
+        assert_eq!(update.oversampling, None);
+
+        let invalid = serde_json::from_value::<FilterUpdate>(json!({ "cutoff": 1000.0 }));
+        assert!(invalid.is_err());
+    }
+}
@@ -0,0 +1,175 @@
+// src/audio_engine/auto_level.rs
+//
+// Starting levels for new nodes. The voice's output node is treated as the
+// mix bus: what is wired into it is added up, oscillator gain times connection
+// amount, as an estimate of the voice peak. A new oscillator starts at the
+// average gain of the oscillators already there, and the suggested amount for
+// wiring it into the mix fills whatever headroom is left, but never drops
+// below an equal-power share of the inputs.
+
+use serde::Serialize;
+
+use crate::graph::AudioGraph;
+use crate::nodes::{AnalogOscillator, WavetableOscillator};
+use crate::{AudioNode, PortId};
+
+/// Headroom reported for a voice with nothing wired into its output.
+const MAX_HEADROOM_DB: f32 = 96.0;
+
+/// What a new node will do in the voice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NodeRole {
+    /// Adds signal of its own (oscillators).
+    Source,
+    /// Works on a signal already in the voice (filters).
+    Processor,
+}
+
+/// Levels chosen for a new node, for the host to show and to use when wiring it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeLevels {
+    /// Gain the node was created with.
+    pub gain: f32,
+    /// Suggested connection amount into the output mixer's audio input.
+    pub mixer_input_gain: f32,
+    /// Estimated peak headroom of the voice before the node was added, in dB.
+    pub headroom_db: f32,
+}
+
+fn oscillator_gain(node: &dyn AudioNode) -> Option<f32> {
+    let node = node.as_any();
+    node.downcast_ref::<AnalogOscillator>()
+        .map(AnalogOscillator::gain)
+        .or_else(|| {
+            node.downcast_ref::<WavetableOscillator>()
+                .map(WavetableOscillator::gain)
+        })
+}
+
+/// Levels for a node about to be added to `graph`. Processors keep unity gain
+/// and take over the level of the path they are inserted into.
+pub(crate) fn node_levels(graph: &AudioGraph, role: NodeRole) -> NodeLevels {
+    let inputs: Vec<f32> = graph
+        .output_node
+        .map(|output| {
+            graph
+                .connections
+                .values()
+                .filter(|c| c.to_node == output && c.to_port == PortId::AudioInput0)
+                .map(|c| {
+                    let gain = graph
+                        .get_node(c.from_node)
+                        .and_then(|node| oscillator_gain(node.as_ref()))
+                        .unwrap_or(1.0);
+                    c.amount.abs() * gain
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let peak: f32 = inputs.iter().sum();
+    let headroom_db = if peak > 0.0 {
+        (-20.0 * peak.log10()).min(MAX_HEADROOM_DB)
+    } else {
+        MAX_HEADROOM_DB
+    };
+
+    if role == NodeRole::Processor {
+        return NodeLevels {
+            gain: 1.0,
+            mixer_input_gain: 1.0,
+            headroom_db,
+        };
+    }
+
+    let gains: Vec<f32> = graph
+        .nodes
+        .values()
+        .filter_map(|node| oscillator_gain(node.as_ref()))
+        .collect();
+    let gain = if gains.is_empty() {
+        1.0
+    } else {
+        (gains.iter().sum::<f32>() / gains.len() as f32).clamp(0.0, 1.0)
+    };
+    let share = 1.0 / ((inputs.len() + 1) as f32).sqrt();
+    let mixer_input_gain = if gain > 0.0 {
+        ((1.0 - peak) / gain).clamp(share, 1.0)
+    } else {
+        1.0
+    };
+    NodeLevels {
+        gain,
+        mixer_input_gain,
+        headroom_db,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Connection, ModulationTransformation, ModulationType};
+    use crate::nodes::{Mixer, Waveform};
+    use crate::NodeId;
+    use rustc_hash::FxHashMap;
+    use std::sync::Arc;
+
+    fn oscillator(gain: f32) -> Box<AnalogOscillator> {
+        let banks = Arc::new(FxHashMap::default());
+        let mut osc = AnalogOscillator::new(48_000.0, Waveform::Sine, banks);
+        osc.set_gain(gain);
+        Box::new(osc)
+    }
+
+    fn connect(graph: &mut AudioGraph, from_node: NodeId, to_node: NodeId, amount: f32) {
+        graph.add_connection(Connection {
+            from_node,
+            from_port: PortId::AudioOutput0,
+            to_node,
+            to_port: PortId::AudioInput0,
+            amount,
+            modulation_type: ModulationType::Additive,
+            modulation_transform: ModulationTransformation::None,
+        });
+    }
+
+    #[test]
+    fn first_source_gets_full_level() {
+        let mut graph = AudioGraph::new(128);
+        let mixer = NodeId::new();
+        graph.add_node_with_id(mixer, Box::new(Mixer::new()));
+        graph.set_output_node(mixer);
+        let levels = node_levels(&graph, NodeRole::Source);
+        assert_eq!((levels.gain, levels.mixer_input_gain), (1.0, 1.0));
+        assert_eq!(levels.headroom_db, MAX_HEADROOM_DB);
+    }
+
+    #[test]
+    fn new_sources_fit_the_remaining_headroom() {
+        let mut graph = AudioGraph::new(128);
+        let mixer = NodeId::new();
+        graph.add_node_with_id(mixer, Box::new(Mixer::new()));
+        graph.set_output_node(mixer);
+        let first = NodeId::new();
+        graph.add_node_with_id(first, oscillator(0.5));
+        connect(&mut graph, first, mixer, 0.5);
+
+        // One input at 0.25 leaves room for another at 0.5 * 1.0.
+        let levels = node_levels(&graph, NodeRole::Source);
+        assert_eq!(levels.gain, 0.5);
+        assert_eq!(levels.mixer_input_gain, 1.0);
+        assert!((levels.headroom_db - 12.04).abs() < 0.01, "{:?}", levels);
+
+        // With the bus already over full scale, the next input comes in at an equal-power share.
+        let second = NodeId::new();
+        graph.add_node_with_id(second, oscillator(0.5));
+        connect(&mut graph, second, mixer, 2.0);
+        let levels = node_levels(&graph, NodeRole::Source);
+        assert!((levels.mixer_input_gain - 1.0 / 3.0f32.sqrt()).abs() < 1e-6);
+        assert!(levels.headroom_db < 0.0);
+        assert_eq!(
+            node_levels(&graph, NodeRole::Processor).mixer_input_gain,
+            1.0
+        );
+    }
+}
@@ -0,0 +1,188 @@
+// src/audio_engine/chain_response.rs
+//
+// Magnitude response of a voice's tone-shaping chain: the filters and
+// saturation stages between the oscillators and the output node. Copies of
+// the stages are fed a small impulse in series, so saturation is measured at
+// its small-signal gain, and the spectrum of the result is sampled on a
+// log-frequency axis for display.
+
+use rustc_hash::FxHashMap;
+use rustfft::{num_complex::Complex, FftPlanner};
+
+use crate::graph::{AudioGraph, ModulationSource, ModulationTransformation, ModulationType};
+use crate::traits::{AudioNode, PortId};
+use crate::NodeId;
+
+const MIN_IMPULSE_LENGTH: usize = 4096;
+const BLOCK_SIZE: usize = 128;
+/// Impulse height; low enough to keep saturation in its linear region.
+const IMPULSE_LEVEL: f32 = 1e-3;
+const MIN_FREQ_HZ: f32 = 20.0;
+const MAX_FREQ_HZ: f32 = 20_000.0;
+const FLOOR_DB: f32 = -120.0;
+
+/// Copies of the stages feeding `graph`'s output node, first stage first.
+/// Walks back from the output for as long as a node has exactly one source
+/// that can be copied and takes audio in (filters, saturation); oscillators
+/// and other sources end the chain.
+pub fn serial_chain(graph: &AudioGraph) -> Vec<Box<dyn AudioNode>> {
+    let mut chain = Vec::new();
+    let mut visited: Vec<NodeId> = Vec::new();
+    let Some(mut current) = graph.output_node else {
+        return chain;
+    };
+    loop {
+        let mut sources: Vec<NodeId> = Vec::new();
+        for connection in graph.connections.values() {
+            if connection.to_node == current
+                && connection.to_port.is_audio_input()
+                && !sources.contains(&connection.from_node)
+            {
+                sources.push(connection.from_node);
+            }
+        }
+        let mut stages = sources.iter().filter_map(|id| {
+            graph
+                .get_node(*id)
+                .filter(|node| node.get_ports().keys().any(PortId::is_audio_input))
+                .and_then(|node| node.clone_node())
+                .map(|node| (*id, node))
+        });
+        let (Some((id, stage)), None) = (stages.next(), stages.next()) else {
+            break;
+        };
+        if visited.contains(&id) {
+            break;
+        }
+        visited.push(id);
+        chain.push(stage);
+        current = id;
+    }
+    chain.reverse();
+    chain
+}
+
+/// Magnitude response in dB of `chain` run in series, at `points`
+/// frequencies spaced logarithmically from 20 Hz to 20 kHz (or Nyquist).
+pub fn chain_response(
+    mut chain: Vec<Box<dyn AudioNode>>,
+    sample_rate: f32,
+    points: usize,
+) -> Vec<f32> {
+    if points == 0 {
+        return Vec::new();
+    }
+    let length = (points * 4).max(MIN_IMPULSE_LENGTH).next_power_of_two();
+    let mut signal = vec![0.0; length];
+    signal[0] = IMPULSE_LEVEL;
+    for stage in &mut chain {
+        stage.reset();
+        signal = run_stage(stage.as_mut(), &signal);
+    }
+
+    let mut spectrum: Vec<Complex<f32>> = signal
+        .into_iter()
+        .map(|x| Complex { re: x, im: 0.0 })
+        .collect();
+    FftPlanner::<f32>::new()
+        .plan_fft_forward(length)
+        .process(&mut spectrum);
+    let magnitude_db: Vec<f32> = spectrum[..length / 2]
+        .iter()
+        .map(|bin| (20.0 * (bin.norm() / IMPULSE_LEVEL).log10()).max(FLOOR_DB))
+        .collect();
+
+    let max_freq = MAX_FREQ_HZ.min(sample_rate * 0.5);
+    let last_bin = magnitude_db.len() - 1;
+    (0..points)
+        .map(|i| {
+            let position = if points > 1 {
+                i as f32 / (points - 1) as f32
+            } else {
+                0.0
+            };
+            let freq = MIN_FREQ_HZ * (max_freq / MIN_FREQ_HZ).powf(position);
+            let bin = (freq * length as f32 / sample_rate).min(last_bin as f32);
+            let below = bin.floor() as usize;
+            let above = (below + 1).min(last_bin);
+            let frac = bin - below as f32;
+            magnitude_db[below] * (1.0 - frac) + magnitude_db[above] * frac
+        })
+        .collect()
+}
+
+/// Runs `input` through one stage, block by block, the way the graph does:
+/// both audio inputs get the signal and stages that are switched off output
+/// silence.
+fn run_stage(stage: &mut dyn AudioNode, input: &[f32]) -> Vec<f32> {
+    let mut output = vec![0.0; input.len()];
+    if !stage.should_process() {
+        return output;
+    }
+    let mut right = vec![0.0; BLOCK_SIZE];
+    for (block_in, block_out) in input.chunks(BLOCK_SIZE).zip(output.chunks_mut(BLOCK_SIZE)) {
+        let source = || {
+            vec![ModulationSource {
+                buffer: block_in,
+                amount: 1.0,
+                mod_type: ModulationType::Additive,
+                transformation: ModulationTransformation::None,
+            }]
+        };
+        let mut inputs = FxHashMap::default();
+        inputs.insert(PortId::AudioInput0, source());
+        inputs.insert(PortId::AudioInput1, source());
+        let len = block_in.len();
+        let mut outputs = FxHashMap::default();
+        outputs.insert(PortId::AudioOutput0, block_out);
+        outputs.insert(PortId::AudioOutput1, &mut right[..len]);
+        stage.process(&inputs, &mut outputs, len);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Connection;
+    use crate::nodes::{FilterCollection, Lfo, Mixer};
+    use uuid::Uuid;
+
+    #[test]
+    fn measures_the_filters_between_source_and_output() {
+        let sample_rate = 48_000.0;
+        let mut graph = AudioGraph::new(BLOCK_SIZE);
+        let ids: Vec<NodeId> = (0..4).map(|_| NodeId(Uuid::new_v4())).collect();
+        let mut filter = FilterCollection::new(sample_rate);
+        filter.set_params(1_000.0, 0.0);
+        graph.add_node_with_id(ids[0], Box::new(Lfo::new(sample_rate)));
+        graph.add_node_with_id(ids[1], Box::new(filter.clone()));
+        graph.add_node_with_id(ids[2], Box::new(filter));
+        graph.add_node_with_id(ids[3], Box::new(Mixer::new()));
+        for pair in ids.windows(2) {
+            graph.add_connection(Connection {
+                from_node: pair[0],
+                from_port: PortId::AudioOutput0,
+                to_node: pair[1],
+                to_port: PortId::AudioInput0,
+                amount: 1.0,
+                modulation_type: ModulationType::Additive,
+                modulation_transform: ModulationTransformation::None,
+            });
+        }
+        graph.set_output_node(ids[3]);
+
+        let chain = serial_chain(&graph);
+        assert_eq!(chain.len(), 2);
+        let response = chain_response(chain, sample_rate, 64);
+        assert_eq!(response.len(), 64);
+        assert!(response[0].abs() < 1.0, "passband at {} dB", response[0]);
+        // Two stacked low-passes fall off faster than one.
+        let mut single = FilterCollection::new(sample_rate);
+        single.set_params(1_000.0, 0.0);
+        let single = chain_response(vec![Box::new(single)], sample_rate, 64);
+        assert!(response[63] < single[63] - 10.0);
+        let open = FilterCollection::new(sample_rate);
+        assert!(chain_response(vec![Box::new(open)], sample_rate, 64)[40].abs() < 1.0);
+    }
+}
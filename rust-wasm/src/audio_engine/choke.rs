@@ -0,0 +1,201 @@
+// src/audio_engine/choke.rs
+//
+// Choke (exclusive) groups for the voice pool. The host tags a voice with a
+// group when it allocates a note there; when a voice's gate rises, every other
+// voice of the same group is released and faded out over a few milliseconds.
+// A choked voice stays silent, even if the host still holds its gate, until its
+// gate is released and triggered again. A voice the allocator steals fades out
+// the same way before its new note starts.
+
+/// Length of the fade applied to a choked voice.
+pub const CHOKE_FADE_SECONDS: f32 = 0.005;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct ChokeState {
+    group: Option<u8>,
+    last_gate: f32,
+    /// Gate ignored until the host releases it.
+    held_off: bool,
+    fade_gain: Option<f32>,
+    /// Fading out before a new note; the gate opens again when the fade ends.
+    stolen: bool,
+}
+
+#[derive(Debug)]
+pub struct ChokeGroups {
+    voices: Vec<ChokeState>,
+    fade_step: f32,
+}
+
+impl ChokeGroups {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut groups = Self {
+            voices: Vec::new(),
+            fade_step: 1.0,
+        };
+        groups.set_sample_rate(sample_rate);
+        groups
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.fade_step = 1.0 / (CHOKE_FADE_SECONDS * sample_rate).max(1.0);
+    }
+
+    /// Puts a voice into a choke group, or takes it out with `None`.
+    pub fn set_group(&mut self, voice_index: usize, group: Option<u8>) {
+        if self.voices.len() <= voice_index {
+            if group.is_none() {
+                return;
+            }
+            self.voices.resize(voice_index + 1, ChokeState::default());
+        }
+        self.voices[voice_index].group = group;
+    }
+
+    pub fn group(&self, voice_index: usize) -> Option<u8> {
+        self.voices.get(voice_index).and_then(|state| state.group)
+    }
+
+    pub fn clear(&mut self) {
+        self.voices.clear();
+    }
+
+    /// Fades out a voice that has been given a new note. Its gate is held off
+    /// until the fade ends, so the new note starts on a fresh gate.
+    pub fn steal(&mut self, voice_index: usize) {
+        if self.voices.len() <= voice_index {
+            self.voices.resize(voice_index + 1, ChokeState::default());
+        }
+        let state = &mut self.voices[voice_index];
+        state.stolen = true;
+        state.held_off = true;
+        state.fade_gain.get_or_insert(1.0);
+    }
+
+    /// Looks for gate onsets before the voices are rendered. `gate_of` returns
+    /// the host gate of a voice for this block.
+    pub fn begin_block(&mut self, gate_of: impl Fn(usize) -> f32) {
+        let mut onsets: Vec<(usize, u8)> = Vec::new();
+        for (index, state) in self.voices.iter_mut().enumerate() {
+            let Some(group) = state.group.filter(|_| !state.stolen) else {
+                continue;
+            };
+            let gate = gate_of(index);
+            if gate > 0.0 && state.last_gate <= 0.0 {
+                state.held_off = false;
+                state.fade_gain = None;
+                onsets.push((index, group));
+            } else if gate <= 0.0 {
+                state.held_off = false;
+            }
+            state.last_gate = gate;
+        }
+
+        for (index, state) in self.voices.iter_mut().enumerate() {
+            let chokes = onsets
+                .iter()
+                .any(|&(onset, group)| onset != index && state.group == Some(group));
+            if chokes && !state.stolen && !onsets.iter().any(|&(onset, _)| onset == index) {
+                state.held_off = state.last_gate > 0.0;
+                state.fade_gain.get_or_insert(1.0);
+            }
+        }
+    }
+
+    /// True while the voice's gate must be ignored.
+    pub fn is_held_off(&self, voice_index: usize) -> bool {
+        self.voices
+            .get(voice_index)
+            .is_some_and(|state| state.held_off)
+    }
+
+    /// Applies the choke fade to a rendered voice. Returns true once the fade
+    /// has finished and the voice should be silenced.
+    pub fn apply_fade(&mut self, voice_index: usize, left: &mut [f32], right: &mut [f32]) -> bool {
+        let Some(state) = self.voices.get_mut(voice_index) else {
+            return false;
+        };
+        let Some(mut gain) = state.fade_gain else {
+            return false;
+        };
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            gain = (gain - self.fade_step).max(0.0);
+            *l *= gain;
+            *r *= gain;
+        }
+        if gain <= 0.0 {
+            state.fade_gain = None;
+            if state.stolen {
+                // The new note's gate rises on the next block.
+                state.stolen = false;
+                state.held_off = false;
+                state.last_gate = 0.0;
+            }
+            true
+        } else {
+            state.fade_gain = Some(gain);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn onset_chokes_other_voices_of_the_group() {
+        let mut choke = ChokeGroups::new(1000.0);
+        choke.set_group(0, Some(1));
+        choke.set_group(1, Some(1));
+        choke.set_group(2, Some(2));
+
+        choke.begin_block(|i| if i == 0 || i == 2 { 1.0 } else { 0.0 });
+        assert!(!choke.is_held_off(0));
+
+        // Voice 1 starts while voice 0 is still held: voice 0 is cut.
+        choke.begin_block(|_| 1.0);
+        assert!(choke.is_held_off(0));
+        assert!(!choke.is_held_off(1));
+        assert!(!choke.is_held_off(2));
+
+        let (mut left, mut right) = (vec![1.0; 4], vec![1.0; 4]);
+        assert!(!choke.apply_fade(1, &mut left, &mut right));
+        assert_eq!(left, vec![1.0; 4]);
+        assert!(!choke.apply_fade(0, &mut left, &mut right));
+        assert!(left[3] < left[0] && left[0] < 1.0);
+        let (mut left, mut right) = (vec![1.0; 4], vec![1.0; 4]);
+        assert!(choke.apply_fade(0, &mut left, &mut right));
+
+        // Releasing the held gate lets the voice trigger again.
+        choke.begin_block(|i| if i == 0 { 0.0 } else { 1.0 });
+        assert!(!choke.is_held_off(0));
+    }
+
+    #[test]
+    fn stolen_voice_fades_out_before_its_new_note() {
+        let mut choke = ChokeGroups::new(1000.0);
+        choke.set_group(1, Some(1));
+        choke.set_group(2, Some(1));
+        choke.begin_block(|_| 1.0);
+
+        choke.steal(1);
+        choke.steal(3);
+        // Still held by the new note, so no onset clears the fade.
+        choke.begin_block(|_| 1.0);
+        assert!(choke.is_held_off(1) && choke.is_held_off(3));
+
+        let (mut left, mut right) = (vec![1.0; 4], vec![1.0; 4]);
+        assert!(!choke.apply_fade(3, &mut left, &mut right));
+        assert!(left[3] < left[0] && left[0] < 1.0);
+        assert!(choke.apply_fade(3, &mut left, &mut right));
+        assert!(!choke.is_held_off(3));
+
+        // Once faded, the grouped voice's new note is an onset like any other.
+        let (mut left, mut right) = (vec![1.0; 8], vec![1.0; 8]);
+        assert!(choke.apply_fade(1, &mut left, &mut right));
+        choke.begin_block(|_| 1.0);
+        assert!(!choke.is_held_off(1));
+        assert!(choke.is_held_off(2));
+    }
+}
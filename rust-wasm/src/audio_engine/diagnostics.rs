@@ -0,0 +1,28 @@
+// src/audio_engine/diagnostics.rs
+//
+// Events the engine reports through `take_diagnostics`: the actions of
+// overload protection, output problems the voices' `NodeHealthMonitor`s found
+// while node health checks are on (NaN, stuck DC, unexpected silence), and
+// edits refused in strict real-time mode.
+
+use serde::Serialize;
+
+use super::overload::OverloadEvent;
+use crate::graph::{CapacityExceeded, NodeHealthEvent};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum DiagnosticEvent {
+    Overload(OverloadEvent),
+    NodeHealth {
+        voice: usize,
+        #[serde(flatten)]
+        event: NodeHealthEvent,
+    },
+    /// `voice` is `None` for engine-wide limits (voice count, block size).
+    Capacity {
+        voice: Option<usize>,
+        #[serde(flatten)]
+        exceeded: CapacityExceeded,
+    },
+}
@@ -0,0 +1,230 @@
+// src/audio_engine/ffi.rs
+//
+// C ABI over the native `AudioEngine` (feature `c-api`), so C, C++, Swift or
+// Kotlin hosts can embed the synth: create and free an engine, load a patch,
+// send notes and render blocks from the host's audio callback. It mirrors the
+// note-driven part of the wasm surface; `include/another_synth.h` declares the
+// same functions. Calls that fail return -1 or false and leave a message for
+// `synth_last_error` on the calling thread.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+use super::native::AudioEngine;
+use super::StealMode;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: impl Into<Vec<u8>>) {
+    let message = CString::new(message).unwrap_or_else(|_| c"invalid error message".into());
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Message of the last failed call on this thread, or null. The pointer stays
+/// valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn synth_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Creates an engine with `num_voices` voices (0 for the default) rendering
+/// blocks of `block_size` frames (0 for the default). Free it with
+/// `synth_engine_free`.
+#[no_mangle]
+pub extern "C" fn synth_engine_new(
+    sample_rate: f32,
+    num_voices: usize,
+    block_size: usize,
+) -> *mut AudioEngine {
+    let mut engine = if block_size == 0 {
+        AudioEngine::new(sample_rate, num_voices)
+    } else {
+        AudioEngine::new_with_block_size(sample_rate, num_voices, block_size)
+    };
+    engine.init(sample_rate, num_voices);
+    Box::into_raw(Box::new(engine))
+}
+
+/// # Safety
+///
+/// `engine` must come from `synth_engine_new` and not be used afterwards.
+/// Null is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn synth_engine_free(engine: *mut AudioEngine) {
+    if !engine.is_null() {
+        drop(Box::from_raw(engine));
+    }
+}
+
+/// Loads a patch file (UTF-8 JSON). Returns the voice count, or -1 on error.
+///
+/// # Safety
+///
+/// `engine` must be a live engine and `patch_json` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn synth_engine_load_patch(
+    engine: *mut AudioEngine,
+    patch_json: *const c_char,
+) -> i32 {
+    let Some(engine) = engine.as_mut() else {
+        set_last_error("Engine is null");
+        return -1;
+    };
+    if patch_json.is_null() {
+        set_last_error("Patch JSON is null");
+        return -1;
+    }
+    let result = CStr::from_ptr(patch_json)
+        .to_str()
+        .map_err(|e| format!("Patch JSON is not UTF-8: {}", e))
+        .and_then(|json| engine.init_with_patch(json));
+    match result {
+        Ok(voices) => voices as i32,
+        Err(err) => {
+            set_last_error(err);
+            -1
+        }
+    }
+}
+
+/// Plays `note` (MIDI note number, velocity 0..1). Returns the voice index, or
+/// -1 if the engine has no voices.
+///
+/// # Safety
+///
+/// `engine` must be a live engine.
+#[no_mangle]
+pub unsafe extern "C" fn synth_engine_note_on(
+    engine: *mut AudioEngine,
+    note: u8,
+    velocity: f32,
+) -> i32 {
+    match engine
+        .as_mut()
+        .and_then(|engine| engine.note_on(note, velocity))
+    {
+        Some(voice) => voice as i32,
+        None => {
+            set_last_error("No voice available");
+            -1
+        }
+    }
+}
+
+/// Releases `note`. Returns false if no voice was playing it.
+///
+/// # Safety
+///
+/// `engine` must be a live engine.
+#[no_mangle]
+pub unsafe extern "C" fn synth_engine_note_off(engine: *mut AudioEngine, note: u8) -> bool {
+    engine.as_mut().is_some_and(|engine| engine.note_off(note))
+}
+
+/// # Safety
+///
+/// `engine` must be a live engine.
+#[no_mangle]
+pub unsafe extern "C" fn synth_engine_all_notes_off(engine: *mut AudioEngine) {
+    if let Some(engine) = engine.as_mut() {
+        engine.all_notes_off();
+    }
+}
+
+/// How note-ons pick a voice when all are busy: 0 = oldest, 1 = quietest,
+/// 2 = same note, 3 = release first.
+///
+/// # Safety
+///
+/// `engine` must be a live engine.
+#[no_mangle]
+pub unsafe extern "C" fn synth_engine_set_steal_mode(engine: *mut AudioEngine, mode: u8) {
+    if let Some(engine) = engine.as_mut() {
+        engine.set_steal_mode(StealMode::from_u8(mode));
+    }
+}
+
+/// # Safety
+///
+/// `engine` must be a live engine.
+#[no_mangle]
+pub unsafe extern "C" fn synth_engine_set_tempo(engine: *mut AudioEngine, bpm: f64) {
+    if let Some(engine) = engine.as_mut() {
+        engine.set_transport_tempo(bpm);
+    }
+}
+
+/// Renders `frames` stereo frames of the notes sent so far into `left` and
+/// `right`, one engine block at a time. Call it from the host's audio
+/// callback.
+///
+/// # Safety
+///
+/// `engine` must be a live engine, and `left` and `right` must each point to
+/// `frames` writable floats.
+#[no_mangle]
+pub unsafe extern "C" fn synth_engine_process(
+    engine: *mut AudioEngine,
+    master_gain: f32,
+    left: *mut f32,
+    right: *mut f32,
+    frames: usize,
+) -> bool {
+    let Some(engine) = engine.as_mut() else {
+        set_last_error("Engine is null");
+        return false;
+    };
+    if left.is_null() || right.is_null() {
+        set_last_error("Output buffer is null");
+        return false;
+    }
+    let left = std::slice::from_raw_parts_mut(left, frames);
+    let right = std::slice::from_raw_parts_mut(right, frames);
+    let block_size = engine.block_size().max(1);
+    for (left, right) in left
+        .chunks_mut(block_size)
+        .zip(right.chunks_mut(block_size))
+    {
+        // Empty gate buffers hand the voices to the note allocator.
+        engine.process_audio(&[], &[], &[], &[], &[], master_gain, left, right);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_through_the_c_abi() {
+        let engine = synth_engine_new(48_000.0, 4, 64);
+        let mut left = vec![0.0f32; 200];
+        let mut right = vec![0.0f32; 200];
+        unsafe {
+            assert!(synth_engine_note_on(engine, 60, 1.0) >= 0);
+            assert!(synth_engine_process(
+                engine,
+                1.0,
+                left.as_mut_ptr(),
+                right.as_mut_ptr(),
+                left.len()
+            ));
+            assert!(synth_engine_note_off(engine, 60));
+            assert!(!synth_engine_note_off(engine, 61));
+
+            assert_eq!(synth_engine_load_patch(engine, c"not json".as_ptr()), -1);
+            let error = CStr::from_ptr(synth_last_error()).to_str().unwrap();
+            assert!(error.contains("Failed to parse patch JSON"));
+
+            synth_engine_free(engine);
+        }
+        assert!(left.iter().chain(&right).all(|s| s.is_finite()));
+    }
+}
@@ -0,0 +1,130 @@
+// src/audio_engine/flat_params.rs
+//
+// Flat parameter export: every node and effect setting as one
+// (node, parameter, value, unit) row, for diffing presets, generating
+// documentation and feeding external analysis tools. Settings come from the
+// node presets, so rows use the same names as a patch's `synthState`; nodes
+// without presets only report whether they're active.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::node_preset::NodePreset;
+use crate::effect_stack::Effect;
+use crate::traits::AudioNode;
+use crate::voice::Voice;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlatParameter {
+    /// Node UUID, or the effect node id for effects.
+    pub node_id: String,
+    pub node_name: &'static str,
+    pub parameter: String,
+    /// A number, bool or enum name.
+    pub value: Value,
+    /// Display unit, e.g. "Hz" or "ms"; empty for plain values.
+    pub unit: &'static str,
+}
+
+/// Rows for the nodes of `voice`, ordered by node id, then for `effects`,
+/// whose node ids count up from `effect_id_offset`.
+pub fn flatten_session(
+    voice: Option<&Voice>,
+    effects: &[Effect],
+    effect_id_offset: usize,
+) -> Vec<FlatParameter> {
+    let mut rows = Vec::new();
+    if let Some(voice) = voice {
+        let mut nodes: Vec<(String, &dyn AudioNode)> = voice
+            .graph
+            .nodes
+            .iter()
+            .map(|(id, node)| (id.to_string(), node.as_ref()))
+            .collect();
+        nodes.sort_by(|a, b| a.0.cmp(&b.0));
+        for (id, node) in nodes {
+            flatten_node(&id, node, &mut rows);
+        }
+    }
+    for (index, effect) in effects.iter().enumerate() {
+        flatten_node(
+            &(effect_id_offset + index).to_string(),
+            effect.node.as_ref(),
+            &mut rows,
+        );
+    }
+    rows
+}
+
+/// Appends the rows of one node to `rows`.
+fn flatten_node(node_id: &str, node: &dyn AudioNode, rows: &mut Vec<FlatParameter>) {
+    let row = |parameter: String, value: Value, unit| FlatParameter {
+        node_id: node_id.to_string(),
+        node_name: node.name(),
+        parameter,
+        value,
+        unit,
+    };
+    let Some(Value::Object(preset)) =
+        NodePreset::from_node(node).and_then(|preset| serde_json::to_value(preset).ok())
+    else {
+        rows.push(row("active".to_string(), Value::Bool(node.is_active()), ""));
+        return;
+    };
+    let kind = preset
+        .get("kind")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let Some(Value::Object(settings)) = preset.get("settings") else {
+        return;
+    };
+    for (parameter, value) in settings {
+        // Ids are blank in presets.
+        if parameter == "id" || value.is_null() {
+            continue;
+        }
+        rows.push(row(parameter.clone(), value.clone(), unit(kind, parameter)));
+    }
+}
+
+fn unit(kind: &str, parameter: &str) -> &'static str {
+    match (kind, parameter) {
+        ("envelope", "attack" | "decay" | "release") => "s",
+        ("lfo", "frequency") => "Hz",
+        ("filter", "cutoff" | "comb_frequency") => "Hz",
+        ("filter", "cutoffModOctaves") => "oct",
+        ("delay", "delayMs") => "ms",
+        ("delay", "syncBeats") => "beats",
+        ("saturation", "preTilt" | "postTilt") => "dB",
+        ("bitcrusher", "bits") => "bits",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::{Delay, Mixer};
+
+    #[test]
+    fn rows_carry_preset_settings_and_units() {
+        let mut rows = Vec::new();
+        flatten_node(
+            "10001",
+            &Delay::new(48_000.0, 2000.0, 350.0, 0.5, 0.1),
+            &mut rows,
+        );
+        let delay_ms = rows.iter().find(|row| row.parameter == "delayMs").unwrap();
+        assert_eq!(delay_ms.unit, "ms");
+        assert_eq!(delay_ms.value.as_f64(), Some(350.0));
+        assert!(rows
+            .iter()
+            .all(|row| row.node_id == "10001" && row.parameter != "id"));
+
+        let mut rows = Vec::new();
+        flatten_node("mixer", &Mixer::new(), &mut rows);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].parameter, "active");
+    }
+}
@@ -0,0 +1,160 @@
+// src/audio_engine/fm_algorithm.rs
+//
+// Standard phase-modulation routings for 2-4 operators, so hosts can pick an
+// algorithm instead of wiring every PhaseMod link themselves.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::graph::{AudioGraph, Connection, ModulationTransformation, ModulationType};
+use crate::{NodeId, PortId};
+
+pub const MAX_FM_OPERATORS: usize = 4;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FmAlgorithm {
+    /// Modulators in series, the first driving every carrier (M3 → M2 → M1 → C).
+    Stack = 0,
+    /// Every modulator drives every carrier (M1 + M2 + M3 → C).
+    Parallel = 1,
+    /// Modulator n drives carrier n; needs as many modulators as carriers.
+    Pairs = 2,
+}
+
+/// The PhaseMod connections `algorithm` makes between the operators.
+pub(crate) fn fm_algorithm_connections(
+    algorithm: FmAlgorithm,
+    carriers: &[NodeId],
+    modulators: &[NodeId],
+) -> Result<Vec<Connection>, String> {
+    let total = carriers.len() + modulators.len();
+    if carriers.is_empty() || modulators.is_empty() || total > MAX_FM_OPERATORS {
+        return Err(format!(
+            "FM algorithms need 2 to {} operators with at least one carrier and one modulator",
+            MAX_FM_OPERATORS
+        ));
+    }
+    let operators: Vec<NodeId> = carriers.iter().chain(modulators).copied().collect();
+    if (1..operators.len()).any(|i| operators[..i].contains(&operators[i])) {
+        return Err("FM operators must be distinct".to_string());
+    }
+
+    let links: Vec<(NodeId, NodeId)> = match algorithm {
+        FmAlgorithm::Stack => {
+            let chain = modulators.windows(2).map(|pair| (pair[1], pair[0]));
+            let into_carriers = carriers.iter().map(|&carrier| (modulators[0], carrier));
+            chain.chain(into_carriers).collect()
+        }
+        FmAlgorithm::Parallel => modulators
+            .iter()
+            .flat_map(|&modulator| carriers.iter().map(move |&carrier| (modulator, carrier)))
+            .collect(),
+        FmAlgorithm::Pairs => {
+            if modulators.len() != carriers.len() {
+                return Err("The pairs algorithm needs one modulator per carrier".to_string());
+            }
+            modulators
+                .iter()
+                .copied()
+                .zip(carriers.iter().copied())
+                .collect()
+        }
+    };
+
+    Ok(links
+        .into_iter()
+        .map(|(from_node, to_node)| Connection {
+            from_node,
+            from_port: PortId::AudioOutput0,
+            to_node,
+            to_port: PortId::PhaseMod,
+            amount: 1.0,
+            modulation_type: ModulationType::Additive,
+            modulation_transform: ModulationTransformation::None,
+        })
+        .collect())
+}
+
+/// Fails unless every operator exists in `graph` and takes phase modulation.
+pub(crate) fn check_fm_operators(graph: &AudioGraph, operators: &[NodeId]) -> Result<(), String> {
+    for &operator in operators {
+        let node = graph
+            .get_node(operator)
+            .ok_or_else(|| format!("Node {} not found", operator.to_string()))?;
+        if node.get_ports().get(&PortId::PhaseMod) != Some(&false) {
+            return Err(format!(
+                "Node {} has no PhaseMod input",
+                operator.to_string()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Removes the PhaseMod links between `operators`, so a new algorithm replaces
+/// the previous one instead of adding to it.
+pub(crate) fn clear_fm_links(graph: &mut AudioGraph, operators: &[NodeId]) {
+    let links: Vec<(NodeId, NodeId)> = graph
+        .connections
+        .values()
+        .filter(|connection| {
+            connection.to_port == PortId::PhaseMod
+                && operators.contains(&connection.from_node)
+                && operators.contains(&connection.to_node)
+        })
+        .map(|connection| (connection.from_node, connection.to_node))
+        .collect();
+    for (from_node, to_node) in links {
+        graph.remove_specific_connection(from_node, to_node, PortId::PhaseMod);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links(algorithm: FmAlgorithm, carriers: usize, modulators: usize) -> Vec<(usize, usize)> {
+        let ids: Vec<NodeId> = (0..carriers + modulators).map(|_| NodeId::new()).collect();
+        let index = |id: NodeId| ids.iter().position(|&other| other == id).unwrap();
+        let mut links: Vec<(usize, usize)> =
+            fm_algorithm_connections(algorithm, &ids[..carriers], &ids[carriers..])
+                .unwrap()
+                .iter()
+                .map(|connection| (index(connection.from_node), index(connection.to_node)))
+                .collect();
+        links.sort();
+        links
+    }
+
+    #[test]
+    fn algorithms_wire_modulators_into_carriers() {
+        // Operator 0 is the carrier, 1-3 the modulators.
+        assert_eq!(
+            links(FmAlgorithm::Stack, 1, 3),
+            vec![(1, 0), (2, 1), (3, 2)]
+        );
+        assert_eq!(
+            links(FmAlgorithm::Parallel, 1, 3),
+            vec![(1, 0), (2, 0), (3, 0)]
+        );
+        assert_eq!(
+            links(FmAlgorithm::Stack, 2, 2),
+            vec![(2, 0), (2, 1), (3, 2)]
+        );
+        assert_eq!(links(FmAlgorithm::Pairs, 2, 2), vec![(2, 0), (3, 1)]);
+    }
+
+    #[test]
+    fn rejects_unsupported_operator_sets() {
+        let ids: Vec<NodeId> = (0..5).map(|_| NodeId::new()).collect();
+        let connect = |carriers: &[NodeId], modulators: &[NodeId]| {
+            fm_algorithm_connections(FmAlgorithm::Stack, carriers, modulators)
+        };
+        assert!(connect(&ids[..1], &[]).is_err());
+        assert!(connect(&ids[..2], &ids[2..]).is_err());
+        assert!(connect(&ids[..1], &ids[..1]).is_err());
+        assert!(fm_algorithm_connections(FmAlgorithm::Pairs, &ids[..1], &ids[1..3]).is_err());
+    }
+}
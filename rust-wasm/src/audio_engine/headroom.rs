@@ -0,0 +1,101 @@
+// src/audio_engine/headroom.rs
+//
+// Polyphony-compensated voice gain. With many voices sounding at full level
+// the voice mix clips long before the master limiter; when enabled, the mix is
+// scaled by 1/sqrt(active voices), the level at which uncorrelated voices sum
+// to the power of a single one. The gain drops quickly as voices come in and
+// recovers slowly as they end, so chords don't pump.
+
+/// Time constant for lowering the gain when voices are added.
+const ATTACK_MS: f32 = 5.0;
+/// Time constant for raising it again as voices end.
+const RELEASE_MS: f32 = 150.0;
+
+#[derive(Debug)]
+pub struct PolyphonyCompensation {
+    enabled: bool,
+    gain: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+}
+
+impl PolyphonyCompensation {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut compensation = Self {
+            enabled: false,
+            gain: 1.0,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+        };
+        compensation.set_sample_rate(sample_rate);
+        compensation
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        let coeff = |ms: f32| (-1.0 / (ms * 0.001 * sample_rate.max(1.0))).exp();
+        self.attack_coeff = coeff(ATTACK_MS);
+        self.release_coeff = coeff(RELEASE_MS);
+    }
+
+    /// Turning compensation off glides the gain back to unity.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn target_gain(&self, active_voices: usize) -> f32 {
+        if self.enabled {
+            1.0 / (active_voices.max(1) as f32).sqrt()
+        } else {
+            1.0
+        }
+    }
+
+    /// Scales the voice mix buffers for this block's active voice count.
+    pub fn apply(&mut self, active_voices: usize, buffers: [&mut [f32]; 4]) {
+        let target = self.target_gain(active_voices);
+        if self.gain == 1.0 && target == 1.0 {
+            return;
+        }
+        let coeff = if target < self.gain {
+            self.attack_coeff
+        } else {
+            self.release_coeff
+        };
+        let len = buffers.iter().map(|b| b.len()).max().unwrap_or(0);
+        let [a, b, c, d] = buffers;
+        for i in 0..len {
+            self.gain = target + coeff * (self.gain - target);
+            if (self.gain - target).abs() < 1e-6 {
+                self.gain = target;
+            }
+            for buffer in [&mut *a, &mut *b, &mut *c, &mut *d] {
+                if let Some(sample) = buffer.get_mut(i) {
+                    *sample *= self.gain;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gain_settles_at_inverse_sqrt_of_the_voice_count() {
+        let mut compensation = PolyphonyCompensation::new(1_000.0);
+        let mut left = vec![1.0; 200];
+        let mut right = vec![1.0; 200];
+        compensation.apply(4, [&mut left, &mut right, &mut [], &mut []]);
+        assert_eq!(left, vec![1.0; 200]);
+
+        compensation.set_enabled(true);
+        compensation.apply(4, [&mut left, &mut right, &mut [], &mut []]);
+        assert!((left[199] - 0.5).abs() < 1e-3, "{}", left[199]);
+        assert_eq!(left, right);
+    }
+}
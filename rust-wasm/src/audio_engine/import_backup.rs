@@ -0,0 +1,197 @@
+// src/audio_engine/import_backup.rs
+//
+// Single-level undo for imports that overwrite shared assets. While enabled,
+// the wavetable collection or sample an import replaces is kept, along with
+// what the affected oscillators were playing, until the next import or a
+// revert. Off by default, since the backup holds the old asset in memory.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::nodes::morph_wavetable::{WavetableMorphCollection, WavetableSynthBank};
+use crate::nodes::{SampleData, Sampler, WavetableOscillator};
+use crate::voice::Voice;
+use crate::NodeId;
+
+enum ImportBackup {
+    Wavetable {
+        name: String,
+        /// `None` when the import added the collection.
+        previous: Option<Rc<WavetableMorphCollection>>,
+        /// The oscillator the import pointed at `name` and, per voice, the
+        /// collection it played before.
+        oscillator: Option<(NodeId, Vec<String>)>,
+    },
+    Sample {
+        node_id: NodeId,
+        /// Per voice, as each sampler may hold its own data.
+        previous: Vec<Rc<RefCell<SampleData>>>,
+    },
+}
+
+#[derive(Default)]
+pub struct ImportHistory {
+    enabled: bool,
+    last: Option<ImportBackup>,
+}
+
+impl ImportHistory {
+    /// Turning the backup off drops the one held.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.last = None;
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn can_revert(&self) -> bool {
+        self.last.is_some()
+    }
+
+    /// Call before a wavetable import stores a collection under `name` and,
+    /// if `oscillator` is given, points that oscillator at it.
+    pub fn backup_wavetable(
+        &mut self,
+        bank: &WavetableSynthBank,
+        name: &str,
+        oscillator: Option<NodeId>,
+        voices: &[Voice],
+    ) {
+        if !self.enabled {
+            return;
+        }
+        let oscillator = oscillator.map(|node_id| {
+            let playing = voices
+                .iter()
+                .filter_map(|voice| voice.graph.get_node(node_id))
+                .filter_map(|node| node.as_any().downcast_ref::<WavetableOscillator>())
+                .map(|osc| osc.current_wavetable().to_string())
+                .collect();
+            (node_id, playing)
+        });
+        self.last = Some(ImportBackup::Wavetable {
+            name: name.to_string(),
+            previous: bank.get_collection(name),
+            oscillator,
+        });
+    }
+
+    /// Call before a sample import replaces the data of sampler `node_id`.
+    pub fn backup_sample(&mut self, node_id: NodeId, voices: &[Voice]) {
+        if !self.enabled {
+            return;
+        }
+        let previous = voices
+            .iter()
+            .filter_map(|voice| voice.graph.get_node(node_id))
+            .filter_map(|node| node.as_any().downcast_ref::<Sampler>())
+            .map(|sampler| sampler.get_sample_data())
+            .collect();
+        self.last = Some(ImportBackup::Sample { node_id, previous });
+    }
+
+    /// Puts back what the last import replaced. Returns false when there is
+    /// nothing to revert.
+    pub fn revert(
+        &mut self,
+        bank: &mut WavetableSynthBank,
+        voices: &mut [Voice],
+    ) -> Result<bool, String> {
+        let Some(backup) = self.last.take() else {
+            return Ok(false);
+        };
+        match backup {
+            ImportBackup::Wavetable {
+                name,
+                previous,
+                oscillator,
+            } => {
+                match previous {
+                    Some(collection) => {
+                        bank.collections.insert(name, collection);
+                    }
+                    None => {
+                        bank.remove_collection(&name);
+                    }
+                }
+                if let Some((node_id, playing)) = oscillator {
+                    for (voice, collection) in voices.iter_mut().zip(&playing) {
+                        wavetable_oscillator_mut(voice, node_id)?.set_current_wavetable(collection);
+                    }
+                }
+            }
+            ImportBackup::Sample { node_id, previous } => {
+                for (voice, data) in voices.iter_mut().zip(previous) {
+                    voice
+                        .graph
+                        .get_node_mut(node_id)
+                        .and_then(|node| node.as_any_mut().downcast_mut::<Sampler>())
+                        .ok_or_else(|| format!("Sampler {} no longer exists", node_id.to_string()))?
+                        .set_sample_data(data);
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+fn wavetable_oscillator_mut(
+    voice: &mut Voice,
+    node_id: NodeId,
+) -> Result<&mut WavetableOscillator, String> {
+    voice
+        .graph
+        .get_node_mut(node_id)
+        .and_then(|node| node.as_any_mut().downcast_mut::<WavetableOscillator>())
+        .ok_or_else(|| {
+            format!(
+                "Wavetable oscillator {} no longer exists",
+                node_id.to_string()
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reverts_the_last_replaced_collection_when_enabled() {
+        let mut bank = WavetableSynthBank::new(48_000.0);
+        let original = bank.get_collection("default").unwrap();
+        let mut history = ImportHistory::default();
+        history.backup_wavetable(&bank, "default", None, &[]);
+        assert!(!history.revert(&mut bank, &mut []).unwrap());
+
+        history.set_enabled(true);
+        history.backup_wavetable(&bank, "pad", None, &[]);
+        bank.add_collection("pad", WavetableMorphCollection::new());
+        history.backup_wavetable(&bank, "default", None, &[]);
+        bank.add_collection("default", WavetableMorphCollection::new());
+        assert!(history.can_revert());
+
+        assert!(history.revert(&mut bank, &mut []).unwrap());
+        assert!(Rc::ptr_eq(
+            &bank.get_collection("default").unwrap(),
+            &original
+        ));
+        // Only one level is kept, so the earlier import stays.
+        assert!(!history.revert(&mut bank, &mut []).unwrap());
+        assert!(bank.get_collection("pad").is_some());
+    }
+
+    #[test]
+    fn reverting_an_added_collection_removes_it() {
+        let mut bank = WavetableSynthBank::new(48_000.0);
+        let mut history = ImportHistory::default();
+        history.set_enabled(true);
+        history.backup_wavetable(&bank, "pad", None, &[]);
+        bank.add_collection("pad", WavetableMorphCollection::new());
+        assert!(history.revert(&mut bank, &mut []).unwrap());
+        assert_eq!(bank.collection_names(), ["default"]);
+    }
+}
@@ -0,0 +1,662 @@
+// src/audio_engine/jobs.rs
+//
+// Background jobs for heavy preprocessing: decoding imported samples, impulse
+// responses and wavetables, and generating impulse responses. A job is split
+// into steps that each do a bounded slice of work. On native each job runs on
+// its own thread; on wasm, where the engine lives on the audio thread without
+// threads, every `poll_job` call runs a single step, so the host decides how much
+// time to give it. Either way the engine only swaps the finished result in when
+// `poll_job` sees the job complete.
+
+use std::io::Cursor;
+
+use rubato::{
+    Resampler, SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction,
+};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::impulse_generator::ImpulseResponseGenerator;
+pub use crate::nodes::morph_wavetable::MAX_WAVETABLE_BASE_SIZE;
+use crate::nodes::morph_wavetable::{
+    is_wt_file, read_wavetable_file, wav_cycle_size, wavetable_cycle_size, MipmappedWavetable,
+    WavetableMorphCollection,
+};
+use crate::nodes::{check_wav_spec, finite_or_zero, generate_mipmapped_bank_dynamic, Convolver};
+
+pub type JobId = u32;
+
+/// Samples decoded per step.
+const DECODE_CHUNK_SAMPLES: usize = 65_536;
+/// Resampler blocks (of `RESAMPLE_CHUNK` frames) run per step.
+const RESAMPLE_BLOCKS_PER_STEP: usize = 16;
+const RESAMPLE_CHUNK: usize = 1024;
+/// Wavetable cycles mipmapped per step.
+const WAVETABLE_CYCLES_PER_STEP: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImpulseShape {
+    Hall,
+    Plate,
+}
+
+/// What a job does. Imports take the WAV file as the job's data.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum JobRequest {
+    /// Loads a sample into every voice's sampler node.
+    ImportSample { node_id: String },
+    /// Loads an impulse response into a convolver effect.
+    ImportImpulse { effect_id: usize },
+    /// Loads a wavetable into a wavetable oscillator. The data is a `.wt` file
+    /// or a WAV of cycles of `base_size` samples, unless the file declares its
+    /// own cycle size (see `read_wavetable_file`).
+    ImportWavetable { node_id: String, base_size: usize },
+    /// Adds a wavetable to the bank under `name`, like `ImportWavetable` but
+    /// without assigning it to an oscillator.
+    ImportNamedWavetable { name: String, base_size: usize },
+    /// Generates an impulse response for a convolver effect. `size` is the room
+    /// size of a hall or the diffusion of a plate (0‥1).
+    GenerateImpulse {
+        effect_id: usize,
+        shape: ImpulseShape,
+        decay_time: f32,
+        size: f32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum JobStatus {
+    /// Still working; `progress` runs from 0 to 1.
+    Running {
+        progress: f32,
+    },
+    /// Done, and the result is in place.
+    Finished,
+    Failed {
+        error: String,
+    },
+}
+
+/// A finished job's result, for the engine to swap in.
+pub enum JobOutput {
+    Sample {
+        samples: Vec<f32>,
+        channels: usize,
+        sample_rate: f32,
+    },
+    Convolver(Convolver),
+    Wavetable(WavetableMorphCollection),
+}
+
+/// WAV decoding that can stop and resume between chunks.
+struct WavDecoder {
+    reader: hound::WavReader<Cursor<Vec<u8>>>,
+    samples: Vec<f32>,
+    total: usize,
+}
+
+impl WavDecoder {
+    fn new(data: Vec<u8>) -> Result<Self, String> {
+        let reader = hound::WavReader::new(Cursor::new(data)).map_err(|e| e.to_string())?;
+        check_wav_spec(&reader.spec(), reader.len())?;
+        let total = reader.len() as usize;
+        Ok(Self {
+            reader,
+            samples: Vec::with_capacity(total),
+            total,
+        })
+    }
+
+    fn channels(&self) -> usize {
+        self.reader.spec().channels as usize
+    }
+
+    fn sample_rate(&self) -> f32 {
+        self.reader.spec().sample_rate as f32
+    }
+
+    fn progress(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.samples.len() as f32 / self.total as f32
+        }
+    }
+
+    /// Decodes up to `max` more samples. Returns true once the file is read.
+    fn decode(&mut self, max: usize) -> Result<bool, String> {
+        let spec = self.reader.spec();
+        let out = &mut self.samples;
+        let read = match (spec.bits_per_sample, spec.sample_format) {
+            (32, hound::SampleFormat::Float) => {
+                read_chunk(self.reader.samples::<f32>(), max, out, finite_or_zero)
+            }
+            (16, hound::SampleFormat::Int) => {
+                read_chunk(self.reader.samples::<i16>(), max, out, |s| {
+                    s as f32 / i16::MAX as f32
+                })
+            }
+            (24, hound::SampleFormat::Int) => {
+                read_chunk(self.reader.samples::<i32>(), max, out, |s| {
+                    (s << 8 >> 8) as f32 / 8_388_607.0
+                })
+            }
+            _ => read_chunk(self.reader.samples::<i32>(), max, out, |s| {
+                s as f32 / i32::MAX as f32
+            }),
+        }?;
+        Ok(read == 0 || self.samples.len() >= self.total)
+    }
+}
+
+fn read_chunk<S: hound::Sample>(
+    samples: hound::WavSamples<'_, Cursor<Vec<u8>>, S>,
+    max: usize,
+    out: &mut Vec<f32>,
+    convert: impl Fn(S) -> f32,
+) -> Result<usize, String> {
+    let mut read = 0;
+    for sample in samples.take(max) {
+        out.push(convert(sample.map_err(|e| e.to_string())?));
+        read += 1;
+    }
+    Ok(read)
+}
+
+enum Phase {
+    Decode(WavDecoder),
+    Generate {
+        shape: ImpulseShape,
+        decay_time: f32,
+        size: f32,
+    },
+    Resample {
+        resampler: Box<SincFixedIn<f64>>,
+        input: Vec<f64>,
+        position: usize,
+        output: Vec<f64>,
+    },
+    BuildConvolver(Vec<f32>),
+    BuildWavetable {
+        samples: Vec<f32>,
+        sample_rate: f32,
+        cycle_size: usize,
+        next_cycle: usize,
+        collection: WavetableMorphCollection,
+    },
+    Done,
+}
+
+/// The work of one job, as a state machine advanced by `step`.
+pub struct JobTask {
+    request: JobRequest,
+    phase: Phase,
+    /// Engine sample rate; impulse responses are resampled to it.
+    sample_rate: f32,
+    partition_size: usize,
+    /// Cycle size a wavetable WAV declares in its header.
+    declared_cycle_size: Option<usize>,
+}
+
+impl JobTask {
+    /// `partition_size` is the target convolver's, for impulse response jobs.
+    pub fn new(
+        request: &JobRequest,
+        data: Vec<u8>,
+        sample_rate: f32,
+        partition_size: usize,
+    ) -> Result<Self, String> {
+        let declared_cycle_size = match request {
+            JobRequest::ImportWavetable { .. } | JobRequest::ImportNamedWavetable { .. } => {
+                wav_cycle_size(&data)
+            }
+            _ => None,
+        };
+        let phase = match request {
+            JobRequest::GenerateImpulse {
+                shape,
+                decay_time,
+                size,
+                ..
+            } => Phase::Generate {
+                shape: *shape,
+                decay_time: decay_time.clamp(0.1, 10.0),
+                size: size.clamp(0.0, 1.0),
+            },
+            JobRequest::ImportWavetable { base_size, .. }
+            | JobRequest::ImportNamedWavetable { base_size, .. }
+                if *base_size > MAX_WAVETABLE_BASE_SIZE =>
+            {
+                return Err(format!(
+                    "Wavetable base size must be at most {}",
+                    MAX_WAVETABLE_BASE_SIZE
+                ))
+            }
+            // `.wt` files are raw samples behind a short header; nothing to
+            // decode in steps.
+            JobRequest::ImportWavetable { base_size, .. }
+            | JobRequest::ImportNamedWavetable { base_size, .. }
+                if is_wt_file(&data) =>
+            {
+                let file = read_wavetable_file(&data, *base_size)?;
+                Phase::BuildWavetable {
+                    samples: file.samples,
+                    sample_rate: file.sample_rate.unwrap_or(sample_rate),
+                    cycle_size: file.cycle_size,
+                    next_cycle: 0,
+                    collection: WavetableMorphCollection::new(),
+                }
+            }
+            _ => Phase::Decode(WavDecoder::new(data)?),
+        };
+        Ok(Self {
+            request: request.clone(),
+            phase,
+            sample_rate,
+            partition_size,
+            declared_cycle_size,
+        })
+    }
+
+    pub fn progress(&self) -> f32 {
+        // Decoding is the first half of an impulse or wavetable import.
+        let decode_share = match self.request {
+            JobRequest::ImportSample { .. } => 1.0,
+            _ => 0.5,
+        };
+        match &self.phase {
+            Phase::Decode(decoder) => decoder.progress() * decode_share,
+            Phase::Generate { .. } => 0.0,
+            Phase::Resample {
+                input, position, ..
+            } => 0.5 + 0.4 * *position as f32 / input.len().max(1) as f32,
+            Phase::BuildConvolver(_) => 0.9,
+            Phase::BuildWavetable {
+                samples,
+                cycle_size,
+                next_cycle,
+                ..
+            } => {
+                let cycles = (samples.len() / cycle_size).max(1);
+                0.5 + 0.5 * *next_cycle as f32 / cycles as f32
+            }
+            Phase::Done => 1.0,
+        }
+    }
+
+    /// Does one bounded slice of work. Returns the output once the job is done.
+    pub fn step(&mut self) -> Result<Option<JobOutput>, String> {
+        match std::mem::replace(&mut self.phase, Phase::Done) {
+            Phase::Decode(mut decoder) => {
+                if !decoder.decode(DECODE_CHUNK_SAMPLES)? {
+                    self.phase = Phase::Decode(decoder);
+                    return Ok(None);
+                }
+                self.decoded(decoder)
+            }
+            Phase::Generate {
+                shape,
+                decay_time,
+                size,
+            } => {
+                let generator = ImpulseResponseGenerator::new(self.sample_rate);
+                let ir = match shape {
+                    ImpulseShape::Hall => generator.hall(decay_time, size),
+                    ImpulseShape::Plate => generator.plate(decay_time, size),
+                };
+                self.phase = Phase::BuildConvolver(ir);
+                Ok(None)
+            }
+            Phase::Resample {
+                mut resampler,
+                input,
+                mut position,
+                mut output,
+            } => {
+                for _ in 0..RESAMPLE_BLOCKS_PER_STEP {
+                    if position >= input.len() {
+                        break;
+                    }
+                    let end = (position + RESAMPLE_CHUNK).min(input.len());
+                    let mut block = input[position..end].to_vec();
+                    block.resize(RESAMPLE_CHUNK, 0.0);
+                    let out = resampler
+                        .process(&[block], None)
+                        .map_err(|e| format!("Resampling process error: {:?}", e))?;
+                    output.extend(out[0].iter());
+                    position += RESAMPLE_CHUNK;
+                }
+                if position < input.len() {
+                    self.phase = Phase::Resample {
+                        resampler,
+                        input,
+                        position,
+                        output,
+                    };
+                    return Ok(None);
+                }
+                let partial = resampler
+                    .process_partial::<Vec<f64>>(None, None)
+                    .map_err(|e| format!("Resampler flush error: {:?}", e))?;
+                output.extend(partial[0].iter());
+                let ir = output.into_iter().map(|x| x as f32).collect();
+                self.phase = Phase::BuildConvolver(ir);
+                Ok(None)
+            }
+            Phase::BuildConvolver(ir) => Ok(Some(JobOutput::Convolver(Convolver::new(
+                ir,
+                self.partition_size,
+                self.sample_rate,
+            )))),
+            Phase::BuildWavetable {
+                samples,
+                sample_rate,
+                cycle_size,
+                mut next_cycle,
+                mut collection,
+            } => {
+                let cycles = samples.len() / cycle_size;
+                let end = (next_cycle + WAVETABLE_CYCLES_PER_STEP).min(cycles);
+                for cycle in next_cycle..end {
+                    let start = cycle * cycle_size;
+                    let cycle_samples = samples[start..start + cycle_size].to_vec();
+                    let bank =
+                        generate_mipmapped_bank_dynamic(cycle_samples, cycle_size, sample_rate)
+                            .map_err(|e| e.to_string())?;
+                    collection.add_wavetable(MipmappedWavetable { bank });
+                }
+                next_cycle = end;
+                if next_cycle < cycles {
+                    self.phase = Phase::BuildWavetable {
+                        samples,
+                        sample_rate,
+                        cycle_size,
+                        next_cycle,
+                        collection,
+                    };
+                    return Ok(None);
+                }
+                Ok(Some(JobOutput::Wavetable(collection)))
+            }
+            Phase::Done => Err("Job already finished".to_string()),
+        }
+    }
+
+    /// Moves on from a fully decoded file.
+    fn decoded(&mut self, decoder: WavDecoder) -> Result<Option<JobOutput>, String> {
+        let channels = decoder.channels().max(1);
+        let file_rate = decoder.sample_rate();
+        let samples = decoder.samples;
+        match self.request {
+            JobRequest::ImportSample { .. } => Ok(Some(JobOutput::Sample {
+                samples,
+                channels,
+                sample_rate: file_rate,
+            })),
+            JobRequest::ImportWavetable { base_size, .. }
+            | JobRequest::ImportNamedWavetable { base_size, .. } => {
+                let cycle_size =
+                    wavetable_cycle_size(self.declared_cycle_size, base_size, samples.len())?;
+                self.phase = Phase::BuildWavetable {
+                    samples,
+                    sample_rate: file_rate,
+                    cycle_size,
+                    next_cycle: 0,
+                    collection: WavetableMorphCollection::new(),
+                };
+                Ok(None)
+            }
+            _ => {
+                // Impulse responses are mixed down to mono.
+                let ir: Vec<f32> = samples
+                    .chunks(channels)
+                    .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+                    .collect();
+                if ir.is_empty() {
+                    return Err("Impulse response is empty".to_string());
+                }
+                if file_rate == self.sample_rate {
+                    self.phase = Phase::BuildConvolver(ir);
+                    return Ok(None);
+                }
+                let params = SincInterpolationParameters {
+                    sinc_len: 256,
+                    f_cutoff: 0.95,
+                    interpolation: SincInterpolationType::Linear,
+                    oversampling_factor: 256,
+                    window: WindowFunction::BlackmanHarris2,
+                };
+                let ratio = self.sample_rate as f64 / file_rate as f64;
+                let resampler = SincFixedIn::<f64>::new(ratio, 2.0, params, RESAMPLE_CHUNK, 1)
+                    .map_err(|e| format!("Resampler creation error: {:?}", e))?;
+                self.phase = Phase::Resample {
+                    resampler: Box::new(resampler),
+                    input: ir.iter().map(|&x| x as f64).collect(),
+                    position: 0,
+                    output: Vec::new(),
+                };
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// What `JobQueue::poll` found.
+pub enum JobPoll {
+    Running(f32),
+    Finished(JobRequest, JobOutput),
+    Failed(String),
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct Job {
+    request: JobRequest,
+    /// Progress as `f32` bits.
+    progress: std::sync::Arc<std::sync::atomic::AtomicU32>,
+    cancel: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    result: std::sync::mpsc::Receiver<Result<JobOutput, String>>,
+}
+
+#[cfg(target_arch = "wasm32")]
+struct Job {
+    request: JobRequest,
+    task: JobTask,
+}
+
+/// The engine's running jobs.
+#[derive(Default)]
+pub struct JobQueue {
+    next_id: JobId,
+    jobs: FxHashMap<JobId, Job>,
+}
+
+impl JobQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn start(&mut self, mut task: JobTask) -> Result<JobId, String> {
+        use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+        use std::sync::{mpsc, Arc};
+
+        let progress = Arc::new(AtomicU32::new(0.0f32.to_bits()));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (sender, result) = mpsc::channel();
+        let request = task.request.clone();
+        {
+            let progress = progress.clone();
+            let cancel = cancel.clone();
+            std::thread::Builder::new()
+                .name("engine-job".to_string())
+                .spawn(move || {
+                    while !cancel.load(Ordering::Relaxed) {
+                        match task.step() {
+                            Ok(None) => {
+                                progress.store(task.progress().to_bits(), Ordering::Relaxed)
+                            }
+                            Ok(Some(output)) => {
+                                let _ = sender.send(Ok(output));
+                                return;
+                            }
+                            Err(err) => {
+                                let _ = sender.send(Err(err));
+                                return;
+                            }
+                        }
+                    }
+                })
+                .map_err(|e| format!("Failed to start job thread: {}", e))?;
+        }
+        Ok(self.insert(Job {
+            request,
+            progress,
+            cancel,
+            result,
+        }))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn start(&mut self, task: JobTask) -> Result<JobId, String> {
+        let request = task.request.clone();
+        Ok(self.insert(Job { request, task }))
+    }
+
+    fn insert(&mut self, job: Job) -> JobId {
+        self.next_id = self.next_id.wrapping_add(1);
+        self.jobs.insert(self.next_id, job);
+        self.next_id
+    }
+
+    /// Checks on a job. Finished and failed jobs are reported once, then
+    /// forgotten.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn poll(&mut self, id: JobId) -> Result<JobPoll, String> {
+        use std::sync::atomic::Ordering;
+        use std::sync::mpsc::TryRecvError;
+
+        let job = self
+            .jobs
+            .get(&id)
+            .ok_or_else(|| format!("Unknown job {}", id))?;
+        let poll = match job.result.try_recv() {
+            Err(TryRecvError::Empty) => {
+                return Ok(JobPoll::Running(f32::from_bits(
+                    job.progress.load(Ordering::Relaxed),
+                )))
+            }
+            Ok(Ok(output)) => JobPoll::Finished(job.request.clone(), output),
+            Ok(Err(err)) => JobPoll::Failed(err),
+            Err(TryRecvError::Disconnected) => JobPoll::Failed("Job thread stopped".to_string()),
+        };
+        self.jobs.remove(&id);
+        Ok(poll)
+    }
+
+    /// Runs one step of a job and reports on it. Finished and failed jobs are
+    /// reported once, then forgotten.
+    #[cfg(target_arch = "wasm32")]
+    pub fn poll(&mut self, id: JobId) -> Result<JobPoll, String> {
+        let job = self
+            .jobs
+            .get_mut(&id)
+            .ok_or_else(|| format!("Unknown job {}", id))?;
+        let poll = match job.task.step() {
+            Ok(None) => return Ok(JobPoll::Running(job.task.progress())),
+            Ok(Some(output)) => JobPoll::Finished(job.request.clone(), output),
+            Err(err) => JobPoll::Failed(err),
+        };
+        self.jobs.remove(&id);
+        Ok(poll)
+    }
+
+    /// Stops a job and drops its result. Returns false for unknown jobs.
+    pub fn cancel(&mut self, id: JobId) -> bool {
+        match self.jobs.remove(&id) {
+            #[cfg(not(target_arch = "wasm32"))]
+            Some(job) => {
+                job.cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+                true
+            }
+            #[cfg(target_arch = "wasm32")]
+            Some(_) => true,
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wav(samples: &[i16], channels: u16) -> Vec<u8> {
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate: 48_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+        for &sample in samples {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+        cursor.into_inner()
+    }
+
+    fn finish(queue: &mut JobQueue, id: JobId) -> JobPoll {
+        loop {
+            match queue.poll(id).unwrap() {
+                JobPoll::Running(progress) => {
+                    assert!((0.0..=1.0).contains(&progress));
+                    std::thread::yield_now();
+                }
+                done => return done,
+            }
+        }
+    }
+
+    #[test]
+    fn jobs_decode_in_steps_and_report_once() {
+        let samples: Vec<i16> = (0..200_000).map(|i| (i % 100) as i16 * 100).collect();
+        let request = JobRequest::ImportSample {
+            node_id: "sampler".to_string(),
+        };
+        let mut task = JobTask::new(&request, wav(&samples, 2), 48_000.0, 0).unwrap();
+        assert!(task.step().unwrap().is_none());
+        assert!(task.progress() > 0.0 && task.progress() < 1.0);
+
+        let mut queue = JobQueue::new();
+        let task = JobTask::new(&request, wav(&samples, 2), 48_000.0, 0).unwrap();
+        let id = queue.start(task).unwrap();
+        match finish(&mut queue, id) {
+            JobPoll::Finished(
+                finished,
+                JobOutput::Sample {
+                    samples, channels, ..
+                },
+            ) => {
+                assert_eq!(finished, request);
+                assert_eq!(samples.len(), 200_000);
+                assert_eq!(channels, 2);
+            }
+            _ => panic!("expected decoded sample"),
+        }
+        assert!(queue.poll(id).is_err());
+
+        let bad = JobTask::new(&request, vec![1, 2, 3], 48_000.0, 0);
+        assert!(bad.is_err());
+        let task = JobTask::new(&request, wav(&samples, 1), 48_000.0, 0).unwrap();
+        let id = queue.start(task).unwrap();
+        assert!(queue.cancel(id));
+        assert!(!queue.cancel(id));
+    }
+}
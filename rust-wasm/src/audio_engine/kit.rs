@@ -0,0 +1,266 @@
+// src/audio_engine/kit.rs
+//
+// Drum-kit mode. Every MIDI key can hold a pad: a single voice built from its
+// own patch (typically a sampler or a short envelope-shaped graph), triggered
+// through `note_on`/`note_off` rather than the host's voice arrays. Pads have
+// their own level and pan and are mixed into the effect send after the voices.
+//
+// One-shot pads ignore note-off: the gate stays open until the pad falls
+// silent, is retriggered or is choked. Pads sharing a choke group cut each
+// other off with a short fade (open/closed hi-hat).
+
+use std::collections::BTreeMap;
+
+use super::choke::CHOKE_FADE_SECONDS;
+use crate::voice::Voice;
+/// Output RMS (about -80 dB) below which a one-shot pad closes its gate.
+const SILENCE_THRESHOLD: f32 = 0.0001;
+
+#[derive(Debug)]
+struct KitPad {
+    voice: Voice,
+    level: f32,
+    /// -1 (left) to 1 (right).
+    pan: f32,
+    one_shot: bool,
+    choke_group: Option<u8>,
+    gate: f32,
+    /// Blocks rendered since the last trigger; one-shot pads keep their gate
+    /// open for at least one block before silence can close it.
+    blocks_since_trigger: u32,
+    fade_gain: f32,
+    fading: bool,
+}
+
+impl KitPad {
+    fn new(voice: Voice) -> Self {
+        Self {
+            voice,
+            level: 1.0,
+            pan: 0.0,
+            one_shot: true,
+            choke_group: None,
+            gate: 0.0,
+            blocks_since_trigger: 0,
+            fade_gain: 1.0,
+            fading: false,
+        }
+    }
+
+    fn choke(&mut self) {
+        if self.gate > 0.0 || self.voice.is_active() {
+            self.gate = 0.0;
+            self.fading = true;
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DrumKit {
+    pads: BTreeMap<u8, KitPad>,
+    fade_step: f32,
+}
+
+impl DrumKit {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut kit = Self {
+            pads: BTreeMap::new(),
+            fade_step: 1.0,
+        };
+        kit.set_sample_rate(sample_rate);
+        kit
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.fade_step = 1.0 / (CHOKE_FADE_SECONDS * sample_rate).max(1.0);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pads.is_empty()
+    }
+
+    /// Installs the voice of a pad, replacing any pad already on `key`.
+    pub fn set_pad(&mut self, key: u8, voice: Voice) {
+        self.pads.insert(key.min(127), KitPad::new(voice));
+    }
+
+    pub fn remove_pad(&mut self, key: u8) -> bool {
+        self.pads.remove(&key).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.pads.clear();
+    }
+
+    fn pad_mut(&mut self, key: u8) -> Result<&mut KitPad, String> {
+        self.pads
+            .get_mut(&key)
+            .ok_or_else(|| format!("No kit pad on key {}", key))
+    }
+
+    pub fn set_pad_mix(&mut self, key: u8, level: f32, pan: f32) -> Result<(), String> {
+        let pad = self.pad_mut(key)?;
+        pad.level = level.max(0.0);
+        pad.pan = pan.clamp(-1.0, 1.0);
+        Ok(())
+    }
+
+    pub fn set_pad_options(
+        &mut self,
+        key: u8,
+        one_shot: bool,
+        choke_group: Option<u8>,
+    ) -> Result<(), String> {
+        let pad = self.pad_mut(key)?;
+        pad.one_shot = one_shot;
+        pad.choke_group = choke_group;
+        Ok(())
+    }
+
+    /// Triggers the pad on `key` with a velocity of 0..1, choking the other
+    /// pads of its group. Returns false if the key has no pad.
+    pub fn note_on(&mut self, key: u8, velocity: f32) -> bool {
+        let Some(group) = self.pads.get(&key).map(|pad| pad.choke_group) else {
+            return false;
+        };
+        if let Some(group) = group {
+            for (_, pad) in self
+                .pads
+                .iter_mut()
+                .filter(|(&other, pad)| other != key && pad.choke_group == Some(group))
+            {
+                pad.choke();
+            }
+        }
+
+        let pad = self.pads.get_mut(&key).expect("pad checked above");
+        // Retriggering a sounding pad resets its graph so envelopes see a
+        // fresh gate edge.
+        if pad.gate > 0.0 {
+            pad.voice.cull();
+        }
+        pad.gate = 1.0;
+        pad.blocks_since_trigger = 0;
+        pad.fade_gain = 1.0;
+        pad.fading = false;
+        pad.voice.current_velocity = velocity.clamp(0.0, 1.0);
+        pad.voice.velocity_ramp_target = None;
+        pad.voice.current_frequency = 440.0 * 2f32.powf((key as f32 - 69.0) / 12.0);
+        true
+    }
+
+    /// Releases a held pad; one-shot pads ignore note-off.
+    pub fn note_off(&mut self, key: u8) -> bool {
+        match self.pads.get_mut(&key) {
+            Some(pad) => {
+                if !pad.one_shot {
+                    pad.gate = 0.0;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cuts every pad with a short fade.
+    pub fn all_notes_off(&mut self) {
+        for pad in self.pads.values_mut() {
+            pad.choke();
+        }
+    }
+
+    pub fn voices_mut(&mut self) -> impl Iterator<Item = &mut Voice> {
+        self.pads.values_mut().map(|pad| &mut pad.voice)
+    }
+
+    /// Renders every pad and adds it to `mix_left`/`mix_right`. The scratch
+    /// buffers must be as long as the mix buffers.
+    pub fn process(
+        &mut self,
+        mix_left: &mut [f32],
+        mix_right: &mut [f32],
+        scratch_left: &mut [f32],
+        scratch_right: &mut [f32],
+    ) {
+        let fade_step = self.fade_step;
+        for pad in self.pads.values_mut() {
+            if pad.gate <= 0.0 && !pad.voice.is_active() {
+                continue;
+            }
+            scratch_left.fill(0.0);
+            scratch_right.fill(0.0);
+            pad.voice.current_gate = pad.gate;
+            pad.voice.process_audio(
+                &[pad.gate],
+                &[pad.voice.current_frequency],
+                scratch_left,
+                scratch_right,
+            );
+
+            let left_gain = pad.level * (1.0 - pad.pan).min(1.0);
+            let right_gain = pad.level * (1.0 + pad.pan).min(1.0);
+            for i in 0..mix_left.len().min(scratch_left.len()) {
+                if pad.fading {
+                    pad.fade_gain = (pad.fade_gain - fade_step).max(0.0);
+                }
+                mix_left[i] += scratch_left[i] * left_gain * pad.fade_gain;
+                mix_right[i] += scratch_right[i] * right_gain * pad.fade_gain;
+            }
+
+            if pad.fading && pad.fade_gain <= 0.0 {
+                pad.voice.cull();
+                pad.fading = false;
+                pad.fade_gain = 1.0;
+            } else if pad.one_shot && pad.gate > 0.0 {
+                pad.blocks_since_trigger = pad.blocks_since_trigger.saturating_add(1);
+                if pad.blocks_since_trigger > 1 && pad.voice.output_rms() < SILENCE_THRESHOLD {
+                    pad.gate = 0.0;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kit_with_pads(keys: &[u8]) -> DrumKit {
+        let mut kit = DrumKit::new(48000.0);
+        for &key in keys {
+            kit.set_pad(key, Voice::new(key as usize, 16));
+        }
+        kit
+    }
+
+    #[test]
+    fn choke_group_fades_other_pads() {
+        let mut kit = kit_with_pads(&[42, 46, 36]);
+        kit.set_pad_options(42, true, Some(1)).unwrap();
+        kit.set_pad_options(46, true, Some(1)).unwrap();
+
+        assert!(kit.note_on(46, 1.0));
+        assert!(kit.note_on(36, 1.0));
+        assert!(kit.note_on(42, 0.8));
+        assert!(!kit.note_on(50, 1.0));
+
+        assert!(kit.pads[&46].fading);
+        assert_eq!(kit.pads[&46].gate, 0.0);
+        assert!(!kit.pads[&36].fading);
+        assert_eq!(kit.pads[&42].gate, 1.0);
+    }
+
+    #[test]
+    fn one_shot_pads_ignore_note_off() {
+        let mut kit = kit_with_pads(&[36, 38]);
+        kit.set_pad_options(38, false, None).unwrap();
+        kit.note_on(36, 1.0);
+        kit.note_on(38, 1.0);
+        kit.note_off(36);
+        kit.note_off(38);
+
+        assert_eq!(kit.pads[&36].gate, 1.0);
+        assert_eq!(kit.pads[&38].gate, 0.0);
+        assert!(kit.set_pad_mix(40, 1.0, 0.0).is_err());
+    }
+}
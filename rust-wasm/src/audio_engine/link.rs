@@ -0,0 +1,110 @@
+// src/audio_engine/link.rs
+//
+// Ableton Link for the native engine (feature `link`). `LinkSync` joins the
+// Link session on the local network and, called once per block from the
+// audio thread, slaves the transport to the session's tempo and beat grid
+// through the host clock source. Everything following the transport (clock
+// nodes, tempo-synced LFOs and delays, the arpeggiator) then plays in time
+// with the other peers.
+
+use std::time::Duration;
+
+use rusty_link::{AblLink, SessionState};
+
+use super::native::AudioEngine;
+use super::ClockSource;
+
+/// Beats per bar the session phase is aligned to.
+pub const DEFAULT_QUANTUM: f64 = 4.0;
+
+pub struct LinkSync {
+    link: AblLink,
+    session: SessionState,
+    quantum: f64,
+    output_latency_micros: i64,
+}
+
+impl LinkSync {
+    /// Joins (or starts) a Link session, proposing `bpm` if there are no
+    /// other peers yet.
+    pub fn new(bpm: f64) -> Self {
+        let link = AblLink::new(bpm);
+        link.enable(true);
+        Self {
+            link,
+            session: SessionState::new(),
+            quantum: DEFAULT_QUANTUM,
+            output_latency_micros: 0,
+        }
+    }
+
+    pub fn num_peers(&self) -> u64 {
+        self.link.num_peers()
+    }
+
+    pub fn set_quantum(&mut self, quantum: f64) {
+        self.quantum = quantum.max(1.0);
+    }
+
+    pub fn quantum(&self) -> f64 {
+        self.quantum
+    }
+
+    /// Time from rendering a block to hearing it, so the audio lines up with
+    /// the other peers rather than the render call.
+    pub fn set_output_latency(&mut self, latency: Duration) {
+        self.output_latency_micros = latency.as_micros().min(i64::MAX as u128) as i64;
+    }
+
+    /// Session tempo as last seen by `sync`.
+    pub fn tempo(&self) -> f64 {
+        self.session.tempo()
+    }
+
+    /// Proposes a new tempo to every peer in the session.
+    pub fn set_tempo(&mut self, bpm: f64) {
+        if bpm <= 0.0 {
+            return;
+        }
+        self.link.capture_app_session_state(&mut self.session);
+        self.session.set_tempo(bpm, self.link.clock_micros());
+        self.link.commit_app_session_state(&self.session);
+    }
+
+    /// Moves `engine`'s transport onto the session's beat grid. Call it from
+    /// the audio thread before each block.
+    pub fn sync(&mut self, engine: &mut AudioEngine) {
+        self.link.capture_audio_session_state(&mut self.session);
+        let host_time = self.link.clock_micros() + self.output_latency_micros;
+        let beat = self.session.beat_at_time(host_time, self.quantum);
+        if engine.clock_source() != ClockSource::Host {
+            engine.set_clock_source(ClockSource::Host);
+        }
+        engine.sync_transport_to_host(beat, self.session.tempo());
+    }
+}
+
+impl Drop for LinkSync {
+    fn drop(&mut self) {
+        self.link.enable(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slaves_the_transport_to_the_session() {
+        let mut link = LinkSync::new(97.0);
+        let mut engine = AudioEngine::new(48_000.0, 1);
+        engine.init(48_000.0, 1);
+        link.sync(&mut engine);
+        assert_eq!(engine.clock_source(), ClockSource::Host);
+        assert!((engine.transport_tempo() - 97.0).abs() < 1e-6);
+
+        link.set_tempo(133.0);
+        link.sync(&mut engine);
+        assert!((engine.transport_tempo() - 133.0).abs() < 1e-6);
+    }
+}
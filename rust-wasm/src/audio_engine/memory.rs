@@ -0,0 +1,122 @@
+// src/audio_engine/memory.rs
+//
+// Memory reports, so hosts on memory-constrained devices (mobile browsers in
+// particular) can warn before running out. Only the large buffers are
+// counted: wavetable banks, sample data, convolver partitions, delay lines
+// and each voice's graph buffer pool. Scratch buffers and the nodes
+// themselves are small next to those.
+
+use std::rc::Rc;
+
+use rustc_hash::FxHashSet;
+use serde::Serialize;
+
+use crate::nodes::{Chorus, Convolver, Delay, Freeverb, Sampler};
+use crate::traits::AudioNode;
+use crate::voice::Voice;
+
+/// Bytes used per subsystem.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryStats {
+    pub wavetable_banks: usize,
+    pub sample_data: usize,
+    pub convolver_partitions: usize,
+    pub delay_buffers: usize,
+    /// Graph buffer pool of each voice, main patch first, then the parts.
+    pub graph_buffers_per_voice: Vec<usize>,
+    pub total: usize,
+}
+
+/// Adds up a `MemoryStats` node by node.
+#[derive(Default)]
+pub struct MemoryCounter {
+    stats: MemoryStats,
+    /// Sample data is shared between the samplers of every voice; each buffer
+    /// is counted once.
+    seen_samples: FxHashSet<usize>,
+}
+
+impl MemoryCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_wavetables(&mut self, bytes: usize) {
+        self.stats.wavetable_banks += bytes;
+    }
+
+    /// Counts a voice's buffer pool and the buffers its nodes hold.
+    pub fn add_voice(&mut self, voice: &Voice) {
+        self.stats
+            .graph_buffers_per_voice
+            .push(voice.graph.buffer_pool.memory_bytes());
+        for node in voice.graph.nodes.values() {
+            self.add_node(node.as_ref());
+        }
+    }
+
+    pub fn add_node(&mut self, node: &dyn AudioNode) {
+        let any = node.as_any();
+        if let Some(sampler) = any.downcast_ref::<Sampler>() {
+            let data = sampler.get_sample_data();
+            if self.seen_samples.insert(Rc::as_ptr(&data) as usize) {
+                self.stats.sample_data +=
+                    data.borrow().samples.capacity() * std::mem::size_of::<f32>();
+            }
+        } else if let Some(convolver) = any.downcast_ref::<Convolver>() {
+            self.stats.convolver_partitions += convolver.memory_bytes();
+        } else if let Some(delay) = any.downcast_ref::<Delay>() {
+            self.stats.delay_buffers += delay.memory_bytes();
+        } else if let Some(chorus) = any.downcast_ref::<Chorus>() {
+            self.stats.delay_buffers += chorus.memory_bytes();
+        } else if let Some(reverb) = any.downcast_ref::<Freeverb>() {
+            self.stats.delay_buffers += reverb.memory_bytes();
+        }
+    }
+
+    pub fn finish(mut self) -> MemoryStats {
+        let stats = &mut self.stats;
+        stats.total = stats.wavetable_banks
+            + stats.sample_data
+            + stats.convolver_partitions
+            + stats.delay_buffers
+            + stats.graph_buffers_per_voice.iter().sum::<usize>();
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_shared_samples_once() {
+        let mut voices: Vec<Voice> = (0..2).map(|id| Voice::new(id, 128)).collect();
+        let delay = Delay::new(48_000.0, 1000.0, 500.0, 0.5, 0.1);
+        let delay_bytes = delay.memory_bytes();
+        voices[0].graph.add_node(Box::new(delay));
+
+        let shared = Sampler::new(48_000.0).get_sample_data();
+        shared.borrow_mut().samples = vec![0.0; 1000];
+        for voice in &mut voices {
+            let mut sampler = Sampler::new(48_000.0);
+            sampler.set_sample_data(shared.clone());
+            voice.graph.add_node(Box::new(sampler));
+        }
+
+        let mut counter = MemoryCounter::new();
+        counter.add_wavetables(64);
+        for voice in &voices {
+            counter.add_voice(voice);
+        }
+        let stats = counter.finish();
+        assert_eq!(stats.sample_data, shared.borrow().samples.capacity() * 4);
+        assert!(delay_bytes >= 48_000 * 4);
+        assert_eq!(stats.delay_buffers, delay_bytes);
+        assert_eq!(stats.graph_buffers_per_voice.len(), 2);
+        assert!(stats.graph_buffers_per_voice[0] > 0);
+        let pools: usize = stats.graph_buffers_per_voice.iter().sum();
+        assert_eq!(stats.total, 64 + stats.sample_data + delay_bytes + pools);
+    }
+}
@@ -1,12 +1,110 @@
 mod patch;
+mod patch_diff;
 mod patch_loader;
 
+#[cfg(any(
+    all(feature = "wasm", target_arch = "wasm32"),
+    feature = "native-host",
+    feature = "ts-bindings"
+))]
+pub mod api;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod auto_level;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+pub use auto_level::NodeLevels;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod chain_response;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod choke;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod diagnostics;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+pub use diagnostics::DiagnosticEvent;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod fm_algorithm;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+pub use fm_algorithm::FmAlgorithm;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod flat_params;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+pub use flat_params::FlatParameter;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod headroom;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod import_backup;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod jobs;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+pub use jobs::{ImpulseShape, JobId, JobRequest, JobStatus, MAX_WAVETABLE_BASE_SIZE};
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod kit;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod modulation_preview;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+pub use modulation_preview::ModulationPreview;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod node_preset;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod output_stage;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+pub use output_stage::{OutputFormat, OutputMode};
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod memory;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+pub use memory::MemoryStats;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod overload;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+pub use overload::{OverloadAction, OverloadEvent};
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod param_lock;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+pub use param_lock::LockableParameter;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod parts;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+pub use parts::{PartConfig, MAX_PARTS};
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod scope;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+pub use scope::{ScopeCapture, ScopeSource, MAX_SCOPE_LENGTH};
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod snapshot;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod standard_voice;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+pub use standard_voice::StandardVoiceIds;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod surround;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+pub use surround::ChannelLayout;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod transport;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+pub use transport::ClockSource;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+mod voice_allocator;
+#[cfg(any(all(feature = "wasm", target_arch = "wasm32"), feature = "native-host"))]
+pub use voice_allocator::StealMode;
+
+#[cfg(feature = "ts-bindings")]
+pub mod typescript;
+
 #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
 pub mod wasm;
 
 #[cfg(feature = "native-host")]
 pub mod native;
 
+#[cfg(feature = "c-api")]
+pub mod ffi;
+
+#[cfg(feature = "link")]
+pub mod link;
+
+#[cfg(feature = "osc")]
+pub mod osc;
+
 #[cfg(all(feature = "wasm", not(feature = "native-host"), target_arch = "wasm32"))]
 pub use wasm::*;
 
@@ -0,0 +1,187 @@
+// src/audio_engine/modulation_preview.rs
+//
+// How a destination parameter moves over a note. Copies of the envelopes and
+// LFOs wired into a port are run against a virtual gate (on at 0, off after
+// the note length), their outputs are combined the way the destination
+// combines modulation, and the result is mapped into the parameter's units
+// when the destination describes the port (see `AudioNode::modulation_target`).
+// Modulation of the sources themselves is left out.
+
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+
+use crate::graph::{
+    AudioGraph, Connection, ModulationProcessor, ModulationSource, ModulationTarget,
+    ModulationTransformation, ModulationType, TargetMapping,
+};
+use crate::traits::{AudioNode, PortId};
+use crate::NodeId;
+
+const BLOCK_SIZE: usize = 128;
+/// Preview values per second.
+const PREVIEW_RATE_HZ: f32 = 200.0;
+pub const MAX_PREVIEW_SECONDS: f32 = 60.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModulationPreview {
+    /// Empty for ports the destination doesn't describe; their values are the
+    /// raw modulation.
+    pub parameter: &'static str,
+    pub unit: &'static str,
+    /// Seconds between values.
+    pub interval: f32,
+    pub values: Vec<f32>,
+}
+
+struct Accumulator;
+
+impl ModulationProcessor for Accumulator {}
+
+/// Renders the modulation arriving at `node`'s `port` for `duration`
+/// seconds of a note held for `note_length` seconds.
+pub fn modulation_preview(
+    graph: &AudioGraph,
+    node: NodeId,
+    port: PortId,
+    sample_rate: f32,
+    duration: f32,
+    note_length: f32,
+) -> Result<ModulationPreview, String> {
+    let destination = graph
+        .get_node(node)
+        .ok_or_else(|| format!("Node {} not found", node.to_string()))?;
+    let target = destination
+        .modulation_target(port)
+        .unwrap_or(ModulationTarget {
+            parameter: "",
+            unit: "",
+            base: 0.0,
+            min: f32::NEG_INFINITY,
+            max: f32::INFINITY,
+            mapping: TargetMapping::Linear,
+        });
+
+    let mut sources: Vec<(Box<dyn AudioNode>, Connection)> = graph
+        .connections
+        .values()
+        .filter(|connection| connection.to_node == node && connection.to_port == port)
+        .filter_map(|connection| {
+            let source = graph.get_node(connection.from_node)?.clone_node()?;
+            Some((source, connection.clone()))
+        })
+        .collect();
+    for (source, _) in &mut sources {
+        source.reset();
+    }
+
+    let total = (duration.clamp(0.0, MAX_PREVIEW_SECONDS) * sample_rate) as usize;
+    let gate_off = (note_length.max(0.0) * sample_rate) as usize;
+    let step = ((sample_rate / PREVIEW_RATE_HZ) as usize).max(1);
+    let mut source_out = vec![vec![0.0; BLOCK_SIZE]; sources.len()];
+    let mut gate = vec![0.0; BLOCK_SIZE];
+    let mut additive = vec![0.0; BLOCK_SIZE];
+    let mut multiplicative = vec![1.0; BLOCK_SIZE];
+    let mut values = Vec::with_capacity(total / step + 1);
+
+    let mut start = 0;
+    while start < total {
+        let len = BLOCK_SIZE.min(total - start);
+        for (i, sample) in gate[..len].iter_mut().enumerate() {
+            *sample = if start + i < gate_off { 1.0 } else { 0.0 };
+        }
+        for ((source, _), out) in sources.iter_mut().zip(&mut source_out) {
+            let mut inputs = FxHashMap::default();
+            inputs.insert(
+                PortId::CombinedGate,
+                vec![ModulationSource {
+                    buffer: &gate[..len],
+                    amount: 1.0,
+                    mod_type: ModulationType::Additive,
+                    transformation: ModulationTransformation::None,
+                }],
+            );
+            let mut outputs = FxHashMap::default();
+            outputs.insert(PortId::AudioOutput0, &mut out[..len]);
+            if source.should_process() {
+                source.process(&inputs, &mut outputs, len);
+            } else {
+                out[..len].fill(0.0);
+            }
+        }
+
+        let modulation: Vec<ModulationSource> = sources
+            .iter()
+            .zip(&source_out)
+            .map(|((_, connection), out)| ModulationSource {
+                buffer: &out[..len],
+                amount: connection.amount,
+                mod_type: connection.modulation_type,
+                transformation: connection.modulation_transform,
+            })
+            .collect();
+        Accumulator::accumulate_modulations_inplace(
+            len,
+            Some(&modulation),
+            &mut additive,
+            &mut multiplicative,
+        );
+        for i in 0..len {
+            if (start + i) % step == 0 {
+                values.push(target.apply(additive[i], multiplicative[i]));
+            }
+        }
+        start += len;
+    }
+
+    Ok(ModulationPreview {
+        parameter: target.parameter,
+        unit: target.unit,
+        interval: step as f32 / sample_rate,
+        values,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::{Envelope, EnvelopeConfig, FilterCollection};
+    use uuid::Uuid;
+
+    #[test]
+    fn follows_the_envelope_over_a_note() {
+        let sample_rate = 48_000.0;
+        let mut graph = AudioGraph::new(BLOCK_SIZE);
+        let (envelope, filter) = (NodeId(Uuid::new_v4()), NodeId(Uuid::new_v4()));
+        let config = EnvelopeConfig {
+            attack: 0.01,
+            decay: 0.1,
+            sustain: 0.5,
+            release: 0.1,
+            ..EnvelopeConfig::default()
+        };
+        let mut filter_node = FilterCollection::new(sample_rate);
+        filter_node.set_params(500.0, 0.0);
+        graph.add_node_with_id(envelope, Box::new(Envelope::new(sample_rate, config)));
+        graph.add_node_with_id(filter, Box::new(filter_node));
+        graph.add_connection(Connection {
+            from_node: envelope,
+            from_port: PortId::AudioOutput0,
+            to_node: filter,
+            to_port: PortId::CutoffMod,
+            amount: 2_000.0,
+            modulation_type: ModulationType::Additive,
+            modulation_transform: ModulationTransformation::None,
+        });
+
+        let preview =
+            modulation_preview(&graph, filter, PortId::CutoffMod, sample_rate, 1.0, 0.5).unwrap();
+        assert_eq!((preview.parameter, preview.unit), ("cutoff", "Hz"));
+        assert_eq!(preview.values.len(), 200);
+        let at = |seconds: f32| preview.values[(seconds / preview.interval) as usize];
+        assert!((at(0.0) - 500.0).abs() < 50.0);
+        // Sustaining at half the amount, then back to the base after release.
+        assert!((at(0.4) - 1_500.0).abs() < 50.0);
+        assert!((at(0.95) - 500.0).abs() < 50.0);
+    }
+}
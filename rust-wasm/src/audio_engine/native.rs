@@ -1,26 +1,92 @@
+use crate::audio_engine::api::{
+    AutoWahUpdate, ChanceUpdate, DualFilterUpdate, GateToolUpdate, NoiseGateUpdate, ReverbUpdate,
+    StereoEnhancerUpdate,
+};
+use crate::audio_engine::auto_level::{node_levels, NodeLevels, NodeRole};
+use crate::audio_engine::chain_response::{chain_response, serial_chain};
+use crate::audio_engine::choke::ChokeGroups;
+use crate::audio_engine::diagnostics::DiagnosticEvent;
+use crate::audio_engine::flat_params::{flatten_session, FlatParameter};
+use crate::audio_engine::fm_algorithm::{
+    check_fm_operators, clear_fm_links, fm_algorithm_connections, FmAlgorithm,
+};
+use crate::audio_engine::headroom::PolyphonyCompensation;
+use crate::audio_engine::import_backup::ImportHistory;
+use crate::audio_engine::jobs::{
+    JobId, JobOutput, JobPoll, JobQueue, JobRequest, JobStatus, JobTask,
+};
+use crate::audio_engine::kit::DrumKit;
+use crate::audio_engine::memory::{MemoryCounter, MemoryStats};
+use crate::audio_engine::modulation_preview::{modulation_preview, ModulationPreview};
+use crate::audio_engine::node_preset::NodePreset;
+use crate::audio_engine::output_stage::{OutputFormat, OutputMode, OutputStage};
+use crate::audio_engine::overload::{
+    OverloadAction, OverloadProtection, OverloadResponse, CULL_RMS_THRESHOLD,
+};
+use crate::audio_engine::param_lock::{LockableParameter, ParameterLocks};
+use crate::audio_engine::parts::{PartConfig, Parts, MAX_PARTS};
 use crate::audio_engine::patch::{
-    BitcrusherState, CompressorState, PatchFile, PatchNode, VoiceLayout as PatchVoiceLayout,
+    ChorusState, MacroRouteState, MacroState, PatchConnection, PatchFile, SynthState,
+    VoiceLayout as PatchVoiceLayout, MAX_PATCH_VOICES,
 };
-use crate::audio_engine::patch_loader::{parse_node_id, NODE_CREATION_ORDER};
+use crate::audio_engine::patch_diff::{graph_matches_layout, LayoutDiff};
+use crate::audio_engine::patch_loader::{
+    add_missing_global_nodes, filter_type_from_i32, for_each_node_in_creation_order,
+    modulation_transform_from_i32, modulation_type_from_i32, parse_node_id, port_id_from_u32,
+};
+use crate::audio_engine::scope::{ScopeCapture, ScopeSource, XyScope};
+use crate::audio_engine::snapshot::{
+    PerformanceState, SessionSnapshot, SnapshotFileWriter, SnapshotRecorder,
+    DEFAULT_SNAPSHOT_INTERVAL_SECONDS,
+};
+use crate::audio_engine::standard_voice::{StandardVoice, StandardVoiceIds};
+use crate::audio_engine::surround::{ChannelLayout, SurroundPanner};
+use crate::audio_engine::transport::{ClockSource, Transport};
+use crate::audio_engine::voice_allocator::{StealMode, VoiceAllocator};
 use crate::automation::AutomationFrame;
 use crate::biquad::FilterType;
-use crate::effect_stack::EffectStack;
-use crate::graph::{Connection, ModulationTransformation, ModulationType};
+use crate::effect_stack::{
+    ContainerSlot, EffectRouting, EffectStack, SidechainSource, EFFECT_LFO_COUNT,
+};
+use crate::graph::{
+    push_capacity_event, CapacityExceeded, CapacityLimits, CapacityResource, Connection,
+    ModulationRange, ModulationTransformation, ModulationType, MAX_PENDING_CAPACITY_EVENTS,
+};
 use crate::impulse_generator::ImpulseResponseGenerator;
-use crate::nodes::morph_wavetable::WavetableSynthBank;
+use crate::macros::{MacroMapping, MacroSource, ModulationTarget};
+use crate::nodes::morph_wavetable::{FrameSpectrum, WavetableMorphCollection, WavetableSynthBank};
+use crate::nodes::sampler::sfz::load_sfz;
 use crate::nodes::{
-    AnalogOscillator, AnalogOscillatorStateUpdate, Bitcrusher, Chorus, Compressor, Convolver,
-    Delay, Envelope, EnvelopeConfig, FilterCollection, FilterSlope, Freeverb, GateMixer, Glide,
-    GlobalFrequencyNode, GlobalVelocityNode, Lfo, Limiter, Mixer, Saturation, Waveform,
-    WavetableBank, WavetableOscillator, WavetableOscillatorStateUpdate,
+    AnalogOscillator, AnalogOscillatorStateUpdate, AutoWah, Binaural, Bitcrusher, Chance,
+    ChanceMode, ChanceRandomness, Chorus, Clock, Compressor, Convolver, Delay, DualFilter,
+    DualFilterRouting, Envelope, EnvelopeConfig, EqBand, EqBandType, EqDynamics, Equalizer,
+    Exciter, ExpressionKind, FilterCollection, FilterSlope, FmOperator, FmOperatorConfig,
+    FormantFilter, FormantVowel, Freeverb, GateMixer, GateTool, Glide, GlobalExpressionNode,
+    GlobalFrequencyNode, GlobalVelocityNode, Lfo, LfoLoopMode, LfoRetriggerMode, LfoWaveform,
+    Limiter, Looper, LooperCommand, LooperSpeed, LooperState, Mixer, Mseg, MsegConfig, Multiband,
+    NoiseGate, Parallel, SampleData, Sampler, Saturation, SaturationCharacter, StereoEnhancer,
+    Waveform, WavetableBank, WavetableOscillator, WavetableOscillatorStateUpdate,
+    DEFAULT_RELEASE_VELOCITY,
 };
 //NoiseGenerator, NoiseUpdate,
-use crate::traits::{AudioNode, PortId};
+use crate::traits::{AudioNode, PortId, QualityMode};
+use crate::utils::aliasing::{measure_aliasing, render_tone, AliasReport};
+use crate::utils::analog_spread::{patch_seed, VoiceVariation};
+use crate::utils::correlation::{
+    analyze_mono_compatibility, CorrelationMeter, MonoCompatibilityReport,
+};
+use crate::utils::gain_staging::{GainStagingReport, LevelMeter, StageKind, StageLevels};
+use crate::utils::groove::Groove;
+use crate::utils::midi_file::MidiFile;
+use crate::utils::null_test::{compare_renders, NullTestReport, RenderNote};
+use crate::utils::simd_kernels;
 use crate::voice::Voice;
 use crate::NodeId;
+use rand::Rng;
 use rustc_hash::FxHashMap;
 use std::{
     cell::RefCell,
+    path::PathBuf,
     rc::Rc,
     sync::{
         atomic::{AtomicUsize, Ordering},
@@ -47,6 +113,39 @@ pub struct AudioEngine {
     cpu_time_accum: f64,
     audio_time_accum: f64,
     last_cpu_usage: f32,
+    quality_mode: QualityMode,
+    overload: OverloadProtection,
+    node_health_checks: bool,
+    /// Whether voice graphs carry the right channel between stereo nodes.
+    stereo_voices: bool,
+    /// Analog spread amount (0..1) and the current patch's seed for it.
+    analog_spread: f32,
+    analog_spread_seed: u32,
+    allocator: VoiceAllocator,
+    locks: ParameterLocks,
+    parts: Parts,
+    kit: DrumKit,
+    choke: ChokeGroups,
+    headroom: PolyphonyCompensation,
+    transport: Transport,
+    output: OutputStage,
+    surround: SurroundPanner,
+    snapshots: SnapshotRecorder,
+    snapshot_sink: Option<SnapshotSink>,
+    pending_snapshot: Option<SessionSnapshot>,
+    /// Voice layout of the patch last loaded, for `apply_patch_incremental`.
+    loaded_layout: Option<PatchVoiceLayout>,
+    jobs: JobQueue,
+    imports: ImportHistory,
+    /// Whether new oscillators and filters get levels from `node_levels`.
+    auto_node_levels: bool,
+    last_node_levels: Option<NodeLevels>,
+    scope: XyScope,
+    correlation: CorrelationMeter,
+    /// Strict real-time mode's fixed capacities, and the engine-wide edits
+    /// refused for them (voice graphs keep their own).
+    capacity_limits: Option<CapacityLimits>,
+    capacity_events: Vec<CapacityExceeded>,
     block_size: usize,
     mix_left: Vec<f32>,
     mix_right: Vec<f32>,
@@ -54,6 +153,14 @@ pub struct AudioEngine {
     voice_right: Vec<f32>,
     effect_left: Vec<f32>,
     effect_right: Vec<f32>,
+    dry_left: Vec<f32>,
+    dry_right: Vec<f32>,
+}
+
+/// Where session snapshots go; without one they wait for `take_snapshot`.
+enum SnapshotSink {
+    Callback(Box<dyn FnMut(&str)>),
+    File(SnapshotFileWriter),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -80,7 +187,7 @@ pub struct NoiseUpdateParams {
 
 #[derive(Debug, Clone, Copy)]
 pub struct LfoUpdateParams {
-    pub lfo_id: usize,
+    pub lfo_id: NodeId,
     pub frequency: f32,
     pub phase_offset: f32,
     pub waveform: u8,
@@ -150,6 +257,33 @@ impl AudioEngine {
             cpu_time_accum: 0.0,
             audio_time_accum: 0.0,
             last_cpu_usage: 0.0,
+            quality_mode: QualityMode::default(),
+            overload: OverloadProtection::new(),
+            node_health_checks: false,
+            stereo_voices: false,
+            analog_spread: 0.0,
+            analog_spread_seed: 0,
+            allocator: VoiceAllocator::new(),
+            locks: ParameterLocks::new(),
+            parts: Parts::new(),
+            kit: DrumKit::new(sample_rate),
+            choke: ChokeGroups::new(sample_rate),
+            headroom: PolyphonyCompensation::new(sample_rate),
+            transport: Transport::new(sample_rate),
+            output: OutputStage::new(),
+            surround: SurroundPanner::new(sample_rate),
+            snapshots: SnapshotRecorder::new(),
+            snapshot_sink: None,
+            pending_snapshot: None,
+            loaded_layout: None,
+            jobs: JobQueue::new(),
+            imports: ImportHistory::default(),
+            auto_node_levels: false,
+            last_node_levels: None,
+            scope: XyScope::new(),
+            correlation: CorrelationMeter::new(sample_rate),
+            capacity_limits: None,
+            capacity_events: Vec::new(),
             block_size,
             mix_left: vec![0.0; block_size],
             mix_right: vec![0.0; block_size],
@@ -157,23 +291,63 @@ impl AudioEngine {
             voice_right: vec![0.0; block_size],
             effect_left: vec![0.0; block_size],
             effect_right: vec![0.0; block_size],
+            dry_left: vec![0.0; block_size],
+            dry_right: vec![0.0; block_size],
         }
     }
 
     pub fn init(&mut self, sample_rate: f32, num_voices: usize) {
-        let voice_count = if num_voices == 0 {
+        let mut voice_count = if num_voices == 0 {
             DEFAULT_NUM_VOICES
         } else {
             num_voices
         };
+        if let Some(limits) = self.capacity_limits {
+            if let Err(exceeded) = limits.check(CapacityResource::Voices, voice_count) {
+                push_capacity_event(&mut self.capacity_events, exceeded);
+                voice_count = limits.max_voices;
+            }
+        }
 
         self.sample_rate = sample_rate;
+        self.kit.set_sample_rate(sample_rate);
+        self.choke.set_sample_rate(sample_rate);
+        self.headroom.set_sample_rate(sample_rate);
+        self.transport.set_sample_rate(sample_rate);
+        self.surround.set_sample_rate(sample_rate);
+        self.correlation.set_sample_rate(sample_rate);
         self.num_voices = voice_count;
         self.voices = (0..voice_count)
             .map(|id| Voice::new(id, self.block_size))
             .collect();
+        self.allocator.reset();
+        self.loaded_layout = None;
+        let quality_mode = self.effective_quality_mode();
+        for (index, voice) in self.voices.iter_mut().enumerate() {
+            voice.graph.set_sample_rate(sample_rate);
+            voice.graph.set_quality_mode(quality_mode);
+            voice.graph.set_health_monitoring(self.node_health_checks);
+            voice.graph.set_stereo(self.stereo_voices);
+            voice.graph.set_voice_variation(VoiceVariation::for_voice(
+                self.analog_spread_seed,
+                index,
+                self.analog_spread,
+            ));
+            // Fresh graphs only hold the global nodes, so this can only fail
+            // for limits too small to run anything; report it and go on.
+            if let Err(exceeded) = voice.graph.set_capacity_limits(self.capacity_limits) {
+                push_capacity_event(&mut self.capacity_events, exceeded);
+            }
+        }
 
         self.effect_stack = EffectStack::new(self.block_size);
+        self.effect_stack
+            .set_quality_mode(self.effective_quality_mode());
+        self.effect_stack
+            .set_limiters_bypassed(self.output.mode().bypasses_limiter());
+        self.effect_stack
+            .set_spread_capture(self.surround.is_enabled());
+        self.effect_stack.set_sample_rate(self.sample_rate);
         self.ir_generator = ImpulseResponseGenerator::new(sample_rate);
 
         let mut chorus = Chorus::new(sample_rate, 65.0, 15.0, 5.0, 0.5, 0.3, 0.5, 90.0);
@@ -203,69 +377,126 @@ impl AudioEngine {
         compressor.set_active(true);
         self.effect_stack.add_effect(Box::new(compressor));
 
-        let mut saturation = Saturation::new(2.0, 0.5);
+        let mut saturation = Saturation::new(sample_rate, 2.0, 0.5);
         saturation.set_active(false);
         self.effect_stack.add_effect(Box::new(saturation));
 
-        let mut bitcrusher = Bitcrusher::new(12, 4, 0.5);
+        let mut bitcrusher = Bitcrusher::new(sample_rate, 12, 4, 0.5);
         bitcrusher.set_active(false);
         self.effect_stack.add_effect(Box::new(bitcrusher));
+
+        let mut enhancer = StereoEnhancer::new(self.sample_rate, 0.0, 12.0, 1.0, 1.0, 1.0);
+        enhancer.set_active(false);
+        self.effect_stack.add_effect(Box::new(enhancer));
+
+        let mut binaural = Binaural::new(self.sample_rate, 0.0, 0.0, 1.0);
+        binaural.set_active(false);
+        self.effect_stack.add_effect(Box::new(binaural));
+
+        let mut looper = Looper::new(self.sample_rate, 1.0);
+        looper.set_active(false);
+        self.effect_stack.add_effect(Box::new(looper));
+
+        let mut auto_wah = AutoWah::new(self.sample_rate, 0.5, 300.0, 3.0, 4.0, 1.0);
+        auto_wah.set_active(false);
+        self.effect_stack.add_effect(Box::new(auto_wah));
+
+        let mut exciter = Exciter::new(self.sample_rate, 3_000.0, 0.3, 1.0);
+        exciter.set_active(false);
+        self.effect_stack.add_effect(Box::new(exciter));
+
+        let mut gate = NoiseGate::new(self.sample_rate, -50.0, 6.0, 1.0, 50.0, 100.0, -80.0);
+        gate.set_active(false);
+        self.effect_stack.add_effect(Box::new(gate));
+
+        let mut multiband = Multiband::new(self.sample_rate, &[200.0, 2_000.0]);
+        multiband.set_active(false);
+        self.effect_stack.add_effect(Box::new(multiband));
+
+        let mut parallel = Parallel::new(self.sample_rate, 0.5);
+        parallel.set_active(false);
+        self.effect_stack.add_effect(Box::new(parallel));
+
+        let mut eq = Equalizer::new(self.sample_rate, &Equalizer::spread_bands(4));
+        eq.set_active(false);
+        self.effect_stack.add_effect(Box::new(eq));
+    }
+
+    /// `init` with the output stage configured up front.
+    pub fn init_with_output(
+        &mut self,
+        sample_rate: f32,
+        num_voices: usize,
+        mode: OutputMode,
+        format: OutputFormat,
+    ) {
+        self.output.set_mode(mode);
+        self.output.set_format(format);
+        self.init(sample_rate, num_voices);
     }
 
     pub fn init_with_patch(&mut self, patch_json: &str) -> Result<usize, String> {
         let mut patch: PatchFile = serde_json::from_str(patch_json)
             .map_err(|e| format!("Failed to parse patch JSON: {}", e))?;
 
-        if let Some(canonical) = patch.synth_state.layout.canonical_voice.as_mut() {
-            let has_glide = canonical
-                .nodes
-                .get("glide")
-                .map(|v| !v.is_empty())
-                .unwrap_or(false);
-            if !has_glide {
-                let new_id = NodeId::new().0.to_string();
-                canonical
-                    .nodes
-                    .entry("glide".to_string())
-                    .or_default()
-                    .push(PatchNode {
-                        id: new_id.clone(),
-                        node_type: "glide".to_string(),
-                        name: "Glide".to_string(),
-                    });
-                patch
-                    .synth_state
-                    .glides
-                    .entry(new_id.clone())
-                    .or_insert(GlideState {
-                        glide_id: new_id,
-                        time: 0.0,
-                        rise_time: None,
-                        fall_time: None,
-                        active: false,
-                    });
-            }
-        }
+        add_missing_global_nodes(&mut patch, None);
 
         let layout = &patch.synth_state.layout;
         let voice_count = layout.resolved_voice_count();
         if voice_count == 0 {
             return Err("Patch contains no voices".to_string());
         }
+        if voice_count > MAX_PATCH_VOICES {
+            return Err(format!(
+                "Patch asks for {} voices, limit {}",
+                voice_count, MAX_PATCH_VOICES
+            ));
+        }
+        if let Some(limits) = self.capacity_limits {
+            if let Err(exceeded) = limits.check(CapacityResource::Voices, voice_count) {
+                push_capacity_event(&mut self.capacity_events, exceeded);
+                return Err(exceeded.to_string());
+            }
+        }
 
         self.num_voices = voice_count;
         self.voices = (0..voice_count)
             .map(|id| Voice::new(id, self.block_size))
             .collect();
+        self.allocator.reset();
+        self.analog_spread_seed = patch_seed(&patch.metadata.id);
 
-        for voice in &mut self.voices {
+        let quality_mode = self.effective_quality_mode();
+        for (index, voice) in self.voices.iter_mut().enumerate() {
+            voice.graph.set_quality_mode(quality_mode);
+            voice.graph.set_health_monitoring(self.node_health_checks);
+            voice.graph.set_stereo(self.stereo_voices);
+            voice.graph.set_voice_variation(VoiceVariation::for_voice(
+                self.analog_spread_seed,
+                index,
+                self.analog_spread,
+            ));
             voice.clear();
+            if let Err(exceeded) = voice.graph.set_capacity_limits(self.capacity_limits) {
+                push_capacity_event(&mut self.capacity_events, exceeded);
+                return Err(exceeded.to_string());
+            }
             voice.graph.global_frequency_node = None;
             voice.graph.global_velocity_node = None;
+            voice.graph.global_pressure_node = None;
+            voice.graph.global_timbre_node = None;
+            voice.graph.global_release_velocity_node = None;
             voice.graph.global_gatemixer_node = None;
         }
 
         self.effect_stack = EffectStack::new(self.block_size);
+        self.effect_stack
+            .set_quality_mode(self.effective_quality_mode());
+        self.effect_stack
+            .set_limiters_bypassed(self.output.mode().bypasses_limiter());
+        self.effect_stack
+            .set_spread_capture(self.surround.is_enabled());
+        self.effect_stack.set_sample_rate(self.sample_rate);
         self.ir_generator = ImpulseResponseGenerator::new(self.sample_rate);
         let mut chorus = Chorus::new(self.sample_rate, 65.0, 15.0, 5.0, 0.5, 0.3, 0.5, 90.0);
         chorus.set_active(false);
@@ -294,25 +525,119 @@ impl AudioEngine {
         compressor.set_active(true);
         self.effect_stack.add_effect(Box::new(compressor));
 
-        let mut saturation = Saturation::new(2.0, 0.5);
+        let mut saturation = Saturation::new(self.sample_rate, 2.0, 0.5);
         saturation.set_active(false);
         self.effect_stack.add_effect(Box::new(saturation));
 
-        let mut bitcrusher = Bitcrusher::new(12, 4, 0.5);
+        let mut bitcrusher = Bitcrusher::new(self.sample_rate, 12, 4, 0.5);
         bitcrusher.set_active(false);
         self.effect_stack.add_effect(Box::new(bitcrusher));
 
+        let mut enhancer = StereoEnhancer::new(self.sample_rate, 0.0, 12.0, 1.0, 1.0, 1.0);
+        enhancer.set_active(false);
+        self.effect_stack.add_effect(Box::new(enhancer));
+
+        let mut binaural = Binaural::new(self.sample_rate, 0.0, 0.0, 1.0);
+        binaural.set_active(false);
+        self.effect_stack.add_effect(Box::new(binaural));
+
+        let mut looper = Looper::new(self.sample_rate, 1.0);
+        looper.set_active(false);
+        self.effect_stack.add_effect(Box::new(looper));
+
+        let mut auto_wah = AutoWah::new(self.sample_rate, 0.5, 300.0, 3.0, 4.0, 1.0);
+        auto_wah.set_active(false);
+        self.effect_stack.add_effect(Box::new(auto_wah));
+
+        let mut exciter = Exciter::new(self.sample_rate, 3_000.0, 0.3, 1.0);
+        exciter.set_active(false);
+        self.effect_stack.add_effect(Box::new(exciter));
+
+        let mut gate = NoiseGate::new(self.sample_rate, -50.0, 6.0, 1.0, 50.0, 100.0, -80.0);
+        gate.set_active(false);
+        self.effect_stack.add_effect(Box::new(gate));
+
+        let mut multiband = Multiband::new(self.sample_rate, &[200.0, 2_000.0]);
+        multiband.set_active(false);
+        self.effect_stack.add_effect(Box::new(multiband));
+
+        let mut parallel = Parallel::new(self.sample_rate, 0.5);
+        parallel.set_active(false);
+        self.effect_stack.add_effect(Box::new(parallel));
+
+        let mut eq = Equalizer::new(self.sample_rate, &Equalizer::spread_bands(4));
+        eq.set_active(false);
+        self.effect_stack.add_effect(Box::new(eq));
+
         let canonical_voice = layout
             .canonical_voice()
             .ok_or_else(|| "Patch layout missing voice data".to_string())?;
 
         self.build_nodes_from_canonical_voice(canonical_voice)?;
         self.connect_from_canonical_voice(canonical_voice)?;
-        self.apply_patch_states(&patch, canonical_voice)?;
+        // Nodes and connections past strict real-time capacity were left out.
+        if let Some(exceeded) = self
+            .voices
+            .iter()
+            .find_map(|voice| voice.graph.capacity_events.first())
+        {
+            return Err(format!("Patch does not fit: {}", exceeded));
+        }
+        self.apply_patch_states(&patch.synth_state, canonical_voice)?;
+        self.loaded_layout = Some(canonical_voice.clone());
+        self.snapshots.patch_loaded(patch_json);
 
         Ok(voice_count)
     }
 
+    /// Applies an edited version of the loaded patch without rebuilding the voices: only
+    /// the nodes and connections that changed are added or removed and parameters are
+    /// updated in place, so ringing notes carry on. Falls back to `init_with_patch` when
+    /// no patch is loaded, the voice count or the global nodes changed, or nodes were
+    /// added or deleted one by one since the last load. Returns the voice count.
+    pub fn apply_patch_incremental(&mut self, patch_json: &str) -> Result<usize, String> {
+        let mut patch: PatchFile = serde_json::from_str(patch_json)
+            .map_err(|e| format!("Failed to parse patch JSON: {}", e))?;
+        add_missing_global_nodes(&mut patch, self.loaded_layout.as_ref());
+
+        let layout = &patch.synth_state.layout;
+        let canonical_voice = layout
+            .canonical_voice()
+            .ok_or_else(|| "Patch layout missing voice data".to_string())?;
+        let diff = match (&self.loaded_layout, self.voices.first()) {
+            (Some(loaded), Some(voice))
+                if layout.resolved_voice_count() == self.voices.len()
+                    && graph_matches_layout(loaded, voice.graph.nodes.keys().copied()) =>
+            {
+                LayoutDiff::between(loaded, canonical_voice)?
+            }
+            _ => None,
+        };
+        let Some(diff) = diff else {
+            return self.init_with_patch(patch_json);
+        };
+
+        for connection in &diff.removed_connections {
+            self.disconnect_patch_connection(connection)?;
+        }
+        for &node_id in &diff.removed_nodes {
+            for voice in &mut self.voices {
+                voice.graph.delete_node(node_id);
+            }
+        }
+        for (node_type, node_id) in &diff.added_nodes {
+            self.instantiate_node(node_type, *node_id)?;
+        }
+        for connection in &diff.added_connections {
+            self.connect_patch_connection(connection)?;
+        }
+        self.apply_patch_states(&patch.synth_state, canonical_voice)?;
+        self.loaded_layout = Some(canonical_voice.clone());
+        self.snapshots.patch_loaded(patch_json);
+
+        Ok(self.voices.len())
+    }
+
     fn build_nodes_from_canonical_voice(
         &mut self,
         canonical_voice: &PatchVoiceLayout,
@@ -320,53 +645,7 @@ impl AudioEngine {
         // Use the same creation order as wasm to ensure consistency
         for_each_node_in_creation_order(canonical_voice, |node_type, patch_node| {
             let id = parse_node_id(&patch_node.id)?;
-
-            // IMPORTANT: Call create_node_from_type *before* mutably borrowing self.voices
-            // to avoid aliasing (&self for creation vs &mut self.voices for insertion).
-            for voice_index in 0..self.voices.len() {
-                let node = self.create_node_from_type(node_type, &id)?;
-
-                {
-                    let voice = &mut self.voices[voice_index];
-                    voice.graph.add_node_with_id(id, node);
-
-                    // If the node is a global node, store its ID for this voice
-                    match node_type {
-                        "global_frequency" => voice.graph.global_frequency_node = Some(id),
-                        "glide" => {
-                            voice.graph.global_glide_node = Some(id);
-                            if let Some(global_freq) = voice.graph.global_frequency_node {
-                                voice.graph.add_connection(Connection {
-                                    from_node: global_freq,
-                                    from_port: PortId::GlobalFrequency,
-                                    to_node: id,
-                                    to_port: PortId::AudioInput0,
-                                    amount: 1.0,
-                                    modulation_type: ModulationType::Additive,
-                                    modulation_transform: ModulationTransformation::None,
-                                });
-                            }
-                            if let Some(gate_mixer) = voice.graph.global_gatemixer_node {
-                                voice.graph.add_connection(Connection {
-                                    from_node: gate_mixer,
-                                    from_port: PortId::CombinedGate,
-                                    to_node: id,
-                                    to_port: PortId::CombinedGate,
-                                    amount: 1.0,
-                                    modulation_type: ModulationType::Additive,
-                                    modulation_transform: ModulationTransformation::None,
-                                });
-                            }
-                        }
-                        "global_velocity" => voice.graph.global_velocity_node = Some(id),
-                        "gatemixer" => voice.graph.global_gatemixer_node = Some(id),
-                        "mixer" => voice.graph.set_output_node(id),
-                        _ => {}
-                    }
-                }
-            }
-
-            Ok::<(), String>(())
+            self.instantiate_node(node_type, id)
         })?;
         // Ensure the glide hears the combined gate even though the gate mixer is created later.
         for voice in &mut self.voices {
@@ -389,6 +668,64 @@ impl AudioEngine {
         Ok(())
     }
 
+    /// Adds a node of a patch type to every voice, wiring up the global ones.
+    fn instantiate_node(&mut self, node_type: &str, id: NodeId) -> Result<(), String> {
+        // IMPORTANT: Call create_node_from_type *before* mutably borrowing self.voices
+        // to avoid aliasing (&self for creation vs &mut self.voices for insertion).
+        for voice_index in 0..self.voices.len() {
+            let mut node = self.create_node_from_type(node_type, &id)?;
+            if let Some(lfo) = node.as_any_mut().downcast_mut::<Lfo>() {
+                lfo.set_voice_index(self.voices[voice_index].id);
+            }
+
+            {
+                let voice = &mut self.voices[voice_index];
+                voice.graph.add_node_with_id(id, node);
+
+                // If the node is a global node, store its ID for this voice
+                match node_type {
+                    "global_frequency" => voice.graph.global_frequency_node = Some(id),
+                    "glide" => {
+                        voice.graph.global_glide_node = Some(id);
+                        if let Some(global_freq) = voice.graph.global_frequency_node {
+                            voice.graph.add_connection(Connection {
+                                from_node: global_freq,
+                                from_port: PortId::GlobalFrequency,
+                                to_node: id,
+                                to_port: PortId::AudioInput0,
+                                amount: 1.0,
+                                modulation_type: ModulationType::Additive,
+                                modulation_transform: ModulationTransformation::None,
+                            });
+                        }
+                        if let Some(gate_mixer) = voice.graph.global_gatemixer_node {
+                            voice.graph.add_connection(Connection {
+                                from_node: gate_mixer,
+                                from_port: PortId::CombinedGate,
+                                to_node: id,
+                                to_port: PortId::CombinedGate,
+                                amount: 1.0,
+                                modulation_type: ModulationType::Additive,
+                                modulation_transform: ModulationTransformation::None,
+                            });
+                        }
+                    }
+                    "global_velocity" => voice.graph.global_velocity_node = Some(id),
+                    "global_pressure" => voice.graph.global_pressure_node = Some(id),
+                    "global_timbre" => voice.graph.global_timbre_node = Some(id),
+                    "global_release_velocity" => {
+                        voice.graph.global_release_velocity_node = Some(id)
+                    }
+                    "gatemixer" => voice.graph.global_gatemixer_node = Some(id),
+                    "mixer" => voice.graph.set_output_node(id),
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     fn create_node_from_type(
         &self,
         node_type: &str,
@@ -405,15 +742,66 @@ impl AudioEngine {
                 self.wavetable_synthbank.clone(),
             ))),
             "filter" => Ok(Box::new(FilterCollection::new(self.sample_rate))),
+            "dual_filter" => Ok(Box::new(DualFilter::new(self.sample_rate))),
+            "formant_filter" => Ok(Box::new(FormantFilter::new(self.sample_rate))),
             "envelope" => Ok(Box::new(Envelope::new(
                 self.sample_rate,
                 Default::default(),
             ))),
             "mixer" => Ok(Box::new(Mixer::new())),
             "lfo" => Ok(Box::new(Lfo::new(self.sample_rate))),
-            "global_frequency" => Ok(Box::new(GlobalFrequencyNode::new(440.0, self.block_size))),
+            "stereo_enhancer" => Ok(Box::new(StereoEnhancer::new(
+                self.sample_rate,
+                0.0,
+                12.0,
+                1.0,
+                1.0,
+                1.0,
+            ))),
+            "binaural" => Ok(Box::new(Binaural::new(self.sample_rate, 0.0, 0.0, 1.0))),
+            "auto_wah" => Ok(Box::new(AutoWah::new(
+                self.sample_rate,
+                0.5,
+                300.0,
+                3.0,
+                4.0,
+                1.0,
+            ))),
+            "noise_gate" => Ok(Box::new(NoiseGate::new(
+                self.sample_rate,
+                -50.0,
+                6.0,
+                1.0,
+                50.0,
+                100.0,
+                -80.0,
+            ))),
+            "saturation" => Ok(Box::new(Saturation::new(self.sample_rate, 2.0, 0.5))),
+            "bitcrusher" => Ok(Box::new(Bitcrusher::new(self.sample_rate, 12, 4, 0.5))),
+            "global_frequency" => {
+                let mut node = GlobalFrequencyNode::new(440.0, self.block_size);
+                node.set_sample_rate(self.sample_rate);
+                Ok(Box::new(node))
+            }
             "global_velocity" => Ok(Box::new(GlobalVelocityNode::new(1.0, self.block_size))),
+            "global_pressure" => Ok(Box::new(GlobalExpressionNode::new(
+                ExpressionKind::Pressure,
+                self.block_size,
+            ))),
+            "global_timbre" => Ok(Box::new(GlobalExpressionNode::new(
+                ExpressionKind::Timbre,
+                self.block_size,
+            ))),
+            "global_release_velocity" => Ok(Box::new(GlobalExpressionNode::new(
+                ExpressionKind::ReleaseVelocity,
+                self.block_size,
+            ))),
             "gatemixer" => Ok(Box::new(GateMixer::new())),
+            "gate_tool" => Ok(Box::new(GateTool::new(self.sample_rate))),
+            "clock" => Ok(Box::new(Clock::new(1.0, 0.5))),
+            "chance" => Ok(Box::new(Chance::new())),
+            "mseg" => Ok(Box::new(Mseg::new(self.sample_rate, MsegConfig::default()))),
+            "fm_operator" => Ok(Box::new(FmOperator::new(self.sample_rate))),
             "glide" => {
                 let mut glide = Glide::new(self.sample_rate, 0.0);
                 glide.set_active(false);
@@ -435,64 +823,91 @@ impl AudioEngine {
         canonical_voice: &PatchVoiceLayout,
     ) -> Result<(), String> {
         for conn_data in &canonical_voice.connections {
-            let from_node = parse_node_id(&conn_data.from_id)?;
-            let to_node = parse_node_id(&conn_data.to_id)?;
+            self.connect_patch_connection(conn_data)?;
+        }
+        Ok(())
+    }
 
-            for voice in &mut self.voices {
-                let from_port = voice
-                    .graph
-                    .nodes
-                    .get(&from_node)
-                    .and_then(|n| {
-                        n.get_ports()
-                            .iter()
-                            .find(|(_, &is_output)| is_output)
-                            .map(|(p, _)| *p)
-                    })
-                    .unwrap_or(PortId::AudioOutput0);
-
-                let mut effective_from_node = from_node;
-                let mut effective_from_port = from_port;
-                let to_port = PortId::from_u32(conn_data.target);
-
-                if to_port == PortId::GlobalFrequency {
-                    if let Some(glide_node) = voice.graph.global_glide_node {
-                        effective_from_node = glide_node;
-                        effective_from_port = PortId::AudioOutput0;
+    fn connect_patch_connection(&mut self, conn_data: &PatchConnection) -> Result<(), String> {
+        let from_node = parse_node_id(&conn_data.from_id)?;
+        let to_node = parse_node_id(&conn_data.to_id)?;
+
+        for voice in &mut self.voices {
+            let from_port = voice
+                .graph
+                .nodes
+                .get(&from_node)
+                .and_then(|n| {
+                    let ports = n.get_ports();
+                    // Prefer the left/main output so stereo pairing applies.
+                    if ports.get(&PortId::AudioOutput0) == Some(&true) {
+                        return Some(PortId::AudioOutput0);
                     }
-                }
+                    ports
+                        .iter()
+                        .find(|(_, &is_output)| is_output)
+                        .map(|(p, _)| *p)
+                })
+                .unwrap_or(PortId::AudioOutput0);
 
-                let connection = Connection {
-                    from_node: effective_from_node,
-                    from_port: effective_from_port,
-                    to_node,
-                    to_port,
-                    amount: conn_data.amount,
-                    modulation_type: ModulationType::from_i32(conn_data.modulation_type),
-                    modulation_transform: ModulationTransformation::from_i32(
-                        conn_data.modulation_transform,
-                    ),
-                };
-                voice.graph.add_connection(connection);
+            let mut effective_from_node = from_node;
+            let mut effective_from_port = from_port;
+            let to_port = PortId::from_u32(conn_data.target);
+
+            if to_port == PortId::GlobalFrequency {
+                if let Some(glide_node) = voice.graph.global_glide_node {
+                    effective_from_node = glide_node;
+                    effective_from_port = PortId::AudioOutput0;
+                }
             }
+
+            let connection = Connection {
+                from_node: effective_from_node,
+                from_port: effective_from_port,
+                to_node,
+                to_port,
+                amount: conn_data.amount,
+                modulation_type: ModulationType::from_i32(conn_data.modulation_type),
+                modulation_transform: ModulationTransformation::from_i32(
+                    conn_data.modulation_transform,
+                ),
+            };
+            voice.graph.add_connection(connection);
+        }
+        Ok(())
+    }
+
+    fn disconnect_patch_connection(&mut self, conn_data: &PatchConnection) -> Result<(), String> {
+        let from_node = parse_node_id(&conn_data.from_id)?;
+        let to_node = parse_node_id(&conn_data.to_id)?;
+        let to_port = PortId::from_u32(conn_data.target);
+        for voice in &mut self.voices {
+            // Frequency connections were routed through the glide.
+            let from_node = match voice.graph.global_glide_node {
+                Some(glide_node) if to_port == PortId::GlobalFrequency => glide_node,
+                _ => from_node,
+            };
+            voice
+                .graph
+                .remove_specific_connection(from_node, to_node, to_port);
         }
         Ok(())
     }
 
     fn apply_patch_states(
         &mut self,
-        patch: &PatchFile,
+        state: &SynthState,
         _canonical_voice: &PatchVoiceLayout,
     ) -> Result<(), String> {
-        for (id, params) in &patch.synth_state.oscillators {
+        for (id, params) in &state.oscillators {
             let node_id = parse_node_id(id)?;
             self.update_oscillator(node_id, params)?;
         }
-        for (id, params) in &patch.synth_state.wavetable_oscillators {
+        for (id, params) in &state.wavetable_oscillators {
             let node_id = parse_node_id(id)?;
             self.update_wavetable_oscillator(node_id, params)?;
         }
-        for (id, config) in &patch.synth_state.envelopes {
+        for (id, config) in &state.envelopes {
             let node_id = parse_node_id(id)?;
             self.update_envelope(
                 node_id,
@@ -505,8 +920,34 @@ impl AudioEngine {
                 config.release_curve,
                 config.active,
             )?;
+            self.set_envelope_gate_threshold(node_id, config.gate_threshold)?;
+            self.set_envelope_time_scaling(
+                node_id,
+                config.velocity_attack,
+                config.velocity_decay,
+                config.key_decay,
+                config.key_release,
+            )?;
+            self.set_release_velocity_amount(node_id, config.velocity_release)?;
+            self.set_envelope_drone(node_id, config.drone)?;
         }
-        for glide in patch.synth_state.glides.values() {
+        for (id, lfo) in &state.lfos {
+            self.update_lfos(LfoUpdateParams {
+                lfo_id: parse_node_id(id)?,
+                frequency: lfo.frequency,
+                phase_offset: lfo.phase_offset,
+                waveform: lfo.waveform,
+                use_absolute: lfo.use_absolute,
+                use_normalized: lfo.use_normalized,
+                trigger_mode: lfo.trigger_mode,
+                gain: lfo.gain,
+                active: lfo.active,
+                loop_mode: lfo.loop_mode,
+                loop_start: lfo.loop_start,
+                loop_end: lfo.loop_end,
+            });
+        }
+        for glide in state.glides.values() {
             let glide_id = parse_node_id(&glide.glide_id)?;
             for voice in &mut self.voices {
                 if let Some(node) = voice.graph.get_node_mut(glide_id) {
@@ -518,7 +959,7 @@ impl AudioEngine {
             }
         }
 
-        for compressor in patch.synth_state.compressors.values() {
+        for compressor in state.compressors.values() {
             if let Ok(node_id) = compressor.id.parse::<usize>() {
                 if let Err(err) = self.update_compressor(
                     node_id,
@@ -535,111 +976,695 @@ impl AudioEngine {
             }
         }
 
-        for saturation in patch.synth_state.saturations.values() {
-            if let Ok(node_id) = saturation.id.parse::<usize>() {
-                if let Err(err) = self.update_saturation(
-                    node_id,
-                    saturation.drive,
-                    saturation.mix,
-                    saturation.active,
-                ) {
-                    eprintln!("Failed to apply saturation state: {}", err);
-                }
+        for saturation in state.saturations.values() {
+            let result = match saturation.id.parse::<usize>() {
+                Ok(node_id) => self
+                    .update_saturation(node_id, saturation.drive, saturation.mix, saturation.active)
+                    .and_then(|_| {
+                        self.update_saturation_tone(
+                            node_id,
+                            saturation.character,
+                            saturation.pre_tilt,
+                            saturation.post_tilt,
+                            saturation.auto_gain,
+                        )
+                    }),
+                Err(_) => parse_node_id(&saturation.id).and_then(|node_id| {
+                    self.update_voice_saturation(
+                        node_id,
+                        saturation.drive,
+                        saturation.mix,
+                        saturation.active,
+                    )?;
+                    self.update_voice_saturation_tone(
+                        node_id,
+                        saturation.character,
+                        saturation.pre_tilt,
+                        saturation.post_tilt,
+                        saturation.auto_gain,
+                    )
+                }),
+            };
+            if let Err(err) = result {
+                eprintln!("Failed to apply saturation state: {}", err);
             }
         }
 
-        for bitcrusher in patch.synth_state.bitcrushers.values() {
-            if let Ok(node_id) = bitcrusher.id.parse::<usize>() {
-                if let Err(err) = self.update_bitcrusher(
+        for bitcrusher in state.bitcrushers.values() {
+            let result = match bitcrusher.id.parse::<usize>() {
+                Ok(node_id) => self.update_bitcrusher(
                     node_id,
                     bitcrusher.bits,
                     bitcrusher.downsample_factor,
                     bitcrusher.mix,
                     bitcrusher.active,
+                ),
+                Err(_) => parse_node_id(&bitcrusher.id).and_then(|node_id| {
+                    self.update_voice_bitcrusher(
+                        node_id,
+                        bitcrusher.bits,
+                        bitcrusher.downsample_factor,
+                        bitcrusher.mix,
+                        bitcrusher.active,
+                    )
+                }),
+            };
+            if let Err(err) = result {
+                eprintln!("Failed to apply bitcrusher state: {}", err);
+            }
+        }
+
+        for enhancer in state.stereo_enhancers.values() {
+            let params = StereoEnhancerUpdate {
+                active: enhancer.active,
+                delay_left_ms: enhancer.delay_left_ms,
+                delay_right_ms: enhancer.delay_right_ms,
+                width: enhancer.width,
+                comb_compensation: enhancer.comb_compensation,
+                mix: enhancer.mix,
+            };
+            let result = match enhancer.id.parse::<usize>() {
+                Ok(node_id) => self.update_stereo_enhancer(node_id, params),
+                Err(_) => parse_node_id(&enhancer.id)
+                    .and_then(|node_id| self.update_voice_stereo_enhancer(node_id, params)),
+            };
+            if let Err(err) = result {
+                eprintln!("Failed to apply stereo enhancer state: {}", err);
+            }
+        }
+
+        for binaural in state.binaurals.values() {
+            let result = match binaural.id.parse::<usize>() {
+                Ok(node_id) => self.update_binaural(
+                    node_id,
+                    binaural.active,
+                    binaural.azimuth,
+                    binaural.elevation,
+                    binaural.mix,
+                ),
+                Err(_) => parse_node_id(&binaural.id).and_then(|node_id| {
+                    self.update_voice_binaural(
+                        node_id,
+                        binaural.active,
+                        binaural.azimuth,
+                        binaural.elevation,
+                        binaural.mix,
+                    )
+                }),
+            };
+            if let Err(err) = result {
+                eprintln!("Failed to apply binaural state: {}", err);
+            }
+        }
+
+        for wah in state.auto_wahs.values() {
+            let params = AutoWahUpdate {
+                active: wah.active,
+                sensitivity: wah.sensitivity,
+                frequency: wah.frequency,
+                range: wah.range,
+                q: wah.q,
+                direction: wah.direction,
+                mix: wah.mix,
+            };
+            let result = match wah.id.parse::<usize>() {
+                Ok(node_id) => self.update_auto_wah(node_id, params),
+                Err(_) => parse_node_id(&wah.id)
+                    .and_then(|node_id| self.update_voice_auto_wah(node_id, params)),
+            };
+            if let Err(err) = result {
+                eprintln!("Failed to apply auto-wah state: {}", err);
+            }
+        }
+
+        for exciter in state.exciters.values() {
+            if let Ok(node_id) = exciter.id.parse::<usize>() {
+                if let Err(err) = self.update_exciter(
+                    node_id,
+                    exciter.active,
+                    exciter.frequency,
+                    exciter.amount,
+                    exciter.mix,
                 ) {
-                    eprintln!("Failed to apply bitcrusher state: {}", err);
+                    eprintln!("Failed to apply exciter state: {}", err);
                 }
             }
         }
-        // ... and so on for other state types (LFOs, filters, etc.)
-        Ok(())
-    }
 
-    pub fn process_audio(
-        &mut self,
-        gates: &[f32],
-        frequencies: &[f32],
-        gains: &[f32],
-        velocities: &[f32],
-        macro_values: &[f32],
-        master_gain: f32,
-        output_left: &mut [f32],
-        output_right: &mut [f32],
-    ) {
-        let voice_macro_span = self.voices.len().saturating_mul(MACRO_COUNT);
-        let macro_buffer_len = if voice_macro_span == 0 {
-            0
-        } else {
-            macro_values.len() / voice_macro_span
-        };
-        self.process_audio_internal(
-            gates,
-            frequencies,
-            gains,
-            velocities,
-            macro_values,
-            macro_buffer_len,
-            master_gain,
-            output_left,
-            output_right,
-        );
-    }
+        for gate in state.noise_gates.values() {
+            let params = NoiseGateUpdate {
+                active: gate.active,
+                threshold_db: gate.threshold_db,
+                hysteresis_db: gate.hysteresis_db,
+                attack_ms: gate.attack_ms,
+                hold_ms: gate.hold_ms,
+                release_ms: gate.release_ms,
+                range_db: gate.range_db,
+            };
+            let result = match gate.id.parse::<usize>() {
+                Ok(node_id) => self.update_noise_gate(node_id, params),
+                Err(_) => parse_node_id(&gate.id)
+                    .and_then(|node_id| self.update_voice_noise_gate(node_id, params)),
+            };
+            if let Err(err) = result {
+                eprintln!("Failed to apply noise gate state: {}", err);
+            }
+        }
 
-    fn process_audio_internal(
-        &mut self,
-        gates: &[f32],
-        frequencies: &[f32],
-        gains: &[f32],
-        velocities: &[f32],
-        macro_values: &[f32],
-        macro_buffer_len: usize,
-        master_gain: f32,
-        output_left: &mut [f32],
-        output_right: &mut [f32],
-    ) {
-        #[cfg(debug_assertions)]
-        {
-            // Debug logging is expensive; keep it out of release builds.
-            static CALL_COUNT: AtomicUsize = AtomicUsize::new(0);
-            let call_count = CALL_COUNT.fetch_add(1, Ordering::Relaxed) + 1;
-            if call_count % 1000 == 0 {
-                eprintln!("ENGINE process_audio_internal call {}:", call_count);
-                eprintln!(
-                    "  output_left.len()={}, output_right.len()={}",
-                    output_left.len(),
-                    output_right.len()
-                );
-                eprintln!("  self.block_size={}", self.block_size);
+        for multiband in state.multibands.values() {
+            let Ok(node_id) = multiband.id.parse::<usize>() else {
+                continue;
+            };
+            let result = self
+                .update_multiband(node_id, multiband.active, &multiband.crossovers)
+                .and_then(|_| {
+                    for (band, settings) in multiband.bands.iter().enumerate() {
+                        self.update_multiband_band(
+                            node_id,
+                            band,
+                            settings.gain,
+                            settings.muted,
+                            settings.soloed,
+                        )?;
+                    }
+                    for member in &multiband.members {
+                        let effect_id = member
+                            .effect_id
+                            .parse::<usize>()
+                            .map_err(|_| format!("Invalid effect id {}", member.effect_id))?;
+                        self.set_effect_band(effect_id, Some((node_id, member.band)))?;
+                    }
+                    Ok(())
+                });
+            if let Err(err) = result {
+                eprintln!("Failed to apply multiband state: {}", err);
             }
         }
 
-        let start = Instant::now();
+        for parallel in state.parallel_chains.values() {
+            let Ok(node_id) = parallel.id.parse::<usize>() else {
+                continue;
+            };
+            let result = self
+                .update_parallel(node_id, parallel.active, parallel.blend)
+                .and_then(|_| {
+                    for member in &parallel.members {
+                        let effect_id = member
+                            .effect_id
+                            .parse::<usize>()
+                            .map_err(|_| format!("Invalid effect id {}", member.effect_id))?;
+                        self.set_effect_band(effect_id, Some((node_id, member.band)))?;
+                    }
+                    Ok(())
+                });
+            if let Err(err) = result {
+                eprintln!("Failed to apply parallel chain state: {}", err);
+            }
+        }
 
-        // Reuse preallocated buffers sized to the engine's block size.
-        let block_len = self.block_size.max(1);
-        self.mix_left.resize(block_len, 0.0);
-        self.mix_right.resize(block_len, 0.0);
-        self.voice_left.resize(block_len, 0.0);
-        self.voice_right.resize(block_len, 0.0);
-        self.effect_left.resize(block_len, 0.0);
-        self.effect_right.resize(block_len, 0.0);
+        for eq in state.equalizers.values() {
+            let Ok(node_id) = eq.id.parse::<usize>() else {
+                continue;
+            };
+            let result = self
+                .update_eq(node_id, eq.active, eq.bands.len())
+                .and_then(|_| {
+                    for (band, settings) in eq.bands.iter().enumerate() {
+                        self.update_eq_band(
+                            node_id,
+                            band,
+                            settings.band_type,
+                            settings.frequency,
+                            settings.q,
+                            settings.gain_db,
+                        )?;
+                        self.update_eq_band_dynamics(node_id, band, settings.dynamics)?;
+                    }
+                    Ok(())
+                });
+            if let Err(err) = result {
+                eprintln!("Failed to apply EQ state: {}", err);
+            }
+        }
 
-        self.mix_left.fill(0.0);
-        self.mix_right.fill(0.0);
-        self.voice_left.fill(0.0);
-        self.voice_right.fill(0.0);
-        self.effect_left.fill(0.0);
-        self.effect_right.fill(0.0);
+        for (index, lfo) in state.effect_lfos.iter().enumerate() {
+            let waveform = LfoWaveform::from_u8(lfo.waveform);
+            if let Err(err) = self.set_effect_lfo(index, waveform, lfo.rate_hz, lfo.sync_beats) {
+                eprintln!("Failed to apply effect LFO state: {}", err);
+                continue;
+            }
+            for route in &lfo.routes {
+                let result = route
+                    .effect_id
+                    .parse::<usize>()
+                    .map_err(|_| format!("Invalid effect id {}", route.effect_id))
+                    .and_then(|node_id| {
+                        self.set_effect_lfo_route(index, node_id, &route.parameter, route.depth)
+                    });
+                if let Err(err) = result {
+                    eprintln!("Failed to apply effect LFO route: {}", err);
+                }
+            }
+        }
+
+        for chorus in state.choruses.values() {
+            if let Ok(node_id) = chorus.id.parse::<usize>() {
+                if let Err(err) = self.update_chorus(node_id, chorus) {
+                    eprintln!("Failed to apply chorus state: {}", err);
+                }
+            }
+        }
+
+        for delay in state.delays.values() {
+            if let Ok(node_id) = delay.id.parse::<usize>() {
+                let result = self.update_delay(
+                    node_id,
+                    delay.delay_ms,
+                    delay.feedback,
+                    delay.wet_mix,
+                    delay.active,
+                );
+                if let Err(err) = result {
+                    eprintln!("Failed to apply delay state: {}", err);
+                }
+                if let Err(err) = self.update_delay_ducking(node_id, delay.ducking) {
+                    eprintln!("Failed to apply delay ducking: {}", err);
+                }
+                if let Err(err) = self.update_delay_sync(node_id, delay.sync_beats) {
+                    eprintln!("Failed to apply delay sync: {}", err);
+                }
+            }
+        }
+
+        for reverb in state.reverbs.values() {
+            if let Ok(node_id) = reverb.id.parse::<usize>() {
+                let result = self.update_reverb(
+                    node_id,
+                    ReverbUpdate {
+                        active: reverb.active,
+                        room_size: reverb.room_size,
+                        damp: reverb.damp,
+                        wet: reverb.wet,
+                        dry: reverb.dry,
+                        width: reverb.width,
+                    },
+                );
+                if let Err(err) = result {
+                    eprintln!("Failed to apply reverb state: {}", err);
+                }
+                if let Err(err) = self.update_reverb_gate(node_id, reverb.gate) {
+                    eprintln!("Failed to apply reverb gate: {}", err);
+                }
+            }
+        }
+
+        for sidechain in state.sidechains.values() {
+            if let Ok(node_id) = sidechain.id.parse::<usize>() {
+                let result = parse_node_id(&sidechain.source_id)
+                    .and_then(|source_id| Ok((source_id, port_id_from_u32(sidechain.source_port)?)))
+                    .and_then(|source| self.set_effect_sidechain(node_id, Some(source)));
+                if let Err(err) = result {
+                    eprintln!("Failed to apply sidechain: {}", err);
+                }
+            }
+        }
+
+        for routing in state.effect_routings.values() {
+            if let Some(index) = routing
+                .id
+                .parse::<usize>()
+                .ok()
+                .and_then(|node_id| node_id.checked_sub(EFFECT_NODE_ID_OFFSET))
+            {
+                if let Err(err) = self.set_effect_routing(index, routing.routing) {
+                    eprintln!("Failed to apply effect routing: {}", err);
+                }
+            }
+        }
+
+        for filter in state.filters.values() {
+            let result = parse_node_id(&filter.id).and_then(|node_id| {
+                self.update_filter_auto_gain(node_id, filter.auto_gain)?;
+                self.update_filter_cutoff_mod_octaves(node_id, filter.cutoff_mod_octaves)?;
+                self.update_filter_oversampling(node_id, filter.oversampling)?;
+                self.update_filter_double_precision(node_id, filter.double_precision_feedback)
+            });
+            if let Err(err) = result {
+                eprintln!("Failed to apply filter state: {}", err);
+            }
+        }
+
+        for dual in state.dual_filters.values() {
+            let result = parse_node_id(&dual.id).and_then(|node_id| {
+                self.update_dual_filter(
+                    node_id,
+                    DualFilterUpdate {
+                        active: dual.active,
+                        routing: dual.routing,
+                        balance: dual.balance,
+                        spacing: dual.spacing,
+                        cutoff: dual.cutoff,
+                        key_tracking: dual.key_tracking,
+                    },
+                )?;
+                for (slot, state) in [&dual.filter_a, &dual.filter_b].into_iter().enumerate() {
+                    let filter_type = filter_type_from_i32(state.filter_type)?;
+                    self.update_dual_filter_slot(
+                        node_id,
+                        slot,
+                        filter_type,
+                        state.filter_slope,
+                        state.resonance,
+                        state.gain,
+                    )?;
+                }
+                Ok(())
+            });
+            if let Err(err) = result {
+                eprintln!("Failed to apply dual filter state: {}", err);
+            }
+        }
+
+        for formant in state.formant_filters.values() {
+            let result = parse_node_id(&formant.id).and_then(|node_id| {
+                self.update_formant_filter(
+                    node_id,
+                    formant.active,
+                    formant.vowel,
+                    formant.gender,
+                    formant.resonance,
+                    formant.mix,
+                )
+            });
+            if let Err(err) = result {
+                eprintln!("Failed to apply formant filter state: {}", err);
+            }
+        }
+
+        for tool in state.gate_tools.values() {
+            let result = parse_node_id(&tool.id).and_then(|node_id| {
+                self.update_gate_tool(
+                    node_id,
+                    GateToolUpdate {
+                        active: tool.active,
+                        length_ms: tool.length_ms,
+                        delay_ms: tool.delay_ms,
+                        division: tool.division,
+                        multiplication: tool.multiplication,
+                        probability: tool.probability,
+                    },
+                )
+            });
+            if let Err(err) = result {
+                eprintln!("Failed to apply gate tool state: {}", err);
+            }
+        }
+
+        for clock in state.clocks.values() {
+            let result = parse_node_id(&clock.id).and_then(|node_id| {
+                self.update_clock(
+                    node_id,
+                    clock.active,
+                    clock.division_beats,
+                    clock.pulse_width,
+                )
+            });
+            if let Err(err) = result {
+                eprintln!("Failed to apply clock state: {}", err);
+            }
+        }
+
+        for chance in state.chances.values() {
+            let result = parse_node_id(&chance.id).and_then(|node_id| {
+                self.update_chance(
+                    node_id,
+                    ChanceUpdate {
+                        active: chance.active,
+                        probability: chance.probability,
+                        mode: chance.mode,
+                        attenuation: chance.attenuation,
+                        seed: chance.seed,
+                        randomness: chance.randomness,
+                    },
+                )
+            });
+            if let Err(err) = result {
+                eprintln!("Failed to apply chance state: {}", err);
+            }
+        }
+
+        for mseg in state.msegs.values() {
+            let result = parse_node_id(&mseg.id)
+                .and_then(|node_id| self.update_mseg(node_id, mseg.config.clone()));
+            if let Err(err) = result {
+                eprintln!("Failed to apply MSEG state: {}", err);
+            }
+        }
+
+        for operator in state.fm_operators.values() {
+            let result = parse_node_id(&operator.id)
+                .and_then(|node_id| self.update_fm_operator(node_id, operator.config()));
+            if let Err(err) = result {
+                eprintln!("Failed to apply FM operator state: {}", err);
+            }
+        }
+
+        if let Some(transport) = &state.transport {
+            self.set_transport_tempo(transport.tempo_bpm);
+            self.set_clock_source(ClockSource::from_u8(transport.clock_source));
+            self.set_clock_pulses_per_beat(transport.pulses_per_beat);
+        }
+
+        if let Some(groove) = &state.groove {
+            self.set_groove(Groove::new(groove.swing_percent, &groove.offsets));
+        }
+        for groove in state.grooves.values() {
+            let node_groove = Some(Groove::new(groove.swing_percent, &groove.offsets));
+            let result = match groove.id.parse::<usize>() {
+                Ok(node_id) => node_id
+                    .checked_sub(EFFECT_NODE_ID_OFFSET)
+                    .ok_or_else(|| format!("Invalid effect node id {}", node_id))
+                    .and_then(|index| self.set_effect_groove(index, node_groove)),
+                Err(_) => parse_node_id(&groove.id)
+                    .map(|node_id| self.set_node_groove(node_id, node_groove)),
+            };
+            if let Err(err) = result {
+                eprintln!("Failed to apply groove: {}", err);
+            }
+        }
+
+        if let Some(tuning) = &state.tuning {
+            if !self.locks.is_locked(LockableParameter::MasterTuning) {
+                self.set_master_tuning(tuning.transpose, tuning.fine);
+            }
+            if !self.locks.is_locked(LockableParameter::VoiceDetune) {
+                for (voice_index, &cents) in tuning.voice_detune.iter().enumerate() {
+                    if voice_index < self.voices.len() {
+                        self.set_voice_detune(voice_index, cents)?;
+                    }
+                }
+            }
+            self.set_analog_spread(tuning.analog_spread);
+        }
+
+        if let Some(macros) = &state.macros {
+            self.apply_macro_state(macros)?;
+        }
+
+        self.set_polyphony_compensation(
+            state
+                .headroom
+                .as_ref()
+                .is_some_and(|headroom| headroom.polyphony_compensation),
+        );
+
+        self.restore_locked_parameters();
+        // ... and so on for other state types (LFOs, filters, etc.)
+        Ok(())
+    }
+
+    pub fn process_audio(
+        &mut self,
+        gates: &[f32],
+        frequencies: &[f32],
+        gains: &[f32],
+        velocities: &[f32],
+        macro_values: &[f32],
+        master_gain: f32,
+        output_left: &mut [f32],
+        output_right: &mut [f32],
+    ) {
+        let voice_macro_span = self.voices.len().saturating_mul(MACRO_COUNT);
+        let macro_buffer_len = if voice_macro_span == 0 {
+            0
+        } else {
+            macro_values.len() / voice_macro_span
+        };
+        self.process_audio_internal(
+            gates,
+            frequencies,
+            gains,
+            &[],
+            velocities,
+            &[],
+            &[],
+            &[],
+            &[],
+            macro_values,
+            macro_buffer_len,
+            master_gain,
+            output_left,
+            output_right,
+        );
+    }
+
+    /// Renders one block of `frame` in the current channel layout. `outputs`
+    /// holds one buffer per channel: front left/right, then the rear pair for
+    /// quad or C, LFE, Ls, Rs for 5.1.
+    pub fn process_audio_multichannel(
+        &mut self,
+        frame: &AutomationFrame,
+        master_gain: f32,
+        outputs: &mut [&mut [f32]],
+    ) -> Result<(), String> {
+        let channels = self.surround.layout().channel_count();
+        if outputs.len() != channels {
+            return Err(format!(
+                "Expected {} output buffers, got {}",
+                channels,
+                outputs.len()
+            ));
+        }
+
+        let (front, extra) = outputs.split_at_mut(2);
+        let (left, right) = front.split_at_mut(1);
+        self.process_with_frame(frame, master_gain, &mut *left[0], &mut *right[0]);
+        for (output, channel) in extra.iter_mut().zip(self.surround.extra_channels()) {
+            let len = output.len().min(channel.len());
+            output[..len].copy_from_slice(&channel[..len]);
+            output[len..].fill(0.0);
+            self.output.apply_mode(output);
+        }
+        Ok(())
+    }
+
+    /// Quality mode in effect: Eco while overload protection is degrading,
+    /// otherwise the requested mode.
+    fn effective_quality_mode(&self) -> QualityMode {
+        if self.overload.is_degraded() {
+            QualityMode::Eco
+        } else {
+            self.quality_mode
+        }
+    }
+
+    fn apply_quality_mode(&mut self, mode: QualityMode) {
+        for voice in &mut self.voices {
+            voice.graph.set_quality_mode(mode);
+        }
+        for (voice, _) in self.parts.extra_voices_mut() {
+            voice.graph.set_quality_mode(mode);
+        }
+        for voice in self.kit.voices_mut() {
+            voice.graph.set_quality_mode(mode);
+        }
+        self.effect_stack.set_quality_mode(mode);
+    }
+
+    fn handle_overload(&mut self) {
+        let response = self
+            .overload
+            .evaluate(self.last_cpu_usage, self.effect_stack.quality_mode());
+        self.respond_to_overload(response);
+    }
+
+    fn respond_to_overload(&mut self, response: OverloadResponse) {
+        let cpu_usage = self.last_cpu_usage;
+        match response {
+            OverloadResponse::None => {}
+            OverloadResponse::ReduceQuality => {
+                self.apply_quality_mode(QualityMode::Eco);
+                self.overload
+                    .record(OverloadAction::ReducedQuality, cpu_usage);
+            }
+            OverloadResponse::CullVoices => {
+                let mut count = 0;
+                for voice in &mut self.voices {
+                    if voice.is_active()
+                        && voice.current_gate <= 0.0
+                        && voice.output_rms() < CULL_RMS_THRESHOLD
+                    {
+                        voice.cull();
+                        count += 1;
+                    }
+                }
+                if count > 0 {
+                    self.overload
+                        .record(OverloadAction::CulledVoices { count }, cpu_usage);
+                }
+            }
+            OverloadResponse::RestoreQuality => {
+                self.apply_quality_mode(self.quality_mode);
+                self.overload
+                    .record(OverloadAction::RestoredQuality, cpu_usage);
+            }
+        }
+    }
+
+    fn process_audio_internal(
+        &mut self,
+        gates: &[f32],
+        frequencies: &[f32],
+        gains: &[f32],
+        gain_ends: &[f32],
+        velocities: &[f32],
+        velocity_ends: &[f32],
+        pressures: &[f32],
+        timbres: &[f32],
+        release_velocities: &[f32],
+        macro_values: &[f32],
+        macro_buffer_len: usize,
+        master_gain: f32,
+        output_left: &mut [f32],
+        output_right: &mut [f32],
+    ) {
+        #[cfg(debug_assertions)]
+        {
+            // Debug logging is expensive; keep it out of release builds.
+            static CALL_COUNT: AtomicUsize = AtomicUsize::new(0);
+            let call_count = CALL_COUNT.fetch_add(1, Ordering::Relaxed) + 1;
+            if call_count % 1000 == 0 {
+                eprintln!("ENGINE process_audio_internal call {}:", call_count);
+                eprintln!(
+                    "  output_left.len()={}, output_right.len()={}",
+                    output_left.len(),
+                    output_right.len()
+                );
+                eprintln!("  self.block_size={}", self.block_size);
+            }
+        }
+
+        let start = Instant::now();
+
+        // Reuse preallocated buffers sized to the engine's block size.
+        let block_len = self.block_size.max(1);
+        self.mix_left.resize(block_len, 0.0);
+        self.mix_right.resize(block_len, 0.0);
+        self.voice_left.resize(block_len, 0.0);
+        self.voice_right.resize(block_len, 0.0);
+        self.effect_left.resize(block_len, 0.0);
+        self.effect_right.resize(block_len, 0.0);
+        self.dry_left.resize(block_len, 0.0);
+        self.dry_right.resize(block_len, 0.0);
+
+        self.mix_left.fill(0.0);
+        self.mix_right.fill(0.0);
+        self.voice_left.fill(0.0);
+        self.voice_right.fill(0.0);
+        self.effect_left.fill(0.0);
+        self.effect_right.fill(0.0);
+        self.dry_left.fill(0.0);
+        self.dry_right.fill(0.0);
 
         let param_voice_count = if block_len > 0 && !gates.is_empty() {
             (gates.len() / block_len).max(1)
@@ -659,7 +1684,36 @@ impl AudioEngine {
             block_len
         };
 
-        for (i, voice) in self.voices.iter_mut().enumerate() {
+        // Gate onsets choke the other voices of their group before any voice
+        // is rendered.
+        let gate_of = |i: usize| {
+            if gates.is_empty() {
+                return self
+                    .allocator
+                    .note(i)
+                    .map_or(0.0, |allocated| allocated.gate());
+            }
+            let start = i.saturating_mul(gate_buffer_len);
+            match gates.get(start..(start + gate_buffer_len).min(gates.len())) {
+                Some(slice) if i < param_voice_count && !slice.is_empty() => {
+                    slice.iter().copied().fold(0.0_f32, f32::max)
+                }
+                _ => gates.get(i).copied().unwrap_or(0.0),
+            }
+        };
+        self.choke.begin_block(gate_of);
+
+        self.effect_stack.begin_sidechain_block(block_len);
+        self.surround.begin_block(block_len);
+        let transport_clock = self.transport.advance(block_len);
+        let main_gains = self.parts.main_config().bus_gains();
+        let voices = self
+            .voices
+            .iter_mut()
+            .map(|voice| (voice, main_gains))
+            .chain(self.parts.extra_voices_mut());
+        let mut active_voices = 0;
+        for (i, (voice, (send_gain, dry_gain))) in voices.enumerate() {
             let gate_slice = if gate_buffer_len > 0 && i < param_voice_count {
                 let start = i.saturating_mul(gate_buffer_len);
                 let end = (start + gate_buffer_len).min(gates.len());
@@ -673,6 +1727,12 @@ impl AudioEngine {
             } else {
                 gate_slice.iter().copied().fold(0.0_f32, f32::max)
             };
+            // Choked voices stay released until the host lets go of the gate.
+            let (gate_slice, gate) = if self.choke.is_held_off(i) {
+                (&[][..], 0.0)
+            } else {
+                (gate_slice, gate)
+            };
             let frequency_slice = if frequency_buffer_len > 0 && i < param_voice_count {
                 let start = i.saturating_mul(frequency_buffer_len);
                 let end = (start + frequency_buffer_len).min(frequencies.len());
@@ -685,8 +1745,41 @@ impl AudioEngine {
             } else {
                 *frequency_slice.first().unwrap_or(&440.0)
             };
-            let gain = gains.get(i).copied().unwrap_or(1.0);
             let velocity = velocities.get(i).copied().unwrap_or(0.0);
+            // Voices assigned by `note_on` play their note when the host sends
+            // no gate buffers.
+            let allocated = if gates.is_empty() {
+                self.allocator.note(i)
+            } else {
+                None
+            };
+            let release_velocity = release_velocities
+                .get(i)
+                .copied()
+                .unwrap_or(DEFAULT_RELEASE_VELOCITY);
+            let (gate, frequency, frequency_slice, velocity, release_velocity) = match allocated {
+                Some(allocated) => {
+                    // Held-off voices fade out on the note they were playing.
+                    let (gate, frequency) = if self.choke.is_held_off(i) {
+                        (0.0, voice.current_frequency)
+                    } else {
+                        (allocated.gate(), allocated.frequency())
+                    };
+                    (
+                        gate,
+                        frequency,
+                        &[][..],
+                        allocated.velocity,
+                        allocated.release_velocity,
+                    )
+                }
+                None => (gate, frequency, frequency_slice, velocity, release_velocity),
+            };
+            let gain = gains.get(i).copied().unwrap_or(1.0);
+            let gain_end = gain_ends.get(i).copied().unwrap_or(gain);
+            let velocity_end = velocity_ends.get(i).copied().unwrap_or(velocity);
+            let pressure = pressures.get(i).copied().unwrap_or(0.0);
+            let timbre = timbres.get(i).copied().unwrap_or(0.0);
 
             // Debug: Log voice parameters when gate is on or when there's activity
             if gate > 0.0 || voice.current_gate > 0.0 {
@@ -699,6 +1792,11 @@ impl AudioEngine {
             voice.current_gate = gate;
             voice.current_frequency = frequency;
             voice.current_velocity = velocity;
+            voice.velocity_ramp_target = (velocity_end != velocity).then_some(velocity_end);
+            voice.current_pressure = pressure;
+            voice.current_timbre = timbre;
+            voice.current_release_velocity = release_velocity;
+            voice.graph.set_transport_clock(transport_clock);
 
             if macro_buffer_len > 0 {
                 for macro_idx in 0..MACRO_COUNT {
@@ -714,9 +1812,10 @@ impl AudioEngine {
             self.voice_right.fill(0.0);
 
             // Process the full block_size
-            let zero_gate = [0.0_f32];
+            // Allocated notes have no gate buffer; theirs is the allocator's gate.
+            let held_gate = [if allocated.is_some() { gate } else { 0.0 }];
             let gate_buffer = if gate_slice.is_empty() {
-                &zero_gate
+                &held_gate
             } else {
                 gate_slice
             };
@@ -732,16 +1831,59 @@ impl AudioEngine {
                 &mut self.voice_left,
                 &mut self.voice_right,
             );
+            if self
+                .choke
+                .apply_fade(i, &mut self.voice_left, &mut self.voice_right)
+            {
+                voice.cull();
+            }
+            if voice.active {
+                active_voices += 1;
+            }
+            self.effect_stack
+                .accumulate_sidechain(|node_id, port| voice.node_output(node_id, port));
+            self.surround.pan_voice(
+                i,
+                &mut self.voice_left,
+                &mut self.voice_right,
+                gain,
+                gain_end,
+                send_gain + dry_gain,
+            );
 
-            // Mix voices together
+            // Mix voices together, ramping the gain towards gain_end
+            let gain_step = (gain_end - gain) / self.voice_left.len().max(1) as f32;
             for (sample_idx, (left, right)) in
                 self.voice_left.iter().zip(self.voice_right.iter()).enumerate()
             {
-                self.mix_left[sample_idx] += left * gain;
-                self.mix_right[sample_idx] += right * gain;
+                let g = gain + gain_step * sample_idx as f32;
+                self.mix_left[sample_idx] += left * g * send_gain;
+                self.mix_right[sample_idx] += right * g * send_gain;
+                if dry_gain != 0.0 {
+                    self.dry_left[sample_idx] += left * g * dry_gain;
+                    self.dry_right[sample_idx] += right * g * dry_gain;
+                }
             }
         }
 
+        self.headroom.apply(
+            active_voices,
+            [
+                &mut self.mix_left,
+                &mut self.mix_right,
+                &mut self.dry_left,
+                &mut self.dry_right,
+            ],
+        );
+
+        // Kit pads go through the effects with the voices.
+        self.kit.process(
+            &mut self.mix_left,
+            &mut self.mix_right,
+            &mut self.voice_left,
+            &mut self.voice_right,
+        );
+
         // Process effects with full block_size buffers
         self.effect_stack
             .process_audio(
@@ -751,6 +1893,14 @@ impl AudioEngine {
                 &mut self.effect_right,
             );
 
+        // Parts that don't send fully to the effects bypass them here.
+        for (out, dry) in self.effect_left.iter_mut().zip(&self.dry_left) {
+            *out += dry;
+        }
+        for (out, dry) in self.effect_right.iter_mut().zip(&self.dry_right) {
+            *out += dry;
+        }
+
         // Apply master gain
         if master_gain != 1.0 {
             for sample in self.effect_left.iter_mut() {
@@ -761,6 +1911,16 @@ impl AudioEngine {
             }
         }
 
+        // Surround layouts take the rear feeds back out of the front pair.
+        let (spread_left, spread_right) = self.effect_stack.spread_output();
+        self.surround.finish_block(
+            &mut self.effect_left,
+            &mut self.effect_right,
+            spread_left,
+            spread_right,
+            master_gain,
+        );
+
         // CRITICAL: Only copy the requested number of samples to the output
         let copy_len = output_left.len().min(self.block_size);
         output_left[..copy_len].copy_from_slice(&self.effect_left[..copy_len]);
@@ -773,6 +1933,18 @@ impl AudioEngine {
         if copy_len < output_right.len() {
             output_right[copy_len..].fill(0.0);
         }
+        self.output
+            .process(&mut output_left[..copy_len], &mut output_right[..copy_len]);
+        self.correlation
+            .process(&output_left[..copy_len], &output_right[..copy_len]);
+        if self.scope.source() == ScopeSource::Master {
+            self.scope
+                .push(&output_left[..copy_len], &output_right[..copy_len]);
+        } else {
+            let extra_voices = self.parts.extra_voices();
+            self.scope
+                .push_nodes(self.voices.iter().chain(extra_voices), copy_len);
+        }
 
         let elapsed_sec = start.elapsed().as_secs_f64();
         let quantum_sec = self.block_size as f64 / self.sample_rate as f64;
@@ -782,13 +1954,57 @@ impl AudioEngine {
             self.last_cpu_usage = ((self.cpu_time_accum / self.audio_time_accum) * 100.0) as f32;
             self.cpu_time_accum = 0.0;
             self.audio_time_accum = 0.0;
+            self.handle_overload();
         }
+        self.record_snapshot(block_len);
     }
 
     pub fn block_size(&self) -> usize {
         self.block_size
     }
 
+    /// Starts keeping `length` samples (up to `MAX_SCOPE_LENGTH`) of the XY
+    /// scope signals for `xy_scope_capture`; 0 stops it.
+    pub fn set_xy_scope_length(&mut self, length: usize) {
+        self.scope.set_length(length);
+    }
+
+    /// Scopes the master output, left on X and right on Y. The default.
+    pub fn set_xy_scope_master(&mut self) {
+        self.scope.set_source(ScopeSource::Master);
+    }
+
+    /// Scopes two voice node outputs against each other, summed over voices.
+    pub fn set_xy_scope_nodes(
+        &mut self,
+        x: (NodeId, PortId),
+        y: (NodeId, PortId),
+    ) -> Result<(), String> {
+        for (node_id, _) in [x, y] {
+            let exists = self
+                .voices
+                .first()
+                .is_some_and(|voice| voice.graph.get_node(node_id).is_some());
+            if !exists {
+                return Err(format!("Node {} not found", node_id.to_string()));
+            }
+        }
+        self.scope.set_source(ScopeSource::Nodes { x, y });
+        Ok(())
+    }
+
+    /// The latest `length` XY samples, starting on a rising zero crossing of
+    /// X when there is one. `None` while the scope is off or still filling.
+    pub fn xy_scope_capture(&self) -> Option<ScopeCapture> {
+        self.scope.capture()
+    }
+
+    /// Stereo correlation of the master output over the last few hundred
+    /// milliseconds: 1.0 mono, 0.0 unrelated channels, below 0.0 out of phase.
+    pub fn get_correlation(&self) -> f32 {
+        self.correlation.correlation()
+    }
+
     pub fn process_with_frame(
         &mut self,
         frame: &AutomationFrame,
@@ -800,7 +2016,12 @@ impl AudioEngine {
             frame.gates(),
             frame.frequencies(),
             frame.gains(),
+            frame.gain_ends(),
             frame.velocities(),
+            frame.velocity_ends(),
+            frame.pressures(),
+            frame.timbres(),
+            frame.release_velocities(),
             frame.macro_buffers(),
             frame.macro_buffer_len(),
             master_gain,
@@ -809,519 +2030,4630 @@ impl AudioEngine {
         );
     }
 
-    pub fn sample_rate(&self) -> f32 {
-        self.sample_rate
+    /// Renders `notes` offline into a stereo buffer of `length_samples`.
+    ///
+    /// Notes are assigned to voices round-robin in the order given; a voice keeps
+    /// its last pitch after the gate closes so release tails render naturally.
+    pub fn render_notes(
+        &mut self,
+        notes: &[RenderNote],
+        length_samples: usize,
+    ) -> (Vec<f32>, Vec<f32>) {
+        self.render_notes_with(notes, length_samples, |_, _, _| {})
     }
 
-    pub fn num_voices(&self) -> usize {
-        self.num_voices
+    /// Renders a Standard MIDI File through the current patch, following its
+    /// tempo map, with `tail_seconds` of extra output for the release tails.
+    pub fn render_midi_file(
+        &mut self,
+        midi_bytes: &[u8],
+        tail_seconds: f32,
+    ) -> Result<(Vec<f32>, Vec<f32>), String> {
+        let file = MidiFile::parse(midi_bytes)?;
+        let notes = file.render_notes(self.sample_rate);
+        let length_samples = ((file.duration_seconds() as f32 + tail_seconds.max(0.0))
+            * self.sample_rate)
+            .ceil() as usize;
+        Ok(self.render_notes(&notes, length_samples))
     }
 
-    fn set_effect_active(&mut self, index: usize, active: bool) {
-        if let Some(effect) = self.effect_stack.effects.get_mut(index) {
-            effect.node.set_active(active);
-        }
+    /// `render_notes`, calling `on_block` with the engine and the block's
+    /// output after every block.
+    fn render_notes_with(
+        &mut self,
+        notes: &[RenderNote],
+        length_samples: usize,
+        mut on_block: impl FnMut(&AudioEngine, &[f32], &[f32]),
+    ) -> (Vec<f32>, Vec<f32>) {
+        let block = self.block_size.max(1);
+        let num_voices = self.voices.len().max(1);
+        let mut frame = AutomationFrame::with_dimensions(num_voices, MACRO_COUNT, block);
+        let mut frequencies = vec![440.0; num_voices];
+        let mut velocities = vec![0.0; num_voices];
+
+        let mut left = vec![0.0; length_samples];
+        let mut right = vec![0.0; length_samples];
+        let mut block_left = vec![0.0; block];
+        let mut block_right = vec![0.0; block];
+
+        let mut pos = 0;
+        while pos < length_samples {
+            let end = pos + block;
+            for (index, note) in notes.iter().enumerate() {
+                if note.overlaps(pos, end) {
+                    frequencies[index % num_voices] = note.frequency;
+                    velocities[index % num_voices] = note.velocity;
+                }
+            }
+            for voice in 0..num_voices {
+                frame.set_voice_values(voice, 0.0, frequencies[voice], velocities[voice], 1.0);
+            }
+            for (index, note) in notes.iter().enumerate() {
+                let offset = (index % num_voices) * block;
+                for i in note.gate_range(pos, end) {
+                    frame.gates_mut()[offset + i] = 1.0;
+                }
+            }
+
+            self.process_with_frame(&frame, 1.0, &mut block_left, &mut block_right);
+            on_block(self, &block_left, &block_right);
+
+            let n = block.min(length_samples - pos);
+            left[pos..pos + n].copy_from_slice(&block_left[..n]);
+            right[pos..pos + n].copy_from_slice(&block_right[..n]);
+            pos = end;
+        }
+
+        (left, right)
     }
 
-    pub fn set_chorus_active(&mut self, active: bool) {
-        self.set_effect_active(0, active);
+    /// Plays `notes` through the current patch and reports how much level the
+    /// render loses when summed to mono. Large losses point at chorus, widener
+    /// or delay settings that cancel on mono playback.
+    pub fn check_mono_compatibility(
+        &mut self,
+        notes: &[RenderNote],
+        length_samples: usize,
+    ) -> MonoCompatibilityReport {
+        let (left, right) = self.render_notes(notes, length_samples);
+        analyze_mono_compatibility(&left, &right)
     }
 
-    pub fn set_delay_active(&mut self, active: bool) {
-        self.set_effect_active(1, active);
+    /// Plays `notes` through the current patch and reports the level at every
+    /// stage: each voice node on the audio path (all sounding voices combined),
+    /// each running master effect's input and output, and the final output.
+    /// Use `GainStagingReport::problems` to find stages that clip or throw
+    /// away a lot of level.
+    pub fn analyze_gain_staging(
+        &mut self,
+        notes: &[RenderNote],
+        length_samples: usize,
+    ) -> GainStagingReport {
+        struct VoiceStage {
+            name: &'static str,
+            input: LevelMeter,
+            output: LevelMeter,
+            has_input: bool,
+        }
+
+        let mut voice_stages: FxHashMap<NodeId, VoiceStage> = FxHashMap::default();
+        let mut master = LevelMeter::default();
+        let mut input_sum = Vec::new();
+        self.effect_stack.set_level_metering(true);
+
+        self.render_notes_with(notes, length_samples, |engine, left, right| {
+            master.add(left);
+            master.add(right);
+            for voice in &engine.voices {
+                let graph = &voice.graph;
+                for (&node_id, node) in &graph.nodes {
+                    // Only nodes on the audio path: the output and anything
+                    // feeding an audio input. Modulators are left out.
+                    let feeds_audio = node_id == voice.output_node
+                        || graph
+                            .connections
+                            .values()
+                            .any(|conn| conn.from_node == node_id && conn.to_port.is_audio_input());
+                    if !feeds_audio {
+                        continue;
+                    }
+                    let ports = node.get_ports();
+                    let mut outputs = ports
+                        .iter()
+                        .filter(|(port, is_output)| **is_output && port.is_audio_output())
+                        .filter_map(|(&port, _)| voice.node_output(node_id, port))
+                        .peekable();
+                    // Voices that skipped the block have no fresh buffers.
+                    if outputs.peek().is_none() {
+                        continue;
+                    }
+                    let stage = voice_stages.entry(node_id).or_insert_with(|| VoiceStage {
+                        name: node.name(),
+                        input: LevelMeter::default(),
+                        output: LevelMeter::default(),
+                        has_input: false,
+                    });
+                    for buffer in outputs {
+                        stage.output.add(buffer);
+                    }
+
+                    for input_port in ports.keys().filter(|port| port.is_audio_input()) {
+                        let sources: Vec<_> = graph
+                            .connections
+                            .values()
+                            .filter(|conn| conn.to_node == node_id && conn.to_port == *input_port)
+                            .collect();
+                        if sources.is_empty() {
+                            continue;
+                        }
+                        input_sum.clear();
+                        input_sum.resize(left.len(), 0.0);
+                        for conn in sources {
+                            if let Some(buffer) = voice.node_output(conn.from_node, conn.from_port)
+                            {
+                                for (sum, sample) in input_sum.iter_mut().zip(buffer) {
+                                    *sum += sample * conn.amount;
+                                }
+                            }
+                        }
+                        stage.input.add(&input_sum);
+                        stage.has_input = true;
+                    }
+                }
+            }
+        });
+
+        let mut stages = Vec::new();
+        // Signal-flow order, as the first voice processes its nodes.
+        if let Some(voice) = self.voices.first() {
+            for node_id in &voice.graph.processing_order {
+                if let Some(stage) = voice_stages.remove(node_id) {
+                    stages.push(StageLevels::new(
+                        node_id.0.to_string(),
+                        stage.name.to_string(),
+                        StageKind::Voice,
+                        stage.has_input.then_some(&stage.input),
+                        &stage.output,
+                    ));
+                }
+            }
+        }
+        for (index, (input, output)) in self.effect_stack.effect_levels().iter().enumerate() {
+            if output.is_empty() {
+                continue;
+            }
+            stages.push(StageLevels::new(
+                (EFFECT_NODE_ID_OFFSET + index).to_string(),
+                self.effect_stack.effects[index].node.name().to_string(),
+                StageKind::Effect,
+                Some(input),
+                output,
+            ));
+        }
+        self.effect_stack.set_level_metering(false);
+        stages.push(StageLevels::new(
+            "master".to_string(),
+            "Output".to_string(),
+            StageKind::Master,
+            None,
+            &master,
+        ));
+
+        GainStagingReport { stages }
     }
 
-    pub fn set_reverb_active(&mut self, active: bool) {
-        self.set_effect_active(2, active);
+    /// Null test for a settings change: loads `patch_json` into two fresh engines,
+    /// applies `change` to the second one, renders `notes` through both and
+    /// compares the results.
+    pub fn null_test_patch<F>(
+        sample_rate: f32,
+        num_voices: usize,
+        patch_json: &str,
+        notes: &[RenderNote],
+        length_samples: usize,
+        change: F,
+    ) -> Result<NullTestReport, String>
+    where
+        F: FnOnce(&mut AudioEngine) -> Result<(), String>,
+    {
+        let mut reference = AudioEngine::new(sample_rate, num_voices);
+        reference.init_with_patch(patch_json)?;
+        let mut candidate = AudioEngine::new(sample_rate, num_voices);
+        candidate.init_with_patch(patch_json)?;
+        change(&mut candidate)?;
+
+        let (reference_left, reference_right) = reference.render_notes(notes, length_samples);
+        let (candidate_left, candidate_right) = candidate.render_notes(notes, length_samples);
+        Ok(compare_renders(
+            &reference_left,
+            &reference_right,
+            &candidate_left,
+            &candidate_right,
+            sample_rate,
+        ))
     }
 
-    pub fn update_compressor(
-        &mut self,
-        node_id: usize,
-        active: bool,
-        threshold_db: f32,
-        ratio: f32,
-        attack_ms: f32,
-        release_ms: f32,
-        makeup_gain_db: f32,
-        mix: f32,
-    ) -> Result<(), String> {
-        let effect_id = node_id
-            .checked_sub(EFFECT_NODE_ID_OFFSET)
-            .ok_or_else(|| "Invalid compressor node id".to_string())?;
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
 
-        let effect = self
-            .effect_stack
-            .effects
-            .get_mut(effect_id)
-            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+    pub fn num_voices(&self) -> usize {
+        self.num_voices
+    }
 
-        if let Some(comp) = effect.node.as_any_mut().downcast_mut::<Compressor>() {
-            comp.set_threshold_db(threshold_db);
-            comp.set_ratio(ratio);
-            comp.set_attack_ms(attack_ms);
-            comp.set_release_ms(release_ms);
-            comp.set_makeup_gain_db(makeup_gain_db);
-            comp.set_mix(mix);
-            comp.set_active(active);
-            Ok(())
-        } else {
-            Err(format!("Effect at index {} is not a compressor", effect_id))
+    fn set_effect_active(&mut self, index: usize, active: bool) {
+        if let Some(effect) = self.effect_stack.effects.get_mut(index) {
+            effect.node.set_active(active);
         }
     }
 
-    pub fn update_saturation(
-        &mut self,
-        node_id: usize,
-        drive: f32,
-        mix: f32,
-        active: bool,
-    ) -> Result<(), String> {
-        let effect_id = node_id
-            .checked_sub(EFFECT_NODE_ID_OFFSET)
-            .ok_or_else(|| "Invalid saturation node id".to_string())?;
+    /// Sets the engine-wide transpose (semitones) and fine tune (cents).
+    pub fn set_master_tuning(&mut self, transpose: f32, fine: f32) {
+        self.locks.remember_master_tuning(transpose, fine);
+        for voice in &mut self.voices {
+            voice.graph.set_master_tuning(transpose, fine);
+        }
+    }
 
-        let effect = self
-            .effect_stack
-            .effects
-            .get_mut(effect_id)
-            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+    /// Sets a per-voice detune offset in cents.
+    pub fn set_voice_detune(&mut self, voice_index: usize, cents: f32) -> Result<(), String> {
+        let voice = self
+            .voices
+            .get_mut(voice_index)
+            .ok_or_else(|| format!("Invalid voice index: {}", voice_index))?;
+        voice.graph.set_voice_detune(cents);
+        self.locks.remember_voice_detune(voice_index, cents);
+        Ok(())
+    }
 
-        if let Some(saturation) = effect.node.as_any_mut().downcast_mut::<Saturation>() {
-            saturation.set_drive(drive);
-            saturation.set_mix(mix);
-            saturation.set_active(active);
-            Ok(())
-        } else {
-            Err(format!(
-                "Effect at index {} is not a saturation effect",
-                effect_id
-            ))
+    /// Analog spread (0..1): gives each voice small fixed offsets to pitch,
+    /// filter cutoff and envelope times, the same every time the patch loads.
+    pub fn set_analog_spread(&mut self, amount: f32) {
+        self.analog_spread = amount.clamp(0.0, 1.0);
+        for (index, voice) in self.voices.iter_mut().enumerate() {
+            voice.graph.set_voice_variation(VoiceVariation::for_voice(
+                self.analog_spread_seed,
+                index,
+                self.analog_spread,
+            ));
         }
     }
 
-    pub fn update_bitcrusher(
+    /// Sparse macro update: `value` at the start of the next block, ramping to
+    /// `ramp_target` by its last sample when given, otherwise gliding to `value`
+    /// over one block. The value is held until the next update, so the macro
+    /// buffers passed to `process_audio` may be left empty. `voice_index` of
+    /// `None` updates every voice.
+    pub fn set_macro_value(
         &mut self,
-        node_id: usize,
-        bits: u8,
-        downsample_factor: usize,
-        mix: f32,
-        active: bool,
+        voice_index: Option<usize>,
+        macro_index: usize,
+        value: f32,
+        ramp_target: Option<f32>,
     ) -> Result<(), String> {
-        let effect_id = node_id
-            .checked_sub(EFFECT_NODE_ID_OFFSET)
-            .ok_or_else(|| "Invalid bitcrusher node id".to_string())?;
-
-        let effect = self
-            .effect_stack
-            .effects
-            .get_mut(effect_id)
-            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
-
-        if let Some(bitcrusher) = effect.node.as_any_mut().downcast_mut::<Bitcrusher>() {
-            bitcrusher.set_bits(bits);
-            bitcrusher.set_downsample_factor(downsample_factor);
-            bitcrusher.set_mix(mix);
-            bitcrusher.set_active(active);
-            Ok(())
-        } else {
-            Err(format!(
-                "Effect at index {} is not a bitcrusher effect",
-                effect_id
-            ))
+        if voice_index.is_none() {
+            self.locks
+                .remember_macro_value(macro_index, ramp_target.unwrap_or(value));
         }
+        let voices: &mut [Voice] = match voice_index {
+            Some(index) => {
+                let voice = self
+                    .voices
+                    .get_mut(index)
+                    .ok_or_else(|| format!("Invalid voice index: {}", index))?;
+                std::slice::from_mut(voice)
+            }
+            None => &mut self.voices,
+        };
+        for voice in voices {
+            voice.set_macro_sparse(macro_index, value, ramp_target)?;
+        }
+        Ok(())
     }
 
-    // Node creation methods
-    pub fn create_oscillator(&mut self) -> Result<usize, String> {
-        let osc_id = NodeId::new();
+    /// Drives a macro in every voice from that voice's velocity, aftertouch,
+    /// the mod wheel or a random value per note, scaled by `amount`, in place
+    /// of the values the host sends. `None` hands the macro back to the host.
+    pub fn set_macro_source(
+        &mut self,
+        macro_index: usize,
+        source: Option<MacroSource>,
+        amount: f32,
+    ) -> Result<(), String> {
         for voice in &mut self.voices {
-            voice.graph.add_node_with_id(
-                osc_id,
-                Box::new(AnalogOscillator::new(
-                    self.sample_rate,
-                    Waveform::Sine,
-                    self.wavetable_banks.clone(),
-                )),
-            );
+            voice.set_macro_source(macro_index, source, amount)?;
         }
-        Ok(osc_id.0.as_u128() as usize)
+        Ok(())
     }
 
-    pub fn create_wavetable_oscillator(&mut self) -> Result<usize, String> {
-        let osc_id = NodeId::new();
+    /// Mod wheel position (0..1) for macros driven by it.
+    pub fn set_mod_wheel(&mut self, value: f32) {
         for voice in &mut self.voices {
-            voice.graph.add_node_with_id(
-                osc_id,
-                Box::new(WavetableOscillator::new(
-                    self.sample_rate,
-                    self.wavetable_synthbank.clone(),
-                )),
-            );
+            voice.current_mod_wheel = value.clamp(0.0, 1.0);
         }
-        Ok(osc_id.0.as_u128() as usize)
     }
 
-    pub fn create_mixer(&mut self) -> Result<usize, String> {
-        let mixer_id = NodeId::new();
+    /// Routes a macro to a target port in every voice through a range mapping.
+    /// A macro may drive any number of targets; connecting the same target again
+    /// replaces its route, and an `amount` of zero or less only removes it.
+    pub fn connect_macro(
+        &mut self,
+        macro_index: usize,
+        target: ModulationTarget,
+    ) -> Result<(), String> {
         for voice in &mut self.voices {
-            voice
-                .graph
-                .add_node_with_id(mixer_id, Box::new(Mixer::new()));
-            voice.graph.set_output_node(mixer_id);
+            voice.remove_macro_route(macro_index, target.node_id, target.port_id)?;
+            if target.amount > 0.0 {
+                voice.add_macro_mapping(macro_index, target.clone())?;
+            }
         }
-        Ok(mixer_id.0.as_u128() as usize)
+        Ok(())
     }
 
-    pub fn create_envelope(&mut self) -> Result<usize, String> {
-        let envelope_id = NodeId::new();
+    /// Changes the range mapping of an existing macro route in every voice.
+    pub fn set_macro_mapping(
+        &mut self,
+        macro_index: usize,
+        target_node: NodeId,
+        target_port: PortId,
+        mapping: MacroMapping,
+    ) -> Result<(), String> {
         for voice in &mut self.voices {
-            voice.graph.add_node_with_id(
-                envelope_id,
-                Box::new(Envelope::new(self.sample_rate, EnvelopeConfig::default())),
-            );
+            if !voice.set_macro_mapping(macro_index, target_node, target_port, mapping)? {
+                return Err(format!(
+                    "Macro {} has no route to {:?} on node {}",
+                    macro_index, target_port, target_node.0
+                ));
+            }
         }
-        Ok(envelope_id.0.as_u128() as usize)
+        Ok(())
     }
 
-    pub fn create_lfo(&mut self) -> Result<usize, String> {
-        let lfo_id = NodeId::new();
-        for voice in &mut self.voices {
+    /// Current macro routes in patch form. Routes are identical in every voice,
+    /// so they are read from the first.
+    pub fn macro_routes(&self) -> Vec<MacroRouteState> {
+        self.voices.first().map_or_else(Vec::new, |voice| {
             voice
-                .graph
-                .add_node_with_id(lfo_id, Box::new(Lfo::new(self.sample_rate)));
+                .macro_routes()
+                .map(|(macro_index, target)| MacroRouteState::from_target(macro_index, target))
+                .collect()
+        })
+    }
+
+    fn apply_macro_state(&mut self, macros: &MacroState) -> Result<(), String> {
+        for route in &macros.routes {
+            let target_node = parse_node_id(&route.target_id)?;
+            let target_port = port_id_from_u32(route.target_port)?;
+            let modulation_type = route
+                .modulation_type
+                .map_or(Ok(ModulationType::VCA), modulation_type_from_i32)?;
+            let modulation_transform = route.modulation_transform.map_or(
+                Ok(ModulationTransformation::None),
+                modulation_transform_from_i32,
+            )?;
+            self.connect_macro(
+                route.macro_index,
+                ModulationTarget {
+                    node_id: target_node,
+                    port_id: target_port,
+                    amount: route.amount,
+                    modulation_type,
+                    modulation_transform,
+                    mapping: route.mapping,
+                    mapped_buffer_idx: None,
+                },
+            )?;
         }
-        Ok(lfo_id.0.as_u128() as usize)
+        for source in &macros.sources {
+            self.set_macro_source(source.macro_index, Some(source.source), source.amount)?;
+        }
+        if !self.locks.is_locked(LockableParameter::MacroValues) {
+            for (macro_index, &value) in macros.values.iter().enumerate().take(MACRO_COUNT) {
+                self.set_macro_value(None, macro_index, value, None)?;
+            }
+        }
+        Ok(())
     }
 
-    pub fn create_filter(&mut self) -> Result<usize, String> {
-        let filter_id = NodeId::new();
+    /// Switches the SIMD effect kernels on or off. Returns whether SIMD is in use
+    /// afterwards (always false on targets without a vector unit).
+    pub fn set_simd_enabled(&mut self, enabled: bool) -> bool {
+        simd_kernels::set_simd_enabled(enabled)
+    }
+
+    pub fn is_simd_enabled(&self) -> bool {
+        simd_kernels::simd_enabled()
+    }
+
+    /// Sets the parameter smoothing time (ms) for every node and effect without
+    /// a per-node override. Nodes without smoothed parameters ignore it (see
+    /// `AudioNode::set_smoothing_time_ms`).
+    pub fn set_parameter_smoothing(&mut self, time_ms: f32) {
+        let time_ms = time_ms.max(0.0);
+        self.locks.remember_smoothing(time_ms);
         for voice in &mut self.voices {
-            voice
-                .graph
-                .add_node_with_id(filter_id, Box::new(FilterCollection::new(self.sample_rate)));
+            voice.graph.set_smoothing_time_ms(time_ms);
         }
-        Ok(filter_id.0.as_u128() as usize)
+        self.effect_stack.set_smoothing_time_ms(time_ms);
     }
 
-    pub fn create_glide(&mut self, glide_time: f32) -> Result<usize, String> {
-        let glide_id = NodeId::new();
-        for voice in &mut self.voices {
-            voice
-                .graph
-                .add_node_with_id(glide_id, Box::new(Glide::new(self.sample_rate, glide_time)));
-            voice.graph.global_glide_node = Some(glide_id);
-            if let Some(global_freq) = voice.graph.global_frequency_node {
-                voice.graph.add_connection(Connection {
-                    from_node: global_freq,
-                    from_port: PortId::GlobalFrequency,
-                    to_node: glide_id,
-                    to_port: PortId::AudioInput0,
-                    amount: 1.0,
-                    modulation_type: ModulationType::Additive,
-                    modulation_transform: ModulationTransformation::None,
-                });
-            }
-            if let Some(gate_mixer_id) = voice.graph.global_gatemixer_node {
-                voice.graph.add_connection(Connection {
-                    from_node: gate_mixer_id,
-                    from_port: PortId::CombinedGate,
-                    to_node: glide_id,
-                    to_port: PortId::CombinedGate,
-                    amount: 1.0,
-                    modulation_type: ModulationType::Additive,
-                    modulation_transform: ModulationTransformation::None,
-                });
-            }
+    /// Locks or unlocks a parameter against patch loads. A locked parameter
+    /// keeps the value last set through the engine API when a patch is loaded.
+    pub fn set_parameter_lock(&mut self, parameter: LockableParameter, locked: bool) {
+        self.locks.set_locked(parameter, locked);
+    }
+
+    pub fn is_parameter_locked(&self, parameter: LockableParameter) -> bool {
+        self.locks.is_locked(parameter)
+    }
+
+    /// Loads a patch into multi-timbral part `part` (1..MAX_PARTS; part 0 is the
+    /// patch loaded with `init_with_patch`). Only the patch's voices are used:
+    /// every part plays through the shared effect stack. Returns the part's
+    /// voice count; see `part_voice_range` for where its voices sit in the
+    /// per-voice parameter arrays.
+    pub fn load_part_patch(&mut self, part: usize, patch_json: &str) -> Result<usize, String> {
+        if part == 0 || part >= MAX_PARTS {
+            return Err(format!("Invalid part index for a part patch: {}", part));
         }
-        Ok(glide_id.0.as_u128() as usize)
+        let mut builder = self.part_builder();
+        builder.init_with_patch(patch_json)?;
+        let voices = std::mem::take(&mut builder.voices);
+        let voice_count = voices.len();
+        self.parts.set_voices(part, voices)?;
+        Ok(voice_count)
     }
 
-    /// Insert a Glide node between the global frequency source and the target node's
-    /// GlobalFrequency input, so that pitch changes are slewed.
-    pub fn insert_glide_on_global_frequency(
+    /// Removes the patch of part `part`, freeing its voices.
+    pub fn clear_part(&mut self, part: usize) -> Result<(), String> {
+        self.parts.set_voices(part, Vec::new())
+    }
+
+    /// Assigns a part to a MIDI channel (`None` for all channels) and key range.
+    pub fn set_part_assignment(
         &mut self,
-        glide_id: usize,
-        target_node: usize,
+        part: usize,
+        channel: Option<u8>,
+        lo_key: u8,
+        hi_key: u8,
     ) -> Result<(), String> {
-        let glide_node = NodeId(Uuid::from_u128(glide_id as u128));
-        let target_node_id = NodeId(Uuid::from_u128(target_node as u128));
+        let config = self.parts.config_mut(part)?;
+        config.channel = channel.map(|c| c.min(15));
+        config.lo_key = lo_key.min(127);
+        config.hi_key = hi_key.clamp(config.lo_key, 127);
+        Ok(())
+    }
 
-        for voice in &mut self.voices {
-            let global_freq_id = voice
-                .graph
-                .global_frequency_node
-                .ok_or_else(|| "GlobalFrequencyNode not found in voice graph".to_string())?;
+    /// Sets a part's output level and how much of it is sent through the effect
+    /// stack; the rest is mixed in dry after the effects.
+    pub fn set_part_mix(&mut self, part: usize, level: f32, send: f32) -> Result<(), String> {
+        let config = self.parts.config_mut(part)?;
+        config.level = level.max(0.0);
+        config.send = send.clamp(0.0, 1.0);
+        Ok(())
+    }
 
-            // Remove the direct GlobalFrequency -> target connection, if present.
-            voice.graph.remove_specific_connection(
-                global_freq_id,
-                target_node_id,
-                PortId::GlobalFrequency,
+    /// Indices of a part's voices in the per-voice parameter arrays passed to
+    /// `process_audio`, or `None` if the part has no voices.
+    pub fn part_voice_range(&self, part: usize) -> Option<std::ops::Range<usize>> {
+        self.parts.voice_range(part, self.voices.len())
+    }
+
+    /// First part whose channel and key range accept the note.
+    pub fn part_for_note(&self, channel: u8, note: u8) -> Option<usize> {
+        self.parts.part_for_note(channel, note, self.voices.len())
+    }
+
+    pub fn part_config(&self, part: usize) -> Option<PartConfig> {
+        self.parts.config(part).copied()
+    }
+
+    /// Sets a part's key and velocity zone. `fade` is the crossfade width, in
+    /// keys and velocity steps, at the zone's inner edges.
+    pub fn set_part_zone(
+        &mut self,
+        part: usize,
+        lo_key: u8,
+        hi_key: u8,
+        lo_vel: u8,
+        hi_vel: u8,
+        fade: u8,
+    ) -> Result<(), String> {
+        let config = self.parts.config_mut(part)?;
+        config.lo_key = lo_key.min(127);
+        config.hi_key = hi_key.clamp(config.lo_key, 127);
+        config.lo_vel = lo_vel.min(127);
+        config.hi_vel = hi_vel.clamp(config.lo_vel, 127);
+        config.fade = fade.min(127);
+        Ok(())
+    }
+
+    /// Gain of every part for a note; the host plays the note on each part
+    /// with a non-zero gain, passing the gain in that voice's `gains` entry.
+    pub fn part_zone_gains(&self, channel: u8, note: u8, velocity: u8) -> [f32; MAX_PARTS] {
+        self.parts
+            .zone_gains(channel, note, velocity, self.voices.len())
+    }
+
+    /// Assignment, zone and mix of every part, for storing with a bank.
+    pub fn parts_state(&self) -> Vec<PartConfig> {
+        self.parts.configs().to_vec()
+    }
+
+    pub fn set_parts_state(&mut self, configs: &[PartConfig]) {
+        self.parts.set_configs(configs);
+    }
+
+    /// Loads a patch as the drum-kit pad on MIDI key `key`. Only the patch's
+    /// first voice is kept; the pad is played with `kit_note_on`.
+    pub fn load_kit_pad(&mut self, key: u8, patch_json: &str) -> Result<(), String> {
+        let mut builder = self.part_builder();
+        builder.init_with_patch(patch_json)?;
+        let voice = builder
+            .voices
+            .drain(..)
+            .next()
+            .ok_or_else(|| "Kit pad patch has no voices".to_string())?;
+        self.kit.set_pad(key, voice);
+        Ok(())
+    }
+
+    /// Scales the voice mix by 1/sqrt(active voices) so big chords don't clip
+    /// before the limiter. Off by default.
+    pub fn set_polyphony_compensation(&mut self, enabled: bool) {
+        self.headroom.set_enabled(enabled);
+    }
+
+    pub fn polyphony_compensation(&self) -> bool {
+        self.headroom.is_enabled()
+    }
+
+    /// Tempo of the internal clock. Slaved transports follow their source.
+    pub fn set_transport_tempo(&mut self, bpm: f64) {
+        self.transport.set_tempo(bpm);
+    }
+
+    /// Current tempo, as measured when slaved to external pulses.
+    pub fn transport_tempo(&self) -> f64 {
+        self.transport.tempo()
+    }
+
+    pub fn transport_play(&mut self) {
+        self.transport.play();
+    }
+
+    pub fn transport_stop(&mut self) {
+        self.transport.stop();
+    }
+
+    pub fn transport_playing(&self) -> bool {
+        self.transport.is_playing()
+    }
+
+    /// Moves the transport, in beats.
+    pub fn set_transport_position(&mut self, beat: f64) {
+        self.transport.set_position(beat);
+    }
+
+    pub fn transport_position(&self) -> f64 {
+        self.transport.position()
+    }
+
+    /// Selects what the transport follows: its own tempo, an external pulse
+    /// buffer (`set_clock_pulses`) or host positions (`sync_transport_to_host`).
+    pub fn set_clock_source(&mut self, source: ClockSource) {
+        self.transport.set_source(source);
+    }
+
+    pub fn clock_source(&self) -> ClockSource {
+        self.transport.source()
+    }
+
+    /// Resolution of external clock pulses (24 for MIDI clock).
+    pub fn set_clock_pulses_per_beat(&mut self, pulses_per_beat: u32) {
+        self.transport.set_pulses_per_beat(pulses_per_beat);
+    }
+
+    pub fn clock_pulses_per_beat(&self) -> u32 {
+        self.transport.pulses_per_beat()
+    }
+
+    /// External clock pulses for the next block, one value per sample.
+    pub fn set_clock_pulses(&mut self, pulses: &[f32]) {
+        self.transport.set_pulses(pulses);
+    }
+
+    /// Host beat position and tempo at the start of the next block.
+    pub fn sync_transport_to_host(&mut self, beat: f64, bpm: f64) {
+        self.transport.sync_to_host(beat, bpm);
+    }
+
+    /// Puts a voice into a choke group (`None` removes it). A gate onset on
+    /// the voice releases every other voice of the group with a short fade,
+    /// e.g. closed hi-hat cutting the open one or one voice per group lines.
+    pub fn set_voice_choke_group(&mut self, voice_index: usize, group: Option<u8>) {
+        self.choke.set_group(voice_index, group);
+    }
+
+    pub fn voice_choke_group(&self, voice_index: usize) -> Option<u8> {
+        self.choke.group(voice_index)
+    }
+
+    pub fn clear_choke_groups(&mut self) {
+        self.choke.clear();
+    }
+
+    /// True once any kit pad is loaded; hosts route notes to `kit_note_on`
+    /// instead of the voice arrays in kit mode.
+    pub fn is_kit_mode(&self) -> bool {
+        !self.kit.is_empty()
+    }
+
+    pub fn remove_kit_pad(&mut self, key: u8) -> bool {
+        self.kit.remove_pad(key)
+    }
+
+    pub fn clear_kit(&mut self) {
+        self.kit.clear();
+    }
+
+    /// Sets a pad's output level and pan (-1 left to 1 right).
+    pub fn set_kit_pad_mix(&mut self, key: u8, level: f32, pan: f32) -> Result<(), String> {
+        self.kit.set_pad_mix(key, level, pan)
+    }
+
+    /// One-shot pads ignore note-off. Triggering a pad chokes the other pads of
+    /// its choke group.
+    pub fn set_kit_pad_options(
+        &mut self,
+        key: u8,
+        one_shot: bool,
+        choke_group: Option<u8>,
+    ) -> Result<(), String> {
+        self.kit.set_pad_options(key, one_shot, choke_group)
+    }
+
+    /// Triggers the pad on `key` (velocity 0..1). Returns false if the key has
+    /// no pad.
+    pub fn kit_note_on(&mut self, key: u8, velocity: f32) -> bool {
+        self.kit.note_on(key, velocity)
+    }
+
+    pub fn kit_note_off(&mut self, key: u8) -> bool {
+        self.kit.note_off(key)
+    }
+
+    pub fn kit_all_notes_off(&mut self) {
+        self.kit.all_notes_off();
+    }
+
+    /// Plays `note` (MIDI note number, velocity 0..1) on a voice chosen by the
+    /// voice allocator, stealing one by the steal mode when none is free, and
+    /// returns its index. Assigned voices follow the allocator whenever
+    /// `process_audio` gets an empty gate buffer.
+    pub fn note_on(&mut self, note: u8, velocity: f32) -> Option<usize> {
+        let assignment = self.allocator.note_on(note, velocity, &self.voices)?;
+        if assignment.stolen {
+            // Fade the old note out first so the envelopes see a fresh gate
+            // without a click.
+            self.choke.steal(assignment.voice);
+        }
+        Some(assignment.voice)
+    }
+
+    /// Releases the voices playing `note`. Returns false if none were.
+    pub fn note_off(&mut self, note: u8) -> bool {
+        self.allocator.note_off(note)
+    }
+
+    /// `note_off` with the note-off velocity (0..1), which the voices' release
+    /// velocity nodes, envelopes and samplers pick up.
+    pub fn note_off_with_velocity(&mut self, note: u8, release_velocity: f32) -> bool {
+        self.allocator
+            .note_off_with_velocity(note, release_velocity)
+    }
+
+    pub fn all_notes_off(&mut self) {
+        self.allocator.all_notes_off();
+    }
+
+    /// How `note_on` picks a voice when all of them are busy.
+    pub fn set_steal_mode(&mut self, mode: StealMode) {
+        self.allocator.set_mode(mode);
+    }
+
+    pub fn steal_mode(&self) -> StealMode {
+        self.allocator.mode()
+    }
+
+    /// Scratch engine used to build a part's voices. It shares the wavetable
+    /// banks and runs at the current quality mode.
+    fn part_builder(&self) -> Self {
+        Self {
+            voices: Vec::new(),
+            sample_rate: self.sample_rate,
+            num_voices: self.num_voices,
+            wavetable_synthbank: Rc::clone(&self.wavetable_synthbank),
+            wavetable_banks: Arc::clone(&self.wavetable_banks),
+            effect_stack: EffectStack::new(self.block_size),
+            ir_generator: ImpulseResponseGenerator::new(self.sample_rate),
+            cpu_time_accum: 0.0,
+            audio_time_accum: 0.0,
+            last_cpu_usage: 0.0,
+            quality_mode: self.effective_quality_mode(),
+            overload: OverloadProtection::new(),
+            node_health_checks: false,
+            stereo_voices: self.stereo_voices,
+            analog_spread: 0.0,
+            analog_spread_seed: 0,
+            allocator: VoiceAllocator::new(),
+            locks: ParameterLocks::new(),
+            parts: Parts::new(),
+            kit: DrumKit::new(self.sample_rate),
+            choke: ChokeGroups::new(self.sample_rate),
+            headroom: PolyphonyCompensation::new(self.sample_rate),
+            transport: Transport::new(self.sample_rate),
+            output: OutputStage::new(),
+            surround: SurroundPanner::new(self.sample_rate),
+            snapshots: SnapshotRecorder::new(),
+            snapshot_sink: None,
+            pending_snapshot: None,
+            loaded_layout: None,
+            jobs: JobQueue::new(),
+            imports: ImportHistory::default(),
+            auto_node_levels: false,
+            last_node_levels: None,
+            scope: XyScope::new(),
+            correlation: CorrelationMeter::new(self.sample_rate),
+            capacity_limits: None,
+            capacity_events: Vec::new(),
+            block_size: self.block_size,
+            mix_left: Vec::new(),
+            mix_right: Vec::new(),
+            voice_left: Vec::new(),
+            voice_right: Vec::new(),
+            effect_left: Vec::new(),
+            effect_right: Vec::new(),
+            dry_left: Vec::new(),
+            dry_right: Vec::new(),
+        }
+    }
+
+    /// Every node and effect setting as (node, parameter, value, unit) rows,
+    /// voice nodes first. Nodes without presets only report `active`.
+    pub fn export_flat_parameters(&self) -> Vec<FlatParameter> {
+        flatten_session(
+            self.voices.first(),
+            &self.effect_stack.effects,
+            EFFECT_NODE_ID_OFFSET,
+        )
+    }
+
+    /// Settings of a single envelope, LFO, filter or effect as a JSON preset
+    /// snippet, for per-module preset menus. Effects use their effect node id.
+    pub fn export_node_preset(&self, node_id: &str) -> Result<String, String> {
+        let node = self.preset_node(node_id)?;
+        NodePreset::from_node(node)
+            .ok_or_else(|| format!("Node {} ({}) has no presets", node_id, node.name()))?
+            .to_json()
+    }
+
+    /// Applies a snippet from `export_node_preset` to a node of the same type
+    /// in every voice.
+    pub fn import_node_preset(&mut self, node_id: &str, preset_json: &str) -> Result<(), String> {
+        let preset = NodePreset::from_json(preset_json)?;
+        self.apply_node_preset(node_id, preset)
+    }
+
+    /// Copies the settings of `src_id` onto `dst_id`, a node of the same type,
+    /// in every voice. Nothing is changed if the nodes don't match.
+    pub fn copy_node_settings(&mut self, src_id: &str, dst_id: &str) -> Result<(), String> {
+        let src = self.preset_node(src_id)?;
+        let preset = NodePreset::from_node(src)
+            .ok_or_else(|| format!("Node {} ({}) has no presets", src_id, src.name()))?;
+        self.apply_node_preset(dst_id, preset)
+    }
+
+    /// Sets one field of a node's preset settings (e.g. `"cutoff"` on a
+    /// filter or `"attack"` on an envelope) in every voice. Boolean fields
+    /// are on for values above 0.5.
+    pub fn set_node_parameter(
+        &mut self,
+        node_id: &str,
+        parameter: &str,
+        value: f64,
+    ) -> Result<(), String> {
+        let node = self.preset_node(node_id)?;
+        let preset = NodePreset::from_node(node)
+            .ok_or_else(|| format!("Node {} ({}) has no presets", node_id, node.name()))?;
+        let mut json = serde_json::to_value(preset)
+            .map_err(|e| format!("Failed to serialize node preset: {}", e))?;
+        let field = json
+            .get_mut("settings")
+            .and_then(|settings| settings.get_mut(parameter))
+            .ok_or_else(|| format!("Node {} has no parameter {}", node_id, parameter))?;
+        *field = match field {
+            serde_json::Value::Bool(_) => serde_json::Value::Bool(value > 0.5),
+            serde_json::Value::Number(number) if number.is_f64() => serde_json::json!(value),
+            serde_json::Value::Number(_) => serde_json::json!(value.round() as i64),
+            _ => return Err(format!("Parameter {} is not a number", parameter)),
+        };
+        let preset = serde_json::from_value(json)
+            .map_err(|e| format!("Invalid value for {}: {}", parameter, e))?;
+        self.apply_node_preset(node_id, preset)
+    }
+
+    /// Moves each setting of effect `index` by a random amount, up to
+    /// `amount` (0..1) of the setting's range.
+    pub fn randomize_effect(&mut self, index: usize, amount: f64) -> Result<(), String> {
+        let node_id = (EFFECT_NODE_ID_OFFSET + index).to_string();
+        let node = self.preset_node(&node_id)?;
+        let preset = NodePreset::from_node(node)
+            .ok_or_else(|| format!("Effect {} ({}) has no presets", index, node.name()))?;
+        let mut rng = rand::rng();
+        let preset = preset.randomized(amount, || rng.random())?;
+        self.apply_node_preset(&node_id, preset)
+    }
+
+    /// Sets effect `index` to the settings `t` (0..1) of the way from
+    /// `preset_a` to `preset_b`, two snippets from `export_node_preset`.
+    pub fn morph_effect(
+        &mut self,
+        index: usize,
+        preset_a: &str,
+        preset_b: &str,
+        t: f64,
+    ) -> Result<(), String> {
+        let a = NodePreset::from_json(preset_a)?;
+        let b = NodePreset::from_json(preset_b)?;
+        let preset = NodePreset::morph(&a, &b, t)?;
+        self.apply_node_preset(&(EFFECT_NODE_ID_OFFSET + index).to_string(), preset)
+    }
+
+    fn apply_node_preset(&mut self, node_id: &str, preset: NodePreset) -> Result<(), String> {
+        let node = self.preset_node(node_id)?;
+        if !preset.fits(node) {
+            return Err(format!(
+                "Preset doesn't match node {} ({})",
+                node_id,
+                node.name()
+            ));
+        }
+        let state = preset.into_synth_state(node_id);
+        self.apply_patch_states(&state, &PatchVoiceLayout::default())?;
+        self.snapshots.node_edited(node_id);
+        Ok(())
+    }
+
+    fn preset_node(&self, node_id: &str) -> Result<&dyn AudioNode, String> {
+        let node = match node_id.parse::<usize>() {
+            Ok(index) => index
+                .checked_sub(EFFECT_NODE_ID_OFFSET)
+                .and_then(|effect| self.effect_stack.effects.get(effect))
+                .map(|effect| effect.node.as_ref()),
+            Err(_) => {
+                let id = parse_node_id(node_id)?;
+                self.voices
+                    .first()
+                    .and_then(|voice| voice.graph.get_node(id))
+                    .map(|node| node.as_ref())
+            }
+        };
+        node.ok_or_else(|| format!("Node {} not found", node_id))
+    }
+
+    /// Re-applies the remembered values of locked parameters to the rebuilt
+    /// voices and effect stack.
+    fn restore_locked_parameters(&mut self) {
+        if let Some((transpose, fine)) = self.locks.locked_master_tuning() {
+            self.set_master_tuning(transpose, fine);
+        }
+        let detune: Vec<(usize, f32)> = self.locks.locked_voice_detune().collect();
+        for (voice_index, cents) in detune {
+            if let Some(voice) = self.voices.get_mut(voice_index) {
+                voice.graph.set_voice_detune(cents);
+            }
+        }
+        if let Some(time_ms) = self.locks.locked_smoothing() {
+            self.set_parameter_smoothing(time_ms);
+        }
+        let macro_values: Vec<(usize, f32)> = self.locks.locked_macro_values().collect();
+        for (macro_index, value) in macro_values {
+            for voice in &mut self.voices {
+                // The locked value is held from the first block, no glide.
+                let _ = voice.set_macro_sparse(macro_index, value, Some(value));
+            }
+        }
+    }
+
+    /// Switches every voice node and effect between Eco, Normal and High quality.
+    /// The mode survives patch loads.
+    pub fn set_quality_mode(&mut self, mode: QualityMode) {
+        self.quality_mode = mode;
+        // While overload protection holds Eco, the new mode takes effect on recovery.
+        if !self.overload.is_degraded() {
+            self.apply_quality_mode(mode);
+        }
+    }
+
+    pub fn quality_mode(&self) -> QualityMode {
+        self.quality_mode
+    }
+
+    /// Chooses how the final mix is bounded: hard clamp, the safety limiter in
+    /// the effect stack (default) or raw float. Survives patch loads.
+    pub fn set_output_mode(&mut self, mode: OutputMode) {
+        self.output.set_mode(mode);
+        self.effect_stack
+            .set_limiters_bypassed(mode.bypasses_limiter());
+    }
+
+    pub fn output_mode(&self) -> OutputMode {
+        self.output.mode()
+    }
+
+    /// Requests dithered 16 or 24-bit integer copies of every block next to the
+    /// float output; `OutputFormat::Float` turns them off.
+    pub fn set_output_format(&mut self, format: OutputFormat) {
+        self.output.set_format(format);
+    }
+
+    pub fn output_format(&self) -> OutputFormat {
+        self.output.format()
+    }
+
+    /// Integer samples of the last processed block, empty for float output.
+    pub fn int_output(&self) -> (&[i32], &[i32]) {
+        (self.output.int_left(), self.output.int_right())
+    }
+
+    /// Switches between stereo, quad and 5.1 output. Outside stereo,
+    /// `process_audio` returns the front pair only; use
+    /// `process_audio_multichannel` for every channel.
+    pub fn set_channel_layout(&mut self, layout: ChannelLayout) {
+        self.surround.set_layout(layout);
+        self.effect_stack
+            .set_spread_capture(self.surround.is_enabled());
+    }
+
+    pub fn channel_layout(&self) -> ChannelLayout {
+        self.surround.layout()
+    }
+
+    /// Places a voice in the surround field: `x` from -1 (left) to 1 (right),
+    /// `y` from -1 (rear) to 1 (front). Voices default to front centre.
+    pub fn set_voice_position(&mut self, voice_index: usize, x: f32, y: f32) {
+        self.surround.set_voice_position(voice_index, x, y);
+    }
+
+    pub fn voice_position(&self, voice_index: usize) -> (f32, f32) {
+        self.surround.voice_position(voice_index)
+    }
+
+    /// Chooses whether a master effect's wet signal also feeds the rear
+    /// channels. Reverbs and delays do by default.
+    pub fn set_effect_surround_send(&mut self, index: usize, enabled: bool) -> Result<(), String> {
+        if index >= self.effect_stack.effects.len() {
+            return Err(format!("Invalid effect index: {}", index));
+        }
+        self.effect_stack.set_effect_surround_send(index, enabled);
+        Ok(())
+    }
+
+    /// Enables automatic degradation when CPU usage passes the overload threshold:
+    /// quality drops to Eco first, then quiet released voices are culled. Actions
+    /// are reported through `take_diagnostics`.
+    pub fn set_overload_protection(&mut self, enabled: bool) {
+        let response = self.overload.set_enabled(enabled);
+        self.respond_to_overload(response);
+    }
+
+    /// Sets the CPU usage (%) that triggers protection and the level usage must
+    /// stay below before quality is restored.
+    pub fn set_overload_thresholds(&mut self, threshold: f32, recovery_threshold: f32) {
+        self.overload.set_thresholds(threshold, recovery_threshold);
+    }
+
+    /// Stereo voices: links between stereo-capable nodes in the voice graph
+    /// carry the right channel as well, so panned or spread sources stay
+    /// stereo through filters and per-voice effects. Off by default; mono
+    /// voices skip the right-channel processing. Applies to the current
+    /// voices and to patches loaded later.
+    pub fn set_stereo_voices(&mut self, enabled: bool) {
+        self.stereo_voices = enabled;
+        for voice in &mut self.voices {
+            voice.graph.set_stereo(enabled);
+        }
+        for (voice, _) in self.parts.extra_voices_mut() {
+            voice.graph.set_stereo(enabled);
+        }
+    }
+
+    /// Diagnostics mode: checks the output of every node in the voices after it
+    /// runs and reports NaN or infinite samples, and outputs stuck at DC or
+    /// silent while their inputs move, through `take_diagnostics`. Costs a
+    /// pass over each output buffer, so leave it off outside debugging.
+    pub fn set_node_health_checks(&mut self, enabled: bool) {
+        self.node_health_checks = enabled;
+        for voice in &mut self.voices {
+            voice.graph.set_health_monitoring(enabled);
+        }
+    }
+
+    /// Strict real-time mode for embedded and pro-audio hosts. Every voice
+    /// graph preallocates its node and connection tables and buffers for
+    /// `limits`; from then on voices, nodes and connections past them are
+    /// refused instead of allocated, patches that don't fit fail to load, and
+    /// each refusal is reported through `take_diagnostics`. Fails, changing
+    /// nothing, when the running session is already bigger than `limits`.
+    /// `None` returns to growing on demand.
+    pub fn set_strict_realtime(&mut self, limits: Option<CapacityLimits>) -> Result<(), String> {
+        if let Some(limits) = limits {
+            limits
+                .check(CapacityResource::Voices, self.voices.len())
+                .and_then(|_| limits.check(CapacityResource::BlockSize, self.block_size))
+                .map_err(|e| e.to_string())?;
+        }
+        for index in 0..self.voices.len() {
+            if let Err(exceeded) = self.voices[index].graph.set_capacity_limits(limits) {
+                for voice in &mut self.voices[..index] {
+                    let _ = voice.graph.set_capacity_limits(self.capacity_limits);
+                }
+                return Err(exceeded.to_string());
+            }
+        }
+        self.capacity_limits = limits;
+        self.capacity_events
+            .reserve(MAX_PENDING_CAPACITY_EVENTS.saturating_sub(self.capacity_events.len()));
+        Ok(())
+    }
+
+    /// Drains the overload protection events, the node health events of each
+    /// voice, then the strict real-time refusals, oldest first.
+    pub fn take_diagnostics(&mut self) -> Vec<DiagnosticEvent> {
+        let mut events: Vec<DiagnosticEvent> = self
+            .overload
+            .take_events()
+            .into_iter()
+            .map(DiagnosticEvent::Overload)
+            .collect();
+        for voice in &mut self.voices {
+            let voice_index = voice.id;
+            events.extend(voice.graph.take_health_events().into_iter().map(|event| {
+                DiagnosticEvent::NodeHealth {
+                    voice: voice_index,
+                    event,
+                }
+            }));
+        }
+        for exceeded in self.capacity_events.drain(..) {
+            events.push(DiagnosticEvent::Capacity {
+                voice: None,
+                exceeded,
+            });
+        }
+        for voice in &mut self.voices {
+            let voice_index = voice.id;
+            events.extend(
+                voice
+                    .graph
+                    .take_capacity_events()
+                    .into_iter()
+                    .map(|exceeded| DiagnosticEvent::Capacity {
+                        voice: Some(voice_index),
+                        exceeded,
+                    }),
             );
+        }
+        events
+    }
+
+    /// Bytes used by wavetables, sample data, convolver partitions, delay
+    /// lines and each voice's graph buffers.
+    pub fn get_memory_stats(&self) -> MemoryStats {
+        let mut counter = MemoryCounter::new();
+        for bank in self.wavetable_banks.values() {
+            counter.add_wavetables(bank.memory_bytes());
+        }
+        for collection in self.wavetable_synthbank.borrow().collections.values() {
+            counter.add_wavetables(collection.memory_bytes());
+        }
+        for voice in self.voices.iter().chain(self.parts.extra_voices()) {
+            counter.add_voice(voice);
+        }
+        for effect in &self.effect_stack.effects {
+            counter.add_node(effect.node.as_ref());
+        }
+        counter.finish()
+    }
+
+    /// Magnitude response in dB of the voice's serial tone-shaping chain
+    /// (filters and saturation between the oscillators and the output), at
+    /// `points` frequencies spaced logarithmically from 20 Hz to 20 kHz.
+    pub fn get_voice_chain_response(&self, points: usize) -> Vec<f32> {
+        let chain = self
+            .voices
+            .first()
+            .map(|voice| serial_chain(&voice.graph))
+            .unwrap_or_default();
+        chain_response(chain, self.sample_rate, points)
+    }
+
+    /// Renders a copy of an oscillator holding a note at `frequency`, with its
+    /// current quality settings, and reports how much aliasing it produces.
+    pub fn measure_oscillator_aliasing(
+        &self,
+        node_id: NodeId,
+        frequency: f32,
+    ) -> Result<AliasReport, String> {
+        let mut node = self
+            .voices
+            .first()
+            .and_then(|voice| voice.graph.get_node(node_id))
+            .ok_or_else(|| format!("Node {} not found", node_id.to_string()))?
+            .clone_node()
+            .ok_or_else(|| format!("Node {} can't be measured", node_id.to_string()))?;
+        node.reset();
+        let samples = render_tone(node.as_mut(), frequency);
+        Ok(measure_aliasing(&samples, self.sample_rate, frequency))
+    }
+
+    /// Loads an SFZ instrument into a sampler, replacing its zones.
+    /// `resolve_sample` returns the WAV file for a region's sample path.
+    /// Returns the number of zones loaded.
+    pub fn import_sfz(
+        &mut self,
+        sampler_id: &str,
+        sfz_text: &str,
+        resolve_sample: impl FnMut(&str) -> Result<Vec<u8>, String>,
+    ) -> Result<usize, String> {
+        let zones = load_sfz(sfz_text, resolve_sample)?;
+        let sampler_id = parse_node_id(sampler_id)?;
+        let mut loaded: Vec<Rc<RefCell<SampleData>>> = Vec::new();
+        for voice in &self.voices {
+            let sample_data = voice
+                .graph
+                .get_node(sampler_id)
+                .ok_or_else(|| format!("Node {} not found", sampler_id.to_string()))?
+                .as_any()
+                .downcast_ref::<Sampler>()
+                .ok_or_else(|| "Node is not a Sampler".to_string())?
+                .get_sample_data();
+            // Voices normally share one buffer.
+            if !loaded.iter().any(|known| Rc::ptr_eq(known, &sample_data)) {
+                sample_data.borrow_mut().zones = zones.clone();
+                loaded.push(sample_data);
+            }
+        }
+        Ok(zones.len())
+    }
+
+    /// Starts a background job and returns its id. Imports take the WAV file
+    /// as `data`. The job runs on its own thread; `poll_job` reports on it and
+    /// swaps the result in once it's done.
+    pub fn start_job(&mut self, request: JobRequest, data: Vec<u8>) -> Result<JobId, String> {
+        let (sample_rate, partition_size) = match &request {
+            JobRequest::ImportImpulse { effect_id }
+            | JobRequest::GenerateImpulse { effect_id, .. } => {
+                let convolver = self.convolver_mut(*effect_id)?;
+                (convolver.sample_rate, convolver.partition_size)
+            }
+            _ => (self.sample_rate, 0),
+        };
+        let task = JobTask::new(&request, data, sample_rate, partition_size)?;
+        self.jobs.start(task)
+    }
+
+    /// A job's progress. The first poll after it finishes applies its result;
+    /// after reporting `Finished` or `Failed` the job is forgotten.
+    pub fn poll_job(&mut self, job_id: JobId) -> Result<JobStatus, String> {
+        Ok(match self.jobs.poll(job_id)? {
+            JobPoll::Running(progress) => JobStatus::Running { progress },
+            JobPoll::Finished(request, output) => match self.apply_job_output(request, output) {
+                Ok(()) => JobStatus::Finished,
+                Err(error) => JobStatus::Failed { error },
+            },
+            JobPoll::Failed(error) => JobStatus::Failed { error },
+        })
+    }
+
+    /// Stops a job without applying anything. Returns false for unknown jobs.
+    pub fn cancel_job(&mut self, job_id: JobId) -> bool {
+        self.jobs.cancel(job_id)
+    }
+
+    fn convolver_mut(&mut self, effect_id: usize) -> Result<&mut Convolver, String> {
+        let index = effect_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .filter(|&index| index < self.effect_stack.effects.len())
+            .ok_or_else(|| format!("Invalid effect node id {}", effect_id))?;
+        self.effect_stack.effects[index]
+            .node
+            .as_any_mut()
+            .downcast_mut::<Convolver>()
+            .ok_or_else(|| "Effect is not a Convolver".to_string())
+    }
+
+    fn apply_job_output(&mut self, request: JobRequest, output: JobOutput) -> Result<(), String> {
+        match (request, output) {
+            (
+                JobRequest::ImportSample { node_id },
+                JobOutput::Sample {
+                    samples,
+                    channels,
+                    sample_rate,
+                },
+            ) => {
+                let node_id = parse_node_id(&node_id)?;
+                self.imports.backup_sample(node_id, &self.voices);
+                let sample_data = Rc::new(RefCell::new(SampleData::new()));
+                sample_data
+                    .borrow_mut()
+                    .load_from_wav(samples, channels, sample_rate);
+                for voice in &mut self.voices {
+                    let sampler = voice
+                        .graph
+                        .get_node_mut(node_id)
+                        .ok_or_else(|| format!("Node {} not found", node_id.to_string()))?
+                        .as_any_mut()
+                        .downcast_mut::<Sampler>()
+                        .ok_or_else(|| "Node is not a Sampler".to_string())?;
+                    sampler.set_sample_data(sample_data.clone());
+                }
+            }
+            (
+                JobRequest::ImportImpulse { effect_id }
+                | JobRequest::GenerateImpulse { effect_id, .. },
+                JobOutput::Convolver(mut convolver),
+            ) => {
+                let quality_mode = self.effect_stack.quality_mode();
+                let current = self.convolver_mut(effect_id)?;
+                convolver.set_wet_level(current.wet_level());
+                convolver.set_active(current.is_active());
+                convolver.set_quality_mode(quality_mode);
+                *current = convolver;
+            }
+            (JobRequest::ImportWavetable { node_id, .. }, JobOutput::Wavetable(collection)) => {
+                let collection_name = format!("wt_{}", node_id);
+                let node_id = parse_node_id(&node_id)?;
+                {
+                    let mut bank = self.wavetable_synthbank.borrow_mut();
+                    self.imports.backup_wavetable(
+                        &bank,
+                        &collection_name,
+                        Some(node_id),
+                        &self.voices,
+                    );
+                    if !bank.collections.contains_key("default") {
+                        bank.add_collection(
+                            "default",
+                            WavetableMorphCollection::generate_test_collection(self.sample_rate),
+                        );
+                    }
+                    bank.add_collection(&collection_name, collection);
+                }
+                for voice in &mut self.voices {
+                    let osc = voice
+                        .graph
+                        .get_node_mut(node_id)
+                        .ok_or_else(|| format!("Node {} not found", node_id.to_string()))?
+                        .as_any_mut()
+                        .downcast_mut::<WavetableOscillator>()
+                        .ok_or_else(|| "Node is not a WavetableOscillator".to_string())?;
+                    osc.set_current_wavetable(&collection_name);
+                }
+            }
+            (JobRequest::ImportNamedWavetable { name, .. }, JobOutput::Wavetable(collection)) => {
+                let mut bank = self.wavetable_synthbank.borrow_mut();
+                self.imports
+                    .backup_wavetable(&bank, &name, None, &self.voices);
+                bank.add_collection(name, collection);
+            }
+            _ => return Err("Job output doesn't match its request".to_string()),
+        }
+        Ok(())
+    }
+
+    /// Keeps the wavetable or sample each import replaces, so the last import
+    /// can be undone with `revert_last_import`. Turning it off frees the backup.
+    pub fn set_import_backup(&mut self, enabled: bool) {
+        self.imports.set_enabled(enabled);
+    }
+
+    pub fn import_backup(&self) -> bool {
+        self.imports.is_enabled()
+    }
+
+    /// Whether `revert_last_import` has a backup to restore.
+    pub fn can_revert_import(&self) -> bool {
+        self.imports.can_revert()
+    }
+
+    /// Restores what the last import replaced: the previous collection (or
+    /// none) under its name, what its oscillator played, or a sampler's
+    /// previous sample. Returns false when there is no backup.
+    pub fn revert_last_import(&mut self) -> Result<bool, String> {
+        let mut bank = self.wavetable_synthbank.borrow_mut();
+        self.imports.revert(&mut bank, &mut self.voices)
+    }
+
+    /// Checks the session for changes every `interval_seconds` and snapshots
+    /// it when it changed, for recovery after a crash. 0 turns snapshots off.
+    pub fn set_snapshot_interval(&mut self, interval_seconds: f32) {
+        self.snapshots
+            .set_interval(self.sample_rate, interval_seconds);
+    }
+
+    /// Hands each snapshot's JSON to `callback`, on the audio thread. Only the
+    /// first snapshot after a patch load includes the patch.
+    pub fn set_snapshot_callback(&mut self, callback: impl FnMut(&str) + 'static) {
+        self.snapshot_sink = Some(SnapshotSink::Callback(Box::new(callback)));
+        self.enable_snapshots();
+    }
+
+    /// Keeps the latest snapshot, patch included, in the file at `path`.
+    pub fn set_snapshot_file(&mut self, path: impl Into<PathBuf>) -> Result<(), String> {
+        self.snapshot_sink = Some(SnapshotSink::File(SnapshotFileWriter::new(path)?));
+        self.enable_snapshots();
+        Ok(())
+    }
+
+    fn enable_snapshots(&mut self) {
+        if !self.snapshots.is_enabled() {
+            self.set_snapshot_interval(DEFAULT_SNAPSHOT_INTERVAL_SECONDS);
+        }
+    }
+
+    /// The snapshot taken since the last call, for hosts that poll instead of
+    /// setting a callback. It includes the patch if any snapshot it replaced
+    /// did.
+    pub fn take_snapshot(&mut self) -> Option<String> {
+        match self.pending_snapshot.take()?.to_json() {
+            Ok(json) => Some(json),
+            Err(err) => {
+                eprintln!("{}", err);
+                None
+            }
+        }
+    }
+
+    /// Replaces the patch later snapshots are based on, for hosts that edit
+    /// the patch outside the engine and serialize it themselves.
+    pub fn set_snapshot_patch(&mut self, patch_json: &str) {
+        self.snapshots.patch_loaded(patch_json);
+    }
+
+    /// Rebuilds a session from a snapshot that includes its patch (see
+    /// `SessionSnapshot::with_patch_from`). Returns the voice count.
+    pub fn restore_snapshot(&mut self, snapshot_json: &str) -> Result<usize, String> {
+        let snapshot = SessionSnapshot::from_json(snapshot_json)?;
+        let patch = snapshot
+            .patch
+            .as_deref()
+            .ok_or("Snapshot has no patch; merge it with the last snapshot that has one")?;
+        let voice_count = self.init_with_patch(patch)?;
+        for (node_id, preset_json) in &snapshot.node_presets {
+            if let Err(err) = self.import_node_preset(node_id, preset_json) {
+                eprintln!("Failed to restore node {}: {}", node_id, err);
+            }
+        }
+
+        let performance = &snapshot.performance;
+        self.set_transport_tempo(performance.tempo_bpm);
+        if let Some((transpose, fine)) = performance.master_tuning {
+            self.set_master_tuning(transpose, fine);
+        }
+        for (voice_index, cents) in performance.voice_detune.iter().enumerate() {
+            if let Some(cents) = cents {
+                // Voices the patch no longer has are skipped.
+                let _ = self.set_voice_detune(voice_index, *cents);
+            }
+        }
+        if let Some(time_ms) = performance.smoothing_ms {
+            self.set_parameter_smoothing(time_ms);
+        }
+        for (macro_index, value) in performance.macro_values.iter().enumerate() {
+            if let Some(value) = value {
+                self.set_macro_value(None, macro_index, *value, None)?;
+            }
+        }
+        Ok(voice_count)
+    }
+
+    fn record_snapshot(&mut self, frames: usize) {
+        if !self.snapshots.advance(frames) {
+            return;
+        }
+        let performance = PerformanceState::capture(&self.locks, self.transport.tempo());
+        if !self.snapshots.has_changes(&performance) {
+            return;
+        }
+        let node_presets = self
+            .snapshots
+            .edited_nodes()
+            .filter_map(|id| Some((id.to_string(), self.export_node_preset(id).ok()?)))
+            .collect();
+        let snapshot = self.snapshots.take(performance, node_presets);
+        match &mut self.snapshot_sink {
+            Some(SnapshotSink::File(writer)) => writer.write(snapshot),
+            Some(SnapshotSink::Callback(callback)) => match snapshot.to_json() {
+                Ok(json) => callback(&json),
+                Err(err) => eprintln!("{}", err),
+            },
+            None => {
+                let snapshot = match self.pending_snapshot.take() {
+                    Some(pending) => snapshot.with_patch_from(&pending),
+                    None => snapshot,
+                };
+                self.pending_snapshot = Some(snapshot);
+            }
+        }
+    }
+
+    /// Overrides the smoothing time of one voice node; `None` clears the override.
+    pub fn set_node_smoothing(&mut self, node_id: NodeId, time_ms: Option<f32>) {
+        for voice in &mut self.voices {
+            voice
+                .graph
+                .set_node_smoothing_time_ms(node_id, time_ms.map(|t| t.max(0.0)));
+        }
+    }
+
+    /// Sets the groove template of the arpeggiators, clock nodes and synced
+    /// delays that don't have their own.
+    pub fn set_groove(&mut self, groove: Groove) {
+        for voice in &mut self.voices {
+            voice.graph.set_groove(groove);
+        }
+        self.effect_stack.set_groove(groove);
+    }
+
+    /// Overrides the groove of one voice node; `None` clears the override.
+    pub fn set_node_groove(&mut self, node_id: NodeId, groove: Option<Groove>) {
+        for voice in &mut self.voices {
+            voice.graph.set_node_groove(node_id, groove);
+        }
+    }
+
+    /// Overrides the groove of one master effect; `None` clears the override.
+    pub fn set_effect_groove(
+        &mut self,
+        index: usize,
+        groove: Option<Groove>,
+    ) -> Result<(), String> {
+        if index >= self.effect_stack.effects.len() {
+            return Err(format!("Invalid effect index: {}", index));
+        }
+        self.effect_stack.set_effect_groove(index, groove);
+        Ok(())
+    }
+
+    /// Overrides the smoothing time of one master effect; `None` clears the override.
+    pub fn set_effect_smoothing(
+        &mut self,
+        index: usize,
+        time_ms: Option<f32>,
+    ) -> Result<(), String> {
+        if index >= self.effect_stack.effects.len() {
+            return Err(format!("Invalid effect index: {}", index));
+        }
+        self.effect_stack
+            .set_effect_smoothing_time_ms(index, time_ms.map(|t| t.max(0.0)));
+        Ok(())
+    }
+
+    /// Routes a master effect in stereo, to the mid or side component only, or
+    /// dual-mono.
+    pub fn set_effect_routing(
+        &mut self,
+        index: usize,
+        routing: EffectRouting,
+    ) -> Result<(), String> {
+        if index >= self.effect_stack.effects.len() {
+            return Err(format!("Invalid effect index: {}", index));
+        }
+        self.effect_stack.set_effect_routing(index, routing);
+        Ok(())
+    }
+
+    pub fn effect_routing(&self, index: usize) -> Option<EffectRouting> {
+        self.effect_stack.effect_routing(index)
+    }
+
+    /// Keys a master effect from a voice node output (e.g. an envelope or the arp
+    /// gate). Compressors duck on it, delays duck their echoes and the reverb gate
+    /// follows it. A `source` of `None` disconnects the sidechain.
+    pub fn set_effect_sidechain(
+        &mut self,
+        node_id: usize,
+        source: Option<(NodeId, PortId)>,
+    ) -> Result<(), String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .filter(|&index| index < self.effect_stack.effects.len())
+            .ok_or_else(|| format!("Invalid effect node id {}", node_id))?;
+
+        if let Some((source_id, _)) = source {
+            let exists = self
+                .voices
+                .first()
+                .is_some_and(|voice| voice.graph.get_node(source_id).is_some());
+            if !exists {
+                return Err(format!("Node {} not found", source_id.to_string()));
+            }
+        }
+        self.effect_stack.set_effect_sidechain(
+            effect_id,
+            source.map(|(node_id, port)| SidechainSource { node_id, port }),
+        );
+        Ok(())
+    }
+
+    pub fn update_delay(
+        &mut self,
+        node_id: usize,
+        delay_ms: f32,
+        feedback: f32,
+        wet_mix: f32,
+        enabled: bool,
+    ) -> Result<(), String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid delay node id".to_string())?;
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        if let Some(delay) = effect.node.as_any_mut().downcast_mut::<Delay>() {
+            delay.set_delay_ms(delay_ms);
+            delay.set_feedback(feedback);
+            delay.set_mix(wet_mix);
+            delay.set_active(enabled);
+            Ok(())
+        } else {
+            Err(format!("Effect at index {} is not a delay", effect_id))
+        }
+    }
+
+    /// How far the sidechain key pulls a delay's echoes down (0..1).
+    pub fn update_delay_ducking(&mut self, node_id: usize, ducking: f32) -> Result<(), String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid delay node id".to_string())?;
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        if let Some(delay) = effect.node.as_any_mut().downcast_mut::<Delay>() {
+            delay.set_ducking(ducking);
+            Ok(())
+        } else {
+            Err(format!("Effect at index {} is not a delay", effect_id))
+        }
+    }
+
+    /// Syncs a delay's time to `sync_beats` beats of the effect tempo; `None`
+    /// returns it to its time in milliseconds.
+    pub fn update_delay_sync(
+        &mut self,
+        node_id: usize,
+        sync_beats: Option<f32>,
+    ) -> Result<(), String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid delay node id".to_string())?;
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        if let Some(delay) = effect.node.as_any_mut().downcast_mut::<Delay>() {
+            delay.set_sync_beats(sync_beats);
+            Ok(())
+        } else {
+            Err(format!("Effect at index {} is not a delay", effect_id))
+        }
+    }
+
+    /// Gates the reverb tail with its sidechain key.
+    pub fn update_reverb_gate(&mut self, node_id: usize, enabled: bool) -> Result<(), String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid reverb node id".to_string())?;
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        if let Some(reverb) = effect.node.as_any_mut().downcast_mut::<Freeverb>() {
+            reverb.set_gate_enabled(enabled);
+            Ok(())
+        } else {
+            Err(format!("Effect at index {} is not a reverb", effect_id))
+        }
+    }
+
+    /// Freezes (or releases) a delay: input muted, the line held at unity feedback.
+    pub fn update_delay_freeze(&mut self, node_id: usize, frozen: bool) -> Result<(), String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid delay node id".to_string())?;
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        if let Some(delay) = effect.node.as_any_mut().downcast_mut::<Delay>() {
+            delay.set_freeze(frozen);
+            Ok(())
+        } else {
+            Err(format!("Effect at index {} is not a delay", effect_id))
+        }
+    }
+
+    /// Freezes (or releases) the reverb tail.
+    pub fn update_reverb_freeze(&mut self, node_id: usize, frozen: bool) -> Result<(), String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid reverb node id".to_string())?;
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        if let Some(reverb) = effect.node.as_any_mut().downcast_mut::<Freeverb>() {
+            reverb.set_freeze(frozen);
+            Ok(())
+        } else {
+            Err(format!("Effect at index {} is not a reverb", effect_id))
+        }
+    }
+
+    pub fn update_reverb(&mut self, node_id: usize, params: ReverbUpdate) -> Result<(), String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid reverb node id".to_string())?;
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        if let Some(reverb) = effect.node.as_any_mut().downcast_mut::<Freeverb>() {
+            reverb.set_room_size(params.room_size);
+            reverb.set_damp(params.damp);
+            reverb.set_wet(params.wet);
+            reverb.set_dry(params.dry);
+            reverb.set_width(params.width);
+            reverb.set_active(params.active);
+            Ok(())
+        } else {
+            Err(format!("Effect at index {} is not a reverb", effect_id))
+        }
+    }
+
+    pub fn update_chorus(&mut self, node_id: usize, state: &ChorusState) -> Result<(), String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid chorus node id".to_string())?;
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        if let Some(chorus) = effect.node.as_any_mut().downcast_mut::<Chorus>() {
+            chorus.set_base_delay_ms(state.base_delay_ms);
+            chorus.set_depth_ms(state.depth_ms);
+            chorus.set_rate_hz(state.lfo_rate_hz);
+            chorus.set_feedback(state.feedback);
+            chorus.set_mix(state.mix);
+            chorus.set_stereo_phase_offset_deg(state.stereo_phase_offset_deg);
+            chorus.set_feedback_filter_cutoff(state.feedback_filter * self.sample_rate);
+            chorus.set_active(state.active);
+            Ok(())
+        } else {
+            Err(format!("Effect at index {} is not a chorus", effect_id))
+        }
+    }
+
+    pub fn set_chorus_active(&mut self, active: bool) {
+        self.set_effect_active(0, active);
+    }
+
+    pub fn set_delay_active(&mut self, active: bool) {
+        self.set_effect_active(1, active);
+    }
+
+    pub fn set_reverb_active(&mut self, active: bool) {
+        self.set_effect_active(2, active);
+    }
+
+    pub fn update_compressor(
+        &mut self,
+        node_id: usize,
+        active: bool,
+        threshold_db: f32,
+        ratio: f32,
+        attack_ms: f32,
+        release_ms: f32,
+        makeup_gain_db: f32,
+        mix: f32,
+    ) -> Result<(), String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid compressor node id".to_string())?;
+
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        if let Some(comp) = effect.node.as_any_mut().downcast_mut::<Compressor>() {
+            comp.set_threshold_db(threshold_db);
+            comp.set_ratio(ratio);
+            comp.set_attack_ms(attack_ms);
+            comp.set_release_ms(release_ms);
+            comp.set_makeup_gain_db(makeup_gain_db);
+            comp.set_mix(mix);
+            comp.set_active(active);
+            Ok(())
+        } else {
+            Err(format!("Effect at index {} is not a compressor", effect_id))
+        }
+    }
+
+    pub fn update_saturation(
+        &mut self,
+        node_id: usize,
+        drive: f32,
+        mix: f32,
+        active: bool,
+    ) -> Result<(), String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid saturation node id".to_string())?;
+
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        if let Some(saturation) = effect.node.as_any_mut().downcast_mut::<Saturation>() {
+            saturation.set_drive(drive);
+            saturation.set_mix(mix);
+            saturation.set_active(active);
+            Ok(())
+        } else {
+            Err(format!(
+                "Effect at index {} is not a saturation effect",
+                effect_id
+            ))
+        }
+    }
+
+    /// Sets the saturation insert's transfer curve, pre/post tilt EQ (dB) and auto-gain.
+    pub fn update_saturation_tone(
+        &mut self,
+        node_id: usize,
+        character: SaturationCharacter,
+        pre_tilt_db: f32,
+        post_tilt_db: f32,
+        auto_gain: bool,
+    ) -> Result<(), String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid saturation node id".to_string())?;
+
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        if let Some(saturation) = effect.node.as_any_mut().downcast_mut::<Saturation>() {
+            saturation.set_character(character);
+            saturation.set_pre_tilt(pre_tilt_db);
+            saturation.set_post_tilt(post_tilt_db);
+            saturation.set_auto_gain(auto_gain);
+            Ok(())
+        } else {
+            Err(format!(
+                "Effect at index {} is not a saturation effect",
+                effect_id
+            ))
+        }
+    }
+
+    pub fn update_bitcrusher(
+        &mut self,
+        node_id: usize,
+        bits: u8,
+        downsample_factor: usize,
+        mix: f32,
+        active: bool,
+    ) -> Result<(), String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid bitcrusher node id".to_string())?;
+
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        if let Some(bitcrusher) = effect.node.as_any_mut().downcast_mut::<Bitcrusher>() {
+            bitcrusher.set_bits(bits);
+            bitcrusher.set_downsample_factor(downsample_factor);
+            bitcrusher.set_mix(mix);
+            bitcrusher.set_active(active);
+            Ok(())
+        } else {
+            Err(format!(
+                "Effect at index {} is not a bitcrusher effect",
+                effect_id
+            ))
+        }
+    }
+
+    pub fn update_stereo_enhancer(
+        &mut self,
+        node_id: usize,
+        params: StereoEnhancerUpdate,
+    ) -> Result<(), String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid stereo enhancer node id".to_string())?;
+
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        if let Some(enhancer) = effect.node.as_any_mut().downcast_mut::<StereoEnhancer>() {
+            enhancer.set_delay_left_ms(params.delay_left_ms);
+            enhancer.set_delay_right_ms(params.delay_right_ms);
+            enhancer.set_width(params.width);
+            enhancer.set_comb_compensation(params.comb_compensation);
+            enhancer.set_mix(params.mix);
+            enhancer.set_active(params.active);
+            Ok(())
+        } else {
+            Err(format!(
+                "Effect at index {} is not a stereo enhancer",
+                effect_id
+            ))
+        }
+    }
+
+    /// Places the master mix around the listener's head (azimuth in degrees
+    /// clockwise from the front, elevation -30 to 90 degrees).
+    pub fn update_binaural(
+        &mut self,
+        node_id: usize,
+        active: bool,
+        azimuth: f32,
+        elevation: f32,
+        mix: f32,
+    ) -> Result<(), String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid binaural node id".to_string())?;
+
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        if let Some(binaural) = effect.node.as_any_mut().downcast_mut::<Binaural>() {
+            binaural.set_position(azimuth, elevation);
+            binaural.set_mix(mix);
+            binaural.set_active(active);
+            Ok(())
+        } else {
+            Err(format!(
+                "Effect at index {} is not a binaural panner",
+                effect_id
+            ))
+        }
+    }
+
+    /// Updates the master auto-wah (sensitivity 0-1, sweep from `frequency`
+    /// Hz over `range` octaves).
+    pub fn update_auto_wah(&mut self, node_id: usize, params: AutoWahUpdate) -> Result<(), String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid auto-wah node id".to_string())?;
+
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        if let Some(wah) = effect.node.as_any_mut().downcast_mut::<AutoWah>() {
+            wah.set_sensitivity(params.sensitivity);
+            wah.set_frequency(params.frequency);
+            wah.set_range(params.range);
+            wah.set_q(params.q);
+            wah.set_direction(params.direction);
+            wah.set_mix(params.mix);
+            wah.set_active(params.active);
+            Ok(())
+        } else {
+            Err(format!("Effect at index {} is not an auto-wah", effect_id))
+        }
+    }
+
+    /// Updates the exciter: harmonics are generated above `frequency` Hz and
+    /// `amount` (0-1) sets how much of them is added.
+    pub fn update_exciter(
+        &mut self,
+        node_id: usize,
+        active: bool,
+        frequency: f32,
+        amount: f32,
+        mix: f32,
+    ) -> Result<(), String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid exciter node id".to_string())?;
+
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        if let Some(exciter) = effect.node.as_any_mut().downcast_mut::<Exciter>() {
+            exciter.set_frequency(frequency);
+            exciter.set_amount(amount);
+            exciter.set_mix(mix);
+            exciter.set_active(active);
+            Ok(())
+        } else {
+            Err(format!("Effect at index {} is not an exciter", effect_id))
+        }
+    }
+
+    /// Updates the master noise gate. It opens above `threshold_db` and closes
+    /// once the level falls `hysteresis_db` lower for longer than `hold_ms`;
+    /// `range_db` is the attenuation while closed.
+    pub fn update_noise_gate(
+        &mut self,
+        node_id: usize,
+        params: NoiseGateUpdate,
+    ) -> Result<(), String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid noise gate node id".to_string())?;
+
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        if let Some(gate) = effect.node.as_any_mut().downcast_mut::<NoiseGate>() {
+            gate.set_threshold_db(params.threshold_db);
+            gate.set_hysteresis_db(params.hysteresis_db);
+            gate.set_attack_ms(params.attack_ms);
+            gate.set_hold_ms(params.hold_ms);
+            gate.set_release_ms(params.release_ms);
+            gate.set_range_db(params.range_db);
+            gate.set_active(params.active);
+            Ok(())
+        } else {
+            Err(format!("Effect at index {} is not a noise gate", effect_id))
+        }
+    }
+
+    fn multiband_mut(&mut self, node_id: usize) -> Result<&mut Multiband, String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid multiband node id".to_string())?;
+
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        effect
+            .node
+            .as_any_mut()
+            .downcast_mut::<Multiband>()
+            .ok_or_else(|| format!("Effect at index {} is not a multiband container", effect_id))
+    }
+
+    /// Sets the crossover frequencies of a multiband container; 1 to 3
+    /// crossovers give 2 to 4 bands.
+    pub fn update_multiband(
+        &mut self,
+        node_id: usize,
+        active: bool,
+        crossovers: &[f32],
+    ) -> Result<(), String> {
+        let multiband = self.multiband_mut(node_id)?;
+        multiband.set_crossovers(crossovers);
+        multiband.set_active(active);
+        Ok(())
+    }
+
+    /// Sets a band's linear gain, mute and solo.
+    pub fn update_multiband_band(
+        &mut self,
+        node_id: usize,
+        band: usize,
+        gain: f32,
+        muted: bool,
+        soloed: bool,
+    ) -> Result<(), String> {
+        let multiband = self.multiband_mut(node_id)?;
+        if band >= multiband.band_count() {
+            return Err(format!("Invalid band {}", band));
+        }
+        multiband.set_band(band, gain, muted, soloed);
+        Ok(())
+    }
+
+    /// Sets the balance of a parallel container (0.0 = chain A only, 1.0 =
+    /// chain B only).
+    pub fn update_parallel(
+        &mut self,
+        node_id: usize,
+        active: bool,
+        blend: f32,
+    ) -> Result<(), String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid parallel node id".to_string())?;
+
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        let parallel = effect
+            .node
+            .as_any_mut()
+            .downcast_mut::<Parallel>()
+            .ok_or_else(|| format!("Effect at index {} is not a parallel container", effect_id))?;
+        parallel.set_blend(blend);
+        parallel.set_active(active);
+        Ok(())
+    }
+
+    fn equalizer_mut(&mut self, node_id: usize) -> Result<&mut Equalizer, String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid EQ node id".to_string())?;
+
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        effect
+            .node
+            .as_any_mut()
+            .downcast_mut::<Equalizer>()
+            .ok_or_else(|| format!("Effect at index {} is not an EQ", effect_id))
+    }
+
+    /// Turns an EQ on or off and sets its band count, up to `MAX_EQ_BANDS`.
+    /// Added bands start as flat peaks.
+    pub fn update_eq(
+        &mut self,
+        node_id: usize,
+        active: bool,
+        band_count: usize,
+    ) -> Result<(), String> {
+        let eq = self.equalizer_mut(node_id)?;
+        eq.set_band_count(band_count);
+        eq.set_active(active);
+        Ok(())
+    }
+
+    /// Sets one EQ band's shape, frequency (Hz), Q and gain (dB). Its dynamic
+    /// mode is kept.
+    pub fn update_eq_band(
+        &mut self,
+        node_id: usize,
+        band: usize,
+        band_type: EqBandType,
+        frequency: f32,
+        q: f32,
+        gain_db: f32,
+    ) -> Result<(), String> {
+        let eq = self.equalizer_mut(node_id)?;
+        let settings = EqBand {
+            band_type,
+            frequency,
+            q,
+            gain_db,
+            dynamics: eq.bands().get(band).and_then(|current| current.dynamics),
+        };
+        if eq.set_band(band, settings) {
+            Ok(())
+        } else {
+            Err(format!("Invalid band {}", band))
+        }
+    }
+
+    /// Turns the dynamic mode of one EQ band on (`Some`) or off.
+    pub fn update_eq_band_dynamics(
+        &mut self,
+        node_id: usize,
+        band: usize,
+        dynamics: Option<EqDynamics>,
+    ) -> Result<(), String> {
+        if self
+            .equalizer_mut(node_id)?
+            .set_band_dynamics(band, dynamics)
+        {
+            Ok(())
+        } else {
+            Err(format!("Invalid band {}", band))
+        }
+    }
+
+    /// Magnitude response in dB of an EQ at `length` frequencies spaced
+    /// logarithmically from 20 Hz to 20 kHz.
+    pub fn get_eq_response(&mut self, node_id: usize, length: usize) -> Result<Vec<f32>, String> {
+        Ok(self.equalizer_mut(node_id)?.response_db(length))
+    }
+
+    /// Configures one of the effect stack's LFOs. With `sync_beats` set the
+    /// LFO runs one cycle every `sync_beats` beats of the effect tempo.
+    pub fn set_effect_lfo(
+        &mut self,
+        index: usize,
+        waveform: LfoWaveform,
+        rate_hz: f32,
+        sync_beats: Option<f32>,
+    ) -> Result<(), String> {
+        if index >= EFFECT_LFO_COUNT {
+            return Err(format!("Invalid effect LFO {}", index));
+        }
+        self.effect_stack
+            .set_lfo(index, waveform, rate_hz, sync_beats);
+        Ok(())
+    }
+
+    /// Routes an effect LFO to a named parameter of a master effect (e.g.
+    /// "time" on a delay); `depth` is the offset at full swing in the
+    /// parameter's units, and 0 removes the route.
+    pub fn set_effect_lfo_route(
+        &mut self,
+        lfo: usize,
+        node_id: usize,
+        parameter: &str,
+        depth: f32,
+    ) -> Result<(), String> {
+        let index = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| format!("Invalid effect node id {}", node_id))?;
+        if self
+            .effect_stack
+            .set_lfo_route(lfo, index, parameter, depth)
+        {
+            Ok(())
+        } else {
+            Err(format!(
+                "Cannot route effect LFO {} to {} on effect {}",
+                lfo, parameter, node_id
+            ))
+        }
+    }
+
+    /// Tempo the synced effect LFOs and delays follow.
+    pub fn set_effect_tempo(&mut self, bpm: f32) {
+        self.effect_stack.set_tempo(bpm);
+    }
+
+    /// Runs a master effect on one band of a container (a multiband band or
+    /// parallel chain 0/1), given as `(container node id, band)`, or back in
+    /// the serial chain with `None`.
+    pub fn set_effect_band(
+        &mut self,
+        node_id: usize,
+        container: Option<(usize, usize)>,
+    ) -> Result<(), String> {
+        let index = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| format!("Invalid effect node id {}", node_id))?;
+        let slot = container
+            .map(|(container_id, band)| {
+                container_id
+                    .checked_sub(EFFECT_NODE_ID_OFFSET)
+                    .map(|container| ContainerSlot { container, band })
+                    .ok_or_else(|| format!("Invalid container node id {}", container_id))
+            })
+            .transpose()?;
+        if self.effect_stack.set_effect_band(index, slot) {
+            Ok(())
+        } else {
+            Err(format!("Cannot place effect {} on that band", node_id))
+        }
+    }
+
+    fn looper_mut(&mut self, node_id: usize) -> Result<&mut Looper, String> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| "Invalid looper node id".to_string())?;
+
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| format!("No effect found at index {}", effect_id))?;
+
+        effect
+            .node
+            .as_any_mut()
+            .downcast_mut::<Looper>()
+            .ok_or_else(|| format!("Effect at index {} is not a looper", effect_id))
+    }
+
+    /// Updates the looper's playback settings. The looper records whatever
+    /// reaches its slot in the effect chain, so it captures the full master
+    /// bus when it is the last effect.
+    pub fn update_looper(
+        &mut self,
+        node_id: usize,
+        active: bool,
+        speed: LooperSpeed,
+        reverse: bool,
+        feedback: f32,
+        level: f32,
+    ) -> Result<(), String> {
+        let looper = self.looper_mut(node_id)?;
+        looper.set_speed(speed);
+        looper.set_reverse(reverse);
+        looper.set_feedback(feedback);
+        looper.set_level(level);
+        looper.set_active(active);
+        Ok(())
+    }
+
+    /// Record, play, overdub, stop or clear the loop.
+    pub fn looper_command(&mut self, node_id: usize, command: LooperCommand) -> Result<(), String> {
+        self.looper_mut(node_id)?.command(command);
+        Ok(())
+    }
+
+    /// Locks new recordings to `beats` beats at `bpm`; pass a tempo of 0 for
+    /// free-length loops.
+    pub fn set_looper_tempo(&mut self, node_id: usize, bpm: f32, beats: f32) -> Result<(), String> {
+        self.looper_mut(node_id)?.set_tempo(bpm, beats);
+        Ok(())
+    }
+
+    /// Realigns a tempo-synced loop with the host transport position in beats.
+    pub fn sync_looper_to_beat(
+        &mut self,
+        node_id: usize,
+        beat_position: f64,
+    ) -> Result<(), String> {
+        self.looper_mut(node_id)?.sync_to_beat(beat_position);
+        Ok(())
+    }
+
+    pub fn looper_state(&mut self, node_id: usize) -> Result<LooperState, String> {
+        Ok(self.looper_mut(node_id)?.state())
+    }
+
+    /// Updates the shared settings of a dual filter (routing 0 = serial, 1 = parallel, 2 = split).
+    pub fn update_dual_filter(
+        &mut self,
+        node_id: NodeId,
+        params: DualFilterUpdate,
+    ) -> Result<(), String> {
+        let DualFilterUpdate {
+            active,
+            routing,
+            balance,
+            spacing,
+            cutoff,
+            key_tracking,
+        } = params;
+        let routing = match routing {
+            1 => DualFilterRouting::Parallel,
+            2 => DualFilterRouting::Split,
+            _ => DualFilterRouting::Serial,
+        };
+        for voice in &mut self.voices {
+            let node = voice
+                .graph
+                .get_node_mut(node_id)
+                .ok_or_else(|| "Node not found".to_string())?;
+            let dual = node
+                .as_any_mut()
+                .downcast_mut::<DualFilter>()
+                .ok_or_else(|| "Node is not a DualFilter".to_string())?;
+            dual.set_routing(routing);
+            dual.set_balance(balance);
+            dual.set_spacing(spacing);
+            dual.set_cutoff(cutoff);
+            for slot in 0..2 {
+                if let Some(filter) = dual.filter_mut(slot) {
+                    filter.set_keyboard_tracking_sensitivity(key_tracking);
+                }
+            }
+            dual.set_active(active);
+        }
+        Ok(())
+    }
+
+    /// Updates a formant filter (vowel 0 = A, 1 = E, 2 = I, 3 = O, 4 = U).
+    pub fn update_formant_filter(
+        &mut self,
+        node_id: NodeId,
+        active: bool,
+        vowel: u8,
+        gender: f32,
+        resonance: f32,
+        mix: f32,
+    ) -> Result<(), String> {
+        for voice in &mut self.voices {
+            let node = voice
+                .graph
+                .get_node_mut(node_id)
+                .ok_or_else(|| "Node not found".to_string())?;
+            let filter = node
+                .as_any_mut()
+                .downcast_mut::<FormantFilter>()
+                .ok_or_else(|| "Node is not a FormantFilter".to_string())?;
+            filter.set_vowel(FormantVowel::from_u8(vowel));
+            filter.set_gender(gender);
+            filter.set_resonance(resonance);
+            filter.set_mix(mix);
+            filter.set_active(active);
+        }
+        Ok(())
+    }
+
+    /// Updates a gate tool; a `length_ms` of 0 follows the incoming gate.
+    pub fn update_gate_tool(
+        &mut self,
+        node_id: NodeId,
+        params: GateToolUpdate,
+    ) -> Result<(), String> {
+        let GateToolUpdate {
+            active,
+            length_ms,
+            delay_ms,
+            division,
+            multiplication,
+            probability,
+        } = params;
+        for voice in &mut self.voices {
+            let node = voice
+                .graph
+                .get_node_mut(node_id)
+                .ok_or_else(|| "Node not found".to_string())?;
+            let tool = node
+                .as_any_mut()
+                .downcast_mut::<GateTool>()
+                .ok_or_else(|| "Node is not a GateTool".to_string())?;
+            tool.set_length_ms(length_ms);
+            tool.set_delay_ms(delay_ms);
+            tool.set_division(division);
+            tool.set_multiplication(multiplication);
+            tool.set_probability(probability);
+            tool.set_active(active);
+        }
+        Ok(())
+    }
+
+    pub fn update_clock(
+        &mut self,
+        node_id: NodeId,
+        active: bool,
+        division_beats: f32,
+        pulse_width: f32,
+    ) -> Result<(), String> {
+        for voice in &mut self.voices {
+            let node = voice
+                .graph
+                .get_node_mut(node_id)
+                .ok_or_else(|| "Node not found".to_string())?;
+            let clock = node
+                .as_any_mut()
+                .downcast_mut::<Clock>()
+                .ok_or_else(|| "Node is not a Clock".to_string())?;
+            clock.set_division_beats(division_beats);
+            clock.set_pulse_width(pulse_width);
+            clock.set_active(active);
+        }
+        Ok(())
+    }
+
+    /// Updates a Chance node. Each voice rolls its own sequence from `seed`
+    /// unless `randomness` is global.
+    pub fn update_chance(&mut self, node_id: NodeId, params: ChanceUpdate) -> Result<(), String> {
+        let ChanceUpdate {
+            active,
+            probability,
+            mode,
+            attenuation,
+            seed,
+            randomness,
+        } = params;
+        for voice in &mut self.voices {
+            let node = voice
+                .graph
+                .get_node_mut(node_id)
+                .ok_or_else(|| "Node not found".to_string())?;
+            let chance = node
+                .as_any_mut()
+                .downcast_mut::<Chance>()
+                .ok_or_else(|| "Node is not a Chance".to_string())?;
+            chance.set_probability(probability);
+            chance.set_mode(ChanceMode::from_u8(mode));
+            chance.set_attenuation(attenuation);
+            chance.set_voice_index(voice.id);
+            chance.set_seed(seed);
+            chance.set_randomness(ChanceRandomness::from_u8(randomness));
+            chance.set_active(active);
+        }
+        Ok(())
+    }
+
+    /// Updates an FM operator's waveform, tuning, feedback and level.
+    pub fn update_fm_operator(
+        &mut self,
+        node_id: NodeId,
+        config: FmOperatorConfig,
+    ) -> Result<(), String> {
+        for voice in &mut self.voices {
+            let node = voice
+                .graph
+                .get_node_mut(node_id)
+                .ok_or_else(|| "Node not found".to_string())?;
+            let operator = node
+                .as_any_mut()
+                .downcast_mut::<FmOperator>()
+                .ok_or_else(|| "Node is not an FM operator".to_string())?;
+            operator.update(config);
+        }
+        Ok(())
+    }
+
+    /// Replaces the segments, sustain and loop points of an MSEG node.
+    pub fn update_mseg(&mut self, node_id: NodeId, config: MsegConfig) -> Result<(), String> {
+        for voice in &mut self.voices {
+            let node = voice
+                .graph
+                .get_node_mut(node_id)
+                .ok_or_else(|| "Node not found".to_string())?;
+            let mseg = node
+                .as_any_mut()
+                .downcast_mut::<Mseg>()
+                .ok_or_else(|| "Node is not an MSEG".to_string())?;
+            mseg.update_config(config.clone());
+        }
+        Ok(())
+    }
+
+    /// Updates one filter of a dual filter (slot 0 = A, 1 = B).
+    pub fn update_dual_filter_slot(
+        &mut self,
+        node_id: NodeId,
+        slot: usize,
+        filter_type: FilterType,
+        filter_slope: FilterSlope,
+        resonance: f32,
+        gain: f32,
+    ) -> Result<(), String> {
+        for voice in &mut self.voices {
+            let node = voice
+                .graph
+                .get_node_mut(node_id)
+                .ok_or_else(|| "Node not found".to_string())?;
+            let dual = node
+                .as_any_mut()
+                .downcast_mut::<DualFilter>()
+                .ok_or_else(|| "Node is not a DualFilter".to_string())?;
+            let filter = dual
+                .filter_mut(slot)
+                .ok_or_else(|| format!("Invalid dual filter slot: {}", slot))?;
+            filter.set_filter_type(filter_type);
+            filter.set_filter_slope(filter_slope);
+            filter.set_gain_db(gain * 24.0 - 12.0);
+            dual.set_resonance(slot, resonance);
+        }
+        Ok(())
+    }
+
+    /// Updates a per-voice stereo enhancer; the master insert uses `update_stereo_enhancer`.
+    pub fn update_voice_stereo_enhancer(
+        &mut self,
+        node_id: NodeId,
+        params: StereoEnhancerUpdate,
+    ) -> Result<(), String> {
+        for voice in &mut self.voices {
+            let node = voice
+                .graph
+                .get_node_mut(node_id)
+                .ok_or_else(|| "Node not found".to_string())?;
+            let enhancer = node
+                .as_any_mut()
+                .downcast_mut::<StereoEnhancer>()
+                .ok_or_else(|| "Node is not a StereoEnhancer".to_string())?;
+            enhancer.set_delay_left_ms(params.delay_left_ms);
+            enhancer.set_delay_right_ms(params.delay_right_ms);
+            enhancer.set_width(params.width);
+            enhancer.set_comb_compensation(params.comb_compensation);
+            enhancer.set_mix(params.mix);
+            enhancer.set_active(params.active);
+        }
+        Ok(())
+    }
+
+    /// Updates a per-voice binaural panner; the master insert uses `update_binaural`.
+    pub fn update_voice_binaural(
+        &mut self,
+        node_id: NodeId,
+        active: bool,
+        azimuth: f32,
+        elevation: f32,
+        mix: f32,
+    ) -> Result<(), String> {
+        for voice in &mut self.voices {
+            let node = voice
+                .graph
+                .get_node_mut(node_id)
+                .ok_or_else(|| "Node not found".to_string())?;
+            let binaural = node
+                .as_any_mut()
+                .downcast_mut::<Binaural>()
+                .ok_or_else(|| "Node is not a Binaural".to_string())?;
+            binaural.set_position(azimuth, elevation);
+            binaural.set_mix(mix);
+            binaural.set_active(active);
+        }
+        Ok(())
+    }
+
+    /// Updates a per-voice auto-wah; the master insert uses `update_auto_wah`.
+    pub fn update_voice_auto_wah(
+        &mut self,
+        node_id: NodeId,
+        params: AutoWahUpdate,
+    ) -> Result<(), String> {
+        for voice in &mut self.voices {
+            let node = voice
+                .graph
+                .get_node_mut(node_id)
+                .ok_or_else(|| "Node not found".to_string())?;
+            let wah = node
+                .as_any_mut()
+                .downcast_mut::<AutoWah>()
+                .ok_or_else(|| "Node is not an AutoWah".to_string())?;
+            wah.set_sensitivity(params.sensitivity);
+            wah.set_frequency(params.frequency);
+            wah.set_range(params.range);
+            wah.set_q(params.q);
+            wah.set_direction(params.direction);
+            wah.set_mix(params.mix);
+            wah.set_active(params.active);
+        }
+        Ok(())
+    }
+
+    /// Updates a per-voice noise gate; the master insert uses `update_noise_gate`.
+    pub fn update_voice_noise_gate(
+        &mut self,
+        node_id: NodeId,
+        params: NoiseGateUpdate,
+    ) -> Result<(), String> {
+        for voice in &mut self.voices {
+            let node = voice
+                .graph
+                .get_node_mut(node_id)
+                .ok_or_else(|| "Node not found".to_string())?;
+            let gate = node
+                .as_any_mut()
+                .downcast_mut::<NoiseGate>()
+                .ok_or_else(|| "Node is not a NoiseGate".to_string())?;
+            gate.set_threshold_db(params.threshold_db);
+            gate.set_hysteresis_db(params.hysteresis_db);
+            gate.set_attack_ms(params.attack_ms);
+            gate.set_hold_ms(params.hold_ms);
+            gate.set_release_ms(params.release_ms);
+            gate.set_range_db(params.range_db);
+            gate.set_active(params.active);
+        }
+        Ok(())
+    }
+
+    /// Updates a per-voice saturation; the master insert uses `update_saturation`.
+    pub fn update_voice_saturation(
+        &mut self,
+        node_id: NodeId,
+        drive: f32,
+        mix: f32,
+        active: bool,
+    ) -> Result<(), String> {
+        for voice in &mut self.voices {
+            let node = voice
+                .graph
+                .get_node_mut(node_id)
+                .ok_or_else(|| "Node not found".to_string())?;
+            let saturation = node
+                .as_any_mut()
+                .downcast_mut::<Saturation>()
+                .ok_or_else(|| "Node is not a Saturation".to_string())?;
+            saturation.set_drive(drive);
+            saturation.set_mix(mix);
+            saturation.set_active(active);
+        }
+        Ok(())
+    }
+
+    /// Sets a per-voice saturation's transfer curve, pre/post tilt EQ (dB) and auto-gain.
+    pub fn update_voice_saturation_tone(
+        &mut self,
+        node_id: NodeId,
+        character: SaturationCharacter,
+        pre_tilt_db: f32,
+        post_tilt_db: f32,
+        auto_gain: bool,
+    ) -> Result<(), String> {
+        for voice in &mut self.voices {
+            let node = voice
+                .graph
+                .get_node_mut(node_id)
+                .ok_or_else(|| "Node not found".to_string())?;
+            let saturation = node
+                .as_any_mut()
+                .downcast_mut::<Saturation>()
+                .ok_or_else(|| "Node is not a Saturation".to_string())?;
+            saturation.set_character(character);
+            saturation.set_pre_tilt(pre_tilt_db);
+            saturation.set_post_tilt(post_tilt_db);
+            saturation.set_auto_gain(auto_gain);
+        }
+        Ok(())
+    }
+
+    /// Updates a per-voice bitcrusher; the master insert uses `update_bitcrusher`.
+    pub fn update_voice_bitcrusher(
+        &mut self,
+        node_id: NodeId,
+        bits: u8,
+        downsample_factor: usize,
+        mix: f32,
+        active: bool,
+    ) -> Result<(), String> {
+        for voice in &mut self.voices {
+            let node = voice
+                .graph
+                .get_node_mut(node_id)
+                .ok_or_else(|| "Node not found".to_string())?;
+            let bitcrusher = node
+                .as_any_mut()
+                .downcast_mut::<Bitcrusher>()
+                .ok_or_else(|| "Node is not a Bitcrusher".to_string())?;
+            bitcrusher.set_bits(bits);
+            bitcrusher.set_downsample_factor(downsample_factor);
+            bitcrusher.set_mix(mix);
+            bitcrusher.set_active(active);
+        }
+        Ok(())
+    }
+
+    // Node creation methods
+    pub fn create_oscillator(&mut self) -> Result<usize, String> {
+        let osc_id = NodeId::new();
+        let levels = self.calibrate_new_node(NodeRole::Source);
+        for voice in &mut self.voices {
+            let mut osc = AnalogOscillator::new(
+                self.sample_rate,
+                Waveform::Sine,
+                self.wavetable_banks.clone(),
+            );
+            if let Some(levels) = levels {
+                osc.set_gain(levels.gain);
+            }
+            voice.graph.add_node_with_id(osc_id, Box::new(osc));
+        }
+        Ok(osc_id.0.as_u128() as usize)
+    }
+
+    pub fn create_wavetable_oscillator(&mut self) -> Result<usize, String> {
+        let osc_id = NodeId::new();
+        let levels = self.calibrate_new_node(NodeRole::Source);
+        for voice in &mut self.voices {
+            let mut osc =
+                WavetableOscillator::new(self.sample_rate, self.wavetable_synthbank.clone());
+            if let Some(levels) = levels {
+                osc.set_gain(levels.gain);
+            }
+            voice.graph.add_node_with_id(osc_id, Box::new(osc));
+        }
+        Ok(osc_id.0.as_u128() as usize)
+    }
+
+    /// New oscillators and filters start at levels chosen from the voice's
+    /// current headroom: oscillators at the gain of the ones already playing,
+    /// filters with level compensation on. `last_node_levels` reports the
+    /// choice, including the amount to wire the node into the output mixer at.
+    pub fn set_auto_node_levels(&mut self, enabled: bool) {
+        self.auto_node_levels = enabled;
+    }
+
+    /// The levels chosen for the last oscillator or filter created, or `None`
+    /// when auto levels were off.
+    pub fn last_node_levels(&self) -> Option<NodeLevels> {
+        self.last_node_levels
+    }
+
+    fn calibrate_new_node(&mut self, role: NodeRole) -> Option<NodeLevels> {
+        self.last_node_levels = self
+            .voices
+            .first()
+            .filter(|_| self.auto_node_levels)
+            .map(|voice| node_levels(&voice.graph, role));
+        self.last_node_levels
+    }
+
+    pub fn create_mixer(&mut self) -> Result<usize, String> {
+        let mixer_id = NodeId::new();
+        for voice in &mut self.voices {
+            voice
+                .graph
+                .add_node_with_id(mixer_id, Box::new(Mixer::new()));
+            voice.graph.set_output_node(mixer_id);
+        }
+        Ok(mixer_id.0.as_u128() as usize)
+    }
+
+    /// Builds a saw → low-pass → mixer voice with separate amp and filter
+    /// envelopes in every voice, makes the mixer the output and returns the
+    /// new node ids.
+    pub fn create_standard_voice(&mut self) -> Result<StandardVoiceIds, String> {
+        let standard = StandardVoice::new();
+        for voice in &mut self.voices {
+            standard.build(
+                &mut voice.graph,
+                self.sample_rate,
+                self.wavetable_banks.clone(),
+            );
+            voice.graph.set_output_node(standard.mixer);
+        }
+        Ok(standard.ids())
+    }
+
+    pub fn create_envelope(&mut self) -> Result<usize, String> {
+        let envelope_id = NodeId::new();
+        for voice in &mut self.voices {
+            voice.graph.add_node_with_id(
+                envelope_id,
+                Box::new(Envelope::new(self.sample_rate, EnvelopeConfig::default())),
+            );
+        }
+        Ok(envelope_id.0.as_u128() as usize)
+    }
+
+    pub fn create_lfo(&mut self) -> Result<usize, String> {
+        let lfo_id = NodeId::new();
+        for voice in &mut self.voices {
+            let mut lfo = Lfo::new(self.sample_rate);
+            lfo.set_voice_index(voice.id);
+            voice.graph.add_node_with_id(lfo_id, Box::new(lfo));
+        }
+        Ok(lfo_id.0.as_u128() as usize)
+    }
+
+    pub fn create_filter(&mut self) -> Result<usize, String> {
+        let filter_id = NodeId::new();
+        let levels = self.calibrate_new_node(NodeRole::Processor);
+        for voice in &mut self.voices {
+            let mut filter = FilterCollection::new(self.sample_rate);
+            filter.set_auto_gain(levels.is_some());
+            voice.graph.add_node_with_id(filter_id, Box::new(filter));
+        }
+        Ok(filter_id.0.as_u128() as usize)
+    }
+
+    pub fn create_glide(&mut self, glide_time: f32) -> Result<usize, String> {
+        let glide_id = NodeId::new();
+        for voice in &mut self.voices {
+            voice
+                .graph
+                .add_node_with_id(glide_id, Box::new(Glide::new(self.sample_rate, glide_time)));
+            voice.graph.global_glide_node = Some(glide_id);
+            if let Some(global_freq) = voice.graph.global_frequency_node {
+                voice.graph.add_connection(Connection {
+                    from_node: global_freq,
+                    from_port: PortId::GlobalFrequency,
+                    to_node: glide_id,
+                    to_port: PortId::AudioInput0,
+                    amount: 1.0,
+                    modulation_type: ModulationType::Additive,
+                    modulation_transform: ModulationTransformation::None,
+                });
+            }
+            if let Some(gate_mixer_id) = voice.graph.global_gatemixer_node {
+                voice.graph.add_connection(Connection {
+                    from_node: gate_mixer_id,
+                    from_port: PortId::CombinedGate,
+                    to_node: glide_id,
+                    to_port: PortId::CombinedGate,
+                    amount: 1.0,
+                    modulation_type: ModulationType::Additive,
+                    modulation_transform: ModulationTransformation::None,
+                });
+            }
+        }
+        Ok(glide_id.0.as_u128() as usize)
+    }
+
+    /// Insert a Glide node between the global frequency source and the target node's
+    /// GlobalFrequency input, so that pitch changes are slewed.
+    pub fn insert_glide_on_global_frequency(
+        &mut self,
+        glide_id: usize,
+        target_node: usize,
+    ) -> Result<(), String> {
+        let glide_node = self.node_from_handle(glide_id);
+        let target_node_id = self.node_from_handle(target_node);
+
+        for voice in &mut self.voices {
+            let global_freq_id = voice
+                .graph
+                .global_frequency_node
+                .ok_or_else(|| "GlobalFrequencyNode not found in voice graph".to_string())?;
+
+            // Remove the direct GlobalFrequency -> target connection, if present.
+            voice.graph.remove_specific_connection(
+                global_freq_id,
+                target_node_id,
+                PortId::GlobalFrequency,
+            );
+
+            // Connect GlobalFrequency -> Glide input.
+            voice.graph.add_connection(Connection {
+                from_node: global_freq_id,
+                from_port: PortId::GlobalFrequency,
+                to_node: glide_node,
+                to_port: PortId::AudioInput0,
+                amount: 1.0,
+                modulation_type: ModulationType::Additive,
+                modulation_transform: ModulationTransformation::None,
+            });
+
+            // Connect Glide output -> target GlobalFrequency input.
+            voice.graph.add_connection(Connection {
+                from_node: glide_node,
+                from_port: PortId::AudioOutput0,
+                to_node: target_node_id,
+                to_port: PortId::GlobalFrequency,
+                amount: 1.0,
+                modulation_type: ModulationType::Additive,
+                modulation_transform: ModulationTransformation::None,
+            });
+        }
+
+        Ok(())
+    }
+
+    // pub fn create_noise(&mut self) -> Result<usize, String> {
+    //     let mut noise_id = NodeId(0);
+    //     for voice in &mut self.voices {
+    //         noise_id = voice
+    //             .graph
+    //             .add_node(Box::new(NoiseGenerator::new(self.sample_rate)));
+    //     }
+    //     Ok(noise_id.0)
+    // }
+
+    /// The node behind a handle from the `create_*` methods, which carry the
+    /// low bits of the node's id.
+    fn node_from_handle(&self, handle: usize) -> NodeId {
+        self.voices
+            .first()
+            .and_then(|voice| {
+                voice
+                    .graph
+                    .nodes
+                    .keys()
+                    .copied()
+                    .find(|id| id.0.as_u128() as usize == handle)
+            })
+            .unwrap_or(NodeId(Uuid::from_u128(handle as u128)))
+    }
+
+    // Connection methods
+    pub fn connect_nodes(
+        &mut self,
+        from_node: usize,
+        from_port: PortId,
+        to_node: usize,
+        to_port: PortId,
+        amount: f32,
+        modulation_type: ModulationType,
+        modulation_transform: ModulationTransformation,
+    ) -> Result<(), String> {
+        let connection = Connection {
+            from_node: self.node_from_handle(from_node),
+            from_port,
+            to_node: self.node_from_handle(to_node),
+            to_port,
+            amount,
+            modulation_type,
+            modulation_transform,
+        };
+
+        // Every voice takes the connection or none does.
+        for voice in &self.voices {
+            voice
+                .graph
+                .check_connection(&connection)
+                .map_err(|e| e.to_string())?;
+        }
+        for voice in &mut self.voices {
+            voice.graph.add_connection(connection.clone());
+        }
+        Ok(())
+    }
+
+    /// Replaces the PhaseMod links between the given operators with the
+    /// routing of `algorithm`, in every voice.
+    pub fn apply_fm_algorithm(
+        &mut self,
+        carrier_ids: &[String],
+        modulator_ids: &[String],
+        algorithm: FmAlgorithm,
+    ) -> Result<(), String> {
+        let parse = |ids: &[String]| -> Result<Vec<NodeId>, String> {
+            ids.iter().map(|id| parse_node_id(id)).collect()
+        };
+        let (carriers, modulators) = (parse(carrier_ids)?, parse(modulator_ids)?);
+        let connections = fm_algorithm_connections(algorithm, &carriers, &modulators)?;
+        let operators: Vec<NodeId> = carriers.iter().chain(&modulators).copied().collect();
+        if let Some(voice) = self.voices.first() {
+            check_fm_operators(&voice.graph, &operators)?;
+        }
+
+        for voice in &mut self.voices {
+            clear_fm_links(&mut voice.graph, &operators);
+        }
+        for connection in connections {
+            for voice in &self.voices {
+                voice
+                    .graph
+                    .check_connection(&connection)
+                    .map_err(|e| e.to_string())?;
+            }
+            for voice in &mut self.voices {
+                voice.graph.add_connection(connection.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// The values the connection from `from_id` into `to_id`'s `to_port`
+    /// sweeps the destination parameter over, in its units (e.g. a cutoff
+    /// moving between 200 Hz and 4.2 kHz), for depth readouts.
+    pub fn get_modulation_range(
+        &self,
+        from_id: &str,
+        to_id: &str,
+        to_port: PortId,
+    ) -> Result<ModulationRange, String> {
+        let (from, to) = (parse_node_id(from_id)?, parse_node_id(to_id)?);
+        self.voices
+            .first()
+            .and_then(|voice| voice.graph.modulation_range(from, to, to_port))
+            .ok_or_else(|| {
+                format!(
+                    "No modulation range for {} -> {} {:?}",
+                    from_id, to_id, to_port
+                )
+            })
+    }
+
+    /// The curve `node_id`'s `port` follows over a virtual note: `duration`
+    /// seconds with the gate held for the first `note_length`, driven by the
+    /// envelopes and LFOs connected to the port.
+    pub fn get_modulation_preview(
+        &self,
+        node_id: &str,
+        port: PortId,
+        duration: f32,
+        note_length: f32,
+    ) -> Result<ModulationPreview, String> {
+        let node = parse_node_id(node_id)?;
+        let voice = self.voices.first().ok_or("No voices")?;
+        modulation_preview(
+            &voice.graph,
+            node,
+            port,
+            self.sample_rate,
+            duration,
+            note_length,
+        )
+    }
+
+    /// The harmonics of one frame of a wavetable collection ("default", or
+    /// "wt_<node id>" for an imported table), from DC up to Nyquist.
+    pub fn get_wavetable_frame_spectrum(
+        &self,
+        name: &str,
+        frame: usize,
+    ) -> Result<FrameSpectrum, String> {
+        self.wavetable_synthbank
+            .borrow()
+            .get_frame_spectrum(name, frame)
+    }
+
+    /// Rebuilds one frame of a wavetable collection from its harmonics, in
+    /// the form `get_wavetable_frame_spectrum` returns.
+    pub fn set_wavetable_frame_spectrum(
+        &mut self,
+        name: &str,
+        frame: usize,
+        magnitudes: &[f32],
+        phases: &[f32],
+    ) -> Result<(), String> {
+        self.wavetable_synthbank
+            .borrow_mut()
+            .set_frame_spectrum(name, frame, magnitudes, phases)
+    }
+
+    /// Replaces the bank analog oscillators play for `waveform` with one built
+    /// from a single `cycle` (see `WavetableBank::from_cycle`), or gives
+    /// `Waveform::Custom` a bank. Every voice moves to the new bank map in the
+    /// same call; the old banks are freed once nothing holds them.
+    pub fn set_waveform_cycle(&mut self, waveform: Waveform, cycle: &[f32]) -> Result<(), String> {
+        let bank = WavetableBank::from_cycle(cycle, self.sample_rate)?;
+        self.swap_waveform_bank(waveform, Some(Arc::new(bank)));
+        Ok(())
+    }
+
+    /// Puts back the built-in bank for `waveform`; `Waveform::Custom` is left
+    /// without one.
+    pub fn reset_waveform_bank(&mut self, waveform: Waveform) -> Result<(), String> {
+        let bank = match waveform {
+            Waveform::Custom => None,
+            _ => Some(Arc::new(
+                WavetableBank::new(waveform, MAX_TABLE_SIZE, self.sample_rate)
+                    .map_err(|e| e.to_string())?,
+            )),
+        };
+        self.swap_waveform_bank(waveform, bank);
+        Ok(())
+    }
+
+    fn swap_waveform_bank(&mut self, waveform: Waveform, bank: Option<Arc<WavetableBank>>) {
+        let mut banks = (*self.wavetable_banks).clone();
+        match bank {
+            Some(bank) => banks.insert(waveform, bank),
+            None => banks.remove(&waveform),
+        };
+        let banks = Arc::new(banks);
+        self.wavetable_banks = Arc::clone(&banks);
+        let extra_voices = self.parts.extra_voices_mut().map(|(voice, _)| voice);
+        for voice in self.voices.iter_mut().chain(extra_voices) {
+            for node in voice.graph.nodes.values_mut() {
+                if let Some(osc) = node.as_any_mut().downcast_mut::<AnalogOscillator>() {
+                    osc.set_wavetable_banks(Arc::clone(&banks));
+                }
+            }
+        }
+    }
+
+    /// Names of the wavetables oscillators can play: "default", tables
+    /// imported into an oscillator ("wt_<node id>") and named imports.
+    pub fn list_wavetables(&self) -> Vec<String> {
+        self.wavetable_synthbank.borrow().collection_names()
+    }
+
+    /// Removes a wavetable from the bank. Oscillators playing it fall back to
+    /// "default", which can't be deleted.
+    pub fn delete_wavetable(&mut self, name: &str) -> Result<(), String> {
+        if name == "default" {
+            return Err("The default wavetable can't be deleted".to_string());
+        }
+        if !self
+            .wavetable_synthbank
+            .borrow_mut()
+            .remove_collection(name)
+        {
+            return Err(format!("Wavetable {} not found", name));
+        }
+        for voice in &mut self.voices {
+            for node in voice.graph.nodes.values_mut() {
+                if let Some(osc) = node.as_any_mut().downcast_mut::<WavetableOscillator>() {
+                    if osc.current_wavetable() == name {
+                        osc.set_current_wavetable("default");
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Points a wavetable oscillator at a wavetable in the bank.
+    pub fn set_current_wavetable(&mut self, node_id: &str, name: &str) -> Result<(), String> {
+        if self
+            .wavetable_synthbank
+            .borrow()
+            .get_collection(name)
+            .is_none()
+        {
+            return Err(format!("Wavetable {} not found", name));
+        }
+        let node_id = parse_node_id(node_id)?;
+        for voice in &mut self.voices {
+            voice
+                .graph
+                .get_node_mut(node_id)
+                .ok_or_else(|| format!("Node {} not found", node_id.to_string()))?
+                .as_any_mut()
+                .downcast_mut::<WavetableOscillator>()
+                .ok_or_else(|| "Node is not a WavetableOscillator".to_string())?
+                .set_current_wavetable(name);
+        }
+        Ok(())
+    }
+
+    // Parameter update methods
+    pub fn update_oscillator(
+        &mut self,
+        oscillator_id: NodeId,
+        params: &AnalogOscillatorStateUpdate,
+    ) -> Result<(), String> {
+        for voice in &mut self.voices {
+            let node = voice
+                .graph
+                .get_node_mut(oscillator_id)
+                .ok_or_else(|| "Node not found in one of the voices".to_string())?;
+            let osc = node
+                .as_any_mut()
+                .downcast_mut::<AnalogOscillator>()
+                .ok_or_else(|| {
+                    "Node is not an AnalogOscillator in one of the voices".to_string()
+                })?;
+            osc.update_params(params);
+        }
+        Ok(())
+    }
+
+    pub fn update_wavetable_oscillator(
+        &mut self,
+        oscillator_id: NodeId,
+        params: &WavetableOscillatorStateUpdate,
+    ) -> Result<(), String> {
+        for voice in &mut self.voices {
+            let node = voice
+                .graph
+                .get_node_mut(oscillator_id)
+                .ok_or_else(|| "Node not found in one of the voices".to_string())?;
+            let osc = node
+                .as_any_mut()
+                .downcast_mut::<WavetableOscillator>()
+                .ok_or_else(|| {
+                    "Node is not a WavetableOscillator in one of the voices".to_string()
+                })?;
+            osc.update_params(params);
+        }
+        Ok(())
+    }
+
+    pub fn update_envelope(
+        &mut self,
+        node_id: NodeId,
+        attack: f32,
+        decay: f32,
+        sustain: f32,
+        release: f32,
+        attack_curve: f32,
+        decay_curve: f32,
+        release_curve: f32,
+        active: bool,
+    ) -> Result<(), String> {
+        let mut errors: Vec<String> = Vec::new();
+
+        for (i, voice) in self.voices.iter_mut().enumerate() {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(env) = node.as_any_mut().downcast_mut::<Envelope>() {
+                    let config = EnvelopeConfig {
+                        attack,
+                        decay,
+                        sustain,
+                        release,
+                        attack_curve,
+                        decay_curve,
+                        release_curve,
+                        attack_smoothing_samples: 16,
+                        active,
+                        ..env.config().clone()
+                    };
+                    env.update_config(config);
+                    env.set_active(active);
+                } else {
+                    errors.push(format!("Voice {}: Node is not an Envelope", i));
+                }
+            } else {
+                errors.push(format!("Voice {}: Node not found", i));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    pub fn update_lfos(&mut self, params: LfoUpdateParams) {
+        let lfo_id = params.lfo_id;
+        let loop_mode = match params.loop_mode {
+            1 => LfoLoopMode::Loop,
+            2 => LfoLoopMode::PingPong,
+            _ => LfoLoopMode::Off,
+        };
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(lfo_id) {
+                if let Some(lfo) = node.as_any_mut().downcast_mut::<Lfo>() {
+                    lfo.set_gain(params.gain);
+                    lfo.set_phase_offset(params.phase_offset);
+                    lfo.set_frequency(params.frequency);
+                    lfo.set_waveform(LfoWaveform::from_u8(params.waveform));
+                    lfo.set_use_absolute(params.use_absolute);
+                    lfo.set_use_normalized(params.use_normalized);
+                    lfo.set_retrigger_mode(LfoRetriggerMode::from_u8(params.trigger_mode));
+                    lfo.set_active(params.active);
+                    lfo.set_loop_mode(loop_mode);
+                    lfo.set_loop_start(params.loop_start);
+                    lfo.set_loop_end(params.loop_end);
+                }
+            }
+        }
+    }
+
+    /// Sets the level the envelope's gate input must cross to trigger it,
+    /// so LFO or sequencer gates routed to it can cycle it rhythmically.
+    pub fn set_envelope_gate_threshold(
+        &mut self,
+        node_id: NodeId,
+        threshold: f32,
+    ) -> Result<(), String> {
+        let mut errors: Vec<String> = Vec::new();
+
+        for (i, voice) in self.voices.iter_mut().enumerate() {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(env) = node.as_any_mut().downcast_mut::<Envelope>() {
+                    env.set_gate_threshold(threshold);
+                } else {
+                    errors.push(format!("Voice {}: Node is not an Envelope", i));
+                }
+            } else {
+                errors.push(format!("Voice {}: Node not found", i));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    /// Sets how note velocity and pitch scale the envelope's stage times:
+    /// velocity -> attack/decay and keytrack -> decay/release amounts.
+    pub fn set_envelope_time_scaling(
+        &mut self,
+        node_id: NodeId,
+        velocity_attack: f32,
+        velocity_decay: f32,
+        key_decay: f32,
+        key_release: f32,
+    ) -> Result<(), String> {
+        let mut errors: Vec<String> = Vec::new();
+
+        for (i, voice) in self.voices.iter_mut().enumerate() {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(env) = node.as_any_mut().downcast_mut::<Envelope>() {
+                    env.set_time_scaling(velocity_attack, velocity_decay, key_decay, key_release);
+                } else {
+                    errors.push(format!("Voice {}: Node is not an Envelope", i));
+                }
+            } else {
+                errors.push(format!("Voice {}: Node not found", i));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    /// Sets how much the note-off velocity scales the release time of an
+    /// envelope, or of a sampler's zone amp envelopes. At 1.0 the slowest
+    /// key lift releases 4x longer and the fastest 4x shorter.
+    pub fn set_release_velocity_amount(
+        &mut self,
+        node_id: NodeId,
+        amount: f32,
+    ) -> Result<(), String> {
+        let mut errors: Vec<String> = Vec::new();
+
+        for (i, voice) in self.voices.iter_mut().enumerate() {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                let node = node.as_any_mut();
+                if let Some(env) = node.downcast_mut::<Envelope>() {
+                    env.set_release_velocity_amount(amount);
+                } else if let Some(sampler) = node.downcast_mut::<Sampler>() {
+                    sampler.set_release_velocity_amount(amount);
+                } else {
+                    errors.push(format!("Voice {}: Node is not an Envelope or Sampler", i));
+                }
+            } else {
+                errors.push(format!("Voice {}: Node not found", i));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    /// Turns drone mode on or off for an envelope: with it on, gate-off
+    /// keeps the envelope sustaining until `release_drones` is called.
+    pub fn set_envelope_drone(&mut self, node_id: NodeId, drone: bool) -> Result<(), String> {
+        let mut errors: Vec<String> = Vec::new();
+
+        for (i, voice) in self.voices.iter_mut().enumerate() {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(env) = node.as_any_mut().downcast_mut::<Envelope>() {
+                    env.set_drone(drone);
+                } else {
+                    errors.push(format!("Voice {}: Node is not an Envelope", i));
+                }
+            } else {
+                errors.push(format!("Voice {}: Node not found", i));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    /// Releases every envelope held by drone mode, in all voices.
+    pub fn release_drones(&mut self) {
+        for voice in &mut self.voices {
+            for node in voice.graph.nodes.values_mut() {
+                if let Some(env) = node.as_any_mut().downcast_mut::<Envelope>() {
+                    env.release_drone();
+                }
+            }
+        }
+    }
+
+    pub fn update_filters(
+        &mut self,
+        filter_id: usize,
+        cutoff: f32,
+        resonance: f32,
+        gain: f32,
+        key_tracking: f32,
+        comb_frequency: f32,
+        comb_dampening: f32,
+        oversampling: u32,
+        filter_type: FilterType,
+        filter_slope: FilterSlope,
+    ) -> Result<(), String> {
+        let filter_id = self.node_from_handle(filter_id);
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(filter_id) {
+                if let Some(filter) = node.as_any_mut().downcast_mut::<FilterCollection>() {
+                    filter.set_filter_type(filter_type);
+                    filter.set_filter_slope(filter_slope);
+                    filter.set_params(cutoff, resonance);
+                    filter.set_comb_target_frequency(comb_frequency);
+                    filter.set_comb_dampening(comb_dampening);
+                    filter.set_gain_db(gain * 24.0 - 12.0);
+                    filter.set_keyboard_tracking_sensitivity(key_tracking);
+                    filter.set_oversampling_factor(oversampling);
+                } else {
+                    return Err("Node is not a Filter".to_string());
+                }
+            } else {
+                return Err("Node not found".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn update_filter_auto_gain(
+        &mut self,
+        filter_id: NodeId,
+        enabled: bool,
+    ) -> Result<(), String> {
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(filter_id) {
+                if let Some(filter) = node.as_any_mut().downcast_mut::<FilterCollection>() {
+                    filter.set_auto_gain(enabled);
+                } else {
+                    return Err("Node is not a Filter".to_string());
+                }
+            } else {
+                return Err("Node not found".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn update_filter_double_precision(
+        &mut self,
+        filter_id: NodeId,
+        enabled: bool,
+    ) -> Result<(), String> {
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(filter_id) {
+                if let Some(filter) = node.as_any_mut().downcast_mut::<FilterCollection>() {
+                    filter.set_double_precision_feedback(enabled);
+                } else {
+                    return Err("Node is not a Filter".to_string());
+                }
+            } else {
+                return Err("Node not found".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs a filter at 1x, 2x or 4x the sample rate.
+    pub fn update_filter_oversampling(
+        &mut self,
+        filter_id: NodeId,
+        factor: u32,
+    ) -> Result<(), String> {
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(filter_id) {
+                if let Some(filter) = node.as_any_mut().downcast_mut::<FilterCollection>() {
+                    filter.set_oversampling_factor(factor);
+                } else {
+                    return Err("Node is not a Filter".to_string());
+                }
+            } else {
+                return Err("Node not found".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn update_filter_cutoff_mod_octaves(
+        &mut self,
+        filter_id: NodeId,
+        octaves: f32,
+    ) -> Result<(), String> {
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(filter_id) {
+                if let Some(filter) = node.as_any_mut().downcast_mut::<FilterCollection>() {
+                    filter.set_cutoff_mod_octaves(octaves);
+                } else {
+                    return Err("Node is not a Filter".to_string());
+                }
+            } else {
+                return Err("Node not found".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    // pub fn update_noise(
+    //     &mut self,
+    //     noise_id: usize,
+    //     params: &NoiseUpdateParams,
+    // ) -> Result<(), String> {
+    //     for voice in &mut self.voices {
+    //         if let Some(node) = voice.graph.get_node_mut(NodeId(noise_id)) {
+    //             if let Some(noise) = node.as_any_mut().downcast_mut::<NoiseGenerator>() {
+    //                 noise.update(NoiseUpdate {
+    //                     noise_type: params.noise_type.into(),
+    //                     cutoff: params.cutoff * self.sample_rate,
+    //                     gain: params.gain,
+    //                     enabled: params.enabled,
+    //                 });
+    //             } else {
+    //                 return Err("Node is not a NoiseGenerator".to_string());
+    //             }
+    //         } else {
+    //             return Err("Node not found".to_string());
+    //         }
+    //     }
+    //     Ok(())
+    // }
+}
 
-            // Connect GlobalFrequency -> Glide input.
+unsafe impl Send for AudioEngine {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio_engine::jobs::ImpulseShape;
+    use crate::graph::{Connection, ConnectionKey, ModulationTransformation, ModulationType};
+    use crate::nodes::{AnalogOscillator, LfoWaveform, Mixer};
+    use crate::PortId;
+    use uuid::Uuid;
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn process_with_frame_outputs_audio_when_gate_is_active() {
+        let sample_rate = 48_000.0;
+        let mut engine = AudioEngine::new(sample_rate, 1);
+        engine.init(sample_rate, 1);
+
+        let voice = engine.voices.get_mut(0).expect("voice should exist");
+        let osc_id = voice.graph.add_node(Box::new(AnalogOscillator::new(
+            sample_rate,
+            Waveform::Sine,
+            engine.wavetable_banks.clone(),
+        )));
+        let mixer_id = voice.graph.add_node(Box::new(Mixer::new()));
+        voice.graph.set_output_node(mixer_id);
+
+        voice.graph.add_connection(Connection {
+            from_node: osc_id,
+            from_port: PortId::AudioOutput0,
+            to_node: mixer_id,
+            to_port: PortId::AudioInput0,
+            amount: 1.0,
+            modulation_type: ModulationType::Additive,
+            modulation_transform: ModulationTransformation::None,
+        });
+
+        let mut frame = AutomationFrame::with_dimensions(engine.num_voices(), MACRO_COUNT, 64);
+        frame.set_voice_values(0, 1.0, 440.0, 1.0, 1.0);
+
+        let mut left = [0.0f32; 128];
+        let mut right = [0.0f32; 128];
+
+        engine.process_with_frame(&frame, 1.0, &mut left, &mut right);
+        engine.process_with_frame(&frame, 1.0, &mut left, &mut right);
+
+        let has_signal = left
+            .iter()
+            .chain(right.iter())
+            .any(|&sample| sample.abs() > 1e-6);
+
+        assert!(has_signal, "expected audio output after gate activation");
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    fn sine_engine(sample_rate: f32) -> AudioEngine {
+        let mut engine = AudioEngine::new(sample_rate, 2);
+        engine.init(sample_rate, 2);
+        let banks = engine.wavetable_banks.clone();
+        for voice in &mut engine.voices {
+            let osc_id = voice.graph.add_node(Box::new(AnalogOscillator::new(
+                sample_rate,
+                Waveform::Sine,
+                banks.clone(),
+            )));
+            let mixer_id = voice.graph.add_node(Box::new(Mixer::new()));
+            voice.graph.set_output_node(mixer_id);
             voice.graph.add_connection(Connection {
-                from_node: global_freq_id,
-                from_port: PortId::GlobalFrequency,
-                to_node: glide_node,
+                from_node: osc_id,
+                from_port: PortId::AudioOutput0,
+                to_node: mixer_id,
                 to_port: PortId::AudioInput0,
                 amount: 1.0,
                 modulation_type: ModulationType::Additive,
                 modulation_transform: ModulationTransformation::None,
             });
+        }
+        engine
+    }
 
-            // Connect Glide output -> target GlobalFrequency input.
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn process_audio_multichannel_fills_every_channel_from_a_frame() {
+        let mut engine = sine_engine(48_000.0);
+        engine.set_channel_layout(ChannelLayout::Quad);
+        engine.set_voice_position(0, 0.0, -1.0);
+        let mut frame = AutomationFrame::with_dimensions(engine.num_voices(), MACRO_COUNT, 128);
+        frame.set_voice_values(0, 1.0, 440.0, 1.0, 1.0);
+
+        let mut channels = [[0.0f32; 128]; 4];
+        let mut outputs: Vec<&mut [f32]> = channels.iter_mut().map(|c| &mut c[..]).collect();
+        assert!(engine
+            .process_audio_multichannel(&frame, 1.0, &mut outputs[..2])
+            .is_err());
+        for _ in 0..2 {
+            engine
+                .process_audio_multichannel(&frame, 1.0, &mut outputs)
+                .expect("four buffers match the quad layout");
+        }
+
+        let peak = |channel: &[f32]| channel.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(peak(&channels[2]) > 1e-3 && peak(&channels[3]) > 1e-3);
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn render_notes_nulls_against_itself_and_flags_detune() {
+        let sample_rate = 48_000.0;
+        let notes = [
+            RenderNote::new(220.0, 1.0, 100, 6_000),
+            RenderNote::new(330.0, 0.8, 3_000, 6_000),
+        ];
+        let length = 12_000;
+
+        let (ref_l, ref_r) = sine_engine(sample_rate).render_notes(&notes, length);
+        assert!(
+            ref_l.iter().any(|s| s.abs() > 1e-4),
+            "render should not be silent"
+        );
+
+        let (same_l, same_r) = sine_engine(sample_rate).render_notes(&notes, length);
+        let report = compare_renders(&ref_l, &ref_r, &same_l, &same_r, sample_rate);
+        assert!(report.is_transparent(90.0, 0.01), "{:?}", report);
+
+        let mut detuned = sine_engine(sample_rate);
+        detuned.set_master_tuning(0.0, 20.0);
+        let (det_l, det_r) = detuned.render_notes(&notes, length);
+        let report = compare_renders(&ref_l, &ref_r, &det_l, &det_r, sample_rate);
+        assert!(report.null_depth_db > -20.0, "{:?}", report);
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn allocated_notes_drive_voices_without_gate_buffers() {
+        let mut engine = sine_engine(48_000.0);
+        let mut left = [0.0f32; 128];
+        let mut right = [0.0f32; 128];
+
+        let first = engine.note_on(69, 0.8).unwrap();
+        let second = engine.note_on(81, 1.0).unwrap();
+        assert_ne!(first, second);
+        engine.process_audio(&[], &[], &[], &[], &[], 1.0, &mut left, &mut right);
+        assert_eq!(engine.voices[first].current_gate, 1.0);
+        assert!((engine.voices[second].current_frequency - 880.0).abs() < 1e-3);
+        assert_eq!(engine.voices[first].current_velocity, 0.8);
+        assert!(left.iter().any(|&sample| sample.abs() > 1e-6));
+
+        assert!(engine.note_off(69));
+        engine.process_audio(&[], &[], &[], &[], &[], 1.0, &mut left, &mut right);
+        assert_eq!(engine.voices[first].current_gate, 0.0);
+        assert_eq!(engine.voices[second].current_gate, 1.0);
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn stolen_voices_fade_out_before_their_new_note() {
+        let mut engine = sine_engine(48_000.0);
+        let mut left = [0.0f32; 128];
+        let mut right = [0.0f32; 128];
+
+        engine.note_on(69, 1.0).unwrap();
+        engine.note_on(72, 1.0).unwrap();
+        engine.process_audio(&[], &[], &[], &[], &[], 1.0, &mut left, &mut right);
+
+        let stolen = engine.note_on(81, 1.0).unwrap();
+        let old_frequency = engine.voices[stolen].current_frequency;
+        engine.process_audio(&[], &[], &[], &[], &[], 1.0, &mut left, &mut right);
+        assert_eq!(engine.voices[stolen].current_gate, 0.0);
+        assert_eq!(engine.voices[stolen].current_frequency, old_frequency);
+
+        // The new note starts once the 5 ms fade is over.
+        for _ in 0..2 {
+            engine.process_audio(&[], &[], &[], &[], &[], 1.0, &mut left, &mut right);
+        }
+        assert_eq!(engine.voices[stolen].current_gate, 1.0);
+        assert!((engine.voices[stolen].current_frequency - 880.0).abs() < 1e-3);
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn eq_bands_shape_its_response() {
+        let mut engine = sine_engine(48_000.0);
+        let eq = EFFECT_NODE_ID_OFFSET + engine.effect_stack.effects.len() - 1;
+        assert!(engine.get_eq_response(EFFECT_NODE_ID_OFFSET, 8).is_err());
+        assert!(engine
+            .get_eq_response(eq, 8)
+            .unwrap()
+            .iter()
+            .all(|db| db.abs() < 1e-3));
+
+        engine.update_eq(eq, true, 3).unwrap();
+        engine
+            .update_eq_band(eq, 2, EqBandType::LowCut, 200.0, 0.707, 0.0)
+            .unwrap();
+        assert!(engine
+            .update_eq_band(eq, 3, EqBandType::Peak, 1_000.0, 1.0, 6.0)
+            .is_err());
+        let response = engine.get_eq_response(eq, 64).unwrap();
+        assert!(response[0] < -30.0, "{:?}", &response[..4]);
+        assert!(response[63].abs() < 0.1);
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn note_off_velocity_reaches_the_released_voice() {
+        let mut engine = sine_engine(48_000.0);
+        let mut left = [0.0f32; 128];
+        let mut right = [0.0f32; 128];
+
+        let first = engine.note_on(60, 1.0).unwrap();
+        let second = engine.note_on(64, 1.0).unwrap();
+        assert!(engine.note_off_with_velocity(60, 0.2));
+        engine.process_audio(&[], &[], &[], &[], &[], 1.0, &mut left, &mut right);
+        assert_eq!(engine.voices[first].current_release_velocity, 0.2);
+        assert_eq!(
+            engine.voices[second].current_release_velocity,
+            DEFAULT_RELEASE_VELOCITY
+        );
+
+        // A plain note-off carries the default.
+        assert!(engine.note_off(64));
+        engine.process_audio(&[], &[], &[], &[], &[], 1.0, &mut left, &mut right);
+        assert_eq!(
+            engine.voices[second].current_release_velocity,
+            DEFAULT_RELEASE_VELOCITY
+        );
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn overload_protection_holds_eco_until_recovery() {
+        let mut engine = sine_engine(48_000.0);
+        engine.set_quality_mode(QualityMode::High);
+
+        // Disabled protection ignores overloads.
+        engine.last_cpu_usage = 99.0;
+        engine.handle_overload();
+        assert_eq!(engine.effect_stack.quality_mode(), QualityMode::High);
+
+        engine.set_overload_protection(true);
+        engine.handle_overload();
+        assert_eq!(engine.effect_stack.quality_mode(), QualityMode::Eco);
+        assert!(matches!(
+            engine.take_diagnostics()[0],
+            DiagnosticEvent::Overload(event) if event.action == OverloadAction::ReducedQuality
+        ));
+
+        // A requested mode waits for recovery instead of undoing the protection.
+        engine.set_quality_mode(QualityMode::Normal);
+        assert_eq!(engine.effect_stack.quality_mode(), QualityMode::Eco);
+
+        engine.set_overload_protection(false);
+        assert_eq!(engine.effect_stack.quality_mode(), QualityMode::Normal);
+        assert_eq!(engine.quality_mode(), QualityMode::Normal);
+        assert!(matches!(
+            engine.take_diagnostics()[0],
+            DiagnosticEvent::Overload(event) if event.action == OverloadAction::RestoredQuality
+        ));
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn incremental_patches_keep_unchanged_nodes() {
+        const MIXER: &str = "00000000-0000-0000-0000-000000000001";
+        const OSC: &str = "00000000-0000-0000-0000-000000000002";
+        const LFO: &str = "00000000-0000-0000-0000-000000000003";
+        let patch = |with_lfo: bool| {
+            let mut nodes = serde_json::json!({
+                "mixer": [{ "id": MIXER, "type": "mixer", "name": "Mixer" }],
+                "oscillator": [{ "id": OSC, "type": "oscillator", "name": "Osc" }],
+            });
+            let mut connections = vec![serde_json::json!({
+                "fromId": OSC, "toId": MIXER, "target": 0, "amount": 1.0,
+                "modulationType": 2, "modulationTransformation": 0,
+            })];
+            if with_lfo {
+                nodes["lfo"] = serde_json::json!([{ "id": LFO, "type": "lfo", "name": "LFO" }]);
+                connections.push(serde_json::json!({
+                    "fromId": LFO, "toId": OSC, "target": 17, "amount": 0.5,
+                    "modulationType": 0, "modulationTransformation": 0,
+                }));
+            }
+            serde_json::json!({
+                "metadata": { "id": "patch", "name": "Patch" },
+                "synthState": { "layout": {
+                    "voiceCount": 2,
+                    "canonicalVoice": { "id": 0, "nodes": nodes, "connections": connections },
+                } },
+            })
+            .to_string()
+        };
+        let node_addr = |engine: &AudioEngine, id: &str| {
+            let node = engine.voices[1]
+                .graph
+                .get_node(parse_node_id(id).unwrap())
+                .unwrap();
+            &**node as *const dyn AudioNode as *const () as usize
+        };
+
+        let mut engine = AudioEngine::new(48_000.0, 2);
+        engine.init(48_000.0, 2);
+        engine.init_with_patch(&patch(false)).unwrap();
+        let osc = node_addr(&engine, OSC);
+        let glide = engine.voices[0].graph.global_glide_node;
+
+        assert_eq!(engine.apply_patch_incremental(&patch(true)).unwrap(), 2);
+        assert_eq!(node_addr(&engine, OSC), osc);
+        assert_eq!(engine.voices[0].graph.global_glide_node, glide);
+        assert!(engine.voices[1]
+            .graph
+            .get_node(parse_node_id(LFO).unwrap())
+            .is_some());
+
+        engine.apply_patch_incremental(&patch(false)).unwrap();
+        assert_eq!(node_addr(&engine, OSC), osc);
+        assert!(engine.voices[1]
+            .graph
+            .get_node(parse_node_id(LFO).unwrap())
+            .is_none());
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn incremental_patches_keep_held_notes_sounding() {
+        const MIXER: &str = "00000000-0000-0000-0000-000000000001";
+        const OSC: &str = "00000000-0000-0000-0000-000000000002";
+        const LFO: &str = "00000000-0000-0000-0000-000000000003";
+        const FREQUENCY: &str = "00000000-0000-0000-0000-000000000004";
+        let patch = |with_lfo: bool| {
+            let mut nodes = serde_json::json!({
+                "global_frequency": [
+                    { "id": FREQUENCY, "type": "global_frequency", "name": "Frequency" },
+                ],
+                "mixer": [{ "id": MIXER, "type": "mixer", "name": "Mixer" }],
+                "oscillator": [{ "id": OSC, "type": "oscillator", "name": "Osc" }],
+            });
+            let mut connections = vec![serde_json::json!({
+                "fromId": OSC, "toId": MIXER, "target": 0, "amount": 1.0,
+                "modulationType": 2, "modulationTransformation": 0,
+            })];
+            if with_lfo {
+                nodes["lfo"] = serde_json::json!([{ "id": LFO, "type": "lfo", "name": "LFO" }]);
+                connections.push(serde_json::json!({
+                    "fromId": LFO, "toId": OSC, "target": 21, "amount": 0.5,
+                    "modulationType": 2, "modulationTransformation": 0,
+                }));
+            }
+            serde_json::json!({
+                "metadata": { "id": "patch", "name": "Patch" },
+                "synthState": { "layout": {
+                    "voiceCount": 2,
+                    "canonicalVoice": { "id": 0, "nodes": nodes, "connections": connections },
+                } },
+            })
+            .to_string()
+        };
+        let peak = |engine: &mut AudioEngine| {
+            let mut left = vec![0.0; engine.block_size()];
+            let mut right = vec![0.0; engine.block_size()];
+            engine.process_audio(&[], &[], &[], &[], &[], 1.0, &mut left, &mut right);
+            left.iter()
+                .fold(0.0f32, |max, sample| max.max(sample.abs()))
+        };
+
+        let mut engine = AudioEngine::new(48_000.0, 2);
+        engine.init(48_000.0, 2);
+        engine.init_with_patch(&patch(false)).unwrap();
+        let voice = engine.note_on(69, 1.0).unwrap();
+        for _ in 0..4 {
+            peak(&mut engine);
+        }
+        let before = peak(&mut engine);
+        assert!(before > 1e-3, "before = {}", before);
+
+        // The edit adds a vibrato LFO; the held note plays on through it.
+        engine.apply_patch_incremental(&patch(true)).unwrap();
+        for _ in 0..4 {
+            let after = peak(&mut engine);
+            assert!(
+                after > before * 0.5,
+                "after = {}, before = {}",
+                after,
+                before
+            );
+        }
+        assert_eq!(engine.voices[voice].current_gate, 1.0);
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn per_voice_saturation_and_bitcrusher_stay_stereo() {
+        const MIXER: &str = "00000000-0000-0000-0000-000000000001";
+        const OSC: &str = "00000000-0000-0000-0000-000000000002";
+        const SATURATION: &str = "00000000-0000-0000-0000-000000000003";
+        const CRUSHER: &str = "00000000-0000-0000-0000-000000000004";
+        let link = |from: &str, to: &str| {
+            serde_json::json!({
+                "fromId": from, "toId": to, "target": 0, "amount": 1.0,
+                "modulationType": 2, "modulationTransformation": 0,
+            })
+        };
+        let patch = serde_json::json!({
+            "metadata": { "id": "patch", "name": "Patch" },
+            "synthState": {
+                "layout": {
+                    "voiceCount": 2,
+                    "canonicalVoice": {
+                        "id": 0,
+                        "nodes": {
+                            "mixer": [{ "id": MIXER, "type": "mixer", "name": "Mixer" }],
+                            "oscillator": [{ "id": OSC, "type": "oscillator", "name": "Osc" }],
+                            // The master insert is listed under the same type.
+                            "saturation": [
+                                { "id": SATURATION, "type": "saturation", "name": "Drive" },
+                                { "id": "10005", "type": "saturation", "name": "Master" },
+                            ],
+                            "bitcrusher": [{ "id": CRUSHER, "type": "bitcrusher", "name": "Bits" }],
+                        },
+                        "connections": [
+                            link(OSC, SATURATION),
+                            link(SATURATION, CRUSHER),
+                            link(CRUSHER, MIXER),
+                        ],
+                    },
+                },
+                "saturations": { SATURATION: {
+                    "id": SATURATION, "active": true, "drive": 4.0, "mix": 1.0,
+                } },
+                "bitcrushers": { CRUSHER: {
+                    "id": CRUSHER, "active": true, "bits": 6, "downsampleFactor": 1, "mix": 1.0,
+                } },
+            },
+        })
+        .to_string();
+
+        let mut engine = AudioEngine::new(48_000.0, 2);
+        engine.init(48_000.0, 2);
+        engine.set_stereo_voices(true);
+        engine.init_with_patch(&patch).unwrap();
+        let [saturation, crusher, mixer] =
+            [SATURATION, CRUSHER, MIXER].map(|id| parse_node_id(id).unwrap());
+        for voice in &mut engine.voices {
+            let node = voice.graph.get_node_mut(saturation).unwrap();
+            let drive = node
+                .as_any_mut()
+                .downcast_mut::<Saturation>()
+                .unwrap()
+                .drive();
+            assert_eq!(drive, 4.0);
+            let node = voice.graph.get_node_mut(crusher).unwrap();
+            assert_eq!(
+                node.as_any_mut()
+                    .downcast_mut::<Bitcrusher>()
+                    .unwrap()
+                    .bits(),
+                6
+            );
+            // Both effects pass a right channel on to the mixer.
+            for (from_node, to_node) in [(saturation, crusher), (crusher, mixer)] {
+                assert!(voice.graph.connections.contains_key(&ConnectionKey::new(
+                    from_node,
+                    PortId::AudioOutput1,
+                    to_node,
+                    PortId::AudioInput1,
+                )));
+            }
+        }
+
+        let (left, right) = engine.render_notes(&[RenderNote::new(110.0, 1.0, 0, 4_800)], 4_800);
+        assert!(left.iter().chain(&right).all(|sample| sample.is_finite()));
+        assert!(right.iter().any(|sample| sample.abs() > 1e-3));
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn snapshots_only_changed_sessions() {
+        let mut engine = sine_engine(48_000.0);
+        let block = engine.block_size();
+        engine.set_snapshot_interval(block as f32 / 48_000.0);
+        let mut left = vec![0.0; block];
+        let mut right = vec![0.0; block];
+
+        engine.set_macro_value(None, 2, 0.25, None).unwrap();
+        engine.process_audio(&[], &[], &[], &[], &[], 1.0, &mut left, &mut right);
+        let json = engine.take_snapshot().expect("a snapshot after a change");
+        let snapshot = SessionSnapshot::from_json(&json).unwrap();
+        assert_eq!(
+            snapshot.performance.macro_values,
+            vec![None, None, Some(0.25)]
+        );
+
+        engine.process_audio(&[], &[], &[], &[], &[], 1.0, &mut left, &mut right);
+        assert_eq!(engine.take_snapshot(), None);
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn memory_stats_cover_every_voice() {
+        let engine = sine_engine(48_000.0);
+        let stats = engine.get_memory_stats();
+        assert_eq!(stats.graph_buffers_per_voice.len(), engine.voices.len());
+        assert!(stats.wavetable_banks > 0);
+        assert!(stats.total >= stats.wavetable_banks + stats.graph_buffers_per_voice[0]);
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn finished_impulse_jobs_replace_the_convolver() {
+        let mut engine = sine_engine(48_000.0);
+        let chorus = EFFECT_NODE_ID_OFFSET;
+        let plate = EFFECT_NODE_ID_OFFSET + 3;
+        let request = |effect_id| JobRequest::GenerateImpulse {
+            effect_id,
+            shape: ImpulseShape::Hall,
+            decay_time: 0.5,
+            size: 0.5,
+        };
+        assert!(engine.start_job(request(chorus), Vec::new()).is_err());
+
+        let job = engine.start_job(request(plate), Vec::new()).unwrap();
+        let status = loop {
+            match engine.poll_job(job).unwrap() {
+                JobStatus::Running { .. } => std::thread::yield_now(),
+                status => break status,
+            }
+        };
+        assert_eq!(status, JobStatus::Finished);
+        let convolver = engine.convolver_mut(plate).unwrap();
+        assert_eq!(convolver.wet_level(), 0.1);
+        assert!(!convolver.is_active());
+        assert!(engine.poll_job(job).is_err());
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn named_wavetables_can_be_assigned_and_deleted() {
+        let mut engine = AudioEngine::new(48_000.0, 2);
+        engine.init(48_000.0, 2);
+        let handle = engine.create_wavetable_oscillator().unwrap();
+        let osc = engine.node_from_handle(handle);
+        let playing = |engine: &AudioEngine| -> Vec<String> {
+            engine
+                .voices
+                .iter()
+                .map(|voice| {
+                    let node = voice.graph.get_node(osc).unwrap();
+                    let osc = node.as_any().downcast_ref::<WavetableOscillator>().unwrap();
+                    osc.current_wavetable().to_string()
+                })
+                .collect()
+        };
+
+        let mut wt = b"vawt".to_vec();
+        wt.extend(256u32.to_le_bytes());
+        wt.extend(1u16.to_le_bytes());
+        wt.extend(0x4u16.to_le_bytes());
+        for i in 0..256 {
+            let phase = i as f32 / 256.0 * std::f32::consts::TAU;
+            wt.extend(((phase.sin() * 16_000.0) as i16).to_le_bytes());
+        }
+        let request = JobRequest::ImportNamedWavetable {
+            name: "pad".to_string(),
+            base_size: 0,
+        };
+        let job = engine.start_job(request, wt).unwrap();
+        while let JobStatus::Running { .. } = engine.poll_job(job).unwrap() {
+            std::thread::yield_now();
+        }
+        assert_eq!(engine.list_wavetables(), ["default", "pad"]);
+        assert_eq!(playing(&engine), ["default", "default"]);
+
+        let id = osc.to_string();
+        assert!(engine.set_current_wavetable(&id, "missing").is_err());
+        engine.set_current_wavetable(&id, "pad").unwrap();
+        assert_eq!(playing(&engine), ["pad", "pad"]);
+
+        assert!(engine.delete_wavetable("default").is_err());
+        engine.delete_wavetable("pad").unwrap();
+        assert_eq!(playing(&engine), ["default", "default"]);
+        assert_eq!(engine.list_wavetables(), ["default"]);
+        assert!(engine.delete_wavetable("pad").is_err());
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn effects_morph_between_presets_and_randomize_within_range() {
+        let mut engine = AudioEngine::new(48_000.0, 1);
+        engine.init(48_000.0, 1);
+        // The default stack starts chorus, delay.
+        let delay = (EFFECT_NODE_ID_OFFSET + 1).to_string();
+        let setting = |engine: &AudioEngine, name: &str| {
+            let preset: serde_json::Value =
+                serde_json::from_str(&engine.export_node_preset(&delay).unwrap()).unwrap();
+            preset["settings"][name].as_f64().unwrap()
+        };
+        let a = engine.export_node_preset(&delay).unwrap();
+        engine.set_node_parameter(&delay, "feedback", 0.9).unwrap();
+        engine
+            .set_node_parameter(&delay, "delayMs", 1000.0)
+            .unwrap();
+        let b = engine.export_node_preset(&delay).unwrap();
+
+        engine.morph_effect(1, &a, &b, 0.5).unwrap();
+        assert!((setting(&engine, "feedback") - 0.7).abs() < 1e-4);
+        assert!((setting(&engine, "delayMs") - 750.0).abs() < 1e-3);
+
+        engine.randomize_effect(1, 0.0).unwrap();
+        assert!((setting(&engine, "delayMs") - 750.0).abs() < 1e-3);
+        for _ in 0..8 {
+            engine.randomize_effect(1, 1.0).unwrap();
+            let feedback = setting(&engine, "feedback");
+            assert!((0.0..=0.95).contains(&feedback), "{}", feedback);
+        }
+        assert!(engine.randomize_effect(0, 0.5).is_err());
+        let reverb = engine.export_node_preset(&(EFFECT_NODE_ID_OFFSET + 2).to_string());
+        assert!(engine.morph_effect(1, &a, &reverb.unwrap(), 0.5).is_err());
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn xy_scope_captures_node_outputs() {
+        let mut engine = AudioEngine::new(48_000.0, 1);
+        engine.init(48_000.0, 1);
+        let ids = engine.create_standard_voice().unwrap();
+        let oscillator = parse_node_id(&ids.oscillator_id).unwrap();
+        let filter = parse_node_id(&ids.filter_id).unwrap();
+        let missing = (NodeId::new(), PortId::AudioOutput0);
+        let (x, y) = (
+            (oscillator, PortId::AudioOutput0),
+            (filter, PortId::AudioOutput0),
+        );
+        assert!(engine.set_xy_scope_nodes(missing, y).is_err());
+        engine.set_xy_scope_nodes(x, y).unwrap();
+        engine.render_notes(&[RenderNote::new(220.0, 1.0, 0, 4_800)], 4_800);
+        assert!(engine.xy_scope_capture().is_none());
+
+        engine.set_xy_scope_length(512);
+        engine.render_notes(&[RenderNote::new(220.0, 1.0, 0, 4_800)], 4_800);
+        let capture = engine.xy_scope_capture().unwrap();
+        assert_eq!((capture.x.len(), capture.y.len()), (512, 512));
+        assert!(capture.triggered);
+        assert!(capture.x.iter().any(|&x| x.abs() > 0.1));
+        assert!(capture.y.iter().any(|&y| y.abs() > 1e-3));
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn chorus_lowers_correlation_and_mono_compatibility() {
+        let mut engine = AudioEngine::new(48_000.0, 1);
+        engine.init(48_000.0, 1);
+        engine.create_standard_voice().unwrap();
+        let notes = [RenderNote::new(220.0, 1.0, 0, 24_000)];
+        let dry = engine.check_mono_compatibility(&notes, 24_000);
+        assert!(dry.loss_db < 1.0, "{:?}", dry);
+        assert!(engine.get_correlation() > 0.5);
+
+        engine.set_chorus_active(true);
+        let chorus = engine.check_mono_compatibility(&notes, 24_000);
+        assert!(
+            chorus.correlation < dry.correlation,
+            "{:?} {:?}",
+            dry,
+            chorus
+        );
+        assert!(chorus.loss_db > dry.loss_db, "{:?} {:?}", dry, chorus);
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn auto_levels_match_new_oscillators_to_the_voice() {
+        let mut engine = AudioEngine::new(48_000.0, 2);
+        engine.init(48_000.0, 2);
+        let ids = engine.create_standard_voice().unwrap();
+        let saw = parse_node_id(&ids.oscillator_id).unwrap();
+        for voice in &mut engine.voices {
+            let node = voice.graph.get_node_mut(saw).unwrap();
+            node.as_any_mut()
+                .downcast_mut::<AnalogOscillator>()
+                .unwrap()
+                .set_gain(0.5);
+        }
+        engine.create_filter().unwrap();
+        assert!(engine.last_node_levels().is_none());
+
+        engine.set_auto_node_levels(true);
+        let handle = engine.create_oscillator().unwrap();
+        let osc = engine.node_from_handle(handle);
+        let levels = engine.last_node_levels().unwrap();
+        assert_eq!(levels.gain, 0.5);
+        // The filtered saw already runs the mixer at full scale.
+        assert!(levels.headroom_db.abs() < 1e-6);
+        assert!((levels.mixer_input_gain - 0.5f32.sqrt()).abs() < 1e-6);
+        for voice in &engine.voices {
+            let node = voice.graph.get_node(osc).unwrap();
+            assert_eq!(
+                node.as_any()
+                    .downcast_ref::<AnalogOscillator>()
+                    .unwrap()
+                    .gain(),
+                0.5
+            );
+        }
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn last_wavetable_import_can_be_reverted() {
+        let mut engine = AudioEngine::new(48_000.0, 2);
+        engine.init(48_000.0, 2);
+        engine.set_import_backup(true);
+        let handle = engine.create_wavetable_oscillator().unwrap();
+        let osc = engine.node_from_handle(handle);
+        let playing = |engine: &AudioEngine| -> Vec<String> {
+            engine
+                .voices
+                .iter()
+                .map(|voice| {
+                    let node = voice.graph.get_node(osc).unwrap();
+                    let osc = node.as_any().downcast_ref::<WavetableOscillator>().unwrap();
+                    osc.current_wavetable().to_string()
+                })
+                .collect()
+        };
+
+        let mut wt = b"vawt".to_vec();
+        wt.extend(256u32.to_le_bytes());
+        wt.extend(1u16.to_le_bytes());
+        wt.extend(0x4u16.to_le_bytes());
+        for i in 0..256 {
+            let phase = i as f32 / 256.0 * std::f32::consts::TAU;
+            wt.extend(((phase.sin() * 16_000.0) as i16).to_le_bytes());
+        }
+        let request = JobRequest::ImportWavetable {
+            node_id: osc.to_string(),
+            base_size: 0,
+        };
+        let job = engine.start_job(request, wt).unwrap();
+        while let JobStatus::Running { .. } = engine.poll_job(job).unwrap() {
+            std::thread::yield_now();
+        }
+        let imported = format!("wt_{}", osc.to_string());
+        assert_eq!(playing(&engine), [imported.clone(), imported]);
+        assert!(engine.import_backup() && engine.can_revert_import());
+
+        assert!(engine.revert_last_import().unwrap());
+        assert_eq!(playing(&engine), ["default", "default"]);
+        assert_eq!(engine.list_wavetables(), ["default"]);
+        assert!(!engine.can_revert_import());
+        assert!(!engine.revert_last_import().unwrap());
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn modulation_ranges_are_reported_in_parameter_units() {
+        let sample_rate = 48_000.0;
+        let mut engine = sine_engine(sample_rate);
+        let (lfo, filter) = (NodeId(Uuid::new_v4()), NodeId(Uuid::new_v4()));
+        for voice in &mut engine.voices {
+            let mut filter_node = FilterCollection::new(sample_rate);
+            filter_node.set_params(1_000.0, 0.0);
+            filter_node.set_cutoff_mod_octaves(2.0);
+            voice
+                .graph
+                .add_node_with_id(lfo, Box::new(Lfo::new(sample_rate)));
+            voice.graph.add_node_with_id(filter, Box::new(filter_node));
             voice.graph.add_connection(Connection {
-                from_node: glide_node,
+                from_node: lfo,
                 from_port: PortId::AudioOutput0,
-                to_node: target_node_id,
-                to_port: PortId::GlobalFrequency,
+                to_node: filter,
+                to_port: PortId::CutoffMod,
                 amount: 1.0,
                 modulation_type: ModulationType::Additive,
                 modulation_transform: ModulationTransformation::None,
             });
         }
 
-        Ok(())
+        let (lfo, filter) = (lfo.to_string(), filter.to_string());
+        let range = engine
+            .get_modulation_range(&lfo, &filter, PortId::CutoffMod)
+            .unwrap();
+        assert_eq!((range.parameter, range.unit), ("cutoff", "Hz"));
+        assert!((range.low - 250.0).abs() < 0.01 && (range.high - 4_000.0).abs() < 0.1);
+        assert!(engine
+            .get_modulation_range(&lfo, &filter, PortId::ResonanceMod)
+            .is_err());
     }
 
-    // pub fn create_noise(&mut self) -> Result<usize, String> {
-    //     let mut noise_id = NodeId(0);
-    //     for voice in &mut self.voices {
-    //         noise_id = voice
-    //             .graph
-    //             .add_node(Box::new(NoiseGenerator::new(self.sample_rate)));
-    //     }
-    //     Ok(noise_id.0)
-    // }
-
-    // Connection methods
-    pub fn connect_nodes(
-        &mut self,
-        from_node: usize,
-        from_port: PortId,
-        to_node: usize,
-        to_port: PortId,
-        amount: f32,
-        modulation_type: ModulationType,
-        modulation_transform: ModulationTransformation,
-    ) -> Result<(), String> {
-        let connection = Connection {
-            from_node: NodeId(Uuid::from_u128(from_node as u128)),
-            from_port,
-            to_node: NodeId(Uuid::from_u128(to_node as u128)),
-            to_port,
-            amount,
-            modulation_type,
-            modulation_transform,
-        };
-
-        for voice in &mut self.voices {
-            voice.graph.add_connection(connection.clone());
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn flat_parameters_cover_voice_nodes_and_effects() {
+        let engine = sine_engine(48_000.0);
+        let rows = engine.export_flat_parameters();
+        let delay_id = (EFFECT_NODE_ID_OFFSET + 1).to_string();
+        assert!(rows
+            .iter()
+            .any(|row| row.node_id == delay_id && row.parameter == "delayMs"));
+        for voice_node in engine.voices[0].graph.nodes.keys() {
+            let id = voice_node.to_string();
+            assert!(rows.iter().any(|row| row.node_id == id));
         }
-        Ok(())
     }
 
-    // Parameter update methods
-    pub fn update_oscillator(
-        &mut self,
-        oscillator_id: NodeId,
-        params: &AnalogOscillatorStateUpdate,
-    ) -> Result<(), String> {
-        for voice in &mut self.voices {
-            let node = voice
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn strict_realtime_refuses_nodes_past_capacity() {
+        let sample_rate = 48_000.0;
+        let mut engine = sine_engine(sample_rate);
+        let graph = &engine.voices[0].graph;
+        let limits = CapacityLimits {
+            max_voices: engine.voices.len(),
+            max_nodes: graph.nodes.len() + 1,
+            max_connections: graph.connections.len() + 8,
+            max_block_size: 128,
+            max_sources_per_port: 4,
+        };
+        let too_few_voices = CapacityLimits {
+            max_voices: engine.voices.len() - 1,
+            ..limits
+        };
+        assert!(engine.set_strict_realtime(Some(too_few_voices)).is_err());
+        engine.set_strict_realtime(Some(limits)).unwrap();
+
+        let (first, second) = (NodeId(Uuid::new_v4()), NodeId(Uuid::new_v4()));
+        for voice in &mut engine.voices {
+            voice
                 .graph
-                .get_node_mut(oscillator_id)
-                .ok_or_else(|| "Node not found in one of the voices".to_string())?;
-            let osc = node
-                .as_any_mut()
-                .downcast_mut::<AnalogOscillator>()
-                .ok_or_else(|| {
-                    "Node is not an AnalogOscillator in one of the voices".to_string()
-                })?;
-            osc.update_params(params);
+                .add_node_with_id(first, Box::new(Lfo::new(sample_rate)));
+            voice
+                .graph
+                .add_node_with_id(second, Box::new(Lfo::new(sample_rate)));
+            assert!(voice.graph.get_node(first).is_some());
+            assert!(voice.graph.get_node(second).is_none());
         }
-        Ok(())
+
+        let events = engine.take_diagnostics();
+        assert_eq!(events.len(), engine.voices.len());
+        assert!(matches!(
+            events[0],
+            DiagnosticEvent::Capacity {
+                voice: Some(0),
+                exceeded: CapacityExceeded {
+                    resource: CapacityResource::Nodes,
+                    ..
+                },
+            }
+        ));
+        assert!(engine.take_diagnostics().is_empty());
     }
 
-    pub fn update_wavetable_oscillator(
-        &mut self,
-        oscillator_id: NodeId,
-        params: &WavetableOscillatorStateUpdate,
-    ) -> Result<(), String> {
-        for voice in &mut self.voices {
-            let node = voice
-                .graph
-                .get_node_mut(oscillator_id)
-                .ok_or_else(|| "Node not found in one of the voices".to_string())?;
-            let osc = node
-                .as_any_mut()
-                .downcast_mut::<WavetableOscillator>()
-                .ok_or_else(|| {
-                    "Node is not a WavetableOscillator in one of the voices".to_string()
-                })?;
-            osc.update_params(params);
-        }
-        Ok(())
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn connect_nodes_refuses_feedback_loops() {
+        let mut engine = AudioEngine::new(48_000.0, 2);
+        engine.init(48_000.0, 2);
+        let first = engine.create_mixer().unwrap();
+        let second = engine.create_mixer().unwrap();
+        let connect = |engine: &mut AudioEngine, from: usize, to: usize| {
+            engine.connect_nodes(
+                from,
+                PortId::AudioOutput0,
+                to,
+                PortId::AudioInput0,
+                1.0,
+                ModulationType::Additive,
+                ModulationTransformation::None,
+            )
+        };
+
+        connect(&mut engine, first, second).unwrap();
+        let connections = engine.voices[0].graph.connections.len();
+        let error = connect(&mut engine, second, first).unwrap_err();
+        assert!(error.contains("feedback loop"), "{}", error);
+        assert!(connect(&mut engine, first, 12_345).is_err());
+        assert!(engine
+            .voices
+            .iter()
+            .all(|voice| voice.graph.connections.len() == connections));
     }
 
-    pub fn update_envelope(
-        &mut self,
-        node_id: NodeId,
-        attack: f32,
-        decay: f32,
-        sustain: f32,
-        release: f32,
-        attack_curve: f32,
-        decay_curve: f32,
-        release_curve: f32,
-        active: bool,
-    ) -> Result<(), String> {
-        let mut errors: Vec<String> = Vec::new();
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn analog_spread_gives_each_voice_fixed_offsets() {
+        let mut engine = AudioEngine::new(48_000.0, 4);
+        engine.init(48_000.0, 4);
+        let variations = |engine: &AudioEngine| -> Vec<VoiceVariation> {
+            engine
+                .voices
+                .iter()
+                .map(|voice| voice.graph.voice_variation)
+                .collect()
+        };
+        assert!(variations(&engine)
+            .iter()
+            .all(|variation| *variation == VoiceVariation::default()));
 
-        for (i, voice) in self.voices.iter_mut().enumerate() {
-            if let Some(node) = voice.graph.get_node_mut(node_id) {
-                if let Some(env) = node.as_any_mut().downcast_mut::<Envelope>() {
-                    let config = EnvelopeConfig {
-                        attack,
-                        decay,
-                        sustain,
-                        release,
-                        attack_curve,
-                        decay_curve,
-                        release_curve,
-                        attack_smoothing_samples: 16,
-                        active,
-                    };
-                    env.update_config(config);
-                    env.set_active(active);
-                } else {
-                    errors.push(format!("Voice {}: Node is not an Envelope", i));
-                }
-            } else {
-                errors.push(format!("Voice {}: Node not found", i));
-            }
-        }
+        engine.set_analog_spread(1.0);
+        let spread = variations(&engine);
+        assert_ne!(spread[0], spread[1]);
+        assert_ne!(spread[0].pitch_cents, 0.0);
 
-        if errors.is_empty() {
-            Ok(())
-        } else {
-            Err(errors.join("; "))
-        }
+        // Re-initialising keeps the amount and the offsets.
+        engine.init(48_000.0, 4);
+        assert_eq!(variations(&engine), spread);
+        engine.set_analog_spread(0.0);
+        assert_eq!(variations(&engine)[2], VoiceVariation::default());
     }
 
-    pub fn update_filters(
-        &mut self,
-        filter_id: usize,
-        cutoff: f32,
-        resonance: f32,
-        gain: f32,
-        key_tracking: f32,
-        comb_frequency: f32,
-        comb_dampening: f32,
-        _oversampling: u32,
-        filter_type: FilterType,
-        filter_slope: FilterSlope,
-    ) -> Result<(), String> {
-        for voice in &mut self.voices {
-            if let Some(node) = voice
-                .graph
-                .get_node_mut(NodeId(Uuid::from_u128(filter_id as u128)))
-            {
-                if let Some(filter) = node.as_any_mut().downcast_mut::<FilterCollection>() {
-                    filter.set_filter_type(filter_type);
-                    filter.set_filter_slope(filter_slope);
-                    filter.set_params(cutoff, resonance);
-                    filter.set_comb_target_frequency(comb_frequency);
-                    filter.set_comb_dampening(comb_dampening);
-                    filter.set_gain_db(gain * 24.0 - 12.0);
-                    filter.set_keyboard_tracking_sensitivity(key_tracking);
-                } else {
-                    return Err("Node is not a Filter".to_string());
-                }
-            } else {
-                return Err("Node not found".to_string());
-            }
-        }
-        Ok(())
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn standard_voice_plays_and_releases() {
+        let sample_rate = 48_000.0;
+        let mut engine = AudioEngine::new(sample_rate, 2);
+        engine.init(sample_rate, 2);
+        let ids = engine.create_standard_voice().unwrap();
+        let filter = parse_node_id(&ids.filter_id).unwrap();
+        let filter_envelope = parse_node_id(&ids.filter_envelope_id).unwrap();
+        assert!(engine.voices.iter().all(|voice| {
+            voice.graph.connections.values().any(|connection| {
+                connection.from_node == filter_envelope
+                    && connection.to_node == filter
+                    && connection.to_port == PortId::CutoffMod
+            })
+        }));
+
+        let length = 48_000;
+        let (left, _) = engine.render_notes(&[RenderNote::new(110.0, 1.0, 0, 12_000)], length);
+        let peak = |samples: &[f32]| samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(peak(&left[..12_000]) > 1e-3, "held note should sound");
+        assert!(
+            peak(&left[length - 4_800..]) < 1e-4,
+            "release should decay to silence"
+        );
     }
 
-    // pub fn update_noise(
-    //     &mut self,
-    //     noise_id: usize,
-    //     params: &NoiseUpdateParams,
-    // ) -> Result<(), String> {
-    //     for voice in &mut self.voices {
-    //         if let Some(node) = voice.graph.get_node_mut(NodeId(noise_id)) {
-    //             if let Some(noise) = node.as_any_mut().downcast_mut::<NoiseGenerator>() {
-    //                 noise.update(NoiseUpdate {
-    //                     noise_type: params.noise_type.into(),
-    //                     cutoff: params.cutoff * self.sample_rate,
-    //                     gain: params.gain,
-    //                     enabled: params.enabled,
-    //                 });
-    //             } else {
-    //                 return Err("Node is not a NoiseGenerator".to_string());
-    //             }
-    //         } else {
-    //             return Err("Node not found".to_string());
-    //         }
-    //     }
-    //     Ok(())
-    // }
-}
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn waveform_cycles_replace_banks_in_every_voice() {
+        let mut engine = AudioEngine::new(48_000.0, 2);
+        engine.init(48_000.0, 2);
+        engine.create_standard_voice().unwrap();
+        // Long enough for the 0.4 s release to die out before the next render.
+        let peak = |engine: &mut AudioEngine| {
+            let (left, _) = engine.render_notes(&[RenderNote::new(110.0, 1.0, 0, 12_000)], 48_000);
+            left.iter().fold(0.0f32, |m, s| m.max(s.abs()))
+        };
+        let loud = peak(&mut engine);
 
-unsafe impl Send for AudioEngine {}
+        let quiet: Vec<f32> = (0..256)
+            .map(|i| 1e-3 * (std::f32::consts::TAU * i as f32 / 256.0).sin())
+            .collect();
+        engine.set_waveform_cycle(Waveform::Saw, &quiet).unwrap();
+        assert!(peak(&mut engine) < loud * 0.05);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::graph::{Connection, ModulationTransformation, ModulationType};
-    use crate::nodes::{AnalogOscillator, Mixer};
-    use crate::PortId;
-    use uuid::Uuid;
+        assert!(engine
+            .set_waveform_cycle(Waveform::Custom, &quiet[..100])
+            .is_err());
+        assert!(engine
+            .set_waveform_cycle(Waveform::Custom, &[0.0; 256])
+            .is_err());
+        engine.set_waveform_cycle(Waveform::Custom, &quiet).unwrap();
+        assert!(engine.wavetable_banks.contains_key(&Waveform::Custom));
+        engine.reset_waveform_bank(Waveform::Custom).unwrap();
+        assert!(!engine.wavetable_banks.contains_key(&Waveform::Custom));
+    }
 
     #[cfg(not(feature = "wasm"))]
     #[test]
-    fn process_with_frame_outputs_audio_when_gate_is_active() {
-        let sample_rate = 48_000.0;
-        let mut engine = AudioEngine::new(sample_rate, 1);
-        engine.init(sample_rate, 1);
-
-        let voice = engine.voices.get_mut(0).expect("voice should exist");
-        let osc_id = voice.graph.add_node(Box::new(AnalogOscillator::new(
-            sample_rate,
-            Waveform::Sine,
-            engine.wavetable_banks.clone(),
-        )));
-        let mixer_id = voice.graph.add_node(Box::new(Mixer::new()));
-        voice.graph.set_output_node(mixer_id);
+    fn measures_oscillator_aliasing_on_a_copy() {
+        let mut engine = AudioEngine::new(48_000.0, 2);
+        engine.init(48_000.0, 2);
+        let ids = engine.create_standard_voice().unwrap();
+        let oscillator = parse_node_id(&ids.oscillator_id).unwrap();
+        // 3.9 kHz sits at the top of a mip level, 260 Hz at the bottom of one.
+        let high = engine
+            .measure_oscillator_aliasing(oscillator, 3_900.0)
+            .unwrap();
+        let low = engine
+            .measure_oscillator_aliasing(oscillator, 260.0)
+            .unwrap();
+        assert!(high.alias_db > low.alias_db + 20.0, "{:?} {:?}", high, low);
+        assert!(engine
+            .measure_oscillator_aliasing(NodeId::new(), 260.0)
+            .is_err());
+    }
 
-        voice.graph.add_connection(Connection {
-            from_node: osc_id,
-            from_port: PortId::AudioOutput0,
-            to_node: mixer_id,
-            to_port: PortId::AudioInput0,
-            amount: 1.0,
-            modulation_type: ModulationType::Additive,
-            modulation_transform: ModulationTransformation::None,
-        });
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn fm_algorithm_replaces_previous_routing() {
+        let mut engine = AudioEngine::new(48_000.0, 2);
+        engine.init(48_000.0, 2);
+        let operators: Vec<NodeId> = (0..4).map(|_| NodeId::new()).collect();
+        for voice in &mut engine.voices {
+            for &id in &operators {
+                voice
+                    .graph
+                    .add_node_with_id(id, Box::new(FmOperator::new(48_000.0)));
+            }
+        }
+        let ids: Vec<String> = operators.iter().map(|id| id.to_string()).collect();
+        let phase_links = |engine: &AudioEngine| {
+            let graph = &engine.voices[1].graph;
+            let mut links: Vec<(usize, usize)> = graph
+                .connections
+                .values()
+                .filter(|c| c.to_port == PortId::PhaseMod)
+                .map(|c| {
+                    let index = |id| operators.iter().position(|&op| op == id).unwrap();
+                    (index(c.from_node), index(c.to_node))
+                })
+                .collect();
+            links.sort();
+            links
+        };
 
-        let mut frame = AutomationFrame::with_dimensions(engine.num_voices(), MACRO_COUNT, 64);
-        frame.set_voice_values(0, 1.0, 440.0, 1.0, 1.0);
+        engine
+            .apply_fm_algorithm(&ids[..1], &ids[1..], FmAlgorithm::Stack)
+            .unwrap();
+        assert_eq!(phase_links(&engine), vec![(1, 0), (2, 1), (3, 2)]);
+        engine
+            .apply_fm_algorithm(&ids[..2], &ids[2..], FmAlgorithm::Pairs)
+            .unwrap();
+        assert_eq!(phase_links(&engine), vec![(2, 0), (3, 1)]);
 
-        let mut left = [0.0f32; 128];
-        let mut right = [0.0f32; 128];
+        let missing = NodeId::new().to_string();
+        let result = engine.apply_fm_algorithm(&ids[..1], &[missing], FmAlgorithm::Stack);
+        assert!(result.is_err());
+        assert_eq!(phase_links(&engine), vec![(2, 0), (3, 1)]);
+    }
 
-        engine.process_with_frame(&frame, 1.0, &mut left, &mut right);
-        engine.process_with_frame(&frame, 1.0, &mut left, &mut right);
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn copy_node_settings_updates_every_voice() {
+        let sample_rate = 48_000.0;
+        let mut engine = sine_engine(sample_rate);
+        let (src, dst) = (NodeId(Uuid::new_v4()), NodeId(Uuid::new_v4()));
+        for voice in &mut engine.voices {
+            let mut lfo = Lfo::new(sample_rate);
+            lfo.set_frequency(6.5);
+            lfo.set_waveform(LfoWaveform::Square);
+            voice.graph.add_node_with_id(src, Box::new(lfo));
+            voice
+                .graph
+                .add_node_with_id(dst, Box::new(Lfo::new(sample_rate)));
+        }
 
-        let has_signal = left
-            .iter()
-            .chain(right.iter())
-            .any(|&sample| sample.abs() > 1e-6);
+        engine
+            .copy_node_settings(&src.to_string(), &dst.to_string())
+            .unwrap();
+        for voice in &engine.voices {
+            let node = voice.graph.get_node(dst).unwrap();
+            let lfo = node.as_any().downcast_ref::<Lfo>().unwrap();
+            assert_eq!(lfo.frequency(), 6.5);
+            assert_eq!(lfo.waveform(), LfoWaveform::Square);
+        }
 
-        assert!(has_signal, "expected audio output after gate activation");
+        // Copying onto a node of another type is rejected.
+        let mixer = engine.voices[0].graph.output_node.unwrap();
+        assert!(engine
+            .copy_node_settings(&src.to_string(), &mixer.to_string())
+            .is_err());
     }
 }
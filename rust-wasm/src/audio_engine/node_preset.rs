@@ -0,0 +1,314 @@
+// src/audio_engine/node_preset.rs
+//
+// Single-node presets: the settings of one envelope, LFO, filter or effect as
+// a small JSON snippet, e.g.
+//
+//   {"kind":"envelope","settings":{"attack":0.001,"decay":0.3,...}}
+//
+// The settings use the same shape as the node's entry in a patch's
+// `synthState`, so importing a preset goes through the regular patch state
+// path. Node ids inside the settings are ignored; the preset is applied to the
+// node it is imported into.
+//
+// Effect presets can also be morphed into one another and randomized, both
+// working on the settings JSON so new preset fields are covered without extra
+// code.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::patch::{
+    BitcrusherState, DelayState, FilterState, LfoState, ReverbState, SaturationState, SynthState,
+};
+use super::patch_loader::filter_type_to_i32;
+use crate::nodes::{
+    Bitcrusher, Delay, Envelope, EnvelopeConfig, FilterCollection, Freeverb, Lfo, Saturation,
+};
+use crate::traits::AudioNode;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "settings", rename_all = "camelCase")]
+pub enum NodePreset {
+    Envelope(EnvelopeConfig),
+    Lfo(LfoState),
+    Filter(FilterState),
+    Delay(DelayState),
+    Reverb(ReverbState),
+    Saturation(SaturationState),
+    Bitcrusher(BitcrusherState),
+}
+
+impl NodePreset {
+    /// Captures the settings of `node`, or `None` if its type has no presets.
+    pub fn from_node(node: &dyn AudioNode) -> Option<Self> {
+        let any = node.as_any();
+        if let Some(envelope) = any.downcast_ref::<Envelope>() {
+            return Some(Self::Envelope(envelope.config().clone()));
+        }
+        if let Some(lfo) = any.downcast_ref::<Lfo>() {
+            return Some(Self::Lfo(LfoState {
+                lfo_id: String::new(),
+                frequency: lfo.frequency(),
+                phase_offset: lfo.phase_offset(),
+                waveform: lfo.waveform().to_u8(),
+                use_absolute: lfo.use_absolute(),
+                use_normalized: lfo.use_normalized(),
+                trigger_mode: lfo.retrigger_mode.to_u8(),
+                gain: lfo.gain(),
+                active: lfo.is_active(),
+                loop_mode: lfo.loop_mode() as usize,
+                loop_start: lfo.loop_start(),
+                loop_end: lfo.loop_end(),
+            }));
+        }
+        if let Some(filter) = any.downcast_ref::<FilterCollection>() {
+            return Some(Self::Filter(FilterState {
+                id: String::new(),
+                cutoff: filter.cutoff(),
+                resonance: filter.resonance(),
+                key_tracking: filter.keyboard_tracking_sensitivity(),
+                comb_frequency: filter.comb_target_frequency(),
+                comb_dampening: filter.comb_dampening(),
+                oversampling: filter.oversampling_factor() as u32,
+                // `update_filters` maps the normalised gain to -12..12 dB.
+                gain: (filter.gain_db() + 12.0) / 24.0,
+                filter_type: filter_type_to_i32(filter.filter_type()),
+                filter_slope: filter.filter_slope(),
+                active: filter.is_active(),
+                auto_gain: filter.auto_gain(),
+                cutoff_mod_octaves: filter.cutoff_mod_octaves(),
+                double_precision_feedback: filter.double_precision_feedback(),
+            }));
+        }
+        if let Some(delay) = any.downcast_ref::<Delay>() {
+            return Some(Self::Delay(DelayState {
+                id: String::new(),
+                delay_ms: delay.delay_ms(),
+                feedback: delay.feedback(),
+                wet_mix: delay.mix(),
+                active: delay.is_active(),
+                ducking: delay.ducking(),
+                sync_beats: delay.sync_beats(),
+            }));
+        }
+        if let Some(reverb) = any.downcast_ref::<Freeverb>() {
+            return Some(Self::Reverb(ReverbState {
+                id: String::new(),
+                active: reverb.is_active(),
+                room_size: reverb.room_size(),
+                damp: reverb.damp(),
+                wet: reverb.wet(),
+                dry: reverb.dry(),
+                width: reverb.width(),
+                gate: reverb.gate_enabled(),
+            }));
+        }
+        if let Some(saturation) = any.downcast_ref::<Saturation>() {
+            return Some(Self::Saturation(SaturationState {
+                id: String::new(),
+                active: saturation.is_active(),
+                drive: saturation.drive(),
+                mix: saturation.mix(),
+                character: saturation.character(),
+                pre_tilt: saturation.pre_tilt(),
+                post_tilt: saturation.post_tilt(),
+                auto_gain: saturation.auto_gain(),
+            }));
+        }
+        if let Some(bitcrusher) = any.downcast_ref::<Bitcrusher>() {
+            return Some(Self::Bitcrusher(BitcrusherState {
+                id: String::new(),
+                active: bitcrusher.is_active(),
+                bits: bitcrusher.bits(),
+                downsample_factor: bitcrusher.downsample_factor(),
+                mix: bitcrusher.mix(),
+            }));
+        }
+        None
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| format!("Invalid node preset: {}", e))
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| format!("Failed to serialize node preset: {}", e))
+    }
+
+    /// Whether the preset can be applied to `node`.
+    pub fn fits(&self, node: &dyn AudioNode) -> bool {
+        Self::from_node(node)
+            .is_some_and(|current| std::mem::discriminant(&current) == std::mem::discriminant(self))
+    }
+
+    /// The settings `t` (0..1) of the way from `a` to `b`, two presets of the
+    /// same kind. Numbers are interpolated, whole numbers rounded; switches and
+    /// modes flip to `b` halfway.
+    pub fn morph(a: &NodePreset, b: &NodePreset, t: f64) -> Result<Self, String> {
+        if std::mem::discriminant(a) != std::mem::discriminant(b) {
+            return Err("Can't morph between presets of different kinds".to_string());
+        }
+        let t = t.clamp(0.0, 1.0);
+        let mut json = Self::to_value(a)?;
+        let to = Self::to_value(b)?;
+        for (parameter, value) in Self::settings_mut(&mut json)? {
+            let target = &to["settings"][parameter.as_str()];
+            *value = match (value.as_f64(), target.as_f64()) {
+                (Some(from), Some(target)) => number_like(value, from + (target - from) * t),
+                _ if t < 0.5 => continue,
+                _ => target.clone(),
+            };
+        }
+        Self::from_value(json)
+    }
+
+    /// A copy with every effect setting that has a range (see
+    /// `parameter_range`) moved by up to `amount` (0..1) of that range.
+    /// `random` returns uniform values in 0..1.
+    pub fn randomized(&self, amount: f64, mut random: impl FnMut() -> f64) -> Result<Self, String> {
+        let amount = amount.clamp(0.0, 1.0);
+        let mut json = Self::to_value(self)?;
+        let kind = json["kind"].as_str().unwrap_or_default().to_string();
+        for (parameter, value) in Self::settings_mut(&mut json)? {
+            let range = parameter_range(&kind, parameter);
+            let (Some((min, max)), Some(current)) = (range, value.as_f64()) else {
+                continue;
+            };
+            let offset = (random() * 2.0 - 1.0) * amount * (max - min);
+            *value = number_like(value, (current + offset).clamp(min, max));
+        }
+        Self::from_value(json)
+    }
+
+    fn to_value(preset: &NodePreset) -> Result<Value, String> {
+        serde_json::to_value(preset).map_err(|e| format!("Failed to serialize node preset: {}", e))
+    }
+
+    fn from_value(json: Value) -> Result<Self, String> {
+        serde_json::from_value(json).map_err(|e| format!("Invalid node preset: {}", e))
+    }
+
+    fn settings_mut(json: &mut Value) -> Result<&mut serde_json::Map<String, Value>, String> {
+        json.get_mut("settings")
+            .and_then(Value::as_object_mut)
+            .ok_or_else(|| "Preset has no settings".to_string())
+    }
+
+    /// A synth state holding only this preset, keyed and tagged with `node_id`.
+    pub fn into_synth_state(self, node_id: &str) -> SynthState {
+        let id = node_id.to_string();
+        let mut state = SynthState::default();
+        match self {
+            Self::Envelope(config) => {
+                state.envelopes.insert(id, config);
+            }
+            Self::Lfo(mut lfo) => {
+                lfo.lfo_id = id.clone();
+                state.lfos.insert(id, lfo);
+            }
+            Self::Filter(mut filter) => {
+                filter.id = id.clone();
+                state.filters.insert(id, filter);
+            }
+            Self::Delay(mut delay) => {
+                delay.id = id.clone();
+                state.delays.insert(id, delay);
+            }
+            Self::Reverb(mut reverb) => {
+                reverb.id = id.clone();
+                state.reverbs.insert(id, reverb);
+            }
+            Self::Saturation(mut saturation) => {
+                saturation.id = id.clone();
+                state.saturations.insert(id, saturation);
+            }
+            Self::Bitcrusher(mut bitcrusher) => {
+                bitcrusher.id = id.clone();
+                state.bitcrushers.insert(id, bitcrusher);
+            }
+        }
+        state
+    }
+}
+
+/// The span randomization moves an effect setting within. Switches, modes
+/// and tempo sync have none and are left alone.
+fn parameter_range(kind: &str, parameter: &str) -> Option<(f64, f64)> {
+    let range = match (kind, parameter) {
+        ("delay", "delayMs") => (1.0, 2000.0),
+        ("delay", "feedback") => (0.0, 0.95),
+        ("delay", "wetMix" | "ducking") => (0.0, 1.0),
+        ("reverb", "room_size" | "damp" | "wet" | "dry" | "width") => (0.0, 1.0),
+        ("saturation", "drive") => (0.1, 10.0),
+        ("saturation", "mix") => (0.0, 1.0),
+        ("saturation", "preTilt" | "postTilt") => (-12.0, 12.0),
+        ("bitcrusher", "bits") => (1.0, 16.0),
+        ("bitcrusher", "downsampleFactor") => (1.0, 32.0),
+        ("bitcrusher", "mix") => (0.0, 1.0),
+        _ => return None,
+    };
+    Some(range)
+}
+
+/// `number` as JSON of the same kind as `like`: rounded if `like` is a whole
+/// number type.
+fn number_like(like: &Value, number: f64) -> Value {
+    if like.is_f64() {
+        serde_json::json!(number)
+    } else {
+        serde_json::json!(number.round() as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captures_and_retargets_node_settings() {
+        let mut lfo = Lfo::new(48000.0);
+        lfo.set_frequency(3.5);
+        lfo.set_loop_end(0.75);
+        let preset = NodePreset::from_node(&lfo).unwrap();
+        assert!(preset.fits(&lfo));
+        assert!(!preset.fits(&Bitcrusher::new(48000.0, 8, 2, 1.0)));
+
+        let json = preset.to_json().unwrap();
+        assert!(json.starts_with(r#"{"kind":"lfo","settings":{"#));
+        let state = NodePreset::from_json(&json)
+            .unwrap()
+            .into_synth_state("target");
+        let lfo_state = &state.lfos["target"];
+        assert_eq!(lfo_state.lfo_id, "target");
+        assert_eq!(lfo_state.frequency, 3.5);
+        assert_eq!(lfo_state.loop_end, 0.75);
+    }
+
+    #[test]
+    fn morphs_and_randomizes_effect_settings() {
+        let mut crusher = Bitcrusher::new(48000.0, 4, 2, 0.0);
+        let a = NodePreset::from_node(&crusher).unwrap();
+        crusher.set_bits(12);
+        crusher.set_mix(1.0);
+        crusher.set_active(false);
+        let b = NodePreset::from_node(&crusher).unwrap();
+
+        let NodePreset::Bitcrusher(middle) = NodePreset::morph(&a, &b, 0.25).unwrap() else {
+            panic!("morph changed the preset kind");
+        };
+        assert_eq!(middle.bits, 6);
+        assert!((middle.mix - 0.25).abs() < 1e-6);
+        assert!(middle.active);
+        let lfo = NodePreset::from_node(&Lfo::new(48000.0)).unwrap();
+        assert!(NodePreset::morph(&a, &lfo, 0.5).is_err());
+
+        let NodePreset::Bitcrusher(wild) = b.randomized(1.0, || 1.0).unwrap() else {
+            panic!("randomizing changed the preset kind");
+        };
+        assert_eq!((wild.bits, wild.mix), (16, 1.0));
+        let NodePreset::Bitcrusher(same) = b.randomized(0.0, || 0.0).unwrap() else {
+            panic!("randomizing changed the preset kind");
+        };
+        assert_eq!((same.bits, same.downsample_factor, same.mix), (12, 2, 1.0));
+    }
+}
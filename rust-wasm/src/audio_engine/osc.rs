@@ -0,0 +1,414 @@
+// src/audio_engine/osc.rs
+//
+// OSC remote control for the native engine (feature `osc`), so TouchOSC and
+// similar controllers can play and tweak the synth without MIDI. A background
+// thread listens on a UDP socket and decodes packets into `OscCommand`s; the
+// audio thread applies them with `OscServer::apply_pending` between blocks.
+//
+// Addresses:
+//
+//   /note/on <note> [velocity]     velocity 0..1 (or 0..127 as an int)
+//   /note/off <note>
+//   /notes/off
+//   /macro/<index> <value>
+//   /tempo <bpm>
+//   /node/<id>/preset <json>       a snippet from `export_node_preset`
+//   /node/<id>/<parameter> <value> one field of the node's preset settings
+
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use super::native::AudioEngine;
+
+/// Largest packet the server reads; bigger datagrams are truncated.
+const MAX_PACKET_SIZE: usize = 8192;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscArg {
+    Int(i64),
+    Float(f64),
+    String(String),
+    Bool(bool),
+}
+
+impl OscArg {
+    fn as_f64(&self) -> Option<f64> {
+        match *self {
+            OscArg::Int(value) => Some(value as f64),
+            OscArg::Float(value) => Some(value),
+            OscArg::Bool(value) => Some(if value { 1.0 } else { 0.0 }),
+            OscArg::String(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OscMessage {
+    pub address: String,
+    pub args: Vec<OscArg>,
+}
+
+/// Decodes a packet into its messages; bundles are flattened in order and
+/// their time tags ignored.
+pub fn decode_packet(bytes: &[u8]) -> Result<Vec<OscMessage>, String> {
+    let mut messages = Vec::new();
+    decode_into(bytes, &mut messages)?;
+    Ok(messages)
+}
+
+fn decode_into(bytes: &[u8], messages: &mut Vec<OscMessage>) -> Result<(), String> {
+    let mut reader = Reader { bytes, pos: 0 };
+    let address = reader.string()?;
+    if address == "#bundle" {
+        reader.take(8)?; // Time tag
+        while !reader.is_empty() {
+            let len = reader.int()?;
+            let len = usize::try_from(len).map_err(|_| "Negative OSC bundle element size")?;
+            decode_into(reader.take(len)?, messages)?;
+        }
+        return Ok(());
+    }
+    if !address.starts_with('/') {
+        return Err(format!("Invalid OSC address: {}", address));
+    }
+    // Messages from old senders may leave out the type tags entirely.
+    let tags = if reader.is_empty() {
+        String::from(",")
+    } else {
+        reader.string()?
+    };
+    let tags = tags
+        .strip_prefix(',')
+        .ok_or_else(|| format!("Invalid OSC type tags: {}", tags))?;
+    let mut args = Vec::with_capacity(tags.len());
+    for tag in tags.chars() {
+        args.push(match tag {
+            'i' => OscArg::Int(i64::from(reader.int()?)),
+            'h' => OscArg::Int(i64::from_be_bytes(reader.array()?)),
+            'f' => OscArg::Float(f64::from(f32::from_be_bytes(reader.array()?))),
+            'd' => OscArg::Float(f64::from_be_bytes(reader.array()?)),
+            's' | 'S' => OscArg::String(reader.string()?),
+            'T' => OscArg::Bool(true),
+            'F' => OscArg::Bool(false),
+            _ => return Err(format!("Unsupported OSC type tag '{}'", tag)),
+        });
+    }
+    messages.push(OscMessage { address, args });
+    Ok(())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or("Unexpected end of OSC packet")?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn int(&mut self) -> Result<i32, String> {
+        Ok(i32::from_be_bytes(self.array()?))
+    }
+
+    /// NUL-terminated string padded to four bytes.
+    fn string(&mut self) -> Result<String, String> {
+        let rest = &self.bytes[self.pos.min(self.bytes.len())..];
+        let len = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or("Unterminated OSC string")?;
+        let text = std::str::from_utf8(&rest[..len])
+            .map_err(|e| format!("OSC string is not UTF-8: {}", e))?
+            .to_string();
+        self.take((len + 4) & !3)?;
+        Ok(text)
+    }
+}
+
+/// An engine operation decoded from an OSC message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscCommand {
+    NoteOn {
+        note: u8,
+        velocity: f32,
+    },
+    NoteOff {
+        note: u8,
+    },
+    AllNotesOff,
+    Macro {
+        index: usize,
+        value: f32,
+    },
+    Tempo {
+        bpm: f64,
+    },
+    NodePreset {
+        node_id: String,
+        json: String,
+    },
+    NodeParameter {
+        node_id: String,
+        parameter: String,
+        value: f64,
+    },
+}
+
+impl OscCommand {
+    pub fn from_message(message: &OscMessage) -> Result<Self, String> {
+        let number = |index: usize| {
+            message
+                .args
+                .get(index)
+                .and_then(OscArg::as_f64)
+                .ok_or_else(|| format!("{} expects a number argument", message.address))
+        };
+        let note = || number(0).map(|note| note.clamp(0.0, 127.0) as u8);
+        let parts: Vec<&str> = message.address.split('/').skip(1).collect();
+        match parts.as_slice() {
+            ["note", "on"] => {
+                let velocity = match message.args.get(1) {
+                    None => 1.0,
+                    Some(OscArg::Int(velocity)) => *velocity as f32 / 127.0,
+                    Some(_) => number(1)? as f32,
+                };
+                Ok(OscCommand::NoteOn {
+                    note: note()?,
+                    velocity: velocity.clamp(0.0, 1.0),
+                })
+            }
+            ["note", "off"] => Ok(OscCommand::NoteOff { note: note()? }),
+            ["notes", "off"] => Ok(OscCommand::AllNotesOff),
+            ["macro", index] => Ok(OscCommand::Macro {
+                index: index
+                    .parse()
+                    .map_err(|_| format!("Invalid macro index: {}", index))?,
+                value: number(0)? as f32,
+            }),
+            ["tempo"] => Ok(OscCommand::Tempo { bpm: number(0)? }),
+            ["node", node_id, "preset"] => match message.args.first() {
+                Some(OscArg::String(json)) => Ok(OscCommand::NodePreset {
+                    node_id: node_id.to_string(),
+                    json: json.clone(),
+                }),
+                _ => Err(format!("{} expects a JSON string", message.address)),
+            },
+            ["node", node_id, parameter] => Ok(OscCommand::NodeParameter {
+                node_id: node_id.to_string(),
+                parameter: parameter.to_string(),
+                value: number(0)?,
+            }),
+            _ => Err(format!("Unknown OSC address: {}", message.address)),
+        }
+    }
+
+    pub fn apply(self, engine: &mut AudioEngine) -> Result<(), String> {
+        match self {
+            OscCommand::NoteOn { note, velocity } => {
+                engine.note_on(note, velocity);
+            }
+            OscCommand::NoteOff { note } => {
+                engine.note_off(note);
+            }
+            OscCommand::AllNotesOff => engine.all_notes_off(),
+            OscCommand::Macro { index, value } => {
+                engine.set_macro_value(None, index, value, None)?;
+            }
+            OscCommand::Tempo { bpm } => engine.set_transport_tempo(bpm),
+            OscCommand::NodePreset { node_id, json } => {
+                engine.import_node_preset(&node_id, &json)?;
+            }
+            OscCommand::NodeParameter {
+                node_id,
+                parameter,
+                value,
+            } => engine.set_node_parameter(&node_id, &parameter, value)?,
+        }
+        Ok(())
+    }
+}
+
+/// Receives OSC over UDP on a background thread. The thread stops when the
+/// server is dropped and the next packet arrives.
+pub struct OscServer {
+    local_addr: SocketAddr,
+    commands: Receiver<OscCommand>,
+}
+
+impl OscServer {
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        let local_addr = socket.local_addr()?;
+        let (sender, commands) = mpsc::channel();
+        thread::Builder::new()
+            .name("osc-server".to_string())
+            .spawn(move || {
+                let mut buffer = [0u8; MAX_PACKET_SIZE];
+                loop {
+                    let len = match socket.recv(&mut buffer) {
+                        Ok(len) => len,
+                        Err(err) => {
+                            eprintln!("OSC receive failed: {}", err);
+                            continue;
+                        }
+                    };
+                    let messages = match decode_packet(&buffer[..len]) {
+                        Ok(messages) => messages,
+                        Err(err) => {
+                            eprintln!("Ignoring OSC packet: {}", err);
+                            continue;
+                        }
+                    };
+                    for message in &messages {
+                        match OscCommand::from_message(message) {
+                            Ok(command) => {
+                                if sender.send(command).is_err() {
+                                    return;
+                                }
+                            }
+                            Err(err) => eprintln!("Ignoring OSC message: {}", err),
+                        }
+                    }
+                }
+            })?;
+        Ok(Self {
+            local_addr,
+            commands,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Applies the commands received since the last call. Failing commands
+    /// are reported and skipped. Returns how many were applied.
+    pub fn apply_pending(&self, engine: &mut AudioEngine) -> usize {
+        let mut applied = 0;
+        for command in self.commands.try_iter() {
+            match command.apply(engine) {
+                Ok(()) => applied += 1,
+                Err(err) => eprintln!("Failed to apply OSC command: {}", err),
+            }
+        }
+        applied
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn padded(text: &str) -> Vec<u8> {
+        let mut bytes = text.as_bytes().to_vec();
+        bytes.resize((text.len() + 4) & !3, 0);
+        bytes
+    }
+
+    fn message(address: &str, tags: &str, args: &[u8]) -> Vec<u8> {
+        let mut bytes = padded(address);
+        bytes.extend(padded(tags));
+        bytes.extend_from_slice(args);
+        bytes
+    }
+
+    #[test]
+    fn decodes_bundles_into_commands() {
+        let note_on = message(
+            "/note/on",
+            ",if",
+            &[[0, 0, 0, 60], 0.5f32.to_be_bytes()].concat(),
+        );
+        let cutoff = message("/node/3/cutoff", ",d", &1200.0f64.to_be_bytes());
+        let mut bundle = padded("#bundle");
+        bundle.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+        for element in [&note_on, &cutoff] {
+            bundle.extend_from_slice(&(element.len() as i32).to_be_bytes());
+            bundle.extend_from_slice(element);
+        }
+
+        let messages = decode_packet(&bundle).unwrap();
+        let commands: Vec<OscCommand> = messages
+            .iter()
+            .map(|message| OscCommand::from_message(message).unwrap())
+            .collect();
+        assert_eq!(
+            commands,
+            vec![
+                OscCommand::NoteOn {
+                    note: 60,
+                    velocity: 0.5
+                },
+                OscCommand::NodeParameter {
+                    node_id: "3".to_string(),
+                    parameter: "cutoff".to_string(),
+                    value: 1200.0
+                },
+            ]
+        );
+
+        let macro_message = &decode_packet(&message("/macro/2", ",T", &[])).unwrap()[0];
+        assert_eq!(
+            OscCommand::from_message(macro_message),
+            Ok(OscCommand::Macro {
+                index: 2,
+                value: 1.0
+            })
+        );
+        assert!(decode_packet(&message("/note/on", ",i", &[0, 0])).is_err());
+        let unknown = &decode_packet(&message("/unknown", ",", &[])).unwrap()[0];
+        assert!(OscCommand::from_message(unknown).is_err());
+    }
+
+    #[test]
+    fn drives_an_engine_over_udp() {
+        let server = OscServer::bind("127.0.0.1:0").unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client
+            .send_to(
+                &message("/note/on", ",i", &[0, 0, 0, 64]),
+                server.local_addr(),
+            )
+            .unwrap();
+        client
+            .send_to(
+                &message("/tempo", ",f", &90.0f32.to_be_bytes()),
+                server.local_addr(),
+            )
+            .unwrap();
+
+        let mut engine = AudioEngine::new(48_000.0, 2);
+        engine.init(48_000.0, 2);
+        let mut applied = 0;
+        for _ in 0..200 {
+            applied += server.apply_pending(&mut engine);
+            if applied == 2 {
+                break;
+            }
+            thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert_eq!(applied, 2);
+        assert!(engine.note_off(64));
+        assert_eq!(engine.transport_tempo(), 90.0);
+    }
+}
@@ -0,0 +1,212 @@
+// src/audio_engine/output_stage.rs
+//
+// Final output stage shared by the wasm and native engines. After the effect
+// stack and master gain the mix is hard clamped to full scale, left to the
+// safety limiter in the effect stack (the default), or passed on as raw float.
+// Outside `SafetyLimiter` mode the stack's limiter effects are bypassed so they
+// don't colour the signal. Hosts that need fixed-point audio can also request
+// 16 or 24-bit integer buffers, quantized with TPDF dither, next to the float
+// output.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum OutputMode {
+    /// Samples are clamped to [-1, 1]; the safety limiter is bypassed.
+    HardClamp = 0,
+    /// The limiter in the effect stack keeps peaks below full scale.
+    #[default]
+    SafetyLimiter = 1,
+    /// No limiting or clamping; samples may exceed full scale.
+    RawFloat = 2,
+}
+
+impl OutputMode {
+    /// Whether limiter effects in the effect stack are skipped in this mode.
+    pub fn bypasses_limiter(self) -> bool {
+        self != OutputMode::SafetyLimiter
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum OutputFormat {
+    /// Float output only.
+    #[default]
+    Float = 0,
+    /// Dithered 16-bit integer buffers in addition to the float output.
+    Int16 = 1,
+    /// Dithered 24-bit integer buffers (stored in `i32`) in addition to the
+    /// float output.
+    Int24 = 2,
+}
+
+impl OutputFormat {
+    /// Largest positive integer sample, or `None` for float output.
+    fn full_scale(self) -> Option<f32> {
+        match self {
+            OutputFormat::Float => None,
+            OutputFormat::Int16 => Some(i16::MAX as f32),
+            OutputFormat::Int24 => Some(8_388_607.0),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct OutputStage {
+    mode: OutputMode,
+    format: OutputFormat,
+    dither_state: u32,
+    int_left: Vec<i32>,
+    int_right: Vec<i32>,
+}
+
+impl Default for OutputStage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OutputStage {
+    pub fn new() -> Self {
+        Self {
+            mode: OutputMode::default(),
+            format: OutputFormat::default(),
+            dither_state: 0x9E37_79B9,
+            int_left: Vec::new(),
+            int_right: Vec::new(),
+        }
+    }
+
+    pub fn set_mode(&mut self, mode: OutputMode) {
+        self.mode = mode;
+    }
+
+    pub fn mode(&self) -> OutputMode {
+        self.mode
+    }
+
+    pub fn set_format(&mut self, format: OutputFormat) {
+        self.format = format;
+        if format == OutputFormat::Float {
+            self.int_left.clear();
+            self.int_right.clear();
+        }
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    /// Applies the output mode to the final mix in place and refreshes the
+    /// integer buffers when an integer format is selected.
+    pub fn process(&mut self, left: &mut [f32], right: &mut [f32]) {
+        self.apply_mode(left);
+        self.apply_mode(right);
+
+        if let Some(full_scale) = self.format.full_scale() {
+            quantize(left, &mut self.int_left, full_scale, &mut self.dither_state);
+            quantize(
+                right,
+                &mut self.int_right,
+                full_scale,
+                &mut self.dither_state,
+            );
+        }
+    }
+
+    /// Applies the output mode to one channel without touching the integer
+    /// buffers, for channels beyond the stereo pair.
+    pub fn apply_mode(&self, samples: &mut [f32]) {
+        if self.mode == OutputMode::HardClamp {
+            for sample in samples.iter_mut() {
+                *sample = sample.clamp(-1.0, 1.0);
+            }
+        }
+    }
+
+    /// Left channel of the last block as integers; empty for float output.
+    pub fn int_left(&self) -> &[i32] {
+        &self.int_left
+    }
+
+    /// Right channel of the last block as integers; empty for float output.
+    pub fn int_right(&self) -> &[i32] {
+        &self.int_right
+    }
+}
+
+/// Scales `samples` to integers with triangular (TPDF) dither of +/-1 LSB,
+/// saturating at the format's range.
+fn quantize(samples: &[f32], out: &mut Vec<i32>, full_scale: f32, state: &mut u32) {
+    out.clear();
+    out.extend(samples.iter().map(|&sample| {
+        let dither = next_uniform(state) + next_uniform(state);
+        (sample * full_scale + dither)
+            .round()
+            .clamp(-full_scale - 1.0, full_scale) as i32
+    }));
+}
+
+/// Xorshift32 noise in [-0.5, 0.5).
+fn next_uniform(state: &mut u32) -> f32 {
+    let mut x = *state;
+    x ^= x << 13;
+    x ^= x >> 17;
+    x ^= x << 5;
+    *state = x;
+    (x >> 8) as f32 / (1u32 << 24) as f32 - 0.5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hard_clamp_limits_to_full_scale_and_raw_passes_through() {
+        let mut stage = OutputStage::new();
+        stage.set_mode(OutputMode::HardClamp);
+        let mut left = [1.5, -2.0, 0.25];
+        let mut right = [0.5, 3.0, -0.75];
+        stage.process(&mut left, &mut right);
+        assert_eq!(left, [1.0, -1.0, 0.25]);
+        assert_eq!(right, [0.5, 1.0, -0.75]);
+
+        stage.set_mode(OutputMode::RawFloat);
+        let mut left = [1.5];
+        let mut right = [-2.0];
+        stage.process(&mut left, &mut right);
+        assert_eq!((left[0], right[0]), (1.5, -2.0));
+        assert!(stage.int_left().is_empty());
+    }
+
+    #[test]
+    fn integer_formats_are_dithered_within_one_lsb_and_saturate() {
+        let mut stage = OutputStage::new();
+        stage.set_format(OutputFormat::Int16);
+        let mut left = vec![0.5; 256];
+        let mut right = vec![2.0; 256];
+        stage.process(&mut left, &mut right);
+
+        let target = (0.5 * i16::MAX as f32).round() as i32;
+        assert_eq!(stage.int_left().len(), 256);
+        assert!(stage.int_left().iter().all(|&s| (s - target).abs() <= 1));
+        // The dither actually varies the output.
+        assert!(stage.int_left().iter().any(|&s| s != stage.int_left()[0]));
+        assert!(stage.int_right().iter().all(|&s| s == i16::MAX as i32));
+
+        stage.set_format(OutputFormat::Int24);
+        let mut left = vec![-4.0; 8];
+        let mut right = vec![0.0; 8];
+        stage.process(&mut left, &mut right);
+        assert!(stage.int_left().iter().all(|&s| s == -8_388_608));
+        assert!(stage.int_right().iter().all(|&s| s.abs() <= 1));
+
+        stage.set_format(OutputFormat::Float);
+        assert!(stage.int_left().is_empty());
+    }
+}
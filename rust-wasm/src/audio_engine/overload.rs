@@ -0,0 +1,212 @@
+// src/audio_engine/overload.rs
+//
+// CPU overload protection shared by the wasm and native engines. The engine
+// feeds every CPU measurement (see `get_cpu_usage`) into `OverloadProtection`,
+// which decides how far to degrade: first the whole synth drops to
+// `QualityMode::Eco` (no oversampling, shortened convolution tails, decimated
+// LFOs), then quiet released voices are culled for as long as the overload
+// lasts. Once usage has stayed below the recovery threshold for a while the
+// requested quality mode is restored. Every step is queued as an
+// `OverloadEvent` for the host to drain.
+
+use serde::Serialize;
+
+use crate::traits::QualityMode;
+
+pub const DEFAULT_OVERLOAD_THRESHOLD: f32 = 85.0;
+pub const DEFAULT_RECOVERY_THRESHOLD: f32 = 60.0;
+/// Consecutive calm CPU measurements (100 ms each) before quality is restored.
+const RECOVERY_MEASUREMENTS: u32 = 20;
+/// Released voices below this output RMS (about -40 dB) may be culled.
+pub const CULL_RMS_THRESHOLD: f32 = 0.01;
+/// Events kept when the host never drains the queue.
+const MAX_PENDING_EVENTS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum OverloadAction {
+    /// Quality was lowered to `QualityMode::Eco`.
+    ReducedQuality,
+    /// Quiet released voices were silenced.
+    CulledVoices { count: usize },
+    /// The requested quality mode was restored after the overload passed.
+    RestoredQuality,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverloadEvent {
+    #[serde(flatten)]
+    pub action: OverloadAction,
+    /// CPU usage (%) that triggered the action.
+    pub cpu_usage: f32,
+}
+
+/// What the engine should do after a CPU measurement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverloadResponse {
+    None,
+    ReduceQuality,
+    CullVoices,
+    RestoreQuality,
+}
+
+#[derive(Debug)]
+pub struct OverloadProtection {
+    enabled: bool,
+    threshold: f32,
+    recovery_threshold: f32,
+    degraded: bool,
+    calm_measurements: u32,
+    events: Vec<OverloadEvent>,
+}
+
+impl Default for OverloadProtection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OverloadProtection {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            threshold: DEFAULT_OVERLOAD_THRESHOLD,
+            recovery_threshold: DEFAULT_RECOVERY_THRESHOLD,
+            degraded: false,
+            calm_measurements: 0,
+            events: Vec::new(),
+        }
+    }
+
+    /// Enables or disables protection. Disabling while degraded asks the engine
+    /// to restore quality.
+    pub fn set_enabled(&mut self, enabled: bool) -> OverloadResponse {
+        self.enabled = enabled;
+        self.calm_measurements = 0;
+        if !enabled && self.degraded {
+            self.degraded = false;
+            return OverloadResponse::RestoreQuality;
+        }
+        OverloadResponse::None
+    }
+
+    /// Sets the CPU usage (%) that triggers degradation and the level it must
+    /// fall below before quality is restored. The recovery threshold is kept
+    /// below the trigger.
+    pub fn set_thresholds(&mut self, threshold: f32, recovery_threshold: f32) {
+        self.threshold = threshold.clamp(1.0, 100.0);
+        self.recovery_threshold = recovery_threshold.clamp(0.0, self.threshold);
+    }
+
+    /// True while quality is held at Eco because of an overload.
+    pub fn is_degraded(&self) -> bool {
+        self.degraded
+    }
+
+    /// Feeds one CPU measurement. `quality` is the mode currently in effect.
+    pub fn evaluate(&mut self, cpu_usage: f32, quality: QualityMode) -> OverloadResponse {
+        if !self.enabled {
+            return OverloadResponse::None;
+        }
+
+        if cpu_usage > self.threshold {
+            self.calm_measurements = 0;
+            if !self.degraded && quality != QualityMode::Eco {
+                self.degraded = true;
+                return OverloadResponse::ReduceQuality;
+            }
+            return OverloadResponse::CullVoices;
+        }
+
+        if self.degraded && cpu_usage < self.recovery_threshold {
+            self.calm_measurements += 1;
+            if self.calm_measurements >= RECOVERY_MEASUREMENTS {
+                self.degraded = false;
+                self.calm_measurements = 0;
+                return OverloadResponse::RestoreQuality;
+            }
+        } else {
+            self.calm_measurements = 0;
+        }
+        OverloadResponse::None
+    }
+
+    pub fn record(&mut self, action: OverloadAction, cpu_usage: f32) {
+        if self.events.len() >= MAX_PENDING_EVENTS {
+            self.events.remove(0);
+        }
+        self.events.push(OverloadEvent { action, cpu_usage });
+    }
+
+    /// Drains the queued events, oldest first.
+    pub fn take_events(&mut self) -> Vec<OverloadEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escalates_from_eco_to_culling_and_recovers() {
+        let mut protection = OverloadProtection::new();
+        assert_eq!(
+            protection.evaluate(99.0, QualityMode::Normal),
+            OverloadResponse::None
+        );
+
+        protection.set_enabled(true);
+        assert_eq!(
+            protection.evaluate(95.0, QualityMode::Normal),
+            OverloadResponse::ReduceQuality
+        );
+        assert_eq!(
+            protection.evaluate(95.0, QualityMode::Eco),
+            OverloadResponse::CullVoices
+        );
+
+        // Between the thresholds nothing happens and the calm count restarts.
+        for _ in 0..RECOVERY_MEASUREMENTS - 1 {
+            assert_eq!(
+                protection.evaluate(10.0, QualityMode::Eco),
+                OverloadResponse::None
+            );
+        }
+        protection.evaluate(70.0, QualityMode::Eco);
+        for _ in 0..RECOVERY_MEASUREMENTS - 1 {
+            protection.evaluate(10.0, QualityMode::Eco);
+        }
+        assert!(protection.is_degraded());
+        assert_eq!(
+            protection.evaluate(10.0, QualityMode::Eco),
+            OverloadResponse::RestoreQuality
+        );
+        assert!(!protection.is_degraded());
+    }
+
+    #[test]
+    fn disabling_while_degraded_restores_quality() {
+        let mut protection = OverloadProtection::new();
+        protection.set_enabled(true);
+        protection.evaluate(95.0, QualityMode::High);
+        assert_eq!(
+            protection.set_enabled(false),
+            OverloadResponse::RestoreQuality
+        );
+        assert_eq!(protection.set_enabled(false), OverloadResponse::None);
+    }
+
+    #[test]
+    fn events_serialize_flat_and_drain() {
+        let mut protection = OverloadProtection::new();
+        protection.record(OverloadAction::CulledVoices { count: 3 }, 91.5);
+        let json = serde_json::to_string(&protection.take_events()).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"action":"culledVoices","count":3,"cpuUsage":91.5}]"#
+        );
+        assert!(protection.take_events().is_empty());
+    }
+}
@@ -0,0 +1,166 @@
+// src/audio_engine/param_lock.rs
+//
+// Parameter locks: engine-level settings the host can exclude from presets.
+// The engine remembers the last value set through its own API for every
+// lockable parameter. While a parameter is locked, patch loads skip the value
+// stored in the patch and the remembered value is restored once the voices have
+// been rebuilt, so hosts don't have to re-send it after every preset change.
+// Master gain is not listed because it is passed to `process_audio` on every
+// block and never stored in the engine.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LockableParameter {
+    /// Master transpose and fine tune (`set_master_tuning`).
+    MasterTuning = 0,
+    /// Per-voice detune offsets (`set_voice_detune`).
+    VoiceDetune = 1,
+    /// Global parameter smoothing time (`set_parameter_smoothing`).
+    ParameterSmoothing = 2,
+    /// Engine-wide macro values (`set_macro_value` without a voice index).
+    MacroValues = 3,
+}
+
+impl LockableParameter {
+    fn mask(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ParameterLocks {
+    locked: u8,
+    master_tuning: Option<(f32, f32)>,
+    voice_detune: Vec<Option<f32>>,
+    smoothing_ms: Option<f32>,
+    macro_values: Vec<Option<f32>>,
+}
+
+impl ParameterLocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_locked(&mut self, parameter: LockableParameter, locked: bool) {
+        if locked {
+            self.locked |= parameter.mask();
+        } else {
+            self.locked &= !parameter.mask();
+        }
+    }
+
+    pub fn is_locked(&self, parameter: LockableParameter) -> bool {
+        self.locked & parameter.mask() != 0
+    }
+
+    pub fn remember_master_tuning(&mut self, transpose: f32, fine: f32) {
+        self.master_tuning = Some((transpose, fine));
+    }
+
+    pub fn remember_voice_detune(&mut self, voice_index: usize, cents: f32) {
+        remember_indexed(&mut self.voice_detune, voice_index, cents);
+    }
+
+    pub fn remember_smoothing(&mut self, time_ms: f32) {
+        self.smoothing_ms = Some(time_ms);
+    }
+
+    pub fn remember_macro_value(&mut self, macro_index: usize, value: f32) {
+        remember_indexed(&mut self.macro_values, macro_index, value);
+    }
+
+    /// Last master tuning set through the engine, locked or not.
+    pub fn master_tuning(&self) -> Option<(f32, f32)> {
+        self.master_tuning
+    }
+
+    /// Last detune set per voice, locked or not.
+    pub fn voice_detune(&self) -> &[Option<f32>] {
+        &self.voice_detune
+    }
+
+    pub fn smoothing(&self) -> Option<f32> {
+        self.smoothing_ms
+    }
+
+    /// Last engine-wide value set per macro, locked or not.
+    pub fn macro_values(&self) -> &[Option<f32>] {
+        &self.macro_values
+    }
+
+    /// Master tuning to restore after a patch load, if locked and ever set.
+    pub fn locked_master_tuning(&self) -> Option<(f32, f32)> {
+        self.master_tuning
+            .filter(|_| self.is_locked(LockableParameter::MasterTuning))
+    }
+
+    /// Voice detune offsets to restore as `(voice_index, cents)`.
+    pub fn locked_voice_detune(&self) -> impl Iterator<Item = (usize, f32)> + '_ {
+        locked_indexed(self, LockableParameter::VoiceDetune, &self.voice_detune)
+    }
+
+    pub fn locked_smoothing(&self) -> Option<f32> {
+        self.smoothing_ms
+            .filter(|_| self.is_locked(LockableParameter::ParameterSmoothing))
+    }
+
+    /// Macro values to restore as `(macro_index, value)`.
+    pub fn locked_macro_values(&self) -> impl Iterator<Item = (usize, f32)> + '_ {
+        locked_indexed(self, LockableParameter::MacroValues, &self.macro_values)
+    }
+}
+
+fn remember_indexed(values: &mut Vec<Option<f32>>, index: usize, value: f32) {
+    if values.len() <= index {
+        values.resize(index + 1, None);
+    }
+    values[index] = Some(value);
+}
+
+fn locked_indexed<'a>(
+    locks: &ParameterLocks,
+    parameter: LockableParameter,
+    values: &'a [Option<f32>],
+) -> impl Iterator<Item = (usize, f32)> + 'a {
+    let values = if locks.is_locked(parameter) {
+        values
+    } else {
+        &[]
+    };
+    values
+        .iter()
+        .enumerate()
+        .filter_map(|(index, value)| value.map(|v| (index, v)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_locked_parameters_are_restored() {
+        let mut locks = ParameterLocks::new();
+        locks.remember_master_tuning(-12.0, 5.0);
+        locks.remember_voice_detune(2, 7.5);
+        locks.remember_smoothing(20.0);
+        assert_eq!(locks.locked_master_tuning(), None);
+        assert_eq!(locks.locked_voice_detune().count(), 0);
+
+        locks.set_locked(LockableParameter::MasterTuning, true);
+        locks.set_locked(LockableParameter::VoiceDetune, true);
+        assert_eq!(locks.locked_master_tuning(), Some((-12.0, 5.0)));
+        assert_eq!(
+            locks.locked_voice_detune().collect::<Vec<_>>(),
+            vec![(2, 7.5)]
+        );
+        assert_eq!(locks.locked_smoothing(), None);
+
+        locks.set_locked(LockableParameter::MasterTuning, false);
+        assert!(!locks.is_locked(LockableParameter::MasterTuning));
+        assert!(locks.is_locked(LockableParameter::VoiceDetune));
+    }
+}
@@ -0,0 +1,275 @@
+// src/audio_engine/parts.rs
+//
+// Multi-timbral parts. Part 0 is the engine's main patch; parts 1.. each hold
+// the voices of another patch, built by loading that patch into a scratch
+// engine that shares the wavetable banks. The voices of all parts form one
+// pool as far as the per-voice parameter arrays are concerned: part 0 owns the
+// first indices and every loaded part follows in order (see `voice_range`).
+// Each part is mixed at its own level; `send` sets how much of it runs through
+// the shared effect stack, the remainder bypasses the effects dry.
+//
+// A part's key and velocity ranges form its zone. Zones of several parts may
+// overlap to layer them; `fade` ramps the gain over that many keys/velocity
+// steps inside each inner edge, so two zones overlapping by `fade` crossfade
+// with gains summing to one. The host asks `zone_gains` which parts play a note
+// and routes it to each with the returned gain.
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::voice::Voice;
+
+pub const MAX_PARTS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PartConfig {
+    /// MIDI channel (0-15) the part responds to; `None` responds to all.
+    pub channel: Option<u8>,
+    pub lo_key: u8,
+    pub hi_key: u8,
+    pub lo_vel: u8,
+    pub hi_vel: u8,
+    /// Crossfade width in keys and velocity steps at the zone's inner edges.
+    pub fade: u8,
+    pub level: f32,
+    /// Share of the part sent through the effect stack (0..1).
+    pub send: f32,
+}
+
+impl Default for PartConfig {
+    fn default() -> Self {
+        Self {
+            channel: None,
+            lo_key: 0,
+            hi_key: 127,
+            lo_vel: 0,
+            hi_vel: 127,
+            fade: 0,
+            level: 1.0,
+            send: 1.0,
+        }
+    }
+}
+
+impl PartConfig {
+    pub fn accepts(&self, channel: u8, note: u8) -> bool {
+        self.channel.is_none_or(|c| c == channel) && (self.lo_key..=self.hi_key).contains(&note)
+    }
+
+    /// Gain of a note inside the part's zone, 0 outside it. Edges at the ends
+    /// of the MIDI range are never faded.
+    pub fn zone_gain(&self, note: u8, velocity: u8) -> f32 {
+        if !(self.lo_key..=self.hi_key).contains(&note)
+            || !(self.lo_vel..=self.hi_vel).contains(&velocity)
+        {
+            return 0.0;
+        }
+        edge_fade(note, self.lo_key, self.hi_key, self.fade)
+            * edge_fade(velocity, self.lo_vel.max(1), self.hi_vel, self.fade)
+    }
+
+    /// Gains applied to the part's voices on the way into the effect stack and
+    /// the dry bus.
+    pub fn bus_gains(&self) -> (f32, f32) {
+        (self.level * self.send, self.level * (1.0 - self.send))
+    }
+}
+
+#[derive(Debug)]
+pub struct Parts {
+    configs: [PartConfig; MAX_PARTS],
+    /// Voices of parts 1..; an empty pool means the part has no patch loaded.
+    extra_voices: Vec<Vec<Voice>>,
+}
+
+impl Default for Parts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parts {
+    pub fn new() -> Self {
+        Self {
+            configs: [PartConfig::default(); MAX_PARTS],
+            extra_voices: (1..MAX_PARTS).map(|_| Vec::new()).collect(),
+        }
+    }
+
+    pub fn main_config(&self) -> &PartConfig {
+        &self.configs[0]
+    }
+
+    pub fn config(&self, part: usize) -> Option<&PartConfig> {
+        self.configs.get(part)
+    }
+
+    pub fn configs(&self) -> &[PartConfig] {
+        &self.configs
+    }
+
+    /// Replaces the configuration of the first `configs.len()` parts; extra
+    /// entries are ignored.
+    pub fn set_configs(&mut self, configs: &[PartConfig]) {
+        for (config, new) in self.configs.iter_mut().zip(configs) {
+            *config = *new;
+        }
+    }
+
+    pub fn config_mut(&mut self, part: usize) -> Result<&mut PartConfig, String> {
+        self.configs
+            .get_mut(part)
+            .ok_or_else(|| format!("Invalid part index: {}", part))
+    }
+
+    /// Installs the voices of a loaded part patch. Part 0 is the main patch and
+    /// can't be replaced here.
+    pub fn set_voices(&mut self, part: usize, voices: Vec<Voice>) -> Result<(), String> {
+        let pool = part
+            .checked_sub(1)
+            .and_then(|index| self.extra_voices.get_mut(index))
+            .ok_or_else(|| format!("Invalid part index for a part patch: {}", part))?;
+        *pool = voices;
+        Ok(())
+    }
+
+    fn voice_count(&self, part: usize, main_voices: usize) -> usize {
+        match part {
+            0 => main_voices,
+            _ => self.extra_voices.get(part - 1).map_or(0, Vec::len),
+        }
+    }
+
+    /// Indices of the part's voices in the per-voice parameter arrays, given
+    /// the number of voices in the main patch. `None` if the part is empty.
+    pub fn voice_range(&self, part: usize, main_voices: usize) -> Option<Range<usize>> {
+        if part >= MAX_PARTS {
+            return None;
+        }
+        let start: usize = (0..part).map(|p| self.voice_count(p, main_voices)).sum();
+        let count = self.voice_count(part, main_voices);
+        (count > 0).then_some(start..start + count)
+    }
+
+    /// First part with voices that responds to `note` on `channel`.
+    pub fn part_for_note(&self, channel: u8, note: u8, main_voices: usize) -> Option<usize> {
+        (0..MAX_PARTS).find(|&part| {
+            self.voice_count(part, main_voices) > 0 && self.configs[part].accepts(channel, note)
+        })
+    }
+
+    /// Gain of every part for a note; parts without voices or on another
+    /// channel get 0.
+    pub fn zone_gains(
+        &self,
+        channel: u8,
+        note: u8,
+        velocity: u8,
+        main_voices: usize,
+    ) -> [f32; MAX_PARTS] {
+        std::array::from_fn(|part| {
+            let config = &self.configs[part];
+            let on_channel = config.channel.is_none_or(|c| c == channel);
+            if on_channel && self.voice_count(part, main_voices) > 0 {
+                config.zone_gain(note, velocity)
+            } else {
+                0.0
+            }
+        })
+    }
+
+    /// Voices of parts 1.. in pool order.
+    pub fn extra_voices(&self) -> impl Iterator<Item = &Voice> {
+        self.extra_voices.iter().flatten()
+    }
+
+    /// Voices of parts 1.. in pool order, with their (send, dry) bus gains.
+    pub fn extra_voices_mut(&mut self) -> impl Iterator<Item = (&mut Voice, (f32, f32))> {
+        self.extra_voices
+            .iter_mut()
+            .zip(&self.configs[1..])
+            .flat_map(|(voices, config)| {
+                let gains = config.bus_gains();
+                voices.iter_mut().map(move |voice| (voice, gains))
+            })
+    }
+}
+
+/// Linear ramp over `fade` values inside the inner edges of `lo..=hi`.
+fn edge_fade(value: u8, lo: u8, hi: u8, fade: u8) -> f32 {
+    if fade == 0 {
+        return 1.0;
+    }
+    let fade = fade as f32;
+    let mut gain = 1.0f32;
+    if lo > 1 {
+        gain = gain.min((value as f32 - lo as f32 + 0.5) / fade);
+    }
+    if hi < 127 {
+        gain = gain.min((hi as f32 - value as f32 + 0.5) / fade);
+    }
+    gain.clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn voice_ranges_follow_loaded_parts_in_order() {
+        let mut parts = Parts::new();
+        parts
+            .set_voices(2, (0..3).map(|id| Voice::new(id, 16)).collect())
+            .unwrap();
+        assert!(parts.set_voices(0, Vec::new()).is_err());
+
+        assert_eq!(parts.voice_range(0, 8), Some(0..8));
+        assert_eq!(parts.voice_range(1, 8), None);
+        assert_eq!(parts.voice_range(2, 8), Some(8..11));
+        assert_eq!(parts.extra_voices_mut().count(), 3);
+    }
+
+    #[test]
+    fn notes_go_to_the_first_matching_part() {
+        let mut parts = Parts::new();
+        parts.set_voices(1, vec![Voice::new(0, 16)]).unwrap();
+        parts.config_mut(0).unwrap().lo_key = 60;
+        parts.config_mut(1).unwrap().channel = Some(9);
+
+        assert_eq!(parts.part_for_note(0, 72, 8), Some(0));
+        assert_eq!(parts.part_for_note(9, 40, 8), Some(1));
+        assert_eq!(parts.part_for_note(0, 40, 8), None);
+    }
+
+    #[test]
+    fn overlapping_zones_crossfade() {
+        let mut parts = Parts::new();
+        parts.set_voices(1, vec![Voice::new(0, 16)]).unwrap();
+        *parts.config_mut(0).unwrap() = PartConfig {
+            hi_key: 64,
+            fade: 5,
+            ..PartConfig::default()
+        };
+        *parts.config_mut(1).unwrap() = PartConfig {
+            lo_key: 60,
+            fade: 5,
+            ..PartConfig::default()
+        };
+
+        assert_eq!(parts.zone_gains(0, 40, 100, 8), [1.0, 0.0, 0.0, 0.0]);
+        assert_eq!(parts.zone_gains(0, 100, 100, 8), [0.0, 1.0, 0.0, 0.0]);
+        for note in 60..=64 {
+            let gains = parts.zone_gains(0, note, 100, 8);
+            assert!(gains[0] > 0.0 && gains[1] > 0.0);
+            assert!((gains[0] + gains[1] - 1.0).abs() < 1e-6);
+        }
+
+        // Velocity switching: a soft layer below 64 and no fade.
+        parts.config_mut(1).unwrap().hi_vel = 63;
+        parts.config_mut(1).unwrap().fade = 0;
+        assert_eq!(parts.zone_gains(0, 100, 40, 8)[1], 1.0);
+        assert_eq!(parts.zone_gains(0, 100, 90, 8)[1], 0.0);
+    }
+}
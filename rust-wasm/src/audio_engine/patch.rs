@@ -7,8 +7,12 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
+use crate::effect_stack::EffectRouting;
+use crate::macros::{MacroMapping, MacroSource, ModulationTarget};
 use crate::nodes::{
-    AnalogOscillatorStateUpdate, EnvelopeConfig, FilterSlope, WavetableOscillatorStateUpdate,
+    AnalogOscillatorStateUpdate, AutoWahDirection, EnvelopeConfig, EqBand, FilterSlope,
+    FmOperatorConfig, FmWaveform, MsegConfig, SaturationCharacter, WavetableOscillatorStateUpdate,
+    DEFAULT_GRAIN_SIZE_MS, DEFAULT_STRETCH_QUALITY,
 };
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -28,7 +32,7 @@ pub struct PatchMetadata {
     pub version: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct SynthState {
     pub layout: Layout,
     #[serde(default)]
@@ -55,17 +59,70 @@ pub struct SynthState {
     pub reverbs: HashMap<String, ReverbState>,
     #[serde(default)]
     pub compressors: HashMap<String, CompressorState>,
+    // States used by both a per-voice node and a master insert are told apart by
+    // their id: a node UUID in the voice, a numeric effect id on the master chain.
     #[serde(default)]
     pub saturations: HashMap<String, SaturationState>,
     #[serde(default)]
     pub bitcrushers: HashMap<String, BitcrusherState>,
+    #[serde(default, rename = "stereoEnhancers")]
+    pub stereo_enhancers: HashMap<String, StereoEnhancerState>,
+    #[serde(default)]
+    pub binaurals: HashMap<String, BinauralState>,
+    #[serde(default, rename = "autoWahs")]
+    pub auto_wahs: HashMap<String, AutoWahState>,
+    #[serde(default)]
+    pub exciters: HashMap<String, ExciterState>,
+    #[serde(default, rename = "noiseGates")]
+    pub noise_gates: HashMap<String, NoiseGateState>,
+    #[serde(default)]
+    pub multibands: HashMap<String, MultibandState>,
+    #[serde(default, rename = "parallelChains")]
+    pub parallel_chains: HashMap<String, ParallelState>,
+    #[serde(default)]
+    pub equalizers: HashMap<String, EqualizerState>,
+    #[serde(default, rename = "effectLfos")]
+    pub effect_lfos: Vec<EffectLfoState>,
+    #[serde(default, rename = "dualFilters")]
+    pub dual_filters: HashMap<String, DualFilterState>,
+    #[serde(default, rename = "formantFilters")]
+    pub formant_filters: HashMap<String, FormantFilterState>,
+    #[serde(default, rename = "gateTools")]
+    pub gate_tools: HashMap<String, GateToolState>,
+    #[serde(default)]
+    pub clocks: HashMap<String, ClockState>,
+    #[serde(default)]
+    pub chances: HashMap<String, ChanceState>,
+    #[serde(default)]
+    pub msegs: HashMap<String, MsegState>,
+    #[serde(default)]
+    pub fm_operators: HashMap<String, FmOperatorState>,
+    /// Per-node groove overrides, keyed by voice node or effect id.
+    #[serde(default)]
+    pub grooves: HashMap<String, NodeGrooveState>,
+    /// Effect sidechain routes, keyed by effect id.
+    #[serde(default)]
+    pub sidechains: HashMap<String, SidechainState>,
+    /// Mid/side and dual-mono effect routing, keyed by effect id.
+    #[serde(default, rename = "effectRoutings")]
+    pub effect_routings: HashMap<String, EffectRoutingState>,
     #[serde(default)]
     pub noise: Option<NoiseState>,
     #[serde(default)]
     pub velocity: Option<VelocityState>,
+    #[serde(default)]
+    pub tuning: Option<TuningState>,
+    #[serde(default)]
+    pub macros: Option<MacroState>,
+    #[serde(default)]
+    pub headroom: Option<HeadroomState>,
+    #[serde(default)]
+    pub transport: Option<TransportState>,
+    #[serde(default)]
+    pub groove: Option<GrooveState>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Layout {
     #[serde(default, rename = "voiceCount")]
     pub voice_count: Option<usize>,
@@ -75,7 +132,7 @@ pub struct Layout {
     pub voices: Vec<VoiceLayout>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct VoiceLayout {
     pub id: usize,
     #[serde(default)]
@@ -84,6 +141,10 @@ pub struct VoiceLayout {
     pub connections: Vec<PatchConnection>,
 }
 
+/// Most voices a patch may ask for, so a corrupted count can't allocate
+/// thousands of voice graphs.
+pub const MAX_PATCH_VOICES: usize = 128;
+
 impl Layout {
     /// Returns the declared voice count, falling back to legacy serialized voices
     /// or to 1 if we have a canonical voice but no explicit count.
@@ -114,7 +175,7 @@ impl Layout {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PatchNode {
     pub id: String,
     #[serde(rename = "type")]
@@ -122,7 +183,7 @@ pub struct PatchNode {
     pub name: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PatchConnection {
     #[serde(rename = "fromId")]
     pub from_id: String,
@@ -179,6 +240,144 @@ pub struct FilterState {
     pub filter_slope: FilterSlope,
     #[serde(default)]
     pub active: bool,
+    /// Calibrated level compensation for resonance/drive (see `FilterCollection::set_auto_gain`).
+    #[serde(default, rename = "autoGain")]
+    pub auto_gain: bool,
+    /// Octaves per unit of CutoffMod; 0 keeps the linear (Hz) behaviour.
+    #[serde(default, rename = "cutoffModOctaves")]
+    pub cutoff_mod_octaves: f32,
+    /// Run the ladder/comb feedback paths in f64.
+    #[serde(default, rename = "doublePrecisionFeedback")]
+    pub double_precision_feedback: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DualFilterState {
+    pub id: String,
+    #[serde(default)]
+    pub active: bool,
+    /// 0 = serial, 1 = parallel, 2 = split.
+    pub routing: u8,
+    pub balance: f32,
+    /// Semitones between filter A's and filter B's cutoff.
+    pub spacing: f32,
+    pub cutoff: f32,
+    #[serde(rename = "keytracking")]
+    pub key_tracking: f32,
+    #[serde(rename = "filterA")]
+    pub filter_a: DualFilterSlotState,
+    #[serde(rename = "filterB")]
+    pub filter_b: DualFilterSlotState,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FormantFilterState {
+    pub id: String,
+    pub active: bool,
+    /// 0 = A, 1 = E, 2 = I, 3 = O, 4 = U.
+    pub vowel: u8,
+    /// Formant shift from -1.0 (larger voice) to 1.0 (smaller voice).
+    pub gender: f32,
+    pub resonance: f32,
+    pub mix: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GateToolState {
+    pub id: String,
+    pub active: bool,
+    /// Fixed output gate length; 0 follows the incoming gate.
+    #[serde(rename = "lengthMs")]
+    pub length_ms: f32,
+    #[serde(rename = "delayMs")]
+    pub delay_ms: f32,
+    pub division: u32,
+    pub multiplication: u32,
+    pub probability: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClockState {
+    pub id: String,
+    pub active: bool,
+    /// Beats between pulses (0.25 = sixteenth notes).
+    #[serde(rename = "divisionBeats")]
+    pub division_beats: f32,
+    #[serde(rename = "pulseWidth")]
+    pub pulse_width: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChanceState {
+    pub id: String,
+    pub active: bool,
+    pub probability: f32,
+    /// 0 = route, 1 = hold, 2 = attenuate.
+    pub mode: u8,
+    /// Gain of failed rolls in attenuate mode.
+    #[serde(default)]
+    pub attenuation: f32,
+    #[serde(default)]
+    pub seed: u32,
+    /// 0 = per voice, 1 = global.
+    #[serde(default)]
+    pub randomness: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MsegState {
+    pub id: String,
+    #[serde(flatten)]
+    pub config: MsegConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FmOperatorState {
+    pub id: String,
+    /// 0 = sine, 1 = half sine, 2 = abs sine, 3 = triangle, 4 = square, 5 = saw.
+    pub waveform: u8,
+    pub fixed: bool,
+    pub ratio: f32,
+    pub fixed_frequency: f32,
+    #[serde(default)]
+    pub detune_oct: f32,
+    #[serde(default)]
+    pub detune_semi: f32,
+    #[serde(default)]
+    pub detune: f32,
+    #[serde(default)]
+    pub feedback: f32,
+    pub level: f32,
+    pub active: bool,
+}
+
+impl FmOperatorState {
+    pub fn config(&self) -> FmOperatorConfig {
+        FmOperatorConfig {
+            waveform: FmWaveform::from_u8(self.waveform),
+            fixed: self.fixed,
+            ratio: self.ratio,
+            fixed_frequency: self.fixed_frequency,
+            detune_oct: self.detune_oct,
+            detune_semi: self.detune_semi,
+            detune: self.detune,
+            feedback: self.feedback,
+            level: self.level,
+            active: self.active,
+        }
+    }
+}
+
+/// Per-filter settings of a `DualFilterState`; cutoff is shared by the container.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DualFilterSlotState {
+    pub resonance: f32,
+    pub gain: f32,
+    #[serde(rename = "filter_type")]
+    pub filter_type: i32,
+    #[serde(rename = "filter_slope")]
+    pub filter_slope: FilterSlope,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -192,6 +391,9 @@ pub struct SamplerState {
     pub loop_start: f32,
     #[serde(rename = "loopEnd")]
     pub loop_end: f32,
+    /// Milliseconds.
+    #[serde(rename = "loopCrossfade", default)]
+    pub loop_crossfade: f32,
     #[serde(rename = "sampleLength", default)]
     pub sample_length: f32,
     #[serde(rename = "rootNote")]
@@ -206,6 +408,21 @@ pub struct SamplerState {
     pub channels: u32,
     #[serde(rename = "fileName", default)]
     pub file_name: Option<String>,
+    /// 0 = repitch, 1 = stretch.
+    #[serde(rename = "playbackMode", default)]
+    pub playback_mode: u8,
+    #[serde(rename = "grainSize", default = "default_grain_size")]
+    pub grain_size: f32,
+    #[serde(rename = "stretchQuality", default = "default_stretch_quality")]
+    pub stretch_quality: u8,
+}
+
+fn default_grain_size() -> f32 {
+    DEFAULT_GRAIN_SIZE_MS
+}
+
+fn default_stretch_quality() -> u8 {
+    DEFAULT_STRETCH_QUALITY
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -225,6 +442,12 @@ pub struct DelayState {
     #[serde(rename = "wetMix")]
     pub wet_mix: f32,
     pub active: bool,
+    /// Echo attenuation driven by the sidechain key (0..1).
+    #[serde(default)]
+    pub ducking: f32,
+    /// Delay time in beats when tempo-synced.
+    #[serde(default, rename = "syncBeats")]
+    pub sync_beats: Option<f32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -267,6 +490,15 @@ pub struct SaturationState {
     pub active: bool,
     pub drive: f32,
     pub mix: f32,
+    #[serde(default)]
+    pub character: SaturationCharacter,
+    /// Tilt EQ before/after the shaper in dB.
+    #[serde(default, rename = "preTilt")]
+    pub pre_tilt: f32,
+    #[serde(default, rename = "postTilt")]
+    pub post_tilt: f32,
+    #[serde(default, rename = "autoGain")]
+    pub auto_gain: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -279,6 +511,145 @@ pub struct BitcrusherState {
     pub mix: f32,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StereoEnhancerState {
+    pub id: String,
+    pub active: bool,
+    #[serde(rename = "delayLeftMs")]
+    pub delay_left_ms: f32,
+    #[serde(rename = "delayRightMs")]
+    pub delay_right_ms: f32,
+    pub width: f32,
+    #[serde(rename = "combCompensation")]
+    pub comb_compensation: f32,
+    pub mix: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinauralState {
+    pub id: String,
+    pub active: bool,
+    /// Degrees clockwise from the front.
+    pub azimuth: f32,
+    /// Degrees, -30 to 90.
+    pub elevation: f32,
+    pub mix: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AutoWahState {
+    pub id: String,
+    pub active: bool,
+    pub sensitivity: f32,
+    /// Bottom of the sweep in Hz.
+    pub frequency: f32,
+    /// Sweep width in octaves.
+    pub range: f32,
+    pub q: f32,
+    #[serde(default)]
+    pub direction: AutoWahDirection,
+    pub mix: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExciterState {
+    pub id: String,
+    pub active: bool,
+    /// Crossover in Hz.
+    pub frequency: f32,
+    pub amount: f32,
+    pub mix: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NoiseGateState {
+    pub id: String,
+    pub active: bool,
+    #[serde(rename = "thresholdDb")]
+    pub threshold_db: f32,
+    #[serde(rename = "hysteresisDb")]
+    pub hysteresis_db: f32,
+    #[serde(rename = "attackMs")]
+    pub attack_ms: f32,
+    #[serde(rename = "holdMs")]
+    pub hold_ms: f32,
+    #[serde(rename = "releaseMs")]
+    pub release_ms: f32,
+    #[serde(rename = "rangeDb")]
+    pub range_db: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MultibandState {
+    pub id: String,
+    pub active: bool,
+    /// Crossover frequencies in Hz; one fewer than the bands.
+    pub crossovers: Vec<f32>,
+    #[serde(default)]
+    pub bands: Vec<MultibandBandState>,
+    /// Effects running on a band instead of in the serial chain.
+    #[serde(default)]
+    pub members: Vec<ContainerMemberState>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MultibandBandState {
+    pub gain: f32,
+    #[serde(default)]
+    pub muted: bool,
+    #[serde(default)]
+    pub soloed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParallelState {
+    pub id: String,
+    pub active: bool,
+    /// 0.0 = chain A only, 1.0 = chain B only.
+    pub blend: f32,
+    /// Effects running on a chain (band 0 = A, 1 = B) instead of in the
+    /// serial chain.
+    #[serde(default)]
+    pub members: Vec<ContainerMemberState>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EqualizerState {
+    pub id: String,
+    pub active: bool,
+    /// Bands in processing order.
+    #[serde(default)]
+    pub bands: Vec<EqBand>,
+}
+
+/// One of the effect stack's LFOs, in index order.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EffectLfoState {
+    pub waveform: u8,
+    #[serde(rename = "rateHz")]
+    pub rate_hz: f32,
+    /// Cycle length in beats when tempo-synced.
+    #[serde(default, rename = "syncBeats")]
+    pub sync_beats: Option<f32>,
+    #[serde(default)]
+    pub routes: Vec<EffectLfoRouteState>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EffectLfoRouteState {
+    #[serde(rename = "effectId")]
+    pub effect_id: String,
+    pub parameter: String,
+    pub depth: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContainerMemberState {
+    #[serde(rename = "effectId")]
+    pub effect_id: String,
+    pub band: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ReverbState {
     pub id: String,
@@ -289,6 +660,80 @@ pub struct ReverbState {
     pub wet: f32,
     pub dry: f32,
     pub width: f32,
+    /// Gate the tail with the sidechain key.
+    #[serde(default)]
+    pub gate: bool,
+}
+
+/// Routes a voice node output into an effect's sidechain input.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SidechainState {
+    /// Effect id (numeric, as for the other master effects).
+    pub id: String,
+    #[serde(rename = "sourceId")]
+    pub source_id: String,
+    /// `PortId` discriminant of the source output.
+    #[serde(rename = "sourcePort")]
+    pub source_port: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EffectRoutingState {
+    /// Effect id (numeric, as for the other master effects).
+    pub id: String,
+    pub routing: EffectRouting,
+}
+
+/// Macro knob values and their routes. One macro may have several routes,
+/// each with its own range mapping.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MacroState {
+    #[serde(default)]
+    pub values: Vec<f32>,
+    #[serde(default)]
+    pub routes: Vec<MacroRouteState>,
+    /// Macros driven by performance data inside the engine.
+    #[serde(default)]
+    pub sources: Vec<MacroSourceState>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MacroSourceState {
+    #[serde(rename = "macroIndex")]
+    pub macro_index: usize,
+    pub source: MacroSource,
+    pub amount: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MacroRouteState {
+    #[serde(rename = "macroIndex")]
+    pub macro_index: usize,
+    #[serde(rename = "targetId")]
+    pub target_id: String,
+    #[serde(rename = "targetPort")]
+    pub target_port: u32,
+    pub amount: f32,
+    #[serde(default, rename = "modulationType")]
+    pub modulation_type: Option<i32>,
+    #[serde(default, rename = "modulationTransformation")]
+    pub modulation_transform: Option<i32>,
+    #[serde(flatten)]
+    pub mapping: MacroMapping,
+}
+
+impl MacroRouteState {
+    pub fn from_target(macro_index: usize, target: &ModulationTarget) -> Self {
+        Self {
+            macro_index,
+            target_id: target.node_id.0.to_string(),
+            target_port: target.port_id as u32,
+            amount: target.amount,
+            modulation_type: Some(target.modulation_type as i32),
+            modulation_transform: Some(target.modulation_transform as i32),
+            mapping: target.mapping,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -308,6 +753,63 @@ pub struct VelocityState {
     pub active: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TuningState {
+    /// Master transpose in semitones.
+    #[serde(default)]
+    pub transpose: f32,
+    /// Master fine tune in cents.
+    #[serde(default)]
+    pub fine: f32,
+    /// Per-voice detune offsets in cents, indexed by voice.
+    #[serde(default, rename = "voiceDetune")]
+    pub voice_detune: Vec<f32>,
+    /// Analog spread amount, 0..1.
+    #[serde(default, rename = "analogSpread")]
+    pub analog_spread: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct HeadroomState {
+    /// Scale the voice mix by 1/sqrt(active voices).
+    #[serde(default, rename = "polyphonyCompensation")]
+    pub polyphony_compensation: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TransportState {
+    #[serde(rename = "tempoBpm")]
+    pub tempo_bpm: f64,
+    /// 0 = internal, 1 = external pulses, 2 = host.
+    #[serde(default, rename = "clockSource")]
+    pub clock_source: u8,
+    #[serde(default = "default_pulses_per_beat", rename = "pulsesPerBeat")]
+    pub pulses_per_beat: u32,
+}
+
+fn default_pulses_per_beat() -> u32 {
+    24
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GrooveState {
+    /// Where the second sixteenth of each eighth lands (50 = straight).
+    #[serde(rename = "swingPercent")]
+    pub swing_percent: f32,
+    /// Micro offset of each sixteenth in the bar, as a fraction of a sixteenth.
+    #[serde(default)]
+    pub offsets: Vec<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NodeGrooveState {
+    pub id: String,
+    #[serde(rename = "swingPercent")]
+    pub swing_percent: f32,
+    #[serde(default)]
+    pub offsets: Vec<f32>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GlideState {
     #[serde(rename = "id")]
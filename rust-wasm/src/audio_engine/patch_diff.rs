@@ -0,0 +1,251 @@
+#![cfg_attr(
+    not(any(feature = "native-host", all(feature = "wasm", target_arch = "wasm32"))),
+    allow(dead_code)
+)]
+
+// Layout diffing for incremental patch application, shared by native and wasm.
+//
+// Applying an edited patch only touches the nodes and connections that changed,
+// so voices that are ringing keep their state. The engine-owned global nodes
+// (frequency, glide, velocity, expression, gate mixer, output mixer) are wired
+// specially when a voice is built; a patch that changes any of them is rebuilt
+// from scratch instead.
+
+use std::collections::{HashMap, HashSet};
+
+#[cfg(feature = "wasm")]
+use crate::audio_engine::patch::AudioAsset;
+use crate::audio_engine::patch::{PatchConnection, VoiceLayout as PatchVoiceLayout};
+use crate::audio_engine::patch_loader::{for_each_node_in_creation_order, parse_node_id};
+use crate::graph::NodeId;
+
+const STRUCTURAL_NODE_TYPES: [&str; 8] = [
+    "global_frequency",
+    "glide",
+    "global_velocity",
+    "global_pressure",
+    "global_timbre",
+    "global_release_velocity",
+    "gatemixer",
+    "mixer",
+];
+
+/// What changed between two voice layouts.
+#[derive(Debug, Default)]
+pub struct LayoutDiff {
+    /// Nodes to delete.
+    pub removed_nodes: Vec<NodeId>,
+    /// Nodes to create, with their patch type, in creation order.
+    pub added_nodes: Vec<(String, NodeId)>,
+    /// Connections of the old layout to remove, including the old version of
+    /// connections whose amount or modulation changed.
+    pub removed_connections: Vec<PatchConnection>,
+    /// Connections of the new layout to add.
+    pub added_connections: Vec<PatchConnection>,
+}
+
+impl LayoutDiff {
+    /// Diffs `old` against `new`. Returns `None` when the engine-owned global
+    /// nodes differ and the voices have to be rebuilt.
+    pub fn between(old: &PatchVoiceLayout, new: &PatchVoiceLayout) -> Result<Option<Self>, String> {
+        let old_nodes = node_types(old)?;
+        let new_nodes = node_types(new)?;
+
+        let structural = |nodes: &HashMap<NodeId, String>| -> HashSet<NodeId> {
+            nodes
+                .iter()
+                .filter(|(_, node_type)| STRUCTURAL_NODE_TYPES.contains(&node_type.as_str()))
+                .map(|(id, _)| *id)
+                .collect()
+        };
+        if structural(&old_nodes) != structural(&new_nodes) {
+            return Ok(None);
+        }
+
+        let mut diff = Self::default();
+        let same_node = |id: &NodeId, node_type: &String| old_nodes.get(id) == Some(node_type);
+        for (id, node_type) in &old_nodes {
+            if new_nodes.get(id) != Some(node_type) {
+                diff.removed_nodes.push(*id);
+            }
+        }
+        for_each_node_in_creation_order(new, |node_type, patch_node| {
+            let id = parse_node_id(&patch_node.id)?;
+            if !same_node(&id, &node_type.to_string()) {
+                diff.added_nodes.push((node_type.to_string(), id));
+            }
+            Ok::<(), String>(())
+        })?;
+
+        let replaced: HashSet<NodeId> = diff.removed_nodes.iter().copied().collect();
+        let touches_replaced = |connection: &PatchConnection| {
+            [&connection.from_id, &connection.to_id]
+                .into_iter()
+                .any(|id| parse_node_id(id).is_ok_and(|id| replaced.contains(&id)))
+        };
+        // Deleting a node takes its connections with it.
+        diff.removed_connections = old
+            .connections
+            .iter()
+            .filter(|connection| !new.connections.contains(connection))
+            .filter(|connection| !touches_replaced(connection))
+            .cloned()
+            .collect();
+        diff.added_connections = new
+            .connections
+            .iter()
+            .filter(|connection| {
+                !old.connections.contains(connection) || touches_replaced(connection)
+            })
+            .cloned()
+            .collect();
+
+        Ok(Some(diff))
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.removed_nodes.is_empty()
+            && self.added_nodes.is_empty()
+            && self.removed_connections.is_empty()
+            && self.added_connections.is_empty()
+    }
+}
+
+/// Whether a voice graph holds exactly the nodes of `layout`, i.e. no nodes were added
+/// or deleted one by one since the layout was applied.
+pub fn graph_matches_layout(
+    layout: &PatchVoiceLayout,
+    node_ids: impl Iterator<Item = NodeId>,
+) -> bool {
+    let Ok(nodes) = node_types(layout) else {
+        return false;
+    };
+    let node_ids: HashSet<NodeId> = node_ids.collect();
+    node_ids.len() == nodes.len() && node_ids.iter().all(|id| nodes.contains_key(id))
+}
+
+/// Fingerprint of each asset's data by asset id. Kept from one apply to the
+/// next so an incremental apply only imports the assets that changed.
+#[cfg(feature = "wasm")]
+pub fn asset_fingerprints(assets: &HashMap<String, AudioAsset>) -> HashMap<String, u64> {
+    use rustc_hash::FxHasher;
+    use std::hash::{Hash, Hasher};
+
+    assets
+        .values()
+        .map(|asset| {
+            let mut hasher = FxHasher::default();
+            asset.base64_data.hash(&mut hasher);
+            (asset.id.clone(), hasher.finish())
+        })
+        .collect()
+}
+
+/// The voice nodes of `layout` by id. Effects are listed in layouts too, but
+/// belong to the effect stack.
+fn node_types(layout: &PatchVoiceLayout) -> Result<HashMap<NodeId, String>, String> {
+    let mut nodes = HashMap::new();
+    for_each_node_in_creation_order(layout, |node_type, patch_node| {
+        nodes.insert(parse_node_id(&patch_node.id)?, node_type.to_string());
+        Ok::<(), String>(())
+    })?;
+    Ok(nodes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio_engine::patch::PatchNode;
+
+    fn layout(nodes: &[(&str, &str)], connections: &[(&str, &str, f32)]) -> PatchVoiceLayout {
+        let mut layout = PatchVoiceLayout::default();
+        for (node_type, id) in nodes {
+            layout
+                .nodes
+                .entry(node_type.to_string())
+                .or_default()
+                .push(PatchNode {
+                    id: id.to_string(),
+                    node_type: node_type.to_string(),
+                    name: node_type.to_string(),
+                });
+        }
+        layout.connections = connections
+            .iter()
+            .map(|(from, to, amount)| PatchConnection {
+                from_id: from.to_string(),
+                to_id: to.to_string(),
+                target: 0,
+                amount: *amount,
+                modulation_type: 0,
+                modulation_transform: 0,
+            })
+            .collect();
+        layout
+    }
+
+    const MIXER: &str = "00000000-0000-0000-0000-000000000001";
+    const OSC: &str = "00000000-0000-0000-0000-000000000002";
+    const FILTER: &str = "00000000-0000-0000-0000-000000000003";
+    const LFO: &str = "00000000-0000-0000-0000-000000000004";
+
+    #[test]
+    fn only_changed_nodes_and_connections_are_touched() {
+        let old = layout(
+            &[("mixer", MIXER), ("oscillator", OSC), ("lfo", LFO)],
+            &[(OSC, MIXER, 1.0), (LFO, OSC, 0.5)],
+        );
+        let new = layout(
+            &[("mixer", MIXER), ("oscillator", OSC), ("filter", FILTER)],
+            &[(OSC, FILTER, 1.0), (FILTER, MIXER, 1.0)],
+        );
+        let diff = LayoutDiff::between(&old, &new).unwrap().unwrap();
+        assert_eq!(diff.removed_nodes, vec![parse_node_id(LFO).unwrap()]);
+        assert_eq!(
+            diff.added_nodes,
+            vec![("filter".to_string(), parse_node_id(FILTER).unwrap())]
+        );
+        // The LFO's connection goes with the LFO.
+        assert_eq!(diff.removed_connections.len(), 1);
+        assert_eq!(diff.removed_connections[0].to_id, MIXER);
+        assert_eq!(diff.added_connections.len(), 2);
+
+        let retuned = layout(
+            &[("mixer", MIXER), ("oscillator", OSC), ("lfo", LFO)],
+            &[(OSC, MIXER, 1.0), (LFO, OSC, 0.25)],
+        );
+        let diff = LayoutDiff::between(&old, &retuned).unwrap().unwrap();
+        assert!(diff.removed_nodes.is_empty() && diff.added_nodes.is_empty());
+        assert_eq!(diff.removed_connections[0].amount, 0.5);
+        assert_eq!(diff.added_connections[0].amount, 0.25);
+        assert!(LayoutDiff::between(&old, &old).unwrap().unwrap().is_empty());
+    }
+
+    #[test]
+    fn a_new_output_mixer_needs_a_rebuild() {
+        let old = layout(&[("mixer", MIXER)], &[]);
+        let new = layout(&[("mixer", OSC)], &[]);
+        assert!(LayoutDiff::between(&old, &new).unwrap().is_none());
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn asset_fingerprints_follow_the_data() {
+        let assets = |data: &str| -> HashMap<String, AudioAsset> {
+            serde_json::from_value(serde_json::json!({
+                "impulse_response_10003": {
+                    "id": "impulse_response_10003", "type": "impulse_response",
+                    "base64Data": data, "sampleRate": 48000.0, "channels": 1,
+                },
+            }))
+            .unwrap()
+        };
+        let loaded = asset_fingerprints(&assets("AAAA"));
+        assert_eq!(asset_fingerprints(&assets("AAAA")), loaded);
+        assert_ne!(
+            asset_fingerprints(&assets("AAAB"))["impulse_response_10003"],
+            loaded["impulse_response_10003"]
+        );
+    }
+}
@@ -5,7 +5,9 @@
 
 // Shared patch loading logic for both native and wasm builds
 
-use crate::audio_engine::patch::{PatchNode, VoiceLayout as PatchVoiceLayout};
+use crate::audio_engine::patch::{
+    GlideState, PatchFile, PatchNode, VoiceLayout as PatchVoiceLayout,
+};
 use crate::biquad::FilterType;
 use crate::graph::{ModulationTransformation, ModulationType, NodeId};
 use crate::traits::PortId;
@@ -51,6 +53,12 @@ pub fn port_id_from_u32(value: u32) -> PatchLoaderResult<PortId> {
         24 => Ok(PortId::AttackMod),
         25 => Ok(PortId::ArpGate),
         26 => Ok(PortId::CombinedGate),
+        27 => Ok(PortId::SampleOffset),
+        28 => Ok(PortId::SidechainInput),
+        29 => Ok(PortId::ClockInput),
+        30 => Ok(PortId::FormantMod),
+        31 => Ok(PortId::DriveMod),
+        32 => Ok(PortId::ReleaseVelocity),
         _ => Err(format!("Unknown port id value {}", value)),
     }
 }
@@ -88,10 +96,27 @@ pub fn filter_type_from_i32(value: i32) -> PatchLoaderResult<FilterType> {
         6 => Ok(FilterType::Ladder),
         7 => Ok(FilterType::Comb),
         8 => Ok(FilterType::BandPass),
+        9 => Ok(FilterType::AllPass),
         _ => Err(format!("Unknown filter type {}", value)),
     }
 }
 
+/// Inverse of `filter_type_from_i32`
+pub fn filter_type_to_i32(filter_type: FilterType) -> i32 {
+    match filter_type {
+        FilterType::LowPass => 0,
+        FilterType::LowShelf => 1,
+        FilterType::Peaking => 2,
+        FilterType::HighShelf => 3,
+        FilterType::Notch => 4,
+        FilterType::HighPass => 5,
+        FilterType::Ladder => 6,
+        FilterType::Comb => 7,
+        FilterType::BandPass => 8,
+        FilterType::AllPass => 9,
+    }
+}
+
 /// Find the first node ID of a specific type in the voice layout
 pub fn find_node_id(voice_layout: &PatchVoiceLayout, node_type: &str) -> Option<String> {
     voice_layout
@@ -124,24 +149,103 @@ pub fn parse_audio_asset_id(asset_id: &str) -> Option<(String, String)> {
 }
 
 /// Node creation order - ensures dependencies are created first
-pub const NODE_CREATION_ORDER: [&str; 13] = [
+pub const NODE_CREATION_ORDER: [&str; 29] = [
     "global_frequency",
     "glide",
     "global_velocity",
+    "global_pressure",
+    "global_timbre",
+    "global_release_velocity",
     "gatemixer",
+    "gate_tool",
+    "clock",
+    "chance",
     "mixer",
     "filter",
+    "dual_filter",
+    "formant_filter",
+    "stereo_enhancer",
+    "binaural",
+    "auto_wah",
+    "noise_gate",
+    "saturation",
+    "bitcrusher",
     "oscillator",
     "wavetable_oscillator",
+    "fm_operator",
     "sampler",
     "envelope",
+    "mseg",
     "lfo",
     "noise",
     "arpeggiator_generator",
 ];
 
+/// Adds the global nodes older patches predate: a glide to route the global frequency
+/// through, and note expression (pressure/timbre) sources. New nodes take the id of the
+/// matching node in `previous` when there is one, so reapplying the same old patch keeps
+/// its layout stable.
+pub fn add_missing_global_nodes(patch: &mut PatchFile, previous: Option<&PatchVoiceLayout>) {
+    let Some(canonical) = patch.synth_state.layout.canonical_voice.as_mut() else {
+        return;
+    };
+    let previous_id = |node_type: &str| {
+        previous
+            .and_then(|layout| layout.nodes.get(node_type))
+            .and_then(|nodes| nodes.first())
+            .map(|node| node.id.clone())
+            .unwrap_or_else(|| NodeId::new().0.to_string())
+    };
+
+    let has_glide = canonical
+        .nodes
+        .get("glide")
+        .map(|v| !v.is_empty())
+        .unwrap_or(false);
+    if !has_glide {
+        let new_id = previous_id("glide");
+        canonical
+            .nodes
+            .entry("glide".to_string())
+            .or_default()
+            .push(PatchNode {
+                id: new_id.clone(),
+                node_type: "glide".to_string(),
+                name: "Glide".to_string(),
+            });
+        patch
+            .synth_state
+            .glides
+            .entry(new_id.clone())
+            .or_insert(GlideState {
+                glide_id: new_id,
+                time: 0.0,
+                rise_time: None,
+                fall_time: None,
+                active: false,
+            });
+    }
+
+    for (node_type, name) in [
+        ("global_pressure", "Global Pressure"),
+        ("global_timbre", "Global Timbre"),
+        ("global_release_velocity", "Global Release Velocity"),
+    ] {
+        let entry = canonical.nodes.entry(node_type.to_string()).or_default();
+        if entry.is_empty() {
+            entry.push(PatchNode {
+                id: previous_id(node_type),
+                node_type: node_type.to_string(),
+                name: name.to_string(),
+            });
+        }
+    }
+}
+
 /// Iterate nodes in creation order, invoking the callback for each node.
-/// Shared by native and wasm loaders to keep ordering in sync.
+/// Shared by native and wasm loaders to keep ordering in sync. Effect-stack
+/// entries share type names with per-voice effects but have numeric ids; they
+/// aren't voice nodes and are skipped.
 pub fn for_each_node_in_creation_order<F, E>(
     voice_layout: &PatchVoiceLayout,
     mut callback: F,
@@ -152,6 +256,9 @@ where
     for node_type in NODE_CREATION_ORDER {
         if let Some(nodes) = voice_layout.nodes.get(node_type) {
             for patch_node in nodes {
+                if patch_node.id.parse::<usize>().is_ok() {
+                    continue;
+                }
                 callback(node_type, patch_node)?;
             }
         }
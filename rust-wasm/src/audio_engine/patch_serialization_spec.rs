@@ -84,8 +84,32 @@ mod tests {
             compressors: Default::default(),
             saturations: Default::default(),
             bitcrushers: Default::default(),
+            stereo_enhancers: Default::default(),
+            binaurals: Default::default(),
+            auto_wahs: Default::default(),
+            exciters: Default::default(),
+            equalizers: Default::default(),
+            noise_gates: Default::default(),
+            multibands: Default::default(),
+            parallel_chains: Default::default(),
+            effect_lfos: Default::default(),
+            dual_filters: Default::default(),
+            formant_filters: Default::default(),
+            gate_tools: Default::default(),
+            clocks: Default::default(),
+            chances: Default::default(),
+            msegs: Default::default(),
+            fm_operators: Default::default(),
+            grooves: Default::default(),
+            sidechains: Default::default(),
+            effect_routings: Default::default(),
             noise: Default::default(),
             velocity: Default::default(),
+            tuning: Default::default(),
+            headroom: Default::default(),
+            transport: Default::default(),
+            groove: Default::default(),
+            macros: Default::default(),
         };
 
         let metadata = PatchMetadata {
@@ -0,0 +1,194 @@
+// src/audio_engine/scope.rs
+//
+// XY (Lissajous) scope capture for goniometer and phase-scope views. A pair of
+// signals, the master output or two voice node outputs summed over the voices,
+// is kept in a short history. Captures start at the latest rising zero
+// crossing of X that still has a full capture after it, so periodic material
+// draws a still figure instead of a rolling one.
+
+use serde::Serialize;
+
+use crate::voice::Voice;
+use crate::{NodeId, PortId};
+
+pub const MAX_SCOPE_LENGTH: usize = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScopeSource {
+    /// Left on X, right on Y, after the output stage.
+    Master,
+    Nodes {
+        x: (NodeId, PortId),
+        y: (NodeId, PortId),
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopeCapture {
+    pub x: Vec<f32>,
+    pub y: Vec<f32>,
+    /// False when X had no rising zero crossing; the capture is then simply
+    /// the newest samples.
+    pub triggered: bool,
+}
+
+#[derive(Debug)]
+pub struct XyScope {
+    source: ScopeSource,
+    /// Samples per capture; 0 when the scope is off.
+    length: usize,
+    /// Two captures' worth of history, oldest first.
+    x: Vec<f32>,
+    y: Vec<f32>,
+    node_x: Vec<f32>,
+    node_y: Vec<f32>,
+}
+
+impl XyScope {
+    pub fn new() -> Self {
+        Self {
+            source: ScopeSource::Master,
+            length: 0,
+            x: Vec::new(),
+            y: Vec::new(),
+            node_x: Vec::new(),
+            node_y: Vec::new(),
+        }
+    }
+
+    /// Sets the capture length in samples, up to `MAX_SCOPE_LENGTH`; 0 turns
+    /// the scope off. Clears the history.
+    pub fn set_length(&mut self, length: usize) {
+        self.length = length.min(MAX_SCOPE_LENGTH);
+        self.x = Vec::with_capacity(self.length * 2);
+        self.y = Vec::with_capacity(self.length * 2);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.length > 0
+    }
+
+    pub fn source(&self) -> ScopeSource {
+        self.source
+    }
+
+    pub fn set_source(&mut self, source: ScopeSource) {
+        if source != self.source {
+            self.source = source;
+            self.x.clear();
+            self.y.clear();
+        }
+    }
+
+    /// Appends a block of X/Y samples, dropping the oldest beyond the history.
+    pub fn push(&mut self, x: &[f32], y: &[f32]) {
+        if !self.is_enabled() {
+            return;
+        }
+        let len = x.len().min(y.len());
+        Self::append(&mut self.x, &x[..len], self.length * 2);
+        Self::append(&mut self.y, &y[..len], self.length * 2);
+    }
+
+    /// Appends the last block of the source nodes, summed over `voices`.
+    /// Voices that skipped the block add nothing.
+    pub fn push_nodes<'v>(&mut self, voices: impl Iterator<Item = &'v Voice>, len: usize) {
+        let ScopeSource::Nodes { x, y } = self.source else {
+            return;
+        };
+        if !self.is_enabled() {
+            return;
+        }
+        let mut node_x = std::mem::take(&mut self.node_x);
+        let mut node_y = std::mem::take(&mut self.node_y);
+        node_x.clear();
+        node_x.resize(len, 0.0);
+        node_y.clear();
+        node_y.resize(len, 0.0);
+        for voice in voices {
+            for ((node, port), sum) in [(x, &mut node_x), (y, &mut node_y)] {
+                if let Some(signal) = voice.node_output(node, port) {
+                    for (acc, &sample) in sum.iter_mut().zip(signal) {
+                        *acc += sample;
+                    }
+                }
+            }
+        }
+        self.push(&node_x, &node_y);
+        self.node_x = node_x;
+        self.node_y = node_y;
+    }
+
+    fn append(history: &mut Vec<f32>, block: &[f32], capacity: usize) {
+        history.extend_from_slice(block);
+        if history.len() > capacity {
+            history.drain(..history.len() - capacity);
+        }
+    }
+
+    /// The latest capture, or `None` while the scope is off or hasn't seen a
+    /// full capture yet.
+    pub fn capture(&self) -> Option<ScopeCapture> {
+        let length = self.length;
+        if length == 0 || self.x.len() < length {
+            return None;
+        }
+        let newest = self.x.len() - length;
+        let trigger = (1..=newest)
+            .rev()
+            .find(|&i| self.x[i - 1] <= 0.0 && self.x[i] > 0.0);
+        let start = trigger.unwrap_or(newest);
+        Some(ScopeCapture {
+            x: self.x[start..start + length].to_vec(),
+            y: self.y[start..start + length].to_vec(),
+            triggered: trigger.is_some(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captures_start_on_a_rising_zero_crossing() {
+        let mut scope = XyScope::new();
+        scope.set_length(64);
+        let sine = |i: usize| (i as f32 * 0.3 + 1.0).sin();
+        let x: Vec<f32> = (0..48).map(sine).collect();
+        let y: Vec<f32> = x.iter().map(|v| -v).collect();
+        scope.push(&x, &y);
+        assert!(scope.capture().is_none());
+        for _ in 0..4 {
+            scope.push(&x, &y);
+        }
+
+        let capture = scope.capture().unwrap();
+        assert!(capture.triggered);
+        assert_eq!(capture.x.len(), 64);
+        assert!(
+            capture.x[0] > 0.0 && capture.x[0] < 0.35,
+            "{:?}",
+            &capture.x[..4]
+        );
+        assert!(capture.x[1] > capture.x[0]);
+        assert_eq!(capture.y[0], -capture.x[0]);
+    }
+
+    #[test]
+    fn untriggered_captures_hold_the_newest_samples() {
+        let mut scope = XyScope::new();
+        scope.set_length(16);
+        let ramp: Vec<f32> = (0..40).map(|i| i as f32 + 1.0).collect();
+        scope.push(&ramp, &ramp);
+        let capture = scope.capture().unwrap();
+        assert!(!capture.triggered);
+        assert_eq!(capture.x[0], 25.0);
+        assert_eq!(*capture.x.last().unwrap(), 40.0);
+
+        scope.set_length(0);
+        scope.push(&ramp, &ramp);
+        assert!(scope.capture().is_none());
+    }
+}
@@ -0,0 +1,275 @@
+// src/audio_engine/snapshot.rs
+//
+// Crash-safe session snapshots. While enabled, the engine checks on the audio
+// clock every few seconds whether the session changed and, if so, serializes a
+// `SessionSnapshot` for the host to keep (a callback, `take_snapshot`, or a
+// file on native). After a crash, `restore_snapshot` rebuilds the session.
+//
+// Snapshots are differential to stay cheap on the audio thread: the patch the
+// session was loaded from is only included in the first snapshot after it
+// changes. Later ones carry the performance state (tempo, macros, tuning) and
+// the presets of the nodes edited since the load. A host keeps the last
+// snapshot with a patch and folds newer ones into it with `with_patch_from`.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::param_lock::ParameterLocks;
+
+pub const DEFAULT_SNAPSHOT_INTERVAL_SECONDS: f32 = 5.0;
+
+/// Tempo, tuning and macro values set through the engine API.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerformanceState {
+    pub tempo_bpm: f64,
+    #[serde(default)]
+    pub master_tuning: Option<(f32, f32)>,
+    #[serde(default)]
+    pub voice_detune: Vec<Option<f32>>,
+    #[serde(default)]
+    pub smoothing_ms: Option<f32>,
+    #[serde(default)]
+    pub macro_values: Vec<Option<f32>>,
+}
+
+impl PerformanceState {
+    pub fn capture(locks: &ParameterLocks, tempo_bpm: f64) -> Self {
+        Self {
+            tempo_bpm,
+            master_tuning: locks.master_tuning(),
+            voice_detune: locks.voice_detune().to_vec(),
+            smoothing_ms: locks.smoothing(),
+            macro_values: locks.macro_values().to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSnapshot {
+    /// Counts up with every snapshot.
+    pub revision: u64,
+    /// Counts up with every patch load; a snapshot without a patch applies to
+    /// the patch of the same revision.
+    pub patch_revision: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch: Option<String>,
+    pub performance: PerformanceState,
+    /// `export_node_preset` JSON of the nodes edited since the patch loaded,
+    /// by node id.
+    #[serde(default)]
+    pub node_presets: BTreeMap<String, String>,
+}
+
+impl SessionSnapshot {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| format!("Invalid session snapshot: {}", e))
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self)
+            .map_err(|e| format!("Failed to serialize session snapshot: {}", e))
+    }
+
+    /// Fills in the patch from an earlier snapshot of the same patch revision.
+    pub fn with_patch_from(mut self, earlier: &SessionSnapshot) -> Self {
+        if self.patch.is_none() && earlier.patch_revision == self.patch_revision {
+            self.patch = earlier.patch.clone();
+        }
+        self
+    }
+}
+
+/// Decides when a snapshot is due and what goes into it.
+#[derive(Debug, Default)]
+pub struct SnapshotRecorder {
+    /// Samples between checks; 0 while disabled.
+    interval_samples: u64,
+    elapsed_samples: u64,
+    revision: u64,
+    patch_revision: u64,
+    patch: Option<String>,
+    /// The patch hasn't been part of a snapshot yet.
+    patch_pending: bool,
+    edited_nodes: BTreeSet<String>,
+    nodes_changed: bool,
+    last_performance: Option<PerformanceState>,
+}
+
+impl SnapshotRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks for changes every `seconds`; 0 or less turns snapshots off.
+    pub fn set_interval(&mut self, sample_rate: f32, seconds: f32) {
+        self.interval_samples = if seconds > 0.0 {
+            ((seconds * sample_rate) as u64).max(1)
+        } else {
+            0
+        };
+        self.elapsed_samples = 0;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.interval_samples > 0
+    }
+
+    /// Starts a new patch revision; node edits of the previous patch are
+    /// dropped.
+    pub fn patch_loaded(&mut self, patch_json: &str) {
+        self.patch_revision += 1;
+        self.patch = Some(patch_json.to_string());
+        self.patch_pending = true;
+        self.edited_nodes.clear();
+        self.nodes_changed = false;
+    }
+
+    pub fn node_edited(&mut self, node_id: &str) {
+        if !self.edited_nodes.contains(node_id) {
+            self.edited_nodes.insert(node_id.to_string());
+        }
+        self.nodes_changed = true;
+    }
+
+    pub fn edited_nodes(&self) -> impl Iterator<Item = &str> {
+        self.edited_nodes.iter().map(String::as_str)
+    }
+
+    /// Moves the clock on by `frames`. Returns true when it's time to check
+    /// for changes.
+    pub fn advance(&mut self, frames: usize) -> bool {
+        if !self.is_enabled() {
+            return false;
+        }
+        self.elapsed_samples += frames as u64;
+        if self.elapsed_samples < self.interval_samples {
+            return false;
+        }
+        self.elapsed_samples = 0;
+        true
+    }
+
+    /// Whether a snapshot with `performance` would differ from the last one.
+    pub fn has_changes(&self, performance: &PerformanceState) -> bool {
+        self.patch_pending
+            || self.nodes_changed
+            || self.last_performance.as_ref() != Some(performance)
+    }
+
+    /// The next snapshot, with the patch only if it is new.
+    pub fn take(
+        &mut self,
+        performance: PerformanceState,
+        node_presets: BTreeMap<String, String>,
+    ) -> SessionSnapshot {
+        self.revision += 1;
+        let patch = if self.patch_pending {
+            self.patch.clone()
+        } else {
+            None
+        };
+        self.patch_pending = false;
+        self.nodes_changed = false;
+        self.last_performance = Some(performance.clone());
+        SessionSnapshot {
+            revision: self.revision,
+            patch_revision: self.patch_revision,
+            patch,
+            performance,
+            node_presets,
+        }
+    }
+}
+
+/// Keeps the latest snapshot in a file, written on a background thread so
+/// the audio thread never waits on the disk. The file always holds a complete
+/// snapshot, patch included.
+#[cfg(feature = "native-host")]
+pub struct SnapshotFileWriter {
+    sender: std::sync::mpsc::Sender<SessionSnapshot>,
+}
+
+#[cfg(feature = "native-host")]
+impl SnapshotFileWriter {
+    pub fn new(path: impl Into<std::path::PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        let (sender, receiver) = std::sync::mpsc::channel::<SessionSnapshot>();
+        std::thread::Builder::new()
+            .name("snapshot-writer".to_string())
+            .spawn(move || {
+                let mut last_full: Option<SessionSnapshot> = None;
+                for snapshot in receiver {
+                    let snapshot = match &last_full {
+                        Some(full) => snapshot.with_patch_from(full),
+                        None => snapshot,
+                    };
+                    if let Err(err) = write_atomically(&path, &snapshot) {
+                        eprintln!("Failed to write session snapshot: {}", err);
+                    }
+                    if snapshot.patch.is_some() {
+                        last_full = Some(snapshot);
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to start snapshot writer: {}", e))?;
+        Ok(Self { sender })
+    }
+
+    pub fn write(&self, snapshot: SessionSnapshot) {
+        // The thread only stops when the writer is dropped.
+        let _ = self.sender.send(snapshot);
+    }
+}
+
+/// Writes next to `path` and renames over it, so a crash mid-write leaves the
+/// previous snapshot intact.
+#[cfg(feature = "native-host")]
+fn write_atomically(path: &std::path::Path, snapshot: &SessionSnapshot) -> Result<(), String> {
+    let json = snapshot.to_json()?;
+    let temp = path.with_extension("tmp");
+    std::fs::write(&temp, json).map_err(|e| format!("{}: {}", temp.display(), e))?;
+    std::fs::rename(&temp, path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_changed_sessions_are_snapshotted() {
+        let mut recorder = SnapshotRecorder::new();
+        assert!(!recorder.advance(1_000));
+        recorder.set_interval(1_000.0, 1.0);
+        recorder.patch_loaded("{\"patch\":1}");
+
+        assert!(!recorder.advance(600));
+        assert!(recorder.advance(600));
+        let performance = PerformanceState {
+            tempo_bpm: 120.0,
+            ..PerformanceState::default()
+        };
+        assert!(recorder.has_changes(&performance));
+        let first = recorder.take(performance.clone(), BTreeMap::new());
+        assert_eq!(first.patch.as_deref(), Some("{\"patch\":1}"));
+        assert!(!recorder.has_changes(&performance));
+
+        recorder.node_edited("filter");
+        assert!(recorder.has_changes(&performance));
+        let mut presets = BTreeMap::new();
+        presets.insert("filter".to_string(), "{}".to_string());
+        let second = recorder.take(performance, presets);
+        assert_eq!(second.patch, None);
+        assert_eq!(second.revision, 2);
+
+        let json = second.to_json().unwrap();
+        let restored = SessionSnapshot::from_json(&json)
+            .unwrap()
+            .with_patch_from(&first);
+        assert_eq!(restored.patch, first.patch);
+        assert_eq!(restored.node_presets.len(), 1);
+        assert_eq!(recorder.edited_nodes().collect::<Vec<_>>(), vec!["filter"]);
+    }
+}
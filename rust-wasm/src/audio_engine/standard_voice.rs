@@ -0,0 +1,152 @@
+// src/audio_engine/standard_voice.rs
+//
+// A basic subtractive voice in one call: saw oscillator into a low-pass filter
+// into the output mixer, with an amp envelope on the mixer gain and a filter
+// envelope, with a release of its own, on the cutoff. Hosts adjust the nodes
+// afterwards through the usual update calls.
+
+use std::sync::Arc;
+
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+
+use crate::biquad::FilterType;
+use crate::graph::{AudioGraph, Connection, ModulationTransformation, ModulationType};
+use crate::nodes::{
+    AnalogOscillator, Envelope, EnvelopeConfig, FilterCollection, Mixer, Waveform, WavetableBank,
+};
+use crate::{NodeId, PortId};
+
+const CUTOFF_HZ: f32 = 800.0;
+const RESONANCE: f32 = 0.2;
+/// Cutoff sweep of the filter envelope at full level.
+const FILTER_ENVELOPE_OCTAVES: f32 = 4.0;
+
+/// The node ids of a standard voice, the same in every voice.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct StandardVoice {
+    pub(crate) oscillator: NodeId,
+    pub(crate) filter: NodeId,
+    pub(crate) mixer: NodeId,
+    pub(crate) amp_envelope: NodeId,
+    pub(crate) filter_envelope: NodeId,
+}
+
+/// `StandardVoice` as the host sees it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StandardVoiceIds {
+    pub oscillator_id: String,
+    pub filter_id: String,
+    pub mixer_id: String,
+    pub amp_envelope_id: String,
+    pub filter_envelope_id: String,
+}
+
+impl StandardVoice {
+    pub(crate) fn new() -> Self {
+        Self {
+            oscillator: NodeId::new(),
+            filter: NodeId::new(),
+            mixer: NodeId::new(),
+            amp_envelope: NodeId::new(),
+            filter_envelope: NodeId::new(),
+        }
+    }
+
+    /// Adds the nodes and routings to one voice's graph. Gate and pitch reach
+    /// the envelopes and oscillator through the graph's automatic links; the
+    /// caller makes the mixer the voice output.
+    pub(crate) fn build(
+        &self,
+        graph: &mut AudioGraph,
+        sample_rate: f32,
+        wavetable_banks: Arc<FxHashMap<Waveform, Arc<WavetableBank>>>,
+    ) {
+        let mut filter = FilterCollection::new(sample_rate);
+        filter.set_filter_type(FilterType::LowPass);
+        filter.set_params(CUTOFF_HZ, RESONANCE);
+        filter.set_cutoff_mod_octaves(FILTER_ENVELOPE_OCTAVES);
+        let amp_envelope = EnvelopeConfig {
+            attack: 0.005,
+            decay: 0.3,
+            sustain: 0.7,
+            release: 0.4,
+            ..EnvelopeConfig::default()
+        };
+        let filter_envelope = EnvelopeConfig {
+            attack: 0.005,
+            decay: 0.5,
+            sustain: 0.3,
+            release: 0.3,
+            ..EnvelopeConfig::default()
+        };
+
+        graph.add_node_with_id(
+            self.oscillator,
+            Box::new(AnalogOscillator::new(
+                sample_rate,
+                Waveform::Saw,
+                wavetable_banks,
+            )),
+        );
+        graph.add_node_with_id(self.filter, Box::new(filter));
+        graph.add_node_with_id(self.mixer, Box::new(Mixer::new()));
+        graph.add_node_with_id(
+            self.amp_envelope,
+            Box::new(Envelope::new(sample_rate, amp_envelope)),
+        );
+        graph.add_node_with_id(
+            self.filter_envelope,
+            Box::new(Envelope::new(sample_rate, filter_envelope)),
+        );
+
+        let routes = [
+            (
+                self.oscillator,
+                self.filter,
+                PortId::AudioInput0,
+                ModulationType::Additive,
+            ),
+            (
+                self.filter,
+                self.mixer,
+                PortId::AudioInput0,
+                ModulationType::Additive,
+            ),
+            (
+                self.amp_envelope,
+                self.mixer,
+                PortId::GainMod,
+                ModulationType::VCA,
+            ),
+            (
+                self.filter_envelope,
+                self.filter,
+                PortId::CutoffMod,
+                ModulationType::Additive,
+            ),
+        ];
+        for (from_node, to_node, to_port, modulation_type) in routes {
+            graph.add_connection(Connection {
+                from_node,
+                from_port: PortId::AudioOutput0,
+                to_node,
+                to_port,
+                amount: 1.0,
+                modulation_type,
+                modulation_transform: ModulationTransformation::None,
+            });
+        }
+    }
+
+    pub(crate) fn ids(&self) -> StandardVoiceIds {
+        StandardVoiceIds {
+            oscillator_id: self.oscillator.to_string(),
+            filter_id: self.filter.to_string(),
+            mixer_id: self.mixer.to_string(),
+            amp_envelope_id: self.amp_envelope.to_string(),
+            filter_envelope_id: self.filter_envelope.to_string(),
+        }
+    }
+}
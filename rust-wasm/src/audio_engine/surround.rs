@@ -0,0 +1,292 @@
+// src/audio_engine/surround.rs
+//
+// Quad and 5.1 output for the wasm and native engines. Every voice gets a 2D
+// position: `x` balances its stereo image left/right and `y` moves it between
+// the front (1) and rear (-1) speaker pairs with an equal-power crossfade.
+// The effect stack keeps running in stereo on the fold-down of all voices. Its
+// output becomes the front pair once the rear feeds are subtracted again, and
+// effects flagged as surround sends (reverb and delay by default) add their
+// wet signal to the rear pair too. In 5.1 the centre channel is left to the
+// phantom image and LFE carries the low-passed mono sum of the front pair.
+
+use std::f32::consts::{FRAC_PI_2, PI};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Crossover of the LFE feed.
+const LFE_CUTOFF_HZ: f32 = 120.0;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ChannelLayout {
+    #[default]
+    Stereo = 0,
+    /// Front left, front right, rear left, rear right.
+    Quad = 1,
+    /// L, R, C, LFE, Ls, Rs.
+    Surround51 = 2,
+}
+
+impl ChannelLayout {
+    pub fn channel_count(self) -> usize {
+        match self {
+            ChannelLayout::Stereo => 2,
+            ChannelLayout::Quad => 4,
+            ChannelLayout::Surround51 => 6,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct VoicePosition {
+    x: f32,
+    y: f32,
+}
+
+impl Default for VoicePosition {
+    /// Front centre, which sounds exactly like the stereo output.
+    fn default() -> Self {
+        Self { x: 0.0, y: 1.0 }
+    }
+}
+
+impl VoicePosition {
+    /// Left balance, right balance, front and rear gains.
+    fn gains(self) -> (f32, f32, f32, f32) {
+        let rear_amount = (1.0 - self.y) * 0.5 * FRAC_PI_2;
+        (
+            (1.0 - self.x).min(1.0),
+            (1.0 + self.x).min(1.0),
+            rear_amount.cos(),
+            rear_amount.sin(),
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct SurroundPanner {
+    layout: ChannelLayout,
+    positions: Vec<VoicePosition>,
+    rear_left: Vec<f32>,
+    rear_right: Vec<f32>,
+    /// Channels after the front pair, in output order.
+    extra: Vec<Vec<f32>>,
+    lfe_coeff: f32,
+    lfe_state: f32,
+}
+
+impl SurroundPanner {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut panner = Self {
+            layout: ChannelLayout::Stereo,
+            positions: Vec::new(),
+            rear_left: Vec::new(),
+            rear_right: Vec::new(),
+            extra: Vec::new(),
+            lfe_coeff: 1.0,
+            lfe_state: 0.0,
+        };
+        panner.set_sample_rate(sample_rate);
+        panner
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.lfe_coeff = 1.0 - (-2.0 * PI * LFE_CUTOFF_HZ / sample_rate.max(1.0)).exp();
+        self.lfe_state = 0.0;
+    }
+
+    pub fn set_layout(&mut self, layout: ChannelLayout) {
+        self.layout = layout;
+        self.extra = vec![Vec::new(); layout.channel_count() - 2];
+        self.lfe_state = 0.0;
+    }
+
+    pub fn layout(&self) -> ChannelLayout {
+        self.layout
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.layout != ChannelLayout::Stereo
+    }
+
+    /// Places a voice: `x` from -1 (left) to 1 (right), `y` from -1 (rear) to
+    /// 1 (front).
+    pub fn set_voice_position(&mut self, voice_index: usize, x: f32, y: f32) {
+        let position = VoicePosition {
+            x: x.clamp(-1.0, 1.0),
+            y: y.clamp(-1.0, 1.0),
+        };
+        if self.positions.len() <= voice_index {
+            if position == VoicePosition::default() {
+                return;
+            }
+            self.positions
+                .resize(voice_index + 1, VoicePosition::default());
+        }
+        self.positions[voice_index] = position;
+    }
+
+    pub fn voice_position(&self, voice_index: usize) -> (f32, f32) {
+        let position = self.positions.get(voice_index).copied().unwrap_or_default();
+        (position.x, position.y)
+    }
+
+    /// Clears the rear bed before the voices of a block are panned.
+    pub fn begin_block(&mut self, len: usize) {
+        if !self.is_enabled() {
+            return;
+        }
+        self.rear_left.resize(len, 0.0);
+        self.rear_right.resize(len, 0.0);
+        self.rear_left.fill(0.0);
+        self.rear_right.fill(0.0);
+    }
+
+    /// Replaces a voice's stereo output in place with its fold-down (front plus
+    /// rear feed) and adds the rear feed to the rear bed, ramping from `gain`
+    /// to `gain_end` and scaled by `bus_gain` like the engine's voice mix.
+    pub fn pan_voice(
+        &mut self,
+        voice_index: usize,
+        left: &mut [f32],
+        right: &mut [f32],
+        gain: f32,
+        gain_end: f32,
+        bus_gain: f32,
+    ) {
+        if !self.is_enabled() {
+            return;
+        }
+        let position = self.positions.get(voice_index).copied().unwrap_or_default();
+        if position == VoicePosition::default() {
+            return;
+        }
+
+        let (left_gain, right_gain, front, rear) = position.gains();
+        let gain_step = (gain_end - gain) / left.len().max(1) as f32;
+        let len = left.len().min(right.len()).min(self.rear_left.len());
+        for i in 0..len {
+            let l = left[i] * left_gain;
+            let r = right[i] * right_gain;
+            let g = (gain + gain_step * i as f32) * bus_gain;
+            self.rear_left[i] += l * rear * g;
+            self.rear_right[i] += r * rear * g;
+            left[i] = l * (front + rear);
+            right[i] = r * (front + rear);
+        }
+    }
+
+    /// Turns the engine's stereo output (after master gain) into the front
+    /// pair and renders the other channels. `spread_*` is the wet signal of
+    /// the surround send effects.
+    pub fn finish_block(
+        &mut self,
+        front_left: &mut [f32],
+        front_right: &mut [f32],
+        spread_left: &[f32],
+        spread_right: &[f32],
+        master_gain: f32,
+    ) {
+        if !self.is_enabled() {
+            return;
+        }
+        let len = front_left.len().min(front_right.len());
+        self.rear_left.resize(len, 0.0);
+        self.rear_right.resize(len, 0.0);
+        for i in 0..len {
+            let rear_l = self.rear_left[i] * master_gain;
+            let rear_r = self.rear_right[i] * master_gain;
+            front_left[i] -= rear_l;
+            front_right[i] -= rear_r;
+            self.rear_left[i] = rear_l + spread_left.get(i).copied().unwrap_or(0.0) * master_gain;
+            self.rear_right[i] = rear_r + spread_right.get(i).copied().unwrap_or(0.0) * master_gain;
+        }
+
+        for channel in &mut self.extra {
+            channel.resize(len, 0.0);
+        }
+        match self.layout {
+            ChannelLayout::Stereo => {}
+            ChannelLayout::Quad => {
+                self.extra[0].copy_from_slice(&self.rear_left[..len]);
+                self.extra[1].copy_from_slice(&self.rear_right[..len]);
+            }
+            ChannelLayout::Surround51 => {
+                self.extra[0].fill(0.0);
+                for (i, lfe) in self.extra[1].iter_mut().enumerate() {
+                    let mono = 0.5 * (front_left[i] + front_right[i]);
+                    self.lfe_state += self.lfe_coeff * (mono - self.lfe_state);
+                    *lfe = self.lfe_state;
+                }
+                self.extra[2].copy_from_slice(&self.rear_left[..len]);
+                self.extra[3].copy_from_slice(&self.rear_right[..len]);
+            }
+        }
+    }
+
+    /// Channels after the front pair from the last block, in output order
+    /// (rear pair for quad; C, LFE, Ls, Rs for 5.1).
+    pub fn extra_channels(&self) -> &[Vec<f32>] {
+        &self.extra
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(panner: &mut SurroundPanner, voice_index: usize) -> (Vec<f32>, Vec<f32>) {
+        // One voice of constant 0.5 through an effect-free engine.
+        let mut left = vec![0.5; 64];
+        let mut right = vec![0.5; 64];
+        panner.begin_block(64);
+        panner.pan_voice(voice_index, &mut left, &mut right, 1.0, 1.0, 1.0);
+        let silent = vec![0.0; 64];
+        panner.finish_block(&mut left, &mut right, &silent, &silent, 1.0);
+        (left, right)
+    }
+
+    #[test]
+    fn rear_voice_moves_to_the_rear_pair() {
+        let mut panner = SurroundPanner::new(48_000.0);
+        panner.set_layout(ChannelLayout::Quad);
+        panner.set_voice_position(1, 0.0, -1.0);
+
+        let (front_left, front_right) = render(&mut panner, 1);
+        assert!(front_left
+            .iter()
+            .chain(&front_right)
+            .all(|s| s.abs() < 1e-6));
+        let rear = panner.extra_channels();
+        assert_eq!(rear.len(), 2);
+        assert!(rear[0]
+            .iter()
+            .chain(&rear[1])
+            .all(|s| (s - 0.5).abs() < 1e-6));
+
+        // Voices left at the default position only use the front pair.
+        let (front_left, _) = render(&mut panner, 0);
+        assert!(front_left.iter().all(|s| (s - 0.5).abs() < 1e-6));
+        assert!(panner.extra_channels()[0].iter().all(|s| s.abs() < 1e-6));
+    }
+
+    #[test]
+    fn surround_51_pans_with_equal_power_and_feeds_lfe() {
+        let mut panner = SurroundPanner::new(48_000.0);
+        panner.set_layout(ChannelLayout::Surround51);
+        panner.set_voice_position(0, 1.0, 0.0);
+
+        let (front_left, front_right) = render(&mut panner, 0);
+        let channels = panner.extra_channels();
+        assert_eq!(channels.len(), 4);
+        let expected = 0.5 * (FRAC_PI_2 * 0.5).cos();
+        assert!(front_left.iter().all(|s| s.abs() < 1e-6));
+        assert!(front_right.iter().all(|s| (s - expected).abs() < 1e-5));
+        assert!(channels[2].iter().all(|s| s.abs() < 1e-6));
+        assert!(channels[3].iter().all(|s| (s - expected).abs() < 1e-5));
+        assert!(channels[0].iter().all(|&s| s == 0.0));
+        assert!(channels[1][63] > channels[1][0] && channels[1][63] > 0.0);
+    }
+}
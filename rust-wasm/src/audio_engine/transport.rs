@@ -0,0 +1,227 @@
+// src/audio_engine/transport.rs
+//
+// The engine's musical clock. It runs from its own tempo, or slaves to an
+// external clock: either a pulse buffer (e.g. 24 PPQN from a hardware clock
+// input), or beat positions and tempo the host reports each block. Each block
+// it hands the voices a `TransportClock` that their `Clock` nodes turn into
+// pulses.
+
+use crate::nodes::TransportClock;
+
+/// Default resolution of external clock pulses (MIDI clock).
+pub const DEFAULT_PULSES_PER_BEAT: u32 = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockSource {
+    /// Runs at the transport's own tempo.
+    Internal,
+    /// Follows rising edges in the buffer passed to `set_pulses`.
+    ExternalPulses,
+    /// Follows the positions passed to `sync_to_host`.
+    Host,
+}
+
+impl ClockSource {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => ClockSource::ExternalPulses,
+            2 => ClockSource::Host,
+            _ => ClockSource::Internal,
+        }
+    }
+
+    pub fn as_u8(self) -> u8 {
+        match self {
+            ClockSource::Internal => 0,
+            ClockSource::ExternalPulses => 1,
+            ClockSource::Host => 2,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Transport {
+    sample_rate: f64,
+    tempo_bpm: f64,
+    playing: bool,
+    beat: f64,
+    source: ClockSource,
+    pulses_per_beat: u32,
+    /// External pulses for the next block.
+    pulses: Vec<f32>,
+    pulse_high: bool,
+    /// Beat of the last external pulse, and samples since it.
+    pulse_beat: Option<f64>,
+    samples_since_pulse: u64,
+}
+
+impl Transport {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate: sample_rate.max(1.0) as f64,
+            tempo_bpm: 120.0,
+            playing: false,
+            beat: 0.0,
+            source: ClockSource::Internal,
+            pulses_per_beat: DEFAULT_PULSES_PER_BEAT,
+            pulses: Vec::new(),
+            pulse_high: false,
+            pulse_beat: None,
+            samples_since_pulse: 0,
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate.max(1.0) as f64;
+    }
+
+    /// Tempo of the internal clock. Slaved transports report the tempo they
+    /// measure instead.
+    pub fn set_tempo(&mut self, bpm: f64) {
+        if bpm > 0.0 {
+            self.tempo_bpm = bpm;
+        }
+    }
+
+    pub fn tempo(&self) -> f64 {
+        self.tempo_bpm
+    }
+
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.playing = false;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn set_position(&mut self, beat: f64) {
+        self.beat = beat.max(0.0);
+        self.pulse_beat = None;
+    }
+
+    pub fn position(&self) -> f64 {
+        self.beat
+    }
+
+    pub fn set_source(&mut self, source: ClockSource) {
+        self.source = source;
+        self.pulse_beat = None;
+    }
+
+    pub fn source(&self) -> ClockSource {
+        self.source
+    }
+
+    pub fn set_pulses_per_beat(&mut self, pulses_per_beat: u32) {
+        self.pulses_per_beat = pulses_per_beat.max(1);
+    }
+
+    pub fn pulses_per_beat(&self) -> u32 {
+        self.pulses_per_beat
+    }
+
+    /// External clock pulses for the next block, one value per sample.
+    pub fn set_pulses(&mut self, pulses: &[f32]) {
+        self.pulses.clear();
+        self.pulses.extend_from_slice(pulses);
+    }
+
+    /// Host position at the start of the next block. Starts the transport, so
+    /// a host that stops reporting positions should call `stop`.
+    pub fn sync_to_host(&mut self, beat: f64, bpm: f64) {
+        self.beat = beat.max(0.0);
+        self.set_tempo(bpm);
+        self.playing = true;
+    }
+
+    fn beats_per_sample(&self) -> f64 {
+        self.tempo_bpm / 60.0 / self.sample_rate
+    }
+
+    /// Moves the transport through a block and returns where it was.
+    pub fn advance(&mut self, len: usize) -> TransportClock {
+        let start_beat = self.beat;
+        if self.playing && self.source == ClockSource::ExternalPulses {
+            self.follow_pulses(len);
+        } else if self.playing {
+            self.beat += self.beats_per_sample() * len as f64;
+        }
+        let beats_per_sample = if len > 0 {
+            (self.beat - start_beat) / len as f64
+        } else {
+            0.0
+        };
+        TransportClock {
+            start_beat,
+            beats_per_sample,
+            playing: self.playing,
+        }
+    }
+
+    /// Each rising edge lands the transport on the next pulse boundary and
+    /// updates the tempo from the time since the previous one. Between pulses
+    /// it runs at that tempo but stops at the next boundary rather than run
+    /// ahead of a clock that is slowing down.
+    fn follow_pulses(&mut self, len: usize) {
+        let pulse_beats = 1.0 / self.pulses_per_beat as f64;
+        let beats_per_sample = self.beats_per_sample();
+        for i in 0..len {
+            let high = self.pulses.get(i).is_some_and(|&p| p > 0.5);
+            self.samples_since_pulse += 1;
+            if high && !self.pulse_high {
+                match self.pulse_beat {
+                    Some(last) => {
+                        let interval = self.samples_since_pulse as f64;
+                        self.tempo_bpm =
+                            60.0 * self.sample_rate / (interval * self.pulses_per_beat as f64);
+                        self.beat = last + pulse_beats;
+                    }
+                    None => {
+                        self.beat = (self.beat / pulse_beats).round() * pulse_beats;
+                    }
+                }
+                self.pulse_beat = Some(self.beat);
+                self.samples_since_pulse = 0;
+            } else if let Some(last) = self.pulse_beat {
+                self.beat = (self.beat + beats_per_sample).min(last + pulse_beats);
+            }
+            self.pulse_high = high;
+        }
+        self.pulses.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slaves_to_external_pulses() {
+        // 1 kHz and 4 pulses per beat: a pulse every 125 samples is 120 BPM.
+        let mut transport = Transport::new(1_000.0);
+        transport.set_source(ClockSource::ExternalPulses);
+        transport.set_pulses_per_beat(4);
+        transport.play();
+
+        let pulses: Vec<f32> = (0..500)
+            .map(|i| if i % 125 < 5 { 1.0 } else { 0.0 })
+            .collect();
+        transport.set_pulses(&pulses);
+        let clock = transport.advance(500);
+        assert_eq!(clock.start_beat, 0.0);
+        assert!((transport.tempo() - 120.0).abs() < 1e-6);
+        // The last pulse (at sample 375) was beat 0.75; the transport then runs
+        // on at the measured tempo.
+        let expected = 0.75 + 124.0 * 2.0 / 1_000.0;
+        assert!((transport.position() - expected).abs() < 1e-9);
+
+        // Without pulses it stops at the next boundary.
+        transport.advance(500);
+        assert!((transport.position() - 1.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,123 @@
+// src/audio_engine/typescript.rs
+//
+// TypeScript definitions for the engine's JSON APIs, generated from
+// `api_schema` so frontends don't keep hand-written copies of the update
+// objects. wasm-bindgen already types the exported classes and enums; what it
+// can't see is the shape of the `JsValue` objects the `update_*_params` calls
+// take, which is what this emits. Written by the `gen_ts` binary (feature
+// `ts-bindings`).
+
+use std::fmt::Write as _;
+
+use serde_json::Value;
+
+use super::api::{api_schema, EFFECT_UPDATE_METHODS};
+
+/// TypeScript type of a field schema.
+fn ts_type(schema: &Value) -> String {
+    if let Some(values) = schema["enum"].as_array() {
+        let members: Vec<String> = values.iter().map(Value::to_string).collect();
+        return members.join(" | ");
+    }
+    match schema["type"].as_str() {
+        Some("number") | Some("integer") => "number".to_string(),
+        Some("boolean") => "boolean".to_string(),
+        Some("string") => "string".to_string(),
+        Some("array") => format!("{}[]", ts_type(&schema["items"])),
+        _ => "unknown".to_string(),
+    }
+}
+
+fn write_doc(out: &mut String, indent: &str, doc: Option<&str>) {
+    if let Some(doc) = doc.filter(|doc| !doc.is_empty()) {
+        let _ = writeln!(out, "{indent}/** {doc} */");
+    }
+}
+
+fn write_interface(out: &mut String, schema: &Value) {
+    let title = schema["title"].as_str().unwrap_or_default();
+    let required: Vec<&str> = schema["required"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .collect();
+
+    write_doc(out, "", schema["description"].as_str());
+    let _ = writeln!(out, "export interface {title} {{");
+    if let Some(properties) = schema["properties"].as_object() {
+        for (name, property) in properties {
+            let optional = if required.contains(&name.as_str()) {
+                ""
+            } else {
+                "?"
+            };
+            write_doc(out, "  ", property["description"].as_str());
+            let _ = writeln!(out, "  {name}{optional}: {};", ts_type(property));
+        }
+    }
+    out.push_str("}\n\n");
+}
+
+/// Contents of the generated `.d.ts` file.
+pub fn typescript_definitions() -> String {
+    let schema = api_schema();
+    let methods = schema.as_object().cloned().unwrap_or_default();
+
+    let mut out = String::from(
+        "// Generated by `cargo run --features ts-bindings --bin gen_ts`; do not edit.\n\
+         // Object forms of the AudioEngine `update_*_params` calls (see `get_api_schema`).\n\n",
+    );
+
+    let mut written = Vec::new();
+    for method_schema in methods.values() {
+        let title = method_schema["title"].as_str().unwrap_or_default();
+        if !written.contains(&title) {
+            written.push(title);
+            write_interface(&mut out, method_schema);
+        }
+    }
+
+    out.push_str("/** Typed signatures of the `update_*_params` methods of `AudioEngine`. */\n");
+    out.push_str("export interface AudioEngineUpdateParams {\n");
+    for (method, method_schema) in &methods {
+        let node_id = if EFFECT_UPDATE_METHODS.contains(&method.as_str()) {
+            "number"
+        } else {
+            "string"
+        };
+        let title = method_schema["title"].as_str().unwrap_or_default();
+        let _ = writeln!(
+            out,
+            "  {method}(node_id: {node_id}, params: {title}): void;"
+        );
+    }
+    out.push_str("}\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emits_interfaces_and_method_signatures() {
+        let definitions = typescript_definitions();
+        assert!(definitions.contains("export interface FilterUpdate {"));
+        assert!(definitions.contains("  filterSlope: 0 | 1;"));
+        assert!(definitions.contains("  oversampling?: number;"));
+        assert!(definitions.contains("  character: \"soft\" | \"tape\" | \"tube\" | \"diode\";"));
+        assert!(definitions
+            .contains("  update_delay_params(node_id: number, params: DelayUpdate): void;"));
+        assert!(definitions.contains(
+            "  update_voice_noise_gate_params(node_id: string, params: NoiseGateUpdate): void;"
+        ));
+        // Structs shared by several methods are declared once.
+        assert_eq!(
+            definitions
+                .matches("export interface NoiseGateUpdate")
+                .count(),
+            1
+        );
+    }
+}
@@ -0,0 +1,247 @@
+// src/audio_engine/voice_allocator.rs
+//
+// Engine-side voice allocation for hosts that send notes instead of per-voice
+// gate buffers. `note_on` hands a note to a free voice, or steals one using the
+// configured `StealMode`, reading each voice's age, envelope phase and output
+// level from `Voice`. While the host passes empty gate buffers to
+// `process_audio`, the allocated voices take their gate, pitch and velocity
+// from here.
+
+use crate::nodes::{EnvelopePhase, DEFAULT_RELEASE_VELOCITY};
+use crate::voice::Voice;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StealMode {
+    /// Steals the voice whose note started longest ago.
+    Oldest,
+    /// Steals the voice with the lowest output level.
+    Quietest,
+    /// Replays a note on the voice already playing it; otherwise steals the
+    /// oldest voice.
+    SameNote,
+    /// Steals the oldest voice whose envelopes are releasing; otherwise the
+    /// oldest voice.
+    ReleaseFirst,
+}
+
+impl StealMode {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => StealMode::Quietest,
+            2 => StealMode::SameNote,
+            3 => StealMode::ReleaseFirst,
+            _ => StealMode::Oldest,
+        }
+    }
+
+    pub fn as_u8(self) -> u8 {
+        match self {
+            StealMode::Oldest => 0,
+            StealMode::Quietest => 1,
+            StealMode::SameNote => 2,
+            StealMode::ReleaseFirst => 3,
+        }
+    }
+}
+
+/// The note an allocated voice is playing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AllocatedNote {
+    pub note: u8,
+    pub velocity: f32,
+    /// Note-off velocity; `DEFAULT_RELEASE_VELOCITY` while the note is held.
+    pub release_velocity: f32,
+    pub held: bool,
+}
+
+impl AllocatedNote {
+    pub fn gate(&self) -> f32 {
+        if self.held {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    pub fn frequency(&self) -> f32 {
+        440.0 * 2f32.powf((self.note as f32 - 69.0) / 12.0)
+    }
+}
+
+/// Where `note_on` put a note.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VoiceAssignment {
+    pub voice: usize,
+    /// The voice was still sounding and must be cut before it plays the note.
+    pub stolen: bool,
+}
+
+#[derive(Debug)]
+pub struct VoiceAllocator {
+    mode: StealMode,
+    notes: Vec<Option<AllocatedNote>>,
+}
+
+impl Default for VoiceAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VoiceAllocator {
+    pub fn new() -> Self {
+        Self {
+            mode: StealMode::Oldest,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn set_mode(&mut self, mode: StealMode) {
+        self.mode = mode;
+    }
+
+    pub fn mode(&self) -> StealMode {
+        self.mode
+    }
+
+    /// Note the allocator assigned to `voice`, if any. `None` for every voice
+    /// until the first `note_on`, so hosts driving gates directly are not
+    /// affected.
+    pub fn note(&self, voice: usize) -> Option<AllocatedNote> {
+        self.notes.get(voice).copied().flatten()
+    }
+
+    /// Picks a voice for `note` with a velocity of 0..1. Returns `None` when
+    /// there are no voices.
+    pub fn note_on(
+        &mut self,
+        note: u8,
+        velocity: f32,
+        voices: &[Voice],
+    ) -> Option<VoiceAssignment> {
+        self.notes.resize(voices.len(), None);
+        let voice = self.pick_voice(note, voices)?;
+        let stolen = voices[voice].is_active() || self.is_held(voice);
+        self.notes[voice] = Some(AllocatedNote {
+            note: note.min(127),
+            velocity: velocity.clamp(0.0, 1.0),
+            release_velocity: DEFAULT_RELEASE_VELOCITY,
+            held: true,
+        });
+        Some(VoiceAssignment { voice, stolen })
+    }
+
+    /// Releases every voice holding `note`. Returns false if none did.
+    pub fn note_off(&mut self, note: u8) -> bool {
+        self.note_off_with_velocity(note, DEFAULT_RELEASE_VELOCITY)
+    }
+
+    /// `note_off` with a release velocity of 0..1.
+    pub fn note_off_with_velocity(&mut self, note: u8, release_velocity: f32) -> bool {
+        let mut released = false;
+        for allocated in self.notes.iter_mut().flatten() {
+            if allocated.note == note && allocated.held {
+                allocated.held = false;
+                allocated.release_velocity = release_velocity.clamp(0.0, 1.0);
+                released = true;
+            }
+        }
+        released
+    }
+
+    pub fn all_notes_off(&mut self) {
+        for allocated in self.notes.iter_mut().flatten() {
+            allocated.held = false;
+        }
+    }
+
+    /// Forgets every assignment, handing the voices back to the host's gate
+    /// buffers.
+    pub fn reset(&mut self) {
+        self.notes.clear();
+    }
+
+    fn is_held(&self, voice: usize) -> bool {
+        self.note(voice).is_some_and(|allocated| allocated.held)
+    }
+
+    fn pick_voice(&self, note: u8, voices: &[Voice]) -> Option<usize> {
+        let all = 0..voices.len();
+        if self.mode == StealMode::SameNote {
+            let same_note = all
+                .clone()
+                .find(|&i| self.note(i).is_some_and(|allocated| allocated.note == note));
+            if same_note.is_some() {
+                return same_note;
+            }
+        }
+
+        let free = all
+            .clone()
+            .filter(|&i| !voices[i].is_active() && !self.is_held(i));
+        if let Some(voice) = oldest(voices, free) {
+            return Some(voice);
+        }
+
+        match self.mode {
+            StealMode::Oldest | StealMode::SameNote => oldest(voices, all),
+            StealMode::Quietest => {
+                all.min_by(|&a, &b| voices[a].output_rms().total_cmp(&voices[b].output_rms()))
+            }
+            StealMode::ReleaseFirst => {
+                let releasing = all
+                    .clone()
+                    .filter(|&i| voices[i].envelope_phase() == EnvelopePhase::Release);
+                oldest(voices, releasing).or_else(|| oldest(voices, all))
+            }
+        }
+    }
+}
+
+fn oldest(voices: &[Voice], candidates: impl Iterator<Item = usize>) -> Option<usize> {
+    candidates.max_by_key(|&i| voices[i].age_samples())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuses_free_voices_then_steals() {
+        let voices: Vec<Voice> = (0..2).map(|id| Voice::new(id, 16)).collect();
+        let mut allocator = VoiceAllocator::new();
+        assert_eq!(allocator.note(0), None);
+
+        let first = allocator.note_on(60, 0.5, &voices).unwrap();
+        let second = allocator.note_on(64, 0.5, &voices).unwrap();
+        assert_ne!(first.voice, second.voice);
+        assert!(!first.stolen && !second.stolen);
+
+        // Every voice is held, so the third note steals one.
+        let third = allocator.note_on(67, 1.0, &voices).unwrap();
+        assert!(third.stolen);
+        assert_eq!(allocator.note(third.voice).unwrap().note, 67);
+
+        assert!(allocator.note_off(67));
+        assert!(!allocator.note_off(67));
+        assert_eq!(allocator.note(third.voice).unwrap().gate(), 0.0);
+        // The released voice is free again.
+        assert_eq!(
+            allocator.note_on(72, 1.0, &voices).unwrap().voice,
+            third.voice
+        );
+    }
+
+    #[test]
+    fn same_note_replays_on_its_voice() {
+        let voices: Vec<Voice> = (0..4).map(|id| Voice::new(id, 16)).collect();
+        let mut allocator = VoiceAllocator::new();
+        allocator.set_mode(StealMode::SameNote);
+        let first = allocator.note_on(60, 1.0, &voices).unwrap();
+        allocator.note_on(62, 1.0, &voices).unwrap();
+        let again = allocator.note_on(60, 1.0, &voices).unwrap();
+        assert_eq!(again.voice, first.voice);
+        assert!(again.stolen);
+        assert!((allocator.note(first.voice).unwrap().frequency() - 261.6256).abs() < 1e-3);
+    }
+}
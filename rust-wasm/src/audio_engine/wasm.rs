@@ -1,48 +1,108 @@
-use super::patch::{AudioAsset, PatchFile, VoiceLayout as PatchVoiceLayout};
+use super::api::{
+    api_schema, AutoWahUpdate, BinauralUpdate, BitcrusherUpdate, ChanceUpdate, ChorusUpdate,
+    ClockUpdate, CompressorUpdate, ConvolverUpdate, DelayUpdate, DualFilterSlotUpdate,
+    DualFilterUpdate, EnvelopeUpdate, EqBandDynamicsUpdate, EqBandUpdate, EqUpdate, ExciterUpdate,
+    FilterUpdate, FmOperatorUpdate, FormantFilterUpdate, GateToolUpdate, GlideUpdate, LooperUpdate,
+    MultibandBandUpdate, MultibandUpdate, NoiseGateUpdate, ParallelUpdate, ReverbUpdate,
+    SamplerUpdate, SaturationToneUpdate, SaturationUpdate, StereoEnhancerUpdate, VelocityUpdate,
+};
+use super::auto_level::{node_levels, NodeLevels, NodeRole};
+use super::chain_response::{chain_response, serial_chain};
+use super::choke::ChokeGroups;
+use super::diagnostics::DiagnosticEvent;
+use super::flat_params::flatten_session;
+use super::fm_algorithm::{
+    check_fm_operators, clear_fm_links, fm_algorithm_connections, FmAlgorithm,
+};
+use super::headroom::PolyphonyCompensation;
+use super::import_backup::ImportHistory;
+use super::jobs::{
+    JobOutput, JobPoll, JobQueue, JobRequest, JobStatus, JobTask, MAX_WAVETABLE_BASE_SIZE,
+};
+use super::kit::DrumKit;
+use super::memory::MemoryCounter;
+use super::modulation_preview::modulation_preview;
+use super::node_preset::NodePreset;
+use super::output_stage::{OutputFormat, OutputMode, OutputStage};
+use super::overload::{OverloadAction, OverloadProtection, OverloadResponse, CULL_RMS_THRESHOLD};
+use super::param_lock::{LockableParameter, ParameterLocks};
+use super::parts::{PartConfig, Parts, MAX_PARTS};
+use super::patch::{
+    AudioAsset, MacroRouteState, MacroState, PatchConnection, PatchFile, SynthState,
+    VoiceLayout as PatchVoiceLayout, MAX_PATCH_VOICES,
+};
+use super::patch_diff::{asset_fingerprints, graph_matches_layout, LayoutDiff};
 use super::patch_loader::{
-    filter_type_from_i32, find_node_id, for_each_node_in_creation_order,
+    add_missing_global_nodes, filter_type_from_i32, find_node_id, for_each_node_in_creation_order,
     modulation_transform_from_i32, modulation_type_from_i32, parse_audio_asset_id, parse_node_id,
     port_id_from_u32,
 };
+use super::scope::{ScopeSource, XyScope};
+use super::snapshot::{
+    PerformanceState, SessionSnapshot, SnapshotRecorder, DEFAULT_SNAPSHOT_INTERVAL_SECONDS,
+};
+use super::standard_voice::StandardVoice;
+use super::surround::{ChannelLayout, SurroundPanner};
+use super::transport::{ClockSource, Transport};
+use super::voice_allocator::{StealMode, VoiceAllocator};
 use crate::automation::AutomationFrame;
 use crate::biquad::FilterType;
-use crate::effect_stack::EffectStack;
-use crate::graph::{Connection, ModulationTransformation, ModulationType, NodeId};
+use crate::effect_stack::{
+    ContainerSlot, EffectRouting, EffectStack, SidechainSource, EFFECT_LFO_COUNT,
+};
+use crate::graph::{
+    push_capacity_event, CapacityExceeded, CapacityLimits, CapacityResource, Connection,
+    ModulationTransformation, ModulationType, NodeId, MAX_PENDING_CAPACITY_EVENTS,
+};
 use crate::impulse_generator::ImpulseResponseGenerator;
+use crate::macros::{MacroMapping, MacroPolarity, MacroSource, ModulationTarget};
 use crate::nodes::morph_wavetable::{
-    MipmappedWavetable, WavetableMorphCollection, WavetableSynthBank,
+    read_wavetable_file, MipmappedWavetable, WavetableMorphCollection, WavetableSynthBank,
 };
+use crate::nodes::sampler::sfz::load_sfz;
 use crate::nodes::{
     generate_mipmapped_bank_dynamic, AnalogOscillator, AnalogOscillatorStateUpdate,
-    ArpeggiatorGenerator, Bitcrusher, Chorus, Compressor, Convolver, Delay, Envelope,
-    EnvelopeConfig, FilterCollection, FilterSlope, Freeverb, GateMixer, Glide, GlobalFrequencyNode,
-    GlobalVelocityNode, Lfo, LfoLoopMode, LfoRetriggerMode, LfoWaveform, Limiter, Mixer,
-    NoiseGenerator, NoiseType, NoiseUpdate, SampleData, Sampler, SamplerLoopMode,
-    SamplerTriggerMode, Saturation, Waveform, WavetableBank, WavetableOscillator,
-    WavetableOscillatorStateUpdate,
+    ArpeggiatorConfig, ArpeggiatorGenerator, AutoWah, AutoWahDirection, Binaural, Bitcrusher,
+    Chance, ChanceMode, ChanceRandomness, Chorus, Clock, Compressor, Convolver, Delay, DualFilter,
+    DualFilterRouting, Envelope, EnvelopeConfig, EqBand, EqBandType, EqDynamics, Equalizer,
+    Exciter, ExpressionKind, FilterCollection, FilterSlope, FmOperator, FmOperatorConfig,
+    FmWaveform, FormantFilter, FormantVowel, Freeverb, GateMixer, GateTool, Glide,
+    GlobalExpressionNode, GlobalFrequencyNode, GlobalVelocityNode, Lfo, LfoLoopMode,
+    LfoRetriggerMode, LfoWaveform, Limiter, Looper, LooperCommand, LooperSpeed, LooperState, Mixer,
+    Mseg, MsegConfig, Multiband, NoiseGate, NoiseGenerator, NoiseType, NoiseUpdate, Parallel,
+    SampleData, Sampler, SamplerLoopMode, SamplerPlaybackMode, SamplerTriggerMode, Saturation,
+    SaturationCharacter, StereoEnhancer, Waveform, WavetableBank, WavetableOscillator,
+    WavetableOscillatorStateUpdate, ZoneMapping, DEFAULT_RELEASE_VELOCITY, KEYTRACK_REFERENCE_HZ,
 };
-use crate::traits::{AudioNode, PortId};
+use crate::traits::{AudioNode, PortId, QualityMode};
+use crate::utils::aliasing::{measure_aliasing, render_tone};
+use crate::utils::analog_spread::{patch_seed, VoiceVariation};
+use crate::utils::correlation::{analyze_mono_compatibility, CorrelationMeter};
+use crate::utils::groove::Groove;
+use crate::utils::null_test::compare_renders;
+use crate::utils::simd_kernels;
 use crate::voice::Voice;
 use base64::engine::general_purpose::STANDARD as BASE64_ENGINE;
 use base64::Engine as _;
+use rand::Rng;
 use rustc_hash::FxHashMap;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json;
 use std::{
     cell::RefCell,
-    collections::HashMap,
-    io::Cursor,
+    collections::{HashMap, HashSet},
     rc::Rc,
     sync::Arc,
 };
 
 #[cfg(feature = "wasm")]
-use wasm_bindgen::prelude::*;
+use wasm_bindgen::{prelude::*, JsCast};
 #[cfg(feature = "wasm")]
 use web_sys::{console, js_sys};
 
-use hound;
 use std::error::Error;
+const MAX_TABLE_SIZE: usize = 2048;
 const EFFECT_NODE_ID_OFFSET: usize = 10_000;
 
 #[cfg(target_arch = "wasm32")]
@@ -53,42 +113,31 @@ fn log_console(message: &str) {
 #[cfg(not(target_arch = "wasm32"))]
 fn log_console(_message: &str) {}
 
-fn import_wav_hound_reader<R: std::io::Read>(
-    reader: R,
+/// Reads the object form of an `update_*_params` call.
+fn parse_update_params<T: DeserializeOwned>(params: JsValue) -> Result<T, JsValue> {
+    serde_wasm_bindgen::from_value(params)
+        .map_err(|e| JsValue::from_str(&format!("Invalid update params: {}", e)))
+}
+
+/// Builds a morph collection from a `.wt` or WAV wavetable (see
+/// `read_wavetable_file`). `sample_rate` stands in for files that don't
+/// store one.
+fn import_wavetable_file(
+    data: &[u8],
     base_size: usize,
+    sample_rate: f32,
 ) -> Result<WavetableMorphCollection, Box<dyn Error>> {
-    let mut wav_reader = hound::WavReader::new(reader)?;
-    let spec = wav_reader.spec();
-    let sample_rate = spec.sample_rate as f32;
-
-    // Read samples from the WAV (handling various bit depths/formats).
-    let samples: Vec<f32> = match (spec.bits_per_sample, spec.sample_format) {
-        (32, hound::SampleFormat::Float) => {
-            wav_reader.samples::<f32>().map(|s| s.unwrap()).collect()
-        }
-        (16, hound::SampleFormat::Int) => wav_reader
-            .samples::<i16>()
-            .map(|s| s.unwrap() as f32 / i16::MAX as f32)
-            .collect(),
-        (24, hound::SampleFormat::Int) => {
-            let shift = 32 - 24;
-            wav_reader
-                .samples::<i32>()
-                .map(|s| (s.unwrap() << shift >> shift) as f32 / 8_388_607.0)
-                .collect()
-        }
-        (32, hound::SampleFormat::Int) => wav_reader
-            .samples::<i32>()
-            .map(|s| s.unwrap() as f32 / i32::MAX as f32)
-            .collect(),
-        (bits, format) => {
-            return Err(format!(
-                "Unsupported WAV format: bits_per_sample={} sample_format={:?}",
-                bits, format
-            )
-            .into())
-        }
-    };
+    if base_size > MAX_WAVETABLE_BASE_SIZE {
+        return Err(format!(
+            "Wavetable base size must be at most {}",
+            MAX_WAVETABLE_BASE_SIZE
+        )
+        .into());
+    }
+    let file = read_wavetable_file(data, base_size)?;
+    let sample_rate = file.sample_rate.unwrap_or(sample_rate);
+    let base_size = file.cycle_size;
+    let samples = file.samples;
 
     let total_samples = samples.len();
     if total_samples % base_size != 0 {
@@ -148,6 +197,18 @@ struct JsEnvelopeConfig {
     decay_curve: f32,
     #[serde(rename = "releaseCurve")]
     release_curve: f32,
+    #[serde(default, rename = "velocityAttack")]
+    velocity_attack: f32,
+    #[serde(default, rename = "velocityDecay")]
+    velocity_decay: f32,
+    #[serde(default, rename = "keyDecay")]
+    key_decay: f32,
+    #[serde(default, rename = "keyRelease")]
+    key_release: f32,
+    #[serde(default, rename = "velocityRelease")]
+    velocity_release: f32,
+    #[serde(default)]
+    drone: bool,
 }
 impl From<JsEnvelopeConfig> for EnvelopeConfig {
     fn from(js_conf: JsEnvelopeConfig) -> Self {
@@ -161,6 +222,13 @@ impl From<JsEnvelopeConfig> for EnvelopeConfig {
             release_curve: js_conf.release_curve,
             attack_smoothing_samples: 16, // a sensible default
             active: js_conf.active,
+            gate_threshold: 0.0,
+            velocity_attack: js_conf.velocity_attack,
+            velocity_decay: js_conf.velocity_decay,
+            key_decay: js_conf.key_decay,
+            key_release: js_conf.key_release,
+            velocity_release: js_conf.velocity_release,
+            drone: js_conf.drone,
         }
     }
 }
@@ -218,6 +286,24 @@ impl From<ModulationType> for WasmModulationType {
     }
 }
 
+/// A full operator config, coarse tuning included, so nothing is kept from the node.
+impl From<FmOperatorConfig> for FmOperatorUpdate {
+    fn from(config: FmOperatorConfig) -> Self {
+        FmOperatorUpdate {
+            waveform: config.waveform as u8,
+            fixed: config.fixed,
+            ratio: config.ratio,
+            fixed_frequency: config.fixed_frequency,
+            detune_oct: Some(config.detune_oct),
+            detune_semi: Some(config.detune_semi),
+            detune: config.detune,
+            feedback: config.feedback,
+            level: config.level,
+            active: config.active,
+        }
+    }
+}
+
 #[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub struct AudioEngine {
     voices: Vec<Voice>,
@@ -230,7 +316,45 @@ pub struct AudioEngine {
     cpu_time_accum: f64,   // accumulated processing time (seconds)
     audio_time_accum: f64, // accumulated quantum time (seconds)
     last_cpu_usage: f32,   // last computed average (%)
+    quality_mode: QualityMode,
+    overload: OverloadProtection,
+    node_health_checks: bool,
+    /// Whether voice graphs carry the right channel between stereo nodes.
+    stereo_voices: bool,
+    /// Analog spread amount (0..1) and the current patch's seed for it.
+    analog_spread: f32,
+    analog_spread_seed: u32,
+    /// Strict real-time mode's fixed capacities, and the engine-wide edits
+    /// refused for them (voice graphs keep their own).
+    capacity_limits: Option<CapacityLimits>,
+    capacity_events: Vec<CapacityExceeded>,
+    allocator: VoiceAllocator,
+    locks: ParameterLocks,
+    parts: Parts,
+    kit: DrumKit,
+    choke: ChokeGroups,
+    headroom: PolyphonyCompensation,
+    transport: Transport,
+    output: OutputStage,
+    surround: SurroundPanner,
+    snapshots: SnapshotRecorder,
+    /// Receives each snapshot's JSON; without one they wait for `take_snapshot`.
+    snapshot_callback: Option<js_sys::Function>,
+    pending_snapshot: Option<SessionSnapshot>,
+    /// Voice layout of the patch last loaded, for `apply_patch_incremental`.
+    loaded_layout: Option<PatchVoiceLayout>,
+    /// Fingerprints of the audio assets imported with it.
+    loaded_assets: HashMap<String, u64>,
+    jobs: JobQueue,
+    imports: ImportHistory,
+    /// Whether new oscillators and filters get levels from `node_levels`.
+    auto_node_levels: bool,
+    last_node_levels: Option<NodeLevels>,
+    scope: XyScope,
+    correlation: CorrelationMeter,
     block_size: usize,
+    /// Scratch for `process_audio_multichannel`, reused between blocks.
+    channel_scratch: Vec<f32>,
 }
 
 /// Internal representation of LFO update parameters used by the engine.
@@ -348,7 +472,6 @@ impl AudioEngine {
     #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
     pub fn new(sample_rate: f32) -> Self {
         let num_voices = 8;
-        let max_table_size = 2048;
         let buffer_size = 128;
         log_console(&format!(
             "INITIALIZING AUDIO ENGINE WITH {} VOICES",
@@ -361,7 +484,7 @@ impl AudioEngine {
         banks.insert(
             Waveform::Sine,
             Arc::new(
-                WavetableBank::new(Waveform::Sine, max_table_size, sample_rate)
+                WavetableBank::new(Waveform::Sine, MAX_TABLE_SIZE, sample_rate)
                     .expect("Failed to create Sine wavetable bank"),
             ),
         );
@@ -369,21 +492,21 @@ impl AudioEngine {
         banks.insert(
             Waveform::Saw,
             Arc::new(
-                WavetableBank::new(Waveform::Saw, max_table_size, sample_rate)
+                WavetableBank::new(Waveform::Saw, MAX_TABLE_SIZE, sample_rate)
                     .expect("Failed to create Saw wavetable bank"),
             ),
         );
         banks.insert(
             Waveform::Square,
             Arc::new(
-                WavetableBank::new(Waveform::Square, max_table_size, sample_rate)
+                WavetableBank::new(Waveform::Square, MAX_TABLE_SIZE, sample_rate)
                     .expect("Failed to create Square wavetable bank"),
             ),
         );
         banks.insert(
             Waveform::Triangle,
             Arc::new(
-                WavetableBank::new(Waveform::Triangle, max_table_size, sample_rate)
+                WavetableBank::new(Waveform::Triangle, MAX_TABLE_SIZE, sample_rate)
                     .expect("Failed to create Triangle wavetable bank"),
             ),
         );
@@ -399,18 +522,81 @@ impl AudioEngine {
             cpu_time_accum: 0.0,
             audio_time_accum: 0.0,
             last_cpu_usage: 0.0,
+            quality_mode: QualityMode::default(),
+            overload: OverloadProtection::new(),
+            node_health_checks: false,
+            stereo_voices: false,
+            analog_spread: 0.0,
+            analog_spread_seed: 0,
+            allocator: VoiceAllocator::new(),
+            locks: ParameterLocks::new(),
+            parts: Parts::new(),
+            kit: DrumKit::new(sample_rate),
+            choke: ChokeGroups::new(sample_rate),
+            headroom: PolyphonyCompensation::new(sample_rate),
+            transport: Transport::new(sample_rate),
+            output: OutputStage::new(),
+            surround: SurroundPanner::new(sample_rate),
+            snapshots: SnapshotRecorder::new(),
+            snapshot_callback: None,
+            pending_snapshot: None,
+            loaded_layout: None,
+            loaded_assets: HashMap::new(),
+            jobs: JobQueue::new(),
+            imports: ImportHistory::default(),
+            auto_node_levels: false,
+            last_node_levels: None,
+            scope: XyScope::new(),
+            correlation: CorrelationMeter::new(sample_rate),
+            capacity_limits: None,
+            capacity_events: Vec::new(),
             block_size: buffer_size,
+            channel_scratch: vec![0.0; buffer_size * 2],
         }
     }
 
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
     pub fn init(&mut self, sample_rate: f32, num_voices: usize) {
         self.sample_rate = sample_rate;
+        self.kit.set_sample_rate(sample_rate);
+        self.choke.set_sample_rate(sample_rate);
+        self.headroom.set_sample_rate(sample_rate);
+        self.transport.set_sample_rate(sample_rate);
+        self.surround.set_sample_rate(sample_rate);
+        self.correlation.set_sample_rate(sample_rate);
+        let mut num_voices = num_voices;
+        if let Some(limits) = self.capacity_limits {
+            if let Err(exceeded) = limits.check(CapacityResource::Voices, num_voices) {
+                push_capacity_event(&mut self.capacity_events, exceeded);
+                num_voices = limits.max_voices;
+            }
+        }
         self.num_voices = num_voices;
 
         self.voices = (0..num_voices)
             .map(|id| Voice::new(id, self.block_size))
             .collect();
+        self.allocator.reset();
+        self.loaded_layout = None;
+        self.loaded_assets.clear();
+        let quality_mode = self.effective_quality_mode();
+        for (index, voice) in self.voices.iter_mut().enumerate() {
+            voice.graph.set_sample_rate(sample_rate);
+            voice.graph.set_quality_mode(quality_mode);
+            voice.graph.set_health_monitoring(self.node_health_checks);
+            voice.graph.set_stereo(self.stereo_voices);
+            voice.graph.set_voice_variation(VoiceVariation::for_voice(
+                self.analog_spread_seed,
+                index,
+                self.analog_spread,
+            ));
+            // Fresh graphs only hold the global nodes, so this can only fail
+            // for limits too small to run anything; report it and go on.
+            if let Err(exceeded) = voice.graph.set_capacity_limits(self.capacity_limits) {
+                push_capacity_event(&mut self.capacity_events, exceeded);
+            }
+        }
+        self.effect_stack.set_sample_rate(sample_rate);
         self.add_chorus().unwrap();
         self.add_delay(2000.0, 500.0, 0.5, 0.1).unwrap();
         self.add_freeverb(0.95, 0.5, 0.3, 0.7, 1.0).unwrap();
@@ -420,10 +606,36 @@ impl AudioEngine {
             .unwrap();
         self.add_saturation(2.0, 0.5, false).unwrap();
         self.add_bitcrusher(12, 4, 0.5, false).unwrap();
+        self.add_stereo_enhancer(0.0, 12.0, 1.0, 1.0, 1.0, false)
+            .unwrap();
+        self.add_binaural(0.0, 0.0, 1.0, false).unwrap();
+        self.add_looper(1.0, false).unwrap();
+        self.add_auto_wah(0.5, 300.0, 3.0, 4.0, AutoWahDirection::Up, 1.0, false)
+            .unwrap();
+        self.add_exciter(3_000.0, 0.3, 1.0, false).unwrap();
+        self.add_noise_gate(-50.0, 6.0, 1.0, 50.0, 100.0, -80.0, false)
+            .unwrap();
+        self.add_multiband(vec![200.0, 2_000.0], false).unwrap();
+        self.add_parallel(0.5, false).unwrap();
+        self.add_eq(4, false).unwrap();
         //self.add_hall_reverb(2.0, 0.8, sample_rate).unwrap();
         log_console(&format!("plate reverb added"));
     }
 
+    /// `init` with the output stage configured up front.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = initWithOutput))]
+    pub fn init_with_output(
+        &mut self,
+        sample_rate: f32,
+        num_voices: usize,
+        mode: OutputMode,
+        format: OutputFormat,
+    ) {
+        self.set_output_mode(mode);
+        self.output.set_format(format);
+        self.init(sample_rate, num_voices);
+    }
+
     #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = initWithPatch))]
     pub fn init_with_patch(&mut self, patch_json: &str) -> Result<usize, JsValue> {
         log_console(&format!(
@@ -504,55 +716,64 @@ impl AudioEngine {
         // Ensure mutable so we can inject defaults like glide
         let mut patch = patch;
 
-        // Ensure a glide node exists to route global frequency through
-        if let Some(canonical) = patch.synth_state.layout.canonical_voice.as_mut() {
-            let has_glide = canonical
-                .nodes
-                .get("glide")
-                .map(|v| !v.is_empty())
-                .unwrap_or(false);
-            if !has_glide {
-                let new_id = NodeId::new().0.to_string();
-                canonical
-                    .nodes
-                    .entry("glide".to_string())
-                    .or_default()
-                    .push(super::patch::PatchNode {
-                        id: new_id.clone(),
-                        node_type: "glide".to_string(),
-                        name: "Glide".to_string(),
-                    });
-                patch.synth_state.glides.entry(new_id.clone()).or_insert(
-                    super::patch::GlideState {
-                        glide_id: new_id,
-                        time: 0.0,
-                        rise_time: None,
-                        fall_time: None,
-                        active: false,
-                    },
-                );
-            }
-        }
+        add_missing_global_nodes(&mut patch, None);
 
         let layout = &patch.synth_state.layout;
         let voice_count = layout.resolved_voice_count();
         if voice_count == 0 {
             return Err(JsValue::from_str("Patch contains no voices"));
         }
+        if voice_count > MAX_PATCH_VOICES {
+            return Err(JsValue::from_str(&format!(
+                "Patch asks for {} voices, limit {}",
+                voice_count, MAX_PATCH_VOICES
+            )));
+        }
+        if let Some(limits) = self.capacity_limits {
+            if let Err(exceeded) = limits.check(CapacityResource::Voices, voice_count) {
+                push_capacity_event(&mut self.capacity_events, exceeded);
+                return Err(JsValue::from_str(&exceeded.to_string()));
+            }
+        }
 
         self.num_voices = voice_count;
         self.voices = (0..voice_count)
             .map(|id| Voice::new(id, self.block_size))
             .collect();
-
-        for voice in &mut self.voices {
+        self.allocator.reset();
+        self.analog_spread_seed = patch_seed(&patch.metadata.id);
+
+        let quality_mode = self.effective_quality_mode();
+        for (index, voice) in self.voices.iter_mut().enumerate() {
+            voice.graph.set_quality_mode(quality_mode);
+            voice.graph.set_health_monitoring(self.node_health_checks);
+            voice.graph.set_stereo(self.stereo_voices);
+            voice.graph.set_voice_variation(VoiceVariation::for_voice(
+                self.analog_spread_seed,
+                index,
+                self.analog_spread,
+            ));
             voice.clear();
+            if let Err(exceeded) = voice.graph.set_capacity_limits(self.capacity_limits) {
+                push_capacity_event(&mut self.capacity_events, exceeded);
+                return Err(JsValue::from_str(&exceeded.to_string()));
+            }
             voice.graph.global_frequency_node = None;
             voice.graph.global_velocity_node = None;
+            voice.graph.global_pressure_node = None;
+            voice.graph.global_timbre_node = None;
+            voice.graph.global_release_velocity_node = None;
             voice.graph.global_gatemixer_node = None;
         }
 
         self.effect_stack = EffectStack::new(self.block_size);
+        self.effect_stack
+            .set_quality_mode(self.effective_quality_mode());
+        self.effect_stack
+            .set_limiters_bypassed(self.output.mode().bypasses_limiter());
+        self.effect_stack
+            .set_spread_capture(self.surround.is_enabled());
+        self.effect_stack.set_sample_rate(self.sample_rate);
         self.ir_generator = ImpulseResponseGenerator::new(self.sample_rate);
         self.add_chorus()?;
         self.add_delay(2000.0, 500.0, 0.5, 0.1)?;
@@ -562,6 +783,15 @@ impl AudioEngine {
         self.add_compressor(-12.0, 4.0, 10.0, 80.0, 3.0, 0.5)?;
         self.add_saturation(2.0, 0.5, false)?;
         self.add_bitcrusher(12, 4, 0.5, false)?;
+        self.add_stereo_enhancer(0.0, 12.0, 1.0, 1.0, 1.0, false)?;
+        self.add_binaural(0.0, 0.0, 1.0, false)?;
+        self.add_looper(1.0, false)?;
+        self.add_auto_wah(0.5, 300.0, 3.0, 4.0, AutoWahDirection::Up, 1.0, false)?;
+        self.add_exciter(3_000.0, 0.3, 1.0, false)?;
+        self.add_noise_gate(-50.0, 6.0, 1.0, 50.0, 100.0, -80.0, false)?;
+        self.add_multiband(vec![200.0, 2_000.0], false)?;
+        self.add_parallel(0.5, false)?;
+        self.add_eq(4, false)?;
 
         let canonical_voice = layout
             .canonical_voice()
@@ -569,6 +799,17 @@ impl AudioEngine {
 
         self.build_nodes_from_canonical_voice(canonical_voice)?;
         self.connect_from_canonical_voice(canonical_voice)?;
+        // Nodes and connections past strict real-time capacity were left out.
+        if let Some(exceeded) = self
+            .voices
+            .iter()
+            .find_map(|voice| voice.graph.capacity_events.first())
+        {
+            return Err(JsValue::from_str(&format!(
+                "Patch does not fit: {}",
+                exceeded
+            )));
+        }
 
         // DEBUG: inspect sampler-related connections after building from patch
         if let (Some(sampler_id_str), Some(filter_id_str)) = (
@@ -632,12 +873,81 @@ impl AudioEngine {
             );
         }
 
-        self.apply_patch_states(&patch, canonical_voice)?;
+        self.apply_patch_states(&patch.synth_state, canonical_voice)?;
         self.import_audio_assets(&patch.audio_assets)?;
+        self.loaded_layout = Some(canonical_voice.clone());
+        self.loaded_assets = asset_fingerprints(&patch.audio_assets);
+        self.snapshots.patch_loaded(patch_json);
 
         Ok(voice_count)
     }
 
+    /// Applies an edited version of the loaded patch without rebuilding the voices: only
+    /// the nodes and connections that changed are added or removed and parameters are
+    /// updated in place, so ringing notes carry on. Audio assets are only imported when
+    /// their data changed, or for new samplers. Falls back to `init_with_patch` when no
+    /// patch is loaded, the voice count or the global nodes changed, or nodes were created
+    /// or deleted one by one since the last load. Returns the voice count.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = applyPatchIncremental))]
+    pub fn apply_patch_incremental(&mut self, patch_json: &str) -> Result<usize, JsValue> {
+        let mut patch: PatchFile = serde_json::from_str(patch_json)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse patch JSON: {}", e)))?;
+        add_missing_global_nodes(&mut patch, self.loaded_layout.as_ref());
+
+        let layout = &patch.synth_state.layout;
+        let canonical_voice = layout
+            .canonical_voice()
+            .ok_or_else(|| JsValue::from_str("Patch layout missing voice data"))?;
+        let diff = match (&self.loaded_layout, self.voices.first()) {
+            (Some(loaded), Some(voice))
+                if layout.resolved_voice_count() == self.voices.len()
+                    && graph_matches_layout(loaded, voice.graph.nodes.keys().copied()) =>
+            {
+                LayoutDiff::between(loaded, canonical_voice).map_err(|e| JsValue::from_str(&e))?
+            }
+            _ => None,
+        };
+        let Some(diff) = diff else {
+            return self.init_with_patch(patch_json);
+        };
+
+        for connection in &diff.removed_connections {
+            self.disconnect_patch_connection(connection)?;
+        }
+        for &node_id in &diff.removed_nodes {
+            for voice in &mut self.voices {
+                voice.graph.delete_node(node_id);
+            }
+        }
+        let mut sampler_cache = HashMap::new();
+        for (node_type, node_id) in &diff.added_nodes {
+            self.instantiate_node(node_type, *node_id, &mut sampler_cache)?;
+        }
+        for connection in &diff.added_connections {
+            self.connect_patch_connection(connection)?;
+        }
+        self.apply_patch_states(&patch.synth_state, canonical_voice)?;
+
+        let added: HashSet<NodeId> = diff.added_nodes.iter().map(|(_, id)| *id).collect();
+        let fingerprints = asset_fingerprints(&patch.audio_assets);
+        patch.audio_assets.retain(|_, asset| {
+            let changed = self.loaded_assets.get(&asset.id) != fingerprints.get(&asset.id);
+            changed
+                || match parse_audio_asset_id(&asset.id) {
+                    Some((kind, node_id)) if kind == "sample" => {
+                        parse_node_id(&node_id).is_ok_and(|id| added.contains(&id))
+                    }
+                    _ => false,
+                }
+        });
+        self.import_audio_assets(&patch.audio_assets)?;
+        self.loaded_layout = Some(canonical_voice.clone());
+        self.loaded_assets = fingerprints;
+        self.snapshots.patch_loaded(patch_json);
+
+        Ok(self.voices.len())
+    }
+
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
     pub fn remove_connection(
         &mut self,
@@ -742,6 +1052,158 @@ impl AudioEngine {
         master_gain: f32,
         output_left: &mut [f32],
         output_right: &mut [f32],
+    ) {
+        self.process_audio_internal(
+            gates,
+            frequencies,
+            gains,
+            &[],
+            velocities,
+            &[],
+            &[],
+            &[],
+            &[],
+            macro_values,
+            master_gain,
+            output_left,
+            output_right,
+        );
+    }
+
+    /// Renders one block of `frame` in the current channel layout into
+    /// `outputs`, one Float32Array per channel of the same length: front
+    /// left/right, then the rear pair for quad or C, LFE, Ls, Rs for 5.1.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn process_audio_multichannel(
+        &mut self,
+        frame: &AutomationFrame,
+        master_gain: f32,
+        outputs: &js_sys::Array,
+    ) -> Result<(), JsValue> {
+        let channels = self.surround.layout().channel_count();
+        if outputs.length() as usize != channels {
+            return Err(JsValue::from_str(&format!(
+                "Expected {} output buffers, got {}",
+                channels,
+                outputs.length()
+            )));
+        }
+        for index in 0..outputs.length() {
+            if !outputs.get(index).is_instance_of::<js_sys::Float32Array>() {
+                return Err(JsValue::from_str("Output buffers must be Float32Arrays"));
+            }
+        }
+        let output = |index: u32| outputs.get(index).unchecked_into::<js_sys::Float32Array>();
+        let block_len = output(0).length();
+        if (1..outputs.length()).any(|index| output(index).length() != block_len) {
+            return Err(JsValue::from_str(
+                "Output buffers must have the same length",
+            ));
+        }
+
+        let block_len = block_len as usize;
+        if self.channel_scratch.len() < block_len * 2 {
+            self.channel_scratch.resize(block_len * 2, 0.0);
+        }
+        let mut scratch = std::mem::take(&mut self.channel_scratch);
+        let (left, right) = scratch[..block_len * 2].split_at_mut(block_len);
+        left.fill(0.0);
+        right.fill(0.0);
+        self.process_with_frame(frame, master_gain, left, right);
+        output(0).copy_from(left);
+        output(1).copy_from(right);
+
+        // The front pair is out, so the left half doubles as the scratch for
+        // the remaining channels.
+        for (index, channel) in (2..).zip(self.surround.extra_channels()) {
+            let len = block_len.min(channel.len());
+            left[..len].copy_from_slice(&channel[..len]);
+            left[len..].fill(0.0);
+            self.output.apply_mode(left);
+            output(index).copy_from(left);
+        }
+        self.channel_scratch = scratch;
+        Ok(())
+    }
+
+    /// Quality mode in effect: Eco while overload protection is degrading,
+    /// otherwise the requested mode.
+    fn effective_quality_mode(&self) -> QualityMode {
+        if self.overload.is_degraded() {
+            QualityMode::Eco
+        } else {
+            self.quality_mode
+        }
+    }
+
+    fn apply_quality_mode(&mut self, mode: QualityMode) {
+        for voice in &mut self.voices {
+            voice.graph.set_quality_mode(mode);
+        }
+        for (voice, _) in self.parts.extra_voices_mut() {
+            voice.graph.set_quality_mode(mode);
+        }
+        for voice in self.kit.voices_mut() {
+            voice.graph.set_quality_mode(mode);
+        }
+        self.effect_stack.set_quality_mode(mode);
+    }
+
+    fn handle_overload(&mut self) {
+        let response = self
+            .overload
+            .evaluate(self.last_cpu_usage, self.effect_stack.quality_mode());
+        self.respond_to_overload(response);
+    }
+
+    fn respond_to_overload(&mut self, response: OverloadResponse) {
+        let cpu_usage = self.last_cpu_usage;
+        match response {
+            OverloadResponse::None => {}
+            OverloadResponse::ReduceQuality => {
+                self.apply_quality_mode(QualityMode::Eco);
+                self.overload
+                    .record(OverloadAction::ReducedQuality, cpu_usage);
+            }
+            OverloadResponse::CullVoices => {
+                let mut count = 0;
+                for voice in &mut self.voices {
+                    if voice.is_active()
+                        && voice.current_gate <= 0.0
+                        && voice.output_rms() < CULL_RMS_THRESHOLD
+                    {
+                        voice.cull();
+                        count += 1;
+                    }
+                }
+                if count > 0 {
+                    self.overload
+                        .record(OverloadAction::CulledVoices { count }, cpu_usage);
+                }
+            }
+            OverloadResponse::RestoreQuality => {
+                self.apply_quality_mode(self.quality_mode);
+                self.overload
+                    .record(OverloadAction::RestoredQuality, cpu_usage);
+            }
+        }
+    }
+
+    fn process_audio_internal(
+        &mut self,
+        gates: &[f32],
+        frequencies: &[f32],
+        gains: &[f32],
+        gain_ends: &[f32],
+        velocities: &[f32],
+        velocity_ends: &[f32],
+        pressures: &[f32],
+        timbres: &[f32],
+        release_velocities: &[f32],
+        macro_values: &[f32],
+        master_gain: f32,
+        output_left: &mut [f32],
+        output_right: &mut [f32],
     ) {
         // Begin CPU measurement:
         #[cfg(feature = "wasm")]
@@ -754,6 +1216,9 @@ impl AudioEngine {
 
         let mut voice_left = vec![0.0; output_left.len()];
         let mut voice_right = vec![0.0; output_right.len()];
+        // Parts that don't send fully to the effects are mixed in here.
+        let mut dry_left = vec![0.0; output_left.len()];
+        let mut dry_right = vec![0.0; output_right.len()];
 
         let block_len = output_left.len().max(1);
         // Parameter voice count is dictated by the automation adapter (fixed to descriptors, usually 8).
@@ -786,8 +1251,37 @@ impl AudioEngine {
         };
         let voice_macro_stride = 4 * macro_buffer_len;
 
-        // Process all voices and mix them
-        for (i, voice) in self.voices.iter_mut().enumerate() {
+        // Gate onsets choke the other voices of their group before any voice
+        // is rendered.
+        let gate_of = |i: usize| {
+            if gates.is_empty() {
+                return self
+                    .allocator
+                    .note(i)
+                    .map_or(0.0, |allocated| allocated.gate());
+            }
+            let start = i.saturating_mul(gate_buffer_len);
+            match gates.get(start..(start + gate_buffer_len).min(gates.len())) {
+                Some(slice) if i < param_voice_count && !slice.is_empty() => {
+                    slice.iter().copied().fold(0.0_f32, f32::max)
+                }
+                _ => gates.get(i).copied().unwrap_or(0.0),
+            }
+        };
+        self.choke.begin_block(gate_of);
+
+        self.effect_stack.begin_sidechain_block(block_len);
+        self.surround.begin_block(block_len);
+        let transport_clock = self.transport.advance(block_len);
+        // Process all voices of every part and mix them
+        let main_gains = self.parts.main_config().bus_gains();
+        let voices = self
+            .voices
+            .iter_mut()
+            .map(|voice| (voice, main_gains))
+            .chain(self.parts.extra_voices_mut());
+        let mut active_voices = 0;
+        for (i, (voice, (send_gain, dry_gain))) in voices.enumerate() {
             let gate_slice = if gate_buffer_len > 0 && i < param_voice_count {
                 let start = i.saturating_mul(gate_buffer_len);
                 let end = (start + gate_buffer_len).min(gates.len());
@@ -801,6 +1295,12 @@ impl AudioEngine {
             } else {
                 gate_slice.iter().copied().fold(0.0_f32, f32::max)
             };
+            // Choked voices stay released until the host lets go of the gate.
+            let (gate_slice, gate) = if self.choke.is_held_off(i) {
+                (&[][..], 0.0)
+            } else {
+                (gate_slice, gate)
+            };
             let frequency_slice = if frequency_buffer_len > 0 && i < param_voice_count {
                 let start = i.saturating_mul(frequency_buffer_len);
                 let end = (start + frequency_buffer_len).min(frequencies.len());
@@ -813,12 +1313,50 @@ impl AudioEngine {
             } else {
                 *frequency_slice.first().unwrap_or(&440.0)
             };
-            let gain = gains.get(i).copied().unwrap_or(1.0);
             let velocity = velocities.get(i).copied().unwrap_or(0.0);
+            // Voices assigned by `note_on` play their note when the host sends
+            // no gate buffers.
+            let allocated = if gates.is_empty() {
+                self.allocator.note(i)
+            } else {
+                None
+            };
+            let release_velocity = release_velocities
+                .get(i)
+                .copied()
+                .unwrap_or(DEFAULT_RELEASE_VELOCITY);
+            let (gate, frequency, frequency_slice, velocity, release_velocity) = match allocated {
+                Some(allocated) => {
+                    // Held-off voices fade out on the note they were playing.
+                    let (gate, frequency) = if self.choke.is_held_off(i) {
+                        (0.0, voice.current_frequency)
+                    } else {
+                        (allocated.gate(), allocated.frequency())
+                    };
+                    (
+                        gate,
+                        frequency,
+                        &[][..],
+                        allocated.velocity,
+                        allocated.release_velocity,
+                    )
+                }
+                None => (gate, frequency, frequency_slice, velocity, release_velocity),
+            };
+            let gain = gains.get(i).copied().unwrap_or(1.0);
+            let gain_end = gain_ends.get(i).copied().unwrap_or(gain);
+            let velocity_end = velocity_ends.get(i).copied().unwrap_or(velocity);
+            let pressure = pressures.get(i).copied().unwrap_or(0.0);
+            let timbre = timbres.get(i).copied().unwrap_or(0.0);
 
             voice.current_gate = gate;
             voice.current_frequency = frequency;
             voice.current_velocity = velocity;
+            voice.velocity_ramp_target = (velocity_end != velocity).then_some(velocity_end);
+            voice.current_pressure = pressure;
+            voice.current_timbre = timbre;
+            voice.current_release_velocity = release_velocity;
+            voice.graph.set_transport_clock(transport_clock);
 
             // Update macro values
             if macro_buffer_len > 0 && i < param_voice_count {
@@ -835,9 +1373,10 @@ impl AudioEngine {
             voice_right.fill(0.0);
 
             // Process voice audio
-            let zero_gate = [0.0_f32];
+            // Allocated notes have no gate buffer; theirs is the allocator's gate.
+            let held_gate = [if allocated.is_some() { gate } else { 0.0 }];
             let gate_buffer = if gate_slice.is_empty() {
-                &zero_gate
+                &held_gate
             } else {
                 gate_slice
             };
@@ -854,18 +1393,58 @@ impl AudioEngine {
                 &mut voice_left,
                 &mut voice_right,
             );
+            if self.choke.apply_fade(i, &mut voice_left, &mut voice_right) {
+                voice.cull();
+            }
+            if voice.active {
+                active_voices += 1;
+            }
+            self.effect_stack
+                .accumulate_sidechain(|node_id, port| voice.node_output(node_id, port));
+            self.surround.pan_voice(
+                i,
+                &mut voice_left,
+                &mut voice_right,
+                gain,
+                gain_end,
+                send_gain + dry_gain,
+            );
 
-
-            // Mix voice into main mix buffers with gain
+            // Mix voice into main mix buffers with gain, ramping towards gain_end
+            let gain_step = (gain_end - gain) / voice_left.len().max(1) as f32;
             for (i, (left, right)) in voice_left.iter().zip(voice_right.iter()).enumerate() {
-                mix_left[i] += left * gain;
-                mix_right[i] += right * gain;
+                let g = gain + gain_step * i as f32;
+                mix_left[i] += left * g * send_gain;
+                mix_right[i] += right * g * send_gain;
+                if dry_gain != 0.0 {
+                    dry_left[i] += left * g * dry_gain;
+                    dry_right[i] += right * g * dry_gain;
+                }
             }
         }
 
+        self.headroom.apply(
+            active_voices,
+            [&mut mix_left, &mut mix_right, &mut dry_left, &mut dry_right],
+        );
+
+        // Kit pads go through the effects with the voices.
+        self.kit.process(
+            &mut mix_left,
+            &mut mix_right,
+            &mut voice_left,
+            &mut voice_right,
+        );
+
         // Process through effect stack
         self.effect_stack
             .process_audio(&mix_left, &mix_right, output_left, output_right);
+        for (out, dry) in output_left.iter_mut().zip(&dry_left) {
+            *out += dry;
+        }
+        for (out, dry) in output_right.iter_mut().zip(&dry_right) {
+            *out += dry;
+        }
 
         // Apply master gain after effects
         if master_gain != 1.0 {
@@ -877,6 +1456,25 @@ impl AudioEngine {
             }
         }
 
+        // Surround layouts take the rear feeds back out of the front pair.
+        let (spread_left, spread_right) = self.effect_stack.spread_output();
+        self.surround.finish_block(
+            output_left,
+            output_right,
+            spread_left,
+            spread_right,
+            master_gain,
+        );
+        self.output.process(output_left, output_right);
+        self.correlation.process(output_left, output_right);
+        if self.scope.source() == ScopeSource::Master {
+            self.scope.push(output_left, output_right);
+        } else {
+            let extra_voices = self.parts.extra_voices();
+            self.scope
+                .push_nodes(self.voices.iter().chain(extra_voices), block_len);
+        }
+
         #[cfg(feature = "wasm")]
         let elapsed_sec = {
             let end = js_sys::Date::now();
@@ -895,7 +1493,9 @@ impl AudioEngine {
             self.last_cpu_usage = ((self.cpu_time_accum / self.audio_time_accum) * 100.0) as f32;
             self.cpu_time_accum = 0.0;
             self.audio_time_accum = 0.0;
+            self.handle_overload();
         }
+        self.record_snapshot(block_len);
     }
 
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
@@ -906,11 +1506,16 @@ impl AudioEngine {
         output_left: &mut [f32],
         output_right: &mut [f32],
     ) {
-        self.process_audio(
+        self.process_audio_internal(
             frame.gates(),
             frame.frequencies(),
             frame.gains(),
+            frame.gain_ends(),
             frame.velocities(),
+            frame.velocity_ends(),
+            frame.pressures(),
+            frame.timbres(),
+            frame.release_velocities(),
             frame.macro_buffers(),
             master_gain,
             output_left,
@@ -918,14 +1523,118 @@ impl AudioEngine {
         );
     }
 
+    /// Null test / A/B comparison of two stereo renders made at the engine's sample
+    /// rate. Returns residual RMS, null depth and per-octave spectral differences.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn compare_renders(
+        &self,
+        reference_left: &[f32],
+        reference_right: &[f32],
+        candidate_left: &[f32],
+        candidate_right: &[f32],
+    ) -> Result<JsValue, JsValue> {
+        let report = compare_renders(
+            reference_left,
+            reference_right,
+            candidate_left,
+            candidate_right,
+            self.sample_rate,
+        );
+        serde_wasm_bindgen::to_value(&report)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize report: {}", e)))
+    }
+
+    /// How much level a stereo render loses summed to mono, as
+    /// `{ stereoDb, monoDb, lossDb, correlation }`. Render the patch with a
+    /// few notes and pass it here to catch phase-cancelling chorus or widener
+    /// settings.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn analyze_mono_compatibility(
+        &self,
+        left: &[f32],
+        right: &[f32],
+    ) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&analyze_mono_compatibility(left, right))
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize report: {}", e)))
+    }
+
+    /// Stereo correlation of the master output over the last few hundred
+    /// milliseconds: 1.0 mono, 0.0 unrelated channels, below 0.0 out of phase.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_correlation(&self) -> f32 {
+        self.correlation.correlation()
+    }
+
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
     pub fn get_cpu_usage(&self) -> f32 {
         self.last_cpu_usage
     }
 
+    /// Starts keeping `length` samples (up to 8192) of the XY scope signals
+    /// for `xy_scope_capture`; 0 stops it.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_xy_scope_length(&mut self, length: usize) {
+        self.scope.set_length(length);
+    }
+
+    /// Scopes the master output, left on X and right on Y. The default.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_xy_scope_master(&mut self) {
+        self.scope.set_source(ScopeSource::Master);
+    }
+
+    /// Scopes two voice node outputs against each other, summed over voices.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_xy_scope_nodes(
+        &mut self,
+        x_node_id: &str,
+        x_port: PortId,
+        y_node_id: &str,
+        y_port: PortId,
+    ) -> Result<(), JsValue> {
+        let node = |node_id: &str| {
+            let id = NodeId::from_string(node_id)
+                .map_err(|e| JsValue::from_str(&format!("Invalid node ID: {}", e)))?;
+            let exists = self
+                .voices
+                .first()
+                .is_some_and(|voice| voice.graph.get_node(id).is_some());
+            if !exists {
+                return Err(JsValue::from_str("Node not found"));
+            }
+            Ok(id)
+        };
+        let x = (node(x_node_id)?, x_port);
+        let y = (node(y_node_id)?, y_port);
+        self.scope.set_source(ScopeSource::Nodes { x, y });
+        Ok(())
+    }
+
+    /// The latest XY samples as `{ x, y, triggered }`, starting on a rising
+    /// zero crossing of X when there is one; null while the scope is off or
+    /// still filling.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn xy_scope_capture(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.scope.capture())
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize capture: {}", e)))
+    }
+
+    /// Switches the SIMD effect kernels (convolver, 24 dB filters) on or off.
+    /// Returns whether SIMD is in use afterwards; builds without simd128 always
+    /// fall back to the scalar kernels.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_simd_enabled(&mut self, enabled: bool) -> bool {
+        simd_kernels::set_simd_enabled(enabled)
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn is_simd_enabled(&self) -> bool {
+        simd_kernels::simd_enabled()
+    }
+
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
     pub fn add_saturation(&mut self, drive: f32, mix: f32, active: bool) -> Result<usize, JsValue> {
-        let mut saturation = Saturation::new(drive, mix);
+        let mut saturation = Saturation::new(self.sample_rate, drive, mix);
         saturation.set_active(active);
         Ok(self.effect_stack.add_effect(Box::new(saturation)))
     }
@@ -938,68 +1647,202 @@ impl AudioEngine {
         mix: f32,
         active: bool,
     ) -> Result<usize, JsValue> {
-        let mut crusher = Bitcrusher::new(bits, downsample_factor, mix);
+        let mut crusher = Bitcrusher::new(self.sample_rate, bits, downsample_factor, mix);
         crusher.set_active(active);
         Ok(self.effect_stack.add_effect(Box::new(crusher)))
     }
 
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
-    pub fn add_delay(
+    pub fn add_stereo_enhancer(
         &mut self,
-        max_delay_ms: f32,
-        delay_ms: f32,
-        feedback: f32,
+        delay_left_ms: f32,
+        delay_right_ms: f32,
+        width: f32,
+        comb_compensation: f32,
         mix: f32,
+        active: bool,
     ) -> Result<usize, JsValue> {
-        let delay = Delay::new(self.sample_rate, max_delay_ms, delay_ms, feedback, mix);
-        Ok(self.effect_stack.add_effect(Box::new(delay)))
+        let mut enhancer = StereoEnhancer::new(
+            self.sample_rate,
+            delay_left_ms,
+            delay_right_ms,
+            width,
+            comb_compensation,
+            mix,
+        );
+        enhancer.set_active(active);
+        Ok(self.effect_stack.add_effect(Box::new(enhancer)))
     }
 
+    /// Adds a binaural panner for headphone listening (azimuth in degrees
+    /// clockwise from the front, elevation -30 to 90 degrees).
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
-    pub fn add_freeverb(
+    pub fn add_binaural(
         &mut self,
-        room_size: f32,
-        damp: f32,
-        wet: f32,
-        dry: f32,
-        width: f32,
+        azimuth: f32,
+        elevation: f32,
+        mix: f32,
+        active: bool,
     ) -> Result<usize, JsValue> {
-        let reverb = Freeverb::new(self.sample_rate, room_size, damp, wet, dry, width);
-        Ok(self.effect_stack.add_effect(Box::new(reverb)))
+        let mut binaural = Binaural::new(self.sample_rate, azimuth, elevation, mix);
+        binaural.set_active(active);
+        Ok(self.effect_stack.add_effect(Box::new(binaural)))
     }
 
+    /// Adds a looper that records the effect bus at its position in the chain.
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
-    pub fn add_hall_reverb(
+    pub fn add_looper(&mut self, level: f32, active: bool) -> Result<usize, JsValue> {
+        let mut looper = Looper::new(self.sample_rate, level);
+        looper.set_active(active);
+        Ok(self.effect_stack.add_effect(Box::new(looper)))
+    }
+
+    /// Adds an envelope-following band-pass (sensitivity 0-1, sweep from
+    /// `frequency` Hz over `range` octaves).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn add_auto_wah(
         &mut self,
-        decay_time: f32,
-        room_size: f32,
-        sample_rate: f32,
+        sensitivity: f32,
+        frequency: f32,
+        range: f32,
+        q: f32,
+        direction: AutoWahDirection,
+        mix: f32,
+        active: bool,
     ) -> Result<usize, JsValue> {
-        // Validate parameters before processing
-        let decay_time = decay_time.clamp(0.1, 10.0);
-        let rsize = room_size.clamp(0.0, 1.0);
-        // Generate hall reverb impulse response
-        let ir = self.ir_generator.hall(decay_time, rsize);
-
-        if ir.is_empty() {
-            return Err(JsValue::from_str("Generated impulse response is empty"));
-        }
-
-        // Create convolver with bounds checking
-        let mut convolver = Convolver::new(ir, 128, sample_rate);
-        convolver.set_wet_level(0.1);
-        Ok(self.effect_stack.add_effect(Box::new(convolver)))
+        let mut wah = AutoWah::new(self.sample_rate, sensitivity, frequency, range, q, mix);
+        wah.set_direction(direction);
+        wah.set_active(active);
+        Ok(self.effect_stack.add_effect(Box::new(wah)))
     }
 
+    /// Adds a harmonic exciter that brightens the mix above `frequency` Hz.
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
-    pub fn add_plate_reverb(
+    pub fn add_exciter(
         &mut self,
-        decay_time: f32,
-        diffusion: f32,
-        sample_rate: f32,
+        frequency: f32,
+        amount: f32,
+        mix: f32,
+        active: bool,
     ) -> Result<usize, JsValue> {
-        // Validate parameters before processing
-        let decay_time = decay_time.clamp(0.1, 10.0);
+        let mut exciter = Exciter::new(self.sample_rate, frequency, amount, mix);
+        exciter.set_active(active);
+        Ok(self.effect_stack.add_effect(Box::new(exciter)))
+    }
+
+    /// Adds a noise gate with hysteresis and hold. Key it from a voice node
+    /// with `set_effect_sidechain`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn add_noise_gate(
+        &mut self,
+        threshold_db: f32,
+        hysteresis_db: f32,
+        attack_ms: f32,
+        hold_ms: f32,
+        release_ms: f32,
+        range_db: f32,
+        active: bool,
+    ) -> Result<usize, JsValue> {
+        let mut gate = NoiseGate::new(
+            self.sample_rate,
+            threshold_db,
+            hysteresis_db,
+            attack_ms,
+            hold_ms,
+            release_ms,
+            range_db,
+        );
+        gate.set_active(active);
+        Ok(self.effect_stack.add_effect(Box::new(gate)))
+    }
+
+    /// Adds a multiband container splitting at the given crossovers (1 to 3,
+    /// for 2 to 4 bands). Place other effects on its bands with
+    /// `set_effect_band`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn add_multiband(&mut self, crossovers: Vec<f32>, active: bool) -> Result<usize, JsValue> {
+        let mut multiband = Multiband::new(self.sample_rate, &crossovers);
+        multiband.set_active(active);
+        Ok(self.effect_stack.add_effect(Box::new(multiband)))
+    }
+
+    /// Adds a parallel container that runs two chains on the same input and
+    /// blends them (0.0 = chain A only, 1.0 = chain B only). Place other
+    /// effects on chain 0 or 1 with `set_effect_band`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn add_parallel(&mut self, blend: f32, active: bool) -> Result<usize, JsValue> {
+        let mut parallel = Parallel::new(self.sample_rate, blend);
+        parallel.set_active(active);
+        Ok(self.effect_stack.add_effect(Box::new(parallel)))
+    }
+
+    /// Adds a parametric EQ with `band_count` flat bands (up to
+    /// `MAX_EQ_BANDS`): a low shelf, peaks and a high shelf spread from
+    /// 100 Hz to 8 kHz. Shape them with `update_eq_band`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn add_eq(&mut self, band_count: usize, active: bool) -> Result<usize, JsValue> {
+        let mut eq = Equalizer::new(self.sample_rate, &Equalizer::spread_bands(band_count));
+        eq.set_active(active);
+        Ok(self.effect_stack.add_effect(Box::new(eq)))
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn add_delay(
+        &mut self,
+        max_delay_ms: f32,
+        delay_ms: f32,
+        feedback: f32,
+        mix: f32,
+    ) -> Result<usize, JsValue> {
+        let delay = Delay::new(self.sample_rate, max_delay_ms, delay_ms, feedback, mix);
+        Ok(self.effect_stack.add_effect(Box::new(delay)))
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn add_freeverb(
+        &mut self,
+        room_size: f32,
+        damp: f32,
+        wet: f32,
+        dry: f32,
+        width: f32,
+    ) -> Result<usize, JsValue> {
+        let reverb = Freeverb::new(self.sample_rate, room_size, damp, wet, dry, width);
+        Ok(self.effect_stack.add_effect(Box::new(reverb)))
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn add_hall_reverb(
+        &mut self,
+        decay_time: f32,
+        room_size: f32,
+        sample_rate: f32,
+    ) -> Result<usize, JsValue> {
+        // Validate parameters before processing
+        let decay_time = decay_time.clamp(0.1, 10.0);
+        let rsize = room_size.clamp(0.0, 1.0);
+        // Generate hall reverb impulse response
+        let ir = self.ir_generator.hall(decay_time, rsize);
+
+        if ir.is_empty() {
+            return Err(JsValue::from_str("Generated impulse response is empty"));
+        }
+
+        // Create convolver with bounds checking
+        let mut convolver = Convolver::new(ir, 128, sample_rate);
+        convolver.set_wet_level(0.1);
+        Ok(self.effect_stack.add_effect(Box::new(convolver)))
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn add_plate_reverb(
+        &mut self,
+        decay_time: f32,
+        diffusion: f32,
+        sample_rate: f32,
+    ) -> Result<usize, JsValue> {
+        // Validate parameters before processing
+        let decay_time = decay_time.clamp(0.1, 10.0);
         let diffusion = diffusion.clamp(0.0, 1.0);
         // Generate plate reverb impulse response
         let ir = self.ir_generator.plate(decay_time, diffusion);
@@ -1091,6 +1934,9 @@ impl AudioEngine {
 
             if voice.graph.global_frequency_node == Some(node_id)
                 || voice.graph.global_velocity_node == Some(node_id)
+                || voice.graph.global_pressure_node == Some(node_id)
+                || voice.graph.global_timbre_node == Some(node_id)
+                || voice.graph.global_release_velocity_node == Some(node_id)
                 || voice.graph.global_gatemixer_node == Some(node_id)
             {
                 return Err(JsValue::from_str(
@@ -1163,6 +2009,13 @@ impl AudioEngine {
         Ok(())
     }
 
+    /// JSON Schema of the object each `update_*_params` call takes, keyed by
+    /// method name.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_api_schema(&self) -> String {
+        api_schema().to_string()
+    }
+
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
     pub fn update_velocity(
         &mut self,
@@ -1170,6 +2023,36 @@ impl AudioEngine {
         sensitivity: f32,
         randomize: f32,
     ) -> Result<(), JsValue> {
+        self.apply_velocity_update(
+            node_id,
+            VelocityUpdate {
+                sensitivity,
+                randomize,
+            },
+        )
+    }
+
+    /// Object form of `update_velocity`, taking the fields of `VelocityUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_velocity_params(
+        &mut self,
+        node_id: &str,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_velocity_update(node_id, params)
+    }
+
+    fn apply_velocity_update(
+        &mut self,
+        node_id: &str,
+        params: VelocityUpdate,
+    ) -> Result<(), JsValue> {
+        let VelocityUpdate {
+            sensitivity,
+            randomize,
+        } = params;
         let requested_node_id = NodeId::from_string(node_id)
             .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
 
@@ -1204,706 +2087,4863 @@ impl AudioEngine {
         }
     }
 
+    /// Sets the engine-wide transpose (semitones) and fine tune (cents).
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
-    pub fn get_gate_mixer_node_id(&mut self) -> Option<String> {
-        self.voices
-            .get(0)
-            .and_then(|voice| voice.graph.global_gatemixer_node)
-            .map(|id| id.to_string())
+    pub fn set_master_tuning(&mut self, transpose: f32, fine: f32) -> Result<(), JsValue> {
+        self.locks.remember_master_tuning(transpose, fine);
+        for voice in &mut self.voices {
+            voice.graph.set_master_tuning(transpose, fine);
+        }
+        Ok(())
     }
 
+    /// Sets a per-voice detune offset in cents.
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
-    pub fn update_envelope(
-        &mut self,
-        node_id: &str,
-        attack: f32,
-        decay: f32,
-        sustain: f32,
-        release: f32,
-        attack_curve: f32,
-        decay_curve: f32,
-        release_curve: f32,
-        active: bool,
-    ) -> Result<(), JsValue> {
-        let mut errors: Vec<String> = Vec::new();
-
-        let node_id = NodeId::from_string(node_id)
-            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+    pub fn set_voice_detune(&mut self, voice_index: usize, cents: f32) -> Result<(), JsValue> {
+        let voice = self
+            .voices
+            .get_mut(voice_index)
+            .ok_or_else(|| JsValue::from_str(&format!("Invalid voice index: {}", voice_index)))?;
+        voice.graph.set_voice_detune(cents);
+        self.locks.remember_voice_detune(voice_index, cents);
+        Ok(())
+    }
 
-        // Iterate over all voices and attempt to update the envelope.
-        for (i, voice) in self.voices.iter_mut().enumerate() {
-            if let Some(node) = voice.graph.get_node_mut(node_id) {
-                if let Some(env) = node.as_any_mut().downcast_mut::<Envelope>() {
-                    let config = EnvelopeConfig {
-                        attack,
-                        decay,
-                        sustain,
-                        release,
-                        attack_curve,
-                        decay_curve,
-                        release_curve,
-                        attack_smoothing_samples: 16,
-                        active,
-                    };
-                    env.update_config(config);
-                    env.set_active(active);
-                } else {
-                    errors.push(format!("Voice {}: Node is not an Envelope", i));
-                }
-            } else {
-                errors.push(format!("Voice {}: Node not found", i));
-            }
+    /// Analog spread (0..1): gives each voice small fixed offsets to pitch,
+    /// filter cutoff and envelope times, the same every time the patch loads.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_analog_spread(&mut self, amount: f32) {
+        self.analog_spread = amount.clamp(0.0, 1.0);
+        for (index, voice) in self.voices.iter_mut().enumerate() {
+            voice.graph.set_voice_variation(VoiceVariation::for_voice(
+                self.analog_spread_seed,
+                index,
+                self.analog_spread,
+            ));
         }
+    }
 
-        if errors.is_empty() {
-            Ok(())
-        } else {
-            Err(JsValue::from_str(&errors.join("; ")))
+    /// Sets the parameter smoothing time (ms) for every node and effect without
+    /// a per-node override. Nodes without smoothed parameters ignore it (see
+    /// `AudioNode::set_smoothing_time_ms`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_parameter_smoothing(&mut self, time_ms: f32) -> Result<(), JsValue> {
+        let time_ms = time_ms.max(0.0);
+        self.locks.remember_smoothing(time_ms);
+        for voice in &mut self.voices {
+            voice.graph.set_smoothing_time_ms(time_ms);
         }
+        self.effect_stack.set_smoothing_time_ms(time_ms);
+        Ok(())
     }
 
+    /// Locks or unlocks a parameter against patch loads. A locked parameter
+    /// keeps the value last set through the engine API when a patch is loaded,
+    /// so hosts don't need to re-apply it after every preset change.
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
-    pub fn get_envelope_preview(
-        sample_rate: f32,
-        js_config: JsValue,
-        preview_duration: f32,
-    ) -> Result<js_sys::Float32Array, JsValue> {
-        // Deserialize the JS object into our helper struct.
-        let js_conf: JsEnvelopeConfig = serde_wasm_bindgen::from_value(js_config)
-            .map_err(|err| JsValue::from_str(&err.to_string()))?;
-
-        // Convert it into our internal EnvelopeConfig.
-        let config: EnvelopeConfig = js_conf.into();
+    pub fn set_parameter_lock(&mut self, parameter: LockableParameter, locked: bool) {
+        self.locks.set_locked(parameter, locked);
+    }
 
-        // Create a temporary envelope and generate the preview.
-        let envelope = Envelope::new(sample_rate, config);
-        let preview_values = envelope.preview(preview_duration);
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn is_parameter_locked(&self, parameter: LockableParameter) -> bool {
+        self.locks.is_locked(parameter)
+    }
 
-        // Convert Vec<f32> into a Float32Array.
-        let array = js_sys::Float32Array::new_with_length(preview_values.len() as u32);
-        for (i, &value) in preview_values.iter().enumerate() {
-            array.set_index(i as u32, value);
+    /// Loads a patch into timbral part `part` (1..MAX_PARTS). Part 0 is the
+    /// patch loaded with `init_with_patch`; every part plays through the shared
+    /// effect stack. Returns the part's voice count; its voices follow those of
+    /// the previous parts in the per-voice parameter arrays.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn load_part_patch(&mut self, part: usize, patch_json: &str) -> Result<usize, JsValue> {
+        if part == 0 || part >= MAX_PARTS {
+            return Err(JsValue::from_str(&format!(
+                "Invalid part index for a part patch: {}",
+                part
+            )));
         }
-        Ok(array)
+        let mut builder = self.part_builder();
+        builder.init_with_patch(patch_json)?;
+        let voices = std::mem::take(&mut builder.voices);
+        let voice_count = voices.len();
+        self.parts
+            .set_voices(part, voices)
+            .map_err(|e| JsValue::from_str(&e))?;
+        Ok(voice_count)
     }
 
+    /// Removes the patch of part `part`, freeing its voices.
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
-    pub fn update_wavetable_oscillator(
+    pub fn clear_part(&mut self, part: usize) -> Result<(), JsValue> {
+        self.parts
+            .set_voices(part, Vec::new())
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Assigns a part to a MIDI channel (`undefined` for all channels) and key
+    /// range.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_part_assignment(
         &mut self,
-        oscillator_id: &str,
-        params: &WavetableOscillatorStateUpdate,
+        part: usize,
+        channel: Option<u8>,
+        lo_key: u8,
+        hi_key: u8,
     ) -> Result<(), JsValue> {
-        let oscillator_id = NodeId::from_string(oscillator_id)
-            .map_err(|e| JsValue::from_str(&format!("Invalid oscillator_id UUID: {}", e)))?;
+        let config = self
+            .parts
+            .config_mut(part)
+            .map_err(|e| JsValue::from_str(&e))?;
+        config.channel = channel.map(|c| c.min(15));
+        config.lo_key = lo_key.min(127);
+        config.hi_key = hi_key.clamp(config.lo_key, 127);
+        Ok(())
+    }
 
-        for voice in &mut self.voices {
-            let node = voice
-                .graph
-                .get_node_mut(oscillator_id)
-                .ok_or_else(|| JsValue::from_str("Node not found in one of the voices"))?;
-            let osc = node
-                .as_any_mut()
-                .downcast_mut::<WavetableOscillator>()
-                .ok_or_else(|| {
-                    JsValue::from_str("Node is not a WavetableOscillator in one of the voices")
-                })?;
-            osc.update_params(params);
-        }
+    /// Sets a part's output level and how much of it is sent through the effect
+    /// stack; the rest is mixed in dry after the effects.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_part_mix(&mut self, part: usize, level: f32, send: f32) -> Result<(), JsValue> {
+        let config = self
+            .parts
+            .config_mut(part)
+            .map_err(|e| JsValue::from_str(&e))?;
+        config.level = level.max(0.0);
+        config.send = send.clamp(0.0, 1.0);
         Ok(())
     }
 
+    /// Index of a part's first voice in the per-voice parameter arrays passed
+    /// to `process_audio`, or `undefined` if the part has no voices.
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
-    pub fn import_wave_impulse(&mut self, effect_id: usize, data: &[u8]) -> Result<(), JsValue> {
-        use rubato::{
-            Resampler, SincFixedIn, SincInterpolationParameters, SincInterpolationType,
-            WindowFunction,
-        };
-        use std::io::Cursor;
+    pub fn get_part_voice_offset(&self, part: usize) -> Option<usize> {
+        self.parts
+            .voice_range(part, self.voices.len())
+            .map(|range| range.start)
+    }
 
-        log_console("Starting import_wave_impulse");
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_part_voice_count(&self, part: usize) -> Option<usize> {
+        self.parts
+            .voice_range(part, self.voices.len())
+            .map(|range| range.len())
+    }
 
-        // Create a hound reader from the data.
-        let cursor = Cursor::new(data);
-        let mut reader =
-            hound::WavReader::new(cursor).map_err(|e| JsValue::from_str(&e.to_string()))?;
-        let spec = reader.spec();
-        log_console(&format!(
-            "WAV spec: sample_rate={}, channels={}, bits_per_sample={}, sample_format={:?}",
-            spec.sample_rate, spec.channels, spec.bits_per_sample, spec.sample_format
-        ));
-        let num_channels = spec.channels;
-
-        // Read the samples in f32 form.
-        let samples: Vec<f32> = match (spec.bits_per_sample, spec.sample_format) {
-            (32, hound::SampleFormat::Float) => {
-                reader.samples::<f32>().map(|s| s.unwrap()).collect()
-            }
-            (16, hound::SampleFormat::Int) => reader
-                .samples::<i16>()
-                .map(|s| s.unwrap() as f32 / i16::MAX as f32)
-                .collect(),
-            (24, hound::SampleFormat::Int) => {
-                let shift = 32 - 24;
-                reader
-                    .samples::<i32>()
-                    .map(|s| (s.unwrap() << shift >> shift) as f32 / 8_388_607.0)
-                    .collect()
-            }
-            (32, hound::SampleFormat::Int) => reader
-                .samples::<i32>()
-                .map(|s| s.unwrap() as f32 / i32::MAX as f32)
-                .collect(),
-            (bits, format) => {
-                return Err(JsValue::from_str(&format!(
-                    "Unsupported WAV format: bits_per_sample={} sample_format={:?}",
-                    bits, format
-                )))
-            }
-        };
+    /// MIDI channel a part is assigned to, or `undefined` for all channels.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_part_channel(&self, part: usize) -> Option<u8> {
+        self.parts.config(part).and_then(|config| config.channel)
+    }
 
-        log_console(&format!("Read {} samples", samples.len()));
+    /// First part whose channel and key range accept the note.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn part_for_note(&self, channel: u8, note: u8) -> Option<usize> {
+        self.parts.part_for_note(channel, note, self.voices.len())
+    }
 
-        // If the WAV is multi‑channel, average the channels to obtain a mono impulse response.
-        let mut ir = if num_channels == 1 {
-            samples
-        } else {
-            let mut mono = Vec::with_capacity(samples.len() / num_channels as usize);
-            for chunk in samples.chunks(num_channels as usize) {
-                let avg = chunk.iter().sum::<f32>() / (num_channels as f32);
-                mono.push(avg);
-            }
-            mono
-        };
+    /// Sets a part's key and velocity zone. `fade` is the crossfade width, in
+    /// keys and velocity steps, at the zone's inner edges.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_part_zone(
+        &mut self,
+        part: usize,
+        lo_key: u8,
+        hi_key: u8,
+        lo_vel: u8,
+        hi_vel: u8,
+        fade: u8,
+    ) -> Result<(), JsValue> {
+        let config = self
+            .parts
+            .config_mut(part)
+            .map_err(|e| JsValue::from_str(&e))?;
+        config.lo_key = lo_key.min(127);
+        config.hi_key = hi_key.clamp(config.lo_key, 127);
+        config.lo_vel = lo_vel.min(127);
+        config.hi_vel = hi_vel.clamp(config.lo_vel, 127);
+        config.fade = fade.min(127);
+        Ok(())
+    }
 
-        log_console(&format!("Mono IR length: {}", ir.len()));
-        if ir.is_empty() {
-            return Err(JsValue::from_str("Impulse response is empty"));
-        }
+    /// Gain of every part for a note; the host plays the note on each part
+    /// with a non-zero gain, passing the gain in that voice's `gains` entry.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn part_zone_gains(&self, channel: u8, note: u8, velocity: u8) -> Vec<f32> {
+        self.parts
+            .zone_gains(channel, note, velocity, self.voices.len())
+            .to_vec()
+    }
 
-        // Locate the effect in the effect stack using the given id (subtracting the offset).
-        let index = effect_id
-            .checked_sub(EFFECT_NODE_ID_OFFSET)
-            .ok_or_else(|| JsValue::from_str("Invalid effect id"))?;
-        if index >= self.effect_stack.effects.len() {
-            return Err(JsValue::from_str("Effect id not found in effect stack"));
-        }
+    /// Assignment, zone and mix of every part, for storing with a bank.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_parts_state(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(self.parts.configs())
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize parts: {}", e)))
+    }
 
-        // Attempt to downcast the effect node to a Convolver.
-        let effect = &mut self.effect_stack.effects[index];
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_parts_state(&mut self, state: JsValue) -> Result<(), JsValue> {
+        let configs: Vec<PartConfig> = serde_wasm_bindgen::from_value(state)
+            .map_err(|e| JsValue::from_str(&format!("Invalid parts state: {}", e)))?;
+        self.parts.set_configs(&configs);
+        Ok(())
+    }
+
+    /// Loads a patch as the drum-kit pad on MIDI key `key`. Only the patch's
+    /// first voice is kept; the pad is played with `kit_note_on`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn load_kit_pad(&mut self, key: u8, patch_json: &str) -> Result<(), JsValue> {
+        let mut builder = self.part_builder();
+        builder.init_with_patch(patch_json)?;
+        let voice = builder
+            .voices
+            .drain(..)
+            .next()
+            .ok_or_else(|| JsValue::from_str("Kit pad patch has no voices"))?;
+        self.kit.set_pad(key, voice);
+        Ok(())
+    }
+
+    /// Scales the voice mix by 1/sqrt(active voices) so big chords don't clip
+    /// before the limiter. Off by default.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_polyphony_compensation(&mut self, enabled: bool) {
+        self.headroom.set_enabled(enabled);
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_polyphony_compensation(&self) -> bool {
+        self.headroom.is_enabled()
+    }
+
+    /// Tempo of the internal clock. Slaved transports follow their source.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_transport_tempo(&mut self, bpm: f64) {
+        self.transport.set_tempo(bpm);
+    }
+
+    /// Current tempo, as measured when slaved to external pulses.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_transport_tempo(&self) -> f64 {
+        self.transport.tempo()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn transport_play(&mut self) {
+        self.transport.play();
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn transport_stop(&mut self) {
+        self.transport.stop();
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_transport_playing(&self) -> bool {
+        self.transport.is_playing()
+    }
+
+    /// Moves the transport, in beats.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_transport_position(&mut self, beat: f64) {
+        self.transport.set_position(beat);
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_transport_position(&self) -> f64 {
+        self.transport.position()
+    }
+
+    /// Selects what the transport follows: 0 = its own tempo, 1 = external
+    /// pulses (`set_clock_pulses`), 2 = host positions (`sync_transport_to_host`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_clock_source(&mut self, source: u8) {
+        self.transport.set_source(ClockSource::from_u8(source));
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_clock_source(&self) -> u8 {
+        self.transport.source().as_u8()
+    }
+
+    /// Resolution of external clock pulses (24 for MIDI clock).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_clock_pulses_per_beat(&mut self, pulses_per_beat: u32) {
+        self.transport.set_pulses_per_beat(pulses_per_beat);
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_clock_pulses_per_beat(&self) -> u32 {
+        self.transport.pulses_per_beat()
+    }
+
+    /// External clock pulses for the next block, one value per sample.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_clock_pulses(&mut self, pulses: &[f32]) {
+        self.transport.set_pulses(pulses);
+    }
+
+    /// Host beat position and tempo at the start of the next block.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn sync_transport_to_host(&mut self, beat: f64, bpm: f64) {
+        self.transport.sync_to_host(beat, bpm);
+    }
+
+    /// Puts a voice into a choke group (`None` removes it). A gate onset on
+    /// the voice releases every other voice of the group with a short fade,
+    /// e.g. closed hi-hat cutting the open one or one voice per group lines.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_voice_choke_group(&mut self, voice_index: usize, group: Option<u8>) {
+        self.choke.set_group(voice_index, group);
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_voice_choke_group(&self, voice_index: usize) -> Option<u8> {
+        self.choke.group(voice_index)
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn clear_choke_groups(&mut self) {
+        self.choke.clear();
+    }
+
+    /// True once any kit pad is loaded; hosts route notes to `kit_note_on`
+    /// instead of the voice arrays in kit mode.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn is_kit_mode(&self) -> bool {
+        !self.kit.is_empty()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn remove_kit_pad(&mut self, key: u8) -> bool {
+        self.kit.remove_pad(key)
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn clear_kit(&mut self) {
+        self.kit.clear();
+    }
+
+    /// Sets a pad's output level and pan (-1 left to 1 right).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_kit_pad_mix(&mut self, key: u8, level: f32, pan: f32) -> Result<(), JsValue> {
+        self.kit
+            .set_pad_mix(key, level, pan)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// One-shot pads ignore note-off. Triggering a pad chokes the other pads of
+    /// its choke group.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_kit_pad_options(
+        &mut self,
+        key: u8,
+        one_shot: bool,
+        choke_group: Option<u8>,
+    ) -> Result<(), JsValue> {
+        self.kit
+            .set_pad_options(key, one_shot, choke_group)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Triggers the pad on `key` (velocity 0..1). Returns false if the key has
+    /// no pad.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn kit_note_on(&mut self, key: u8, velocity: f32) -> bool {
+        self.kit.note_on(key, velocity)
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn kit_note_off(&mut self, key: u8) -> bool {
+        self.kit.note_off(key)
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn kit_all_notes_off(&mut self) {
+        self.kit.all_notes_off();
+    }
+
+    /// Plays `note` (MIDI note number, velocity 0..1) on a voice chosen by the
+    /// voice allocator, stealing one by the steal mode when none is free, and
+    /// returns its index. Assigned voices follow the allocator whenever
+    /// `process_audio` gets an empty gate buffer.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn note_on(&mut self, note: u8, velocity: f32) -> Option<usize> {
+        let assignment = self.allocator.note_on(note, velocity, &self.voices)?;
+        if assignment.stolen {
+            // Fade the old note out first so the envelopes see a fresh gate
+            // without a click.
+            self.choke.steal(assignment.voice);
+        }
+        Some(assignment.voice)
+    }
+
+    /// Releases the voices playing `note`. Returns false if none were.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn note_off(&mut self, note: u8) -> bool {
+        self.allocator.note_off(note)
+    }
+
+    /// `note_off` with the note-off velocity (0..1), which the voices' release
+    /// velocity nodes, envelopes and samplers pick up.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn note_off_with_velocity(&mut self, note: u8, release_velocity: f32) -> bool {
+        self.allocator
+            .note_off_with_velocity(note, release_velocity)
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn all_notes_off(&mut self) {
+        self.allocator.all_notes_off();
+    }
+
+    /// How `note_on` picks a voice when all of them are busy: 0 = oldest,
+    /// 1 = quietest, 2 = same note, 3 = releasing voices first.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_steal_mode(&mut self, mode: u8) {
+        self.allocator.set_mode(StealMode::from_u8(mode));
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_steal_mode(&self) -> u8 {
+        self.allocator.mode().as_u8()
+    }
+
+    /// Scratch engine used to build a part's voices. It shares the wavetable
+    /// banks and runs at the current quality mode.
+    fn part_builder(&self) -> Self {
+        Self {
+            voices: Vec::new(),
+            sample_rate: self.sample_rate,
+            num_voices: self.num_voices,
+            wavetable_synthbank: Rc::clone(&self.wavetable_synthbank),
+            wavetable_banks: Arc::clone(&self.wavetable_banks),
+            effect_stack: EffectStack::new(self.block_size),
+            ir_generator: ImpulseResponseGenerator::new(self.sample_rate),
+            cpu_time_accum: 0.0,
+            audio_time_accum: 0.0,
+            last_cpu_usage: 0.0,
+            quality_mode: self.effective_quality_mode(),
+            overload: OverloadProtection::new(),
+            node_health_checks: false,
+            stereo_voices: self.stereo_voices,
+            analog_spread: 0.0,
+            analog_spread_seed: 0,
+            allocator: VoiceAllocator::new(),
+            locks: ParameterLocks::new(),
+            parts: Parts::new(),
+            kit: DrumKit::new(self.sample_rate),
+            choke: ChokeGroups::new(self.sample_rate),
+            headroom: PolyphonyCompensation::new(self.sample_rate),
+            transport: Transport::new(self.sample_rate),
+            output: OutputStage::new(),
+            surround: SurroundPanner::new(self.sample_rate),
+            snapshots: SnapshotRecorder::new(),
+            snapshot_callback: None,
+            pending_snapshot: None,
+            loaded_layout: None,
+            loaded_assets: HashMap::new(),
+            jobs: JobQueue::new(),
+            imports: ImportHistory::default(),
+            auto_node_levels: false,
+            last_node_levels: None,
+            scope: XyScope::new(),
+            correlation: CorrelationMeter::new(self.sample_rate),
+            capacity_limits: None,
+            capacity_events: Vec::new(),
+            block_size: self.block_size,
+            channel_scratch: vec![0.0; self.block_size * 2],
+        }
+    }
+
+    /// Every node and effect setting as an array of `{ nodeId, nodeName,
+    /// parameter, value, unit }` rows, for diffing presets, generating docs
+    /// or external preset analysis. Nodes without presets only report
+    /// `active`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn export_flat_parameters(&self) -> Result<JsValue, JsValue> {
+        let rows = flatten_session(
+            self.voices.first(),
+            &self.effect_stack.effects,
+            EFFECT_NODE_ID_OFFSET,
+        );
+        serde_wasm_bindgen::to_value(&rows)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize parameters: {}", e)))
+    }
+
+    /// Settings of a single envelope, LFO, filter or effect as a JSON preset
+    /// snippet, for per-module preset menus. Effects use their effect node id.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn export_node_preset(&self, node_id: &str) -> Result<String, JsValue> {
+        let node = self.preset_node(node_id)?;
+        NodePreset::from_node(node)
+            .ok_or_else(|| {
+                JsValue::from_str(&format!(
+                    "Node {} ({}) has no presets",
+                    node_id,
+                    node.name()
+                ))
+            })?
+            .to_json()
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Applies a snippet from `export_node_preset` to a node of the same type
+    /// in every voice.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn import_node_preset(&mut self, node_id: &str, preset_json: &str) -> Result<(), JsValue> {
+        let preset = NodePreset::from_json(preset_json).map_err(|e| JsValue::from_str(&e))?;
+        self.apply_node_preset(node_id, preset)
+    }
+
+    /// Copies the settings of `src_id` onto `dst_id`, a node of the same type,
+    /// in every voice. Nothing is changed if the nodes don't match.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn copy_node_settings(&mut self, src_id: &str, dst_id: &str) -> Result<(), JsValue> {
+        let src = self.preset_node(src_id)?;
+        let preset = NodePreset::from_node(src).ok_or_else(|| {
+            JsValue::from_str(&format!("Node {} ({}) has no presets", src_id, src.name()))
+        })?;
+        self.apply_node_preset(dst_id, preset)
+    }
+
+    /// Moves each setting of effect `index` by a random amount, up to
+    /// `amount` (0..1) of the setting's range.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn randomize_effect(&mut self, index: usize, amount: f64) -> Result<(), JsValue> {
+        let node_id = (EFFECT_NODE_ID_OFFSET + index).to_string();
+        let node = self.preset_node(&node_id)?;
+        let preset = NodePreset::from_node(node).ok_or_else(|| {
+            JsValue::from_str(&format!(
+                "Effect {} ({}) has no presets",
+                index,
+                node.name()
+            ))
+        })?;
+        let mut rng = rand::rng();
+        let preset = preset
+            .randomized(amount, || rng.random())
+            .map_err(|e| JsValue::from_str(&e))?;
+        self.apply_node_preset(&node_id, preset)
+    }
+
+    /// Sets effect `index` to the settings `t` (0..1) of the way from
+    /// `preset_a` to `preset_b`, two snippets from `export_node_preset`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn morph_effect(
+        &mut self,
+        index: usize,
+        preset_a: &str,
+        preset_b: &str,
+        t: f64,
+    ) -> Result<(), JsValue> {
+        let parse = |json: &str| NodePreset::from_json(json).map_err(|e| JsValue::from_str(&e));
+        let preset = NodePreset::morph(&parse(preset_a)?, &parse(preset_b)?, t)
+            .map_err(|e| JsValue::from_str(&e))?;
+        self.apply_node_preset(&(EFFECT_NODE_ID_OFFSET + index).to_string(), preset)
+    }
+
+    fn apply_node_preset(&mut self, node_id: &str, preset: NodePreset) -> Result<(), JsValue> {
+        let node = self.preset_node(node_id)?;
+        if !preset.fits(node) {
+            return Err(JsValue::from_str(&format!(
+                "Preset doesn't match node {} ({})",
+                node_id,
+                node.name()
+            )));
+        }
+        let state = preset.into_synth_state(node_id);
+        self.apply_patch_states(&state, &PatchVoiceLayout::default())?;
+        self.snapshots.node_edited(node_id);
+        Ok(())
+    }
+
+    fn preset_node(&self, node_id: &str) -> Result<&dyn AudioNode, JsValue> {
+        let node = match node_id.parse::<usize>() {
+            Ok(index) => index
+                .checked_sub(EFFECT_NODE_ID_OFFSET)
+                .and_then(|effect| self.effect_stack.effects.get(effect))
+                .map(|effect| effect.node.as_ref()),
+            Err(_) => {
+                let id = NodeId::from_string(node_id)
+                    .map_err(|e| JsValue::from_str(&format!("Invalid node UUID: {}", e)))?;
+                self.voices
+                    .first()
+                    .and_then(|voice| voice.graph.get_node(id))
+                    .map(|node| node.as_ref())
+            }
+        };
+        node.ok_or_else(|| JsValue::from_str(&format!("Node {} not found", node_id)))
+    }
+
+    /// Re-applies the remembered values of locked parameters to the rebuilt
+    /// voices and effect stack.
+    fn restore_locked_parameters(&mut self) -> Result<(), JsValue> {
+        if let Some((transpose, fine)) = self.locks.locked_master_tuning() {
+            self.set_master_tuning(transpose, fine)?;
+        }
+        let detune: Vec<(usize, f32)> = self.locks.locked_voice_detune().collect();
+        for (voice_index, cents) in detune {
+            if let Some(voice) = self.voices.get_mut(voice_index) {
+                voice.graph.set_voice_detune(cents);
+            }
+        }
+        if let Some(time_ms) = self.locks.locked_smoothing() {
+            self.set_parameter_smoothing(time_ms)?;
+        }
+        let macro_values: Vec<(usize, f32)> = self.locks.locked_macro_values().collect();
+        for (macro_index, value) in macro_values {
+            for voice in &mut self.voices {
+                // The locked value is held from the first block, no glide.
+                let _ = voice.set_macro_sparse(macro_index, value, Some(value));
+            }
+        }
+        Ok(())
+    }
+
+    /// Switches every voice node and effect between Eco, Normal and High quality
+    /// (oversampling factors, interpolation orders, control-rate decimation).
+    /// The mode survives patch loads.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_quality_mode(&mut self, mode: QualityMode) {
+        self.quality_mode = mode;
+        // While overload protection holds Eco, the new mode takes effect on recovery.
+        if !self.overload.is_degraded() {
+            self.apply_quality_mode(mode);
+        }
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_quality_mode(&self) -> QualityMode {
+        self.quality_mode
+    }
+
+    /// Chooses how the final mix is bounded: hard clamp, the safety limiter in
+    /// the effect stack (default) or raw float. Survives patch loads.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_output_mode(&mut self, mode: OutputMode) {
+        self.output.set_mode(mode);
+        self.effect_stack
+            .set_limiters_bypassed(mode.bypasses_limiter());
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_output_mode(&self) -> OutputMode {
+        self.output.mode()
+    }
+
+    /// Requests dithered 16 or 24-bit integer copies of every block next to the
+    /// float output; `OutputFormat::Float` turns them off.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_output_format(&mut self, format: OutputFormat) {
+        self.output.set_format(format);
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_output_format(&self) -> OutputFormat {
+        self.output.format()
+    }
+
+    /// Left channel of the last processed block as integers (Int32Array),
+    /// empty for float output.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_int_output_left(&self) -> Vec<i32> {
+        self.output.int_left().to_vec()
+    }
+
+    /// Right channel of the last processed block as integers (Int32Array),
+    /// empty for float output.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_int_output_right(&self) -> Vec<i32> {
+        self.output.int_right().to_vec()
+    }
+
+    /// Switches between stereo, quad and 5.1 output. Outside stereo,
+    /// `process_audio` returns the front pair only; use
+    /// `process_audio_multichannel` for every channel.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_channel_layout(&mut self, layout: ChannelLayout) {
+        self.surround.set_layout(layout);
+        self.effect_stack
+            .set_spread_capture(self.surround.is_enabled());
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_channel_layout(&self) -> ChannelLayout {
+        self.surround.layout()
+    }
+
+    /// Places a voice in the surround field: `x` from -1 (left) to 1 (right),
+    /// `y` from -1 (rear) to 1 (front). Voices default to front centre.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_voice_position(&mut self, voice_index: usize, x: f32, y: f32) {
+        self.surround.set_voice_position(voice_index, x, y);
+    }
+
+    /// Position of a voice as `[x, y]`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_voice_position(&self, voice_index: usize) -> Vec<f32> {
+        let (x, y) = self.surround.voice_position(voice_index);
+        vec![x, y]
+    }
+
+    /// Chooses whether a master effect's wet signal also feeds the rear
+    /// channels. Reverbs and delays do by default.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_effect_surround_send(
+        &mut self,
+        node_id: usize,
+        enabled: bool,
+    ) -> Result<(), JsValue> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .filter(|&index| index < self.effect_stack.effects.len())
+            .ok_or_else(|| JsValue::from_str(&format!("Invalid effect node id {}", node_id)))?;
+        self.effect_stack
+            .set_effect_surround_send(effect_id, enabled);
+        Ok(())
+    }
+
+    /// Enables automatic degradation when CPU usage passes the overload threshold:
+    /// quality drops to Eco first, then quiet released voices are culled. Actions
+    /// are reported through `take_diagnostics`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_overload_protection(&mut self, enabled: bool) {
+        let response = self.overload.set_enabled(enabled);
+        self.respond_to_overload(response);
+    }
+
+    /// Sets the CPU usage (%) that triggers protection and the level usage must
+    /// stay below before quality is restored.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_overload_thresholds(&mut self, threshold: f32, recovery_threshold: f32) {
+        self.overload.set_thresholds(threshold, recovery_threshold);
+    }
+
+    /// Stereo voices: links between stereo-capable nodes in the voice graph
+    /// carry the right channel as well, so panned or spread sources stay
+    /// stereo through filters and per-voice effects. Off by default; mono
+    /// voices skip the right-channel processing. Applies to the current
+    /// voices and to patches loaded later.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_stereo_voices(&mut self, enabled: bool) {
+        self.stereo_voices = enabled;
+        for voice in &mut self.voices {
+            voice.graph.set_stereo(enabled);
+        }
+        for (voice, _) in self.parts.extra_voices_mut() {
+            voice.graph.set_stereo(enabled);
+        }
+    }
+
+    /// Diagnostics mode: checks the output of every node in the voices after it
+    /// runs and reports NaN or infinite samples, and outputs stuck at DC or
+    /// silent while their inputs move, through `take_diagnostics`. Costs a
+    /// pass over each output buffer, so leave it off outside debugging.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_node_health_checks(&mut self, enabled: bool) {
+        self.node_health_checks = enabled;
+        for voice in &mut self.voices {
+            voice.graph.set_health_monitoring(enabled);
+        }
+    }
+
+    /// Strict real-time mode. `limits` is `null` to grow on demand again, or
+    /// `{ maxVoices, maxNodes, maxConnections, maxBlockSize,
+    /// maxSourcesPerPort }`: every voice graph preallocates for it, and
+    /// voices, nodes and connections past it are refused instead of
+    /// allocated, patches that don't fit fail to load, and each refusal is
+    /// reported through `take_diagnostics`. Fails, changing nothing, when the
+    /// running session is already bigger.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_strict_realtime(&mut self, limits: JsValue) -> Result<(), JsValue> {
+        let limits: Option<CapacityLimits> = if limits.is_null() || limits.is_undefined() {
+            None
+        } else {
+            Some(
+                serde_wasm_bindgen::from_value(limits)
+                    .map_err(|e| JsValue::from_str(&format!("Invalid capacity limits: {}", e)))?,
+            )
+        };
+        if let Some(limits) = limits {
+            limits
+                .check(CapacityResource::Voices, self.voices.len())
+                .and_then(|_| limits.check(CapacityResource::BlockSize, self.block_size))
+                .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        }
+        for index in 0..self.voices.len() {
+            if let Err(exceeded) = self.voices[index].graph.set_capacity_limits(limits) {
+                for voice in &mut self.voices[..index] {
+                    let _ = voice.graph.set_capacity_limits(self.capacity_limits);
+                }
+                return Err(JsValue::from_str(&exceeded.to_string()));
+            }
+        }
+        self.capacity_limits = limits;
+        self.capacity_events
+            .reserve(MAX_PENDING_CAPACITY_EVENTS.saturating_sub(self.capacity_events.len()));
+        Ok(())
+    }
+
+    /// Drains the diagnostics events, oldest first, as an array of
+    /// `{ kind: "overload", action, cpuUsage, count? }`,
+    /// `{ kind: "nodeHealth", voice, nodeId, port, issue, value }` and
+    /// `{ kind: "capacity", voice, resource, requested, limit }` objects;
+    /// `voice` is `null` for engine-wide capacity refusals.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn take_diagnostics(&mut self) -> Result<JsValue, JsValue> {
+        let mut events: Vec<DiagnosticEvent> = self
+            .overload
+            .take_events()
+            .into_iter()
+            .map(DiagnosticEvent::Overload)
+            .collect();
+        for voice in &mut self.voices {
+            let voice_index = voice.id;
+            events.extend(voice.graph.take_health_events().into_iter().map(|event| {
+                DiagnosticEvent::NodeHealth {
+                    voice: voice_index,
+                    event,
+                }
+            }));
+        }
+        for exceeded in self.capacity_events.drain(..) {
+            events.push(DiagnosticEvent::Capacity {
+                voice: None,
+                exceeded,
+            });
+        }
+        for voice in &mut self.voices {
+            let voice_index = voice.id;
+            events.extend(
+                voice
+                    .graph
+                    .take_capacity_events()
+                    .into_iter()
+                    .map(|exceeded| DiagnosticEvent::Capacity {
+                        voice: Some(voice_index),
+                        exceeded,
+                    }),
+            );
+        }
+        serde_wasm_bindgen::to_value(&events)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize diagnostics: {}", e)))
+    }
+
+    /// Bytes used per subsystem, as `{ wavetableBanks, sampleData,
+    /// convolverPartitions, delayBuffers, graphBuffersPerVoice, total }`, so
+    /// the app can warn before the tab runs out of memory.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_memory_stats(&self) -> Result<JsValue, JsValue> {
+        let mut counter = MemoryCounter::new();
+        for bank in self.wavetable_banks.values() {
+            counter.add_wavetables(bank.memory_bytes());
+        }
+        for collection in self.wavetable_synthbank.borrow().collections.values() {
+            counter.add_wavetables(collection.memory_bytes());
+        }
+        for voice in self.voices.iter().chain(self.parts.extra_voices()) {
+            counter.add_voice(voice);
+        }
+        for effect in &self.effect_stack.effects {
+            counter.add_node(effect.node.as_ref());
+        }
+        serde_wasm_bindgen::to_value(&counter.finish())
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize memory stats: {}", e)))
+    }
+
+    /// Starts a background job and returns its id. `request` is a `JobRequest`
+    /// object, e.g. `{ kind: "importImpulse", effectId: 10003 }`; imports take
+    /// the WAV file as `data`. Nothing runs until `poll_job`, which does one
+    /// bounded step of work per call, so the host spreads a job over as many
+    /// audio callbacks or idle slots as it likes.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn start_job(&mut self, request: JsValue, data: Vec<u8>) -> Result<u32, JsValue> {
+        let request: JobRequest = serde_wasm_bindgen::from_value(request)
+            .map_err(|e| JsValue::from_str(&format!("Invalid job request: {}", e)))?;
+        let (sample_rate, partition_size) = match &request {
+            JobRequest::ImportImpulse { effect_id }
+            | JobRequest::GenerateImpulse { effect_id, .. } => {
+                let convolver = self.convolver_mut(*effect_id)?;
+                (convolver.sample_rate, convolver.partition_size)
+            }
+            _ => (self.sample_rate, 0),
+        };
+        let task = JobTask::new(&request, data, sample_rate, partition_size)
+            .map_err(|e| JsValue::from_str(&e))?;
+        self.jobs.start(task).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Runs the next step of a job and returns `{ state: "running", progress }`,
+    /// `{ state: "finished" }` once its result is in place, or
+    /// `{ state: "failed", error }`. Finished and failed jobs are forgotten.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn poll_job(&mut self, job_id: u32) -> Result<JsValue, JsValue> {
+        let status = match self.jobs.poll(job_id).map_err(|e| JsValue::from_str(&e))? {
+            JobPoll::Running(progress) => JobStatus::Running { progress },
+            JobPoll::Finished(request, output) => match self.apply_job_output(request, output) {
+                Ok(()) => JobStatus::Finished,
+                Err(error) => JobStatus::Failed {
+                    error: error.as_string().unwrap_or_default(),
+                },
+            },
+            JobPoll::Failed(error) => JobStatus::Failed { error },
+        };
+        serde_wasm_bindgen::to_value(&status)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize job status: {}", e)))
+    }
+
+    /// Drops a job without applying anything. Returns false for unknown jobs.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn cancel_job(&mut self, job_id: u32) -> bool {
+        self.jobs.cancel(job_id)
+    }
+
+    fn convolver_mut(&mut self, effect_id: usize) -> Result<&mut Convolver, JsValue> {
+        let index = effect_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .filter(|&index| index < self.effect_stack.effects.len())
+            .ok_or_else(|| JsValue::from_str("Effect id not found in effect stack"))?;
+        self.effect_stack.effects[index]
+            .node
+            .as_any_mut()
+            .downcast_mut::<Convolver>()
+            .ok_or_else(|| JsValue::from_str("Effect is not a Convolver"))
+    }
+
+    fn apply_job_output(&mut self, request: JobRequest, output: JobOutput) -> Result<(), JsValue> {
+        match (request, output) {
+            (
+                JobRequest::ImportSample { node_id },
+                JobOutput::Sample {
+                    samples,
+                    channels,
+                    sample_rate,
+                },
+            ) => {
+                let node_id = NodeId::from_string(&node_id)
+                    .map_err(|e| JsValue::from_str(&format!("Invalid sampler_id UUID: {}", e)))?;
+                self.imports.backup_sample(node_id, &self.voices);
+                let sample_data = Rc::new(RefCell::new(SampleData::new()));
+                sample_data
+                    .borrow_mut()
+                    .load_from_wav(samples, channels, sample_rate);
+                for voice in &mut self.voices {
+                    let sampler = voice
+                        .graph
+                        .get_node_mut(node_id)
+                        .ok_or_else(|| JsValue::from_str("Node not found"))?
+                        .as_any_mut()
+                        .downcast_mut::<Sampler>()
+                        .ok_or_else(|| JsValue::from_str("Node is not a Sampler"))?;
+                    sampler.set_sample_data(sample_data.clone());
+                }
+            }
+            (
+                JobRequest::ImportImpulse { effect_id }
+                | JobRequest::GenerateImpulse { effect_id, .. },
+                JobOutput::Convolver(mut convolver),
+            ) => {
+                let quality_mode = self.effect_stack.quality_mode();
+                let current = self.convolver_mut(effect_id)?;
+                convolver.set_wet_level(current.wet_level());
+                convolver.set_active(current.is_active());
+                convolver.set_quality_mode(quality_mode);
+                *current = convolver;
+            }
+            (JobRequest::ImportWavetable { node_id, .. }, JobOutput::Wavetable(collection)) => {
+                let collection_name = format!("wt_{}", node_id);
+                let node_id = NodeId::from_string(&node_id)
+                    .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+                {
+                    let mut bank = self.wavetable_synthbank.borrow_mut();
+                    self.imports.backup_wavetable(
+                        &bank,
+                        &collection_name,
+                        Some(node_id),
+                        &self.voices,
+                    );
+                    if !bank.collections.contains_key("default") {
+                        bank.add_collection(
+                            "default",
+                            WavetableMorphCollection::generate_test_collection(self.sample_rate),
+                        );
+                    }
+                    bank.add_collection(&collection_name, collection);
+                }
+                for voice in &mut self.voices {
+                    let osc = voice
+                        .graph
+                        .get_node_mut(node_id)
+                        .ok_or_else(|| JsValue::from_str("Node not found in one of the voices"))?
+                        .as_any_mut()
+                        .downcast_mut::<WavetableOscillator>()
+                        .ok_or_else(|| JsValue::from_str("Node is not a WavetableOscillator"))?;
+                    osc.set_current_wavetable(&collection_name);
+                }
+            }
+            (JobRequest::ImportNamedWavetable { name, .. }, JobOutput::Wavetable(collection)) => {
+                let mut bank = self.wavetable_synthbank.borrow_mut();
+                self.imports
+                    .backup_wavetable(&bank, &name, None, &self.voices);
+                bank.add_collection(name, collection);
+            }
+            _ => return Err(JsValue::from_str("Job output doesn't match its request")),
+        }
+        Ok(())
+    }
+
+    /// Checks the session for changes every `interval_seconds` and snapshots
+    /// it when it changed, for recovery after a tab crash. 0 turns snapshots
+    /// off.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_snapshot_interval(&mut self, interval_seconds: f32) {
+        self.snapshots
+            .set_interval(self.sample_rate, interval_seconds);
+    }
+
+    /// Calls `callback` with each snapshot's JSON string, from the audio
+    /// thread. Only the first snapshot after a patch load includes the patch,
+    /// so keep that one and fold later ones into it. `undefined` goes back to
+    /// `take_snapshot`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_snapshot_callback(&mut self, callback: Option<js_sys::Function>) {
+        self.snapshot_callback = callback;
+        if self.snapshot_callback.is_some() && !self.snapshots.is_enabled() {
+            self.set_snapshot_interval(DEFAULT_SNAPSHOT_INTERVAL_SECONDS);
+        }
+    }
+
+    /// The snapshot taken since the last call, for hosts that poll. It
+    /// includes the patch if any snapshot it replaced did.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn take_snapshot(&mut self) -> Result<Option<String>, JsValue> {
+        self.pending_snapshot
+            .take()
+            .map(|snapshot| snapshot.to_json().map_err(|e| JsValue::from_str(&e)))
+            .transpose()
+    }
+
+    /// Replaces the patch later snapshots are based on. The web app edits
+    /// patches through the update calls, so it sends its serialized patch
+    /// whenever it saves one.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_snapshot_patch(&mut self, patch_json: &str) {
+        self.snapshots.patch_loaded(patch_json);
+    }
+
+    /// Rebuilds a session from a snapshot that includes its patch. Returns the
+    /// voice count.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn restore_snapshot(&mut self, snapshot_json: &str) -> Result<usize, JsValue> {
+        let snapshot =
+            SessionSnapshot::from_json(snapshot_json).map_err(|e| JsValue::from_str(&e))?;
+        let patch = snapshot.patch.as_deref().ok_or_else(|| {
+            JsValue::from_str("Snapshot has no patch; merge it with the last snapshot that has one")
+        })?;
+        let voice_count = self.init_with_patch(patch)?;
+        for (node_id, preset_json) in &snapshot.node_presets {
+            if let Err(err) = self.import_node_preset(node_id, preset_json) {
+                log_console(&format!("Failed to restore node {}: {:?}", node_id, err));
+            }
+        }
+
+        let performance = &snapshot.performance;
+        self.set_transport_tempo(performance.tempo_bpm);
+        if let Some((transpose, fine)) = performance.master_tuning {
+            self.set_master_tuning(transpose, fine)?;
+        }
+        for (voice_index, cents) in performance.voice_detune.iter().enumerate() {
+            if let Some(cents) = cents {
+                // Voices the patch no longer has are skipped.
+                let _ = self.set_voice_detune(voice_index, *cents);
+            }
+        }
+        if let Some(time_ms) = performance.smoothing_ms {
+            self.set_parameter_smoothing(time_ms)?;
+        }
+        for (macro_index, value) in performance.macro_values.iter().enumerate() {
+            if let Some(value) = value {
+                self.set_macro_value(None, macro_index, *value, None)?;
+            }
+        }
+        Ok(voice_count)
+    }
+
+    fn record_snapshot(&mut self, frames: usize) {
+        if !self.snapshots.advance(frames) {
+            return;
+        }
+        let performance = PerformanceState::capture(&self.locks, self.transport.tempo());
+        if !self.snapshots.has_changes(&performance) {
+            return;
+        }
+        let node_presets = self
+            .snapshots
+            .edited_nodes()
+            .filter_map(|id| Some((id.to_string(), self.export_node_preset(id).ok()?)))
+            .collect();
+        let snapshot = self.snapshots.take(performance, node_presets);
+        match &self.snapshot_callback {
+            Some(callback) => {
+                let result = snapshot
+                    .to_json()
+                    .map_err(|e| JsValue::from_str(&e))
+                    .and_then(|json| callback.call1(&JsValue::NULL, &JsValue::from_str(&json)));
+                if let Err(err) = result {
+                    log_console(&format!("Failed to deliver session snapshot: {:?}", err));
+                }
+            }
+            None => {
+                let snapshot = match self.pending_snapshot.take() {
+                    Some(pending) => snapshot.with_patch_from(&pending),
+                    None => snapshot,
+                };
+                self.pending_snapshot = Some(snapshot);
+            }
+        }
+    }
+
+    /// Overrides the smoothing time of one voice node; `None` clears the override.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_node_smoothing(
+        &mut self,
+        node_id: &str,
+        time_ms: Option<f32>,
+    ) -> Result<(), JsValue> {
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node UUID: {}", e)))?;
+        for voice in &mut self.voices {
+            voice
+                .graph
+                .set_node_smoothing_time_ms(node_id, time_ms.map(|t| t.max(0.0)));
+        }
+        Ok(())
+    }
+
+    /// Sets the groove template of the arpeggiators, clock nodes and synced
+    /// delays that don't have their own: swing (50 = straight, 75 = hardest)
+    /// and a micro offset per sixteenth of the bar, as a fraction of a sixteenth.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_groove(&mut self, swing_percent: f32, offsets: &[f32]) {
+        let groove = Groove::new(swing_percent, offsets);
+        for voice in &mut self.voices {
+            voice.graph.set_groove(groove);
+        }
+        self.effect_stack.set_groove(groove);
+    }
+
+    /// Overrides the groove of one voice node; a `swing_percent` of `None`
+    /// clears the override.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_node_groove(
+        &mut self,
+        node_id: &str,
+        swing_percent: Option<f32>,
+        offsets: &[f32],
+    ) -> Result<(), JsValue> {
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node UUID: {}", e)))?;
+        let groove = swing_percent.map(|swing| Groove::new(swing, offsets));
+        for voice in &mut self.voices {
+            voice.graph.set_node_groove(node_id, groove);
+        }
+        Ok(())
+    }
+
+    /// Overrides the groove of one master effect; a `swing_percent` of `None`
+    /// clears the override.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_effect_groove(
+        &mut self,
+        node_id: usize,
+        swing_percent: Option<f32>,
+        offsets: &[f32],
+    ) -> Result<(), JsValue> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .filter(|&index| index < self.effect_stack.effects.len())
+            .ok_or_else(|| JsValue::from_str(&format!("Invalid effect node id {}", node_id)))?;
+        let groove = swing_percent.map(|swing| Groove::new(swing, offsets));
+        self.effect_stack.set_effect_groove(effect_id, groove);
+        Ok(())
+    }
+
+    /// Overrides the smoothing time of one master effect; `None` clears the override.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_effect_smoothing(
+        &mut self,
+        node_id: usize,
+        time_ms: Option<f32>,
+    ) -> Result<(), JsValue> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .filter(|&index| index < self.effect_stack.effects.len())
+            .ok_or_else(|| JsValue::from_str(&format!("Invalid effect node id {}", node_id)))?;
+        self.effect_stack
+            .set_effect_smoothing_time_ms(effect_id, time_ms.map(|t| t.max(0.0)));
+        Ok(())
+    }
+
+    /// Routes a master effect in stereo, to the mid or side component only, or
+    /// dual-mono.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_effect_routing(
+        &mut self,
+        node_id: usize,
+        routing: EffectRouting,
+    ) -> Result<(), JsValue> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .filter(|&index| index < self.effect_stack.effects.len())
+            .ok_or_else(|| JsValue::from_str(&format!("Invalid effect node id {}", node_id)))?;
+        self.effect_stack.set_effect_routing(effect_id, routing);
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_effect_routing(&self, node_id: usize) -> Option<EffectRouting> {
+        node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .and_then(|index| self.effect_stack.effect_routing(index))
+    }
+
+    /// Keys a master effect from a voice node output (e.g. an envelope or the arp
+    /// gate). Compressors duck on it, delays duck their echoes and the reverb gate
+    /// follows it. `None` as `source_node_id` disconnects the sidechain.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_effect_sidechain(
+        &mut self,
+        node_id: usize,
+        source_node_id: Option<String>,
+        source_port: PortId,
+    ) -> Result<(), JsValue> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .filter(|&index| index < self.effect_stack.effects.len())
+            .ok_or_else(|| JsValue::from_str(&format!("Invalid effect node id {}", node_id)))?;
+
+        let source = match source_node_id {
+            Some(id) => {
+                let source_id = NodeId::from_string(&id)
+                    .map_err(|e| JsValue::from_str(&format!("Invalid node ID: {}", e)))?;
+                let exists = self
+                    .voices
+                    .first()
+                    .is_some_and(|voice| voice.graph.get_node(source_id).is_some());
+                if !exists {
+                    return Err(JsValue::from_str("Node not found"));
+                }
+                Some(SidechainSource {
+                    node_id: source_id,
+                    port: source_port,
+                })
+            }
+            None => None,
+        };
+        self.effect_stack.set_effect_sidechain(effect_id, source);
+        Ok(())
+    }
+
+    /// How far the sidechain key pulls a delay's echoes down (0..1).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_delay_ducking(&mut self, node_id: usize, ducking: f32) -> Result<(), JsValue> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| JsValue::from_str("Invalid delay node id"))?;
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| JsValue::from_str(&format!("No effect found at index {}", effect_id)))?;
+        let delay = effect
+            .node
+            .as_any_mut()
+            .downcast_mut::<Delay>()
+            .ok_or_else(|| {
+                JsValue::from_str(&format!("Effect at index {} is not a Delay", effect_id))
+            })?;
+        delay.set_ducking(ducking);
+        Ok(())
+    }
+
+    /// Syncs a delay's time to `sync_beats` beats of the effect tempo; `None`
+    /// returns it to its time in milliseconds.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_delay_sync(
+        &mut self,
+        node_id: usize,
+        sync_beats: Option<f32>,
+    ) -> Result<(), JsValue> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| JsValue::from_str("Invalid delay node id"))?;
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| JsValue::from_str(&format!("No effect found at index {}", effect_id)))?;
+        let delay = effect
+            .node
+            .as_any_mut()
+            .downcast_mut::<Delay>()
+            .ok_or_else(|| {
+                JsValue::from_str(&format!("Effect at index {} is not a Delay", effect_id))
+            })?;
+        delay.set_sync_beats(sync_beats);
+        Ok(())
+    }
+
+    /// Gates the reverb tail with its sidechain key.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_reverb_gate(&mut self, node_id: usize, enabled: bool) -> Result<(), JsValue> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| JsValue::from_str("Invalid reverb node id"))?;
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| JsValue::from_str(&format!("No effect found at index {}", effect_id)))?;
+        let reverb = effect
+            .node
+            .as_any_mut()
+            .downcast_mut::<Freeverb>()
+            .ok_or_else(|| {
+                JsValue::from_str(&format!("Effect at index {} is not a reverb", effect_id))
+            })?;
+        reverb.set_gate_enabled(enabled);
+        Ok(())
+    }
+
+    /// Freezes (or releases) a delay: input muted, the line held at unity feedback.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_delay_freeze(&mut self, node_id: usize, frozen: bool) -> Result<(), JsValue> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| JsValue::from_str("Invalid delay node id"))?;
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| JsValue::from_str(&format!("No effect found at index {}", effect_id)))?;
+        let delay = effect
+            .node
+            .as_any_mut()
+            .downcast_mut::<Delay>()
+            .ok_or_else(|| {
+                JsValue::from_str(&format!("Effect at index {} is not a Delay", effect_id))
+            })?;
+        delay.set_freeze(frozen);
+        Ok(())
+    }
+
+    /// Freezes (or releases) the reverb tail.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_reverb_freeze(&mut self, node_id: usize, frozen: bool) -> Result<(), JsValue> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| JsValue::from_str("Invalid reverb node id"))?;
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| JsValue::from_str(&format!("No effect found at index {}", effect_id)))?;
+        let reverb = effect
+            .node
+            .as_any_mut()
+            .downcast_mut::<Freeverb>()
+            .ok_or_else(|| {
+                JsValue::from_str(&format!("Effect at index {} is not a reverb", effect_id))
+            })?;
+        reverb.set_freeze(frozen);
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_gate_mixer_node_id(&mut self) -> Option<String> {
+        self.voices
+            .get(0)
+            .and_then(|voice| voice.graph.global_gatemixer_node)
+            .map(|id| id.to_string())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_pressure_node_id(&mut self) -> Option<String> {
+        self.voices
+            .get(0)
+            .and_then(|voice| voice.graph.global_pressure_node)
+            .map(|id| id.to_string())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_timbre_node_id(&mut self) -> Option<String> {
+        self.voices
+            .get(0)
+            .and_then(|voice| voice.graph.global_timbre_node)
+            .map(|id| id.to_string())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_release_velocity_node_id(&mut self) -> Option<String> {
+        self.voices
+            .get(0)
+            .and_then(|voice| voice.graph.global_release_velocity_node)
+            .map(|id| id.to_string())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_envelope(
+        &mut self,
+        node_id: &str,
+        attack: f32,
+        decay: f32,
+        sustain: f32,
+        release: f32,
+        attack_curve: f32,
+        decay_curve: f32,
+        release_curve: f32,
+        active: bool,
+    ) -> Result<(), JsValue> {
+        self.apply_envelope_update(
+            node_id,
+            EnvelopeUpdate {
+                attack,
+                decay,
+                sustain,
+                release,
+                attack_curve,
+                decay_curve,
+                release_curve,
+                active,
+            },
+        )
+    }
+
+    /// Object form of `update_envelope`, taking the fields of `EnvelopeUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_envelope_params(
+        &mut self,
+        node_id: &str,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_envelope_update(node_id, params)
+    }
+
+    fn apply_envelope_update(
+        &mut self,
+        node_id: &str,
+        params: EnvelopeUpdate,
+    ) -> Result<(), JsValue> {
+        let EnvelopeUpdate {
+            attack,
+            decay,
+            sustain,
+            release,
+            attack_curve,
+            decay_curve,
+            release_curve,
+            active,
+        } = params;
+        let mut errors: Vec<String> = Vec::new();
+
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+
+        // Iterate over all voices and attempt to update the envelope.
+        for (i, voice) in self.voices.iter_mut().enumerate() {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(env) = node.as_any_mut().downcast_mut::<Envelope>() {
+                    let config = EnvelopeConfig {
+                        attack,
+                        decay,
+                        sustain,
+                        release,
+                        attack_curve,
+                        decay_curve,
+                        release_curve,
+                        attack_smoothing_samples: 16,
+                        active,
+                        ..env.config().clone()
+                    };
+                    env.update_config(config);
+                    env.set_active(active);
+                } else {
+                    errors.push(format!("Voice {}: Node is not an Envelope", i));
+                }
+            } else {
+                errors.push(format!("Voice {}: Node not found", i));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(JsValue::from_str(&errors.join("; ")))
+        }
+    }
+
+    /// Sets the level the envelope's gate input must cross to trigger it,
+    /// so LFO or sequencer gates routed to it can cycle it rhythmically.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_envelope_gate_threshold(
+        &mut self,
+        node_id: &str,
+        threshold: f32,
+    ) -> Result<(), JsValue> {
+        let mut errors: Vec<String> = Vec::new();
+
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+
+        for (i, voice) in self.voices.iter_mut().enumerate() {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(env) = node.as_any_mut().downcast_mut::<Envelope>() {
+                    env.set_gate_threshold(threshold);
+                } else {
+                    errors.push(format!("Voice {}: Node is not an Envelope", i));
+                }
+            } else {
+                errors.push(format!("Voice {}: Node not found", i));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(JsValue::from_str(&errors.join("; ")))
+        }
+    }
+
+    /// Sets how note velocity and pitch scale the envelope's stage times:
+    /// velocity -> attack/decay and keytrack -> decay/release amounts.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_envelope_time_scaling(
+        &mut self,
+        node_id: &str,
+        velocity_attack: f32,
+        velocity_decay: f32,
+        key_decay: f32,
+        key_release: f32,
+    ) -> Result<(), JsValue> {
+        let mut errors: Vec<String> = Vec::new();
+
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+
+        for (i, voice) in self.voices.iter_mut().enumerate() {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(env) = node.as_any_mut().downcast_mut::<Envelope>() {
+                    env.set_time_scaling(velocity_attack, velocity_decay, key_decay, key_release);
+                } else {
+                    errors.push(format!("Voice {}: Node is not an Envelope", i));
+                }
+            } else {
+                errors.push(format!("Voice {}: Node not found", i));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(JsValue::from_str(&errors.join("; ")))
+        }
+    }
+
+    /// Sets how much the note-off velocity scales the release time of an
+    /// envelope, or of a sampler's zone amp envelopes. At 1.0 the slowest
+    /// key lift releases 4x longer and the fastest 4x shorter.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_release_velocity_amount(
+        &mut self,
+        node_id: &str,
+        amount: f32,
+    ) -> Result<(), JsValue> {
+        let mut errors: Vec<String> = Vec::new();
+
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+
+        for (i, voice) in self.voices.iter_mut().enumerate() {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                let node = node.as_any_mut();
+                if let Some(env) = node.downcast_mut::<Envelope>() {
+                    env.set_release_velocity_amount(amount);
+                } else if let Some(sampler) = node.downcast_mut::<Sampler>() {
+                    sampler.set_release_velocity_amount(amount);
+                } else {
+                    errors.push(format!("Voice {}: Node is not an Envelope or Sampler", i));
+                }
+            } else {
+                errors.push(format!("Voice {}: Node not found", i));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(JsValue::from_str(&errors.join("; ")))
+        }
+    }
+
+    /// Turns drone mode on or off for an envelope: with it on, gate-off
+    /// keeps the envelope sustaining until `release_drones` is called.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_envelope_drone(&mut self, node_id: &str, drone: bool) -> Result<(), JsValue> {
+        let mut errors: Vec<String> = Vec::new();
+
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+
+        for (i, voice) in self.voices.iter_mut().enumerate() {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(env) = node.as_any_mut().downcast_mut::<Envelope>() {
+                    env.set_drone(drone);
+                } else {
+                    errors.push(format!("Voice {}: Node is not an Envelope", i));
+                }
+            } else {
+                errors.push(format!("Voice {}: Node not found", i));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(JsValue::from_str(&errors.join("; ")))
+        }
+    }
+
+    /// Releases every envelope held by drone mode, in all voices.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn release_drones(&mut self) {
+        for voice in &mut self.voices {
+            for node in voice.graph.nodes.values_mut() {
+                if let Some(env) = node.as_any_mut().downcast_mut::<Envelope>() {
+                    env.release_drone();
+                }
+            }
+        }
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_envelope_preview(
+        sample_rate: f32,
+        js_config: JsValue,
+        preview_duration: f32,
+        velocity: Option<f32>,
+        frequency: Option<f32>,
+    ) -> Result<js_sys::Float32Array, JsValue> {
+        // Deserialize the JS object into our helper struct.
+        let js_conf: JsEnvelopeConfig = serde_wasm_bindgen::from_value(js_config)
+            .map_err(|err| JsValue::from_str(&err.to_string()))?;
+
+        // Convert it into our internal EnvelopeConfig.
+        let config: EnvelopeConfig = js_conf.into();
+
+        // Create a temporary envelope and generate the preview.
+        let envelope = Envelope::new(sample_rate, config);
+        // Without a note, preview a full-velocity middle C (no time scaling).
+        let preview_values = envelope.preview(
+            preview_duration,
+            velocity.unwrap_or(1.0),
+            frequency.unwrap_or(KEYTRACK_REFERENCE_HZ),
+        );
+
+        // Convert Vec<f32> into a Float32Array.
+        let array = js_sys::Float32Array::new_with_length(preview_values.len() as u32);
+        for (i, &value) in preview_values.iter().enumerate() {
+            array.set_index(i as u32, value);
+        }
+        Ok(array)
+    }
+
+    /// Preview of an MSEG for the UI; `points_json` is the same JSON
+    /// `MsegConfig` `update_mseg` takes.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_mseg_preview(
+        sample_rate: f32,
+        points_json: &str,
+        preview_duration: f32,
+    ) -> Result<js_sys::Float32Array, JsValue> {
+        let config: MsegConfig = serde_json::from_str(points_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid MSEG config: {}", e)))?;
+        let preview_values = Mseg::new(sample_rate, config).preview(preview_duration);
+        Ok(js_sys::Float32Array::from(preview_values.as_slice()))
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_wavetable_oscillator(
+        &mut self,
+        oscillator_id: &str,
+        params: &WavetableOscillatorStateUpdate,
+    ) -> Result<(), JsValue> {
+        let oscillator_id = NodeId::from_string(oscillator_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid oscillator_id UUID: {}", e)))?;
+
+        for voice in &mut self.voices {
+            let node = voice
+                .graph
+                .get_node_mut(oscillator_id)
+                .ok_or_else(|| JsValue::from_str("Node not found in one of the voices"))?;
+            let osc = node
+                .as_any_mut()
+                .downcast_mut::<WavetableOscillator>()
+                .ok_or_else(|| {
+                    JsValue::from_str("Node is not a WavetableOscillator in one of the voices")
+                })?;
+            osc.update_params(params);
+        }
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn import_wave_impulse(&mut self, effect_id: usize, data: &[u8]) -> Result<(), JsValue> {
+        use rubato::{
+            Resampler, SincFixedIn, SincInterpolationParameters, SincInterpolationType,
+            WindowFunction,
+        };
+        log_console("Starting import_wave_impulse");
+
+        let wav = SampleData::from_wav_bytes(data).map_err(|e| JsValue::from_str(&e))?;
+        let file_sample_rate = wav.sample_rate;
+        log_console(&format!(
+            "WAV spec: sample_rate={}, channels={}",
+            file_sample_rate, wav.channels
+        ));
+        let num_channels = wav.channels;
+        let samples = wav.samples;
+
+        log_console(&format!("Read {} samples", samples.len()));
+
+        // If the WAV is multi‑channel, average the channels to obtain a mono impulse response.
+        let mut ir = if num_channels == 1 {
+            samples
+        } else {
+            let mut mono = Vec::with_capacity(samples.len() / num_channels);
+            for chunk in samples.chunks(num_channels) {
+                let avg = chunk.iter().sum::<f32>() / (num_channels as f32);
+                mono.push(avg);
+            }
+            mono
+        };
+
+        log_console(&format!("Mono IR length: {}", ir.len()));
+        if ir.is_empty() {
+            return Err(JsValue::from_str("Impulse response is empty"));
+        }
+
+        // Locate the effect in the effect stack using the given id (subtracting the offset).
+        let index = effect_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| JsValue::from_str("Invalid effect id"))?;
+        if index >= self.effect_stack.effects.len() {
+            return Err(JsValue::from_str("Effect id not found in effect stack"));
+        }
+
+        let quality_mode = self.effect_stack.quality_mode();
+        // Attempt to downcast the effect node to a Convolver.
+        let effect = &mut self.effect_stack.effects[index];
         if let Some(convolver) = effect.node.as_any_mut().downcast_mut::<Convolver>() {
             let partition_size = convolver.partition_size;
             let target_sample_rate = convolver.sample_rate;
-            let wet_level = convolver.wet_level;
+            let wet_level = convolver.wet_level();
+            log_console(&format!(
+                "Convolver target sample rate: {}",
+                target_sample_rate
+            ));
+
+            // If the IR's sample rate doesn't match the target sample rate, resample it.
+            if file_sample_rate != target_sample_rate {
+                log_console(&format!(
+                    "Resampling needed: IR sample rate {} != target {}",
+                    file_sample_rate, target_sample_rate
+                ));
+                let conversion_ratio = target_sample_rate as f64 / file_sample_rate as f64;
+                log_console(&format!("Conversion ratio: {}", conversion_ratio));
+                let chunk_size = 1024; // fixed chunk size
+                log_console(&format!("Using chunk size: {}", chunk_size));
+
+                // Set up the interpolation parameters per the rubato example.
+                let params = SincInterpolationParameters {
+                    sinc_len: 256,
+                    f_cutoff: 0.95,
+                    interpolation: SincInterpolationType::Linear,
+                    oversampling_factor: 256,
+                    window: WindowFunction::BlackmanHarris2,
+                };
+
+                // Create a SincFixedIn resampler for 1 channel.
+                let mut resampler =
+                    SincFixedIn::<f64>::new(conversion_ratio, 2.0, params, chunk_size, 1).map_err(
+                        |e| JsValue::from_str(&format!("Resampler creation error: {:?}", e)),
+                    )?;
+
+                // Convert the IR to f64.
+                let ir_f64: Vec<f64> = ir.iter().map(|&x| x as f64).collect();
+                let mut resampled_output: Vec<f64> = Vec::new();
+
+                // Process the IR in blocks of `chunk_size`.
+                let mut pos = 0;
+                while pos < ir_f64.len() {
+                    let end = (pos + chunk_size).min(ir_f64.len());
+                    let mut block = ir_f64[pos..end].to_vec();
+                    if block.len() < chunk_size {
+                        // Pad with zeros to fill the chunk.
+                        block.resize(chunk_size, 0.0);
+                    }
+                    let input_block = vec![block]; // one channel
+                    let out = resampler.process(&input_block, None).map_err(|e| {
+                        JsValue::from_str(&format!("Resampling process error: {:?}", e))
+                    })?;
+                    // Append the output (first channel) to our accumulator.
+                    resampled_output.extend(out[0].iter());
+                    pos += chunk_size;
+                }
+
+                // Now flush any remaining samples using process_partial.
+                let partial = resampler
+                    .process_partial::<Vec<f64>>(None, None)
+                    .map_err(|e| JsValue::from_str(&format!("Resampler flush error: {:?}", e)))?;
+                resampled_output.extend(partial[0].iter());
+                log_console(&format!(
+                    "Resampled output length: {}",
+                    resampled_output.len()
+                ));
+
+                // Replace IR with the resampled output (convert back to f32).
+                ir = resampled_output.into_iter().map(|x| x as f32).collect();
+                log_console(&format!("Final IR length after resampling: {}", ir.len()));
+            } else {
+                log_console("No resampling needed");
+            }
+
+            // Create a new convolver using the (resampled) impulse response.
+            let mut new_convolver = Convolver::new(ir, partition_size, target_sample_rate);
+            new_convolver.set_quality_mode(quality_mode);
+            effect.node = Box::new(new_convolver);
+            // Restore the original wet level.
+            if let Some(new_conv) = effect.node.as_any_mut().downcast_mut::<Convolver>() {
+                new_conv.set_wet_level(wet_level);
+            }
+            log_console("Impulse response imported successfully");
+            Ok(())
+        } else {
+            Err(JsValue::from_str("Effect is not a Convolver"))
+        }
+    }
+
+    /// Imports a wavetable into an oscillator. It accepts a `.wt` file or WAV
+    /// data as a byte slice, decodes it with `read_wavetable_file` (`base_size`
+    /// 0 lets the file decide the cycle size), builds a new morph collection from
+    /// the data, adds it to the synth bank under "wt_<node_id>", and then points
+    /// that oscillator at it in every voice.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn import_wavetable(
+        &mut self,
+        node_id: &str,
+        data: &[u8],
+        base_size: usize,
+    ) -> Result<(), JsValue> {
+        let collection_name = format!("wt_{}", node_id);
+        let collection = import_wavetable_file(data, base_size, self.sample_rate)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        // Parse the target oscillator ID.
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+
+        // Keep existing collections (including the default) and register this one by node.
+        {
+            let mut bank = self.wavetable_synthbank.borrow_mut();
+            self.imports
+                .backup_wavetable(&bank, &collection_name, Some(node_id), &self.voices);
+            if !bank.collections.contains_key("default") {
+                bank.add_collection(
+                    "default",
+                    WavetableMorphCollection::generate_test_collection(self.sample_rate),
+                );
+            }
+            bank.add_collection(&collection_name, collection);
+        }
+
+        let collection_name_clone = collection_name.clone();
+        // Update the oscillator's active wavetable to the newly imported collection.
+        for voice in &mut self.voices {
+            let node = voice
+                .graph
+                .get_node_mut(node_id)
+                .ok_or_else(|| JsValue::from_str("Node not found in one of the voices"))?;
+            let osc = node
+                .as_any_mut()
+                .downcast_mut::<WavetableOscillator>()
+                .ok_or_else(|| {
+                    JsValue::from_str("Node is not a WavetableOscillator in one of the voices")
+                })?;
+            osc.set_current_wavetable(&collection_name_clone);
+        }
+
+        Ok(())
+    }
+
+    /// The harmonics of one frame of a wavetable collection ("default", or
+    /// "wt_<node id>" for an imported table) as `{ magnitudes, phases }`,
+    /// indexed by harmonic from DC up to Nyquist.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_wavetable_frame_spectrum(
+        &self,
+        name: &str,
+        frame: usize,
+    ) -> Result<JsValue, JsValue> {
+        let spectrum = self
+            .wavetable_synthbank
+            .borrow()
+            .get_frame_spectrum(name, frame)
+            .map_err(|e| JsValue::from_str(&e))?;
+        serde_wasm_bindgen::to_value(&spectrum)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize spectrum: {}", e)))
+    }
+
+    /// Rebuilds one frame of a wavetable collection from its harmonics, in
+    /// the form `get_wavetable_frame_spectrum` returns.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_wavetable_frame_spectrum(
+        &mut self,
+        name: &str,
+        frame: usize,
+        magnitudes: &[f32],
+        phases: &[f32],
+    ) -> Result<(), JsValue> {
+        self.wavetable_synthbank
+            .borrow_mut()
+            .set_frame_spectrum(name, frame, magnitudes, phases)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Adds a `.wt` or WAV wavetable to the bank under `name`, replacing any
+    /// table of that name, without assigning it to an oscillator (see
+    /// `set_current_wavetable`). `base_size` is as for `import_wavetable`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn import_wavetable_named(
+        &mut self,
+        name: &str,
+        data: &[u8],
+        base_size: usize,
+    ) -> Result<(), JsValue> {
+        let collection = import_wavetable_file(data, base_size, self.sample_rate)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let mut bank = self.wavetable_synthbank.borrow_mut();
+        self.imports
+            .backup_wavetable(&bank, name, None, &self.voices);
+        bank.add_collection(name, collection);
+        Ok(())
+    }
+
+    /// Keeps the wavetable or sample each import replaces, so the last import
+    /// can be undone with `revert_last_import`. Turning it off frees the backup.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_import_backup(&mut self, enabled: bool) {
+        self.imports.set_enabled(enabled);
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_import_backup(&self) -> bool {
+        self.imports.is_enabled()
+    }
+
+    /// Whether `revert_last_import` has a backup to restore.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn can_revert_import(&self) -> bool {
+        self.imports.can_revert()
+    }
+
+    /// Restores what the last import replaced: the previous collection (or
+    /// none) under its name, what its oscillator played, or a sampler's
+    /// previous sample. Returns false when there is no backup.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn revert_last_import(&mut self) -> Result<bool, JsValue> {
+        let mut bank = self.wavetable_synthbank.borrow_mut();
+        self.imports
+            .revert(&mut bank, &mut self.voices)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Rebuilds the bank analog oscillators play for `waveform` from a single
+    /// cycle (power-of-two length) and moves every voice onto it.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_waveform_cycle(&mut self, waveform: Waveform, cycle: &[f32]) -> Result<(), JsValue> {
+        let bank = WavetableBank::from_cycle(cycle, self.sample_rate)
+            .map_err(|e| JsValue::from_str(&e))?;
+        self.swap_waveform_bank(waveform, Some(Arc::new(bank)));
+        Ok(())
+    }
+
+    /// Puts back the built-in bank for `waveform`; `Waveform::Custom` is left
+    /// without one.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn reset_waveform_bank(&mut self, waveform: Waveform) -> Result<(), JsValue> {
+        let bank = match waveform {
+            Waveform::Custom => None,
+            _ => Some(Arc::new(
+                WavetableBank::new(waveform, MAX_TABLE_SIZE, self.sample_rate)
+                    .map_err(|e| JsValue::from_str(&e.to_string()))?,
+            )),
+        };
+        self.swap_waveform_bank(waveform, bank);
+        Ok(())
+    }
+
+    fn swap_waveform_bank(&mut self, waveform: Waveform, bank: Option<Arc<WavetableBank>>) {
+        let mut banks = (*self.wavetable_banks).clone();
+        match bank {
+            Some(bank) => banks.insert(waveform, bank),
+            None => banks.remove(&waveform),
+        };
+        let banks = Arc::new(banks);
+        self.wavetable_banks = Arc::clone(&banks);
+        let extra_voices = self.parts.extra_voices_mut().map(|(voice, _)| voice);
+        for voice in self.voices.iter_mut().chain(extra_voices) {
+            for node in voice.graph.nodes.values_mut() {
+                if let Some(osc) = node.as_any_mut().downcast_mut::<AnalogOscillator>() {
+                    osc.set_wavetable_banks(Arc::clone(&banks));
+                }
+            }
+        }
+    }
+
+    /// Names of the wavetables oscillators can play: "default", tables
+    /// imported into an oscillator ("wt_<node id>") and named imports.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn list_wavetables(&self) -> Vec<String> {
+        self.wavetable_synthbank.borrow().collection_names()
+    }
+
+    /// Removes a wavetable from the bank. Oscillators playing it fall back to
+    /// "default", which can't be deleted.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn delete_wavetable(&mut self, name: &str) -> Result<(), JsValue> {
+        if name == "default" {
+            return Err(JsValue::from_str("The default wavetable can't be deleted"));
+        }
+        if !self
+            .wavetable_synthbank
+            .borrow_mut()
+            .remove_collection(name)
+        {
+            return Err(JsValue::from_str(&format!("Wavetable {} not found", name)));
+        }
+        for voice in &mut self.voices {
+            for node in voice.graph.nodes.values_mut() {
+                if let Some(osc) = node.as_any_mut().downcast_mut::<WavetableOscillator>() {
+                    if osc.current_wavetable() == name {
+                        osc.set_current_wavetable("default");
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Points a wavetable oscillator at a wavetable in the bank.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_current_wavetable(&mut self, node_id: &str, name: &str) -> Result<(), JsValue> {
+        if self
+            .wavetable_synthbank
+            .borrow()
+            .get_collection(name)
+            .is_none()
+        {
+            return Err(JsValue::from_str(&format!("Wavetable {} not found", name)));
+        }
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+        for voice in &mut self.voices {
+            voice
+                .graph
+                .get_node_mut(node_id)
+                .ok_or_else(|| JsValue::from_str("Node not found in one of the voices"))?
+                .as_any_mut()
+                .downcast_mut::<WavetableOscillator>()
+                .ok_or_else(|| JsValue::from_str("Node is not a WavetableOscillator"))?
+                .set_current_wavetable(name);
+        }
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_oscillator(
+        &mut self,
+        oscillator_id: &str,
+        params: &AnalogOscillatorStateUpdate,
+    ) -> Result<(), JsValue> {
+        let oscillator_id = NodeId::from_string(oscillator_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid oscillator_id UUID: {}", e)))?;
+
+        for voice in &mut self.voices {
+            let node = voice
+                .graph
+                .get_node_mut(oscillator_id)
+                .ok_or_else(|| JsValue::from_str("Node not found in one of the voices"))?;
+            let osc = node
+                .as_any_mut()
+                .downcast_mut::<AnalogOscillator>()
+                .ok_or_else(|| {
+                    JsValue::from_str("Node is not an AnalogOscillator in one of the voices")
+                })?;
+            osc.update_params(params);
+        }
+        Ok(())
+    }
+
+    pub fn update_delay(
+        &mut self,
+        node_id: usize,
+        delay_ms: f32,
+        feedback: f32,
+        wet_mix: f32,
+        enabled: bool,
+    ) {
+        self.apply_delay_update(
+            node_id,
+            DelayUpdate {
+                delay_ms,
+                feedback,
+                wet_mix,
+                enabled,
+            },
+        );
+    }
+
+    /// Object form of `update_delay`, taking the fields of `DelayUpdate` (see `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_delay_params(&mut self, node_id: usize, params: JsValue) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_delay_update(node_id, params);
+        Ok(())
+    }
+
+    fn apply_delay_update(&mut self, node_id: usize, params: DelayUpdate) {
+        let DelayUpdate {
+            delay_ms,
+            feedback,
+            wet_mix,
+            enabled,
+        } = params;
+        // Calculate the effect index based on the provided node_id.
+        let effect_id = node_id - EFFECT_NODE_ID_OFFSET;
+
+        // Try to get a mutable reference to the effect at that index.
+        if let Some(effect) = self.effect_stack.effects.get_mut(effect_id) {
+            // Attempt to downcast the boxed AudioNode to a Convolver.
+            if let Some(delay) = effect.node.as_any_mut().downcast_mut::<Delay>() {
+                delay.set_delay_ms(delay_ms);
+                delay.set_feedback(feedback);
+                delay.set_mix(wet_mix);
+                delay.set_active(enabled);
+            } else {
+                // Log a warning if the node at that index isn't a Convolver.
+                log_console(&format!("Effect at index {} is not a Delay", effect_id));
+            }
+        } else {
+            // Log a warning if there is no effect at that index.
+            log_console(&format!("No effect found at index {}", effect_id));
+        }
+    }
+
+    pub fn update_saturation(&mut self, node_id: usize, drive: f32, mix: f32, active: bool) {
+        self.apply_saturation_update(node_id, SaturationUpdate { drive, mix, active });
+    }
+
+    /// Object form of `update_saturation`, taking the fields of `SaturationUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_saturation_params(
+        &mut self,
+        node_id: usize,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_saturation_update(node_id, params);
+        Ok(())
+    }
+
+    fn apply_saturation_update(&mut self, node_id: usize, params: SaturationUpdate) {
+        let SaturationUpdate { drive, mix, active } = params;
+        let Some(effect_id) = node_id.checked_sub(EFFECT_NODE_ID_OFFSET) else {
+            log_console(&format!(
+                "Invalid saturation node id {}; expected offset {}",
+                node_id, EFFECT_NODE_ID_OFFSET
+            ));
+            return;
+        };
+
+        if let Some(effect) = self.effect_stack.effects.get_mut(effect_id) {
+            if let Some(saturation) = effect.node.as_any_mut().downcast_mut::<Saturation>() {
+                saturation.set_drive(drive);
+                saturation.set_mix(mix);
+                saturation.set_active(active);
+            } else {
+                log_console(&format!(
+                    "Effect at index {} is not a Saturation",
+                    effect_id
+                ));
+            }
+        } else {
+            log_console(&format!("No effect found at index {}", effect_id));
+        }
+    }
+
+    /// Sets the saturation insert's transfer curve, pre/post tilt EQ (dB) and auto-gain.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_saturation_tone(
+        &mut self,
+        node_id: usize,
+        character: SaturationCharacter,
+        pre_tilt_db: f32,
+        post_tilt_db: f32,
+        auto_gain: bool,
+    ) -> Result<(), JsValue> {
+        self.apply_saturation_tone_update(
+            node_id,
+            SaturationToneUpdate {
+                character,
+                pre_tilt_db,
+                post_tilt_db,
+                auto_gain,
+            },
+        )
+    }
+
+    /// Object form of `update_saturation_tone`, taking the fields of `SaturationToneUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_saturation_tone_params(
+        &mut self,
+        node_id: usize,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_saturation_tone_update(node_id, params)
+    }
+
+    fn apply_saturation_tone_update(
+        &mut self,
+        node_id: usize,
+        params: SaturationToneUpdate,
+    ) -> Result<(), JsValue> {
+        let SaturationToneUpdate {
+            character,
+            pre_tilt_db,
+            post_tilt_db,
+            auto_gain,
+        } = params;
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| JsValue::from_str("Invalid saturation node id"))?;
+        let effect = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .ok_or_else(|| JsValue::from_str(&format!("No effect found at index {}", effect_id)))?;
+        let saturation = effect
+            .node
+            .as_any_mut()
+            .downcast_mut::<Saturation>()
+            .ok_or_else(|| {
+                JsValue::from_str(&format!(
+                    "Effect at index {} is not a Saturation",
+                    effect_id
+                ))
+            })?;
+
+        saturation.set_character(character);
+        saturation.set_pre_tilt(pre_tilt_db);
+        saturation.set_post_tilt(post_tilt_db);
+        saturation.set_auto_gain(auto_gain);
+        Ok(())
+    }
+
+    pub fn update_bitcrusher(
+        &mut self,
+        node_id: usize,
+        bits: u8,
+        downsample_factor: usize,
+        mix: f32,
+        active: bool,
+    ) {
+        self.apply_bitcrusher_update(
+            node_id,
+            BitcrusherUpdate {
+                bits,
+                downsample_factor,
+                mix,
+                active,
+            },
+        );
+    }
+
+    /// Object form of `update_bitcrusher`, taking the fields of `BitcrusherUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_bitcrusher_params(
+        &mut self,
+        node_id: usize,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_bitcrusher_update(node_id, params);
+        Ok(())
+    }
+
+    fn apply_bitcrusher_update(&mut self, node_id: usize, params: BitcrusherUpdate) {
+        let BitcrusherUpdate {
+            bits,
+            downsample_factor,
+            mix,
+            active,
+        } = params;
+        let Some(effect_id) = node_id.checked_sub(EFFECT_NODE_ID_OFFSET) else {
+            log_console(&format!(
+                "Invalid bitcrusher node id {}; expected offset {}",
+                node_id, EFFECT_NODE_ID_OFFSET
+            ));
+            return;
+        };
+
+        if let Some(effect) = self.effect_stack.effects.get_mut(effect_id) {
+            if let Some(crusher) = effect.node.as_any_mut().downcast_mut::<Bitcrusher>() {
+                crusher.set_bits(bits);
+                crusher.set_downsample_factor(downsample_factor);
+                crusher.set_mix(mix);
+                crusher.set_active(active);
+            } else {
+                log_console(&format!(
+                    "Effect at index {} is not a Bitcrusher",
+                    effect_id
+                ));
+            }
+        } else {
+            log_console(&format!("No effect found at index {}", effect_id));
+        }
+    }
+
+    pub fn update_stereo_enhancer(
+        &mut self,
+        node_id: usize,
+        active: bool,
+        delay_left_ms: f32,
+        delay_right_ms: f32,
+        width: f32,
+        comb_compensation: f32,
+        mix: f32,
+    ) {
+        self.apply_stereo_enhancer_update(
+            node_id,
+            StereoEnhancerUpdate {
+                active,
+                delay_left_ms,
+                delay_right_ms,
+                width,
+                comb_compensation,
+                mix,
+            },
+        );
+    }
+
+    /// Object form of `update_stereo_enhancer`, taking the fields of `StereoEnhancerUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_stereo_enhancer_params(
+        &mut self,
+        node_id: usize,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_stereo_enhancer_update(node_id, params);
+        Ok(())
+    }
+
+    fn apply_stereo_enhancer_update(&mut self, node_id: usize, params: StereoEnhancerUpdate) {
+        let StereoEnhancerUpdate {
+            active,
+            delay_left_ms,
+            delay_right_ms,
+            width,
+            comb_compensation,
+            mix,
+        } = params;
+        let Some(effect_id) = node_id.checked_sub(EFFECT_NODE_ID_OFFSET) else {
+            log_console(&format!(
+                "Invalid stereo enhancer node id {}; expected offset {}",
+                node_id, EFFECT_NODE_ID_OFFSET
+            ));
+            return;
+        };
+
+        if let Some(effect) = self.effect_stack.effects.get_mut(effect_id) {
+            if let Some(enhancer) = effect.node.as_any_mut().downcast_mut::<StereoEnhancer>() {
+                enhancer.set_delay_left_ms(delay_left_ms);
+                enhancer.set_delay_right_ms(delay_right_ms);
+                enhancer.set_width(width);
+                enhancer.set_comb_compensation(comb_compensation);
+                enhancer.set_mix(mix);
+                enhancer.set_active(active);
+            } else {
+                log_console(&format!(
+                    "Effect at index {} is not a StereoEnhancer",
+                    effect_id
+                ));
+            }
+        } else {
+            log_console(&format!("No effect found at index {}", effect_id));
+        }
+    }
+
+    pub fn update_binaural(
+        &mut self,
+        node_id: usize,
+        active: bool,
+        azimuth: f32,
+        elevation: f32,
+        mix: f32,
+    ) {
+        self.apply_binaural_update(
+            node_id,
+            BinauralUpdate {
+                active,
+                azimuth,
+                elevation,
+                mix,
+            },
+        );
+    }
+
+    /// Object form of `update_binaural`, taking the fields of `BinauralUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_binaural_params(
+        &mut self,
+        node_id: usize,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_binaural_update(node_id, params);
+        Ok(())
+    }
+
+    fn apply_binaural_update(&mut self, node_id: usize, params: BinauralUpdate) {
+        let BinauralUpdate {
+            active,
+            azimuth,
+            elevation,
+            mix,
+        } = params;
+        let Some(effect_id) = node_id.checked_sub(EFFECT_NODE_ID_OFFSET) else {
+            log_console(&format!(
+                "Invalid binaural node id {}; expected offset {}",
+                node_id, EFFECT_NODE_ID_OFFSET
+            ));
+            return;
+        };
+
+        if let Some(effect) = self.effect_stack.effects.get_mut(effect_id) {
+            if let Some(binaural) = effect.node.as_any_mut().downcast_mut::<Binaural>() {
+                binaural.set_position(azimuth, elevation);
+                binaural.set_mix(mix);
+                binaural.set_active(active);
+            } else {
+                log_console(&format!("Effect at index {} is not a Binaural", effect_id));
+            }
+        } else {
+            log_console(&format!("No effect found at index {}", effect_id));
+        }
+    }
+
+    pub fn update_auto_wah(
+        &mut self,
+        node_id: usize,
+        active: bool,
+        sensitivity: f32,
+        frequency: f32,
+        range: f32,
+        q: f32,
+        direction: AutoWahDirection,
+        mix: f32,
+    ) {
+        self.apply_auto_wah_update(
+            node_id,
+            AutoWahUpdate {
+                active,
+                sensitivity,
+                frequency,
+                range,
+                q,
+                direction,
+                mix,
+            },
+        );
+    }
+
+    /// Object form of `update_auto_wah`, taking the fields of `AutoWahUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_auto_wah_params(
+        &mut self,
+        node_id: usize,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_auto_wah_update(node_id, params);
+        Ok(())
+    }
+
+    fn apply_auto_wah_update(&mut self, node_id: usize, params: AutoWahUpdate) {
+        let AutoWahUpdate {
+            active,
+            sensitivity,
+            frequency,
+            range,
+            q,
+            direction,
+            mix,
+        } = params;
+        let Some(effect_id) = node_id.checked_sub(EFFECT_NODE_ID_OFFSET) else {
+            log_console(&format!(
+                "Invalid auto-wah node id {}; expected offset {}",
+                node_id, EFFECT_NODE_ID_OFFSET
+            ));
+            return;
+        };
+
+        if let Some(effect) = self.effect_stack.effects.get_mut(effect_id) {
+            if let Some(wah) = effect.node.as_any_mut().downcast_mut::<AutoWah>() {
+                wah.set_sensitivity(sensitivity);
+                wah.set_frequency(frequency);
+                wah.set_range(range);
+                wah.set_q(q);
+                wah.set_direction(direction);
+                wah.set_mix(mix);
+                wah.set_active(active);
+            } else {
+                log_console(&format!("Effect at index {} is not an AutoWah", effect_id));
+            }
+        } else {
+            log_console(&format!("No effect found at index {}", effect_id));
+        }
+    }
+
+    pub fn update_exciter(
+        &mut self,
+        node_id: usize,
+        active: bool,
+        frequency: f32,
+        amount: f32,
+        mix: f32,
+    ) {
+        self.apply_exciter_update(
+            node_id,
+            ExciterUpdate {
+                active,
+                frequency,
+                amount,
+                mix,
+            },
+        );
+    }
+
+    /// Object form of `update_exciter`, taking the fields of `ExciterUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_exciter_params(
+        &mut self,
+        node_id: usize,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_exciter_update(node_id, params);
+        Ok(())
+    }
+
+    fn apply_exciter_update(&mut self, node_id: usize, params: ExciterUpdate) {
+        let ExciterUpdate {
+            active,
+            frequency,
+            amount,
+            mix,
+        } = params;
+        let Some(effect_id) = node_id.checked_sub(EFFECT_NODE_ID_OFFSET) else {
             log_console(&format!(
-                "Convolver target sample rate: {}",
-                target_sample_rate
+                "Invalid exciter node id {}; expected offset {}",
+                node_id, EFFECT_NODE_ID_OFFSET
+            ));
+            return;
+        };
+
+        if let Some(effect) = self.effect_stack.effects.get_mut(effect_id) {
+            if let Some(exciter) = effect.node.as_any_mut().downcast_mut::<Exciter>() {
+                exciter.set_frequency(frequency);
+                exciter.set_amount(amount);
+                exciter.set_mix(mix);
+                exciter.set_active(active);
+            } else {
+                log_console(&format!("Effect at index {} is not an Exciter", effect_id));
+            }
+        } else {
+            log_console(&format!("No effect found at index {}", effect_id));
+        }
+    }
+
+    pub fn update_noise_gate(
+        &mut self,
+        node_id: usize,
+        active: bool,
+        threshold_db: f32,
+        hysteresis_db: f32,
+        attack_ms: f32,
+        hold_ms: f32,
+        release_ms: f32,
+        range_db: f32,
+    ) {
+        self.apply_noise_gate_update(
+            node_id,
+            NoiseGateUpdate {
+                active,
+                threshold_db,
+                hysteresis_db,
+                attack_ms,
+                hold_ms,
+                release_ms,
+                range_db,
+            },
+        );
+    }
+
+    /// Object form of `update_noise_gate`, taking the fields of `NoiseGateUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_noise_gate_params(
+        &mut self,
+        node_id: usize,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_noise_gate_update(node_id, params);
+        Ok(())
+    }
+
+    fn apply_noise_gate_update(&mut self, node_id: usize, params: NoiseGateUpdate) {
+        let NoiseGateUpdate {
+            active,
+            threshold_db,
+            hysteresis_db,
+            attack_ms,
+            hold_ms,
+            release_ms,
+            range_db,
+        } = params;
+        let Some(effect_id) = node_id.checked_sub(EFFECT_NODE_ID_OFFSET) else {
+            log_console(&format!(
+                "Invalid noise gate node id {}; expected offset {}",
+                node_id, EFFECT_NODE_ID_OFFSET
             ));
+            return;
+        };
+
+        if let Some(effect) = self.effect_stack.effects.get_mut(effect_id) {
+            if let Some(gate) = effect.node.as_any_mut().downcast_mut::<NoiseGate>() {
+                gate.set_threshold_db(threshold_db);
+                gate.set_hysteresis_db(hysteresis_db);
+                gate.set_attack_ms(attack_ms);
+                gate.set_hold_ms(hold_ms);
+                gate.set_release_ms(release_ms);
+                gate.set_range_db(range_db);
+                gate.set_active(active);
+            } else {
+                log_console(&format!("Effect at index {} is not a NoiseGate", effect_id));
+            }
+        } else {
+            log_console(&format!("No effect found at index {}", effect_id));
+        }
+    }
+
+    fn multiband_mut(&mut self, node_id: usize) -> Result<&mut Multiband, JsValue> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| JsValue::from_str(&format!("Invalid multiband node id {}", node_id)))?;
+
+        self.effect_stack
+            .effects
+            .get_mut(effect_id)
+            .and_then(|effect| effect.node.as_any_mut().downcast_mut::<Multiband>())
+            .ok_or_else(|| {
+                JsValue::from_str(&format!("Effect at index {} is not a Multiband", effect_id))
+            })
+    }
+
+    /// Sets the crossover frequencies of a multiband container; 1 to 3
+    /// crossovers give 2 to 4 bands.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_multiband(
+        &mut self,
+        node_id: usize,
+        active: bool,
+        crossovers: Vec<f32>,
+    ) -> Result<(), JsValue> {
+        self.apply_multiband_update(node_id, MultibandUpdate { active, crossovers })
+    }
+
+    /// Object form of `update_multiband`, taking the fields of `MultibandUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_multiband_params(
+        &mut self,
+        node_id: usize,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_multiband_update(node_id, params)
+    }
+
+    fn apply_multiband_update(
+        &mut self,
+        node_id: usize,
+        params: MultibandUpdate,
+    ) -> Result<(), JsValue> {
+        let MultibandUpdate { active, crossovers } = params;
+        let multiband = self.multiband_mut(node_id)?;
+        multiband.set_crossovers(&crossovers);
+        multiband.set_active(active);
+        Ok(())
+    }
+
+    /// Sets a band's linear gain, mute and solo.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_multiband_band(
+        &mut self,
+        node_id: usize,
+        band: usize,
+        gain: f32,
+        muted: bool,
+        soloed: bool,
+    ) -> Result<(), JsValue> {
+        self.apply_multiband_band_update(
+            node_id,
+            MultibandBandUpdate {
+                band,
+                gain,
+                muted,
+                soloed,
+            },
+        )
+    }
+
+    /// Object form of `update_multiband_band`, taking the fields of `MultibandBandUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_multiband_band_params(
+        &mut self,
+        node_id: usize,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_multiband_band_update(node_id, params)
+    }
+
+    fn apply_multiband_band_update(
+        &mut self,
+        node_id: usize,
+        params: MultibandBandUpdate,
+    ) -> Result<(), JsValue> {
+        let MultibandBandUpdate {
+            band,
+            gain,
+            muted,
+            soloed,
+        } = params;
+        let multiband = self.multiband_mut(node_id)?;
+        if band >= multiband.band_count() {
+            return Err(JsValue::from_str(&format!("Invalid band {}", band)));
+        }
+        multiband.set_band(band, gain, muted, soloed);
+        Ok(())
+    }
+
+    /// Sets the balance of a parallel container.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_parallel(
+        &mut self,
+        node_id: usize,
+        active: bool,
+        blend: f32,
+    ) -> Result<(), JsValue> {
+        self.apply_parallel_update(node_id, ParallelUpdate { active, blend })
+    }
+
+    /// Object form of `update_parallel`, taking the fields of `ParallelUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_parallel_params(
+        &mut self,
+        node_id: usize,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_parallel_update(node_id, params)
+    }
+
+    fn apply_parallel_update(
+        &mut self,
+        node_id: usize,
+        params: ParallelUpdate,
+    ) -> Result<(), JsValue> {
+        let ParallelUpdate { active, blend } = params;
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| JsValue::from_str(&format!("Invalid parallel node id {}", node_id)))?;
+
+        let parallel = self
+            .effect_stack
+            .effects
+            .get_mut(effect_id)
+            .and_then(|effect| effect.node.as_any_mut().downcast_mut::<Parallel>())
+            .ok_or_else(|| {
+                JsValue::from_str(&format!("Effect at index {} is not a Parallel", effect_id))
+            })?;
+        parallel.set_blend(blend);
+        parallel.set_active(active);
+        Ok(())
+    }
+
+    fn equalizer_mut(&mut self, node_id: usize) -> Result<&mut Equalizer, JsValue> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| JsValue::from_str(&format!("Invalid EQ node id {}", node_id)))?;
+
+        self.effect_stack
+            .effects
+            .get_mut(effect_id)
+            .and_then(|effect| effect.node.as_any_mut().downcast_mut::<Equalizer>())
+            .ok_or_else(|| {
+                JsValue::from_str(&format!("Effect at index {} is not an EQ", effect_id))
+            })
+    }
+
+    /// Turns an EQ on or off and sets its band count, up to `MAX_EQ_BANDS`.
+    /// Added bands start as flat peaks.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_eq(
+        &mut self,
+        node_id: usize,
+        active: bool,
+        band_count: usize,
+    ) -> Result<(), JsValue> {
+        self.apply_eq_update(node_id, EqUpdate { active, band_count })
+    }
+
+    /// Object form of `update_eq`, taking the fields of `EqUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_eq_params(&mut self, node_id: usize, params: JsValue) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_eq_update(node_id, params)
+    }
+
+    fn apply_eq_update(&mut self, node_id: usize, params: EqUpdate) -> Result<(), JsValue> {
+        let EqUpdate { active, band_count } = params;
+        let eq = self.equalizer_mut(node_id)?;
+        eq.set_band_count(band_count);
+        eq.set_active(active);
+        Ok(())
+    }
+
+    /// Sets one EQ band's shape, frequency (Hz), Q and gain (dB). Its dynamic
+    /// mode is kept.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_eq_band(
+        &mut self,
+        node_id: usize,
+        band: usize,
+        band_type: EqBandType,
+        frequency: f32,
+        q: f32,
+        gain_db: f32,
+    ) -> Result<(), JsValue> {
+        self.apply_eq_band_update(
+            node_id,
+            EqBandUpdate {
+                band,
+                band_type,
+                frequency,
+                q,
+                gain_db,
+            },
+        )
+    }
+
+    /// Object form of `update_eq_band`, taking the fields of `EqBandUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_eq_band_params(
+        &mut self,
+        node_id: usize,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_eq_band_update(node_id, params)
+    }
+
+    fn apply_eq_band_update(
+        &mut self,
+        node_id: usize,
+        params: EqBandUpdate,
+    ) -> Result<(), JsValue> {
+        let EqBandUpdate {
+            band,
+            band_type,
+            frequency,
+            q,
+            gain_db,
+        } = params;
+        let eq = self.equalizer_mut(node_id)?;
+        let settings = EqBand {
+            band_type,
+            frequency,
+            q,
+            gain_db,
+            dynamics: eq.bands().get(band).and_then(|current| current.dynamics),
+        };
+        if eq.set_band(band, settings) {
+            Ok(())
+        } else {
+            Err(JsValue::from_str(&format!("Invalid band {}", band)))
+        }
+    }
+
+    /// Dynamic mode of one EQ band: above `threshold_db` (dBFS, measured in
+    /// the band's range) its gain is turned down by `ratio`, with the
+    /// reduction following the level over `attack_ms` and `release_ms`.
+    /// `enabled` false turns the mode off.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_eq_band_dynamics(
+        &mut self,
+        node_id: usize,
+        band: usize,
+        enabled: bool,
+        threshold_db: f32,
+        ratio: f32,
+        attack_ms: f32,
+        release_ms: f32,
+    ) -> Result<(), JsValue> {
+        self.apply_eq_band_dynamics_update(
+            node_id,
+            EqBandDynamicsUpdate {
+                band,
+                enabled,
+                threshold_db,
+                ratio,
+                attack_ms,
+                release_ms,
+            },
+        )
+    }
+
+    /// Object form of `update_eq_band_dynamics`, taking the fields of
+    /// `EqBandDynamicsUpdate` (see `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_eq_band_dynamics_params(
+        &mut self,
+        node_id: usize,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_eq_band_dynamics_update(node_id, params)
+    }
+
+    fn apply_eq_band_dynamics_update(
+        &mut self,
+        node_id: usize,
+        params: EqBandDynamicsUpdate,
+    ) -> Result<(), JsValue> {
+        let EqBandDynamicsUpdate {
+            band,
+            enabled,
+            threshold_db,
+            ratio,
+            attack_ms,
+            release_ms,
+        } = params;
+        let dynamics = enabled.then_some(EqDynamics {
+            threshold_db,
+            ratio,
+            attack_ms,
+            release_ms,
+        });
+        self.set_eq_band_dynamics(node_id, band, dynamics)
+    }
+
+    fn set_eq_band_dynamics(
+        &mut self,
+        node_id: usize,
+        band: usize,
+        dynamics: Option<EqDynamics>,
+    ) -> Result<(), JsValue> {
+        if self
+            .equalizer_mut(node_id)?
+            .set_band_dynamics(band, dynamics)
+        {
+            Ok(())
+        } else {
+            Err(JsValue::from_str(&format!("Invalid band {}", band)))
+        }
+    }
+
+    /// Magnitude response in dB of an EQ at `length` frequencies spaced
+    /// logarithmically from 20 Hz to 20 kHz, for drawing its curve. Unlike
+    /// `get_filter_ir_waveform` the values are plain dB, computed from the
+    /// band coefficients rather than a rendered impulse.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_eq_response(&mut self, node_id: usize, length: usize) -> Result<Vec<f32>, JsValue> {
+        Ok(self.equalizer_mut(node_id)?.response_db(length))
+    }
+
+    /// Configures one of the effect stack's LFOs (waveform as in
+    /// `update_lfos`). A `sync_beats` above 0 runs one cycle every that many
+    /// beats of the effect tempo instead of at `rate_hz`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_effect_lfo(
+        &mut self,
+        index: usize,
+        waveform: u8,
+        rate_hz: f32,
+        sync_beats: f32,
+    ) -> Result<(), JsValue> {
+        if index >= EFFECT_LFO_COUNT {
+            return Err(JsValue::from_str(&format!("Invalid effect LFO {}", index)));
+        }
+        let sync_beats = (sync_beats > 0.0).then_some(sync_beats);
+        self.effect_stack
+            .set_lfo(index, LfoWaveform::from_u8(waveform), rate_hz, sync_beats);
+        Ok(())
+    }
+
+    /// Routes an effect LFO to a named parameter of a master effect (e.g.
+    /// "time" on a delay); `depth` is the offset at full swing in the
+    /// parameter's units, and 0 removes the route.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_effect_lfo_route(
+        &mut self,
+        lfo: usize,
+        node_id: usize,
+        parameter: &str,
+        depth: f32,
+    ) -> Result<(), JsValue> {
+        let index = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| JsValue::from_str(&format!("Invalid effect node id {}", node_id)))?;
+        if self
+            .effect_stack
+            .set_lfo_route(lfo, index, parameter, depth)
+        {
+            Ok(())
+        } else {
+            Err(JsValue::from_str(&format!(
+                "Cannot route effect LFO {} to {} on effect {}",
+                lfo, parameter, node_id
+            )))
+        }
+    }
+
+    /// Tempo the synced effect LFOs and delays follow.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_effect_tempo(&mut self, bpm: f32) {
+        self.effect_stack.set_tempo(bpm);
+    }
+
+    /// Runs a master effect on one band of a container (a multiband band or
+    /// parallel chain 0/1), or back in the serial chain when
+    /// `container_node_id` is `None`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_effect_band(
+        &mut self,
+        node_id: usize,
+        container_node_id: Option<usize>,
+        band: usize,
+    ) -> Result<(), JsValue> {
+        let index = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| JsValue::from_str(&format!("Invalid effect node id {}", node_id)))?;
+        let slot = match container_node_id {
+            Some(container_id) => Some(ContainerSlot {
+                container: container_id
+                    .checked_sub(EFFECT_NODE_ID_OFFSET)
+                    .ok_or_else(|| {
+                        JsValue::from_str(&format!("Invalid container node id {}", container_id))
+                    })?,
+                band,
+            }),
+            None => None,
+        };
+        if self.effect_stack.set_effect_band(index, slot) {
+            Ok(())
+        } else {
+            Err(JsValue::from_str(&format!(
+                "Cannot place effect {} on that band",
+                node_id
+            )))
+        }
+    }
+
+    fn looper_mut(&mut self, node_id: usize) -> Result<&mut Looper, JsValue> {
+        let effect_id = node_id
+            .checked_sub(EFFECT_NODE_ID_OFFSET)
+            .ok_or_else(|| JsValue::from_str(&format!("Invalid looper node id {}", node_id)))?;
+
+        self.effect_stack
+            .effects
+            .get_mut(effect_id)
+            .and_then(|effect| effect.node.as_any_mut().downcast_mut::<Looper>())
+            .ok_or_else(|| {
+                JsValue::from_str(&format!("Effect at index {} is not a Looper", effect_id))
+            })
+    }
+
+    /// Updates the looper's playback settings. The looper records whatever
+    /// reaches its slot in the effect chain, so it captures the full master
+    /// bus when it is the last effect.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_looper(
+        &mut self,
+        node_id: usize,
+        active: bool,
+        speed: LooperSpeed,
+        reverse: bool,
+        feedback: f32,
+        level: f32,
+    ) -> Result<(), JsValue> {
+        self.apply_looper_update(
+            node_id,
+            LooperUpdate {
+                active,
+                speed,
+                reverse,
+                feedback,
+                level,
+            },
+        )
+    }
+
+    /// Object form of `update_looper`, taking the fields of `LooperUpdate` (see `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_looper_params(&mut self, node_id: usize, params: JsValue) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_looper_update(node_id, params)
+    }
+
+    fn apply_looper_update(&mut self, node_id: usize, params: LooperUpdate) -> Result<(), JsValue> {
+        let LooperUpdate {
+            active,
+            speed,
+            reverse,
+            feedback,
+            level,
+        } = params;
+        let looper = self.looper_mut(node_id)?;
+        looper.set_speed(speed);
+        looper.set_reverse(reverse);
+        looper.set_feedback(feedback);
+        looper.set_level(level);
+        looper.set_active(active);
+        Ok(())
+    }
+
+    /// Record, play, overdub, stop or clear the loop.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn looper_command(
+        &mut self,
+        node_id: usize,
+        command: LooperCommand,
+    ) -> Result<(), JsValue> {
+        self.looper_mut(node_id)?.command(command);
+        Ok(())
+    }
+
+    /// Locks new recordings to `beats` beats at `bpm`; pass a tempo of 0 for
+    /// free-length loops.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_looper_tempo(
+        &mut self,
+        node_id: usize,
+        bpm: f32,
+        beats: f32,
+    ) -> Result<(), JsValue> {
+        self.looper_mut(node_id)?.set_tempo(bpm, beats);
+        Ok(())
+    }
+
+    /// Realigns a tempo-synced loop with the host transport position in beats.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn sync_looper_to_beat(
+        &mut self,
+        node_id: usize,
+        beat_position: f64,
+    ) -> Result<(), JsValue> {
+        self.looper_mut(node_id)?.sync_to_beat(beat_position);
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_looper_state(&mut self, node_id: usize) -> Result<LooperState, JsValue> {
+        Ok(self.looper_mut(node_id)?.state())
+    }
+
+    pub fn update_convolver(&mut self, node_id: usize, wet_mix: f32, enabled: bool) {
+        self.apply_convolver_update(node_id, ConvolverUpdate { wet_mix, enabled });
+    }
+
+    /// Object form of `update_convolver`, taking the fields of `ConvolverUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_convolver_params(
+        &mut self,
+        node_id: usize,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_convolver_update(node_id, params);
+        Ok(())
+    }
+
+    fn apply_convolver_update(&mut self, node_id: usize, params: ConvolverUpdate) {
+        let ConvolverUpdate { wet_mix, enabled } = params;
+        // Calculate the effect index based on the provided node_id.
+        let effect_id = node_id - EFFECT_NODE_ID_OFFSET;
+
+        // Try to get a mutable reference to the effect at that index.
+        if let Some(effect) = self.effect_stack.effects.get_mut(effect_id) {
+            // Attempt to downcast the boxed AudioNode to a Convolver.
+            if let Some(convolver) = effect.node.as_any_mut().downcast_mut::<Convolver>() {
+                convolver.set_wet_level(wet_mix);
+                convolver.set_enabled(enabled);
+            } else {
+                // Log a warning if the node at that index isn't a Convolver.
+                log_console(&format!("Effect at index {} is not a Convolver", effect_id));
+            }
+        } else {
+            // Log a warning if there is no effect at that index.
+            log_console(&format!("No effect found at index {}", effect_id));
+        }
+    }
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+
+    pub fn add_limiter(&mut self) -> Result<usize, JsValue> {
+        let mut limiter = Limiter::new(self.sample_rate, -0.5, 0.1, 50.0, 1.5, true);
+        limiter.set_active(true);
+        Ok(self.effect_stack.add_effect(Box::new(limiter)))
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn add_compressor(
+        &mut self,
+        threshold_db: f32,
+        ratio: f32,
+        attack_ms: f32,
+        release_ms: f32,
+        makeup_gain_db: f32,
+        mix: f32,
+    ) -> Result<usize, JsValue> {
+        let mut compressor = Compressor::new(
+            self.sample_rate,
+            threshold_db,
+            ratio,
+            attack_ms,
+            release_ms,
+            makeup_gain_db,
+            mix,
+        );
+        compressor.set_active(true);
+        Ok(self.effect_stack.add_effect(Box::new(compressor)))
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+
+    pub fn add_chorus(&mut self) -> Result<usize, JsValue> {
+        let mut chorus = Chorus::new(
+            self.sample_rate,
+            // max_base_delay_ms: Needs to accommodate the base delay + depth.
+            // max base delay in ms:
+            65.0,
+            // base_delay_ms: The central delay time. 20-30ms is typical for chorus.
+            15.0,
+            // depth_ms: How much the delay time varies. 2-7ms is a common range.
+            5.0,
+            // lfo_rate_hz: Speed of the warble. Slow rates are classic chorus.
+            // 0.3 - 0.8 Hz is a good starting range.
+            0.5,
+            // feedback: Usually low or zero for standard chorus. High feedback -> flanger.
+            0.3,
+            // mix: 0.5 gives an equal blend of dry and wet signal. Standard starting point.
+            0.5,
+            // stereo_phase_offset_deg: Creates stereo width. 90 degrees is common
+            // and effective. 0 would be mono LFO. 180 is also common.
+            90.0,
+        );
+        chorus.set_active(false);
+        Ok(self.effect_stack.add_effect(Box::new(chorus)))
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_reverb(
+        &mut self,
+        node_id: usize,
+        active: bool,
+        room_size: f32,
+        damp: f32,
+        wet: f32,
+        dry: f32,
+        width: f32,
+    ) {
+        self.apply_reverb_update(
+            node_id,
+            ReverbUpdate {
+                active,
+                room_size,
+                damp,
+                wet,
+                dry,
+                width,
+            },
+        );
+    }
+
+    /// Object form of `update_reverb`, taking the fields of `ReverbUpdate` (see `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_reverb_params(&mut self, node_id: usize, params: JsValue) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_reverb_update(node_id, params);
+        Ok(())
+    }
+
+    fn apply_reverb_update(&mut self, node_id: usize, params: ReverbUpdate) {
+        let ReverbUpdate {
+            active,
+            room_size,
+            damp,
+            wet,
+            dry,
+            width,
+        } = params;
+        let effect_id = node_id - EFFECT_NODE_ID_OFFSET;
+
+        // Try to get a mutable reference to the effect at that index.
+        if let Some(effect) = self.effect_stack.effects.get_mut(effect_id) {
+            // Attempt to downcast the boxed AudioNode to a Convolver.
+            if let Some(reverb) = effect.node.as_any_mut().downcast_mut::<Freeverb>() {
+                reverb.set_room_size(room_size);
+                reverb.set_damp(damp);
+                reverb.set_wet(wet);
+                reverb.set_dry(dry);
+                reverb.set_width(width);
+                reverb.set_active(active);
+            } else {
+                // Log a warning if the node at that index isn't a Convolver.
+                log_console(&format!("Effect at index {} is not a reverb", effect_id));
+            }
+        } else {
+            // Log a warning if there is no effect at that index.
+            log_console(&format!("No effect found at index {}", effect_id));
+        }
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_compressor(
+        &mut self,
+        node_id: usize,
+        active: bool,
+        threshold_db: f32,
+        ratio: f32,
+        attack_ms: f32,
+        release_ms: f32,
+        makeup_gain_db: f32,
+        mix: f32,
+    ) {
+        self.apply_compressor_update(
+            node_id,
+            CompressorUpdate {
+                active,
+                threshold_db,
+                ratio,
+                attack_ms,
+                release_ms,
+                makeup_gain_db,
+                mix,
+            },
+        );
+    }
+
+    /// Object form of `update_compressor`, taking the fields of `CompressorUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_compressor_params(
+        &mut self,
+        node_id: usize,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_compressor_update(node_id, params);
+        Ok(())
+    }
+
+    fn apply_compressor_update(&mut self, node_id: usize, params: CompressorUpdate) {
+        let CompressorUpdate {
+            active,
+            threshold_db,
+            ratio,
+            attack_ms,
+            release_ms,
+            makeup_gain_db,
+            mix,
+        } = params;
+        let effect_id = node_id - EFFECT_NODE_ID_OFFSET;
+
+        if let Some(effect) = self.effect_stack.effects.get_mut(effect_id) {
+            if let Some(compressor) = effect.node.as_any_mut().downcast_mut::<Compressor>() {
+                compressor.set_threshold_db(threshold_db);
+                compressor.set_ratio(ratio);
+                compressor.set_attack_ms(attack_ms);
+                compressor.set_release_ms(release_ms);
+                compressor.set_makeup_gain_db(makeup_gain_db);
+                compressor.set_mix(mix);
+                compressor.set_active(active);
+            } else {
+                log_console(&format!(
+                    "Effect at index {} is not a compressor",
+                    effect_id
+                ));
+            }
+        } else {
+            log_console(&format!("No effect found at index {}", effect_id));
+        }
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_chorus(
+        &mut self,
+        node_id: usize,
+        active: bool,
+        base_delay_ms: f32,
+        depth_ms: f32,
+        lfo_rate_hz: f32,
+        feedback: f32,
+        feedback_filter: f32,
+        mix: f32,
+        stereo_phase_offset_deg: f32,
+    ) {
+        self.apply_chorus_update(
+            node_id,
+            ChorusUpdate {
+                active,
+                base_delay_ms,
+                depth_ms,
+                lfo_rate_hz,
+                feedback,
+                feedback_filter,
+                mix,
+                stereo_phase_offset_deg,
+            },
+        );
+    }
+
+    /// Object form of `update_chorus`, taking the fields of `ChorusUpdate` (see `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_chorus_params(&mut self, node_id: usize, params: JsValue) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_chorus_update(node_id, params);
+        Ok(())
+    }
+
+    fn apply_chorus_update(&mut self, node_id: usize, params: ChorusUpdate) {
+        let ChorusUpdate {
+            active,
+            base_delay_ms,
+            depth_ms,
+            lfo_rate_hz,
+            feedback,
+            feedback_filter,
+            mix,
+            stereo_phase_offset_deg,
+        } = params;
+        let effect_id = node_id - EFFECT_NODE_ID_OFFSET;
+
+        // Try to get a mutable reference to the effect at that index.
+        if let Some(effect) = self.effect_stack.effects.get_mut(effect_id) {
+            // Attempt to downcast the boxed AudioNode to a Convolver.
+            if let Some(chorus) = effect.node.as_any_mut().downcast_mut::<Chorus>() {
+                chorus.set_base_delay_ms(base_delay_ms);
+                chorus.set_depth_ms(depth_ms);
+                chorus.set_rate_hz(lfo_rate_hz);
+                chorus.set_feedback(feedback);
+                chorus.set_mix(mix);
+                chorus.set_stereo_phase_offset_deg(stereo_phase_offset_deg);
+                chorus.set_feedback_filter_cutoff(feedback_filter * self.sample_rate);
+                chorus.set_active(active);
+            } else {
+                // Log a warning if the node at that index isn't a Convolver.
+                log_console(&format!("Effect at index {} is not a chorus", effect_id));
+            }
+        } else {
+            // Log a warning if there is no effect at that index.
+            log_console(&format!("No effect found at index {}", effect_id));
+        }
+    }
 
-            // If the IR's sample rate doesn't match the target sample rate, resample it.
-            if spec.sample_rate as f32 != target_sample_rate {
-                log_console(&format!(
-                    "Resampling needed: IR sample rate {} != target {}",
-                    spec.sample_rate, target_sample_rate
-                ));
-                let conversion_ratio = target_sample_rate as f64 / spec.sample_rate as f64;
-                log_console(&format!("Conversion ratio: {}", conversion_ratio));
-                let chunk_size = 1024; // fixed chunk size
-                log_console(&format!("Using chunk size: {}", chunk_size));
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn create_envelope(&mut self) -> Result<JsValue, JsValue> {
+        let envelope_id = NodeId::new();
+        for voice in &mut self.voices {
+            voice.graph.add_node_with_id(
+                envelope_id,
+                Box::new(Envelope::new(self.sample_rate, EnvelopeConfig::default())),
+            );
+        }
+        let obj = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &obj,
+            &"envelopeId".into(),
+            &JsValue::from_str(&envelope_id.to_string()),
+        )?;
 
-                // Set up the interpolation parameters per the rubato example.
-                let params = SincInterpolationParameters {
-                    sinc_len: 256,
-                    f_cutoff: 0.95,
-                    interpolation: SincInterpolationType::Linear,
-                    oversampling_factor: 256,
-                    window: WindowFunction::BlackmanHarris2,
-                };
+        Ok(obj.into())
+    }
 
-                // Create a SincFixedIn resampler for 1 channel.
-                let mut resampler =
-                    SincFixedIn::<f64>::new(conversion_ratio, 2.0, params, chunk_size, 1).map_err(
-                        |e| JsValue::from_str(&format!("Resampler creation error: {:?}", e)),
-                    )?;
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn create_arpeggiator(&mut self) -> Result<JsValue, JsValue> {
+        let arp_id = NodeId::new();
+        for voice in &mut self.voices {
+            let arp =
+                ArpeggiatorGenerator::with_config(self.sample_rate, ArpeggiatorConfig::default());
+            voice.graph.add_node_with_id(arp_id, Box::new(arp));
+
+            if let Some(gate_mixer_id) = voice.graph.global_gatemixer_node {
+                voice.graph.add_connection(Connection {
+                    from_node: arp_id,
+                    from_port: PortId::ArpGate,
+                    to_node: gate_mixer_id,
+                    to_port: PortId::ArpGate,
+                    amount: 1.0,
+                    modulation_type: ModulationType::VCA,
+                    modulation_transform: ModulationTransformation::None,
+                });
+            }
+        }
+        Ok(JsValue::from_str(&arp_id.to_string()))
+    }
+
+    /// Updates an arpeggiator from `config_json`, a JSON `ArpeggiatorConfig`:
+    /// `{ "direction": "up" | "down" | "upDown" | "random" | "asPlayed",
+    /// "octaves", "gateLength", "swing", "latch", "tempoSync", "bpm",
+    /// "stepsPerBeat", "rateHz", "heldNotes" }`; missing keys take their
+    /// defaults. Send it again with the new `heldNotes` whenever a key goes
+    /// down or up, and play the voice at the lowest held note.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_arpeggiator(&mut self, node_id: &str, config_json: &str) -> Result<(), JsValue> {
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+        let config: ArpeggiatorConfig = serde_json::from_str(config_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid arpeggiator config: {}", e)))?;
+
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(arp) = node.as_any_mut().downcast_mut::<ArpeggiatorGenerator>() {
+                    arp.update_config(config.clone());
+                } else {
+                    return Err(JsValue::from_str("Node is not an Arpeggiator"));
+                }
+            } else {
+                return Err(JsValue::from_str("Node not found"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Rebuilds an arpeggiator's pattern as a euclidean rhythm: `pulses` active
+    /// steps spread over `steps`, rotated left by `rotation`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_arpeggiator_euclidean(
+        &mut self,
+        node_id: &str,
+        steps: usize,
+        pulses: usize,
+        rotation: usize,
+    ) -> Result<(), JsValue> {
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(arp) = node.as_any_mut().downcast_mut::<ArpeggiatorGenerator>() {
+                    arp.set_euclidean(steps, pulses, rotation);
+                } else {
+                    return Err(JsValue::from_str("Node is not an Arpeggiator"));
+                }
+            } else {
+                return Err(JsValue::from_str("Node not found"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Splits one step of an arpeggiator's pattern into `ratchets` gates.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_arpeggiator_step_ratchets(
+        &mut self,
+        node_id: &str,
+        step: usize,
+        ratchets: u32,
+    ) -> Result<(), JsValue> {
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(arp) = node.as_any_mut().downcast_mut::<ArpeggiatorGenerator>() {
+                    if !arp.set_step_ratchets(step, ratchets) {
+                        return Err(JsValue::from_str(&format!("Invalid step index {}", step)));
+                    }
+                } else {
+                    return Err(JsValue::from_str("Node is not an Arpeggiator"));
+                }
+            } else {
+                return Err(JsValue::from_str("Node not found"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns an arpeggiator's pattern as an array of `{ value, active, ratchets }`
+    /// objects, for display.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_arpeggiator_pattern(&self, node_id: &str) -> Result<JsValue, JsValue> {
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+        let arp = self
+            .voices
+            .first()
+            .and_then(|voice| voice.graph.get_node(node_id))
+            .ok_or_else(|| JsValue::from_str("Node not found"))?
+            .as_any()
+            .downcast_ref::<ArpeggiatorGenerator>()
+            .ok_or_else(|| JsValue::from_str("Node is not an Arpeggiator"))?;
+        serde_wasm_bindgen::to_value(arp.pattern())
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize pattern: {}", e)))
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn create_mixer(&mut self) -> Result<JsValue, JsValue> {
+        let mixer_id = NodeId::new();
+        for voice in &mut self.voices {
+            voice
+                .graph
+                .add_node_with_id(mixer_id, Box::new(Mixer::new()));
+            voice.graph.set_output_node(mixer_id);
+        }
+        // Just return the ID directly like other nodes
+        Ok(JsValue::from_str(&mixer_id.to_string()))
+    }
+
+    /// Builds a saw → low-pass → mixer voice with separate amp and filter
+    /// envelopes and returns `{oscillatorId, filterId, mixerId, ampEnvelopeId,
+    /// filterEnvelopeId}`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn create_standard_voice(&mut self) -> Result<JsValue, JsValue> {
+        let standard = StandardVoice::new();
+        for voice in &mut self.voices {
+            standard.build(
+                &mut voice.graph,
+                self.sample_rate,
+                self.wavetable_banks.clone(),
+            );
+            voice.graph.set_output_node(standard.mixer);
+        }
+        serde_wasm_bindgen::to_value(&standard.ids())
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize voice ids: {}", e)))
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn create_lfo(&mut self) -> Result<JsValue, JsValue> {
+        let lfo_id = NodeId::new();
+        for voice in &mut self.voices {
+            let mut lfo = Lfo::new(self.sample_rate);
+            lfo.set_voice_index(voice.id);
+            voice.graph.add_node_with_id(lfo_id, Box::new(lfo));
+        }
+        let obj = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &obj,
+            &"lfoId".into(),
+            &JsValue::from_str(&lfo_id.to_string()),
+        )?;
+
+        Ok(obj.into())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn create_filter(&mut self) -> Result<String, JsValue> {
+        let filter_id = NodeId::new();
+        let levels = self.calibrate_new_node(NodeRole::Processor);
+        for voice in &mut self.voices {
+            let mut filter = FilterCollection::new(self.sample_rate);
+            filter.set_auto_gain(levels.is_some());
+            voice.graph.add_node_with_id(filter_id, Box::new(filter));
+        }
+        Ok(filter_id.to_string())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn create_stereo_enhancer(&mut self) -> Result<String, JsValue> {
+        let enhancer_id = NodeId::new();
+        for voice in &mut self.voices {
+            voice.graph.add_node_with_id(
+                enhancer_id,
+                Box::new(StereoEnhancer::new(
+                    self.sample_rate,
+                    0.0,
+                    12.0,
+                    1.0,
+                    1.0,
+                    1.0,
+                )),
+            );
+        }
+        Ok(enhancer_id.to_string())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn create_binaural(&mut self) -> Result<String, JsValue> {
+        let binaural_id = NodeId::new();
+        for voice in &mut self.voices {
+            voice.graph.add_node_with_id(
+                binaural_id,
+                Box::new(Binaural::new(self.sample_rate, 0.0, 0.0, 1.0)),
+            );
+        }
+        Ok(binaural_id.to_string())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn create_auto_wah(&mut self) -> Result<String, JsValue> {
+        let wah_id = NodeId::new();
+        for voice in &mut self.voices {
+            voice.graph.add_node_with_id(
+                wah_id,
+                Box::new(AutoWah::new(self.sample_rate, 0.5, 300.0, 3.0, 4.0, 1.0)),
+            );
+        }
+        Ok(wah_id.to_string())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn create_noise_gate(&mut self) -> Result<String, JsValue> {
+        let gate_id = NodeId::new();
+        for voice in &mut self.voices {
+            voice.graph.add_node_with_id(
+                gate_id,
+                Box::new(NoiseGate::new(
+                    self.sample_rate,
+                    -50.0,
+                    6.0,
+                    1.0,
+                    50.0,
+                    100.0,
+                    -80.0,
+                )),
+            );
+        }
+        Ok(gate_id.to_string())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn create_saturation(&mut self) -> Result<String, JsValue> {
+        let saturation_id = NodeId::new();
+        for voice in &mut self.voices {
+            voice.graph.add_node_with_id(
+                saturation_id,
+                Box::new(Saturation::new(self.sample_rate, 2.0, 0.5)),
+            );
+        }
+        Ok(saturation_id.to_string())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn create_bitcrusher(&mut self) -> Result<String, JsValue> {
+        let crusher_id = NodeId::new();
+        for voice in &mut self.voices {
+            voice.graph.add_node_with_id(
+                crusher_id,
+                Box::new(Bitcrusher::new(self.sample_rate, 12, 4, 0.5)),
+            );
+        }
+        Ok(crusher_id.to_string())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn create_gate_tool(&mut self) -> Result<String, JsValue> {
+        let tool_id = NodeId::new();
+        for voice in &mut self.voices {
+            voice
+                .graph
+                .add_node_with_id(tool_id, Box::new(GateTool::new(self.sample_rate)));
+        }
+        Ok(tool_id.to_string())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn create_clock(&mut self) -> Result<String, JsValue> {
+        let clock_id = NodeId::new();
+        for voice in &mut self.voices {
+            voice
+                .graph
+                .add_node_with_id(clock_id, Box::new(Clock::new(1.0, 0.5)));
+        }
+        Ok(clock_id.to_string())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn create_chance(&mut self) -> Result<String, JsValue> {
+        let chance_id = NodeId::new();
+        for voice in &mut self.voices {
+            let mut chance = Chance::new();
+            chance.set_voice_index(voice.id);
+            voice.graph.add_node_with_id(chance_id, Box::new(chance));
+        }
+        Ok(chance_id.to_string())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn create_fm_operator(&mut self) -> Result<String, JsValue> {
+        let operator_id = NodeId::new();
+        for voice in &mut self.voices {
+            voice
+                .graph
+                .add_node_with_id(operator_id, Box::new(FmOperator::new(self.sample_rate)));
+        }
+        Ok(operator_id.to_string())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn create_mseg(&mut self) -> Result<String, JsValue> {
+        let mseg_id = NodeId::new();
+        for voice in &mut self.voices {
+            voice.graph.add_node_with_id(
+                mseg_id,
+                Box::new(Mseg::new(self.sample_rate, MsegConfig::default())),
+            );
+        }
+        Ok(mseg_id.to_string())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn create_dual_filter(&mut self) -> Result<String, JsValue> {
+        let filter_id = NodeId::new();
+        for voice in &mut self.voices {
+            voice
+                .graph
+                .add_node_with_id(filter_id, Box::new(DualFilter::new(self.sample_rate)));
+        }
+        Ok(filter_id.to_string())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn create_formant_filter(&mut self) -> Result<String, JsValue> {
+        let filter_id = NodeId::new();
+        for voice in &mut self.voices {
+            voice
+                .graph
+                .add_node_with_id(filter_id, Box::new(FormantFilter::new(self.sample_rate)));
+        }
+        Ok(filter_id.to_string())
+    }
 
-                // Convert the IR to f64.
-                let ir_f64: Vec<f64> = ir.iter().map(|&x| x as f64).collect();
-                let mut resampled_output: Vec<f64> = Vec::new();
+    /// Updates a formant filter (vowel 0 = A, 1 = E, 2 = I, 3 = O, 4 = U).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_formant_filter(
+        &mut self,
+        node_id: &str,
+        active: bool,
+        vowel: u8,
+        gender: f32,
+        resonance: f32,
+        mix: f32,
+    ) -> Result<(), JsValue> {
+        self.apply_formant_filter_update(
+            node_id,
+            FormantFilterUpdate {
+                active,
+                vowel,
+                gender,
+                resonance,
+                mix,
+            },
+        )
+    }
 
-                // Process the IR in blocks of `chunk_size`.
-                let mut pos = 0;
-                while pos < ir_f64.len() {
-                    let end = (pos + chunk_size).min(ir_f64.len());
-                    let mut block = ir_f64[pos..end].to_vec();
-                    if block.len() < chunk_size {
-                        // Pad with zeros to fill the chunk.
-                        block.resize(chunk_size, 0.0);
-                    }
-                    let input_block = vec![block]; // one channel
-                    let out = resampler.process(&input_block, None).map_err(|e| {
-                        JsValue::from_str(&format!("Resampling process error: {:?}", e))
-                    })?;
-                    // Append the output (first channel) to our accumulator.
-                    resampled_output.extend(out[0].iter());
-                    pos += chunk_size;
-                }
+    /// Object form of `update_formant_filter`, taking the fields of `FormantFilterUpdate`
+    /// (see `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_formant_filter_params(
+        &mut self,
+        node_id: &str,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_formant_filter_update(node_id, params)
+    }
 
-                // Now flush any remaining samples using process_partial.
-                let partial = resampler
-                    .process_partial::<Vec<f64>>(None, None)
-                    .map_err(|e| JsValue::from_str(&format!("Resampler flush error: {:?}", e)))?;
-                resampled_output.extend(partial[0].iter());
-                log_console(&format!(
-                    "Resampled output length: {}",
-                    resampled_output.len()
-                ));
+    fn apply_formant_filter_update(
+        &mut self,
+        node_id: &str,
+        params: FormantFilterUpdate,
+    ) -> Result<(), JsValue> {
+        let FormantFilterUpdate {
+            active,
+            vowel,
+            gender,
+            resonance,
+            mix,
+        } = params;
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
 
-                // Replace IR with the resampled output (convert back to f32).
-                ir = resampled_output.into_iter().map(|x| x as f32).collect();
-                log_console(&format!("Final IR length after resampling: {}", ir.len()));
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(filter) = node.as_any_mut().downcast_mut::<FormantFilter>() {
+                    filter.set_vowel(FormantVowel::from_u8(vowel));
+                    filter.set_gender(gender);
+                    filter.set_resonance(resonance);
+                    filter.set_mix(mix);
+                    filter.set_active(active);
+                } else {
+                    return Err(JsValue::from_str("Node is not a FormantFilter"));
+                }
             } else {
-                log_console("No resampling needed");
-            }
-
-            // Create a new convolver using the (resampled) impulse response.
-            let new_convolver = Convolver::new(ir, partition_size, target_sample_rate);
-            effect.node = Box::new(new_convolver);
-            // Restore the original wet level.
-            if let Some(new_conv) = effect.node.as_any_mut().downcast_mut::<Convolver>() {
-                new_conv.set_wet_level(wet_level);
+                return Err(JsValue::from_str("Node not found"));
             }
-            log_console("Impulse response imported successfully");
-            Ok(())
-        } else {
-            Err(JsValue::from_str("Effect is not a Convolver"))
         }
+        Ok(())
     }
 
-    /// Refactored import_wavetable function that uses the hound-based helper.
-    /// It accepts the WAV data as a byte slice, uses a Cursor to create a reader,
-    /// builds a new morph collection from the data, adds it to the synth bank under
-    /// the name "imported", and then updates all wavetable oscillators to use it.
+    /// Updates the shared settings of a dual filter (routing 0 = serial, 1 = parallel, 2 = split).
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
-    pub fn import_wavetable(
+    pub fn update_dual_filter(
         &mut self,
         node_id: &str,
-        data: &[u8],
-        base_size: usize,
+        active: bool,
+        routing: u8,
+        balance: f32,
+        spacing: f32,
+        cutoff: f32,
+        key_tracking: f32,
     ) -> Result<(), JsValue> {
-        let collection_name = format!("wt_{}", node_id);
-        // Wrap the incoming data in a Cursor and call the hound helper.
-        let cursor = Cursor::new(data);
-        let collection = import_wav_hound_reader(cursor, base_size)
-            .map_err(|e| JsValue::from_str(&e.to_string()))?;
-        // Keep existing collections (including the default) and register this one by node.
-        {
-            let mut bank = self.wavetable_synthbank.borrow_mut();
-            if !bank.collections.contains_key("default") {
-                bank.add_collection(
-                    "default",
-                    WavetableMorphCollection::generate_test_collection(self.sample_rate),
-                );
-            }
-            bank.add_collection(&collection_name, collection);
-        }
+        self.apply_dual_filter_update(
+            node_id,
+            DualFilterUpdate {
+                active,
+                routing,
+                balance,
+                spacing,
+                cutoff,
+                key_tracking,
+            },
+        )
+    }
+
+    /// Object form of `update_dual_filter`, taking the fields of `DualFilterUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_dual_filter_params(
+        &mut self,
+        node_id: &str,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_dual_filter_update(node_id, params)
+    }
+
+    fn apply_dual_filter_update(
+        &mut self,
+        node_id: &str,
+        params: DualFilterUpdate,
+    ) -> Result<(), JsValue> {
+        let DualFilterUpdate {
+            active,
+            routing,
+            balance,
+            spacing,
+            cutoff,
+            key_tracking,
+        } = params;
+        let routing = match routing {
+            1 => DualFilterRouting::Parallel,
+            2 => DualFilterRouting::Split,
+            _ => DualFilterRouting::Serial,
+        };
 
-        // Parse the target oscillator ID.
         let node_id = NodeId::from_string(node_id)
             .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
 
-        let collection_name_clone = collection_name.clone();
-        // Update the oscillator's active wavetable to the newly imported collection.
         for voice in &mut self.voices {
-            let node = voice
-                .graph
-                .get_node_mut(node_id)
-                .ok_or_else(|| JsValue::from_str("Node not found in one of the voices"))?;
-            let osc = node
-                .as_any_mut()
-                .downcast_mut::<WavetableOscillator>()
-                .ok_or_else(|| {
-                    JsValue::from_str("Node is not a WavetableOscillator in one of the voices")
-                })?;
-            osc.set_current_wavetable(&collection_name_clone);
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(dual) = node.as_any_mut().downcast_mut::<DualFilter>() {
+                    dual.set_routing(routing);
+                    dual.set_balance(balance);
+                    dual.set_spacing(spacing);
+                    dual.set_cutoff(cutoff);
+                    for slot in 0..2 {
+                        if let Some(filter) = dual.filter_mut(slot) {
+                            filter.set_keyboard_tracking_sensitivity(key_tracking);
+                        }
+                    }
+                    dual.set_active(active);
+                } else {
+                    return Err(JsValue::from_str("Node is not a DualFilter"));
+                }
+            } else {
+                return Err(JsValue::from_str("Node not found"));
+            }
         }
-
         Ok(())
     }
 
+    /// Updates a gate tool; a `length_ms` of 0 follows the incoming gate.
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
-    pub fn update_oscillator(
+    pub fn update_gate_tool(
         &mut self,
-        oscillator_id: &str,
-        params: &AnalogOscillatorStateUpdate,
+        node_id: &str,
+        active: bool,
+        length_ms: f32,
+        delay_ms: f32,
+        division: u32,
+        multiplication: u32,
+        probability: f32,
     ) -> Result<(), JsValue> {
-        let oscillator_id = NodeId::from_string(oscillator_id)
-            .map_err(|e| JsValue::from_str(&format!("Invalid oscillator_id UUID: {}", e)))?;
+        self.apply_gate_tool_update(
+            node_id,
+            GateToolUpdate {
+                active,
+                length_ms,
+                delay_ms,
+                division,
+                multiplication,
+                probability,
+            },
+        )
+    }
+
+    /// Object form of `update_gate_tool`, taking the fields of `GateToolUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_gate_tool_params(
+        &mut self,
+        node_id: &str,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_gate_tool_update(node_id, params)
+    }
+
+    fn apply_gate_tool_update(
+        &mut self,
+        node_id: &str,
+        params: GateToolUpdate,
+    ) -> Result<(), JsValue> {
+        let GateToolUpdate {
+            active,
+            length_ms,
+            delay_ms,
+            division,
+            multiplication,
+            probability,
+        } = params;
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
 
         for voice in &mut self.voices {
-            let node = voice
-                .graph
-                .get_node_mut(oscillator_id)
-                .ok_or_else(|| JsValue::from_str("Node not found in one of the voices"))?;
-            let osc = node
-                .as_any_mut()
-                .downcast_mut::<AnalogOscillator>()
-                .ok_or_else(|| {
-                    JsValue::from_str("Node is not an AnalogOscillator in one of the voices")
-                })?;
-            osc.update_params(params);
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(tool) = node.as_any_mut().downcast_mut::<GateTool>() {
+                    tool.set_length_ms(length_ms);
+                    tool.set_delay_ms(delay_ms);
+                    tool.set_division(division);
+                    tool.set_multiplication(multiplication);
+                    tool.set_probability(probability);
+                    tool.set_active(active);
+                } else {
+                    return Err(JsValue::from_str("Node is not a GateTool"));
+                }
+            } else {
+                return Err(JsValue::from_str("Node not found"));
+            }
         }
         Ok(())
     }
 
-    pub fn update_delay(
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_clock(
         &mut self,
-        node_id: usize,
-        delay_ms: f32,
-        feedback: f32,
-        wet_mix: f32,
-        enabled: bool,
-    ) {
-        // Calculate the effect index based on the provided node_id.
-        let effect_id = node_id - EFFECT_NODE_ID_OFFSET;
+        node_id: &str,
+        active: bool,
+        division_beats: f32,
+        pulse_width: f32,
+    ) -> Result<(), JsValue> {
+        self.apply_clock_update(
+            node_id,
+            ClockUpdate {
+                active,
+                division_beats,
+                pulse_width,
+            },
+        )
+    }
 
-        // Try to get a mutable reference to the effect at that index.
-        if let Some(effect) = self.effect_stack.effects.get_mut(effect_id) {
-            // Attempt to downcast the boxed AudioNode to a Convolver.
-            if let Some(delay) = effect.node.as_any_mut().downcast_mut::<Delay>() {
-                delay.set_delay_ms(delay_ms);
-                delay.set_feedback(feedback);
-                delay.set_mix(wet_mix);
-                delay.set_active(enabled);
+    /// Object form of `update_clock`, taking the fields of `ClockUpdate` (see `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_clock_params(&mut self, node_id: &str, params: JsValue) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_clock_update(node_id, params)
+    }
+
+    fn apply_clock_update(&mut self, node_id: &str, params: ClockUpdate) -> Result<(), JsValue> {
+        let ClockUpdate {
+            active,
+            division_beats,
+            pulse_width,
+        } = params;
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(clock) = node.as_any_mut().downcast_mut::<Clock>() {
+                    clock.set_division_beats(division_beats);
+                    clock.set_pulse_width(pulse_width);
+                    clock.set_active(active);
+                } else {
+                    return Err(JsValue::from_str("Node is not a Clock"));
+                }
             } else {
-                // Log a warning if the node at that index isn't a Convolver.
-                log_console(&format!("Effect at index {} is not a Delay", effect_id));
+                return Err(JsValue::from_str("Node not found"));
             }
-        } else {
-            // Log a warning if there is no effect at that index.
-            log_console(&format!("No effect found at index {}", effect_id));
         }
+        Ok(())
     }
 
-    pub fn update_saturation(&mut self, node_id: usize, drive: f32, mix: f32, active: bool) {
-        let Some(effect_id) = node_id.checked_sub(EFFECT_NODE_ID_OFFSET) else {
-            log_console(&format!(
-                "Invalid saturation node id {}; expected offset {}",
-                node_id, EFFECT_NODE_ID_OFFSET
-            ));
-            return;
-        };
+    /// Updates a Chance node (mode 0 = route, 1 = hold, 2 = attenuate;
+    /// randomness 0 = per voice, 1 = global).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_chance(
+        &mut self,
+        node_id: &str,
+        active: bool,
+        probability: f32,
+        mode: u8,
+        attenuation: f32,
+        seed: u32,
+        randomness: u8,
+    ) -> Result<(), JsValue> {
+        self.apply_chance_update(
+            node_id,
+            ChanceUpdate {
+                active,
+                probability,
+                mode,
+                attenuation,
+                seed,
+                randomness,
+            },
+        )
+    }
 
-        if let Some(effect) = self.effect_stack.effects.get_mut(effect_id) {
-            if let Some(saturation) = effect.node.as_any_mut().downcast_mut::<Saturation>() {
-                saturation.set_drive(drive);
-                saturation.set_mix(mix);
-                saturation.set_active(active);
+    /// Object form of `update_chance`, taking the fields of `ChanceUpdate` (see `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_chance_params(&mut self, node_id: &str, params: JsValue) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_chance_update(node_id, params)
+    }
+
+    fn apply_chance_update(&mut self, node_id: &str, params: ChanceUpdate) -> Result<(), JsValue> {
+        let ChanceUpdate {
+            active,
+            probability,
+            mode,
+            attenuation,
+            seed,
+            randomness,
+        } = params;
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(chance) = node.as_any_mut().downcast_mut::<Chance>() {
+                    chance.set_probability(probability);
+                    chance.set_mode(ChanceMode::from_u8(mode));
+                    chance.set_attenuation(attenuation);
+                    chance.set_voice_index(voice.id);
+                    chance.set_seed(seed);
+                    chance.set_randomness(ChanceRandomness::from_u8(randomness));
+                    chance.set_active(active);
+                } else {
+                    return Err(JsValue::from_str("Node is not a Chance"));
+                }
             } else {
-                log_console(&format!(
-                    "Effect at index {} is not a Saturation",
-                    effect_id
-                ));
+                return Err(JsValue::from_str("Node not found"));
             }
-        } else {
-            log_console(&format!("No effect found at index {}", effect_id));
         }
+        Ok(())
+    }
+
+    /// Updates an FM operator. `waveform`: 0 = sine, 1 = half sine, 2 = abs sine,
+    /// 3 = triangle, 4 = square, 5 = saw. With `fixed` the operator runs at
+    /// `fixed_frequency` Hz instead of `ratio` times the note; `detune` is in
+    /// cents. Octave and semitone tuning set with `update_fm_operator_params`
+    /// is kept.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_fm_operator(
+        &mut self,
+        node_id: &str,
+        waveform: u8,
+        fixed: bool,
+        ratio: f32,
+        fixed_frequency: f32,
+        detune: f32,
+        feedback: f32,
+        level: f32,
+        active: bool,
+    ) -> Result<(), JsValue> {
+        self.apply_fm_operator_update(
+            node_id,
+            FmOperatorUpdate {
+                waveform,
+                fixed,
+                ratio,
+                fixed_frequency,
+                detune_oct: None,
+                detune_semi: None,
+                detune,
+                feedback,
+                level,
+                active,
+            },
+        )
+    }
+
+    /// Object form of `update_fm_operator`, taking the fields of `FmOperatorUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_fm_operator_params(
+        &mut self,
+        node_id: &str,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_fm_operator_update(node_id, params)
     }
 
-    pub fn update_bitcrusher(
+    fn apply_fm_operator_update(
         &mut self,
-        node_id: usize,
-        bits: u8,
-        downsample_factor: usize,
-        mix: f32,
-        active: bool,
-    ) {
-        let Some(effect_id) = node_id.checked_sub(EFFECT_NODE_ID_OFFSET) else {
-            log_console(&format!(
-                "Invalid bitcrusher node id {}; expected offset {}",
-                node_id, EFFECT_NODE_ID_OFFSET
-            ));
-            return;
+        node_id: &str,
+        params: FmOperatorUpdate,
+    ) -> Result<(), JsValue> {
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+        let config = FmOperatorConfig {
+            waveform: FmWaveform::from_u8(params.waveform),
+            fixed: params.fixed,
+            ratio: params.ratio,
+            fixed_frequency: params.fixed_frequency,
+            detune_oct: 0.0,
+            detune_semi: 0.0,
+            detune: params.detune,
+            feedback: params.feedback,
+            level: params.level,
+            active: params.active,
         };
 
-        if let Some(effect) = self.effect_stack.effects.get_mut(effect_id) {
-            if let Some(crusher) = effect.node.as_any_mut().downcast_mut::<Bitcrusher>() {
-                crusher.set_bits(bits);
-                crusher.set_downsample_factor(downsample_factor);
-                crusher.set_mix(mix);
-                crusher.set_active(active);
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(operator) = node.as_any_mut().downcast_mut::<FmOperator>() {
+                    // Coarse tuning the call leaves out stays as it was.
+                    let current = *operator.config();
+                    operator.update(FmOperatorConfig {
+                        detune_oct: params.detune_oct.unwrap_or(current.detune_oct),
+                        detune_semi: params.detune_semi.unwrap_or(current.detune_semi),
+                        ..config
+                    });
+                } else {
+                    return Err(JsValue::from_str("Node is not an FM operator"));
+                }
             } else {
-                log_console(&format!(
-                    "Effect at index {} is not a Bitcrusher",
-                    effect_id
-                ));
+                return Err(JsValue::from_str("Node not found"));
             }
-        } else {
-            log_console(&format!("No effect found at index {}", effect_id));
         }
+        Ok(())
     }
 
-    pub fn update_convolver(&mut self, node_id: usize, wet_mix: f32, enabled: bool) {
-        // Calculate the effect index based on the provided node_id.
-        let effect_id = node_id - EFFECT_NODE_ID_OFFSET;
+    /// Replaces the segments of an MSEG node with `points_json`, a JSON
+    /// `MsegConfig`: `{ "points": [{ "time", "level", "curve" }, ...],
+    /// "sustainPoint", "loopStart", "active" }`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_mseg(&mut self, node_id: &str, points_json: &str) -> Result<(), JsValue> {
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+        let config: MsegConfig = serde_json::from_str(points_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid MSEG config: {}", e)))?;
 
-        // Try to get a mutable reference to the effect at that index.
-        if let Some(effect) = self.effect_stack.effects.get_mut(effect_id) {
-            // Attempt to downcast the boxed AudioNode to a Convolver.
-            if let Some(convolver) = effect.node.as_any_mut().downcast_mut::<Convolver>() {
-                convolver.set_wet_level(wet_mix);
-                convolver.set_enabled(enabled);
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(mseg) = node.as_any_mut().downcast_mut::<Mseg>() {
+                    mseg.update_config(config.clone());
+                } else {
+                    return Err(JsValue::from_str("Node is not an MSEG"));
+                }
             } else {
-                // Log a warning if the node at that index isn't a Convolver.
-                log_console(&format!("Effect at index {} is not a Convolver", effect_id));
+                return Err(JsValue::from_str("Node not found"));
             }
-        } else {
-            // Log a warning if there is no effect at that index.
-            log_console(&format!("No effect found at index {}", effect_id));
         }
+        Ok(())
+    }
+
+    /// Updates one filter of a dual filter (slot 0 = A, 1 = B).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_dual_filter_slot(
+        &mut self,
+        node_id: &str,
+        slot: usize,
+        filter_type: FilterType,
+        filter_slope: FilterSlope,
+        resonance: f32,
+        gain: f32,
+    ) -> Result<(), JsValue> {
+        self.apply_dual_filter_slot_update(
+            node_id,
+            DualFilterSlotUpdate {
+                slot,
+                filter_type,
+                filter_slope,
+                resonance,
+                gain,
+            },
+        )
     }
+
+    /// Object form of `update_dual_filter_slot`, taking the fields of `DualFilterSlotUpdate` (see
+    /// `get_api_schema`).
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_dual_filter_slot_params(
+        &mut self,
+        node_id: &str,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_dual_filter_slot_update(node_id, params)
+    }
 
-    pub fn add_limiter(&mut self) -> Result<usize, JsValue> {
-        let mut limiter = Limiter::new(self.sample_rate, -0.5, 0.1, 50.0, 1.5, true);
-        limiter.set_active(true);
-        Ok(self.effect_stack.add_effect(Box::new(limiter)))
+    fn apply_dual_filter_slot_update(
+        &mut self,
+        node_id: &str,
+        params: DualFilterSlotUpdate,
+    ) -> Result<(), JsValue> {
+        let DualFilterSlotUpdate {
+            slot,
+            filter_type,
+            filter_slope,
+            resonance,
+            gain,
+        } = params;
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(dual) = node.as_any_mut().downcast_mut::<DualFilter>() {
+                    let filter = dual.filter_mut(slot).ok_or_else(|| {
+                        JsValue::from_str(&format!("Invalid dual filter slot: {}", slot))
+                    })?;
+                    filter.set_filter_type(filter_type);
+                    filter.set_filter_slope(filter_slope);
+                    filter.set_gain_db(gain * 24.0 - 12.0);
+                    dual.set_resonance(slot, resonance);
+                } else {
+                    return Err(JsValue::from_str("Node is not a DualFilter"));
+                }
+            } else {
+                return Err(JsValue::from_str("Node not found"));
+            }
+        }
+        Ok(())
     }
 
+    /// Updates a per-voice stereo enhancer; the master insert uses `update_stereo_enhancer`.
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
-    pub fn add_compressor(
+    pub fn update_voice_stereo_enhancer(
         &mut self,
-        threshold_db: f32,
-        ratio: f32,
-        attack_ms: f32,
-        release_ms: f32,
-        makeup_gain_db: f32,
+        node_id: &str,
+        active: bool,
+        delay_left_ms: f32,
+        delay_right_ms: f32,
+        width: f32,
+        comb_compensation: f32,
         mix: f32,
-    ) -> Result<usize, JsValue> {
-        let mut compressor = Compressor::new(
-            self.sample_rate,
-            threshold_db,
-            ratio,
-            attack_ms,
-            release_ms,
-            makeup_gain_db,
-            mix,
-        );
-        compressor.set_active(true);
-        Ok(self.effect_stack.add_effect(Box::new(compressor)))
+    ) -> Result<(), JsValue> {
+        self.apply_voice_stereo_enhancer_update(
+            node_id,
+            StereoEnhancerUpdate {
+                active,
+                delay_left_ms,
+                delay_right_ms,
+                width,
+                comb_compensation,
+                mix,
+            },
+        )
     }
 
+    /// Object form of `update_voice_stereo_enhancer`, taking the fields of `StereoEnhancerUpdate`
+    /// (see `get_api_schema`).
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_voice_stereo_enhancer_params(
+        &mut self,
+        node_id: &str,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_voice_stereo_enhancer_update(node_id, params)
+    }
 
-    pub fn add_chorus(&mut self) -> Result<usize, JsValue> {
-        let mut chorus = Chorus::new(
-            self.sample_rate,
-            // max_base_delay_ms: Needs to accommodate the base delay + depth.
-            // max base delay in ms:
-            65.0,
-            // base_delay_ms: The central delay time. 20-30ms is typical for chorus.
-            15.0,
-            // depth_ms: How much the delay time varies. 2-7ms is a common range.
-            5.0,
-            // lfo_rate_hz: Speed of the warble. Slow rates are classic chorus.
-            // 0.3 - 0.8 Hz is a good starting range.
-            0.5,
-            // feedback: Usually low or zero for standard chorus. High feedback -> flanger.
-            0.3,
-            // mix: 0.5 gives an equal blend of dry and wet signal. Standard starting point.
-            0.5,
-            // stereo_phase_offset_deg: Creates stereo width. 90 degrees is common
-            // and effective. 0 would be mono LFO. 180 is also common.
-            90.0,
-        );
-        chorus.set_active(false);
-        Ok(self.effect_stack.add_effect(Box::new(chorus)))
+    fn apply_voice_stereo_enhancer_update(
+        &mut self,
+        node_id: &str,
+        params: StereoEnhancerUpdate,
+    ) -> Result<(), JsValue> {
+        let StereoEnhancerUpdate {
+            active,
+            delay_left_ms,
+            delay_right_ms,
+            width,
+            comb_compensation,
+            mix,
+        } = params;
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(enhancer) = node.as_any_mut().downcast_mut::<StereoEnhancer>() {
+                    enhancer.set_delay_left_ms(delay_left_ms);
+                    enhancer.set_delay_right_ms(delay_right_ms);
+                    enhancer.set_width(width);
+                    enhancer.set_comb_compensation(comb_compensation);
+                    enhancer.set_mix(mix);
+                    enhancer.set_active(active);
+                } else {
+                    return Err(JsValue::from_str("Node is not a StereoEnhancer"));
+                }
+            } else {
+                return Err(JsValue::from_str("Node not found"));
+            }
+        }
+        Ok(())
     }
 
+    /// Updates a per-voice binaural panner; the master insert uses `update_binaural`.
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
-    pub fn update_reverb(
+    pub fn update_voice_binaural(
         &mut self,
-        node_id: usize,
+        node_id: &str,
         active: bool,
-        room_size: f32,
-        damp: f32,
-        wet: f32,
-        dry: f32,
-        width: f32,
-    ) {
-        let effect_id = node_id - EFFECT_NODE_ID_OFFSET;
+        azimuth: f32,
+        elevation: f32,
+        mix: f32,
+    ) -> Result<(), JsValue> {
+        self.apply_voice_binaural_update(
+            node_id,
+            BinauralUpdate {
+                active,
+                azimuth,
+                elevation,
+                mix,
+            },
+        )
+    }
 
-        // Try to get a mutable reference to the effect at that index.
-        if let Some(effect) = self.effect_stack.effects.get_mut(effect_id) {
-            // Attempt to downcast the boxed AudioNode to a Convolver.
-            if let Some(reverb) = effect.node.as_any_mut().downcast_mut::<Freeverb>() {
-                reverb.set_room_size(room_size);
-                reverb.set_damp(damp);
-                reverb.set_wet(wet);
-                reverb.set_dry(dry);
-                reverb.set_width(width);
-                reverb.set_active(active);
+    /// Object form of `update_voice_binaural`, taking the fields of `BinauralUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_voice_binaural_params(
+        &mut self,
+        node_id: &str,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_voice_binaural_update(node_id, params)
+    }
+
+    fn apply_voice_binaural_update(
+        &mut self,
+        node_id: &str,
+        params: BinauralUpdate,
+    ) -> Result<(), JsValue> {
+        let BinauralUpdate {
+            active,
+            azimuth,
+            elevation,
+            mix,
+        } = params;
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(binaural) = node.as_any_mut().downcast_mut::<Binaural>() {
+                    binaural.set_position(azimuth, elevation);
+                    binaural.set_mix(mix);
+                    binaural.set_active(active);
+                } else {
+                    return Err(JsValue::from_str("Node is not a Binaural"));
+                }
             } else {
-                // Log a warning if the node at that index isn't a Convolver.
-                log_console(&format!("Effect at index {} is not a reverb", effect_id));
+                return Err(JsValue::from_str("Node not found"));
             }
-        } else {
-            // Log a warning if there is no effect at that index.
-            log_console(&format!("No effect found at index {}", effect_id));
         }
+        Ok(())
     }
 
+    /// Updates a per-voice auto-wah; the master insert uses `update_auto_wah`.
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
-    pub fn update_compressor(
+    pub fn update_voice_auto_wah(
         &mut self,
-        node_id: usize,
+        node_id: &str,
         active: bool,
-        threshold_db: f32,
-        ratio: f32,
-        attack_ms: f32,
-        release_ms: f32,
-        makeup_gain_db: f32,
+        sensitivity: f32,
+        frequency: f32,
+        range: f32,
+        q: f32,
+        direction: AutoWahDirection,
         mix: f32,
-    ) {
-        let effect_id = node_id - EFFECT_NODE_ID_OFFSET;
+    ) -> Result<(), JsValue> {
+        self.apply_voice_auto_wah_update(
+            node_id,
+            AutoWahUpdate {
+                active,
+                sensitivity,
+                frequency,
+                range,
+                q,
+                direction,
+                mix,
+            },
+        )
+    }
 
-        if let Some(effect) = self.effect_stack.effects.get_mut(effect_id) {
-            if let Some(compressor) = effect.node.as_any_mut().downcast_mut::<Compressor>() {
-                compressor.set_threshold_db(threshold_db);
-                compressor.set_ratio(ratio);
-                compressor.set_attack_ms(attack_ms);
-                compressor.set_release_ms(release_ms);
-                compressor.set_makeup_gain_db(makeup_gain_db);
-                compressor.set_mix(mix);
-                compressor.set_active(active);
+    /// Object form of `update_voice_auto_wah`, taking the fields of `AutoWahUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_voice_auto_wah_params(
+        &mut self,
+        node_id: &str,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_voice_auto_wah_update(node_id, params)
+    }
+
+    fn apply_voice_auto_wah_update(
+        &mut self,
+        node_id: &str,
+        params: AutoWahUpdate,
+    ) -> Result<(), JsValue> {
+        let AutoWahUpdate {
+            active,
+            sensitivity,
+            frequency,
+            range,
+            q,
+            direction,
+            mix,
+        } = params;
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(wah) = node.as_any_mut().downcast_mut::<AutoWah>() {
+                    wah.set_sensitivity(sensitivity);
+                    wah.set_frequency(frequency);
+                    wah.set_range(range);
+                    wah.set_q(q);
+                    wah.set_direction(direction);
+                    wah.set_mix(mix);
+                    wah.set_active(active);
+                } else {
+                    return Err(JsValue::from_str("Node is not an AutoWah"));
+                }
             } else {
-                log_console(&format!(
-                    "Effect at index {} is not a compressor",
-                    effect_id
-                ));
+                return Err(JsValue::from_str("Node not found"));
             }
-        } else {
-            log_console(&format!("No effect found at index {}", effect_id));
         }
+        Ok(())
     }
 
+    /// Updates a per-voice noise gate; the master insert uses `update_noise_gate`.
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
-    pub fn update_chorus(
+    pub fn update_voice_noise_gate(
         &mut self,
-        node_id: usize,
+        node_id: &str,
         active: bool,
-        base_delay_ms: f32,
-        depth_ms: f32,
-        lfo_rate_hz: f32,
-        feedback: f32,
-        feedback_filter: f32,
-        mix: f32,
-        stereo_phase_offset_deg: f32,
-    ) {
-        let effect_id = node_id - EFFECT_NODE_ID_OFFSET;
+        threshold_db: f32,
+        hysteresis_db: f32,
+        attack_ms: f32,
+        hold_ms: f32,
+        release_ms: f32,
+        range_db: f32,
+    ) -> Result<(), JsValue> {
+        self.apply_voice_noise_gate_update(
+            node_id,
+            NoiseGateUpdate {
+                active,
+                threshold_db,
+                hysteresis_db,
+                attack_ms,
+                hold_ms,
+                release_ms,
+                range_db,
+            },
+        )
+    }
 
-        // Try to get a mutable reference to the effect at that index.
-        if let Some(effect) = self.effect_stack.effects.get_mut(effect_id) {
-            // Attempt to downcast the boxed AudioNode to a Convolver.
-            if let Some(chorus) = effect.node.as_any_mut().downcast_mut::<Chorus>() {
-                chorus.set_base_delay_ms(base_delay_ms);
-                chorus.set_depth_ms(depth_ms);
-                chorus.set_rate_hz(lfo_rate_hz);
-                chorus.set_feedback(feedback);
-                chorus.set_mix(mix);
-                chorus.set_stereo_phase_offset_deg(stereo_phase_offset_deg);
-                chorus.set_feedback_filter_cutoff(feedback_filter * self.sample_rate);
-                chorus.set_active(active);
+    /// Object form of `update_voice_noise_gate`, taking the fields of `NoiseGateUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_voice_noise_gate_params(
+        &mut self,
+        node_id: &str,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_voice_noise_gate_update(node_id, params)
+    }
+
+    fn apply_voice_noise_gate_update(
+        &mut self,
+        node_id: &str,
+        params: NoiseGateUpdate,
+    ) -> Result<(), JsValue> {
+        let NoiseGateUpdate {
+            active,
+            threshold_db,
+            hysteresis_db,
+            attack_ms,
+            hold_ms,
+            release_ms,
+            range_db,
+        } = params;
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(gate) = node.as_any_mut().downcast_mut::<NoiseGate>() {
+                    gate.set_threshold_db(threshold_db);
+                    gate.set_hysteresis_db(hysteresis_db);
+                    gate.set_attack_ms(attack_ms);
+                    gate.set_hold_ms(hold_ms);
+                    gate.set_release_ms(release_ms);
+                    gate.set_range_db(range_db);
+                    gate.set_active(active);
+                } else {
+                    return Err(JsValue::from_str("Node is not a NoiseGate"));
+                }
             } else {
-                // Log a warning if the node at that index isn't a Convolver.
-                log_console(&format!("Effect at index {} is not a chorus", effect_id));
+                return Err(JsValue::from_str("Node not found"));
             }
-        } else {
-            // Log a warning if there is no effect at that index.
-            log_console(&format!("No effect found at index {}", effect_id));
         }
+        Ok(())
     }
 
+    /// Updates a per-voice saturation; the master insert uses `update_saturation`.
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
-    pub fn create_envelope(&mut self) -> Result<JsValue, JsValue> {
-        let envelope_id = NodeId::new();
-        for voice in &mut self.voices {
-            voice.graph.add_node_with_id(
-                envelope_id,
-                Box::new(Envelope::new(self.sample_rate, EnvelopeConfig::default())),
-            );
-        }
-        let obj = js_sys::Object::new();
-        js_sys::Reflect::set(
-            &obj,
-            &"envelopeId".into(),
-            &JsValue::from_str(&envelope_id.to_string()),
-        )?;
-
-        Ok(obj.into())
+    pub fn update_voice_saturation(
+        &mut self,
+        node_id: &str,
+        drive: f32,
+        mix: f32,
+        active: bool,
+    ) -> Result<(), JsValue> {
+        self.apply_voice_saturation_update(node_id, SaturationUpdate { drive, mix, active })
     }
 
+    /// Object form of `update_voice_saturation`, taking the fields of `SaturationUpdate` (see
+    /// `get_api_schema`).
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
-    pub fn create_arpeggiator(&mut self) -> Result<JsValue, JsValue> {
-        let arp_id = NodeId::new();
-        for voice in &mut self.voices {
-            let mut arp = ArpeggiatorGenerator::new();
-            arp.create_test_pattern(self.sample_rate, 0.225);
-            voice.graph.add_node_with_id(arp_id, Box::new(arp));
+    pub fn update_voice_saturation_params(
+        &mut self,
+        node_id: &str,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_voice_saturation_update(node_id, params)
+    }
 
-            if let Some(gate_mixer_id) = voice.graph.global_gatemixer_node {
-                voice.graph.add_connection(Connection {
-                    from_node: arp_id,
-                    from_port: PortId::ArpGate,
-                    to_node: gate_mixer_id,
-                    to_port: PortId::ArpGate,
-                    amount: 1.0,
-                    modulation_type: ModulationType::VCA,
-                    modulation_transform: ModulationTransformation::None,
-                });
+    fn apply_voice_saturation_update(
+        &mut self,
+        node_id: &str,
+        params: SaturationUpdate,
+    ) -> Result<(), JsValue> {
+        let SaturationUpdate { drive, mix, active } = params;
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(saturation) = node.as_any_mut().downcast_mut::<Saturation>() {
+                    saturation.set_drive(drive);
+                    saturation.set_mix(mix);
+                    saturation.set_active(active);
+                } else {
+                    return Err(JsValue::from_str("Node is not a Saturation"));
+                }
+            } else {
+                return Err(JsValue::from_str("Node not found"));
             }
         }
-        Ok(JsValue::from_str(&arp_id.to_string()))
+        Ok(())
     }
 
+    /// Sets a per-voice saturation's transfer curve, pre/post tilt EQ (dB) and auto-gain.
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
-    pub fn create_mixer(&mut self) -> Result<JsValue, JsValue> {
-        let mixer_id = NodeId::new();
-        for voice in &mut self.voices {
-            voice
-                .graph
-                .add_node_with_id(mixer_id, Box::new(Mixer::new()));
-            voice.graph.set_output_node(mixer_id);
-        }
-        // Just return the ID directly like other nodes
-        Ok(JsValue::from_str(&mixer_id.to_string()))
+    pub fn update_voice_saturation_tone(
+        &mut self,
+        node_id: &str,
+        character: SaturationCharacter,
+        pre_tilt_db: f32,
+        post_tilt_db: f32,
+        auto_gain: bool,
+    ) -> Result<(), JsValue> {
+        self.apply_voice_saturation_tone_update(
+            node_id,
+            SaturationToneUpdate {
+                character,
+                pre_tilt_db,
+                post_tilt_db,
+                auto_gain,
+            },
+        )
     }
 
+    /// Object form of `update_voice_saturation_tone`, taking the fields of
+    /// `SaturationToneUpdate` (see `get_api_schema`).
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
-    pub fn create_lfo(&mut self) -> Result<JsValue, JsValue> {
-        let lfo_id = NodeId::new();
+    pub fn update_voice_saturation_tone_params(
+        &mut self,
+        node_id: &str,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_voice_saturation_tone_update(node_id, params)
+    }
+
+    fn apply_voice_saturation_tone_update(
+        &mut self,
+        node_id: &str,
+        params: SaturationToneUpdate,
+    ) -> Result<(), JsValue> {
+        let SaturationToneUpdate {
+            character,
+            pre_tilt_db,
+            post_tilt_db,
+            auto_gain,
+        } = params;
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+
         for voice in &mut self.voices {
-            voice
-                .graph
-                .add_node_with_id(lfo_id, Box::new(Lfo::new(self.sample_rate)));
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(saturation) = node.as_any_mut().downcast_mut::<Saturation>() {
+                    saturation.set_character(character);
+                    saturation.set_pre_tilt(pre_tilt_db);
+                    saturation.set_post_tilt(post_tilt_db);
+                    saturation.set_auto_gain(auto_gain);
+                } else {
+                    return Err(JsValue::from_str("Node is not a Saturation"));
+                }
+            } else {
+                return Err(JsValue::from_str("Node not found"));
+            }
         }
-        let obj = js_sys::Object::new();
-        js_sys::Reflect::set(
-            &obj,
-            &"lfoId".into(),
-            &JsValue::from_str(&lfo_id.to_string()),
-        )?;
+        Ok(())
+    }
 
-        Ok(obj.into())
+    /// Updates a per-voice bitcrusher; the master insert uses `update_bitcrusher`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_voice_bitcrusher(
+        &mut self,
+        node_id: &str,
+        bits: u8,
+        downsample_factor: usize,
+        mix: f32,
+        active: bool,
+    ) -> Result<(), JsValue> {
+        self.apply_voice_bitcrusher_update(
+            node_id,
+            BitcrusherUpdate {
+                bits,
+                downsample_factor,
+                mix,
+                active,
+            },
+        )
     }
 
+    /// Object form of `update_voice_bitcrusher`, taking the fields of `BitcrusherUpdate` (see
+    /// `get_api_schema`).
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
-    pub fn create_filter(&mut self) -> Result<String, JsValue> {
-        let filter_id = NodeId::new();
+    pub fn update_voice_bitcrusher_params(
+        &mut self,
+        node_id: &str,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_voice_bitcrusher_update(node_id, params)
+    }
+
+    fn apply_voice_bitcrusher_update(
+        &mut self,
+        node_id: &str,
+        params: BitcrusherUpdate,
+    ) -> Result<(), JsValue> {
+        let BitcrusherUpdate {
+            bits,
+            downsample_factor,
+            mix,
+            active,
+        } = params;
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))?;
+
         for voice in &mut self.voices {
-            voice
-                .graph
-                .add_node_with_id(filter_id, Box::new(FilterCollection::new(self.sample_rate)));
+            if let Some(node) = voice.graph.get_node_mut(node_id) {
+                if let Some(crusher) = node.as_any_mut().downcast_mut::<Bitcrusher>() {
+                    crusher.set_bits(bits);
+                    crusher.set_downsample_factor(downsample_factor);
+                    crusher.set_mix(mix);
+                    crusher.set_active(active);
+                } else {
+                    return Err(JsValue::from_str("Node is not a Bitcrusher"));
+                }
+            } else {
+                return Err(JsValue::from_str("Node not found"));
+            }
         }
-        Ok(filter_id.to_string())
+        Ok(())
     }
 
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
@@ -1920,15 +6960,17 @@ impl AudioEngine {
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
     pub fn create_oscillator(&mut self) -> Result<String, JsValue> {
         let osc_id = NodeId::new();
+        let levels = self.calibrate_new_node(NodeRole::Source);
         for voice in &mut self.voices {
-            voice.graph.add_node_with_id(
-                osc_id,
-                Box::new(AnalogOscillator::new(
-                    self.sample_rate,
-                    Waveform::Sine,
-                    self.wavetable_banks.clone(), // pass the shared banks
-                )),
+            let mut osc = AnalogOscillator::new(
+                self.sample_rate,
+                Waveform::Sine,
+                self.wavetable_banks.clone(), // pass the shared banks
             );
+            if let Some(levels) = levels {
+                osc.set_gain(levels.gain);
+            }
+            voice.graph.add_node_with_id(osc_id, Box::new(osc));
         }
         Ok(osc_id.to_string())
     }
@@ -1936,18 +6978,44 @@ impl AudioEngine {
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
     pub fn create_wavetable_oscillator(&mut self) -> Result<String, JsValue> {
         let osc_id = NodeId::new();
+        let levels = self.calibrate_new_node(NodeRole::Source);
         for voice in &mut self.voices {
-            voice.graph.add_node_with_id(
-                osc_id,
-                Box::new(WavetableOscillator::new(
-                    self.sample_rate,
-                    self.wavetable_synthbank.clone(),
-                )),
-            );
+            let mut osc =
+                WavetableOscillator::new(self.sample_rate, self.wavetable_synthbank.clone());
+            if let Some(levels) = levels {
+                osc.set_gain(levels.gain);
+            }
+            voice.graph.add_node_with_id(osc_id, Box::new(osc));
         }
         Ok(osc_id.to_string())
     }
 
+    /// New oscillators and filters start at levels chosen from the voice's
+    /// current headroom: oscillators at the gain of the ones already playing,
+    /// filters with level compensation on. `last_node_levels` reports the
+    /// choice, including the amount to wire the node into the output mixer at.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_auto_node_levels(&mut self, enabled: bool) {
+        self.auto_node_levels = enabled;
+    }
+
+    /// `{ gain, mixerInputGain, headroomDb }` chosen for the last oscillator or
+    /// filter created, or null when auto levels were off.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn last_node_levels(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.last_node_levels)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize levels: {}", e)))
+    }
+
+    fn calibrate_new_node(&mut self, role: NodeRole) -> Option<NodeLevels> {
+        self.last_node_levels = self
+            .voices
+            .first()
+            .filter(|_| self.auto_node_levels)
+            .map(|voice| node_levels(&voice.graph, role));
+        self.last_node_levels
+    }
+
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
     pub fn create_sampler(&mut self) -> Result<String, JsValue> {
         let sample_data = Rc::new(RefCell::new(SampleData::new()));
@@ -1968,77 +7036,118 @@ impl AudioEngine {
 
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
     pub fn import_sample(&mut self, sampler_id: &str, data: &[u8]) -> Result<(), JsValue> {
-        use std::io::Cursor;
-
         log_console("Starting import_sample");
-
-        // Create a hound reader from the data
-        let cursor = Cursor::new(data);
-        let mut reader =
-            hound::WavReader::new(cursor).map_err(|e| JsValue::from_str(&e.to_string()))?;
-        let spec = reader.spec();
-        log_console(&format!(
-            "WAV spec: sample_rate={}, channels={}, bits_per_sample={}, sample_format={:?}",
-            spec.sample_rate, spec.channels, spec.bits_per_sample, spec.sample_format
+        let sample_data = Rc::new(RefCell::new(
+            SampleData::from_wav_bytes(data).map_err(|e| JsValue::from_str(&e))?,
         ));
 
-        // Read the samples in f32 form
-        let samples: Vec<f32> = match (spec.bits_per_sample, spec.sample_format) {
-            (32, hound::SampleFormat::Float) => {
-                reader.samples::<f32>().map(|s| s.unwrap()).collect()
-            }
-            (16, hound::SampleFormat::Int) => reader
-                .samples::<i16>()
-                .map(|s| s.unwrap() as f32 / i16::MAX as f32)
-                .collect(),
-            (24, hound::SampleFormat::Int) => {
-                let shift = 32 - 24;
-                reader
-                    .samples::<i32>()
-                    .map(|s| (s.unwrap() << shift >> shift) as f32 / 8_388_607.0)
-                    .collect()
-            }
-            (32, hound::SampleFormat::Int) => reader
-                .samples::<i32>()
-                .map(|s| s.unwrap() as f32 / i32::MAX as f32)
-                .collect(),
-            (bits, format) => {
-                return Err(JsValue::from_str(&format!(
-                    "Unsupported WAV format: bits_per_sample={} sample_format={:?}",
-                    bits, format
-                )))
+        // Parse sampler UUID
+        let sampler_id = NodeId::from_string(sampler_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid sampler_id UUID: {}", e)))?;
+        self.imports.backup_sample(sampler_id, &self.voices);
+
+        // Update all sampler nodes with the new sample data
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(sampler_id) {
+                if let Some(sampler) = node.as_any_mut().downcast_mut::<Sampler>() {
+                    sampler.set_sample_data(sample_data.clone());
+                } else {
+                    return Err(JsValue::from_str("Node is not a Sampler"));
+                }
+            } else {
+                return Err(JsValue::from_str("Node not found"));
             }
-        };
+        }
 
-        log_console(&format!("Read {} samples", samples.len()));
+        log_console("Sample imported successfully");
+        Ok(())
+    }
 
-        // Create new sample data
-        let sample_data = Rc::new(RefCell::new(SampleData::new()));
-        sample_data.borrow_mut().load_from_wav(
-            samples,
-            spec.channels as usize,
-            spec.sample_rate as f32,
-        );
+    /// Adds a multisample zone to a sampler from a WAV file. `mapping` is a
+    /// `ZoneMapping` (`{ keyLow, keyHigh, velocityLow, velocityHigh, rootNote,
+    /// roundRobinGroup }`); returns the zone's index for `set_zone_mapping`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn import_sample_zone(
+        &mut self,
+        sampler_id: &str,
+        data: &[u8],
+        mapping: JsValue,
+    ) -> Result<usize, JsValue> {
+        let mapping: ZoneMapping = serde_wasm_bindgen::from_value(mapping)
+            .map_err(|e| JsValue::from_str(&format!("Invalid zone mapping: {}", e)))?;
+        let sample = SampleData::from_wav_bytes(data).map_err(|e| JsValue::from_str(&e))?;
+        let mut index = 0;
+        for sample_data in self.sampler_data(sampler_id)? {
+            index = sample_data.borrow_mut().add_zone(sample.clone(), mapping);
+        }
+        Ok(index)
+    }
+
+    /// Moves a sampler zone to a new key/velocity range, root note or
+    /// round-robin group.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_zone_mapping(
+        &mut self,
+        sampler_id: &str,
+        zone_index: usize,
+        mapping: JsValue,
+    ) -> Result<(), JsValue> {
+        let mapping: ZoneMapping = serde_wasm_bindgen::from_value(mapping)
+            .map_err(|e| JsValue::from_str(&format!("Invalid zone mapping: {}", e)))?;
+        for sample_data in self.sampler_data(sampler_id)? {
+            sample_data
+                .borrow_mut()
+                .zones
+                .get_mut(zone_index)
+                .ok_or_else(|| JsValue::from_str("Zone not found"))?
+                .set_mapping(mapping);
+        }
+        Ok(())
+    }
 
-        // Parse sampler UUID
+    /// Loads an SFZ instrument into a sampler, replacing its zones.
+    /// `resolve_sample` is called with each region's sample path and must
+    /// return the WAV file as a `Uint8Array`. Returns the number of zones.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn import_sfz(
+        &mut self,
+        sampler_id: &str,
+        sfz_text: &str,
+        resolve_sample: &js_sys::Function,
+    ) -> Result<usize, JsValue> {
+        let zones = load_sfz(sfz_text, |path| {
+            let bytes = resolve_sample
+                .call1(&JsValue::NULL, &JsValue::from_str(path))
+                .map_err(|e| format!("Failed to resolve {}: {:?}", path, e))?;
+            Ok(js_sys::Uint8Array::new(&bytes).to_vec())
+        })
+        .map_err(|e| JsValue::from_str(&e))?;
+        for sample_data in self.sampler_data(sampler_id)? {
+            sample_data.borrow_mut().zones = zones.clone();
+        }
+        Ok(zones.len())
+    }
+
+    /// A sampler's sample data, once per distinct buffer; voices normally
+    /// share one.
+    fn sampler_data(&self, sampler_id: &str) -> Result<Vec<Rc<RefCell<SampleData>>>, JsValue> {
         let sampler_id = NodeId::from_string(sampler_id)
             .map_err(|e| JsValue::from_str(&format!("Invalid sampler_id UUID: {}", e)))?;
-
-        // Update all sampler nodes with the new sample data
-        for voice in &mut self.voices {
-            if let Some(node) = voice.graph.get_node_mut(sampler_id) {
-                if let Some(sampler) = node.as_any_mut().downcast_mut::<Sampler>() {
-                    sampler.set_sample_data(sample_data.clone());
-                } else {
-                    return Err(JsValue::from_str("Node is not a Sampler"));
-                }
-            } else {
-                return Err(JsValue::from_str("Node not found"));
+        let mut shared: Vec<Rc<RefCell<SampleData>>> = Vec::new();
+        for voice in &self.voices {
+            let sample_data = voice
+                .graph
+                .get_node(sampler_id)
+                .ok_or_else(|| JsValue::from_str("Node not found"))?
+                .as_any()
+                .downcast_ref::<Sampler>()
+                .ok_or_else(|| JsValue::from_str("Node is not a Sampler"))?
+                .get_sample_data();
+            if !shared.iter().any(|known| Rc::ptr_eq(known, &sample_data)) {
+                shared.push(sample_data);
             }
         }
-
-        log_console("Sample imported successfully");
-        Ok(())
+        Ok(shared)
     }
 
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
@@ -2048,6 +7157,18 @@ impl AudioEngine {
         glide_time: f32,
         active: bool,
     ) -> Result<(), JsValue> {
+        self.apply_glide_update(glide_id, GlideUpdate { glide_time, active })
+    }
+
+    /// Object form of `update_glide`, taking the fields of `GlideUpdate` (see `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_glide_params(&mut self, glide_id: &str, params: JsValue) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_glide_update(glide_id, params)
+    }
+
+    fn apply_glide_update(&mut self, glide_id: &str, params: GlideUpdate) -> Result<(), JsValue> {
+        let GlideUpdate { glide_time, active } = params;
         let glide_id = NodeId::from_string(glide_id)
             .map_err(|e| JsValue::from_str(&format!("Invalid glide_id UUID: {}", e)))?;
 
@@ -2094,10 +7215,64 @@ impl AudioEngine {
         loop_mode: u8,
         loop_start: f32,
         loop_end: f32,
+        loop_crossfade: f32,
         root_note: f32,
         trigger_mode: u8,
         active: bool,
+        playback_mode: u8,
+        grain_size: f32,
+        stretch_quality: u8,
+    ) -> Result<(), JsValue> {
+        self.apply_sampler_update(
+            sampler_id,
+            SamplerUpdate {
+                frequency,
+                gain,
+                loop_mode,
+                loop_start,
+                loop_end,
+                loop_crossfade: Some(loop_crossfade),
+                root_note,
+                trigger_mode,
+                active,
+                playback_mode: Some(playback_mode),
+                grain_size: Some(grain_size),
+                stretch_quality: Some(stretch_quality),
+            },
+        )
+    }
+
+    /// Object form of `update_sampler`, taking the fields of `SamplerUpdate` (see
+    /// `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_sampler_params(
+        &mut self,
+        sampler_id: &str,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_sampler_update(sampler_id, params)
+    }
+
+    fn apply_sampler_update(
+        &mut self,
+        sampler_id: &str,
+        params: SamplerUpdate,
     ) -> Result<(), JsValue> {
+        let SamplerUpdate {
+            frequency,
+            gain,
+            loop_mode,
+            loop_start,
+            loop_end,
+            loop_crossfade,
+            root_note,
+            trigger_mode,
+            active,
+            playback_mode,
+            grain_size,
+            stretch_quality,
+        } = params;
         let loop_mode = match loop_mode {
             0 => SamplerLoopMode::Off,
             1 => SamplerLoopMode::Loop,
@@ -2112,6 +7287,11 @@ impl AudioEngine {
             _ => SamplerTriggerMode::Gate,
         };
 
+        let playback_mode = playback_mode.map(|mode| match mode {
+            1 => SamplerPlaybackMode::Stretch,
+            _ => SamplerPlaybackMode::Repitch,
+        });
+
         let sampler_id = NodeId::from_string(sampler_id)
             .map_err(|e| JsValue::from_str(&format!("Invalid sampler_id UUID: {}", e)))?;
 
@@ -2123,9 +7303,21 @@ impl AudioEngine {
                     sampler.set_loop_mode(loop_mode);
                     sampler.set_loop_start(loop_start);
                     sampler.set_loop_end(loop_end);
+                    if let Some(crossfade) = loop_crossfade {
+                        sampler.set_loop_crossfade(crossfade);
+                    }
                     sampler.set_root_note(root_note);
                     sampler.set_trigger_mode(trigger_mode);
                     sampler.set_active(active);
+                    if let Some(mode) = playback_mode {
+                        sampler.set_playback_mode(mode);
+                    }
+                    if let Some(grain_size) = grain_size {
+                        sampler.set_grain_size(grain_size);
+                    }
+                    if let Some(quality) = stretch_quality {
+                        sampler.set_stretch_quality(quality);
+                    }
                 } else {
                     return Err(JsValue::from_str("Node is not a Sampler"));
                 }
@@ -2329,10 +7521,53 @@ impl AudioEngine {
         key_tracking: f32,
         comb_frequency: f32,
         comb_dampening: f32,
-        _oversampling: u32,
+        oversampling: u32,
         filter_type: FilterType,
         filter_slope: FilterSlope,
     ) -> Result<(), JsValue> {
+        self.apply_filters_update(
+            filter_id,
+            FilterUpdate {
+                cutoff,
+                resonance,
+                gain,
+                key_tracking,
+                comb_frequency,
+                comb_dampening,
+                oversampling: Some(oversampling),
+                filter_type,
+                filter_slope,
+            },
+        )
+    }
+
+    /// Object form of `update_filters`, taking the fields of `FilterUpdate` (see `get_api_schema`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_filters_params(
+        &mut self,
+        filter_id: &str,
+        params: JsValue,
+    ) -> Result<(), JsValue> {
+        let params = parse_update_params(params)?;
+        self.apply_filters_update(filter_id, params)
+    }
+
+    fn apply_filters_update(
+        &mut self,
+        filter_id: &str,
+        params: FilterUpdate,
+    ) -> Result<(), JsValue> {
+        let FilterUpdate {
+            cutoff,
+            resonance,
+            gain,
+            key_tracking,
+            comb_frequency,
+            comb_dampening,
+            oversampling,
+            filter_type,
+            filter_slope,
+        } = params;
         let filter_id = NodeId::from_string(filter_id)
             .map_err(|e| JsValue::from_str(&format!("Invalid filter_id UUID: {}", e)))?;
 
@@ -2352,7 +7587,81 @@ impl AudioEngine {
                     //log key_tracking
                     // log_console(&format!("key_tracking is {}", key_tracking));
                     filter.set_keyboard_tracking_sensitivity(key_tracking);
-                    //filter.set_oversampling_factor(oversampling);
+                    if let Some(factor) = oversampling {
+                        filter.set_oversampling_factor(factor);
+                    }
+                } else {
+                    return Err(JsValue::from_str("Node is not a Filter"));
+                }
+            } else {
+                return Err(JsValue::from_str("Node not found"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Toggles calibrated resonance/drive level compensation on a filter.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_filter_auto_gain(
+        &mut self,
+        filter_id: &str,
+        enabled: bool,
+    ) -> Result<(), JsValue> {
+        let filter_id = NodeId::from_string(filter_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid filter_id UUID: {}", e)))?;
+
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(filter_id) {
+                if let Some(filter) = node.as_any_mut().downcast_mut::<FilterCollection>() {
+                    filter.set_auto_gain(enabled);
+                } else {
+                    return Err(JsValue::from_str("Node is not a Filter"));
+                }
+            } else {
+                return Err(JsValue::from_str("Node not found"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs a filter's ladder/comb feedback paths in f64 (off by default).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_filter_double_precision(
+        &mut self,
+        filter_id: &str,
+        enabled: bool,
+    ) -> Result<(), JsValue> {
+        let filter_id = NodeId::from_string(filter_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid filter_id UUID: {}", e)))?;
+
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(filter_id) {
+                if let Some(filter) = node.as_any_mut().downcast_mut::<FilterCollection>() {
+                    filter.set_double_precision_feedback(enabled);
+                } else {
+                    return Err(JsValue::from_str("Node is not a Filter"));
+                }
+            } else {
+                return Err(JsValue::from_str("Node not found"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets how many octaves a unit of CutoffMod moves the cutoff (0 = linear Hz).
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn update_filter_cutoff_mod_octaves(
+        &mut self,
+        filter_id: &str,
+        octaves: f32,
+    ) -> Result<(), JsValue> {
+        let filter_id = NodeId::from_string(filter_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid filter_id UUID: {}", e)))?;
+
+        for voice in &mut self.voices {
+            if let Some(node) = voice.graph.get_node_mut(filter_id) {
+                if let Some(filter) = node.as_any_mut().downcast_mut::<FilterCollection>() {
+                    filter.set_cutoff_mod_octaves(octaves);
                 } else {
                     return Err(JsValue::from_str("Node is not a Filter"));
                 }
@@ -2386,6 +7695,45 @@ impl AudioEngine {
         Ok(vec![])
     }
 
+    /// Magnitude response in dB of the voice's serial tone-shaping chain
+    /// (filters and saturation between the oscillators and the output), at
+    /// `points` frequencies spaced logarithmically from 20 Hz to 20 kHz.
+    /// Unlike `get_filter_ir_waveform` it covers every stage, and the values
+    /// are plain dB rather than a display range.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_voice_chain_response(&self, points: usize) -> Vec<f32> {
+        let chain = self
+            .voices
+            .first()
+            .map(|voice| serial_chain(&voice.graph))
+            .unwrap_or_default();
+        chain_response(chain, self.sample_rate, points)
+    }
+
+    /// Renders a copy of an oscillator holding a note at `frequency`, with its
+    /// current quality settings, and returns an `AliasReport`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn measure_oscillator_aliasing(
+        &self,
+        node_id: &str,
+        frequency: f32,
+    ) -> Result<JsValue, JsValue> {
+        let node_id = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node UUID: {}", e)))?;
+        let mut node = self
+            .voices
+            .first()
+            .and_then(|voice| voice.graph.get_node(node_id))
+            .ok_or_else(|| JsValue::from_str("Node not found"))?
+            .clone_node()
+            .ok_or_else(|| JsValue::from_str("Node can't be measured"))?;
+        node.reset();
+        let samples = render_tone(node.as_mut(), frequency);
+        let report = measure_aliasing(&samples, self.sample_rate, frequency);
+        serde_wasm_bindgen::to_value(&report)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize report: {}", e)))
+    }
+
     /// Update all LFOs across all voices. This is called by the host when the user
     /// changes an LFO's settings.
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
@@ -2409,6 +7757,8 @@ impl AudioEngine {
                         2 => LfoWaveform::Square,
                         3 => LfoWaveform::Saw,
                         4 => LfoWaveform::InverseSaw,
+                        5 => LfoWaveform::SampleAndHold,
+                        6 => LfoWaveform::SmoothRandom,
                         _ => LfoWaveform::Sine,
                     };
 
@@ -2451,6 +7801,8 @@ impl AudioEngine {
             2 => LfoWaveform::Square,
             3 => LfoWaveform::Saw,
             4 => LfoWaveform::InverseSaw,
+            5 => LfoWaveform::SampleAndHold,
+            6 => LfoWaveform::SmoothRandom,
             _ => return Err(JsValue::from_str("Invalid waveform type")),
         };
 
@@ -2499,6 +7851,13 @@ impl AudioEngine {
             modulation_transform,
         };
 
+        // Every voice takes the connection or none does.
+        for voice in &self.voices {
+            voice
+                .graph
+                .check_connection(&connection)
+                .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        }
         for voice in &mut self.voices {
             voice.graph.add_connection(connection.clone());
         }
@@ -2531,6 +7890,107 @@ impl AudioEngine {
         Ok(())
     }
 
+    /// Replaces the PhaseMod links between the given operators with the
+    /// routing of `algorithm` (stack, parallel or pairs), in every voice.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn apply_fm_algorithm(
+        &mut self,
+        carrier_ids: Vec<String>,
+        modulator_ids: Vec<String>,
+        algorithm: FmAlgorithm,
+    ) -> Result<(), JsValue> {
+        let parse = |ids: &[String]| -> Result<Vec<NodeId>, JsValue> {
+            ids.iter()
+                .map(|id| {
+                    NodeId::from_string(id)
+                        .map_err(|e| JsValue::from_str(&format!("Invalid node_id UUID: {}", e)))
+                })
+                .collect()
+        };
+        let (carriers, modulators) = (parse(&carrier_ids)?, parse(&modulator_ids)?);
+        let connections = fm_algorithm_connections(algorithm, &carriers, &modulators)
+            .map_err(|e| JsValue::from_str(&e))?;
+        let operators: Vec<NodeId> = carriers.iter().chain(&modulators).copied().collect();
+        if let Some(voice) = self.voices.first() {
+            check_fm_operators(&voice.graph, &operators).map_err(|e| JsValue::from_str(&e))?;
+        }
+
+        for voice in &mut self.voices {
+            clear_fm_links(&mut voice.graph, &operators);
+        }
+        for connection in connections {
+            for voice in &self.voices {
+                voice
+                    .graph
+                    .check_connection(&connection)
+                    .map_err(|e| JsValue::from_str(&e.to_string()))?;
+            }
+            for voice in &mut self.voices {
+                voice.graph.add_connection(connection.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// The values a connection sweeps the destination parameter over, as
+    /// `{ parameter, unit, base, low, high }` in the parameter's units (e.g. a
+    /// cutoff moving between 200 and 4200 Hz), so the UI can show depth in
+    /// real terms rather than the raw amount.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_modulation_range(
+        &self,
+        from_node: &str,
+        to_node: &str,
+        to_port: PortId,
+    ) -> Result<JsValue, JsValue> {
+        let from_node_id = NodeId::from_string(from_node)
+            .map_err(|e| JsValue::from_str(&format!("Invalid from_node UUID: {}", e)))?;
+        let to_node_id = NodeId::from_string(to_node)
+            .map_err(|e| JsValue::from_str(&format!("Invalid to_node UUID: {}", e)))?;
+        let range = self
+            .voices
+            .first()
+            .and_then(|voice| {
+                voice
+                    .graph
+                    .modulation_range(from_node_id, to_node_id, to_port)
+            })
+            .ok_or_else(|| JsValue::from_str("Connection has no modulation range"))?;
+        serde_wasm_bindgen::to_value(&range)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize range: {}", e)))
+    }
+
+    /// The curve a node's modulation input follows over a virtual note held
+    /// for `note_length` of `duration` seconds, as `{ parameter, unit,
+    /// interval, values }`, so the UI can draw what the envelopes and LFOs
+    /// on the port do to e.g. the cutoff.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_modulation_preview(
+        &self,
+        node_id: &str,
+        port: PortId,
+        duration: f32,
+        note_length: f32,
+    ) -> Result<JsValue, JsValue> {
+        let node = NodeId::from_string(node_id)
+            .map_err(|e| JsValue::from_str(&format!("Invalid node UUID: {}", e)))?;
+        let voice = self
+            .voices
+            .first()
+            .ok_or_else(|| JsValue::from_str("No voices"))?;
+        let preview = modulation_preview(
+            &voice.graph,
+            node,
+            port,
+            self.sample_rate,
+            duration,
+            note_length,
+        )
+        .map_err(|e| JsValue::from_str(&e))?;
+        serde_wasm_bindgen::to_value(&preview)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize preview: {}", e)))
+    }
+
     #[cfg_attr(feature = "wasm", wasm_bindgen)]
     pub fn connect_macro(
         &mut self,
@@ -2577,6 +8037,188 @@ impl AudioEngine {
             .map_err(|e| JsValue::from_str(&e))
     }
 
+    /// Sparse macro update: `value` at the start of the next block, ramping to
+    /// `ramp_target` by its last sample when given. Without a target the macro
+    /// glides to `value` over one block. The value is held afterwards, so hosts
+    /// only send changes and can pass empty macro buffers to `process_audio`.
+    /// `voice_index` of `None` updates every voice.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_macro_value(
+        &mut self,
+        voice_index: Option<usize>,
+        macro_index: usize,
+        value: f32,
+        ramp_target: Option<f32>,
+    ) -> Result<(), JsValue> {
+        if voice_index.is_none() {
+            self.locks
+                .remember_macro_value(macro_index, ramp_target.unwrap_or(value));
+        }
+        let voices: &mut [Voice] = match voice_index {
+            Some(index) => {
+                let voice = self
+                    .voices
+                    .get_mut(index)
+                    .ok_or_else(|| JsValue::from_str("Invalid voice index"))?;
+                std::slice::from_mut(voice)
+            }
+            None => &mut self.voices,
+        };
+        for voice in voices {
+            voice
+                .set_macro_sparse(macro_index, value, ramp_target)
+                .map_err(|e| JsValue::from_str(&e))?;
+        }
+        Ok(())
+    }
+
+    /// Sets the range mapping of a macro route made with `connect_macro`: the
+    /// macro's 0..1 travel is mapped onto `min..max` through `curve` (see
+    /// `get_curved_value`). A bipolar mapping centres the range on the knob's
+    /// midpoint. `voice_index` of `None` updates every voice.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_macro_mapping(
+        &mut self,
+        voice_index: Option<usize>,
+        macro_index: usize,
+        target_node: &str,
+        target_port: PortId,
+        min: f32,
+        max: f32,
+        curve: f32,
+        bipolar: bool,
+    ) -> Result<(), JsValue> {
+        let target_node_id = NodeId::from_string(target_node)
+            .map_err(|e| JsValue::from_str(&format!("Invalid target_node UUID: {}", e)))?;
+        let mapping = MacroMapping {
+            min,
+            max,
+            curve,
+            polarity: if bipolar {
+                MacroPolarity::Bipolar
+            } else {
+                MacroPolarity::Unipolar
+            },
+        };
+        let voices: &mut [Voice] = match voice_index {
+            Some(index) => {
+                let voice = self
+                    .voices
+                    .get_mut(index)
+                    .ok_or_else(|| JsValue::from_str("Invalid voice index"))?;
+                std::slice::from_mut(voice)
+            }
+            None => &mut self.voices,
+        };
+        for voice in voices {
+            let found = voice
+                .set_macro_mapping(macro_index, target_node_id, target_port, mapping)
+                .map_err(|e| JsValue::from_str(&e))?;
+            if !found {
+                return Err(JsValue::from_str(&format!(
+                    "Macro {} has no route to {:?} on node {}",
+                    macro_index, target_port, target_node
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Drives a macro in every voice from performance data, scaled by
+    /// `amount`, in place of the values the host sends: 1 = velocity,
+    /// 2 = aftertouch, 3 = mod wheel, 4 = a random value per note. 0 hands the
+    /// macro back to the host.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_macro_source(
+        &mut self,
+        macro_index: usize,
+        source: u8,
+        amount: f32,
+    ) -> Result<(), JsValue> {
+        let source = MacroSource::from_u8(source);
+        for voice in &mut self.voices {
+            voice
+                .set_macro_source(macro_index, source, amount)
+                .map_err(|e| JsValue::from_str(&e))?;
+        }
+        Ok(())
+    }
+
+    /// Mod wheel position (0..1) for macros driven by it.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn set_mod_wheel(&mut self, value: f32) {
+        for voice in &mut self.voices {
+            voice.current_mod_wheel = value.clamp(0.0, 1.0);
+        }
+    }
+
+    /// Macro routes with their mappings, in the patch `macros.routes` format.
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_macro_routes(&self) -> Result<JsValue, JsValue> {
+        let routes: Vec<MacroRouteState> = self.voices.first().map_or_else(Vec::new, |voice| {
+            voice
+                .macro_routes()
+                .map(|(macro_index, target)| MacroRouteState::from_target(macro_index, target))
+                .collect()
+        });
+        serde_wasm_bindgen::to_value(&routes)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize macro routes: {}", e)))
+    }
+
+    fn apply_macro_state(&mut self, macros: &MacroState) -> Result<(), JsValue> {
+        for route in &macros.routes {
+            let target_node_id =
+                parse_node_id(&route.target_id).map_err(|e| JsValue::from_str(&e))?;
+            let target_port =
+                port_id_from_u32(route.target_port).map_err(|e| JsValue::from_str(&e))?;
+            let modulation_type = route
+                .modulation_type
+                .map_or(Ok(ModulationType::VCA), modulation_type_from_i32)
+                .map_err(|e| JsValue::from_str(&e))?;
+            let modulation_transform = route
+                .modulation_transform
+                .map_or(
+                    Ok(ModulationTransformation::None),
+                    modulation_transform_from_i32,
+                )
+                .map_err(|e| JsValue::from_str(&e))?;
+            for voice in &mut self.voices {
+                let _ = voice.remove_macro_route(route.macro_index, target_node_id, target_port);
+                if route.amount > 0.0 {
+                    voice
+                        .add_macro_mapping(
+                            route.macro_index,
+                            ModulationTarget {
+                                node_id: target_node_id,
+                                port_id: target_port,
+                                amount: route.amount,
+                                modulation_type,
+                                modulation_transform,
+                                mapping: route.mapping,
+                                mapped_buffer_idx: None,
+                            },
+                        )
+                        .map_err(|e| JsValue::from_str(&e))?;
+                }
+            }
+        }
+        if !self.locks.is_locked(LockableParameter::MacroValues) {
+            for (macro_index, &value) in macros.values.iter().enumerate() {
+                if let Err(err) = self.set_macro_value(None, macro_index, value, None) {
+                    log_console(&format!("Failed to apply macro value: {:?}", err));
+                }
+            }
+        }
+        for source in &macros.sources {
+            for voice in &mut self.voices {
+                voice
+                    .set_macro_source(source.macro_index, Some(source.source), source.amount)
+                    .map_err(|e| JsValue::from_str(&e))?;
+            }
+        }
+        Ok(())
+    }
+
     fn build_nodes_from_canonical_voice(
         &mut self,
         voice_layout: &PatchVoiceLayout,
@@ -2598,10 +8240,9 @@ impl AudioEngine {
         match node_type {
             "global_frequency" => {
                 for voice in &mut self.voices {
-                    voice.graph.add_node_with_id(
-                        node_id,
-                        Box::new(GlobalFrequencyNode::new(440.0, self.block_size)),
-                    );
+                    let mut node = GlobalFrequencyNode::new(440.0, self.block_size);
+                    node.set_sample_rate(self.sample_rate);
+                    voice.graph.add_node_with_id(node_id, Box::new(node));
                     voice.graph.global_frequency_node = Some(node_id);
                 }
             }
@@ -2645,6 +8286,42 @@ impl AudioEngine {
                     voice.graph.global_velocity_node = Some(node_id);
                 }
             }
+            "global_pressure" => {
+                for voice in &mut self.voices {
+                    voice.graph.add_node_with_id(
+                        node_id,
+                        Box::new(GlobalExpressionNode::new(
+                            ExpressionKind::Pressure,
+                            self.block_size,
+                        )),
+                    );
+                    voice.graph.global_pressure_node = Some(node_id);
+                }
+            }
+            "global_timbre" => {
+                for voice in &mut self.voices {
+                    voice.graph.add_node_with_id(
+                        node_id,
+                        Box::new(GlobalExpressionNode::new(
+                            ExpressionKind::Timbre,
+                            self.block_size,
+                        )),
+                    );
+                    voice.graph.global_timbre_node = Some(node_id);
+                }
+            }
+            "global_release_velocity" => {
+                for voice in &mut self.voices {
+                    voice.graph.add_node_with_id(
+                        node_id,
+                        Box::new(GlobalExpressionNode::new(
+                            ExpressionKind::ReleaseVelocity,
+                            self.block_size,
+                        )),
+                    );
+                    voice.graph.global_release_velocity_node = Some(node_id);
+                }
+            }
             "gatemixer" => {
                 for voice in &mut self.voices {
                     voice
@@ -2704,9 +8381,9 @@ impl AudioEngine {
             }
             "lfo" => {
                 for voice in &mut self.voices {
-                    voice
-                        .graph
-                        .add_node_with_id(node_id, Box::new(Lfo::new(self.sample_rate)));
+                    let mut lfo = Lfo::new(self.sample_rate);
+                    lfo.set_voice_index(voice.id);
+                    voice.graph.add_node_with_id(node_id, Box::new(lfo));
                 }
             }
             "filter" => {
@@ -2732,16 +8409,131 @@ impl AudioEngine {
                         .add_node_with_id(node_id, Box::new(NoiseGenerator::new(self.sample_rate)));
                 }
             }
+            "dual_filter" => {
+                for voice in &mut self.voices {
+                    voice
+                        .graph
+                        .add_node_with_id(node_id, Box::new(DualFilter::new(self.sample_rate)));
+                }
+            }
+            "formant_filter" => {
+                for voice in &mut self.voices {
+                    voice
+                        .graph
+                        .add_node_with_id(node_id, Box::new(FormantFilter::new(self.sample_rate)));
+                }
+            }
+            "gate_tool" => {
+                for voice in &mut self.voices {
+                    voice
+                        .graph
+                        .add_node_with_id(node_id, Box::new(GateTool::new(self.sample_rate)));
+                }
+            }
+            "clock" => {
+                for voice in &mut self.voices {
+                    voice
+                        .graph
+                        .add_node_with_id(node_id, Box::new(Clock::new(1.0, 0.5)));
+                }
+            }
+            "chance" => {
+                for voice in &mut self.voices {
+                    let mut chance = Chance::new();
+                    chance.set_voice_index(voice.id);
+                    voice.graph.add_node_with_id(node_id, Box::new(chance));
+                }
+            }
+            "fm_operator" => {
+                for voice in &mut self.voices {
+                    voice
+                        .graph
+                        .add_node_with_id(node_id, Box::new(FmOperator::new(self.sample_rate)));
+                }
+            }
+            "mseg" => {
+                for voice in &mut self.voices {
+                    voice.graph.add_node_with_id(
+                        node_id,
+                        Box::new(Mseg::new(self.sample_rate, MsegConfig::default())),
+                    );
+                }
+            }
+            "stereo_enhancer" => {
+                for voice in &mut self.voices {
+                    voice.graph.add_node_with_id(
+                        node_id,
+                        Box::new(StereoEnhancer::new(
+                            self.sample_rate,
+                            0.0,
+                            12.0,
+                            1.0,
+                            1.0,
+                            1.0,
+                        )),
+                    );
+                }
+            }
+            "binaural" => {
+                for voice in &mut self.voices {
+                    voice.graph.add_node_with_id(
+                        node_id,
+                        Box::new(Binaural::new(self.sample_rate, 0.0, 0.0, 1.0)),
+                    );
+                }
+            }
+            "auto_wah" => {
+                for voice in &mut self.voices {
+                    voice.graph.add_node_with_id(
+                        node_id,
+                        Box::new(AutoWah::new(self.sample_rate, 0.5, 300.0, 3.0, 4.0, 1.0)),
+                    );
+                }
+            }
+            "noise_gate" => {
+                for voice in &mut self.voices {
+                    voice.graph.add_node_with_id(
+                        node_id,
+                        Box::new(NoiseGate::new(
+                            self.sample_rate,
+                            -50.0,
+                            6.0,
+                            1.0,
+                            50.0,
+                            100.0,
+                            -80.0,
+                        )),
+                    );
+                }
+            }
+            "saturation" => {
+                for voice in &mut self.voices {
+                    voice.graph.add_node_with_id(
+                        node_id,
+                        Box::new(Saturation::new(self.sample_rate, 2.0, 0.5)),
+                    );
+                }
+            }
+            "bitcrusher" => {
+                for voice in &mut self.voices {
+                    voice.graph.add_node_with_id(
+                        node_id,
+                        Box::new(Bitcrusher::new(self.sample_rate, 12, 4, 0.5)),
+                    );
+                }
+            }
             "arpeggiator_generator" => {
                 for voice in &mut self.voices {
-                    let mut arp = ArpeggiatorGenerator::new();
-                    arp.create_test_pattern(self.sample_rate, 0.225);
+                    let arp = ArpeggiatorGenerator::with_config(
+                        self.sample_rate,
+                        ArpeggiatorConfig::default(),
+                    );
                     voice.graph.add_node_with_id(node_id, Box::new(arp));
                 }
             }
             // Effect nodes exist in the effect stack.
             "chorus" | "delay" | "freeverb" | "convolver" | "limiter" | "compressor"
-            | "saturation" | "bitcrusher" => {}
+            | "exciter" | "multiband" | "parallel" | "equalizer" => {}
             other => log_console(&format!("Skipping unsupported node type {}", other)),
         }
         Ok(())
@@ -2752,71 +8544,99 @@ impl AudioEngine {
         voice_layout: &PatchVoiceLayout,
     ) -> Result<(), JsValue> {
         for connection in &voice_layout.connections {
-            let to_port = port_id_from_u32(connection.target).map_err(|e| JsValue::from_str(&e))?;
-            let base_modulation_type = modulation_type_from_i32(connection.modulation_type)
-                .map_err(|e| JsValue::from_str(&e))?;
-            let modulation_type = Some(WasmModulationType::from(base_modulation_type));
-            let modulation_transform =
-                modulation_transform_from_i32(connection.modulation_transform)
-                    .map_err(|e| JsValue::from_str(&e))?;
-
-            // Determine the appropriate output port on the source node.
-            let from_port = self
-                .voices
-                .get(0)
-                .and_then(|voice| {
-                    NodeId::from_string(&connection.from_id)
-                        .ok()
-                        .and_then(|node_id| voice.graph.get_node(node_id))
-                        .map(|node| {
-                            let ports = node.get_ports();
-                            ports
-                                .iter()
-                                .find(|(_, &is_output)| is_output)
-                                .map(|(p, _)| *p)
-                                .unwrap_or(PortId::AudioOutput0)
-                        })
-                })
-                .unwrap_or(PortId::AudioOutput0);
+            self.connect_patch_connection(connection)?;
+        }
+        Ok(())
+    }
 
-            let mut from_id = connection.from_id.clone();
-            let mut effective_from_port = from_port;
+    fn connect_patch_connection(&mut self, connection: &PatchConnection) -> Result<(), JsValue> {
+        let to_port = port_id_from_u32(connection.target).map_err(|e| JsValue::from_str(&e))?;
+        let base_modulation_type = modulation_type_from_i32(connection.modulation_type)
+            .map_err(|e| JsValue::from_str(&e))?;
+        let modulation_type = Some(WasmModulationType::from(base_modulation_type));
+        let modulation_transform = modulation_transform_from_i32(connection.modulation_transform)
+            .map_err(|e| JsValue::from_str(&e))?;
 
-            if to_port == PortId::GlobalFrequency {
-                if let Some(glide_node) = self.voices.get(0).and_then(|v| v.graph.global_glide_node)
-                {
-                    from_id = glide_node.to_string();
-                    effective_from_port = PortId::AudioOutput0;
-                }
+        // Determine the appropriate output port on the source node.
+        let from_port = self
+            .voices
+            .get(0)
+            .and_then(|voice| {
+                NodeId::from_string(&connection.from_id)
+                    .ok()
+                    .and_then(|node_id| voice.graph.get_node(node_id))
+                    .map(|node| {
+                        let ports = node.get_ports();
+                        // Prefer the left/main output so stereo pairing applies.
+                        if ports.get(&PortId::AudioOutput0) == Some(&true) {
+                            return PortId::AudioOutput0;
+                        }
+                        ports
+                            .iter()
+                            .find(|(_, &is_output)| is_output)
+                            .map(|(p, _)| *p)
+                            .unwrap_or(PortId::AudioOutput0)
+                    })
+            })
+            .unwrap_or(PortId::AudioOutput0);
+
+        let mut from_id = connection.from_id.clone();
+        let mut effective_from_port = from_port;
+
+        if to_port == PortId::GlobalFrequency {
+            if let Some(glide_node) = self.voices.get(0).and_then(|v| v.graph.global_glide_node) {
+                from_id = glide_node.to_string();
+                effective_from_port = PortId::AudioOutput0;
             }
+        }
 
-            self.connect_nodes(
-                &from_id,
-                effective_from_port,
-                &connection.to_id,
-                to_port,
-                connection.amount,
-                modulation_type,
-                modulation_transform,
-            )?;
+        // Patches load what they can: a connection the graph refuses (into a
+        // skipped node type, or closing a loop) is dropped.
+        if let Err(e) = self.connect_nodes(
+            &from_id,
+            effective_from_port,
+            &connection.to_id,
+            to_port,
+            connection.amount,
+            modulation_type,
+            modulation_transform,
+        ) {
+            log_console(&format!("Skipping patch connection: {:?}", e));
+        }
+        Ok(())
+    }
+
+    fn disconnect_patch_connection(&mut self, connection: &PatchConnection) -> Result<(), JsValue> {
+        let to_port = port_id_from_u32(connection.target).map_err(|e| JsValue::from_str(&e))?;
+        let from_node = parse_node_id(&connection.from_id).map_err(|e| JsValue::from_str(&e))?;
+        let to_node = parse_node_id(&connection.to_id).map_err(|e| JsValue::from_str(&e))?;
+        for voice in &mut self.voices {
+            // Frequency connections were routed through the glide.
+            let from_node = match voice.graph.global_glide_node {
+                Some(glide_node) if to_port == PortId::GlobalFrequency => glide_node,
+                _ => from_node,
+            };
+            voice
+                .graph
+                .remove_specific_connection(from_node, to_node, to_port);
         }
         Ok(())
     }
 
     fn apply_patch_states(
         &mut self,
-        patch: &PatchFile,
+        state: &SynthState,
         canonical_voice: &PatchVoiceLayout,
     ) -> Result<(), JsValue> {
-        for (id, state) in &patch.synth_state.oscillators {
+        for (id, state) in &state.oscillators {
             self.update_oscillator(id, state)?;
         }
 
-        for (id, state) in &patch.synth_state.wavetable_oscillators {
+        for (id, state) in &state.wavetable_oscillators {
             self.update_wavetable_oscillator(id, state)?;
         }
 
-        for (id, config) in &patch.synth_state.envelopes {
+        for (id, config) in &state.envelopes {
             self.update_envelope(
                 id,
                 config.attack,
@@ -2828,9 +8648,19 @@ impl AudioEngine {
                 config.release_curve,
                 config.active,
             )?;
+            self.set_envelope_gate_threshold(id, config.gate_threshold)?;
+            self.set_envelope_time_scaling(
+                id,
+                config.velocity_attack,
+                config.velocity_decay,
+                config.key_decay,
+                config.key_release,
+            )?;
+            self.set_release_velocity_amount(id, config.velocity_release)?;
+            self.set_envelope_drone(id, config.drone)?;
         }
 
-        for state in patch.synth_state.lfos.values() {
+        for state in state.lfos.values() {
             let params = WasmLfoUpdateParams::new(
                 state.lfo_id.clone(),
                 state.frequency,
@@ -2848,7 +8678,7 @@ impl AudioEngine {
             self.update_lfos(params);
         }
 
-        for filter in patch.synth_state.filters.values() {
+        for filter in state.filters.values() {
             let filter_type =
                 filter_type_from_i32(filter.filter_type).map_err(|e| JsValue::from_str(&e))?;
             self.update_filters(
@@ -2863,9 +8693,110 @@ impl AudioEngine {
                 filter_type,
                 filter.filter_slope,
             )?;
+            self.update_filter_auto_gain(&filter.id, filter.auto_gain)?;
+            self.update_filter_cutoff_mod_octaves(&filter.id, filter.cutoff_mod_octaves)?;
+            self.update_filter_double_precision(&filter.id, filter.double_precision_feedback)?;
+        }
+
+        for dual in state.dual_filters.values() {
+            self.update_dual_filter(
+                &dual.id,
+                dual.active,
+                dual.routing,
+                dual.balance,
+                dual.spacing,
+                dual.cutoff,
+                dual.key_tracking,
+            )?;
+            for (slot, state) in [&dual.filter_a, &dual.filter_b].into_iter().enumerate() {
+                let filter_type =
+                    filter_type_from_i32(state.filter_type).map_err(|e| JsValue::from_str(&e))?;
+                self.update_dual_filter_slot(
+                    &dual.id,
+                    slot,
+                    filter_type,
+                    state.filter_slope,
+                    state.resonance,
+                    state.gain,
+                )?;
+            }
+        }
+
+        for formant in state.formant_filters.values() {
+            self.update_formant_filter(
+                &formant.id,
+                formant.active,
+                formant.vowel,
+                formant.gender,
+                formant.resonance,
+                formant.mix,
+            )?;
+        }
+
+        for tool in state.gate_tools.values() {
+            self.update_gate_tool(
+                &tool.id,
+                tool.active,
+                tool.length_ms,
+                tool.delay_ms,
+                tool.division,
+                tool.multiplication,
+                tool.probability,
+            )?;
+        }
+
+        for clock in state.clocks.values() {
+            self.update_clock(
+                &clock.id,
+                clock.active,
+                clock.division_beats,
+                clock.pulse_width,
+            )?;
+        }
+
+        for chance in state.chances.values() {
+            self.update_chance(
+                &chance.id,
+                chance.active,
+                chance.probability,
+                chance.mode,
+                chance.attenuation,
+                chance.seed,
+                chance.randomness,
+            )?;
+        }
+
+        for operator in state.fm_operators.values() {
+            self.apply_fm_operator_update(&operator.id, operator.config().into())?;
+        }
+
+        for mseg in state.msegs.values() {
+            let config = serde_json::to_string(&mseg.config)
+                .map_err(|e| JsValue::from_str(&format!("Invalid MSEG config: {}", e)))?;
+            self.update_mseg(&mseg.id, &config)?;
+        }
+
+        if let Some(transport) = &state.transport {
+            self.set_transport_tempo(transport.tempo_bpm);
+            self.set_clock_source(transport.clock_source);
+            self.set_clock_pulses_per_beat(transport.pulses_per_beat);
+        }
+
+        if let Some(groove) = &state.groove {
+            self.set_groove(groove.swing_percent, &groove.offsets);
+        }
+        for groove in state.grooves.values() {
+            match groove.id.parse::<usize>() {
+                Ok(node_id) => {
+                    self.set_effect_groove(node_id, Some(groove.swing_percent), &groove.offsets)?
+                }
+                Err(_) => {
+                    self.set_node_groove(&groove.id, Some(groove.swing_percent), &groove.offsets)?
+                }
+            }
         }
 
-        for sampler in patch.synth_state.samplers.values() {
+        for sampler in state.samplers.values() {
             self.update_sampler(
                 &sampler.id,
                 sampler.frequency,
@@ -2873,17 +8804,21 @@ impl AudioEngine {
                 sampler.loop_mode,
                 sampler.loop_start,
                 sampler.loop_end,
+                sampler.loop_crossfade,
                 sampler.root_note,
                 sampler.trigger_mode,
                 sampler.active,
+                sampler.playback_mode,
+                sampler.grain_size,
+                sampler.stretch_quality,
             )?;
         }
 
-        for glide in patch.synth_state.glides.values() {
+        for glide in state.glides.values() {
             self.update_glide(&glide.glide_id, glide.resolved_time(), glide.active)?;
         }
 
-        for chorus in patch.synth_state.choruses.values() {
+        for chorus in state.choruses.values() {
             if let Ok(node_id) = chorus.id.parse::<usize>() {
                 self.update_chorus(
                     node_id,
@@ -2899,7 +8834,7 @@ impl AudioEngine {
             }
         }
 
-        for delay in patch.synth_state.delays.values() {
+        for delay in state.delays.values() {
             if let Ok(node_id) = delay.id.parse::<usize>() {
                 self.update_delay(
                     node_id,
@@ -2908,10 +8843,16 @@ impl AudioEngine {
                     delay.wet_mix,
                     delay.active,
                 );
+                if let Err(err) = self.update_delay_ducking(node_id, delay.ducking) {
+                    log_console(&format!("Failed to apply delay ducking: {:?}", err));
+                }
+                if let Err(err) = self.update_delay_sync(node_id, delay.sync_beats) {
+                    log_console(&format!("Failed to apply delay sync: {:?}", err));
+                }
             }
         }
 
-        for reverb in patch.synth_state.reverbs.values() {
+        for reverb in state.reverbs.values() {
             if let Ok(node_id) = reverb.id.parse::<usize>() {
                 self.update_reverb(
                     node_id,
@@ -2922,10 +8863,13 @@ impl AudioEngine {
                     reverb.dry,
                     reverb.width,
                 );
+                if let Err(err) = self.update_reverb_gate(node_id, reverb.gate) {
+                    log_console(&format!("Failed to apply reverb gate: {:?}", err));
+                }
             }
         }
 
-        for compressor in patch.synth_state.compressors.values() {
+        for compressor in state.compressors.values() {
             if let Ok(node_id) = compressor.id.parse::<usize>() {
                 self.update_compressor(
                     node_id,
@@ -2940,7 +8884,7 @@ impl AudioEngine {
             }
         }
 
-        for saturation in patch.synth_state.saturations.values() {
+        for saturation in state.saturations.values() {
             if let Ok(node_id) = saturation.id.parse::<usize>() {
                 self.update_saturation(
                     node_id,
@@ -2948,10 +8892,31 @@ impl AudioEngine {
                     saturation.mix,
                     saturation.active,
                 );
+                self.update_saturation_tone(
+                    node_id,
+                    saturation.character,
+                    saturation.pre_tilt,
+                    saturation.post_tilt,
+                    saturation.auto_gain,
+                )?;
+            } else {
+                self.update_voice_saturation(
+                    &saturation.id,
+                    saturation.drive,
+                    saturation.mix,
+                    saturation.active,
+                )?;
+                self.update_voice_saturation_tone(
+                    &saturation.id,
+                    saturation.character,
+                    saturation.pre_tilt,
+                    saturation.post_tilt,
+                    saturation.auto_gain,
+                )?;
             }
         }
 
-        for bitcrusher in patch.synth_state.bitcrushers.values() {
+        for bitcrusher in state.bitcrushers.values() {
             if let Ok(node_id) = bitcrusher.id.parse::<usize>() {
                 self.update_bitcrusher(
                     node_id,
@@ -2960,16 +8925,215 @@ impl AudioEngine {
                     bitcrusher.mix,
                     bitcrusher.active,
                 );
+            } else {
+                self.update_voice_bitcrusher(
+                    &bitcrusher.id,
+                    bitcrusher.bits,
+                    bitcrusher.downsample_factor,
+                    bitcrusher.mix,
+                    bitcrusher.active,
+                )?;
             }
         }
 
-        for convolver in patch.synth_state.convolvers.values() {
+        for convolver in state.convolvers.values() {
             if let Ok(node_id) = convolver.id.parse::<usize>() {
                 self.update_convolver(node_id, convolver.wet_mix, convolver.active);
             }
         }
 
-        if let Some(noise_state) = &patch.synth_state.noise {
+        for enhancer in state.stereo_enhancers.values() {
+            if let Ok(node_id) = enhancer.id.parse::<usize>() {
+                self.update_stereo_enhancer(
+                    node_id,
+                    enhancer.active,
+                    enhancer.delay_left_ms,
+                    enhancer.delay_right_ms,
+                    enhancer.width,
+                    enhancer.comb_compensation,
+                    enhancer.mix,
+                );
+            } else {
+                self.update_voice_stereo_enhancer(
+                    &enhancer.id,
+                    enhancer.active,
+                    enhancer.delay_left_ms,
+                    enhancer.delay_right_ms,
+                    enhancer.width,
+                    enhancer.comb_compensation,
+                    enhancer.mix,
+                )?;
+            }
+        }
+
+        for binaural in state.binaurals.values() {
+            if let Ok(node_id) = binaural.id.parse::<usize>() {
+                self.update_binaural(
+                    node_id,
+                    binaural.active,
+                    binaural.azimuth,
+                    binaural.elevation,
+                    binaural.mix,
+                );
+            } else {
+                self.update_voice_binaural(
+                    &binaural.id,
+                    binaural.active,
+                    binaural.azimuth,
+                    binaural.elevation,
+                    binaural.mix,
+                )?;
+            }
+        }
+
+        for wah in state.auto_wahs.values() {
+            if let Ok(node_id) = wah.id.parse::<usize>() {
+                self.update_auto_wah(
+                    node_id,
+                    wah.active,
+                    wah.sensitivity,
+                    wah.frequency,
+                    wah.range,
+                    wah.q,
+                    wah.direction,
+                    wah.mix,
+                );
+            } else {
+                self.update_voice_auto_wah(
+                    &wah.id,
+                    wah.active,
+                    wah.sensitivity,
+                    wah.frequency,
+                    wah.range,
+                    wah.q,
+                    wah.direction,
+                    wah.mix,
+                )?;
+            }
+        }
+
+        for exciter in state.exciters.values() {
+            if let Ok(node_id) = exciter.id.parse::<usize>() {
+                self.update_exciter(
+                    node_id,
+                    exciter.active,
+                    exciter.frequency,
+                    exciter.amount,
+                    exciter.mix,
+                );
+            }
+        }
+
+        for multiband in state.multibands.values() {
+            if let Ok(node_id) = multiband.id.parse::<usize>() {
+                self.update_multiband(node_id, multiband.active, multiband.crossovers.clone())?;
+                for (band, settings) in multiband.bands.iter().enumerate() {
+                    self.update_multiband_band(
+                        node_id,
+                        band,
+                        settings.gain,
+                        settings.muted,
+                        settings.soloed,
+                    )?;
+                }
+                for member in &multiband.members {
+                    if let Ok(effect_id) = member.effect_id.parse::<usize>() {
+                        self.set_effect_band(effect_id, Some(node_id), member.band)?;
+                    }
+                }
+            }
+        }
+
+        for parallel in state.parallel_chains.values() {
+            if let Ok(node_id) = parallel.id.parse::<usize>() {
+                self.update_parallel(node_id, parallel.active, parallel.blend)?;
+                for member in &parallel.members {
+                    if let Ok(effect_id) = member.effect_id.parse::<usize>() {
+                        self.set_effect_band(effect_id, Some(node_id), member.band)?;
+                    }
+                }
+            }
+        }
+
+        for eq in state.equalizers.values() {
+            if let Ok(node_id) = eq.id.parse::<usize>() {
+                self.update_eq(node_id, eq.active, eq.bands.len())?;
+                for (band, settings) in eq.bands.iter().enumerate() {
+                    self.update_eq_band(
+                        node_id,
+                        band,
+                        settings.band_type,
+                        settings.frequency,
+                        settings.q,
+                        settings.gain_db,
+                    )?;
+                    self.set_eq_band_dynamics(node_id, band, settings.dynamics)?;
+                }
+            }
+        }
+
+        for (index, lfo) in state.effect_lfos.iter().enumerate() {
+            self.set_effect_lfo(
+                index,
+                lfo.waveform,
+                lfo.rate_hz,
+                lfo.sync_beats.unwrap_or(0.0),
+            )?;
+            for route in &lfo.routes {
+                if let Ok(node_id) = route.effect_id.parse::<usize>() {
+                    self.set_effect_lfo_route(index, node_id, &route.parameter, route.depth)?;
+                }
+            }
+        }
+
+        for gate in state.noise_gates.values() {
+            if let Ok(node_id) = gate.id.parse::<usize>() {
+                self.update_noise_gate(
+                    node_id,
+                    gate.active,
+                    gate.threshold_db,
+                    gate.hysteresis_db,
+                    gate.attack_ms,
+                    gate.hold_ms,
+                    gate.release_ms,
+                    gate.range_db,
+                );
+            } else {
+                self.update_voice_noise_gate(
+                    &gate.id,
+                    gate.active,
+                    gate.threshold_db,
+                    gate.hysteresis_db,
+                    gate.attack_ms,
+                    gate.hold_ms,
+                    gate.release_ms,
+                    gate.range_db,
+                )?;
+            }
+        }
+
+        for sidechain in state.sidechains.values() {
+            if let Ok(node_id) = sidechain.id.parse::<usize>() {
+                let result = port_id_from_u32(sidechain.source_port)
+                    .map_err(|e| JsValue::from_str(&e))
+                    .and_then(|port| {
+                        self.set_effect_sidechain(node_id, Some(sidechain.source_id.clone()), port)
+                    });
+                if let Err(err) = result {
+                    log_console(&format!("Failed to apply sidechain: {:?}", err));
+                }
+            }
+        }
+
+        for routing in state.effect_routings.values() {
+            if let Ok(node_id) = routing.id.parse::<usize>() {
+                if let Err(err) = self.set_effect_routing(node_id, routing.routing) {
+                    log_console(&format!("Failed to apply effect routing: {:?}", err));
+                }
+            }
+        }
+
+        if let Some(noise_state) = &state.noise {
             if let Some(noise_id) = find_node_id(canonical_voice, "noise") {
                 let params = NoiseUpdateParams::new(
                     match noise_state.noise_type {
@@ -2985,7 +9149,7 @@ impl AudioEngine {
             }
         }
 
-        if let Some(velocity_state) = &patch.synth_state.velocity {
+        if let Some(velocity_state) = &state.velocity {
             if let Some(velocity_id) = find_node_id(canonical_voice, "global_velocity") {
                 self.update_velocity(
                     &velocity_id,
@@ -2995,6 +9159,33 @@ impl AudioEngine {
             }
         }
 
+        if let Some(tuning) = &state.tuning {
+            if !self.locks.is_locked(LockableParameter::MasterTuning) {
+                self.set_master_tuning(tuning.transpose, tuning.fine)?;
+            }
+            if !self.locks.is_locked(LockableParameter::VoiceDetune) {
+                for (voice_index, &cents) in tuning.voice_detune.iter().enumerate() {
+                    if voice_index < self.voices.len() {
+                        self.set_voice_detune(voice_index, cents)?;
+                    }
+                }
+            }
+            self.set_analog_spread(tuning.analog_spread);
+        }
+
+        if let Some(macros) = &state.macros {
+            self.apply_macro_state(macros)?;
+        }
+
+        self.set_polyphony_compensation(
+            state
+                .headroom
+                .as_ref()
+                .is_some_and(|headroom| headroom.polyphony_compensation),
+        );
+
+        self.restore_locked_parameters()?;
+
         Ok(())
     }
 
@@ -1,4 +1,7 @@
-use crate::{audio_engine::WasmModulationType, graph::ModulationTransformation, traits::PortId};
+use crate::{
+    audio_engine::WasmModulationType, graph::ModulationTransformation,
+    nodes::DEFAULT_RELEASE_VELOCITY, traits::PortId,
+};
 
 #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
 use crate::audio_engine::AudioEngine;
@@ -11,6 +14,8 @@ const DEFAULT_FREQUENCY: f32 = 440.0;
 const DEFAULT_GAIN: f32 = 1.0;
 const DEFAULT_GATE: f32 = 0.0;
 const DEFAULT_VELOCITY: f32 = 0.0;
+const DEFAULT_PRESSURE: f32 = 0.0;
+const DEFAULT_TIMBRE: f32 = 0.0;
 
 /// Frame of automation data that can be shared between wasm and native hosts.
 #[cfg_attr(all(feature = "wasm", target_arch = "wasm32"), wasm_bindgen)]
@@ -25,6 +30,13 @@ pub struct AutomationFrame {
     frequencies: Vec<f32>, // Per-voice, per-sample
     velocities: Vec<f32>,
     gains: Vec<f32>,
+    /// End-of-block targets; the engine ramps from `velocities`/`gains` towards these.
+    velocity_ends: Vec<f32>,
+    gain_ends: Vec<f32>,
+    pressures: Vec<f32>,
+    timbres: Vec<f32>,
+    /// Velocity of each voice's last note-off.
+    release_velocities: Vec<f32>,
     macro_buffers: Vec<f32>,
 }
 
@@ -45,6 +57,11 @@ impl AutomationFrame {
             frequencies: frequency_buffers,
             velocities: vec![DEFAULT_VELOCITY; num_voices],
             gains: vec![DEFAULT_GAIN; num_voices],
+            velocity_ends: vec![DEFAULT_VELOCITY; num_voices],
+            gain_ends: vec![DEFAULT_GAIN; num_voices],
+            pressures: vec![DEFAULT_PRESSURE; num_voices],
+            timbres: vec![DEFAULT_TIMBRE; num_voices],
+            release_velocities: vec![DEFAULT_RELEASE_VELOCITY; num_voices],
             macro_buffers,
         }
     }
@@ -85,6 +102,26 @@ impl AutomationFrame {
         &self.gains
     }
 
+    pub fn velocity_ends(&self) -> &[f32] {
+        &self.velocity_ends
+    }
+
+    pub fn gain_ends(&self) -> &[f32] {
+        &self.gain_ends
+    }
+
+    pub fn pressures(&self) -> &[f32] {
+        &self.pressures
+    }
+
+    pub fn timbres(&self) -> &[f32] {
+        &self.timbres
+    }
+
+    pub fn release_velocities(&self) -> &[f32] {
+        &self.release_velocities
+    }
+
     pub fn macro_buffers(&self) -> &[f32] {
         &self.macro_buffers
     }
@@ -105,6 +142,18 @@ impl AutomationFrame {
         &mut self.gains
     }
 
+    pub fn pressures_mut(&mut self) -> &mut [f32] {
+        &mut self.pressures
+    }
+
+    pub fn timbres_mut(&mut self) -> &mut [f32] {
+        &mut self.timbres
+    }
+
+    pub fn release_velocities_mut(&mut self) -> &mut [f32] {
+        &mut self.release_velocities
+    }
+
     pub fn macro_buffers_mut(&mut self) -> &mut [f32] {
         &mut self.macro_buffers
     }
@@ -124,6 +173,67 @@ impl AutomationFrame {
         self.set_frequency_value(voice_index, frequency);
         self.velocities[voice_index] = velocity;
         self.gains[voice_index] = gain;
+        self.velocity_ends[voice_index] = velocity;
+        self.gain_ends[voice_index] = gain;
+    }
+
+    /// Writes an exponential (constant pitch-rate) ramp from `start` to `end` Hz into the
+    /// voice's frequency buffer, so pitch bends glide across the block instead of stepping.
+    pub fn set_frequency_ramp(&mut self, voice_index: usize, start: f32, end: f32) {
+        if voice_index >= self.num_voices {
+            return;
+        }
+        let begin = self.frequency_offset(voice_index);
+        let len = self.frequency_buffer_len;
+        if begin + len > self.frequencies.len() {
+            return;
+        }
+        let exponential = start > 0.0 && end > 0.0;
+        let ratio = if exponential { end / start } else { 0.0 };
+        for (i, slot) in self.frequencies[begin..begin + len].iter_mut().enumerate() {
+            let t = i as f32 / len as f32;
+            *slot = if exponential {
+                start * ratio.powf(t)
+            } else {
+                start + (end - start) * t
+            };
+        }
+    }
+
+    /// Ramps the voice velocity from `start` to `end` across the block.
+    pub fn set_velocity_ramp(&mut self, voice_index: usize, start: f32, end: f32) {
+        if voice_index >= self.num_voices {
+            return;
+        }
+        self.velocities[voice_index] = start;
+        self.velocity_ends[voice_index] = end;
+    }
+
+    /// Ramps the voice output gain from `start` to `end` across the block.
+    pub fn set_gain_ramp(&mut self, voice_index: usize, start: f32, end: f32) {
+        if voice_index >= self.num_voices {
+            return;
+        }
+        self.gains[voice_index] = start;
+        self.gain_ends[voice_index] = end;
+    }
+
+    /// Sets the per-voice note-expression values (pressure / timbre).
+    pub fn set_voice_expression(&mut self, voice_index: usize, pressure: f32, timbre: f32) {
+        if voice_index >= self.num_voices {
+            return;
+        }
+        self.pressures[voice_index] = pressure;
+        self.timbres[voice_index] = timbre;
+    }
+
+    /// Sets the velocity (0..1) of the voice's note-off. Send it with the
+    /// block the gate closes in; it holds until the next note-off.
+    pub fn set_release_velocity(&mut self, voice_index: usize, release_velocity: f32) {
+        if voice_index >= self.num_voices {
+            return;
+        }
+        self.release_velocities[voice_index] = release_velocity.clamp(0.0, 1.0);
     }
 
     pub fn set_macro_value(&mut self, voice_index: usize, macro_index: usize, value: f32) {
@@ -141,6 +251,27 @@ impl AutomationFrame {
         }
     }
 
+    /// Ramps a macro from `start` on the first sample of the block to `end` on
+    /// the last, so macro sweeps stay sample-accurate without host-side buffers.
+    pub fn set_macro_ramp(&mut self, voice_index: usize, macro_index: usize, start: f32, end: f32) {
+        if voice_index >= self.num_voices || macro_index >= self.macro_count {
+            return;
+        }
+        let begin = self.macro_offset(voice_index, macro_index);
+        let len = self.macro_buffer_len;
+        if len == 0 || begin + len > self.macro_buffers.len() {
+            return;
+        }
+        let step = (end - start) / (len - 1).max(1) as f32;
+        for (i, slot) in self.macro_buffers[begin..begin + len]
+            .iter_mut()
+            .enumerate()
+        {
+            *slot = start + step * i as f32;
+        }
+        self.macro_buffers[begin + len - 1] = end;
+    }
+
     pub fn macro_slice(&self, voice_index: usize, macro_index: usize) -> &[f32] {
         let start = self.macro_offset(voice_index, macro_index);
         let end = start + self.macro_buffer_len;
@@ -273,6 +404,11 @@ impl AutomationFrame {
         self.frequencies.fill(DEFAULT_FREQUENCY);
         self.velocities.fill(DEFAULT_VELOCITY);
         self.gains.fill(DEFAULT_GAIN);
+        self.velocity_ends.fill(DEFAULT_VELOCITY);
+        self.gain_ends.fill(DEFAULT_GAIN);
+        self.pressures.fill(DEFAULT_PRESSURE);
+        self.timbres.fill(DEFAULT_TIMBRE);
+        self.release_velocities.fill(DEFAULT_RELEASE_VELOCITY);
         self.macro_buffers.fill(0.0);
     }
 
@@ -376,6 +512,11 @@ impl AutomationFrame {
             let freq_key = format!("frequency_{}", voice);
             let gain_key = format!("gain_{}", voice);
             let velocity_key = format!("velocity_{}", voice);
+            let gain_end_key = format!("gain_end_{}", voice);
+            let velocity_end_key = format!("velocity_end_{}", voice);
+            let pressure_key = format!("pressure_{}", voice);
+            let timbre_key = format!("timbre_{}", voice);
+            let release_velocity_key = format!("release_velocity_{}", voice);
 
             let gate_values =
                 self.read_parameter_buffer(parameters, &gate_key, gate_buffer_len, DEFAULT_GATE)?;
@@ -388,16 +529,38 @@ impl AutomationFrame {
             let gain = self.read_parameter_scalar(parameters, &gain_key, DEFAULT_GAIN)?;
             let velocity =
                 self.read_parameter_scalar(parameters, &velocity_key, DEFAULT_VELOCITY)?;
+            let gain_end = self.read_parameter_scalar(parameters, &gain_end_key, gain)?;
+            let velocity_end =
+                self.read_parameter_scalar(parameters, &velocity_end_key, velocity)?;
+            let pressure =
+                self.read_parameter_scalar(parameters, &pressure_key, DEFAULT_PRESSURE)?;
+            let timbre = self.read_parameter_scalar(parameters, &timbre_key, DEFAULT_TIMBRE)?;
+            let release_velocity = self.read_parameter_scalar(
+                parameters,
+                &release_velocity_key,
+                DEFAULT_RELEASE_VELOCITY,
+            )?;
 
             self.set_gate_buffer(voice, &gate_values);
             self.set_frequency_buffer(voice, &frequency_values);
             self.velocities[voice] = velocity;
             self.gains[voice] = gain;
+            self.velocity_ends[voice] = velocity_end;
+            self.gain_ends[voice] = gain_end;
+            self.pressures[voice] = pressure;
+            self.timbres[voice] = timbre;
+            self.release_velocities[voice] = release_velocity;
 
             for macro_index in 0..self.macro_count {
                 let macro_key = format!("macro_{}_{}", voice, macro_index);
+                let macro_end_key = format!("macro_end_{}_{}", voice, macro_index);
                 let value = self.read_parameter_scalar(parameters, &macro_key, 0.0)?;
-                self.set_macro_value(voice, macro_index, value);
+                let end = self.read_parameter_scalar(parameters, &macro_end_key, value)?;
+                if end != value {
+                    self.set_macro_ramp(voice, macro_index, value, end);
+                } else {
+                    self.set_macro_value(voice, macro_index, value);
+                }
             }
         }
 
@@ -741,6 +904,39 @@ mod tests {
         assert_eq!(frame.gains()[1], DEFAULT_GAIN);
     }
 
+    #[test]
+    fn frequency_ramp_is_exponential_across_block() {
+        let mut frame = AutomationFrame::with_dimensions(1, 4, 4);
+        frame.set_frequency_ramp(0, 220.0, 880.0);
+        let slice = frame.frequency_slice(0);
+        assert!((slice[0] - 220.0).abs() < 1e-3);
+        assert!((slice[2] - 440.0).abs() < 1e-2);
+
+        frame.set_gain_ramp(0, 1.0, 0.5);
+        assert_eq!(frame.gains()[0], 1.0);
+        assert_eq!(frame.gain_ends()[0], 0.5);
+    }
+
+    #[test]
+    fn macro_ramp_hits_both_endpoints() {
+        let mut frame = AutomationFrame::with_dimensions(1, 4, 5);
+        frame.set_macro_ramp(0, 1, 0.0, 1.0);
+        assert_eq!(frame.macro_slice(0, 1), &[0.0, 0.25, 0.5, 0.75, 1.0]);
+        assert!(frame.macro_slice(0, 0).iter().all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn voice_expression_defaults_and_updates() {
+        let mut frame = AutomationFrame::with_dimensions(2, 4, 8);
+        assert!(frame.pressures().iter().all(|&v| v == DEFAULT_PRESSURE));
+        assert!(frame.timbres().iter().all(|&v| v == DEFAULT_TIMBRE));
+
+        frame.set_voice_expression(1, 0.6, 0.3);
+        frame.set_voice_expression(5, 1.0, 1.0);
+        assert_eq!(frame.pressures(), &[DEFAULT_PRESSURE, 0.6]);
+        assert_eq!(frame.timbres(), &[DEFAULT_TIMBRE, 0.3]);
+    }
+
     #[derive(Default)]
     struct MockEngine {
         connections: Vec<(
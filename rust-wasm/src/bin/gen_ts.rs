@@ -0,0 +1,21 @@
+//! Writes TypeScript definitions for the engine's JSON APIs.
+//!
+//! Usage: `cargo run --features ts-bindings --bin gen_ts [output.d.ts]`; without
+//! a path the definitions go to stdout.
+
+use std::env;
+use std::fs;
+
+use audio_processor::audio_engine::typescript::typescript_definitions;
+
+fn main() -> anyhow::Result<()> {
+    let definitions = typescript_definitions();
+    match env::args().nth(1) {
+        Some(path) => {
+            fs::write(&path, definitions)?;
+            eprintln!("Wrote {path}");
+        }
+        None => print!("{definitions}"),
+    }
+    Ok(())
+}
@@ -8,6 +8,11 @@ mod audio_renderer;
 mod composition;
 #[path = "native_demo/cpal_host.rs"]
 mod cpal_host;
+#[path = "native_demo/midi_player.rs"]
+mod midi_player;
+#[cfg(feature = "osc")]
+#[path = "native_demo/osc_player.rs"]
+mod osc_player;
 
 use std::env;
 use std::time::Duration;
@@ -15,6 +20,7 @@ use std::time::Duration;
 use audio_renderer::AudioRenderer;
 use composition::Composition;
 use cpal_host::{AudioHost, AudioHostOptions};
+use midi_player::MidiPlayer;
 
 fn main() -> anyhow::Result<()> {
     let args: Vec<String> = env::args().collect();
@@ -36,7 +42,8 @@ fn main() -> anyhow::Result<()> {
             println!("  - {} ({}, id: {:?})", host.name, device_status, host.id);
         }
         println!(
-            "\nUsage: {} [--host <host_name>] [--buffer-size <frames>]",
+            "\nUsage: {} [--host <host_name>] [--buffer-size <frames>] \
+             [--midi <file.mid> --patch <patch.json>] [--osc <port> --patch <patch.json>]",
             args[0]
         );
         println!("Example: {} --host ALSA --buffer-size 128", args[0]);
@@ -46,6 +53,9 @@ fn main() -> anyhow::Result<()> {
     // Parse CLI arguments
     let mut preferred_host_name: Option<String> = None;
     let mut requested_buffer_size: Option<usize> = None;
+    let mut midi_path: Option<String> = None;
+    let mut patch_path: Option<String> = None;
+    let mut osc_port: Option<u16> = None;
 
     let mut index = 1;
     while index < args.len() {
@@ -72,6 +82,30 @@ fn main() -> anyhow::Result<()> {
                 requested_buffer_size = Some(value);
                 index += 2;
             }
+            "--midi" => {
+                if index + 1 >= args.len() {
+                    return Err(anyhow::anyhow!("Expected a MIDI file after --midi"));
+                }
+                midi_path = Some(args[index + 1].clone());
+                index += 2;
+            }
+            "--patch" => {
+                if index + 1 >= args.len() {
+                    return Err(anyhow::anyhow!("Expected a patch file after --patch"));
+                }
+                patch_path = Some(args[index + 1].clone());
+                index += 2;
+            }
+            "--osc" => {
+                if index + 1 >= args.len() {
+                    return Err(anyhow::anyhow!("Expected a UDP port after --osc"));
+                }
+                let port = args[index + 1].parse::<u16>().map_err(|err| {
+                    anyhow::anyhow!("Invalid OSC port '{}': {}", args[index + 1], err)
+                })?;
+                osc_port = Some(port);
+                index += 2;
+            }
             "--list-hosts" => {
                 index += 1;
             }
@@ -91,13 +125,25 @@ fn main() -> anyhow::Result<()> {
         println!("Requested buffer size: {} frames\n", size);
     }
 
-    println!("=== CREATING COMPOSITION ===");
-
     let options = AudioHostOptions {
         preferred_host: preferred_host_name,
         buffer_size: requested_buffer_size,
     };
 
+    if let Some(midi_path) = midi_path {
+        let patch_path = patch_path
+            .ok_or_else(|| anyhow::anyhow!("--midi needs a patch to play: --patch <file>"))?;
+        return play_midi(&midi_path, &patch_path, options);
+    }
+
+    if let Some(port) = osc_port {
+        let patch_path = patch_path
+            .ok_or_else(|| anyhow::anyhow!("--osc needs a patch to play: --patch <file>"))?;
+        return play_osc(port, &patch_path, options);
+    }
+
+    println!("=== CREATING COMPOSITION ===");
+
     let host = AudioHost::with_options(
         |sample_rate, block_size| {
             let composition =
@@ -135,6 +181,58 @@ fn main() -> anyhow::Result<()> {
     }
 }
 
+fn play_midi(midi_path: &str, patch_path: &str, options: AudioHostOptions) -> anyhow::Result<()> {
+    let midi_bytes = std::fs::read(midi_path)?;
+    let patch_json = std::fs::read_to_string(patch_path)?;
+    println!("=== RENDERING {} WITH {} ===", midi_path, patch_path);
+
+    let mut duration = 0.0;
+    let _host = AudioHost::with_options(
+        |sample_rate, block_size| {
+            let player = MidiPlayer::new(sample_rate, block_size, &patch_json, &midi_bytes)
+                .expect("Failed to render MIDI file");
+            duration = player.duration_seconds(sample_rate);
+            player
+        },
+        options,
+    )?;
+
+    println!("\nPlaying {:.1} s", duration);
+    std::thread::sleep(Duration::from_secs_f32(duration));
+    Ok(())
+}
+
+#[cfg(feature = "osc")]
+fn play_osc(port: u16, patch_path: &str, options: AudioHostOptions) -> anyhow::Result<()> {
+    use audio_processor::audio_engine::osc::OscServer;
+    use osc_player::OscPlayer;
+
+    let patch_json = std::fs::read_to_string(patch_path)?;
+    let server = OscServer::bind(("0.0.0.0", port))?;
+    println!("=== LISTENING FOR OSC ON {} ===", server.local_addr());
+
+    let _host = AudioHost::with_options(
+        |sample_rate, block_size| {
+            OscPlayer::new(sample_rate, block_size, &patch_json, server)
+                .expect("Failed to load patch")
+        },
+        options,
+    )?;
+
+    println!("\n   Send /note/on, /note/off, /macro/<n> or /node/<id>/<param>");
+    println!("   Press Ctrl+C to stop\n");
+    loop {
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}
+
+#[cfg(not(feature = "osc"))]
+fn play_osc(_port: u16, _patch_path: &str, _options: AudioHostOptions) -> anyhow::Result<()> {
+    Err(anyhow::anyhow!(
+        "--osc needs the demo built with --features osc"
+    ))
+}
+
 impl AudioRenderer for Composition {
     fn process_block(&mut self, output_left: &mut [f32], output_right: &mut [f32]) {
         self.process_block(output_left, output_right);
@@ -0,0 +1,52 @@
+//! Plays a MIDI file through a patch.
+
+use audio_processor::audio_engine::native::AudioEngine;
+
+use super::audio_renderer::AudioRenderer;
+
+/// Seconds rendered past the last MIDI event so release tails ring out.
+const RELEASE_TAIL_SECONDS: f32 = 2.0;
+
+/// Renders the whole file up front, then streams it to the audio host and
+/// falls silent at the end.
+pub struct MidiPlayer {
+    left: Vec<f32>,
+    right: Vec<f32>,
+    position: usize,
+}
+
+impl MidiPlayer {
+    pub fn new(
+        sample_rate: f32,
+        block_size: usize,
+        patch_json: &str,
+        midi_bytes: &[u8],
+    ) -> Result<Self, String> {
+        // The patch sets the voice count.
+        let mut engine = AudioEngine::new_with_block_size(sample_rate, 1, block_size);
+        engine.init(sample_rate, 1);
+        engine.init_with_patch(patch_json)?;
+        let (left, right) = engine.render_midi_file(midi_bytes, RELEASE_TAIL_SECONDS)?;
+        Ok(Self {
+            left,
+            right,
+            position: 0,
+        })
+    }
+
+    pub fn duration_seconds(&self, sample_rate: f32) -> f32 {
+        self.left.len() as f32 / sample_rate
+    }
+}
+
+impl AudioRenderer for MidiPlayer {
+    fn process_block(&mut self, output_left: &mut [f32], output_right: &mut [f32]) {
+        let start = self.position.min(self.left.len());
+        let n = output_left.len().min(self.left.len() - start);
+        output_left[..n].copy_from_slice(&self.left[start..start + n]);
+        output_right[..n].copy_from_slice(&self.right[start..start + n]);
+        output_left[n..].fill(0.0);
+        output_right[n..].fill(0.0);
+        self.position += n;
+    }
+}
@@ -0,0 +1,44 @@
+//! Plays a patch live from an OSC controller.
+
+use audio_processor::audio_engine::native::AudioEngine;
+use audio_processor::audio_engine::osc::OscServer;
+
+use super::audio_renderer::AudioRenderer;
+
+/// Applies the OSC commands received since the last block, then renders it.
+pub struct OscPlayer {
+    engine: AudioEngine,
+    server: OscServer,
+}
+
+impl OscPlayer {
+    pub fn new(
+        sample_rate: f32,
+        block_size: usize,
+        patch_json: &str,
+        server: OscServer,
+    ) -> Result<Self, String> {
+        // The patch sets the voice count.
+        let mut engine = AudioEngine::new_with_block_size(sample_rate, 1, block_size);
+        engine.init(sample_rate, 1);
+        engine.init_with_patch(patch_json)?;
+        Ok(Self { engine, server })
+    }
+}
+
+impl AudioRenderer for OscPlayer {
+    fn process_block(&mut self, output_left: &mut [f32], output_right: &mut [f32]) {
+        self.server.apply_pending(&mut self.engine);
+        let block_size = self.engine.block_size().max(1);
+        for (left, right) in output_left
+            .chunks_mut(block_size)
+            .zip(output_right.chunks_mut(block_size))
+        {
+            // Empty gate buffers hand the voices to the note allocator.
+            self.engine
+                .process_audio(&[], &[], &[], &[], &[], 1.0, left, right);
+        }
+    }
+}
+
+unsafe impl Send for OscPlayer {}
@@ -1,4 +1,6 @@
+use crate::utils::simd_kernels::simd_enabled;
 use std::f64::consts::PI as PI64;
+use std::simd::f32x2;
 #[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::wasm_bindgen;
 
@@ -14,6 +16,8 @@ pub enum FilterType {
     Ladder,
     Comb,
     BandPass,
+    /// Flat magnitude, phase turning through 180 degrees at the cutoff.
+    AllPass,
 }
 
 pub trait Filter {
@@ -88,8 +92,9 @@ impl Biquad {
         let freq64 = self.frequency.clamp(1.0, self.sample_rate * 0.49999) as f64;
         let q64 = self.q.max(0.001) as f64;
         let gain_db64 = self.gain_db as f64;
-        let a_lin64 = 10.0_f64.powf(gain_db64 / 20.0);
-        let a_sqrt64 = 10.0_f64.powf(gain_db64 / 40.0);
+        // RBJ cookbook amplitude: shelves and peaks reach `gain_db`, not twice it.
+        let a_lin64 = 10.0_f64.powf(gain_db64 / 40.0);
+        let a_sqrt64 = a_lin64.sqrt();
 
         let omega64 = 2.0 * PI64 * freq64 / sr64;
         let sn64 = omega64.sin();
@@ -168,6 +173,14 @@ impl Biquad {
                 a1_64 = -two_cs64;
                 a2_64 = 1.0 - alpha64;
             }
+            FilterType::AllPass => {
+                b0_64 = 1.0 - alpha64;
+                b1_64 = -two_cs64;
+                b2_64 = 1.0 + alpha64;
+                a0_64 = 1.0 + alpha64;
+                a1_64 = -two_cs64;
+                a2_64 = 1.0 - alpha64;
+            }
             FilterType::Peaking => {
                 b0_64 = 1.0 + alpha64 * a_lin64;
                 b1_64 = -two_cs64;
@@ -257,6 +270,25 @@ impl Biquad {
         self.y1
     }
 
+    /// Filters `buffer` in place.
+    pub fn process_block(&mut self, buffer: &mut [f32]) {
+        for sample in buffer.iter_mut() {
+            *sample = self.process(*sample);
+        }
+    }
+
+    /// Magnitude response in dB at `frequency` Hz, from the current
+    /// coefficients.
+    pub fn magnitude_db(&self, frequency: f32) -> f32 {
+        let omega = 2.0 * PI64 * frequency as f64 / self.sample_rate as f64;
+        let (sin1, cos1) = omega.sin_cos();
+        let (sin2, cos2) = (2.0 * omega).sin_cos();
+        let [b0, b1, b2, a1, a2] = [self.b0, self.b1, self.b2, self.a1, self.a2].map(f64::from);
+        let numerator = (b0 + b1 * cos1 + b2 * cos2).hypot(b1 * sin1 + b2 * sin2);
+        let denominator = (1.0 + a1 * cos1 + a2 * cos2).hypot(a1 * sin1 + a2 * sin2);
+        (20.0 * (numerator / denominator.max(1e-12)).max(1e-12).log10()) as f32
+    }
+
     fn reset(&mut self) {
         self.x1 = 0.0;
         self.x2 = 0.0;
@@ -312,6 +344,64 @@ impl CascadedBiquad {
     }
 }
 
+impl CascadedBiquad {
+    /// Filters `buffer` in place through both stages.
+    ///
+    /// With the SIMD kernels enabled the two stages run side by side in one
+    /// vector, the second stage one sample behind the first, which halves the
+    /// length of the serial dependency chain. Results match the scalar path.
+    pub fn process_block(&mut self, buffer: &mut [f32]) {
+        if buffer.len() < 2 || !simd_enabled() {
+            for sample in buffer.iter_mut() {
+                *sample = self.second.process(self.first.process(*sample));
+            }
+            return;
+        }
+
+        // Stage one handles the first sample alone to fill the pipeline.
+        let mut carry = self.first.process(buffer[0]);
+
+        let (first, second) = (&self.first, &self.second);
+        let pair = |a: f32, b: f32| f32x2::from_array([a, b]);
+        let b0 = pair(first.b0, second.b0);
+        let b1 = pair(first.b1, second.b1);
+        let b2 = pair(first.b2, second.b2);
+        let a1 = pair(first.a1, second.a1);
+        let a2 = pair(first.a2, second.a2);
+        let mut x1 = pair(first.x1, second.x1);
+        let mut x2 = pair(first.x2, second.x2);
+        let mut y1 = pair(first.y1, second.y1);
+        let mut y2 = pair(first.y2, second.y2);
+        let flush_denormal = |v: f32| if v.abs() < 1e-18 { 0.0 } else { v };
+
+        for n in 1..buffer.len() {
+            let input = pair(buffer[n], carry);
+            let output = b0 * input + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+            x2 = x1;
+            x1 = input;
+            y2 = y1;
+
+            let [stage_one, stage_two] = output.to_array().map(flush_denormal);
+            y1 = pair(stage_one, stage_two);
+            buffer[n - 1] = stage_two;
+            carry = stage_one;
+        }
+
+        let [x1_first, x1_second] = x1.to_array();
+        let [x2_first, x2_second] = x2.to_array();
+        let [y1_first, y1_second] = y1.to_array();
+        let [y2_first, y2_second] = y2.to_array();
+        (self.first.x1, self.first.x2) = (x1_first, x2_first);
+        (self.first.y1, self.first.y2) = (y1_first, y2_first);
+        (self.second.x1, self.second.x2) = (x1_second, x2_second);
+        (self.second.y1, self.second.y2) = (y1_second, y2_second);
+
+        // Drain the pipeline: stage two is still one sample behind.
+        let last = buffer.len() - 1;
+        buffer[last] = self.second.process(carry);
+    }
+}
+
 impl Filter for CascadedBiquad {
     #[inline(always)]
     fn process(&mut self, input: f32) -> f32 {
@@ -587,6 +677,7 @@ mod tests {
             FilterType::HighPass,
             FilterType::BandPass,
             FilterType::Notch,
+            FilterType::AllPass,
             FilterType::Peaking,
             FilterType::LowShelf,
             FilterType::HighShelf,
@@ -661,6 +752,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cascaded_block_matches_per_sample() {
+        let mut per_sample =
+            CascadedBiquad::new(FilterType::LowPass, TEST_SAMPLE_RATE, 1200.0, 2.0, 0.0);
+        let mut block = per_sample;
+        let input: Vec<f32> = (0..257)
+            .map(|i| (2.0 * PI * 440.0 * i as f32 / TEST_SAMPLE_RATE).sin())
+            .collect();
+
+        let expected: Vec<f32> = input
+            .iter()
+            .map(|&x| Filter::process(&mut per_sample, x))
+            .collect();
+        // Odd chunk sizes check that the pipeline state carries across calls.
+        let mut output = input.clone();
+        for chunk in output.chunks_mut(37) {
+            block.process_block(chunk);
+        }
+
+        // The vector path evaluates the same expression, so it matches exactly.
+        assert_eq!(output, expected);
+        assert_eq!(block.second.y1, per_sample.second.y1);
+    }
+
     #[test]
     fn test_biquad_denormal_prevention() {
         // Test that very small values are properly handled
@@ -1,30 +1,264 @@
 use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::wasm_bindgen;
 
 use crate::{
     graph::{ModulationSource, ModulationTransformation, ModulationType},
-    AudioNode, PortId,
+    nodes::{Delay, LfoWaveform, Multiband, Parallel, RandomSteps, PARALLEL_CHAINS},
+    utils::{gain_staging::LevelMeter, groove::Groove},
+    AudioNode, NodeId, PortId, QualityMode,
 };
 
+/// A voice node output used as an effect's sidechain key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SidechainSource {
+    pub node_id: NodeId,
+    pub port: PortId,
+}
+
+/// Which part of the stereo signal an effect slot processes.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EffectRouting {
+    #[default]
+    Stereo = 0,
+    /// Only the mid (L+R) component goes through the effect; the side passes dry.
+    MidOnly = 1,
+    /// Only the side (L-R) component goes through the effect; the mid passes dry.
+    SideOnly = 2,
+    /// Left and right go through the effect as two mono signals, one after the
+    /// other, so nothing crosses between the channels. The passes share the
+    /// effect's state, which suits dynamics and distortion better than
+    /// delays and reverbs.
+    DualMono = 3,
+}
+
+/// Band of a container effect (a `Multiband` band or a `Parallel` chain) an
+/// effect runs in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContainerSlot {
+    /// Index of the container effect in the stack.
+    pub container: usize,
+    pub band: usize,
+}
+
+/// Effects that split the signal into bands for other effects in the stack to
+/// process before mixing them back together.
+trait EffectContainer {
+    fn split(&mut self, left: &[f32], right: &[f32], len: usize);
+    fn band_count(&self) -> usize;
+    fn band_buffers_mut(&mut self, band: usize) -> Option<(&mut [f32], &mut [f32])>;
+    /// Latency of the effects that ran on a band this block.
+    fn set_band_latency(&mut self, _band: usize, _samples: usize) {}
+    fn mix(&mut self, out_left: &mut [f32], out_right: &mut [f32], len: usize);
+}
+
+impl EffectContainer for Multiband {
+    fn split(&mut self, left: &[f32], right: &[f32], len: usize) {
+        Multiband::split(self, left, right, len);
+    }
+
+    fn band_count(&self) -> usize {
+        Multiband::band_count(self)
+    }
+
+    fn band_buffers_mut(&mut self, band: usize) -> Option<(&mut [f32], &mut [f32])> {
+        Multiband::band_buffers_mut(self, band)
+    }
+
+    fn mix(&mut self, out_left: &mut [f32], out_right: &mut [f32], len: usize) {
+        Multiband::mix(self, out_left, out_right, len);
+    }
+}
+
+impl EffectContainer for Parallel {
+    fn split(&mut self, left: &[f32], right: &[f32], len: usize) {
+        Parallel::split(self, left, right, len);
+    }
+
+    fn band_count(&self) -> usize {
+        PARALLEL_CHAINS
+    }
+
+    fn band_buffers_mut(&mut self, band: usize) -> Option<(&mut [f32], &mut [f32])> {
+        self.chain_buffers_mut(band)
+    }
+
+    fn set_band_latency(&mut self, band: usize, samples: usize) {
+        self.set_chain_latency(band, samples);
+    }
+
+    fn mix(&mut self, out_left: &mut [f32], out_right: &mut [f32], len: usize) {
+        Parallel::mix(self, out_left, out_right, len);
+    }
+}
+
+fn as_container(node: &dyn AudioNode) -> Option<&dyn EffectContainer> {
+    let any = node.as_any();
+    if let Some(multiband) = any.downcast_ref::<Multiband>() {
+        return Some(multiband);
+    }
+    any.downcast_ref::<Parallel>()
+        .map(|parallel| parallel as &dyn EffectContainer)
+}
+
+fn container_mut(effect: &mut Effect) -> &mut dyn EffectContainer {
+    let any = effect.node.as_any_mut();
+    if any.is::<Multiband>() {
+        return any.downcast_mut::<Multiband>().unwrap();
+    }
+    any.downcast_mut::<Parallel>().expect("container effect")
+}
+
+/// Number of LFOs the effect stack owns for modulating effect parameters.
+pub const EFFECT_LFO_COUNT: usize = 4;
+
+/// Free-running LFO owned by the effect stack, independent of the voice LFOs.
+/// It advances once per block, which is plenty for the slow sweeps it drives;
+/// the effects smooth the offsets they receive.
+#[derive(Debug, Clone, Copy)]
+struct EffectLfo {
+    waveform: LfoWaveform,
+    rate_hz: f32,
+    /// Cycle length in beats when synced to the stack tempo.
+    sync_beats: Option<f32>,
+    phase: f32,
+    random: RandomSteps,
+}
+
+impl Default for EffectLfo {
+    fn default() -> Self {
+        Self {
+            waveform: LfoWaveform::Sine,
+            rate_hz: 1.0,
+            sync_beats: None,
+            phase: 0.0,
+            random: RandomSteps::new(0),
+        }
+    }
+}
+
+impl EffectLfo {
+    /// Bipolar output (-1.0 to 1.0) at the current phase.
+    fn value(&self) -> f32 {
+        let phase = self.phase;
+        match self.waveform {
+            LfoWaveform::Sine => (std::f32::consts::TAU * phase).sin(),
+            LfoWaveform::Triangle => 1.0 - 4.0 * ((phase + 0.25).fract() - 0.5).abs(),
+            LfoWaveform::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            LfoWaveform::Saw => 2.0 * phase - 1.0,
+            LfoWaveform::InverseSaw => 1.0 - 2.0 * phase,
+            LfoWaveform::SampleAndHold | LfoWaveform::SmoothRandom => {
+                self.random.value(self.waveform)
+            }
+        }
+    }
+
+    fn advance(&mut self, samples: usize, sample_rate: f32, tempo_bpm: f32) {
+        let rate = match self.sync_beats {
+            Some(beats) => tempo_bpm / 60.0 / beats,
+            None => self.rate_hz,
+        };
+        let cycles = rate * samples as f32 / sample_rate;
+        self.phase = (self.phase + cycles).rem_euclid(1.0);
+        self.random.advance(cycles);
+    }
+}
+
+/// Routes an effect LFO to a named parameter of an effect (see
+/// `AudioNode::modulate_parameter`).
+struct EffectLfoRoute {
+    lfo: usize,
+    effect: usize,
+    parameter: String,
+    /// Offset at full LFO swing, in the parameter's units.
+    depth: f32,
+}
+
 pub struct Effect {
     pub node: Box<dyn AudioNode>,
+    /// Per-effect smoothing time that takes precedence over the stack-wide setting.
+    pub smoothing_override: Option<f32>,
+    /// Per-effect groove that takes precedence over the stack-wide one.
+    pub groove_override: Option<Groove>,
+    /// Voice node output fed to the effect's `SidechainInput` port.
+    pub sidechain: Option<SidechainSource>,
+    /// Whether the effect's wet signal also feeds the rear channels in
+    /// surround output.
+    pub surround_send: bool,
+    pub routing: EffectRouting,
+    /// Set when the effect runs on one band of a container effect instead of
+    /// in the serial chain.
+    pub band: Option<ContainerSlot>,
+}
+
+impl Effect {
+    fn is_running(&self, limiters_bypassed: bool) -> bool {
+        self.node.is_active() && !(limiters_bypassed && self.node.node_type() == "limiter")
+    }
 }
 
 pub struct EffectStack {
     pub effects: Vec<Effect>,
+    smoothing_time_ms: Option<f32>,
+    quality_mode: QualityMode,
+    groove: Option<Groove>,
+    limiters_bypassed: bool,
+    spread_capture: bool,
+    spread_left: Vec<f32>,
+    spread_right: Vec<f32>,
     work_left_a: Vec<f32>,
     work_right_a: Vec<f32>,
     work_left_b: Vec<f32>,
     work_right_b: Vec<f32>,
+    // Mid/side or mono scratch for effects that aren't routed in stereo.
+    route_a: Vec<f32>,
+    route_b: Vec<f32>,
+    // One key buffer per distinct source, shared by every effect keyed from it.
+    sidechain_buffers: Vec<(SidechainSource, Vec<f32>)>,
+    lfos: [EffectLfo; EFFECT_LFO_COUNT],
+    lfo_routes: Vec<EffectLfoRoute>,
+    // (input, output) levels per effect while metering; empty otherwise.
+    level_meters: Vec<(LevelMeter, LevelMeter)>,
+    sample_rate: f32,
+    tempo_bpm: f32,
 }
 
 impl EffectStack {
     pub fn new(_buffer_size: usize) -> Self {
         Self {
             effects: Vec::new(),
+            smoothing_time_ms: None,
+            quality_mode: QualityMode::default(),
+            groove: None,
+            limiters_bypassed: false,
+            spread_capture: false,
+            spread_left: Vec::new(),
+            spread_right: Vec::new(),
             work_left_a: Vec::new(),
             work_right_a: Vec::new(),
             work_left_b: Vec::new(),
             work_right_b: Vec::new(),
+            route_a: Vec::new(),
+            route_b: Vec::new(),
+            sidechain_buffers: Vec::new(),
+            lfos: std::array::from_fn(|index| EffectLfo {
+                random: RandomSteps::new(index as u32),
+                ..EffectLfo::default()
+            }),
+            lfo_routes: Vec::new(),
+            level_meters: Vec::new(),
+            sample_rate: 48_000.0,
+            tempo_bpm: 120.0,
         }
     }
 
@@ -41,17 +275,161 @@ impl EffectStack {
         if self.work_right_b.len() < len {
             self.work_right_b.resize(len, 0.0);
         }
+        if self.route_a.len() < len {
+            self.route_a.resize(len, 0.0);
+        }
+        if self.route_b.len() < len {
+            self.route_b.resize(len, 0.0);
+        }
     }
 
-    pub fn add_effect(&mut self, effect: Box<dyn AudioNode>) -> usize {
+    pub fn add_effect(&mut self, mut effect: Box<dyn AudioNode>) -> usize {
+        if let Some(time_ms) = self.smoothing_time_ms {
+            effect.set_smoothing_time_ms(time_ms);
+        }
+        effect.set_quality_mode(self.quality_mode);
+        if let Some(groove) = &self.groove {
+            effect.set_groove(groove);
+        }
+        if let Some(delay) = effect.as_any_mut().downcast_mut::<Delay>() {
+            delay.set_tempo(self.tempo_bpm);
+        }
+        let surround_send = matches!(effect.node_type(), "freeverb" | "convolver" | "delay");
         let index = self.effects.len();
-        self.effects.push(Effect { node: effect });
+        self.effects.push(Effect {
+            node: effect,
+            smoothing_override: None,
+            groove_override: None,
+            sidechain: None,
+            surround_send,
+            routing: EffectRouting::default(),
+            band: None,
+        });
         index
     }
 
+    /// Sets the parameter smoothing time for every effect without its own override,
+    /// including effects added later.
+    pub fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.smoothing_time_ms = Some(time_ms);
+        for effect in &mut self.effects {
+            if effect.smoothing_override.is_none() {
+                effect.node.set_smoothing_time_ms(time_ms);
+            }
+        }
+    }
+
+    /// Applies a quality mode to every effect, including effects added later.
+    pub fn set_quality_mode(&mut self, mode: QualityMode) {
+        self.quality_mode = mode;
+        for effect in &mut self.effects {
+            effect.node.set_quality_mode(mode);
+        }
+    }
+
+    pub fn quality_mode(&self) -> QualityMode {
+        self.quality_mode
+    }
+
+    /// Overrides the smoothing time of a single effect. `None` hands it back to the
+    /// stack-wide setting (if one has been made).
+    pub fn set_effect_smoothing_time_ms(&mut self, index: usize, time_ms: Option<f32>) {
+        if let Some(effect) = self.effects.get_mut(index) {
+            effect.smoothing_override = time_ms;
+            if let Some(time_ms) = time_ms.or(self.smoothing_time_ms) {
+                effect.node.set_smoothing_time_ms(time_ms);
+            }
+        }
+    }
+
+    /// Sets the groove template for every effect without its own override,
+    /// including effects added later.
+    pub fn set_groove(&mut self, groove: Groove) {
+        self.groove = Some(groove);
+        for effect in &mut self.effects {
+            if effect.groove_override.is_none() {
+                effect.node.set_groove(&groove);
+            }
+        }
+    }
+
+    /// Overrides the groove of a single effect. `None` hands it back to the
+    /// stack-wide groove (straight if none has been set).
+    pub fn set_effect_groove(&mut self, index: usize, groove: Option<Groove>) {
+        if let Some(effect) = self.effects.get_mut(index) {
+            effect.groove_override = groove;
+            effect
+                .node
+                .set_groove(&groove.or(self.groove).unwrap_or_default());
+        }
+    }
+
+    /// Keys an effect from a voice node output; `None` disconnects the sidechain.
+    pub fn set_effect_sidechain(&mut self, index: usize, source: Option<SidechainSource>) {
+        if let Some(effect) = self.effects.get_mut(index) {
+            effect.sidechain = source;
+            self.update_sidechain_buffers();
+        }
+    }
+
+    pub fn effect_sidechain(&self, index: usize) -> Option<SidechainSource> {
+        self.effects.get(index).and_then(|effect| effect.sidechain)
+    }
+
+    fn update_sidechain_buffers(&mut self) {
+        let effects = &self.effects;
+        self.sidechain_buffers
+            .retain(|(source, _)| effects.iter().any(|e| e.sidechain == Some(*source)));
+        for effect in &self.effects {
+            if let Some(source) = effect.sidechain {
+                if !self.sidechain_buffers.iter().any(|(s, _)| *s == source) {
+                    self.sidechain_buffers.push((source, Vec::new()));
+                }
+            }
+        }
+    }
+
+    /// Sidechain sources that need to be collected from the voices each block.
+    pub fn has_sidechains(&self) -> bool {
+        !self.sidechain_buffers.is_empty()
+    }
+
+    /// Clears the key buffers ahead of a block of `len` samples.
+    pub fn begin_sidechain_block(&mut self, len: usize) {
+        for (_, buffer) in &mut self.sidechain_buffers {
+            buffer.resize(len, 0.0);
+            buffer.fill(0.0);
+        }
+    }
+
+    /// Folds one voice's node outputs into the key buffers. Voices are combined by
+    /// keeping the largest-magnitude sample, so an envelope or gate keys the effect
+    /// while any voice holds it up rather than scaling with polyphony.
+    pub fn accumulate_sidechain<'v>(
+        &mut self,
+        node_output: impl Fn(NodeId, PortId) -> Option<&'v [f32]>,
+    ) {
+        for (source, buffer) in &mut self.sidechain_buffers {
+            let Some(signal) = node_output(source.node_id, source.port) else {
+                continue;
+            };
+            for (acc, &sample) in buffer.iter_mut().zip(signal.iter()) {
+                if sample.abs() > acc.abs() {
+                    *acc = sample;
+                }
+            }
+        }
+    }
+
     pub fn remove_effect(&mut self, index: usize) {
         if index < self.effects.len() {
             self.effects.remove(index);
+            self.remap_effect_indices(|container| match container.cmp(&index) {
+                std::cmp::Ordering::Less => Some(container),
+                std::cmp::Ordering::Equal => None,
+                std::cmp::Ordering::Greater => Some(container - 1),
+            });
+            self.update_sidechain_buffers();
         }
     }
 
@@ -59,7 +437,145 @@ impl EffectStack {
         if from < self.effects.len() && to < self.effects.len() {
             let effect = self.effects.remove(from);
             self.effects.insert(to, effect);
+            self.remap_effect_indices(|container| {
+                Some(if container == from {
+                    to
+                } else if from < to && (from + 1..=to).contains(&container) {
+                    container - 1
+                } else if to < from && (to..from).contains(&container) {
+                    container + 1
+                } else {
+                    container
+                })
+            });
+        }
+    }
+
+    /// Keeps band assignments and LFO routes pointing at their effects after
+    /// the effect indices shift; members of a removed container return to the
+    /// chain and routes to a removed effect are dropped.
+    fn remap_effect_indices(&mut self, remap: impl Fn(usize) -> Option<usize>) {
+        for effect in &mut self.effects {
+            if let Some(slot) = effect.band {
+                effect.band = remap(slot.container).map(|container| ContainerSlot {
+                    container,
+                    band: slot.band,
+                });
+            }
+        }
+        self.lfo_routes
+            .retain_mut(|route| match remap(route.effect) {
+                Some(effect) => {
+                    route.effect = effect;
+                    true
+                }
+                None => false,
+            });
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate.max(1.0);
+    }
+
+    /// Tempo the synced effect LFOs and delays follow.
+    pub fn set_tempo(&mut self, bpm: f32) {
+        self.tempo_bpm = bpm.max(1.0);
+        for effect in &mut self.effects {
+            if let Some(delay) = effect.node.as_any_mut().downcast_mut::<Delay>() {
+                delay.set_tempo(self.tempo_bpm);
+            }
+        }
+    }
+
+    /// Configures an effect LFO. With `sync_beats` set the rate follows the
+    /// stack tempo, one cycle every `sync_beats` beats, and `rate_hz` is
+    /// ignored.
+    pub fn set_lfo(
+        &mut self,
+        index: usize,
+        waveform: LfoWaveform,
+        rate_hz: f32,
+        sync_beats: Option<f32>,
+    ) {
+        if let Some(lfo) = self.lfos.get_mut(index) {
+            lfo.waveform = waveform;
+            lfo.rate_hz = rate_hz.max(0.0);
+            lfo.sync_beats = sync_beats.filter(|beats| *beats > 0.0);
+        }
+    }
+
+    /// Routes an LFO to a parameter of an effect, replacing any route already
+    /// on that parameter; a depth of 0 removes the route. Returns false if the
+    /// LFO or effect doesn't exist or the effect has no such parameter.
+    pub fn set_lfo_route(
+        &mut self,
+        lfo: usize,
+        effect: usize,
+        parameter: &str,
+        depth: f32,
+    ) -> bool {
+        if lfo >= EFFECT_LFO_COUNT {
+            return false;
+        }
+        let Some(target) = self.effects.get_mut(effect) else {
+            return false;
+        };
+        // Also clears the offset left by a replaced route.
+        if !target.node.modulate_parameter(parameter, 0.0) {
+            return false;
         }
+        self.lfo_routes
+            .retain(|route| route.effect != effect || route.parameter != parameter);
+        if depth != 0.0 {
+            self.lfo_routes.push(EffectLfoRoute {
+                lfo,
+                effect,
+                parameter: parameter.to_string(),
+                depth,
+            });
+        }
+        true
+    }
+
+    /// Sends the current LFO values to their parameters and advances the LFOs
+    /// by one block.
+    fn apply_lfos(&mut self, len: usize) {
+        for route in &self.lfo_routes {
+            let offset = self.lfos[route.lfo].value() * route.depth;
+            if let Some(effect) = self.effects.get_mut(route.effect) {
+                effect.node.modulate_parameter(&route.parameter, offset);
+            }
+        }
+        for lfo in &mut self.lfos {
+            lfo.advance(len, self.sample_rate, self.tempo_bpm);
+        }
+    }
+
+    /// Moves an effect onto one band of a container effect (a multiband band
+    /// or a parallel chain), or back into the serial chain with `None`.
+    /// Members of a band run in stack order. Returns false if the slot doesn't
+    /// name a band of a container, or if the effect is a container itself.
+    pub fn set_effect_band(&mut self, index: usize, slot: Option<ContainerSlot>) -> bool {
+        if index >= self.effects.len() {
+            return false;
+        }
+        if let Some(slot) = slot {
+            let container = |i: usize| {
+                self.effects
+                    .get(i)
+                    .and_then(|effect| as_container(effect.node.as_ref()))
+            };
+            let valid_band = container(slot.container).is_some_and(|c| slot.band < c.band_count());
+            if !valid_band || index == slot.container || container(index).is_some() {
+                return false;
+            }
+        }
+        self.effects[index].band = slot;
+        true
+    }
+
+    pub fn effect_band(&self, index: usize) -> Option<ContainerSlot> {
+        self.effects.get(index).and_then(|effect| effect.band)
     }
 
     pub fn set_effect_enabled(&mut self, index: usize, enabled: bool) {
@@ -68,6 +584,62 @@ impl EffectStack {
         }
     }
 
+    /// Skips limiter effects without changing their active state, for output
+    /// modes that don't want the safety limiter.
+    pub fn set_limiters_bypassed(&mut self, bypassed: bool) {
+        self.limiters_bypassed = bypassed;
+    }
+
+    /// Chooses whether an effect's wet signal feeds the rear channels in
+    /// surround output. Reverbs and delays do by default.
+    pub fn set_effect_surround_send(&mut self, index: usize, enabled: bool) {
+        if let Some(effect) = self.effects.get_mut(index) {
+            effect.surround_send = enabled;
+        }
+    }
+
+    /// Restricts an effect to the mid or side component, or runs it dual-mono.
+    pub fn set_effect_routing(&mut self, index: usize, routing: EffectRouting) {
+        if let Some(effect) = self.effects.get_mut(index) {
+            effect.routing = routing;
+        }
+    }
+
+    pub fn effect_routing(&self, index: usize) -> Option<EffectRouting> {
+        self.effects.get(index).map(|effect| effect.routing)
+    }
+
+    /// Makes `process_audio` collect the wet signal (output minus input) of the
+    /// surround send effects, read back through `spread_output`.
+    pub fn set_spread_capture(&mut self, enabled: bool) {
+        self.spread_capture = enabled;
+        if !enabled {
+            self.spread_left.clear();
+            self.spread_right.clear();
+        }
+    }
+
+    /// Wet signal of the surround send effects from the last block.
+    pub fn spread_output(&self) -> (&[f32], &[f32]) {
+        (&self.spread_left, &self.spread_right)
+    }
+
+    /// Starts (or stops) collecting input and output levels of every effect in
+    /// the serial chain; starting again clears the previous readings. Effects
+    /// running on a container band are measured as part of their container.
+    pub fn set_level_metering(&mut self, enabled: bool) {
+        self.level_meters = if enabled {
+            vec![Default::default(); self.effects.len()]
+        } else {
+            Vec::new()
+        };
+    }
+
+    /// (input, output) levels per effect index since metering started.
+    pub fn effect_levels(&self) -> &[(LevelMeter, LevelMeter)] {
+        &self.level_meters
+    }
+
     pub fn get_effect_count(&self) -> usize {
         self.effects.len()
     }
@@ -90,14 +662,31 @@ impl EffectStack {
             return;
         }
 
+        self.apply_lfos(actual_buffer_size);
+
+        if self.spread_capture {
+            self.spread_left.resize(actual_buffer_size, 0.0);
+            self.spread_right.resize(actual_buffer_size, 0.0);
+            self.spread_left.fill(0.0);
+            self.spread_right.fill(0.0);
+        }
+
         // If all effects are disabled, bypass processing entirely.
-        if self.effects.iter().all(|e| !e.node.is_active()) {
+        if self
+            .effects
+            .iter()
+            .all(|e| !e.is_running(self.limiters_bypassed))
+        {
             output_left[..actual_buffer_size].copy_from_slice(&input_left[..actual_buffer_size]);
             output_right[..actual_buffer_size].copy_from_slice(&input_right[..actual_buffer_size]);
             return;
         }
 
-        if self.effects.iter().all(|e| !e.node.is_active()) {
+        if self
+            .effects
+            .iter()
+            .all(|e| !e.is_running(self.limiters_bypassed))
+        {
             output_left[..actual_buffer_size].copy_from_slice(&input_left[..actual_buffer_size]);
             output_right[..actual_buffer_size].copy_from_slice(&input_right[..actual_buffer_size]);
             return;
@@ -111,10 +700,13 @@ impl EffectStack {
         let mut current_is_a = true;
         let mut had_active_effect = false;
 
-        for effect in &mut self.effects {
-            if !effect.node.is_active() {
+        for index in 0..self.effects.len() {
+            let effect = &self.effects[index];
+            // Band members run inside their container.
+            if !effect.is_running(self.limiters_bypassed) || effect.band.is_some() {
                 continue;
             }
+            let is_container = as_container(effect.node.as_ref()).is_some();
             had_active_effect = true;
 
             let (current_left, current_right, next_left, next_right) = if current_is_a {
@@ -133,38 +725,94 @@ impl EffectStack {
                 )
             };
 
-            next_left[..actual_buffer_size].fill(0.0);
-            next_right[..actual_buffer_size].fill(0.0);
-
-            // Zero-copy: Create ModulationSource with references to current buffers
-            let mut inputs = FxHashMap::with_capacity_and_hasher(2, Default::default());
-
-            let left_source = ModulationSource {
-                buffer: &current_left[..actual_buffer_size],
-                amount: 1.0,
-                mod_type: ModulationType::Additive,
-                transformation: ModulationTransformation::None,
-            };
-
-            let right_source = ModulationSource {
-                buffer: &current_right[..actual_buffer_size],
-                amount: 1.0,
-                mod_type: ModulationType::Additive,
-                transformation: ModulationTransformation::None,
-            };
-
-            inputs.insert(PortId::AudioInput0, vec![left_source]);
-            inputs.insert(PortId::AudioInput1, vec![right_source]);
+            let len = actual_buffer_size;
+            if is_container {
+                process_container(
+                    &mut self.effects,
+                    index,
+                    &self.sidechain_buffers,
+                    self.limiters_bypassed,
+                    (&current_left[..len], &current_right[..len]),
+                    (&mut next_left[..len], &mut next_right[..len]),
+                    (&mut self.route_a[..len], &mut self.route_b[..len]),
+                );
+            } else {
+                let effect = &mut self.effects[index];
+                let key = sidechain_key_for(&self.sidechain_buffers, effect.sidechain, len);
+                let node = effect.node.as_mut();
+                match effect.routing {
+                    EffectRouting::Stereo => run_effect(
+                        node,
+                        &current_left[..len],
+                        &current_right[..len],
+                        key,
+                        &mut next_left[..len],
+                        &mut next_right[..len],
+                    ),
+                    EffectRouting::MidOnly | EffectRouting::SideOnly => {
+                        // route_a holds the processed component, route_b the dry one.
+                        let mid_only = effect.routing == EffectRouting::MidOnly;
+                        for i in 0..len {
+                            let mid = 0.5 * (current_left[i] + current_right[i]);
+                            let side = 0.5 * (current_left[i] - current_right[i]);
+                            let (wet, dry) = if mid_only { (mid, side) } else { (side, mid) };
+                            self.route_a[i] = wet;
+                            self.route_b[i] = dry;
+                        }
+                        run_effect(
+                            node,
+                            &self.route_a[..len],
+                            &self.route_a[..len],
+                            key,
+                            &mut next_left[..len],
+                            &mut next_right[..len],
+                        );
+                        for i in 0..len {
+                            let processed = 0.5 * (next_left[i] + next_right[i]);
+                            let (mid, side) = if mid_only {
+                                (processed, self.route_b[i])
+                            } else {
+                                (self.route_b[i], processed)
+                            };
+                            next_left[i] = mid + side;
+                            next_right[i] = mid - side;
+                        }
+                    }
+                    EffectRouting::DualMono => {
+                        run_effect(
+                            node,
+                            &current_left[..len],
+                            &current_left[..len],
+                            key,
+                            &mut next_left[..len],
+                            &mut self.route_a[..len],
+                        );
+                        run_effect(
+                            node,
+                            &current_right[..len],
+                            &current_right[..len],
+                            key,
+                            &mut self.route_a[..len],
+                            &mut next_right[..len],
+                        );
+                    }
+                }
+            }
 
-            let mut outputs = FxHashMap::with_capacity_and_hasher(2, Default::default());
-            outputs.insert(PortId::AudioOutput0, &mut next_left[..actual_buffer_size]);
-            outputs.insert(PortId::AudioOutput1, &mut next_right[..actual_buffer_size]);
+            if let Some((input_meter, output_meter)) = self.level_meters.get_mut(index) {
+                input_meter.add(&current_left[..len]);
+                input_meter.add(&current_right[..len]);
+                output_meter.add(&next_left[..len]);
+                output_meter.add(&next_right[..len]);
+            }
 
-            effect
-                .node
-                .process(&inputs, &mut outputs, actual_buffer_size);
+            if self.spread_capture && self.effects[index].surround_send {
+                for i in 0..actual_buffer_size {
+                    self.spread_left[i] += next_left[i] - current_left[i];
+                    self.spread_right[i] += next_right[i] - current_right[i];
+                }
+            }
 
-            // No need to store ModulationSource instances - they're created on the fly
             current_is_a = !current_is_a;
         }
 
@@ -190,3 +838,279 @@ impl EffectStack {
         }
     }
 }
+
+fn sidechain_key_for(
+    buffers: &[(SidechainSource, Vec<f32>)],
+    source: Option<SidechainSource>,
+    len: usize,
+) -> Option<&[f32]> {
+    let source = source?;
+    buffers
+        .iter()
+        .find(|(s, _)| *s == source)
+        .map(|(_, key)| &key[..len.min(key.len())])
+}
+
+/// Splits the block with the container effect at `container`, runs every
+/// effect assigned to a band over that band (in stereo) and mixes the bands
+/// into `output`.
+fn process_container(
+    effects: &mut [Effect],
+    container: usize,
+    sidechain_buffers: &[(SidechainSource, Vec<f32>)],
+    limiters_bypassed: bool,
+    input: (&[f32], &[f32]),
+    output: (&mut [f32], &mut [f32]),
+    scratch: (&mut [f32], &mut [f32]),
+) {
+    let len = output.0.len();
+    let (scratch_left, scratch_right) = scratch;
+    let band_count = {
+        let bands = container_mut(&mut effects[container]);
+        bands.split(input.0, input.1, len);
+        bands.band_count()
+    };
+
+    for band in 0..band_count {
+        let slot = Some(ContainerSlot { container, band });
+        let mut latency = 0;
+        for member in 0..effects.len() {
+            if effects[member].band != slot || !effects[member].is_running(limiters_bypassed) {
+                continue;
+            }
+            let (container_effect, member_effect) = if member < container {
+                let (head, tail) = effects.split_at_mut(container);
+                (&mut tail[0], &mut head[member])
+            } else {
+                let (head, tail) = effects.split_at_mut(member);
+                (&mut head[container], &mut tail[0])
+            };
+            let key = sidechain_key_for(sidechain_buffers, member_effect.sidechain, len);
+            let Some((band_left, band_right)) =
+                container_mut(container_effect).band_buffers_mut(band)
+            else {
+                continue;
+            };
+            run_effect(
+                member_effect.node.as_mut(),
+                band_left,
+                band_right,
+                key,
+                scratch_left,
+                scratch_right,
+            );
+            band_left.copy_from_slice(scratch_left);
+            band_right.copy_from_slice(scratch_right);
+            latency += member_effect.node.latency_samples();
+        }
+        container_mut(&mut effects[container]).set_band_latency(band, latency);
+    }
+
+    container_mut(&mut effects[container]).mix(output.0, output.1, len);
+}
+
+/// Runs one effect over a block, feeding `key` to its sidechain input.
+fn run_effect(
+    node: &mut dyn AudioNode,
+    left: &[f32],
+    right: &[f32],
+    key: Option<&[f32]>,
+    out_left: &mut [f32],
+    out_right: &mut [f32],
+) {
+    let len = out_left.len();
+    out_left.fill(0.0);
+    out_right.fill(0.0);
+
+    fn source(buffer: &[f32]) -> ModulationSource<'_> {
+        ModulationSource {
+            buffer,
+            amount: 1.0,
+            mod_type: ModulationType::Additive,
+            transformation: ModulationTransformation::None,
+        }
+    }
+
+    let mut inputs = FxHashMap::with_capacity_and_hasher(3, Default::default());
+    inputs.insert(PortId::AudioInput0, vec![source(left)]);
+    inputs.insert(PortId::AudioInput1, vec![source(right)]);
+    if let Some(key) = key {
+        inputs.insert(PortId::SidechainInput, vec![source(key)]);
+    }
+
+    let mut outputs = FxHashMap::with_capacity_and_hasher(2, Default::default());
+    outputs.insert(PortId::AudioOutput0, out_left);
+    outputs.insert(PortId::AudioOutput1, out_right);
+    node.process(&inputs, &mut outputs, len);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::Compressor;
+
+    fn doubler() -> Box<Compressor> {
+        // Unity ratio with +6 dB makeup: a plain x2 gain.
+        Box::new(Compressor::new(
+            48_000.0,
+            0.0,
+            1.0,
+            1.0,
+            50.0,
+            20.0 * 2.0f32.log10(),
+            1.0,
+        ))
+    }
+
+    const BLOCK: usize = 128;
+
+    fn keyed_stack(source: SidechainSource) -> EffectStack {
+        let mut stack = EffectStack::new(BLOCK);
+        // Hard, fast compressor so a full-scale key pulls the program down clearly.
+        let index = stack.add_effect(Box::new(Compressor::new(
+            48_000.0, -20.0, 20.0, 0.1, 50.0, 0.0, 1.0,
+        )));
+        stack.set_effect_sidechain(index, Some(source));
+        stack
+    }
+
+    fn render_block(stack: &mut EffectStack, key: &[f32]) -> f32 {
+        let source = stack.effect_sidechain(0).expect("sidechain source");
+        let input = vec![0.05; BLOCK];
+        let mut left = vec![0.0; BLOCK];
+        let mut right = vec![0.0; BLOCK];
+        stack.begin_sidechain_block(BLOCK);
+        stack.accumulate_sidechain(|node_id, port| {
+            (node_id == source.node_id && port == source.port).then_some(key)
+        });
+        stack.process_audio(&input, &input, &mut left, &mut right);
+        left[BLOCK - 1]
+    }
+
+    #[test]
+    fn compressor_ducks_on_voice_sidechain() {
+        let source = SidechainSource {
+            node_id: NodeId::new(),
+            port: PortId::AudioOutput0,
+        };
+        let mut stack = keyed_stack(source);
+
+        // The program alone sits below threshold, so only the key can duck it.
+        let open = render_block(&mut stack, &[0.0; BLOCK]);
+        assert!((open - 0.05).abs() < 1e-4, "{}", open);
+        let ducked = render_block(&mut stack, &[1.0; BLOCK]);
+        assert!(ducked < 0.01, "{}", ducked);
+
+        stack.set_effect_sidechain(0, None);
+        assert!(!stack.has_sidechains());
+    }
+
+    #[test]
+    fn voices_combine_by_peak_magnitude() {
+        let source = SidechainSource {
+            node_id: NodeId::new(),
+            port: PortId::AudioOutput0,
+        };
+        let mut stack = keyed_stack(source);
+        stack.begin_sidechain_block(4);
+        for voice in [[0.2, -0.9, 0.0, 0.5], [0.6, 0.3, 0.0, -0.4]] {
+            stack.accumulate_sidechain(|_, _| Some(&voice[..]));
+        }
+        assert_eq!(stack.sidechain_buffers[0].1, vec![0.6, -0.9, 0.0, 0.5]);
+    }
+
+    #[test]
+    fn mid_and_side_routing_only_touch_their_component() {
+        let mut stack = EffectStack::new(BLOCK);
+        let index = stack.add_effect(doubler());
+        let input_left = vec![1.0; BLOCK];
+        let input_right = vec![0.0; BLOCK];
+        let mut left = vec![0.0; BLOCK];
+        let mut right = vec![0.0; BLOCK];
+
+        stack.set_effect_routing(index, EffectRouting::MidOnly);
+        stack.process_audio(&input_left, &input_right, &mut left, &mut right);
+        assert!((left[0] - 1.5).abs() < 1e-4 && (right[0] - 0.5).abs() < 1e-4);
+
+        stack.set_effect_routing(index, EffectRouting::SideOnly);
+        stack.process_audio(&input_left, &input_right, &mut left, &mut right);
+        assert!((left[0] - 1.5).abs() < 1e-4 && (right[0] + 0.5).abs() < 1e-4);
+        assert_eq!(stack.effect_routing(index), Some(EffectRouting::SideOnly));
+    }
+
+    #[test]
+    fn multiband_members_only_process_their_band() {
+        let mut stack = EffectStack::new(BLOCK);
+        let container = stack.add_effect(Box::new(Multiband::new(48_000.0, &[1_000.0])));
+        let member = stack.add_effect(doubler());
+        assert!(stack.set_effect_band(member, Some(ContainerSlot { container, band: 1 })));
+        assert!(!stack.set_effect_band(container, Some(ContainerSlot { container, band: 0 })));
+
+        let mut render = |input: &[f32]| {
+            let mut left = vec![0.0; BLOCK];
+            let mut right = vec![0.0; BLOCK];
+            for _ in 0..20 {
+                stack.process_audio(input, input, &mut left, &mut right);
+            }
+            left
+        };
+        // DC stays in the low band and passes unchanged; Nyquist is all high band.
+        let low = render(&[0.5; BLOCK]);
+        assert!((low[BLOCK - 1] - 0.5).abs() < 1e-3, "{}", low[BLOCK - 1]);
+        let nyquist: Vec<f32> = (0..BLOCK)
+            .map(|n| if n % 2 == 0 { 0.5 } else { -0.5 })
+            .collect();
+        let high = render(&nyquist);
+        assert!(
+            (high[BLOCK - 1].abs() - 1.0).abs() < 1e-2,
+            "{}",
+            high[BLOCK - 1]
+        );
+
+        stack.remove_effect(container);
+        assert_eq!(stack.effect_band(0), None);
+    }
+
+    #[test]
+    fn lfo_routes_follow_their_effect_and_sync_to_tempo() {
+        let mut stack = EffectStack::new(BLOCK);
+        stack.set_sample_rate(48_000.0);
+        let compressor = stack.add_effect(doubler());
+        let delay = stack.add_effect(Box::new(crate::nodes::Delay::new(
+            48_000.0, 1_000.0, 250.0, 0.3, 0.5,
+        )));
+        assert!(!stack.set_lfo_route(0, compressor, "time", 5.0));
+        assert!(!stack.set_lfo_route(EFFECT_LFO_COUNT, delay, "time", 5.0));
+        assert!(stack.set_lfo_route(0, delay, "time", 5.0));
+        assert!(stack.set_lfo_route(1, delay, "time", 2.0));
+        assert_eq!(stack.lfo_routes.len(), 1);
+
+        stack.remove_effect(compressor);
+        assert_eq!(stack.lfo_routes[0].effect, 0);
+        stack.remove_effect(0);
+        assert!(stack.lfo_routes.is_empty());
+
+        // One beat at 120 bpm is 24_000 samples: a quarter cycle at 4 beats.
+        stack.set_tempo(120.0);
+        stack.set_lfo(0, LfoWaveform::Saw, 10.0, Some(4.0));
+        stack.apply_lfos(24_000);
+        assert!((stack.lfos[0].phase - 0.25).abs() < 1e-5);
+    }
+
+    #[test]
+    fn parallel_chains_blend_with_the_untouched_input() {
+        let mut stack = EffectStack::new(BLOCK);
+        let container = stack.add_effect(Box::new(Parallel::new(48_000.0, 0.5)));
+        let member = stack.add_effect(doubler());
+        assert!(stack.set_effect_band(member, Some(ContainerSlot { container, band: 1 })));
+        assert!(!stack.set_effect_band(member, Some(ContainerSlot { container, band: 2 })));
+
+        let input = vec![0.5; BLOCK];
+        let mut left = vec![0.0; BLOCK];
+        let mut right = vec![0.0; BLOCK];
+        stack.process_audio(&input, &input, &mut left, &mut right);
+        // Half the dry chain plus half the doubled one.
+        assert!((left[0] - 0.75).abs() < 1e-4, "{}", left[0]);
+        assert_eq!(left, right);
+    }
+}
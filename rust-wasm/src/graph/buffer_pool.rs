@@ -95,6 +95,23 @@ impl AudioBufferPool {
         Ok(result)
     }
 
+    /// Grows the pool to at least `total` buffers so later acquires up to that
+    /// many don't allocate.
+    pub fn reserve(&mut self, total: usize, buffer_size: usize) {
+        while self.buffers.len() < total {
+            self.available.push(self.buffers.len());
+            self.buffers.push(vec![0.0; buffer_size]);
+        }
+        self.available
+            .reserve(total.saturating_sub(self.available.len()));
+        self.in_use.reserve(total.saturating_sub(self.in_use.len()));
+    }
+
+    /// Buffers that can be acquired without allocating.
+    pub fn available_count(&self) -> usize {
+        self.available.len()
+    }
+
     pub fn release(&mut self, index: usize) {
         if self.in_use.remove(&index) {
             self.available.push(index);
@@ -189,4 +206,10 @@ impl AudioBufferPool {
 
         Ok((immutable_refs, mutable_refs))
     }
+
+    /// Bytes held by every buffer in the pool, in use or not.
+    pub fn memory_bytes(&self) -> usize {
+        let samples: usize = self.buffers.iter().map(|buffer| buffer.capacity()).sum();
+        samples * std::mem::size_of::<f32>()
+    }
 }
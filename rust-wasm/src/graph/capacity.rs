@@ -0,0 +1,99 @@
+// src/graph/capacity.rs
+//
+// Fixed capacities for strict real-time mode. The host declares the largest
+// session it will run up front; graphs and the engine preallocate for it and
+// refuse anything bigger instead of growing, reporting a `CapacityExceeded`
+// so the refusal shows up in diagnostics rather than as a dropout.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Refusals kept when the host never drains the queue.
+pub const MAX_PENDING_CAPACITY_EVENTS: usize = 64;
+/// Buffers preallocated per node: one per port, and few nodes have more.
+pub const BUFFERS_PER_NODE: usize = 8;
+
+/// The largest session strict real-time mode has to hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapacityLimits {
+    pub max_voices: usize,
+    /// Nodes per voice, including the global nodes every voice starts with.
+    pub max_nodes: usize,
+    /// Connections per voice.
+    pub max_connections: usize,
+    /// Samples per processing block.
+    pub max_block_size: usize,
+    /// Modulation sources feeding any one input port.
+    pub max_sources_per_port: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CapacityResource {
+    Voices,
+    Nodes,
+    Connections,
+    BlockSize,
+    SourcesPerPort,
+    /// Port buffers; nodes with unusually many ports can run out before
+    /// `max_nodes` is reached.
+    Buffers,
+}
+
+/// An operation refused because it needed more than the declared capacity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapacityExceeded {
+    pub resource: CapacityResource,
+    pub requested: usize,
+    pub limit: usize,
+}
+
+impl fmt::Display for CapacityExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} capacity exceeded: {} requested, limit {}",
+            self.resource, self.requested, self.limit
+        )
+    }
+}
+
+impl CapacityLimits {
+    pub fn limit(&self, resource: CapacityResource) -> usize {
+        match resource {
+            CapacityResource::Voices => self.max_voices,
+            CapacityResource::Nodes => self.max_nodes,
+            CapacityResource::Connections => self.max_connections,
+            CapacityResource::BlockSize => self.max_block_size,
+            CapacityResource::SourcesPerPort => self.max_sources_per_port,
+            CapacityResource::Buffers => self.max_nodes * BUFFERS_PER_NODE,
+        }
+    }
+
+    pub fn check(
+        &self,
+        resource: CapacityResource,
+        requested: usize,
+    ) -> Result<(), CapacityExceeded> {
+        let limit = self.limit(resource);
+        if requested <= limit {
+            Ok(())
+        } else {
+            Err(CapacityExceeded {
+                resource,
+                requested,
+                limit,
+            })
+        }
+    }
+}
+
+/// Queues a refusal without growing the queue past its preallocated size.
+pub fn push_capacity_event(events: &mut Vec<CapacityExceeded>, event: CapacityExceeded) {
+    if events.len() < MAX_PENDING_CAPACITY_EVENTS {
+        events.push(event);
+    }
+}
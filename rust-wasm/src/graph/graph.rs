@@ -33,12 +33,19 @@ use rustc_hash::{FxHashMap, FxHashSet};
 ///
 /// The system maintains thread safety through Rust's ownership system and provides
 /// real-time safety by avoiding allocations in the processing path. It supports
-/// arbitrary node graphs as long as they don't contain feedback loops.
+/// arbitrary node graphs as long as they don't contain feedback loops; connections
+/// that would close one are refused (see `topology` for the limits).
 ///
 use super::{
     buffer_pool::AudioBufferPool,
+    capacity::{
+        push_capacity_event, CapacityExceeded, CapacityLimits, CapacityResource,
+        MAX_PENDING_CAPACITY_EVENTS,
+    },
+    health::{NodeHealthEvent, NodeHealthMonitor},
+    topology::{closes_cycle, ConnectionError, MAX_SOURCES_PER_PORT},
     types::{Connection, ConnectionKey, ModulationTransformation, NodeId},
-    ModulationSource,
+    ModulationRange, ModulationSource,
 };
 #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
 use web_sys::console;
@@ -51,24 +58,26 @@ fn log_console(message: &str) {
 #[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
 fn log_console(_message: &str) {}
 
-#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
-fn log_error(message: &str) {
-    console::error_1(&message.into());
-}
-
-#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
-fn log_error(message: &str) {
-    eprintln!("{message}");
-}
+use crate::utils::analog_spread::VoiceVariation;
+use crate::utils::error_sink::report_error;
+use crate::utils::groove::Groove;
 use crate::{
     graph::ModulationType,
-    nodes::{GateMixer, GlobalFrequencyNode, GlobalVelocityNode},
+    nodes::{
+        Clock, ExpressionKind, GateMixer, GlobalExpressionNode, GlobalFrequencyNode,
+        GlobalVelocityNode, TransportClock,
+    },
 };
-use crate::{AudioNode, MacroManager, PortId};
+use crate::{AudioNode, MacroManager, PortId, QualityMode};
 
 pub struct AudioGraph {
     pub(crate) nodes: FxHashMap<NodeId, Box<dyn AudioNode>>,
     pub(crate) connections: FxHashMap<ConnectionKey, Connection>,
+    // Right-channel links added by the stereo convention rather than by the caller;
+    // only these go away together with their left link.
+    pub(crate) implied_stereo_links: FxHashSet<ConnectionKey>,
+    // Whether left links between stereo-capable nodes imply the right one.
+    pub(crate) stereo: bool,
     pub(crate) processing_order: Vec<NodeId>,
     pub(crate) buffer_size: usize,
     pub(crate) buffer_pool: AudioBufferPool,
@@ -90,8 +99,25 @@ pub struct AudioGraph {
     pub(crate) global_frequency_node: Option<NodeId>,
     pub(crate) global_glide_node: Option<NodeId>,
     pub(crate) global_velocity_node: Option<NodeId>,
+    pub(crate) global_pressure_node: Option<NodeId>,
+    pub(crate) global_timbre_node: Option<NodeId>,
+    pub(crate) global_release_velocity_node: Option<NodeId>,
     pub(crate) global_gatemixer_node: Option<NodeId>,
     pub(crate) output_node: Option<NodeId>,
+    // Graph-wide parameter smoothing time, and per-node overrides of it.
+    pub(crate) smoothing_time_ms: Option<f32>,
+    pub(crate) smoothing_overrides: FxHashMap<NodeId, f32>,
+    pub(crate) quality_mode: QualityMode,
+    // Graph-wide groove template, and per-node overrides of it.
+    pub(crate) groove: Option<Groove>,
+    pub(crate) groove_overrides: FxHashMap<NodeId, Groove>,
+    // This voice's analog spread offsets.
+    pub(crate) voice_variation: VoiceVariation,
+    // Output checks for diagnostics mode; `None` while diagnostics are off.
+    pub(crate) health_monitor: Option<NodeHealthMonitor>,
+    // Strict real-time mode: fixed capacities, and the edits refused for them.
+    pub(crate) capacity: Option<CapacityLimits>,
+    pub(crate) capacity_events: Vec<CapacityExceeded>,
 }
 
 impl AudioGraph {
@@ -102,6 +128,8 @@ impl AudioGraph {
         let mut graph = Self {
             nodes: FxHashMap::default(),
             connections: FxHashMap::default(),
+            implied_stereo_links: FxHashSet::default(),
+            stereo: false,
             processing_order: Vec::new(),
             buffer_size,
             buffer_pool,
@@ -112,8 +140,20 @@ impl AudioGraph {
             global_frequency_node: None,
             global_glide_node: None,
             global_velocity_node: None,
+            global_pressure_node: None,
+            global_timbre_node: None,
+            global_release_velocity_node: None,
             global_gatemixer_node: None,
             output_node: None,
+            smoothing_time_ms: None,
+            smoothing_overrides: FxHashMap::default(),
+            quality_mode: QualityMode::default(),
+            groove: None,
+            groove_overrides: FxHashMap::default(),
+            voice_variation: VoiceVariation::default(),
+            health_monitor: None,
+            capacity: None,
+            capacity_events: Vec::new(),
         };
 
         // Create and add the GlobalVelocityNode:
@@ -124,6 +164,22 @@ impl AudioGraph {
         let global_node = Box::new(GlobalFrequencyNode::new(440.0, buffer_size));
         let global_node_id = graph.add_node(global_node);
         graph.global_frequency_node = Some(global_node_id);
+        // Create and add the note-expression nodes (pressure / timbre / release velocity):
+        let pressure_node = Box::new(GlobalExpressionNode::new(
+            ExpressionKind::Pressure,
+            buffer_size,
+        ));
+        graph.global_pressure_node = Some(graph.add_node(pressure_node));
+        let timbre_node = Box::new(GlobalExpressionNode::new(
+            ExpressionKind::Timbre,
+            buffer_size,
+        ));
+        graph.global_timbre_node = Some(graph.add_node(timbre_node));
+        let release_velocity_node = Box::new(GlobalExpressionNode::new(
+            ExpressionKind::ReleaseVelocity,
+            buffer_size,
+        ));
+        graph.global_release_velocity_node = Some(graph.add_node(release_velocity_node));
 
         let gate_mixer = Box::new(GateMixer::new());
         let gate_mixer_id = graph.add_node(gate_mixer);
@@ -142,8 +198,14 @@ impl AudioGraph {
 
         // Clear graph structures.
         self.connections.clear();
+        self.implied_stereo_links.clear();
         self.input_connections.clear();
         self.nodes.clear();
+        self.smoothing_overrides.clear();
+        self.groove_overrides.clear();
+        if let Some(monitor) = &mut self.health_monitor {
+            monitor.clear();
+        }
         self.processing_order.clear();
         self.node_buffers.clear();
         self.temp_buffer_indices.clear();
@@ -152,6 +214,9 @@ impl AudioGraph {
         self.global_frequency_node = None;
         self.global_glide_node = None;
         self.global_velocity_node = None;
+        self.global_pressure_node = None;
+        self.global_timbre_node = None;
+        self.global_release_velocity_node = None;
         self.global_gatemixer_node = None;
         self.output_node = None;
 
@@ -172,9 +237,24 @@ impl AudioGraph {
     /// corresponding nodes across different voices share the same
     /// logical identifier, while still keeping the graph's internal
     /// representation based on NodeId keys.
-    pub fn add_node_with_id(&mut self, id: NodeId, node: Box<dyn AudioNode>) {
-        // Allocate buffers for each port.
+    pub fn add_node_with_id(&mut self, id: NodeId, mut node: Box<dyn AudioNode>) {
         let ports = node.get_ports();
+        if let Err(exceeded) = self.check_node_capacity(id, ports.len()) {
+            push_capacity_event(&mut self.capacity_events, exceeded);
+            return;
+        }
+
+        let smoothing = self.smoothing_overrides.get(&id).copied();
+        if let Some(time_ms) = smoothing.or(self.smoothing_time_ms) {
+            node.set_smoothing_time_ms(time_ms);
+        }
+        node.set_quality_mode(self.quality_mode);
+        if let Some(groove) = self.groove_overrides.get(&id).or(self.groove.as_ref()) {
+            node.set_groove(groove);
+        }
+        node.set_voice_variation(&self.voice_variation);
+
+        // Allocate buffers for each port.
         for (port, _) in &ports {
             let buffer_idx = self.buffer_pool.acquire(self.buffer_size);
             self.node_buffers.insert((id, *port), buffer_idx);
@@ -205,6 +285,36 @@ impl AudioGraph {
             }
         }
 
+        // Auto-connect the GlobalVelocity node if the new node accepts it.
+        if ports.contains_key(&PortId::GlobalVelocity) {
+            if let Some(global_velocity_id) = self.global_velocity_node {
+                self.add_connection(Connection {
+                    from_node: global_velocity_id,
+                    from_port: PortId::AudioOutput0,
+                    to_node: id,
+                    to_port: PortId::GlobalVelocity,
+                    amount: 1.0,
+                    modulation_type: ModulationType::Additive,
+                    modulation_transform: ModulationTransformation::None,
+                });
+            }
+        }
+
+        // Auto-connect the release velocity node if the new node accepts it.
+        if ports.contains_key(&PortId::ReleaseVelocity) {
+            if let Some(release_velocity_id) = self.global_release_velocity_node {
+                self.add_connection(Connection {
+                    from_node: release_velocity_id,
+                    from_port: PortId::AudioOutput0,
+                    to_node: id,
+                    to_port: PortId::ReleaseVelocity,
+                    amount: 1.0,
+                    modulation_type: ModulationType::Additive,
+                    modulation_transform: ModulationTransformation::None,
+                });
+            }
+        }
+
         // Auto-connect the GateMixer node if the new node accepts CombinedGate.
         if ports.contains_key(&PortId::CombinedGate) {
             if let Some(gate_mixer_node_id) = self.global_gatemixer_node {
@@ -227,10 +337,91 @@ impl AudioGraph {
         id
     }
 
+    /// Sets the parameter smoothing time for every node without its own override,
+    /// including nodes added later.
+    pub fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.smoothing_time_ms = Some(time_ms);
+        for (id, node) in self.nodes.iter_mut() {
+            if !self.smoothing_overrides.contains_key(id) {
+                node.set_smoothing_time_ms(time_ms);
+            }
+        }
+    }
+
+    /// Applies a quality mode to every node, including nodes added later.
+    pub fn set_quality_mode(&mut self, mode: QualityMode) {
+        self.quality_mode = mode;
+        for node in self.nodes.values_mut() {
+            node.set_quality_mode(mode);
+        }
+    }
+
+    /// Overrides the smoothing time of a single node. `None` hands the node back
+    /// to the graph-wide setting (if one has been made).
+    pub fn set_node_smoothing_time_ms(&mut self, node_id: NodeId, time_ms: Option<f32>) {
+        match time_ms {
+            Some(time_ms) => {
+                self.smoothing_overrides.insert(node_id, time_ms);
+            }
+            None => {
+                self.smoothing_overrides.remove(&node_id);
+            }
+        }
+        if let Some(time_ms) = time_ms.or(self.smoothing_time_ms) {
+            if let Some(node) = self.nodes.get_mut(&node_id) {
+                node.set_smoothing_time_ms(time_ms);
+            }
+        }
+    }
+
+    /// Sets the groove template for every node without its own override,
+    /// including nodes added later.
+    pub fn set_groove(&mut self, groove: Groove) {
+        self.groove = Some(groove);
+        for (id, node) in self.nodes.iter_mut() {
+            if !self.groove_overrides.contains_key(id) {
+                node.set_groove(&groove);
+            }
+        }
+    }
+
+    /// Hands this voice's analog spread offsets to every node, including
+    /// nodes added later.
+    pub fn set_voice_variation(&mut self, variation: VoiceVariation) {
+        self.voice_variation = variation;
+        for node in self.nodes.values_mut() {
+            node.set_voice_variation(&variation);
+        }
+    }
+
+    /// Overrides the groove of a single node. `None` hands the node back to the
+    /// graph-wide groove (straight if none has been set).
+    pub fn set_node_groove(&mut self, node_id: NodeId, groove: Option<Groove>) {
+        match groove {
+            Some(groove) => {
+                self.groove_overrides.insert(node_id, groove);
+            }
+            None => {
+                self.groove_overrides.remove(&node_id);
+            }
+        }
+        let groove = groove.or(self.groove).unwrap_or_default();
+        if let Some(node) = self.nodes.get_mut(&node_id) {
+            node.set_groove(&groove);
+        }
+    }
+
     pub fn delete_node(&mut self, node_id: NodeId) {
+        self.smoothing_overrides.remove(&node_id);
+        self.groove_overrides.remove(&node_id);
+        if let Some(monitor) = &mut self.health_monitor {
+            monitor.remove_node(node_id);
+        }
         // Remove all connections involving this node
         self.connections
             .retain(|_, conn| conn.from_node != node_id && conn.to_node != node_id);
+        self.implied_stereo_links
+            .retain(|key| key.from_node != node_id && key.to_node != node_id);
 
         // Remove from input_connections
         self.input_connections.remove(&node_id);
@@ -276,13 +467,22 @@ impl AudioGraph {
             connection.to_port,
         );
 
+        if let Err(exceeded) = self.check_connection_capacity(&key) {
+            push_capacity_event(&mut self.capacity_events, exceeded);
+            return;
+        }
+        if let Err(error) = self.check_topology(&connection) {
+            report_error(&format!("add_connection: {}", error));
+            return;
+        }
+
         let source_buffer_idx = match self
             .node_buffers
             .get(&(connection.from_node, connection.from_port))
         {
             Some(&idx) => idx,
             None => {
-                log_error(&format!(
+                report_error(&format!(
                     "add_connection: source buffer not found for node {:?}, port {:?}",
                     connection.from_node, connection.from_port
                 ));
@@ -294,6 +494,8 @@ impl AudioGraph {
         let amount = connection.amount;
 
         self.connections.insert(key.clone(), connection.clone());
+        // A link wired explicitly is the caller's, even if it was implied before.
+        self.implied_stereo_links.remove(&key);
 
         // Update input_connections with the source node included.
         self.input_connections.entry(to_node).or_default().push((
@@ -306,6 +508,171 @@ impl AudioGraph {
         ));
 
         self.update_processing_order();
+
+        // A gate tool feeding a node's gate takes over from the automatic
+        // GateMixer link, so the reshaped gate isn't summed with the raw one.
+        if to_port == PortId::CombinedGate && self.is_gate_tool(connection.from_node) {
+            if let Some(gate_mixer) = self.global_gatemixer_node {
+                self.remove_specific_connection(gate_mixer, to_node, PortId::CombinedGate);
+            }
+        }
+
+        if to_port == PortId::AudioInput1 {
+            self.refresh_stereo_input(to_node);
+        }
+        self.add_stereo_pair(&connection);
+    }
+
+    /// Adds the right-channel link `connection` implies, if any (see
+    /// `stereo_pair_of`) and it isn't wired already.
+    fn add_stereo_pair(&mut self, connection: &Connection) {
+        if let Some(paired) = self.stereo_pair_of(connection) {
+            let paired_key = ConnectionKey::new(
+                paired.from_node,
+                paired.from_port,
+                paired.to_node,
+                paired.to_port,
+            );
+            if !self.connections.contains_key(&paired_key) {
+                self.add_connection(paired);
+                if self.connections.contains_key(&paired_key) {
+                    self.implied_stereo_links.insert(paired_key);
+                }
+            }
+        }
+    }
+
+    /// Stereo voices: links from a stereo source's `AudioOutput0` into a
+    /// stereo-capable `AudioInput0` also carry the right channel. Off by
+    /// default, so mono patches don't run right-channel filters and effects
+    /// for a copy of the left. Turning it on pairs the links already wired,
+    /// turning it off drops the implied ones; right links wired explicitly
+    /// stay either way.
+    pub fn set_stereo(&mut self, enabled: bool) {
+        if enabled == self.stereo {
+            return;
+        }
+        self.stereo = enabled;
+        if enabled {
+            let links: Vec<Connection> = self.connections.values().cloned().collect();
+            for connection in &links {
+                self.add_stereo_pair(connection);
+            }
+        } else {
+            let implied: Vec<Connection> = self
+                .implied_stereo_links
+                .iter()
+                .filter_map(|key| self.connections.get(key).cloned())
+                .collect();
+            for connection in &implied {
+                self.remove_connection(connection);
+            }
+            self.implied_stereo_links.clear();
+        }
+    }
+
+    /// Tells `node_id` whether anything feeds its `AudioInput1`, so nodes with
+    /// per-channel state build or drop their right channel here rather than
+    /// while processing.
+    fn refresh_stereo_input(&mut self, node_id: NodeId) {
+        let connected = self
+            .connections
+            .values()
+            .any(|c| c.to_node == node_id && c.to_port == PortId::AudioInput1);
+        if let Some(node) = self.nodes.get_mut(&node_id) {
+            node.set_stereo_input(connected);
+        }
+    }
+
+    /// Checks that `connection` joins existing ports and stays within the
+    /// topology limits, without adding it.
+    pub fn check_connection(&self, connection: &Connection) -> Result<(), ConnectionError> {
+        for node in [connection.from_node, connection.to_node] {
+            if !self.nodes.contains_key(&node) {
+                return Err(ConnectionError::UnknownNode(node));
+            }
+        }
+        if !self
+            .node_buffers
+            .contains_key(&(connection.from_node, connection.from_port))
+        {
+            return Err(ConnectionError::UnknownPort {
+                node: connection.from_node,
+                port: connection.from_port,
+            });
+        }
+        self.check_topology(connection)
+    }
+
+    /// Refuses feedback loops and fan-in past `MAX_SOURCES_PER_PORT`.
+    /// Reconnecting an existing connection is always allowed.
+    fn check_topology(&self, connection: &Connection) -> Result<(), ConnectionError> {
+        let key = ConnectionKey::new(
+            connection.from_node,
+            connection.from_port,
+            connection.to_node,
+            connection.to_port,
+        );
+        if self.connections.contains_key(&key) {
+            return Ok(());
+        }
+        if closes_cycle(&self.connections, connection.from_node, connection.to_node) {
+            return Err(ConnectionError::Cycle {
+                from: connection.from_node,
+                to: connection.to_node,
+            });
+        }
+        let sources = self
+            .input_connections
+            .get(&connection.to_node)
+            .map_or(0, |inputs| {
+                inputs
+                    .iter()
+                    .filter(|input| input.0 == connection.to_port)
+                    .count()
+            });
+        if sources >= MAX_SOURCES_PER_PORT {
+            return Err(ConnectionError::TooManySources {
+                node: connection.to_node,
+                port: connection.to_port,
+            });
+        }
+        Ok(())
+    }
+
+    fn is_gate_tool(&self, node_id: NodeId) -> bool {
+        self.nodes
+            .get(&node_id)
+            .is_some_and(|n| n.node_type() == "gate_tool")
+    }
+
+    /// Stereo convention: with stereo voices on (see `set_stereo`), a connection
+    /// from a stereo source's `AudioOutput0` into a stereo-capable `AudioInput0`
+    /// implies the matching `AudioOutput1 -> AudioInput1` link, so left/right stay
+    /// paired through the voice graph.
+    fn stereo_pair_of(&self, connection: &Connection) -> Option<Connection> {
+        if !self.stereo
+            || connection.from_port != PortId::AudioOutput0
+            || connection.to_port != PortId::AudioInput0
+        {
+            return None;
+        }
+        let source_is_stereo = self
+            .nodes
+            .get(&connection.from_node)
+            .is_some_and(|n| n.get_ports().get(&PortId::AudioOutput1) == Some(&true));
+        let target_is_stereo = self
+            .nodes
+            .get(&connection.to_node)
+            .is_some_and(|n| n.get_ports().get(&PortId::AudioInput1) == Some(&false));
+        if !source_is_stereo || !target_is_stereo {
+            return None;
+        }
+        Some(Connection {
+            from_port: PortId::AudioOutput1,
+            to_port: PortId::AudioInput1,
+            ..connection.clone()
+        })
     }
 
     pub fn remove_macro_connection(
@@ -336,6 +703,19 @@ impl AudioGraph {
         to_node: NodeId,
         to_port: PortId,
     ) {
+        // Drop the implied right-channel link along with the left one; a right link
+        // wired explicitly stays until it is removed itself.
+        if to_port == PortId::AudioInput0
+            && self.implied_stereo_links.contains(&ConnectionKey::new(
+                from_node,
+                PortId::AudioOutput1,
+                to_node,
+                PortId::AudioInput1,
+            ))
+        {
+            self.remove_specific_connection(from_node, to_node, PortId::AudioInput1);
+        }
+
         // web_sys::console::log_1(
         //     &format!(
         //         "remove_specific_connection - Before remove: connections={:?}, inputs={:?}",
@@ -355,6 +735,7 @@ impl AudioGraph {
             .collect();
 
         for key in to_remove {
+            self.implied_stereo_links.remove(&key);
             self.connections.remove(&key);
         }
 
@@ -391,6 +772,10 @@ impl AudioGraph {
         //     }
         // }
 
+        if to_port == PortId::AudioInput1 {
+            self.refresh_stereo_input(to_node);
+        }
+
         // web_sys::console::log_1(
         //     &format!(
         //         "remove_specific_connection - After remove: connections={:?}, inputs={:?}",
@@ -402,7 +787,24 @@ impl AudioGraph {
 
     /// Removes a connection based on an entire Connection struct.
     pub fn remove_connection(&mut self, connection: &Connection) {
+        if let Some(paired) = self.stereo_pair_of(connection) {
+            if self.implied_stereo_links.contains(&ConnectionKey::new(
+                paired.from_node,
+                paired.from_port,
+                paired.to_node,
+                paired.to_port,
+            )) {
+                self.remove_connection(&paired);
+            }
+        }
+
         // Remove the matching connection from the connections map.
+        self.implied_stereo_links.remove(&ConnectionKey::new(
+            connection.from_node,
+            connection.from_port,
+            connection.to_node,
+            connection.to_port,
+        ));
         self.connections.retain(|_, existing| {
             !(existing.from_node == connection.from_node
                 && existing.to_node == connection.to_node
@@ -443,6 +845,9 @@ impl AudioGraph {
         //     }
         // }
 
+        if connection.to_port == PortId::AudioInput1 {
+            self.refresh_stereo_input(connection.to_node);
+        }
         self.update_processing_order();
     }
 
@@ -460,6 +865,8 @@ impl AudioGraph {
         );
 
         self.connections.insert(key.clone(), connection.clone());
+        // A link wired explicitly is the caller's, even if it was implied before.
+        self.implied_stereo_links.remove(&key);
 
         let source_buffer_idx = self.node_buffers[&(connection.from_node, connection.from_port)];
         let inputs = self
@@ -531,6 +938,9 @@ impl AudioGraph {
             self.connections, self.input_connections
         ));
 
+        if connection.to_port == PortId::AudioInput1 {
+            self.refresh_stereo_input(connection.to_node);
+        }
         self.update_processing_order();
         key
     }
@@ -543,17 +953,54 @@ impl AudioGraph {
         self.nodes.get_mut(&node_id)
     }
 
+    /// The values the connection from `from` into `to`'s `to_port` sweeps the
+    /// parameter behind that port over. None if there's no such connection or
+    /// the destination doesn't describe the port.
+    pub fn modulation_range(
+        &self,
+        from: NodeId,
+        to: NodeId,
+        to_port: PortId,
+    ) -> Option<ModulationRange> {
+        let connection = self.connections.values().find(|connection| {
+            connection.from_node == from
+                && connection.to_node == to
+                && connection.to_port == to_port
+        })?;
+        let target = self.nodes.get(&to)?.modulation_target(to_port)?;
+        let source_range = self.nodes.get(&from)?.output_range();
+        Some(target.range(
+            source_range,
+            connection.amount,
+            connection.modulation_type,
+            connection.modulation_transform,
+        ))
+    }
+
+    /// The buffer a node's output port rendered into during the last block.
+    pub fn node_output(&self, node_id: NodeId, port: PortId) -> Option<&[f32]> {
+        self.node_buffers
+            .get(&(node_id, port))
+            .map(|&idx| self.buffer_pool.copy_out(idx))
+    }
+
     fn update_processing_order(&mut self) {
         let mut in_degree: FxHashMap<NodeId, usize> = FxHashMap::default();
+        let mut outgoing: FxHashMap<NodeId, Vec<NodeId>> = FxHashMap::default();
 
         // Initialize in-degrees for all nodes to 0
         for &node_id in self.nodes.keys() {
             in_degree.insert(node_id, 0);
         }
 
-        // Compute in-degrees for each node.
+        // Compute in-degrees for each node, and each node's destinations so the
+        // walk below stays linear in the size of the graph.
         for conn in self.connections.values() {
             *in_degree.entry(conn.to_node).or_insert(0) += 1;
+            outgoing
+                .entry(conn.from_node)
+                .or_default()
+                .push(conn.to_node);
         }
 
         // Start with all nodes that have no incoming connections.
@@ -569,23 +1016,22 @@ impl AudioGraph {
             self.processing_order.push(node_id);
 
             // For each connection from this node, reduce the in-degree of its destination.
-            for conn in self.connections.values() {
-                if conn.from_node == node_id {
-                    let dest = conn.to_node;
-                    if let Some(degree) = in_degree.get_mut(&dest) {
-                        *degree -= 1;
-                        if *degree == 0 {
-                            queue.push(dest);
-                        }
+            for &dest in outgoing.get(&node_id).into_iter().flatten() {
+                if let Some(degree) = in_degree.get_mut(&dest) {
+                    *degree -= 1;
+                    if *degree == 0 {
+                        queue.push(dest);
                     }
                 }
             }
         }
 
         // If there is a cycle (or some nodes were not reached), add any remaining nodes.
+        // `add_connection` refuses cycles, but `connect` doesn't check.
         if self.processing_order.len() < self.nodes.len() {
+            let ordered: FxHashSet<NodeId> = self.processing_order.iter().copied().collect();
             for &node_id in self.nodes.keys() {
-                if !self.processing_order.contains(&node_id) {
+                if !ordered.contains(&node_id) {
                     self.processing_order.push(node_id);
                 }
             }
@@ -626,6 +1072,43 @@ impl AudioGraph {
         }
     }
 
+    pub fn set_master_tuning(&mut self, transpose: f32, fine_tune: f32) {
+        if let Some(global_node_id) = self.global_frequency_node {
+            if let Some(node) = self.get_node_mut(global_node_id) {
+                if let Some(global_freq_node) =
+                    node.as_any_mut().downcast_mut::<GlobalFrequencyNode>()
+                {
+                    global_freq_node.set_master_tuning(transpose, fine_tune);
+                }
+            }
+        }
+    }
+
+    /// Times the global frequency node's tuning glide against `sample_rate`.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        if let Some(global_node_id) = self.global_frequency_node {
+            if let Some(node) = self.get_node_mut(global_node_id) {
+                if let Some(global_freq_node) =
+                    node.as_any_mut().downcast_mut::<GlobalFrequencyNode>()
+                {
+                    global_freq_node.set_sample_rate(sample_rate);
+                }
+            }
+        }
+    }
+
+    pub fn set_voice_detune(&mut self, cents: f32) {
+        if let Some(global_node_id) = self.global_frequency_node {
+            if let Some(node) = self.get_node_mut(global_node_id) {
+                if let Some(global_freq_node) =
+                    node.as_any_mut().downcast_mut::<GlobalFrequencyNode>()
+                {
+                    global_freq_node.set_voice_detune(cents);
+                }
+            }
+        }
+    }
+
     pub fn set_velocity(&mut self, velocity: &[f32]) {
         if let Some(global_node_id) = self.global_velocity_node {
             if let Some(node) = self.get_node_mut(global_node_id) {
@@ -638,6 +1121,159 @@ impl AudioGraph {
         }
     }
 
+    pub fn set_velocity_ramp(&mut self, start: f32, end: f32) {
+        if let Some(global_node_id) = self.global_velocity_node {
+            if let Some(node) = self.get_node_mut(global_node_id) {
+                if let Some(global_vel_node) =
+                    node.as_any_mut().downcast_mut::<GlobalVelocityNode>()
+                {
+                    global_vel_node.set_velocity_ramp(start, end);
+                }
+            }
+        }
+    }
+
+    pub fn set_pressure(&mut self, pressure: &[f32]) {
+        if let Some(node_id) = self.global_pressure_node {
+            self.set_expression(node_id, pressure);
+        }
+    }
+
+    pub fn set_timbre(&mut self, timbre: &[f32]) {
+        if let Some(node_id) = self.global_timbre_node {
+            self.set_expression(node_id, timbre);
+        }
+    }
+
+    pub fn set_release_velocity(&mut self, release_velocity: &[f32]) {
+        if let Some(node_id) = self.global_release_velocity_node {
+            self.set_expression(node_id, release_velocity);
+        }
+    }
+
+    /// Hands the transport position for the next block to every clock node.
+    pub fn set_transport_clock(&mut self, transport: TransportClock) {
+        for node in self.nodes.values_mut() {
+            if let Some(clock) = node.as_any_mut().downcast_mut::<Clock>() {
+                clock.set_transport(transport);
+            }
+        }
+    }
+
+    /// Turns the per-node output checks of diagnostics mode on or off.
+    pub fn set_health_monitoring(&mut self, enabled: bool) {
+        if !enabled {
+            self.health_monitor = None;
+        } else if self.health_monitor.is_none() {
+            self.health_monitor = Some(NodeHealthMonitor::new());
+        }
+    }
+
+    /// Strict real-time mode. Preallocates the node and connection tables and
+    /// the buffer pool for `limits`; from then on nodes and connections past
+    /// them are refused and reported through `take_capacity_events` instead
+    /// of growing the graph. Fails, leaving the graph as it was, when it
+    /// already holds more than `limits` allow. `None` lifts the limits.
+    pub fn set_capacity_limits(
+        &mut self,
+        limits: Option<CapacityLimits>,
+    ) -> Result<(), CapacityExceeded> {
+        let Some(limits) = limits else {
+            self.capacity = None;
+            return Ok(());
+        };
+        limits.check(CapacityResource::Nodes, self.nodes.len())?;
+        limits.check(CapacityResource::Connections, self.connections.len())?;
+        limits.check(CapacityResource::BlockSize, self.buffer_size)?;
+        for inputs in self.input_connections.values() {
+            for (port, ..) in inputs {
+                let sources = inputs.iter().filter(|input| input.0 == *port).count();
+                limits.check(CapacityResource::SourcesPerPort, sources)?;
+            }
+        }
+
+        let max_nodes = limits.max_nodes;
+        self.nodes
+            .reserve(max_nodes.saturating_sub(self.nodes.len()));
+        self.connections.reserve(
+            limits
+                .max_connections
+                .saturating_sub(self.connections.len()),
+        );
+        self.processing_order
+            .reserve(max_nodes.saturating_sub(self.processing_order.len()));
+        self.input_connections
+            .reserve(max_nodes.saturating_sub(self.input_connections.len()));
+        let max_buffers = limits.limit(CapacityResource::Buffers);
+        self.node_buffers
+            .reserve(max_buffers.saturating_sub(self.node_buffers.len()));
+        let in_use = self.buffer_pool.buffers.len() - self.buffer_pool.available_count();
+        let remaining = max_buffers.saturating_sub(self.node_buffers.len());
+        self.buffer_pool
+            .reserve(in_use + remaining, self.buffer_size);
+        self.capacity_events
+            .reserve(MAX_PENDING_CAPACITY_EVENTS.saturating_sub(self.capacity_events.len()));
+        self.capacity = Some(limits);
+        Ok(())
+    }
+
+    /// Drains the strict real-time refusals since the last call, oldest first.
+    /// The queue keeps its preallocated capacity.
+    pub fn take_capacity_events(&mut self) -> Vec<CapacityExceeded> {
+        self.capacity_events.drain(..).collect()
+    }
+
+    fn check_node_capacity(&self, id: NodeId, ports: usize) -> Result<(), CapacityExceeded> {
+        let Some(limits) = self.capacity else {
+            return Ok(());
+        };
+        if self.nodes.contains_key(&id) {
+            return Ok(());
+        }
+        limits.check(CapacityResource::Nodes, self.nodes.len() + 1)?;
+        let available = self.buffer_pool.available_count();
+        if ports > available {
+            return Err(CapacityExceeded {
+                resource: CapacityResource::Buffers,
+                requested: self.node_buffers.len() + ports,
+                limit: self.node_buffers.len() + available,
+            });
+        }
+        Ok(())
+    }
+
+    fn check_connection_capacity(&self, key: &ConnectionKey) -> Result<(), CapacityExceeded> {
+        let Some(limits) = self.capacity else {
+            return Ok(());
+        };
+        if self.connections.contains_key(key) {
+            return Ok(());
+        }
+        limits.check(CapacityResource::Connections, self.connections.len() + 1)?;
+        let sources = self
+            .input_connections
+            .get(&key.to_node)
+            .map_or(0, |inputs| {
+                inputs.iter().filter(|input| input.0 == key.to_port).count()
+            });
+        limits.check(CapacityResource::SourcesPerPort, sources + 1)
+    }
+
+    /// Drains the health events found since the last call, oldest first.
+    pub fn take_health_events(&mut self) -> Vec<NodeHealthEvent> {
+        self.health_monitor
+            .as_mut()
+            .map_or_else(Vec::new, NodeHealthMonitor::take_events)
+    }
+
+    fn set_expression(&mut self, node_id: NodeId, values: &[f32]) {
+        if let Some(node) = self.get_node_mut(node_id) {
+            if let Some(expr_node) = node.as_any_mut().downcast_mut::<GlobalExpressionNode>() {
+                expr_node.set_values(values);
+            }
+        }
+    }
+
     pub fn process_audio(&mut self, output_left: &mut [f32], output_right: &mut [f32]) {
         self.process_audio_with_macros(None, output_left, output_right);
     }
@@ -721,7 +1357,7 @@ impl AudioGraph {
             {
                 Ok((inputs, outputs)) => (inputs, outputs),
                 Err(e) => {
-                    log_error(&format!(
+                    report_error(&format!(
                         "Error getting buffers for node {:?}: {}",
                         node_id, e
                     ));
@@ -788,6 +1424,10 @@ impl AudioGraph {
                             mgr.apply_modulation(0, data, &mut outputs);
                         }
                     }
+
+                    if let Some(monitor) = &mut self.health_monitor {
+                        monitor.check(node_id, &inputs, &outputs, self.buffer_size);
+                    }
                 }
             } else {
                 // Node has no outputs, just process it (might have side effects?)
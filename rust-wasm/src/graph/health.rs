@@ -0,0 +1,235 @@
+// src/graph/health.rs
+//
+// Per-node output checks for diagnostics mode. When a graph has a
+// `NodeHealthMonitor`, every node's output buffers are inspected after it
+// runs: outputs carrying NaN or infinite samples are flagged at once, and
+// outputs that stay frozen, at zero or at a DC level, for about a second while
+// the node's inputs keep moving are flagged as silent or stuck. Each problem is
+// reported once when it appears and again only after the output has recovered.
+
+use rustc_hash::FxHashMap;
+use serde::{Serialize, Serializer};
+
+use super::{ModulationSource, NodeId};
+use crate::traits::PortId;
+
+/// Samples an output must stay frozen before it is flagged (one second at 48 kHz).
+const STUCK_SAMPLES: usize = 48_000;
+/// Peak-to-peak range below which a buffer counts as frozen.
+const FLAT_EPSILON: f32 = 1e-6;
+/// Level below which a frozen output counts as silent rather than stuck at DC.
+const SILENCE_LEVEL: f32 = 1e-5;
+/// Events kept when the host never drains the queue.
+const MAX_PENDING_EVENTS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NodeHealthIssue {
+    /// The output produced NaN or infinite samples.
+    NonFinite,
+    /// The output held a constant non-zero level while the inputs moved.
+    StuckDc,
+    /// The output stayed silent while the inputs moved.
+    Silent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeHealthEvent {
+    pub node_id: NodeId,
+    #[serde(serialize_with = "serialize_port")]
+    pub port: PortId,
+    pub issue: NodeHealthIssue,
+    /// The offending sample for `NonFinite`, otherwise the level the output
+    /// is frozen at.
+    pub value: f32,
+}
+
+fn serialize_port<S: Serializer>(port: &PortId, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u32(*port as u32)
+}
+
+#[derive(Debug, Default)]
+struct OutputHealth {
+    frozen_samples: usize,
+    flagged: Option<NodeHealthIssue>,
+}
+
+#[derive(Debug, Default)]
+pub struct NodeHealthMonitor {
+    outputs: FxHashMap<(NodeId, PortId), OutputHealth>,
+    events: Vec<NodeHealthEvent>,
+}
+
+impl NodeHealthMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inspects the first `len` samples of a node's outputs after it ran.
+    pub fn check(
+        &mut self,
+        node_id: NodeId,
+        inputs: &FxHashMap<PortId, Vec<ModulationSource>>,
+        outputs: &FxHashMap<PortId, &mut [f32]>,
+        len: usize,
+    ) {
+        let mut inputs_moving = None;
+        for (&port, output) in outputs {
+            let samples = &output[..len.min(output.len())];
+            if samples.is_empty() {
+                continue;
+            }
+            let health = self.outputs.entry((node_id, port)).or_default();
+
+            if let Some(&bad) = samples.iter().find(|s| !s.is_finite()) {
+                health.frozen_samples = 0;
+                let issue = NodeHealthIssue::NonFinite;
+                Self::flag(&mut self.events, health, node_id, port, issue, bad);
+                continue;
+            }
+
+            let (min, max) = peak_range(samples);
+            let frozen = max - min < FLAT_EPSILON
+                && *inputs_moving.get_or_insert_with(|| any_input_moving(inputs, len));
+            if !frozen {
+                health.frozen_samples = 0;
+                health.flagged = None;
+                continue;
+            }
+
+            health.frozen_samples += samples.len();
+            if health.frozen_samples >= STUCK_SAMPLES {
+                let issue = if max.abs() < SILENCE_LEVEL {
+                    NodeHealthIssue::Silent
+                } else {
+                    NodeHealthIssue::StuckDc
+                };
+                Self::flag(&mut self.events, health, node_id, port, issue, max);
+            }
+        }
+    }
+
+    fn flag(
+        events: &mut Vec<NodeHealthEvent>,
+        health: &mut OutputHealth,
+        node_id: NodeId,
+        port: PortId,
+        issue: NodeHealthIssue,
+        value: f32,
+    ) {
+        if health.flagged == Some(issue) {
+            return;
+        }
+        health.flagged = Some(issue);
+        if events.len() >= MAX_PENDING_EVENTS {
+            events.remove(0);
+        }
+        events.push(NodeHealthEvent {
+            node_id,
+            port,
+            issue,
+            value,
+        });
+    }
+
+    /// Forgets the history of a deleted node.
+    pub fn remove_node(&mut self, node_id: NodeId) {
+        self.outputs.retain(|&(id, _), _| id != node_id);
+    }
+
+    /// Forgets every node's history; queued events are kept.
+    pub fn clear(&mut self) {
+        self.outputs.clear();
+    }
+
+    /// Drains the queued events, oldest first.
+    pub fn take_events(&mut self) -> Vec<NodeHealthEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+fn peak_range(samples: &[f32]) -> (f32, f32) {
+    samples
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &s| {
+            (min.min(s), max.max(s))
+        })
+}
+
+fn any_input_moving(inputs: &FxHashMap<PortId, Vec<ModulationSource>>, len: usize) -> bool {
+    inputs.values().flatten().any(|source| {
+        let samples = &source.buffer[..len.min(source.buffer.len())];
+        let (min, max) = peak_range(samples);
+        source.amount != 0.0 && max - min >= FLAT_EPSILON
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{ModulationTransformation, ModulationType};
+
+    fn run(
+        monitor: &mut NodeHealthMonitor,
+        node_id: NodeId,
+        input: &[f32],
+        output: &mut [f32],
+        blocks: usize,
+    ) {
+        let mut inputs = FxHashMap::default();
+        inputs.insert(
+            PortId::AudioInput0,
+            vec![ModulationSource {
+                buffer: input,
+                amount: 1.0,
+                mod_type: ModulationType::Additive,
+                transformation: ModulationTransformation::None,
+            }],
+        );
+        let len = output.len();
+        let mut outputs = FxHashMap::default();
+        outputs.insert(PortId::AudioOutput0, output);
+        for _ in 0..blocks {
+            monitor.check(node_id, &inputs, &outputs, len);
+        }
+    }
+
+    #[test]
+    fn flags_frozen_outputs_only_while_inputs_move() {
+        let mut monitor = NodeHealthMonitor::new();
+        let node_id = NodeId::new();
+        let moving: Vec<f32> = (0..128).map(|i| (i as f32 * 0.1).sin()).collect();
+        let still = vec![0.5; 128];
+        let blocks = STUCK_SAMPLES / 128 + 1;
+
+        // A constant output is fine while the inputs are constant too.
+        run(&mut monitor, node_id, &still, &mut [0.0; 128], blocks);
+        assert!(monitor.take_events().is_empty());
+
+        run(&mut monitor, node_id, &moving, &mut [0.0; 128], blocks);
+        let events = monitor.take_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].issue, NodeHealthIssue::Silent);
+
+        // The change from silence to DC is reported; repeats are not.
+        run(&mut monitor, node_id, &moving, &mut [0.3; 128], blocks * 2);
+        let events = monitor.take_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].issue, NodeHealthIssue::StuckDc);
+        assert_eq!(events[0].value, 0.3);
+    }
+
+    #[test]
+    fn flags_non_finite_samples_immediately() {
+        let mut monitor = NodeHealthMonitor::new();
+        let node_id = NodeId::new();
+        let mut output = vec![0.1; 64];
+        output[10] = f32::NAN;
+        run(&mut monitor, node_id, &[0.0; 64], &mut output, 3);
+        let events = monitor.take_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].issue, NodeHealthIssue::NonFinite);
+        assert_eq!(events[0].port, PortId::AudioOutput0);
+    }
+}
@@ -1,13 +1,24 @@
 mod buffer_pool;
+mod capacity;
 mod graph;
+mod health;
 mod modulation_processor;
+mod modulation_range;
 #[cfg(test)]
 mod tests;
+mod topology;
 mod types;
 
 pub use buffer_pool::AudioBufferPool;
+pub use capacity::{
+    push_capacity_event, CapacityExceeded, CapacityLimits, CapacityResource,
+    MAX_PENDING_CAPACITY_EVENTS,
+};
 pub use graph::AudioGraph;
+pub use health::{NodeHealthEvent, NodeHealthIssue, NodeHealthMonitor};
 pub use modulation_processor::ModulationProcessor;
+pub use modulation_range::{ModulationRange, ModulationTarget, TargetMapping};
+pub use topology::{ConnectionError, MAX_SOURCES_PER_PORT};
 pub use types::{
     Connection, ConnectionId, ConnectionKey, ModulationSource, ModulationTransformation,
     ModulationType, NodeId,
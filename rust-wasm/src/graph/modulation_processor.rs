@@ -362,3 +362,184 @@ pub trait ModulationProcessor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{ModulationTarget, TargetMapping};
+    use proptest::prelude::*;
+
+    struct Accumulator;
+
+    impl ModulationProcessor for Accumulator {}
+
+    /// Owned parts of a `ModulationSource`; sources borrow their buffers.
+    type Source = (Vec<f32>, f32, ModulationType, ModulationTransformation);
+
+    const TRANSFORMS: [ModulationTransformation; 4] = [
+        ModulationTransformation::None,
+        ModulationTransformation::Invert,
+        ModulationTransformation::Square,
+        ModulationTransformation::Cube,
+    ];
+
+    fn mod_type() -> impl Strategy<Value = ModulationType> {
+        (0..3i32).prop_map(ModulationType::from_i32)
+    }
+
+    fn transform() -> impl Strategy<Value = ModulationTransformation> {
+        (0..4i32).prop_map(ModulationTransformation::from_i32)
+    }
+
+    /// Buffers long and short enough to hit both the SIMD chunks and the
+    /// scalar remainder, with levels and amounts in the ranges patches use.
+    fn source() -> impl Strategy<Value = Source> {
+        (
+            prop::collection::vec(-10.0f32..10.0, 0..40),
+            -10_000.0f32..10_000.0,
+            mod_type(),
+            transform(),
+        )
+    }
+
+    fn accumulate(buffer_size: usize, sources: &[Source]) -> (Vec<f32>, Vec<f32>) {
+        let sources: Vec<ModulationSource> = sources
+            .iter()
+            .map(
+                |(buffer, amount, mod_type, transformation)| ModulationSource {
+                    buffer,
+                    amount: *amount,
+                    mod_type: *mod_type,
+                    transformation: *transformation,
+                },
+            )
+            .collect();
+        let mut additive = vec![0.0; buffer_size];
+        let mut multiplicative = vec![1.0; buffer_size];
+        Accumulator::accumulate_modulations_inplace(
+            buffer_size,
+            Some(&sources),
+            &mut additive,
+            &mut multiplicative,
+        );
+        (additive, multiplicative)
+    }
+
+    proptest! {
+        #[test]
+        fn silent_vca_sources_silence_the_output(
+            others in prop::collection::vec(source(), 0..4),
+            len in 1usize..40,
+            amount in -10_000.0f32..10_000.0,
+            base in -1_000.0f32..1_000.0,
+            transform in prop::sample::select(vec![
+                ModulationTransformation::None,
+                ModulationTransformation::Square,
+                ModulationTransformation::Cube,
+            ]),
+        ) {
+            let mut sources = others;
+            sources.push((vec![0.0; len], amount, ModulationType::VCA, transform));
+            let (additive, multiplicative) = accumulate(len, &sources);
+            for i in 0..len {
+                prop_assert_eq!((base + additive[i]) * multiplicative[i], 0.0);
+            }
+        }
+
+        #[test]
+        fn additive_sources_commute(
+            buffers in prop::collection::vec(prop::collection::vec(-10.0f32..10.0, 40), 1..5),
+            amounts in prop::collection::vec(-10_000.0f32..10_000.0, 5),
+            transforms in prop::collection::vec(transform(), 5),
+        ) {
+            let sources: Vec<Source> = buffers
+                .into_iter()
+                .zip(amounts.into_iter().zip(transforms))
+                .map(|(buffer, (amount, transform))| {
+                    (buffer, amount, ModulationType::Additive, transform)
+                })
+                .collect();
+            let mut reversed = sources.clone();
+            reversed.reverse();
+            let (forward, _) = accumulate(40, &sources);
+            let (backward, _) = accumulate(40, &reversed);
+            // Summation order only moves the last few bits of the largest terms,
+            // which may mostly cancel out in the sum.
+            let mut magnitude = vec![0.0f32; 40];
+            for source in &sources {
+                let (term, _) = accumulate(40, std::slice::from_ref(source));
+                for (total, x) in magnitude.iter_mut().zip(&term) {
+                    *total += x.abs();
+                }
+            }
+            for ((a, b), scale) in forward.iter().zip(&backward).zip(&magnitude) {
+                prop_assert!((a - b).abs() <= 1e-5 * scale.max(1.0));
+            }
+        }
+
+        #[test]
+        fn transforms_stay_in_their_ranges(x in -1.0f32..=1.0) {
+            for transform in TRANSFORMS {
+                let y = transform.apply(x);
+                let (low, high) = match transform {
+                    ModulationTransformation::Invert => (0.0, 2.0),
+                    ModulationTransformation::Square => (0.0, 1.0),
+                    _ => (-1.0, 1.0),
+                };
+                prop_assert!((low..=high).contains(&y), "{:?}({}) = {}", transform, x, y);
+                // Unipolar sources stay unipolar.
+                if x >= 0.0 {
+                    prop_assert!((0.0..=1.0).contains(&y));
+                }
+                prop_assert_eq!(Accumulator::transform_scalar(x, transform), y);
+                let simd = Accumulator::transform_simd::<4>(
+                    Simd::splat(x),
+                    transform,
+                    Simd::splat(1.0),
+                );
+                prop_assert_eq!(simd, Simd::splat(y));
+            }
+        }
+
+        #[test]
+        fn targets_clamp_to_their_declared_range(
+            // Wide enough to push past both ends, small enough that an octave
+            // mapping stays finite.
+            additive in -10.0f32..10.0,
+            multiplicative in -100.0f32..100.0,
+            base in 1.0f32..20_000.0,
+            octaves in 0.0f32..10.0,
+        ) {
+            for mapping in [TargetMapping::Linear, TargetMapping::Octaves(octaves)] {
+                let target = ModulationTarget {
+                    parameter: "cutoff",
+                    unit: "Hz",
+                    base,
+                    min: 20.0,
+                    max: 20_000.0,
+                    mapping,
+                };
+                let value = target.apply(additive, multiplicative);
+                prop_assert!((20.0..=20_000.0).contains(&value), "{:?}: {}", mapping, value);
+            }
+        }
+
+        #[test]
+        fn finite_inputs_never_produce_nan(
+            sources in prop::collection::vec(source(), 0..5),
+            buffer_size in 0usize..40,
+            base in -1_000.0f32..1_000.0,
+        ) {
+            let (additive, multiplicative) = accumulate(buffer_size, &sources);
+            let mut combined = vec![0.0; buffer_size];
+            Accumulator::combine_modulation_inplace(
+                &mut combined,
+                buffer_size,
+                base,
+                &additive,
+                &multiplicative,
+            );
+            prop_assert!(combined.iter().all(|value| value.is_finite()), "{:?}", combined);
+        }
+    }
+}
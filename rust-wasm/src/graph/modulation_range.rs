@@ -0,0 +1,143 @@
+// Depth readouts for modulation connections. A destination node describes how
+// a modulation input maps onto the parameter behind it (`ModulationTarget`);
+// combined with the range its source outputs and the connection's amount, type
+// and transform, that gives the values the parameter actually sweeps, e.g. a
+// cutoff moving between 200 Hz and 4.2 kHz.
+
+use serde::Serialize;
+
+use super::{ModulationTransformation, ModulationType};
+
+/// Source values sampled across the source's range; transforms such as
+/// squaring aren't monotonic over bipolar ranges, so the end points alone
+/// aren't enough.
+const RANGE_STEPS: usize = 64;
+
+/// How additive modulation changes a parameter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TargetMapping {
+    /// Added to the parameter in its own units.
+    Linear,
+    /// +1.0 raises the parameter by this many octaves, -1.0 lowers it.
+    Octaves(f32),
+}
+
+/// The parameter a modulation input drives, as the destination node sees it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModulationTarget {
+    pub parameter: &'static str,
+    /// Display unit, e.g. "Hz" or "st"; empty for plain factors.
+    pub unit: &'static str,
+    /// The parameter's value without modulation.
+    pub base: f32,
+    pub min: f32,
+    pub max: f32,
+    pub mapping: TargetMapping,
+}
+
+/// The values a parameter takes under one modulation connection.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModulationRange {
+    pub parameter: &'static str,
+    pub unit: &'static str,
+    pub base: f32,
+    pub low: f32,
+    pub high: f32,
+}
+
+impl ModulationTarget {
+    /// The range swept while a source moving over `source_range` drives the
+    /// parameter through a connection with the given settings. Other
+    /// modulation of the same input is left out.
+    pub fn range(
+        &self,
+        source_range: (f32, f32),
+        amount: f32,
+        modulation_type: ModulationType,
+        transformation: ModulationTransformation,
+    ) -> ModulationRange {
+        let (from, to) = source_range;
+        let (mut low, mut high) = (f32::INFINITY, f32::NEG_INFINITY);
+        for step in 0..=RANGE_STEPS {
+            let source = from + (to - from) * step as f32 / RANGE_STEPS as f32;
+            let value = self.value(transformation.apply(source) * amount, modulation_type);
+            low = low.min(value);
+            high = high.max(value);
+        }
+        ModulationRange {
+            parameter: self.parameter,
+            unit: self.unit,
+            base: self.base,
+            low,
+            high,
+        }
+    }
+
+    /// The parameter under accumulated modulation, following the
+    /// `(base + additive) * multiplicative` combination every node uses.
+    pub fn apply(&self, additive: f32, multiplicative: f32) -> f32 {
+        let value = match self.mapping {
+            TargetMapping::Linear => (self.base + additive) * multiplicative,
+            TargetMapping::Octaves(octaves) => {
+                self.base * (additive * octaves).exp2() * multiplicative
+            }
+        };
+        value.clamp(self.min, self.max)
+    }
+
+    /// The parameter for one weighted modulation value.
+    fn value(&self, weighted: f32, modulation_type: ModulationType) -> f32 {
+        match modulation_type {
+            ModulationType::VCA => self.apply(0.0, weighted),
+            ModulationType::Bipolar => self.apply(0.0, 1.0 + weighted),
+            ModulationType::Additive => self.apply(weighted, 1.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_follow_the_destination_mapping() {
+        let cutoff = ModulationTarget {
+            parameter: "cutoff",
+            unit: "Hz",
+            base: 1_000.0,
+            min: 10.0,
+            max: 20_000.0,
+            mapping: TargetMapping::Octaves(4.0),
+        };
+        let range = cutoff.range(
+            (-1.0, 1.0),
+            0.5,
+            ModulationType::Additive,
+            ModulationTransformation::None,
+        );
+        assert!((range.low - 250.0).abs() < 0.01);
+        assert!((range.high - 4_000.0).abs() < 0.1);
+
+        // Squaring folds a bipolar source onto 0..1.
+        let range = cutoff.range(
+            (-1.0, 1.0),
+            1.0,
+            ModulationType::VCA,
+            ModulationTransformation::Square,
+        );
+        assert_eq!((range.low, range.high), (10.0, 1_000.0));
+
+        let gain = ModulationTarget {
+            mapping: TargetMapping::Linear,
+            ..cutoff
+        };
+        let range = gain.range(
+            (0.0, 1.0),
+            15_000.0,
+            ModulationType::Additive,
+            ModulationTransformation::None,
+        );
+        assert_eq!((range.low, range.high), (1_000.0, 16_000.0));
+    }
+}
@@ -0,0 +1,243 @@
+// src/graph/topology.rs
+//
+// Limits on the shape of a voice graph. Nodes run once per block in
+// topological order, so a connection that would close a loop has no order to
+// run in and is refused, as is fan-in past `MAX_SOURCES_PER_PORT`. Node count
+// and chain depth are bounded only by memory: ordering and the loop check walk
+// the graph iteratively, in time linear in its nodes and connections.
+
+use std::fmt;
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use super::types::{Connection, ConnectionKey, NodeId};
+use crate::traits::PortId;
+
+/// Connections feeding any one input port.
+pub const MAX_SOURCES_PER_PORT: usize = 256;
+
+/// Why a connection can't be added to a graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionError {
+    UnknownNode(NodeId),
+    /// The source node has no such output.
+    UnknownPort {
+        node: NodeId,
+        port: PortId,
+    },
+    /// `to` already feeds `from`, directly or through other nodes.
+    Cycle {
+        from: NodeId,
+        to: NodeId,
+    },
+    TooManySources {
+        node: NodeId,
+        port: PortId,
+    },
+}
+
+impl fmt::Display for ConnectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionError::UnknownNode(node) => write!(f, "Node {} not found", node.0),
+            ConnectionError::UnknownPort { node, port } => {
+                write!(f, "Node {} has no {:?} output", node.0, port)
+            }
+            ConnectionError::Cycle { from, to } => write!(
+                f,
+                "Connecting {} to {} would create a feedback loop",
+                from.0, to.0
+            ),
+            ConnectionError::TooManySources { node, port } => write!(
+                f,
+                "{:?} of node {} already has {} sources",
+                port, node.0, MAX_SOURCES_PER_PORT
+            ),
+        }
+    }
+}
+
+/// Whether `to` reaches `from` through `connections`, so that connecting
+/// `from` to `to` would close a loop.
+pub(crate) fn closes_cycle(
+    connections: &FxHashMap<ConnectionKey, Connection>,
+    from: NodeId,
+    to: NodeId,
+) -> bool {
+    if from == to {
+        return true;
+    }
+    let mut outgoing: FxHashMap<NodeId, Vec<NodeId>> = FxHashMap::default();
+    for connection in connections.values() {
+        outgoing
+            .entry(connection.from_node)
+            .or_default()
+            .push(connection.to_node);
+    }
+    let mut visited = FxHashSet::default();
+    let mut pending = vec![to];
+    while let Some(node) = pending.pop() {
+        if node == from {
+            return true;
+        }
+        if visited.insert(node) {
+            if let Some(next) = outgoing.get(&node) {
+                pending.extend(next.iter().copied());
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{AudioGraph, ModulationTransformation, ModulationType};
+    use crate::nodes::{FilterCollection, Lfo, Mixer};
+
+    fn audio(from_node: NodeId, to_node: NodeId) -> Connection {
+        Connection {
+            from_node,
+            from_port: PortId::AudioOutput0,
+            to_node,
+            to_port: PortId::AudioInput0,
+            amount: 1.0,
+            modulation_type: ModulationType::Additive,
+            modulation_transform: ModulationTransformation::None,
+        }
+    }
+
+    #[test]
+    fn long_chains_run_in_order() {
+        let mut graph = AudioGraph::new(64);
+        let mut chain = vec![graph.add_node(Box::new(Lfo::new(48_000.0)))];
+        for _ in 0..2_000 {
+            let node = graph.add_node(Box::new(Mixer::new()));
+            graph.add_connection(audio(*chain.last().unwrap(), node));
+            chain.push(node);
+        }
+        graph.set_output_node(*chain.last().unwrap());
+
+        let position: FxHashMap<NodeId, usize> = graph
+            .processing_order
+            .iter()
+            .enumerate()
+            .map(|(i, &node)| (node, i))
+            .collect();
+        assert!(chain
+            .windows(2)
+            .all(|pair| position[&pair[0]] < position[&pair[1]]));
+        let (mut left, mut right) = (vec![0.0; 64], vec![0.0; 64]);
+        graph.process_audio(&mut left, &mut right);
+        assert!(left.iter().all(|sample| sample.is_finite()));
+    }
+
+    #[test]
+    fn refuses_loops_and_excess_fan_in() {
+        let mut graph = AudioGraph::new(64);
+        let nodes: Vec<NodeId> = (0..3)
+            .map(|_| graph.add_node(Box::new(Mixer::new())))
+            .collect();
+        graph.add_connection(audio(nodes[0], nodes[1]));
+        graph.add_connection(audio(nodes[1], nodes[2]));
+        let connections = graph.connections.len();
+
+        let back = audio(nodes[2], nodes[0]);
+        let refused = Err(ConnectionError::Cycle {
+            from: nodes[2],
+            to: nodes[0],
+        });
+        assert_eq!(graph.check_connection(&back), refused);
+        graph.add_connection(back);
+        assert_eq!(graph.connections.len(), connections);
+        let own = audio(nodes[1], nodes[1]);
+        assert!(graph.check_connection(&own).is_err());
+        let missing = NodeId::new();
+        assert_eq!(
+            graph.check_connection(&audio(nodes[0], missing)),
+            Err(ConnectionError::UnknownNode(missing))
+        );
+
+        let sink = graph.add_node(Box::new(Mixer::new()));
+        for _ in 0..MAX_SOURCES_PER_PORT {
+            let source = graph.add_node(Box::new(Lfo::new(48_000.0)));
+            graph.add_connection(audio(source, sink));
+        }
+        let extra = graph.add_node(Box::new(Lfo::new(48_000.0)));
+        assert_eq!(
+            graph.check_connection(&audio(extra, sink)),
+            Err(ConnectionError::TooManySources {
+                node: sink,
+                port: PortId::AudioInput0,
+            })
+        );
+    }
+    #[test]
+    fn removing_a_left_link_keeps_an_explicit_right_link() {
+        let mut graph = AudioGraph::new(64);
+        graph.set_stereo(true);
+        let nodes: Vec<NodeId> = (0..4)
+            .map(|_| graph.add_node(Box::new(Mixer::new())))
+            .collect();
+        let right = |from_node, to_node| Connection {
+            from_port: PortId::AudioOutput1,
+            to_port: PortId::AudioInput1,
+            ..audio(from_node, to_node)
+        };
+        let has = |graph: &AudioGraph, from_node, to_node| {
+            graph.connections.contains_key(&ConnectionKey::new(
+                from_node,
+                PortId::AudioOutput1,
+                to_node,
+                PortId::AudioInput1,
+            ))
+        };
+
+        // The implied right link follows its left link out.
+        graph.add_connection(audio(nodes[0], nodes[1]));
+        assert!(has(&graph, nodes[0], nodes[1]));
+        graph.remove_specific_connection(nodes[0], nodes[1], PortId::AudioInput0);
+        assert!(!has(&graph, nodes[0], nodes[1]));
+
+        // One wired by hand stays, whichever order the pair was made in.
+        graph.add_connection(right(nodes[2], nodes[3]));
+        graph.add_connection(audio(nodes[2], nodes[3]));
+        graph.remove_specific_connection(nodes[2], nodes[3], PortId::AudioInput0);
+        assert!(has(&graph, nodes[2], nodes[3]));
+        graph.add_connection(audio(nodes[0], nodes[1]));
+        graph.add_connection(right(nodes[0], nodes[1]));
+        graph.remove_connection(&audio(nodes[0], nodes[1]));
+        assert!(has(&graph, nodes[0], nodes[1]));
+    }
+
+    #[test]
+    fn mono_graphs_stay_mono_until_stereo_is_turned_on() {
+        let mut graph = AudioGraph::new(64);
+        let source = graph.add_node(Box::new(Mixer::new()));
+        let filter = graph.add_node(Box::new(FilterCollection::new(48_000.0)));
+        let sink = graph.add_node(Box::new(Mixer::new()));
+        graph.add_connection(audio(source, filter));
+        graph.add_connection(audio(filter, sink));
+        graph.set_output_node(sink);
+        let state = |graph: &AudioGraph| {
+            let stereo = graph.nodes[&filter]
+                .as_any()
+                .downcast_ref::<FilterCollection>()
+                .unwrap()
+                .is_stereo();
+            let right_links = graph
+                .connections
+                .keys()
+                .filter(|key| key.to_port == PortId::AudioInput1)
+                .count();
+            (right_links, stereo)
+        };
+
+        // Mono: no right links, and the filter builds no right channel.
+        assert_eq!(state(&graph), (0, false));
+        graph.set_stereo(true);
+        assert_eq!(state(&graph), (2, true));
+        graph.set_stereo(false);
+        assert_eq!(state(&graph), (0, false));
+    }
+}
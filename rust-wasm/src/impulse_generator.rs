@@ -6,18 +6,8 @@ use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
 #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
 use wasm_bindgen::{JsCast, JsValue};
-#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
-use web_sys::console;
-
-#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
-fn log_error(message: &str) {
-    console::error_1(&message.into());
-}
 
-#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
-fn log_error(message: &str) {
-    eprintln!("{message}");
-}
+use crate::utils::error_sink::report_error;
 
 /// Fallback to JS crypto.getRandomValues if available, or use Math.random() as a last resort.
 #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
@@ -77,7 +67,7 @@ pub fn fill_seed(seed: &mut [u8]) -> Result<(), String> {
         match fill(seed) {
             Ok(()) => Ok(()),
             Err(e) => {
-                log_error(&format!("getrandom failed: {:?}", e));
+                report_error(&format!("getrandom failed: {:?}", e));
                 js_fallback_fill(seed)
             }
         }
@@ -227,7 +217,7 @@ impl ImpulseResponseGenerator {
 
         let mut seed = [0u8; 32];
         if let Err(e) = fill_seed(&mut seed) {
-            log_error(&format!("failed to generate random seed: {}", e));
+            report_error(&format!("failed to generate random seed: {}", e));
             panic!("failed to generate random seed: {}", e);
         }
         let mut rng = StdRng::from_seed(seed);
@@ -292,7 +282,7 @@ impl ImpulseResponseGenerator {
 
         let mut seed = [0u8; 32];
         if let Err(e) = fill_seed(&mut seed) {
-            log_error(&format!("failed to generate random seed: {}", e));
+            report_error(&format!("failed to generate random seed: {}", e));
             panic!("failed to generate random seed: {}", e);
         }
         let mut rng = StdRng::from_seed(seed);
@@ -1,7 +1,11 @@
 #![feature(portable_simd)]
 
 pub mod audio;
+// The engine hosts drive the DSP core below through `AudioGraph` and `Voice`;
+// the DSP-only module subset (`dsp-only`) leaves them out.
+#[cfg(any(feature = "wasm", feature = "native-host", not(feature = "dsp-only")))]
 pub mod audio_engine;
+#[cfg(any(feature = "wasm", feature = "native-host", not(feature = "dsp-only")))]
 pub mod automation;
 pub mod biquad;
 pub mod effect_stack;
@@ -14,12 +18,13 @@ pub mod traits;
 pub mod utils;
 pub mod voice;
 
+#[cfg(any(feature = "wasm", feature = "native-host", not(feature = "dsp-only")))]
 pub use automation::{AutomationFrame, ConnectionUpdate};
 pub use graph::AudioGraph;
 pub use graph::{Connection, ConnectionId, NodeId};
-pub use macros::{MacroManager, ModulationTarget};
+pub use macros::{MacroManager, MacroMapping, MacroPolarity, MacroSource, ModulationTarget};
 pub use nodes::{Envelope, EnvelopeConfig};
-pub use traits::{AudioNode, PortId};
+pub use traits::{AudioNode, PortId, QualityMode};
 pub use utils::*;
 pub use voice::Voice;
 
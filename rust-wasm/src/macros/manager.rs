@@ -1,16 +1,28 @@
 use rustc_hash::FxHashMap;
 
-use super::types::{ModulationMacro, ModulationTarget};
+use super::types::{MacroSource, MacroSourceValues, ModulationMacro, ModulationTarget};
 use crate::graph::{AudioBufferPool, ModulationType};
 use crate::{NodeId, PortId};
 use std::simd::f32x4;
 
+/// A sparse macro update waiting to be expanded into the macro buffer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SparseMacroUpdate {
+    value: f32,
+    ramp_target: Option<f32>,
+}
+
 #[derive(Debug)]
 pub struct MacroManager {
     macros: Vec<ModulationMacro>,
     buffer_size: usize,
     /// Preallocated scratch buffer to avoid per‐block allocation in update_macro.
     scratch_buffer: Vec<f32>,
+    /// Last sample written to each macro buffer, the start point for smoothing.
+    last_values: Vec<f32>,
+    pending_sparse: Vec<Option<SparseMacroUpdate>>,
+    /// Internal source driving each macro, and its amount.
+    sources: Vec<Option<(MacroSource, f32)>>,
 }
 
 pub struct MacroData {
@@ -32,43 +44,129 @@ impl MacroManager {
             macros,
             buffer_size,
             scratch_buffer: vec![0.0; buffer_size],
+            last_values: vec![0.0; num_macros],
+            pending_sparse: vec![None; num_macros],
+            sources: vec![None; num_macros],
         }
     }
 
+    /// Adds a target to a macro and returns the buffer the target should read.
+    /// Targets with a non-identity mapping get a buffer of their own, refilled
+    /// by `update_mapped_buffers`.
     pub fn add_modulation(
         &mut self,
         macro_index: usize,
-        target: ModulationTarget,
-    ) -> Result<(), String> {
-        self.macros
+        mut target: ModulationTarget,
+        buffer_pool: &mut AudioBufferPool,
+    ) -> Result<usize, String> {
+        let macro_mod = self
+            .macros
             .get_mut(macro_index)
-            .ok_or_else(|| format!("Invalid macro index: {}", macro_index))?
-            .add_target(target);
-        Ok(())
+            .ok_or_else(|| format!("Invalid macro index: {}", macro_index))?;
+        let buffer_idx = if target.mapping.is_identity() {
+            target.mapped_buffer_idx = None;
+            macro_mod.get_value_buffer_idx()
+        } else {
+            let idx = buffer_pool.acquire(self.buffer_size);
+            let start = target.mapping.map(self.last_values[macro_index]);
+            buffer_pool.fill(idx, start);
+            target.mapped_buffer_idx = Some(idx);
+            idx
+        };
+        macro_mod.add_target(target);
+        Ok(buffer_idx)
     }
 
+    /// Removes the targets of a macro on `node_id`/`port_id` and returns the
+    /// buffers they were reading, which the caller should disconnect.
     pub fn remove_target(
         &mut self,
         macro_index: usize,
         node_id: NodeId,
         port_id: PortId,
-    ) -> Result<bool, String> {
+        buffer_pool: &mut AudioBufferPool,
+    ) -> Result<Vec<usize>, String> {
         let macro_mod = self
             .macros
             .get_mut(macro_index)
             .ok_or_else(|| format!("Invalid macro index: {}", macro_index))?;
-        Ok(macro_mod.remove_target(node_id, port_id))
+        let value_buffer_idx = macro_mod.get_value_buffer_idx();
+        Ok(macro_mod
+            .remove_target(node_id, port_id)
+            .into_iter()
+            .map(|target| match target.mapped_buffer_idx {
+                Some(idx) => {
+                    buffer_pool.release(idx);
+                    idx
+                }
+                None => value_buffer_idx,
+            })
+            .collect())
     }
 
-    pub fn clear_macro(&mut self, macro_index: usize) -> Result<(), String> {
+    pub fn clear_macro(
+        &mut self,
+        macro_index: usize,
+        buffer_pool: &mut AudioBufferPool,
+    ) -> Result<(), String> {
         let macro_mod = self
             .macros
             .get_mut(macro_index)
             .ok_or_else(|| format!("Invalid macro index: {}", macro_index))?;
-        macro_mod.clear_targets();
+        for target in macro_mod.clear_targets() {
+            if let Some(idx) = target.mapped_buffer_idx {
+                buffer_pool.release(idx);
+            }
+        }
         Ok(())
     }
 
+    /// Drops every macro target and releases their mapped buffers. Used when the
+    /// graph the targets pointed into has been cleared.
+    pub fn clear_routes(&mut self, buffer_pool: &mut AudioBufferPool) {
+        for macro_index in 0..self.macros.len() {
+            let _ = self.clear_macro(macro_index, buffer_pool);
+        }
+    }
+
+    /// Targets of a macro, in the order they were added.
+    pub fn targets(&self, macro_index: usize) -> &[ModulationTarget] {
+        self.macros
+            .get(macro_index)
+            .map_or(&[], |m| m.get_targets())
+    }
+
+    pub fn num_macros(&self) -> usize {
+        self.macros.len()
+    }
+
+    /// Refills the mapped buffers of every target from its macro buffer. Call
+    /// once per block after the macro buffers are up to date.
+    pub fn update_mapped_buffers(&mut self, buffer_pool: &mut AudioBufferPool) {
+        for macro_mod in &self.macros {
+            if macro_mod
+                .get_targets()
+                .iter()
+                .all(|t| t.mapped_buffer_idx.is_none())
+            {
+                continue;
+            }
+            let source = buffer_pool.copy_out(macro_mod.get_value_buffer_idx());
+            let len = source.len().min(self.scratch_buffer.len());
+            self.scratch_buffer[..len].copy_from_slice(&source[..len]);
+            for target in macro_mod.get_targets() {
+                let Some(idx) = target.mapped_buffer_idx else {
+                    continue;
+                };
+                if let Some(buffer) = buffer_pool.buffers.get_mut(idx) {
+                    for (out, &value) in buffer.iter_mut().zip(&self.scratch_buffer[..len]) {
+                        *out = target.mapping.map(value);
+                    }
+                }
+            }
+        }
+    }
+
     pub fn has_active_macros(&self) -> bool {
         self.macros.iter().any(|m| !m.get_targets().is_empty())
     }
@@ -114,13 +212,116 @@ impl MacroManager {
             *sample = 0.0;
         }
         buffer_pool.copy_in(buffer_idx, &self.scratch_buffer);
+        // A full buffer supersedes any sparse update still waiting.
+        self.last_values[macro_index] = self.scratch_buffer[dest_buffer_size - 1];
+        self.pending_sparse[macro_index] = None;
+        Ok(())
+    }
+
+    /// Sparse alternative to `update_macro`: the host sends one value and an
+    /// optional ramp target instead of a full buffer. The next block ramps from
+    /// `value` on its first sample to `ramp_target` on its last; without a target
+    /// it glides from the previous value to `value` across the block so steps
+    /// don't zipper. The macro then holds its final value until updated again.
+    pub fn set_macro_sparse(
+        &mut self,
+        macro_index: usize,
+        value: f32,
+        ramp_target: Option<f32>,
+    ) -> Result<(), String> {
+        let pending = self
+            .pending_sparse
+            .get_mut(macro_index)
+            .ok_or_else(|| format!("Invalid macro index: {}", macro_index))?;
+        *pending = Some(SparseMacroUpdate { value, ramp_target });
+        Ok(())
+    }
+
+    /// Drives a macro from `source` scaled by `amount`, in place of the values
+    /// the host sends for it. `None` hands the macro back to the host.
+    pub fn set_source(
+        &mut self,
+        macro_index: usize,
+        source: Option<MacroSource>,
+        amount: f32,
+    ) -> Result<(), String> {
+        let slot = self
+            .sources
+            .get_mut(macro_index)
+            .ok_or_else(|| format!("Invalid macro index: {}", macro_index))?;
+        *slot = source.map(|source| (source, amount));
         Ok(())
     }
 
+    pub fn source(&self, macro_index: usize) -> Option<(MacroSource, f32)> {
+        self.sources.get(macro_index).copied().flatten()
+    }
+
+    /// Queues this block's value for every macro with a source, as a sparse
+    /// update so changes glide over the block. Call before
+    /// `expand_sparse_updates`.
+    pub fn drive_sources(&mut self, values: &MacroSourceValues) {
+        for (pending, source) in self.pending_sparse.iter_mut().zip(&self.sources) {
+            if let Some((source, amount)) = *source {
+                *pending = Some(SparseMacroUpdate {
+                    value: (values.get(source) * amount).clamp(0.0, 1.0),
+                    ramp_target: None,
+                });
+            }
+        }
+    }
+
+    /// Expands pending sparse updates into the macro buffers. Call once per block
+    /// before processing.
+    pub fn expand_sparse_updates(&mut self, buffer_pool: &mut AudioBufferPool) {
+        for (macro_index, macro_mod) in self.macros.iter().enumerate() {
+            let Some(update) = self.pending_sparse[macro_index] else {
+                continue;
+            };
+            let buffer_idx = macro_mod.get_value_buffer_idx();
+            let Some(buffer) = buffer_pool.buffers.get_mut(buffer_idx) else {
+                continue;
+            };
+            let len = buffer.len();
+            if len == 0 {
+                continue;
+            }
+
+            let (start, end) = match update.ramp_target {
+                Some(target) => (update.value, target),
+                None => (self.last_values[macro_index], update.value),
+            };
+            if start == end {
+                buffer.fill(end);
+                self.pending_sparse[macro_index] = None;
+            } else {
+                // An explicit ramp starts on `value`; smoothing starts one step
+                // past the previous block's last sample.
+                let (offset, steps) = match update.ramp_target {
+                    Some(_) => (0.0, (len - 1).max(1) as f32),
+                    None => (1.0, len as f32),
+                };
+                let step = (end - start) / steps;
+                for (i, sample) in buffer.iter_mut().enumerate() {
+                    *sample = start + step * (i as f32 + offset);
+                }
+                buffer[len - 1] = end;
+                // Hold the end value on the following block.
+                self.pending_sparse[macro_index] = Some(SparseMacroUpdate {
+                    value: end,
+                    ramp_target: None,
+                });
+            }
+            self.last_values[macro_index] = end;
+        }
+    }
+
     pub fn clear(&mut self, buffer_pool: &mut AudioBufferPool) {
         for macro_mod in &self.macros {
             buffer_pool.clear(macro_mod.get_value_buffer_idx());
         }
+        self.last_values.fill(0.0);
+        self.pending_sparse.fill(None);
     }
 
     pub fn get_macro_buffer_idx(&self, macro_index: usize) -> Option<usize> {
@@ -173,7 +374,12 @@ impl MacroManager {
                     // Apply modulation transformation to the macro values
                     let mut transformed = [0.0; 4];
                     for j in 0..current_chunk_size {
-                        transformed[j] = target.modulation_transform.apply(macro_chunk[j]);
+                        let mapped = if target.mapped_buffer_idx.is_some() {
+                            target.mapping.map(macro_chunk[j])
+                        } else {
+                            macro_chunk[j]
+                        };
+                        transformed[j] = target.modulation_transform.apply(mapped);
                     }
                     let transformed_simd = f32x4::from_array(transformed);
 
@@ -206,3 +412,163 @@ impl MacroManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::ModulationTransformation;
+    use crate::macros::types::{MacroMapping, MacroPolarity};
+
+    fn manager() -> (MacroManager, AudioBufferPool, usize) {
+        let mut pool = AudioBufferPool::new(8, 4);
+        let manager = MacroManager::new(1, &mut pool, 8);
+        let idx = manager.get_macro_buffer_idx(0).unwrap();
+        (manager, pool, idx)
+    }
+
+    #[test]
+    fn sparse_ramp_is_sample_accurate_then_holds() {
+        let (mut manager, mut pool, idx) = manager();
+        manager.set_macro_sparse(0, 0.0, Some(0.7)).unwrap();
+        manager.expand_sparse_updates(&mut pool);
+        let ramp = pool.copy_out(idx);
+        assert_eq!(ramp[0], 0.0);
+        assert!((ramp[1] - 0.1).abs() < 1e-6);
+        assert_eq!(ramp[7], 0.7);
+
+        manager.expand_sparse_updates(&mut pool);
+        assert!(pool.copy_out(idx).iter().all(|&v| v == 0.7));
+    }
+
+    #[test]
+    fn sparse_step_glides_from_the_previous_value() {
+        let (mut manager, mut pool, idx) = manager();
+        manager.update_macro(0, &[0.5; 8], &mut pool).unwrap();
+        manager.set_macro_sparse(0, 1.3, None).unwrap();
+        manager.expand_sparse_updates(&mut pool);
+        let glide = pool.copy_out(idx);
+        assert!((glide[0] - 0.6).abs() < 1e-6);
+        assert_eq!(glide[7], 1.3);
+
+        // Full buffers still win over a pending sparse update.
+        manager.set_macro_sparse(0, 0.0, None).unwrap();
+        manager.update_macro(0, &[0.25; 8], &mut pool).unwrap();
+        manager.expand_sparse_updates(&mut pool);
+        assert!(pool.copy_out(idx).iter().all(|&v| v == 0.25));
+    }
+
+    #[test]
+    fn sources_drive_the_macro_in_place_of_the_host() {
+        let (mut manager, mut pool, idx) = manager();
+        let values = MacroSourceValues {
+            velocity: 0.8,
+            mod_wheel: 0.4,
+            ..MacroSourceValues::default()
+        };
+        assert!(manager
+            .set_source(1, Some(MacroSource::Velocity), 1.0)
+            .is_err());
+        manager
+            .set_source(0, Some(MacroSource::Velocity), 0.5)
+            .unwrap();
+        manager.update_macro(0, &[0.4; 8], &mut pool).unwrap();
+        manager.drive_sources(&values);
+        manager.expand_sparse_updates(&mut pool);
+        assert_eq!(pool.copy_out(idx)[7], 0.4);
+
+        manager
+            .set_source(0, Some(MacroSource::ModWheel), 3.0)
+            .unwrap();
+        manager.drive_sources(&values);
+        manager.expand_sparse_updates(&mut pool);
+        assert_eq!(pool.copy_out(idx)[7], 1.0);
+
+        manager.set_source(0, None, 0.0).unwrap();
+        assert_eq!(manager.source(0), None);
+        manager.update_macro(0, &[0.25; 8], &mut pool).unwrap();
+        manager.drive_sources(&values);
+        manager.expand_sparse_updates(&mut pool);
+        assert!(pool.copy_out(idx).iter().all(|&v| v == 0.25));
+    }
+
+    fn target(port_id: PortId, mapping: MacroMapping) -> ModulationTarget {
+        ModulationTarget {
+            node_id: NodeId::new(),
+            port_id,
+            amount: 1.0,
+            modulation_type: ModulationType::Additive,
+            modulation_transform: ModulationTransformation::None,
+            mapping,
+            mapped_buffer_idx: None,
+        }
+    }
+
+    #[test]
+    fn one_macro_maps_each_target_into_its_own_range() {
+        let (mut manager, mut pool, idx) = manager();
+        let identity = manager
+            .add_modulation(
+                0,
+                target(PortId::GainMod, MacroMapping::default()),
+                &mut pool,
+            )
+            .unwrap();
+        let inverted = MacroMapping {
+            min: 0.8,
+            max: 0.2,
+            ..MacroMapping::default()
+        };
+        let inverted_idx = manager
+            .add_modulation(0, target(PortId::CutoffMod, inverted), &mut pool)
+            .unwrap();
+        let bipolar = MacroMapping {
+            min: -1.0,
+            max: 1.0,
+            curve: 2.0,
+            polarity: MacroPolarity::Bipolar,
+        };
+        let bipolar_idx = manager
+            .add_modulation(0, target(PortId::DetuneMod, bipolar), &mut pool)
+            .unwrap();
+        assert_eq!(identity, idx);
+
+        manager
+            .update_macro(0, &[0.0, 0.25, 0.5, 0.75, 1.0, 1.0, 1.0, 1.0], &mut pool)
+            .unwrap();
+        manager.update_mapped_buffers(&mut pool);
+
+        let mapped = pool.copy_out(inverted_idx);
+        assert!((mapped[0] - 0.8).abs() < 1e-6);
+        assert!((mapped[2] - 0.5).abs() < 1e-6);
+        assert!((mapped[4] - 0.2).abs() < 1e-6);
+
+        // The bipolar curve is symmetric around the knob centre.
+        let mapped = pool.copy_out(bipolar_idx);
+        assert!((mapped[0] + 1.0).abs() < 1e-6);
+        assert!(mapped[2].abs() < 1e-6);
+        assert!((mapped[1] + mapped[3]).abs() < 1e-6);
+        assert!(mapped[3] < 0.5);
+        assert!((mapped[4] - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn removing_a_mapped_target_releases_its_buffer() {
+        let (mut manager, mut pool, _) = manager();
+        let node = target(
+            PortId::GainMod,
+            MacroMapping {
+                min: 0.5,
+                ..MacroMapping::default()
+            },
+        );
+        let node_id = node.node_id;
+        let mapped_idx = manager.add_modulation(0, node, &mut pool).unwrap();
+
+        let removed = manager
+            .remove_target(0, node_id, PortId::GainMod, &mut pool)
+            .unwrap();
+        assert_eq!(removed, vec![mapped_idx]);
+        assert!(manager.targets(0).is_empty());
+        assert_eq!(pool.acquire(8), mapped_idx);
+    }
+}
@@ -2,4 +2,4 @@ mod manager;
 mod types;
 
 pub use manager::MacroManager;
-pub use types::ModulationTarget;
+pub use types::{MacroMapping, MacroPolarity, MacroSource, MacroSourceValues, ModulationTarget};
@@ -1,5 +1,111 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::curves::get_curved_value;
 use crate::{graph::ModulationTransformation, graph::ModulationType, NodeId, PortId};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MacroPolarity {
+    /// The knob sweeps from `min` (fully down) to `max` (fully up).
+    #[default]
+    Unipolar,
+    /// The knob centre sits halfway between `min` and `max`; the curve bends
+    /// both halves away from the centre.
+    Bipolar,
+}
+
+/// Per-target range mapping of a macro knob (0..1) onto the value sent to the
+/// target, like the macro ranges in Serum or Vital.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MacroMapping {
+    pub min: f32,
+    pub max: f32,
+    /// Response curve, see `get_curved_value`. 0 is linear.
+    pub curve: f32,
+    pub polarity: MacroPolarity,
+}
+
+impl Default for MacroMapping {
+    fn default() -> Self {
+        Self {
+            min: 0.0,
+            max: 1.0,
+            curve: 0.0,
+            polarity: MacroPolarity::Unipolar,
+        }
+    }
+}
+
+impl MacroMapping {
+    /// True when the mapping passes the macro value through unchanged.
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    #[inline]
+    pub fn map(&self, value: f32) -> f32 {
+        let value = value.clamp(0.0, 1.0);
+        match self.polarity {
+            MacroPolarity::Unipolar => {
+                self.min + (self.max - self.min) * get_curved_value(value, self.curve)
+            }
+            MacroPolarity::Bipolar => {
+                let centre = 0.5 * (self.min + self.max);
+                let half_range = 0.5 * (self.max - self.min);
+                let offset = 2.0 * value - 1.0;
+                centre + half_range * offset.signum() * get_curved_value(offset.abs(), self.curve)
+            }
+        }
+    }
+}
+
+/// Performance data that can drive a macro from inside the engine, for hosts
+/// that only send notes and controllers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MacroSource {
+    Velocity,
+    Aftertouch,
+    ModWheel,
+    /// A new random value for every note, held while it plays.
+    RandomPerNote,
+}
+
+impl MacroSource {
+    /// 1 = velocity, 2 = aftertouch, 3 = mod wheel, 4 = random per note;
+    /// anything else is no source.
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(MacroSource::Velocity),
+            2 => Some(MacroSource::Aftertouch),
+            3 => Some(MacroSource::ModWheel),
+            4 => Some(MacroSource::RandomPerNote),
+            _ => None,
+        }
+    }
+}
+
+/// A voice's current value of every macro source, each 0..1.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MacroSourceValues {
+    pub velocity: f32,
+    pub aftertouch: f32,
+    pub mod_wheel: f32,
+    pub random: f32,
+}
+
+impl MacroSourceValues {
+    pub fn get(&self, source: MacroSource) -> f32 {
+        match source {
+            MacroSource::Velocity => self.velocity,
+            MacroSource::Aftertouch => self.aftertouch,
+            MacroSource::ModWheel => self.mod_wheel,
+            MacroSource::RandomPerNote => self.random,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ModulationTarget {
     pub node_id: NodeId,
@@ -7,6 +113,10 @@ pub struct ModulationTarget {
     pub amount: f32,
     pub modulation_type: ModulationType,
     pub modulation_transform: ModulationTransformation,
+    pub mapping: MacroMapping,
+    /// Buffer holding the mapped macro signal for this target. `None` when the
+    /// mapping is the identity and the target reads the macro buffer directly.
+    pub mapped_buffer_idx: Option<usize>,
 }
 
 #[derive(Debug)]
@@ -27,15 +137,18 @@ impl ModulationMacro {
         self.targets.push(target);
     }
 
-    pub fn remove_target(&mut self, node_id: NodeId, port_id: PortId) -> bool {
-        let before = self.targets.len();
-        self.targets
-            .retain(|t| !(t.node_id == node_id && t.port_id == port_id));
-        before != self.targets.len()
+    /// Removes the targets matching `node_id`/`port_id` and returns them so the
+    /// caller can release their mapped buffers.
+    pub fn remove_target(&mut self, node_id: NodeId, port_id: PortId) -> Vec<ModulationTarget> {
+        let (removed, kept) = std::mem::take(&mut self.targets)
+            .into_iter()
+            .partition(|t| t.node_id == node_id && t.port_id == port_id);
+        self.targets = kept;
+        removed
     }
 
-    pub fn clear_targets(&mut self) {
-        self.targets.clear();
+    pub fn clear_targets(&mut self) -> Vec<ModulationTarget> {
+        std::mem::take(&mut self.targets)
     }
 
     pub fn get_targets(&self) -> &[ModulationTarget] {
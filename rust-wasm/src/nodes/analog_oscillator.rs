@@ -27,7 +27,6 @@ use rustc_hash::FxHashMap;
 use rustfft::num_traits::Float;
 use serde::{Deserialize, Serialize};
 use std::any::Any;
-use std::f32::consts::PI;
 use std::simd::{LaneCount, Simd, SupportedLaneCount};
 use std::sync::Arc;
 #[cfg(feature = "wasm")]
@@ -35,9 +34,12 @@ use wasm_bindgen::prelude::*;
 #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
 use web_sys::console;
 
-use crate::graph::{ModulationProcessor, ModulationSource};
+use crate::graph::{ModulationProcessor, ModulationSource, ModulationTarget, TargetMapping};
+use crate::utils::smoothing::smoothing_coefficient;
+use crate::utils::tuning::resolve_detune_cents;
 use crate::{AudioNode, PortId};
 
+use super::fm_operator::{FEEDBACK_DIVISOR, PHASE_MOD_SCALE};
 use super::{Waveform, WavetableBank};
 
 // ------------------------------------------------------------------------------------------------------------------
@@ -67,8 +69,59 @@ pub struct AnalogOscillatorStateUpdate {
     pub waveform: Waveform,
     pub unison_voices: u32,
     pub spread: f32, // Total width in cents (peak‑to‑peak)
+    /// Stereo width of the unison voices: 0 keeps them centred, 1 pans them from hard left to
+    /// hard right.
+    #[serde(default = "default_unity")]
+    pub stereo_spread: f32,
+    /// How far each voice's start phase is randomized when the gate opens (0‥1).
+    #[serde(default)]
+    pub phase_randomization: f32,
+    /// Level of the detuned voices against the centre voice(s) (0‥1).
+    #[serde(default = "default_unity")]
+    pub blend: f32,
     #[serde(default)]
     pub wave_index: f32,
+    /// Level of the built-in sub oscillator; 0 turns it off.
+    #[serde(default)]
+    pub sub_gain: f32,
+    /// Octaves the sub oscillator plays below the main pitch (1 or 2).
+    #[serde(default = "default_sub_octave")]
+    pub sub_octave: u32,
+    /// Shape of the sub oscillator: `Sine`, or `Square` for anything else.
+    #[serde(default = "default_sub_waveform")]
+    pub sub_waveform: Waveform,
+    /// Octaves added to the pitch when picking a mip level: above 0 picks darker tables that
+    /// alias less, below 0 brighter ones that alias more.
+    #[serde(default)]
+    pub mip_bias: f32,
+    /// Points per cycle read from each table (a power of two); 0 reads every stored point.
+    #[serde(default)]
+    pub table_size: u32,
+    #[serde(default)]
+    pub interpolation: TableInterpolation,
+}
+
+/// How table lookups fill in between stored points.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TableInterpolation {
+    Linear = 1,
+    #[default]
+    Cubic = 3,
+}
+
+fn default_unity() -> f32 {
+    1.0
+}
+
+fn default_sub_octave() -> u32 {
+    1
+}
+
+fn default_sub_waveform() -> Waveform {
+    Waveform::Square
 }
 
 #[cfg(feature = "wasm")]
@@ -101,7 +154,16 @@ impl AnalogOscillatorStateUpdate {
             waveform,
             unison_voices,
             spread,
+            stereo_spread: 1.0,
+            phase_randomization: 0.0,
+            blend: 1.0,
             wave_index: 0.0,
+            sub_gain: 0.0,
+            sub_octave: default_sub_octave(),
+            sub_waveform: default_sub_waveform(),
+            mip_bias: 0.0,
+            table_size: 0,
+            interpolation: TableInterpolation::default(),
         }
     }
 }
@@ -111,6 +173,12 @@ impl AnalogOscillatorStateUpdate {
 // ------------------------------------------------------------------------------------------------------------------
 
 const SIMD_WIDTH: usize = 4;
+
+/// Most unison voices an oscillator runs.
+pub const MAX_UNISON_VOICES: usize = 16;
+/// Furthest a mip level can be biased, in octaves either way.
+pub const MAX_MIP_BIAS: f32 = 4.0;
+const MIN_TABLE_POINTS: usize = 16;
 type F32xN<const LANES: usize> = Simd<f32, LANES>;
 
 #[inline(always)]
@@ -144,6 +212,7 @@ where
 // The oscillator struct
 // ------------------------------------------------------------------------------------------------------------------
 
+#[derive(Clone)]
 pub struct AnalogOscillator {
     // --- static params -------------------------------------------------------------------
     sample_rate_recip: f32,
@@ -167,6 +236,8 @@ pub struct AnalogOscillator {
     smoothed_feedback: f32,
     smoothed_phase_mod_amount: f32,
     smoothed_spread_cents: f32,
+    target_sub_gain: f32,
+    smoothed_sub_gain: f32,
 
     // --- live state ----------------------------------------------------------------------
     active: bool,
@@ -175,10 +246,25 @@ pub struct AnalogOscillator {
     waveform: Waveform,
 
     unison_voices: usize,
+    stereo_spread: f32,
+    phase_randomization: f32,
+    blend: f32,
+    rng_state: u32,
     voice_phases: Vec<f32>,
     voice_last_out: Vec<f32>,
     voice_offsets: Vec<f32>,
-    voice_weights: Vec<f32>, // allows future thick/super‑saw tricks
+    voice_weights: Vec<f32>, // centre voices 1.0, detuned voices `blend`
+    voice_pan_gains: Vec<(f32, f32)>,
+
+    sub_octave: u32,
+    sub_waveform: Waveform,
+    sub_phase: f32,
+
+    // --- table quality -------------------------------------------------------------------
+    mip_bias_ratio: f32,
+    /// Points per cycle read from each table; 0 reads every stored point.
+    table_points: usize,
+    interpolation: TableInterpolation,
 
     // --- scratch buffers (audio‑rate) ----------------------------------------------------
     mod_add: Vec<f32>,
@@ -199,15 +285,6 @@ impl ModulationProcessor for AnalogOscillator {}
 // Construction helpers
 // ------------------------------------------------------------------------------------------------------------------
 
-fn smoothing_coeff(sample_rate: f32, time_ms: f32) -> f32 {
-    let samples = sample_rate * (time_ms / 1000.0);
-    if samples > 0.0 {
-        1.0 - (-1.0 / samples).exp()
-    } else {
-        1.0
-    }
-}
-
 impl AnalogOscillator {
     pub fn new(
         sample_rate: f32,
@@ -226,7 +303,7 @@ impl AnalogOscillator {
 
         // --- smoothed/target -----------------------------------------------------------------
         let smoothing_ms = 1.0;
-        let smooth_coeff = smoothing_coeff(sample_rate, smoothing_ms);
+        let smooth_coeff = smoothing_coefficient(sample_rate, smoothing_ms);
 
         // --- scratch buffer capacity ---------------------------------------------------------
         let buf_cap = 128;
@@ -236,8 +313,8 @@ impl AnalogOscillator {
             sample_rate_recip: 1.0 / sample_rate,
             cent_ratio: 2.0_f32.powf(1.0 / 1200.0),
             semitone_ratio: 2.0_f32.powf(1.0 / 12.0),
-            two_pi_recip: 1.0 / (2.0 * PI),
-            feedback_divisor: PI * 1.5,
+            two_pi_recip: PHASE_MOD_SCALE,
+            feedback_divisor: FEEDBACK_DIVISOR,
             wavetable_banks,
 
             // smoothing
@@ -251,6 +328,8 @@ impl AnalogOscillator {
             smoothed_feedback: init_fb,
             smoothed_phase_mod_amount: init_pm,
             smoothed_spread_cents: init_spread.clamp(0.0, max_spread_cents),
+            target_sub_gain: 0.0,
+            smoothed_sub_gain: 0.0,
 
             // live state
             active: true,
@@ -259,10 +338,23 @@ impl AnalogOscillator {
             waveform,
 
             unison_voices: init_voice_count,
+            stereo_spread: 1.0,
+            phase_randomization: 0.0,
+            blend: 1.0,
+            rng_state: 0x9E37_79B9,
             voice_phases: vec![0.0; init_voice_count],
             voice_last_out: vec![0.0; init_voice_count],
             voice_offsets: vec![0.0; init_voice_count],
             voice_weights: vec![1.0; init_voice_count],
+            voice_pan_gains: vec![(0.0, 0.0); init_voice_count],
+
+            sub_octave: 1,
+            sub_waveform: Waveform::Square,
+            sub_phase: 0.0,
+
+            mip_bias_ratio: 1.0,
+            table_points: 0,
+            interpolation: TableInterpolation::Cubic,
 
             // scratch
             mod_add: vec![0.0; buf_cap],
@@ -278,6 +370,7 @@ impl AnalogOscillator {
         };
 
         osc.recalc_voice_offsets();
+        osc.recalc_voice_mix();
         osc
     }
 
@@ -285,26 +378,63 @@ impl AnalogOscillator {
     // Parameter updates & helpers
     // --------------------------------------------------------------------------------------------------------------
 
+    /// Sets the output gain without smoothing, for freshly created oscillators.
+    pub fn set_gain(&mut self, gain: f32) {
+        self.target_gain = gain;
+        self.smoothed_gain = gain;
+    }
+
+    pub fn gain(&self) -> f32 {
+        self.target_gain
+    }
+
     pub fn update_params(&mut self, p: &AnalogOscillatorStateUpdate) {
         self.target_gain = p.gain;
         self.target_feedback = p.feedback_amount;
         self.target_phase_mod_amount = p.phase_mod_amount;
-        self.target_detune_cents = p.detune;
+        self.target_detune_cents =
+            resolve_detune_cents(p.detune_oct, p.detune_semi, p.detune_cents, p.detune);
         self.target_spread_cents = p.spread.clamp(0.0, 100.0);
 
         self.hard_sync = p.hard_sync;
         self.active = p.active;
         self.waveform = p.waveform;
 
-        let new_voice_count = p.unison_voices.max(1) as usize;
+        self.stereo_spread = p.stereo_spread.clamp(0.0, 1.0);
+        self.phase_randomization = p.phase_randomization.clamp(0.0, 1.0);
+        self.blend = p.blend.clamp(0.0, 1.0);
+
+        self.target_sub_gain = p.sub_gain.max(0.0);
+        self.sub_octave = p.sub_octave.clamp(1, 2);
+        self.sub_waveform = match p.sub_waveform {
+            Waveform::Sine => Waveform::Sine,
+            _ => Waveform::Square,
+        };
+
+        self.mip_bias_ratio = p.mip_bias.clamp(-MAX_MIP_BIAS, MAX_MIP_BIAS).exp2();
+        self.table_points = match p.table_size as usize {
+            0 => 0,
+            points => points.next_power_of_two().max(MIN_TABLE_POINTS),
+        };
+        self.interpolation = p.interpolation;
+
+        let new_voice_count = (p.unison_voices as usize).clamp(1, MAX_UNISON_VOICES);
         if new_voice_count != self.unison_voices {
             self.unison_voices = new_voice_count;
             self.voice_phases.resize(new_voice_count, 0.0);
             self.voice_last_out.resize(new_voice_count, 0.0);
             self.voice_weights.resize(new_voice_count, 1.0);
             self.voice_offsets.resize(new_voice_count, 0.0);
+            self.voice_pan_gains.resize(new_voice_count, (0.0, 0.0));
             self.recalc_voice_offsets();
         }
+        self.recalc_voice_mix();
+    }
+
+    /// Switches to another set of banks, e.g. after a waveform was replaced.
+    /// Playback carries on from the same phase.
+    pub fn set_wavetable_banks(&mut self, banks: Arc<FxHashMap<Waveform, Arc<WavetableBank>>>) {
+        self.wavetable_banks = banks;
     }
 
     #[inline]
@@ -321,6 +451,50 @@ impl AnalogOscillator {
         }
     }
 
+    /// Per-voice weights and equal-power pan gains. The voices are spread evenly from left to
+    /// right, scaled by the stereo spread; the middle one (or two) count as the centre.
+    fn recalc_voice_mix(&mut self) {
+        let n = self.unison_voices;
+        for i in 0..n {
+            let pan = if n > 1 {
+                ((i as f32 / (n - 1) as f32) * 2.0 - 1.0) * self.stereo_spread
+            } else {
+                0.0
+            };
+            let pan_norm = (pan + 1.0) * 0.5;
+            self.voice_pan_gains[i] = (
+                ((1.0 - pan_norm) * std::f32::consts::FRAC_PI_2).sin(),
+                (pan_norm * std::f32::consts::FRAC_PI_2).sin(),
+            );
+            let centre = i == (n - 1) / 2 || i == n / 2;
+            self.voice_weights[i] = if centre { 1.0 } else { self.blend };
+        }
+    }
+
+    fn next_random(&mut self) -> f32 {
+        // xorshift32
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng_state = x;
+        (x >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Restarts the voices on a new note: from phase 0 with hard sync, offset by a random
+    /// amount of up to `phase_randomization` cycles.
+    fn restart_phases(&mut self) {
+        for i in 0..self.voice_phases.len() {
+            let offset = if self.phase_randomization > 0.0 {
+                self.next_random() * self.phase_randomization
+            } else {
+                0.0
+            };
+            self.voice_phases[i] = offset;
+        }
+        self.sub_phase = 0.0;
+    }
+
     #[inline]
     fn ensure_buf<T: Clone>(buf: &mut Vec<T>, required: usize, fill: T) {
         if buf.len() < required {
@@ -347,10 +521,9 @@ impl AnalogOscillator {
 
     #[inline(always)]
     fn check_gate(&mut self, gate: f32) {
-        if self.hard_sync && gate > 0.0 && self.last_gate_val <= 0.0 {
-            for ph in &mut self.voice_phases {
-                *ph = 0.0;
-            }
+        let rising = gate > 0.0 && self.last_gate_val <= 0.0;
+        if rising && (self.hard_sync || self.phase_randomization > 0.0) {
+            self.restart_phases();
         }
         self.last_gate_val = gate;
     }
@@ -388,9 +561,6 @@ impl AnalogOscillator {
         let mut sum_r = 0.0;
         let mut v = 0;
 
-        // Calculate stereo spread: voices are panned across the stereo field
-        let total_voices = self.unison_voices as f32;
-
         while v + LANES <= self.unison_voices {
             // Vector of voice offsets
             let offs =
@@ -417,9 +587,7 @@ impl AnalogOscillator {
             // sample lookup
             let mut voice_smp = [0.0f32; LANES];
             for k in 0..LANES {
-                let freq_k = eff_freq[k];
-                let table = bank.select_table(freq_k);
-                voice_smp[k] = cubic_interp(&table.samples, lookup_phase[k]);
+                voice_smp[k] = self.read_table(bank, eff_freq[k], lookup_phase[k]);
             }
 
             // write back phases & outs, and accumulate with stereo panning
@@ -427,20 +595,10 @@ impl AnalogOscillator {
                 self.voice_phases[v + k] = new_phase[k];
                 self.voice_last_out[v + k] = voice_smp[k];
 
-                // Calculate pan position for this voice: -1 (left) to +1 (right)
-                let pan = if total_voices > 1.0 {
-                    ((v + k) as f32 / (total_voices - 1.0)) * 2.0 - 1.0
-                } else {
-                    0.0 // Center for single voice
-                };
-
-                // Equal power panning: sqrt((1-pan)/2) for left, sqrt((1+pan)/2) for right
-                let pan_norm = (pan + 1.0) * 0.5; // Normalize to 0..1
-                let gain_l = ((1.0 - pan_norm) * std::f32::consts::FRAC_PI_2).cos();
-                let gain_r = (pan_norm * std::f32::consts::FRAC_PI_2).cos();
-
-                sum_l += voice_smp[k] * gain_l;
-                sum_r += voice_smp[k] * gain_r;
+                let weighted = voice_smp[k] * self.voice_weights[v + k];
+                let (gain_l, gain_r) = self.voice_pan_gains[v + k];
+                sum_l += weighted * gain_l;
+                sum_r += weighted * gain_r;
             }
 
             v += LANES;
@@ -454,26 +612,14 @@ impl AnalogOscillator {
             let np = (self.voice_phases[r] + inc).rem_euclid(1.0);
             let fb = (self.voice_last_out[r] * feedback_amt) / self.feedback_divisor;
             let lookup = (np + ext_phase_offset + fb).rem_euclid(1.0);
-            let samp = {
-                let tbl = bank.select_table(eff_freq);
-                cubic_interp(&tbl.samples, lookup)
-            };
+            let samp = self.read_table(bank, eff_freq, lookup);
             self.voice_phases[r] = np;
             self.voice_last_out[r] = samp;
 
-            // Calculate pan for this voice
-            let pan = if total_voices > 1.0 {
-                (r as f32 / (total_voices - 1.0)) * 2.0 - 1.0
-            } else {
-                0.0
-            };
-
-            let pan_norm = (pan + 1.0) * 0.5;
-            let gain_l = ((1.0 - pan_norm) * std::f32::consts::FRAC_PI_2).cos();
-            let gain_r = (pan_norm * std::f32::consts::FRAC_PI_2).cos();
-
-            sum_l += samp * gain_l;
-            sum_r += samp * gain_r;
+            let weighted = samp * self.voice_weights[r];
+            let (gain_l, gain_r) = self.voice_pan_gains[r];
+            sum_l += weighted * gain_l;
+            sum_r += weighted * gain_r;
         }
 
         let total_weight: f32 = self.voice_weights.iter().sum();
@@ -484,23 +630,70 @@ impl AnalogOscillator {
         };
         (sum_l * norm * gain, sum_r * norm * gain)
     }
+
+    /// Reads the mip level for `frequency` at `phase`, with the quality settings applied.
+    #[inline(always)]
+    fn read_table(&self, bank: &WavetableBank, frequency: f32, phase: f32) -> f32 {
+        let select_freq = frequency * self.mip_bias_ratio;
+        let table = bank.select_table(select_freq);
+        let points = self.table_points;
+        // Each table carries a copy of its first point at the end; the cycle stops before it.
+        if points == 0 || points >= table.table_size {
+            let size = table.table_size;
+            return interpolate(&table.samples, size, 1, phase, self.interpolation);
+        }
+
+        // Fewer points hold fewer harmonics: stay on levels with at most points / 2 of them.
+        let min_freq = 4.0 / (3.0 * points as f32 * self.sample_rate_recip);
+        let table = bank.select_table(select_freq.max(min_freq));
+        let stride = table.table_size / points;
+        interpolate(&table.samples, points, stride, phase, self.interpolation)
+    }
+
+    /// One sample of the sub oscillator. It follows the main pitch, detune included,
+    /// `sub_octave` octaves down and sits in the centre at the level of a single voice.
+    #[inline(always)]
+    fn process_sub(&mut self, i: usize, bank: &WavetableBank, base_freq: f32) -> f32 {
+        let detune = self.cent_ratio.powf(self.target_detune_cents)
+            * self.semitone_ratio.powf(self.detune_mod_buf[i]);
+        let freq = base_freq * detune / (1u32 << self.sub_octave) as f32;
+        self.sub_phase = (self.sub_phase + freq * self.sample_rate_recip).rem_euclid(1.0);
+        self.read_table(bank, freq, self.sub_phase)
+            * self.smoothed_sub_gain
+            * self.gain_buf[i]
+            * std::f32::consts::FRAC_1_SQRT_2
+    }
 }
 
 // ------------------------------------------------------------------------------------------------------------------
 // Small helpers that didn’t fit anywhere else
 // ------------------------------------------------------------------------------------------------------------------
 
+/// Reads `samples` as `points` evenly spaced values, `stride` apart, at `phase` (0..1).
 #[inline(always)]
-fn cubic_interp(samples: &[f32], phase: f32) -> f32 {
-    let n = samples.len();
-    if n == 0 {
+fn interpolate(
+    samples: &[f32],
+    points: usize,
+    stride: usize,
+    phase: f32,
+    interpolation: TableInterpolation,
+) -> f32 {
+    if points == 0 {
         return 0.0;
     }
-    let pos = phase.rem_euclid(1.0) * n as f32;
+    let pos = phase.rem_euclid(1.0) * points as f32;
     let i = pos.floor() as isize;
     let frac = pos - i as f32;
 
-    let idx = |j: isize| -> f32 { samples[((i + j).rem_euclid(n as isize)) as usize] };
+    let idx = |j: isize| -> f32 {
+        let point = (i + j).rem_euclid(points as isize) as usize;
+        samples[point * stride]
+    };
+
+    if interpolation == TableInterpolation::Linear {
+        let p1 = idx(0);
+        return p1 + (idx(1) - p1) * frac;
+    }
 
     let p0 = idx(-1);
     let p1 = idx(0);
@@ -574,6 +767,7 @@ impl AudioNode for AnalogOscillator {
         if (self.smoothed_spread_cents - prev_spread).abs() > 0.5 {
             self.recalc_voice_offsets();
         }
+        self.smoothed_sub_gain += alpha * (self.target_sub_gain - self.smoothed_sub_gain);
 
         // --- 2) modulation helpers ---------------------------------------------------------------------------
         let mut scratch = |port: PortId, base: f32, target: &mut [f32]| {
@@ -693,6 +887,12 @@ impl AudioNode for AnalogOscillator {
             }
         };
 
+        let sub_bank = if self.smoothed_sub_gain > 0.0 || self.target_sub_gain > 0.0 {
+            self.wavetable_banks.get(&self.sub_waveform).cloned()
+        } else {
+            None
+        };
+
         // Initialize output buffers
         if let Some(o) = outputs.get_mut(&PortId::AudioOutput0) {
             o[..buffer_size].fill(0.0);
@@ -714,12 +914,16 @@ impl AudioNode for AnalogOscillator {
                 // fewer than 4 voices but more than 1 → fall back to scalar remainder code
                 self.process_simd::<1>(i, &bank, freq)
             };
+            let sub = match &sub_bank {
+                Some(sub_bank) => self.process_sub(i, sub_bank, freq),
+                None => 0.0,
+            };
 
             if let Some(o) = outputs.get_mut(&PortId::AudioOutput0) {
-                o[i] = sample_l;
+                o[i] = sample_l + sub;
             }
             if let Some(o) = outputs.get_mut(&PortId::AudioOutput1) {
-                o[i] = sample_r;
+                o[i] = sample_r + sub;
             }
         }
     }
@@ -732,6 +936,7 @@ impl AudioNode for AnalogOscillator {
         for o in &mut self.voice_last_out {
             *o = 0.0;
         }
+        self.sub_phase = 0.0;
     }
 
     fn as_any_mut(&mut self) -> &mut dyn Any {
@@ -743,12 +948,37 @@ impl AudioNode for AnalogOscillator {
     fn is_active(&self) -> bool {
         self.active
     }
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.smoothing_coeff = smoothing_coefficient(1.0 / self.sample_rate_recip, time_ms);
+    }
+
     fn set_active(&mut self, active: bool) {
         self.active = active;
         if !active {
             self.reset();
         }
     }
+    fn modulation_target(&self, port: PortId) -> Option<ModulationTarget> {
+        let (parameter, unit, base, min) = match port {
+            // Frequency modulation acts on the played pitch; A4 stands in for it.
+            PortId::FrequencyMod => ("frequency", "Hz", 440.0, 0.0),
+            PortId::GainMod => ("gain", "", self.target_gain, 0.0),
+            PortId::DetuneMod => ("detune", "st", 0.0, f32::NEG_INFINITY),
+            PortId::ModIndex => ("modIndex", "", self.target_phase_mod_amount, 0.0),
+            _ => return None,
+        };
+        Some(ModulationTarget {
+            parameter,
+            unit,
+            base,
+            min,
+            max: f32::INFINITY,
+            mapping: TargetMapping::Linear,
+        })
+    }
+    fn clone_node(&self) -> Option<Box<dyn AudioNode>> {
+        Some(Box::new(self.clone()))
+    }
     fn name(&self) -> &'static str {
         "Analog Oscillator"
     }
@@ -756,3 +986,142 @@ impl AudioNode for AnalogOscillator {
         "analog_oscillator"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::aliasing::{measure_aliasing, render_tone};
+
+    fn unison(voices: u32, stereo_spread: f32, blend: f32) -> AnalogOscillatorStateUpdate {
+        AnalogOscillatorStateUpdate {
+            id: None,
+            phase_mod_amount: 0.0,
+            freq_mod_amount: 0.0,
+            detune_oct: 0.0,
+            detune_semi: 0.0,
+            detune_cents: 0.0,
+            detune: 0.0,
+            hard_sync: false,
+            gain: 1.0,
+            active: true,
+            feedback_amount: 0.0,
+            waveform: Waveform::Saw,
+            unison_voices: voices,
+            spread: 10.0,
+            stereo_spread,
+            phase_randomization: 0.0,
+            blend,
+            wave_index: 0.0,
+            sub_gain: 0.0,
+            sub_octave: 1,
+            sub_waveform: Waveform::Square,
+            mip_bias: 0.0,
+            table_size: 0,
+            interpolation: TableInterpolation::Cubic,
+        }
+    }
+
+    fn oscillator(update: &AnalogOscillatorStateUpdate) -> AnalogOscillator {
+        let mut banks = FxHashMap::default();
+        for waveform in [Waveform::Saw, Waveform::Sine, Waveform::Square] {
+            let bank = WavetableBank::new(waveform, 256, 48_000.0).unwrap();
+            banks.insert(waveform, Arc::new(bank));
+        }
+        let mut osc = AnalogOscillator::new(48_000.0, Waveform::Saw, Arc::new(banks));
+        osc.update_params(update);
+        osc
+    }
+
+    fn render(update: &AnalogOscillatorStateUpdate) -> (Vec<f32>, Vec<f32>) {
+        let mut osc = oscillator(update);
+
+        let mut left = vec![0.0; 2048];
+        let mut right = vec![0.0; 2048];
+        let mut outputs = FxHashMap::default();
+        outputs.insert(PortId::AudioOutput0, left.as_mut_slice());
+        outputs.insert(PortId::AudioOutput1, right.as_mut_slice());
+        osc.process(&FxHashMap::default(), &mut outputs, 2048);
+        drop(outputs);
+        (left, right)
+    }
+
+    #[test]
+    fn unison_spreads_voices_across_the_stereo_field() {
+        let (left, right) = render(&unison(3, 0.0, 1.0));
+        assert_eq!(left, right);
+        let (left, right) = render(&unison(3, 1.0, 1.0));
+        assert_ne!(left, right);
+
+        // Without the detuned voices only the centre one is heard.
+        let (single, _) = render(&unison(1, 1.0, 1.0));
+        let (centre, _) = render(&unison(3, 1.0, 0.0));
+        assert_eq!(single, centre);
+        assert!(single.iter().any(|s| s.abs() > 0.1));
+    }
+    #[test]
+    fn sub_oscillator_plays_octaves_below() {
+        let (dry, _) = render(&unison(1, 1.0, 1.0));
+        let rising_edges = |sub_octave: u32| {
+            let update = AnalogOscillatorStateUpdate {
+                sub_gain: 1.0,
+                sub_octave,
+                ..unison(1, 1.0, 1.0)
+            };
+            let (left, right) = render(&update);
+            assert_eq!(left, right);
+            let sub: Vec<f32> = left.iter().zip(&dry).map(|(l, d)| l - d).collect();
+            sub.windows(2).filter(|w| w[0] < 0.0 && w[1] >= 0.0).count()
+        };
+        // 2048 samples of 440 Hz hold about 18.8 cycles.
+        assert!((8..=10).contains(&rising_edges(1)));
+        assert!((4..=5).contains(&rising_edges(2)));
+    }
+
+    #[test]
+    fn quality_settings_trade_brightness_for_aliasing() {
+        // At 3.9 kHz the default level holds 7 harmonics, the top ones past Nyquist.
+        let measure = |update: AnalogOscillatorStateUpdate| {
+            let mut osc = oscillator(&update);
+            measure_aliasing(&render_tone(&mut osc, 3_900.0), 48_000.0, 3_900.0)
+        };
+        let default = measure(unison(1, 0.0, 1.0));
+        let biased = measure(AnalogOscillatorStateUpdate {
+            mip_bias: 1.0,
+            ..unison(1, 0.0, 1.0)
+        });
+        assert!(default.alias_db > -25.0, "{:?}", default);
+        assert!(biased.alias_db < default.alias_db - 20.0, "{:?}", biased);
+
+        // A few points with linear steps leave images of the harmonics behind.
+        let coarse = measure(AnalogOscillatorStateUpdate {
+            mip_bias: 1.0,
+            table_size: 16,
+            interpolation: TableInterpolation::Linear,
+            ..unison(1, 0.0, 1.0)
+        });
+        assert!(coarse.alias_db > biased.alias_db + 10.0, "{:?}", coarse);
+    }
+
+    #[test]
+    fn swapped_banks_replace_a_waveform() {
+        let cycle: Vec<f32> = (0..256)
+            .map(|i| (std::f32::consts::TAU * i as f32 / 256.0).sin())
+            .collect();
+        let mut swapped = oscillator(&unison(1, 0.0, 1.0));
+        let mut banks = (*swapped.wavetable_banks).clone();
+        let bank = WavetableBank::from_cycle(&cycle, 48_000.0).unwrap();
+        banks.insert(Waveform::Saw, Arc::new(bank));
+        swapped.set_wavetable_banks(Arc::new(banks));
+
+        let mut sine = oscillator(&AnalogOscillatorStateUpdate {
+            waveform: Waveform::Sine,
+            ..unison(1, 0.0, 1.0)
+        });
+        let expected = render_tone(&mut sine, 440.0);
+        let max_diff = render_tone(&mut swapped, 440.0)
+            .iter()
+            .zip(&expected)
+            .fold(0.0f32, |m, (a, b)| m.max((a - b).abs()));
+        assert!(max_diff < 1e-4, "{}", max_diff);
+    }
+}
@@ -2,6 +2,7 @@ use core::simd::Simd;
 use std::any::Any;
 
 use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
 #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
 use web_sys::console;
 
@@ -14,15 +15,129 @@ fn log_console(message: &str) {
 fn log_console(_message: &str) {}
 
 use crate::graph::ModulationSource;
+use crate::utils::groove::{Groove, STRAIGHT_SWING_PERCENT};
 use crate::{AudioNode, PortId};
 
+/// Most gates a single step can be split into.
+pub const MAX_RATCHETS: u32 = 8;
+
 /// A single step in the arpeggiator pattern.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
 pub struct PatternStep {
     /// The modulation value (in cents) for this step.
     pub value: f32,
     /// Whether this step is active (i.e. should trigger the gate).
     pub active: bool,
+    /// Number of evenly spaced gates the step is split into (1 = a single gate).
+    pub ratchets: u32,
+}
+
+impl Default for PatternStep {
+    fn default() -> Self {
+        Self {
+            value: 0.0,
+            active: true,
+            ratchets: 1,
+        }
+    }
+}
+
+/// Spreads `pulses` onsets as evenly as possible over `steps` steps (Bjorklund's
+/// rhythms, e.g. 3 in 8 is the tresillo), rotated left by `rotation` steps.
+pub fn euclidean_rhythm(steps: usize, pulses: usize, rotation: usize) -> Vec<bool> {
+    let pulses = pulses.min(steps);
+    (0..steps)
+        .map(|i| ((i + rotation) % steps * pulses) % steps < pulses)
+        .collect()
+}
+
+/// Most octaves the held notes are spread over.
+pub const MAX_ARP_OCTAVES: u32 = 4;
+
+/// Order the held notes are played in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ArpeggiatorDirection {
+    #[default]
+    Up,
+    Down,
+    /// Up then down, without repeating the top and bottom notes.
+    UpDown,
+    /// A random held note each step.
+    Random,
+    /// The order the notes were pressed in.
+    AsPlayed,
+}
+
+/// Settings and held notes of a note-driven arpeggiator.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ArpeggiatorConfig {
+    pub direction: ArpeggiatorDirection,
+    /// Octaves the held notes repeat over (1 to `MAX_ARP_OCTAVES`).
+    pub octaves: u32,
+    /// Fraction of each step (or ratchet) the gate stays high.
+    pub gate_length: f32,
+    /// Swing percent (50 to 75); 50 keeps the swing of the engine's groove.
+    pub swing: f32,
+    /// Keeps playing the last notes after every key is released; the next
+    /// chord replaces them. The host keeps the voice gate open meanwhile.
+    pub latch: bool,
+    /// Steps follow `bpm` and `steps_per_beat` instead of `rate_hz`.
+    pub tempo_sync: bool,
+    pub bpm: f32,
+    /// Steps per beat when synced (4 = sixteenths).
+    pub steps_per_beat: f32,
+    /// Steps per second when not synced.
+    pub rate_hz: f32,
+    /// MIDI notes currently held, in the order they were pressed.
+    pub held_notes: Vec<u8>,
+}
+
+impl Default for ArpeggiatorConfig {
+    fn default() -> Self {
+        Self {
+            direction: ArpeggiatorDirection::Up,
+            octaves: 1,
+            gate_length: 1.0,
+            swing: STRAIGHT_SWING_PERCENT,
+            latch: false,
+            tempo_sync: true,
+            bpm: 120.0,
+            steps_per_beat: 4.0,
+            rate_hz: 8.0,
+            held_notes: Vec::new(),
+        }
+    }
+}
+
+/// Builds the step values (cents above the lowest note) for `notes`, given in
+/// the order they were pressed.
+pub fn arpeggio_steps(notes: &[u8], direction: ArpeggiatorDirection, octaves: u32) -> Vec<f32> {
+    let Some(&lowest) = notes.iter().min() else {
+        return Vec::new();
+    };
+    let mut ordered = notes.to_vec();
+    if direction != ArpeggiatorDirection::AsPlayed {
+        ordered.sort_unstable();
+        ordered.dedup();
+    }
+    let mut steps: Vec<f32> = (0..octaves.clamp(1, MAX_ARP_OCTAVES))
+        .flat_map(|octave| {
+            ordered.iter().map(move |&note| {
+                (f32::from(note) - f32::from(lowest)) * 100.0 + octave as f32 * 1200.0
+            })
+        })
+        .collect();
+    match direction {
+        ArpeggiatorDirection::Down => steps.reverse(),
+        ArpeggiatorDirection::UpDown if steps.len() > 2 => {
+            let down: Vec<f32> = steps[1..steps.len() - 1].iter().rev().copied().collect();
+            steps.extend(down);
+        }
+        _ => {}
+    }
+    steps
 }
 
 /// Modes for the arpeggiator.
@@ -40,6 +155,13 @@ pub enum ArpeggiatorMode {
 /// In Trigger mode, a gate input is used to reset the progression on a rising edge.
 /// If gate output is enabled, it writes a gate signal that is high for active steps (except during a brief gap)
 /// and low for skipped steps.
+/// When a clock (e.g. the transport's `Clock` node) is connected to `ClockInput`, each rising edge
+/// advances one step instead of the internal step timer, and the gate follows the clock pulse.
+/// With the internal timer, each step counts as a sixteenth of the groove template.
+///
+/// Fed held notes through `update_config`, it builds the pattern from them:
+/// the output is then in cents above the lowest held note, which the host
+/// plays the voice at.
 pub struct ArpeggiatorGenerator {
     /// Whether the arpeggiator is enabled.
     enabled: bool,
@@ -57,6 +179,23 @@ pub struct ArpeggiatorGenerator {
     gate_output_enabled: bool,
     /// Field for storing the previous step index (for potential further extensions).
     prev_step: usize,
+    /// The previous clock input state (for clock edge detection).
+    prev_clock_active: bool,
+    /// Steps taken on the clock input, or None before the first clock edge.
+    clock_steps: Option<usize>,
+    /// Samples since the last clock edge and between the last two, for ratchets.
+    samples_since_clock: usize,
+    clock_period: Option<usize>,
+    /// Gate written by the clocked path, kept between blocks so it isn't reallocated.
+    clock_gate: Vec<f32>,
+    /// Groove applied to the internal step timer.
+    groove: Groove,
+    /// Groove set on the graph, before the config's swing is applied.
+    graph_groove: Groove,
+    sample_rate: f32,
+    config: ArpeggiatorConfig,
+    /// Notes the pattern is built from; outlives `held_notes` when latched.
+    playing_notes: Vec<u8>,
 }
 
 impl ArpeggiatorGenerator {
@@ -71,6 +210,100 @@ impl ArpeggiatorGenerator {
             prev_gate_active: false,
             gate_output_enabled: false,
             prev_step: 0,
+            prev_clock_active: false,
+            clock_steps: None,
+            samples_since_clock: 0,
+            clock_period: None,
+            clock_gate: vec![0.0; 128],
+            groove: Groove::default(),
+            graph_groove: Groove::default(),
+            sample_rate: 44_100.0,
+            config: ArpeggiatorConfig::default(),
+            playing_notes: Vec::new(),
+        }
+    }
+
+    /// Creates a note-driven arpeggiator with its gate output enabled.
+    pub fn with_config(sample_rate: f32, config: ArpeggiatorConfig) -> Self {
+        let mut arp = Self::new();
+        arp.sample_rate = sample_rate;
+        arp.set_gate_output_enabled(true);
+        arp.update_config(config);
+        arp
+    }
+
+    pub fn config(&self) -> &ArpeggiatorConfig {
+        &self.config
+    }
+
+    /// Notes the arpeggio is playing, which may be latched notes no longer held.
+    pub fn playing_notes(&self) -> &[u8] {
+        &self.playing_notes
+    }
+
+    /// Applies new settings and held notes. The pattern is rebuilt from the
+    /// notes, keeping the active steps and ratchets of the current rhythm;
+    /// without notes the arpeggiator falls silent.
+    pub fn update_config(&mut self, mut config: ArpeggiatorConfig) {
+        config.octaves = config.octaves.clamp(1, MAX_ARP_OCTAVES);
+        config.gate_length = config.gate_length.clamp(0.01, 1.0);
+        config.held_notes.retain(|&note| note < 128);
+
+        let was_held = !self.config.held_notes.is_empty();
+        if config.held_notes.is_empty() {
+            if !config.latch {
+                self.playing_notes.clear();
+            }
+        } else if config.latch && was_held {
+            // Keys added to a chord that is still held join the latched notes.
+            for &note in &config.held_notes {
+                if !self.playing_notes.contains(&note) {
+                    self.playing_notes.push(note);
+                }
+            }
+        } else {
+            self.playing_notes = config.held_notes.clone();
+        }
+
+        let step_seconds = if config.tempo_sync {
+            60.0 / (config.bpm.max(1.0) * config.steps_per_beat.max(0.0625))
+        } else {
+            1.0 / config.rate_hz.max(0.01)
+        };
+        let step_samples = ((step_seconds * self.sample_rate) as usize).max(1);
+        if step_samples != self.step_samples {
+            self.set_delay_time(step_samples);
+        }
+
+        self.config = config;
+        self.apply_swing();
+
+        let values = arpeggio_steps(
+            &self.playing_notes,
+            self.config.direction,
+            self.config.octaves,
+        );
+        self.enabled = !values.is_empty();
+        if self.enabled {
+            let template = std::mem::take(&mut self.pattern);
+            self.pattern = values
+                .into_iter()
+                .enumerate()
+                .map(|(i, value)| PatternStep {
+                    value,
+                    ..template
+                        .get(i % template.len().max(1))
+                        .copied()
+                        .unwrap_or_default()
+                })
+                .collect();
+        }
+    }
+
+    fn apply_swing(&mut self) {
+        self.groove = self.graph_groove;
+        if self.config.swing > STRAIGHT_SWING_PERCENT {
+            self.groove.set_swing_percent(self.config.swing);
         }
     }
 
@@ -100,78 +333,40 @@ impl ArpeggiatorGenerator {
         self.prev_step = 0;
     }
 
-    pub fn create_test_pattern(&mut self, sample_rate: f32, arp_delay: f32) {
-        // Two measures, each 16 notes, for a total of 32 steps.
-        // Measure 1: a-c-f-g-a-c-f-g-a (up), then g-f-c-a-g-f-c (down)
-        // Measure 2: f-a-d-e-f-a-d-e-f (up), then e-d-a-f-e-d-a (down)
-        //
-        // Offsets are relative to the FIRST A = 0 semitones.
-
-        // Measure 1 (16 steps):
-        let measure1 = vec![
-            // Up (9)
-            0.0,  // A
-            3.0,  // C
-            8.0,  // F
-            10.0, // G
-            12.0, // A (1 octave up)
-            15.0, // C
-            20.0, // F
-            22.0, // G
-            24.0, // A (2 octaves above the start)
-            // Down (7)
-            22.0, // G
-            20.0, // F
-            15.0, // C
-            12.0, // A
-            10.0, // G
-            8.0,  // F
-            3.0,  // C
-        ];
-
-        // Measure 2 (16 steps):
-        let measure2 = vec![
-            // Up (9)
-            8.0 - 12.0,  // F
-            12.0 - 12.0, // A
-            17.0 - 12.0, // D
-            19.0 - 12.0, // E
-            20.0 - 12.0, // F
-            24.0 - 12.0, // A
-            29.0 - 12.0, // D
-            31.0 - 12.0, // E
-            32.0 - 12.0, // F (top)
-            // Down (7)
-            31.0 - 12.0, // E
-            29.0 - 12.0, // D
-            24.0 - 12.0, // A
-            20.0 - 12.0, // F
-            19.0 - 12.0, // E
-            17.0 - 12.0, // D
-            12.0 - 12.0, // A
-        ];
-
-        // Convert them to PatternStep structs with `active: true`
-        let mut pattern = Vec::with_capacity(measure1.len() + measure2.len());
-        for semitone in measure1.iter().chain(measure2.iter()) {
-            pattern.push(PatternStep {
-                value: *semitone,
-                active: true,
-            });
-        }
-
-        // Determine how many samples each note should last
-        let step_samples = (sample_rate * arp_delay) as usize;
-
-        // Enable the gate so the notes sound
-        self.set_gate_output_enabled(true);
+    /// The current pattern, for display.
+    pub fn pattern(&self) -> &[PatternStep] {
+        &self.pattern
+    }
 
-        // Load the combined pattern (32 steps)
-        self.enable(pattern, step_samples);
+    /// Rebuilds the pattern as a euclidean rhythm of `pulses` active steps out of
+    /// `steps`, rotated by `rotation`. Step values and ratchets repeat the current
+    /// pattern; the progression restarts.
+    pub fn set_euclidean(&mut self, steps: usize, pulses: usize, rotation: usize) {
+        let template = std::mem::take(&mut self.pattern);
+        let pattern = euclidean_rhythm(steps, pulses, rotation)
+            .into_iter()
+            .enumerate()
+            .map(|(i, active)| PatternStep {
+                active,
+                ..template
+                    .get(i % template.len().max(1))
+                    .copied()
+                    .unwrap_or_default()
+            })
+            .collect();
+        self.set_pattern(pattern);
+    }
 
-        // Cycle through them
-        self.set_mode(ArpeggiatorMode::Trigger);
-        self.set_gate_output_enabled(true);
+    /// Splits a step into `ratchets` gates (1 to `MAX_RATCHETS`). Returns false if
+    /// the step doesn't exist.
+    pub fn set_step_ratchets(&mut self, step: usize, ratchets: u32) -> bool {
+        match self.pattern.get_mut(step) {
+            Some(pattern_step) => {
+                pattern_step.ratchets = ratchets.clamp(1, MAX_RATCHETS);
+                true
+            }
+            None => false,
+        }
     }
 
     /// Set the delay time between steps (in samples) and reset the progression.
@@ -186,24 +381,21 @@ impl ArpeggiatorGenerator {
         self.gate_output_enabled = enabled;
     }
 
-    /// Computes the modulation value (in cents) for the given sample index.
-    /// If the corresponding pattern step is inactive, it returns 0.0.
+    /// Maps the number of steps taken onto an index in the pattern.
     #[inline]
-    fn modulation_value(&self, sample_index: usize) -> f32 {
-        if !self.enabled || self.pattern.is_empty() || self.step_samples == 0 {
-            return 0.0;
+    fn pattern_index(&self, steps: usize) -> usize {
+        if self.config.direction == ArpeggiatorDirection::Random {
+            return random_index(steps, self.pattern.len());
         }
-        let step_index = match self.mode {
-            ArpeggiatorMode::FreeRunning | ArpeggiatorMode::Trigger => {
-                (sample_index / self.step_samples) % self.pattern.len()
-            }
+        match self.mode {
+            ArpeggiatorMode::FreeRunning | ArpeggiatorMode::Trigger => steps % self.pattern.len(),
             ArpeggiatorMode::PingPong => {
                 let n = self.pattern.len();
                 if n == 1 {
                     0
                 } else {
                     let period = 2 * n - 2;
-                    let pos = (sample_index / self.step_samples) % period;
+                    let pos = steps % period;
                     if pos < n {
                         pos
                     } else {
@@ -211,7 +403,39 @@ impl ArpeggiatorGenerator {
                     }
                 }
             }
-        };
+        }
+    }
+
+    /// First sample of the given step, moved by the groove.
+    #[inline]
+    fn step_onset(&self, step: usize) -> usize {
+        let offset = (self.groove.step_offset(step) * self.step_samples as f32).round() as isize;
+        ((step * self.step_samples) as isize + offset).max(0) as usize
+    }
+
+    /// Returns the step playing at the given sample index and how many samples into it we are.
+    #[inline]
+    fn step_at(&self, sample_index: usize) -> (usize, usize) {
+        let step = sample_index / self.step_samples;
+        if self.groove.is_straight() {
+            return (step, sample_index % self.step_samples);
+        }
+        // A grooved step starts at most a quarter step early or three quarters late.
+        let step = [step + 1, step, step.saturating_sub(1)]
+            .into_iter()
+            .find(|&step| self.step_onset(step) <= sample_index)
+            .unwrap_or(0);
+        (step, sample_index.saturating_sub(self.step_onset(step)))
+    }
+
+    /// Computes the modulation value (in cents) for the given sample index.
+    /// If the corresponding pattern step is inactive, it returns 0.0.
+    #[inline]
+    fn modulation_value(&self, sample_index: usize) -> f32 {
+        if !self.enabled || self.pattern.is_empty() || self.step_samples == 0 {
+            return 0.0;
+        }
+        let step_index = self.pattern_index(self.step_at(sample_index).0);
 
         let step = self.pattern[step_index];
         if step.active {
@@ -254,8 +478,8 @@ impl ArpeggiatorGenerator {
             let global_index = self.sample_counter + i;
             let global_index_end = self.sample_counter + i + LANES - 1;
             // Determine if the entire SIMD block falls within the same arpeggiator step.
-            let step_start = global_index / self.step_samples;
-            let step_end = global_index_end / self.step_samples;
+            let step_start = self.step_at(global_index).0;
+            let step_end = self.step_at(global_index_end).0;
             if self.enabled && step_start == step_end {
                 let value = self.modulation_value(global_index);
                 let modulation_vec = Vf32::splat(value);
@@ -363,6 +587,77 @@ impl ArpeggiatorGenerator {
         // }
     }
 
+    /// Process the arpeggiator stepping on the clock input.
+    ///
+    /// Each rising clock edge advances one step; in Trigger mode a gate rising edge makes the next
+    /// clock edge start the pattern over. The gate output is high while the clock pulse is high on
+    /// active steps; ratcheted steps split the last clock period into evenly spaced gates.
+    fn process_clocked<'a>(
+        &mut self,
+        inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+        outputs: &mut FxHashMap<PortId, &mut [f32]>,
+        buffer_size: usize,
+    ) {
+        let clock = self.process_modulations(buffer_size, inputs.get(&PortId::ClockInput), 0.0);
+        let gate_mod = self.process_modulations(buffer_size, inputs.get(&PortId::GlobalGate), 0.0);
+        let mut gate = std::mem::take(&mut self.clock_gate);
+        if gate.len() < buffer_size {
+            gate.resize(buffer_size, 0.0);
+        }
+        gate[..buffer_size].fill(0.0);
+        let output = outputs
+            .get_mut(&PortId::AudioOutput0)
+            .expect("Expected AudioOutput0 output port");
+        for j in 0..buffer_size {
+            let current_gate = gate_mod[j] > 0.5;
+            if self.mode == ArpeggiatorMode::Trigger && !self.prev_gate_active && current_gate {
+                self.clock_steps = None;
+            }
+            self.prev_gate_active = current_gate;
+
+            let clock_active = clock[j] > 0.5;
+            if clock_active && !self.prev_clock_active {
+                if self.clock_steps.is_some() {
+                    self.clock_period = Some(self.samples_since_clock);
+                }
+                self.clock_steps = Some(self.clock_steps.map_or(0, |steps| steps + 1));
+                self.samples_since_clock = 0;
+            }
+            self.prev_clock_active = clock_active;
+            let since_clock = self.samples_since_clock;
+            self.samples_since_clock += 1;
+
+            output[j] = 0.0;
+            if !self.enabled || self.pattern.is_empty() {
+                continue;
+            }
+            if let Some(steps) = self.clock_steps {
+                let pattern_step = self.pattern[self.pattern_index(steps)];
+                if pattern_step.active {
+                    output[j] = pattern_step.value;
+                    let gate_length = self.config.gate_length;
+                    let high = match self.clock_period {
+                        Some(period) if pattern_step.ratchets > 1 || gate_length < 1.0 => {
+                            let ratchet_length = (period / pattern_step.ratchets as usize).max(2);
+                            let duty = if gate_length < 1.0 { gate_length } else { 0.5 };
+                            let high_length = ((ratchet_length as f32 * duty) as usize).max(1);
+                            since_clock % ratchet_length < high_length
+                        }
+                        _ => clock_active,
+                    };
+                    gate[j] = if high { 1.0 } else { 0.0 };
+                }
+            }
+        }
+
+        if self.gate_output_enabled {
+            if let Some(gate_output) = outputs.get_mut(&PortId::ArpGate) {
+                gate_output[..buffer_size].copy_from_slice(&gate[..buffer_size]);
+            }
+        }
+        self.clock_gate = gate;
+    }
+
     /// Process the node while optionally writing a gate signal.
     ///
     /// For each step, if the step is active the gate output is high (except for a brief gap at the end of the step);
@@ -374,6 +669,10 @@ impl ArpeggiatorGenerator {
         buffer_size: usize,
     ) {
         // Process modulation output according to mode.
+        if inputs.contains_key(&PortId::ClockInput) {
+            self.process_clocked(inputs, outputs, buffer_size);
+            return;
+        }
         if self.mode == ArpeggiatorMode::Trigger {
             self.process_trigger_mode(inputs, outputs, buffer_size);
         } else {
@@ -383,25 +682,33 @@ impl ArpeggiatorGenerator {
         // If gate output is enabled, write the gate signal.
         if self.gate_output_enabled {
             if let Some(gate_output) = outputs.get_mut(&PortId::ArpGate) {
+                if !self.enabled || self.pattern.is_empty() || self.step_samples == 0 {
+                    gate_output[..buffer_size].fill(0.0);
+                    return;
+                }
                 // Define a gap duration (in samples) at the end of an active step.
                 let gap_samples = 2;
                 // Compute the starting index for the current block.
-                let block_start = self.sample_counter - buffer_size;
+                let block_start = self.sample_counter.saturating_sub(buffer_size);
                 for j in 0..buffer_size {
                     let global_index = block_start + j;
-                    let relative = global_index % self.step_samples;
-                    let step_index = (global_index / self.step_samples) % self.pattern.len();
-                    let pattern_step = self.pattern[step_index];
+                    let (step, relative) = self.step_at(global_index);
+                    let step_length = self
+                        .step_onset(step + 1)
+                        .saturating_sub(self.step_onset(step));
+                    let pattern_step = self.pattern[self.pattern_index(step)];
                     // If the step is inactive, the gate remains off.
                     if !pattern_step.active {
                         gate_output[j] = 0.0;
                     } else {
-                        // Otherwise, gate is high for most of the step except during the gap.
-                        gate_output[j] = if relative >= self.step_samples - gap_samples {
-                            0.0
-                        } else {
-                            1.0
-                        };
+                        // Otherwise, gate is high for most of each ratchet except during the gap.
+                        let ratchet_length =
+                            (step_length / pattern_step.ratchets.max(1) as usize).max(1);
+                        let relative = relative % ratchet_length;
+                        let high_length = ((ratchet_length as f32 * self.config.gate_length)
+                            as usize)
+                            .min(ratchet_length.saturating_sub(gap_samples));
+                        gate_output[j] = if relative >= high_length { 0.0 } else { 1.0 };
                     }
                 }
             }
@@ -409,17 +716,28 @@ impl ArpeggiatorGenerator {
     }
 }
 
+/// Pattern index for step `steps` of a random arpeggio, repeatable per step.
+#[inline]
+fn random_index(steps: usize, len: usize) -> usize {
+    let mut x = (steps as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    ((x ^ (x >> 31)) % len.max(1) as u64) as usize
+}
+
 impl AudioNode for ArpeggiatorGenerator {
     /// Define the node's ports.
     ///
     /// - PortId::AudioOutput0: modulation output (in cents).
     /// - PortId::GlobalGate: optional gate input (for Trigger mode).
     /// - PortId::ArpGate: optional gate trigger output.
+    /// - PortId::ClockInput: optional clock that steps the pattern.
     fn get_ports(&self) -> FxHashMap<PortId, bool> {
         let mut ports = FxHashMap::default();
         ports.insert(PortId::AudioOutput0, true);
         ports.insert(PortId::GlobalGate, false);
         ports.insert(PortId::ArpGate, true);
+        ports.insert(PortId::ClockInput, false);
         ports
     }
 
@@ -436,6 +754,15 @@ impl AudioNode for ArpeggiatorGenerator {
         self.sample_counter = 0;
         self.prev_gate_active = false;
         self.prev_step = 0;
+        self.prev_clock_active = false;
+        self.clock_steps = None;
+        self.samples_since_clock = 0;
+        self.clock_period = None;
+    }
+
+    fn set_groove(&mut self, groove: &Groove) {
+        self.graph_groove = *groove;
+        self.apply_swing();
     }
 
     fn as_any_mut(&mut self) -> &mut dyn Any {
@@ -457,3 +784,83 @@ impl AudioNode for ArpeggiatorGenerator {
         "arpeggiator_generator"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn euclidean_patterns_keep_values_and_ratchets() {
+        let on = |rhythm: Vec<bool>| rhythm.iter().map(|&a| a as u8).collect::<Vec<_>>();
+        assert_eq!(on(euclidean_rhythm(8, 3, 0)), vec![1, 0, 0, 1, 0, 0, 1, 0]);
+        assert_eq!(on(euclidean_rhythm(8, 3, 1)), vec![0, 0, 1, 0, 0, 1, 0, 1]);
+        assert_eq!(on(euclidean_rhythm(4, 9, 0)), vec![1, 1, 1, 1]);
+
+        let mut arp = ArpeggiatorGenerator::new();
+        arp.set_pattern(vec![
+            PatternStep {
+                value: 0.0,
+                ..Default::default()
+            },
+            PatternStep {
+                value: 700.0,
+                ..Default::default()
+            },
+        ]);
+        assert!(arp.set_step_ratchets(1, 3));
+        assert!(!arp.set_step_ratchets(2, 3));
+        arp.set_euclidean(4, 2, 0);
+        let pattern = arp.pattern();
+        assert_eq!(pattern.len(), 4);
+        assert_eq!(
+            pattern.iter().map(|step| step.active).collect::<Vec<_>>(),
+            vec![true, false, true, false]
+        );
+        assert_eq!(pattern[3].value, 700.0);
+        assert_eq!(pattern[3].ratchets, 3);
+    }
+
+    #[test]
+    fn builds_arpeggios_from_held_notes() {
+        use ArpeggiatorDirection::*;
+        assert_eq!(
+            arpeggio_steps(&[64, 60, 67], Up, 1),
+            vec![0.0, 400.0, 700.0]
+        );
+        assert_eq!(
+            arpeggio_steps(&[64, 60], Down, 2),
+            vec![1600.0, 1200.0, 400.0, 0.0]
+        );
+        assert_eq!(
+            arpeggio_steps(&[60, 64, 67], UpDown, 1),
+            vec![0.0, 400.0, 700.0, 400.0]
+        );
+        assert_eq!(arpeggio_steps(&[64, 60], AsPlayed, 1), vec![400.0, 0.0]);
+        assert!(arpeggio_steps(&[], Random, 3).is_empty());
+
+        let mut config = ArpeggiatorConfig {
+            latch: true,
+            held_notes: vec![60, 64],
+            ..Default::default()
+        };
+        let mut arp = ArpeggiatorGenerator::with_config(48_000.0, config.clone());
+        // 120 BPM sixteenths.
+        assert_eq!(arp.step_samples, 6_000);
+        config.held_notes = vec![60, 64, 67];
+        arp.update_config(config.clone());
+        config.held_notes.clear();
+        arp.update_config(config.clone());
+        assert_eq!(arp.playing_notes(), &[60, 64, 67]);
+        assert_eq!(arp.pattern().len(), 3);
+        // A new chord after letting go replaces the latched one.
+        config.held_notes = vec![62];
+        arp.update_config(config.clone());
+        assert_eq!(arp.playing_notes(), &[62]);
+
+        config.latch = false;
+        config.held_notes.clear();
+        arp.update_config(config);
+        assert!(arp.playing_notes().is_empty());
+        assert_eq!(arp.modulation_value(0), 0.0);
+    }
+}
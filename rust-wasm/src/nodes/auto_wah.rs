@@ -0,0 +1,344 @@
+use std::any::Any;
+use std::f32::consts::PI;
+
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::graph::ModulationSource;
+use crate::traits::{AudioNode, PortId};
+use crate::utils::smoothing::{SmoothedParam, DEFAULT_SMOOTHING_MS};
+
+const ATTACK_MS: f32 = 4.0;
+const RELEASE_MS: f32 = 120.0;
+/// Envelope gain at full sensitivity; typical program material peaks well
+/// below full scale.
+const MAX_ENVELOPE_GAIN: f32 = 8.0;
+const MAX_RANGE_OCTAVES: f32 = 6.0;
+/// The filter coefficients are recomputed every this many samples.
+const CONTROL_INTERVAL: usize = 16;
+
+/// Which way the filter sweeps as the input gets louder.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AutoWahDirection {
+    #[default]
+    Up = 0,
+    Down = 1,
+}
+
+/// Envelope filter.
+///
+/// A peak follower on the (linked) stereo input sweeps a state-variable
+/// band-pass across `range` octaves above `frequency`: upwards as the input
+/// gets louder, or from the top of the range downwards with
+/// `AutoWahDirection::Down`.
+pub struct AutoWah {
+    enabled: bool,
+    sample_rate: f32,
+    direction: AutoWahDirection,
+    envelope: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    // SVF state per channel: (ic1eq, ic2eq).
+    state: [(f32, f32); 2],
+    g: f32,
+    k: f32,
+    sensitivity: SmoothedParam,
+    frequency: SmoothedParam,
+    range: SmoothedParam,
+    q: SmoothedParam,
+    mix: SmoothedParam,
+    // Offsets from the effect stack's LFOs; the frequency offset is in octaves.
+    frequency_offset: SmoothedParam,
+    mix_offset: SmoothedParam,
+}
+
+impl AutoWah {
+    /// Creates a new AutoWah node.
+    ///
+    /// * `sample_rate` - The sample rate in Hz.
+    /// * `sensitivity` - How strongly the input level opens the filter (0.0 to 1.0).
+    /// * `frequency` - Bottom of the sweep in Hz.
+    /// * `range` - Width of the sweep in octaves (0 to 6).
+    /// * `q` - Resonance of the band-pass (0.5 to 20).
+    /// * `mix` - The mix amount (0.0 = fully dry, 1.0 = fully wet).
+    pub fn new(
+        sample_rate: f32,
+        sensitivity: f32,
+        frequency: f32,
+        range: f32,
+        q: f32,
+        mix: f32,
+    ) -> Self {
+        let mut wah = Self {
+            enabled: true,
+            sample_rate,
+            direction: AutoWahDirection::Up,
+            envelope: 0.0,
+            attack_coeff: time_coefficient(sample_rate, ATTACK_MS),
+            release_coeff: time_coefficient(sample_rate, RELEASE_MS),
+            state: [(0.0, 0.0); 2],
+            g: 0.0,
+            k: 1.0,
+            sensitivity: SmoothedParam::new(0.0, sample_rate, DEFAULT_SMOOTHING_MS),
+            frequency: SmoothedParam::new(200.0, sample_rate, DEFAULT_SMOOTHING_MS),
+            range: SmoothedParam::new(0.0, sample_rate, DEFAULT_SMOOTHING_MS),
+            q: SmoothedParam::new(1.0, sample_rate, DEFAULT_SMOOTHING_MS),
+            mix: SmoothedParam::new(0.0, sample_rate, DEFAULT_SMOOTHING_MS),
+            frequency_offset: SmoothedParam::new(0.0, sample_rate, DEFAULT_SMOOTHING_MS),
+            mix_offset: SmoothedParam::new(0.0, sample_rate, DEFAULT_SMOOTHING_MS),
+        };
+        wah.set_sensitivity(sensitivity);
+        wah.set_frequency(frequency);
+        wah.set_range(range);
+        wah.set_q(q);
+        wah.set_mix(mix);
+        wah.sensitivity.set_immediate(wah.sensitivity.target());
+        wah.frequency.set_immediate(wah.frequency.target());
+        wah.range.set_immediate(wah.range.target());
+        wah.q.set_immediate(wah.q.target());
+        wah.mix.set_immediate(wah.mix.target());
+        wah
+    }
+
+    pub fn set_sensitivity(&mut self, sensitivity: f32) {
+        self.sensitivity.set_target(sensitivity.clamp(0.0, 1.0));
+    }
+
+    pub fn sensitivity(&self) -> f32 {
+        self.sensitivity.target()
+    }
+
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.frequency
+            .set_target(frequency.clamp(20.0, self.sample_rate * 0.45));
+    }
+
+    pub fn frequency(&self) -> f32 {
+        self.frequency.target()
+    }
+
+    pub fn set_range(&mut self, octaves: f32) {
+        self.range.set_target(octaves.clamp(0.0, MAX_RANGE_OCTAVES));
+    }
+
+    pub fn range(&self) -> f32 {
+        self.range.target()
+    }
+
+    pub fn set_q(&mut self, q: f32) {
+        self.q.set_target(q.clamp(0.5, 20.0));
+    }
+
+    pub fn q(&self) -> f32 {
+        self.q.target()
+    }
+
+    pub fn set_direction(&mut self, direction: AutoWahDirection) {
+        self.direction = direction;
+    }
+
+    pub fn direction(&self) -> AutoWahDirection {
+        self.direction
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix.set_target(mix.clamp(0.0, 1.0));
+    }
+
+    pub fn mix(&self) -> f32 {
+        self.mix.target()
+    }
+
+    /// Current centre frequency for an envelope of `envelope`.
+    fn cutoff(&self, envelope: f32) -> f32 {
+        let amount = (envelope * self.sensitivity.current() * MAX_ENVELOPE_GAIN).min(1.0);
+        let amount = match self.direction {
+            AutoWahDirection::Up => amount,
+            AutoWahDirection::Down => 1.0 - amount,
+        };
+        let octaves = self.range.current() * amount + self.frequency_offset.current();
+        let cutoff = self.frequency.current() * octaves.exp2();
+        cutoff.clamp(20.0, self.sample_rate * 0.45)
+    }
+
+    fn update_coefficients(&mut self) {
+        let cutoff = self.cutoff(self.envelope);
+        self.g = (PI * cutoff / self.sample_rate).tan();
+        self.k = 1.0 / self.q.current();
+    }
+
+    #[inline]
+    fn band_pass(&mut self, channel: usize, input: f32) -> f32 {
+        let (ic1eq, ic2eq) = self.state[channel];
+        let a1 = 1.0 / (1.0 + self.g * (self.g + self.k));
+        let v1 = a1 * (ic1eq + self.g * (input - ic2eq));
+        let v2 = ic2eq + self.g * v1;
+        self.state[channel] = (2.0 * v1 - ic1eq, 2.0 * v2 - ic2eq);
+        v1
+    }
+}
+
+fn time_coefficient(sample_rate: f32, time_ms: f32) -> f32 {
+    (-1.0 / (time_ms * 0.001 * sample_rate)).exp()
+}
+
+impl AudioNode for AutoWah {
+    fn get_ports(&self) -> FxHashMap<PortId, bool> {
+        let mut ports = FxHashMap::default();
+        ports.insert(PortId::AudioInput0, false); // Left (or mono) input
+        ports.insert(PortId::AudioInput1, false); // Optional right input
+        ports.insert(PortId::AudioOutput0, true); // Left output
+        ports.insert(PortId::AudioOutput1, true); // Right output
+        ports
+    }
+
+    fn process<'a>(
+        &mut self,
+        inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+        outputs: &mut FxHashMap<PortId, &mut [f32]>,
+        buffer_size: usize,
+    ) {
+        let left_in = inputs
+            .get(&PortId::AudioInput0)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer);
+        let right_in = inputs
+            .get(&PortId::AudioInput1)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer)
+            .or(left_in);
+
+        let outs = outputs.get_disjoint_mut([&PortId::AudioOutput0, &PortId::AudioOutput1]);
+        let [Some(out_left), Some(out_right)] = outs else {
+            panic!("Missing stereo output buffers");
+        };
+        let out_left: &mut [f32] = out_left;
+        let out_right: &mut [f32] = out_right;
+
+        for i in 0..buffer_size {
+            let l = left_in.and_then(|b| b.get(i)).copied().unwrap_or(0.0);
+            let r = right_in.and_then(|b| b.get(i)).copied().unwrap_or(0.0);
+
+            let level = l.abs().max(r.abs());
+            let coeff = if level > self.envelope {
+                self.attack_coeff
+            } else {
+                self.release_coeff
+            };
+            self.envelope = level + coeff * (self.envelope - level);
+
+            self.sensitivity.next_value();
+            self.frequency.next_value();
+            self.range.next_value();
+            self.q.next_value();
+            self.frequency_offset.next_value();
+            let mix = (self.mix.next_value() + self.mix_offset.next_value()).clamp(0.0, 1.0);
+            if i % CONTROL_INTERVAL == 0 {
+                self.update_coefficients();
+            }
+
+            let wet_l = self.band_pass(0, l);
+            let wet_r = self.band_pass(1, r);
+            out_left[i] = l + (wet_l - l) * mix;
+            out_right[i] = r + (wet_r - r) * mix;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.envelope = 0.0;
+        self.state = [(0.0, 0.0); 2];
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn is_active(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.sensitivity.set_time_ms(time_ms);
+        self.frequency.set_time_ms(time_ms);
+        self.range.set_time_ms(time_ms);
+        self.q.set_time_ms(time_ms);
+        self.mix.set_time_ms(time_ms);
+    }
+
+    fn modulate_parameter(&mut self, parameter: &str, offset: f32) -> bool {
+        let target = match parameter {
+            "frequency" => &mut self.frequency_offset,
+            "mix" => &mut self.mix_offset,
+            _ => return false,
+        };
+        target.set_target(offset);
+        true
+    }
+
+    fn set_active(&mut self, active: bool) {
+        self.enabled = active;
+    }
+
+    fn name(&self) -> &'static str {
+        "AutoWah"
+    }
+
+    fn node_type(&self) -> &str {
+        "auto_wah"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn louder_input_opens_the_filter_and_down_reverses_it() {
+        let mut wah = AutoWah::new(48_000.0, 1.0, 200.0, 4.0, 2.0, 1.0);
+        assert!((wah.cutoff(0.0) - 200.0).abs() < 1e-3);
+        assert!((wah.cutoff(1.0) - 3_200.0).abs() < 1e-1);
+
+        wah.set_direction(AutoWahDirection::Down);
+        assert!((wah.cutoff(0.0) - 3_200.0).abs() < 1e-1);
+        assert!((wah.cutoff(1.0) - 200.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn follower_tracks_the_input_and_output_stays_bounded() {
+        let mut wah = AutoWah::new(48_000.0, 0.5, 300.0, 3.0, 4.0, 1.0);
+        let input: Vec<f32> = (0..4_800)
+            .map(|n| 0.5 * (2.0 * PI * 700.0 * n as f32 / 48_000.0).sin())
+            .collect();
+        let mut left = vec![0.0; input.len()];
+        let mut right = vec![0.0; input.len()];
+        {
+            let mut inputs = FxHashMap::default();
+            inputs.insert(
+                PortId::AudioInput0,
+                vec![ModulationSource {
+                    buffer: &input,
+                    amount: 1.0,
+                    mod_type: crate::graph::ModulationType::Additive,
+                    transformation: crate::graph::ModulationTransformation::None,
+                }],
+            );
+            let mut outputs = FxHashMap::default();
+            outputs.insert(PortId::AudioOutput0, left.as_mut_slice());
+            outputs.insert(PortId::AudioOutput1, right.as_mut_slice());
+            wah.process(&inputs, &mut outputs, input.len());
+        }
+
+        assert!((wah.envelope - 0.5).abs() < 0.05);
+        assert!(left.iter().all(|s| s.is_finite() && s.abs() < 4.0));
+        assert_eq!(left, right);
+    }
+}
@@ -0,0 +1,465 @@
+use std::any::Any;
+use std::f32::consts::PI;
+
+use rustc_hash::FxHashMap;
+
+use crate::graph::ModulationSource;
+use crate::traits::{AudioNode, PortId};
+use crate::utils::smoothing::{SmoothedParam, DEFAULT_SMOOTHING_MS};
+
+// Spherical head model (Brown & Duda, 1998).
+const HEAD_RADIUS_M: f32 = 0.0875;
+const SPEED_OF_SOUND: f32 = 343.0;
+const SHADOW_ALPHA_MIN: f32 = 0.1;
+const SHADOW_THETA_MIN_DEG: f32 = 150.0;
+// Pinna echoes: reflection coefficient, A, B (samples at 44.1 kHz) and D.
+const PINNA_ECHOES: [(f32, f32, f32, f32); 5] = [
+    (0.5, 1.0, 2.0, 1.0),
+    (-1.0, 5.0, 4.0, 0.5),
+    (0.5, 5.0, 7.0, 0.5),
+    (-0.25, 5.0, 11.0, 0.5),
+    (0.25, 5.0, 13.0, 0.5),
+];
+
+// Built-in grid: every 30 degrees of azimuth, elevations -30 to 90 degrees.
+const AZIMUTH_STEP_DEG: f32 = 30.0;
+const AZIMUTH_POINTS: usize = 12;
+const ELEVATION_MIN_DEG: f32 = -30.0;
+const ELEVATION_STEP_DEG: f32 = 30.0;
+const ELEVATION_POINTS: usize = 5;
+// Long enough for the largest ITD, the pinna echoes and the head-shadow tail.
+const HRIR_SECONDS: f32 = 0.002;
+const CROSSFADE_MS: f32 = 10.0;
+
+/// Head-related impulse responses for both ears on a fixed direction grid,
+/// generated from a spherical head with pinna echoes.
+struct HrtfSet {
+    len: usize,
+    /// `[elevation][azimuth][ear]`, ear 0 = left.
+    responses: Vec<[Vec<f32>; 2]>,
+}
+
+impl HrtfSet {
+    fn new(sample_rate: f32) -> Self {
+        let len = ((HRIR_SECONDS * sample_rate).ceil() as usize).max(8);
+        let mut responses = Vec::with_capacity(AZIMUTH_POINTS * ELEVATION_POINTS);
+        for el in 0..ELEVATION_POINTS {
+            let elevation = ELEVATION_MIN_DEG + el as f32 * ELEVATION_STEP_DEG;
+            for az in 0..AZIMUTH_POINTS {
+                let azimuth = az as f32 * AZIMUTH_STEP_DEG;
+                responses.push([
+                    Self::response(sample_rate, len, -azimuth, elevation),
+                    Self::response(sample_rate, len, azimuth, elevation),
+                ]);
+            }
+        }
+        Self { len, responses }
+    }
+
+    /// Right-ear response for a source at `azimuth` (clockwise from the front)
+    /// and `elevation`; the left ear uses the mirrored azimuth.
+    fn response(sample_rate: f32, len: usize, azimuth: f32, elevation: f32) -> Vec<f32> {
+        let (az, el) = (azimuth.to_radians(), elevation.to_radians());
+        // Angle between the source and the ear axis.
+        let incidence = (el.cos() * az.sin()).clamp(-1.0, 1.0).acos();
+        let head_time = HEAD_RADIUS_M / SPEED_OF_SOUND;
+        let delay_seconds = if incidence < PI / 2.0 {
+            head_time * (1.0 - incidence.cos())
+        } else {
+            head_time * (1.0 + incidence - PI / 2.0)
+        };
+        let delay = delay_seconds * sample_rate;
+
+        let mut excitation = vec![0.0; len];
+        let mut add_impulse = |position: f32, gain: f32| {
+            let index = position.floor() as usize;
+            let frac = position - index as f32;
+            if let Some(sample) = excitation.get_mut(index) {
+                *sample += gain * (1.0 - frac);
+            }
+            if let Some(sample) = excitation.get_mut(index + 1) {
+                *sample += gain * frac;
+            }
+        };
+        add_impulse(delay, 1.0);
+        let wrapped_az = (azimuth + 180.0).rem_euclid(360.0) - 180.0;
+        for (rho, a, b, d) in PINNA_ECHOES {
+            let echo = a
+                * (wrapped_az.to_radians() / 2.0).cos()
+                * (d * (90.0 - elevation)).to_radians().sin()
+                + b;
+            add_impulse(delay + echo * sample_rate / 44_100.0, rho);
+        }
+
+        // Head shadow: one pole, one zero with incidence-dependent zero (bilinear).
+        let alpha = (1.0 + SHADOW_ALPHA_MIN / 2.0)
+            + (1.0 - SHADOW_ALPHA_MIN / 2.0)
+                * (incidence.to_degrees() / SHADOW_THETA_MIN_DEG * PI).cos();
+        let beta = 2.0 * SPEED_OF_SOUND / HEAD_RADIUS_M;
+        let k = 2.0 * sample_rate;
+        let b0 = (beta + alpha * k) / (beta + k);
+        let b1 = (beta - alpha * k) / (beta + k);
+        let a1 = (beta - k) / (beta + k);
+        let (mut x1, mut y1) = (0.0, 0.0);
+        excitation
+            .into_iter()
+            .map(|x| {
+                let y = b0 * x + b1 * x1 - a1 * y1;
+                x1 = x;
+                y1 = y;
+                y
+            })
+            .collect()
+    }
+
+    /// Bilinear interpolation between the four surrounding grid responses,
+    /// written time-reversed into `out` for the convolution.
+    fn interpolate(&self, azimuth: f32, elevation: f32, out: &mut [Vec<f32>; 2]) {
+        let az = azimuth.rem_euclid(360.0) / AZIMUTH_STEP_DEG;
+        let az0 = (az.floor() as usize) % AZIMUTH_POINTS;
+        let az1 = (az0 + 1) % AZIMUTH_POINTS;
+        let az_frac = az - az.floor();
+        let el = ((elevation - ELEVATION_MIN_DEG) / ELEVATION_STEP_DEG)
+            .clamp(0.0, (ELEVATION_POINTS - 1) as f32);
+        let el0 = (el.floor() as usize).min(ELEVATION_POINTS - 2);
+        let el_frac = el - el0 as f32;
+
+        let corners = [
+            (el0, az0, (1.0 - el_frac) * (1.0 - az_frac)),
+            (el0, az1, (1.0 - el_frac) * az_frac),
+            (el0 + 1, az0, el_frac * (1.0 - az_frac)),
+            (el0 + 1, az1, el_frac * az_frac),
+        ];
+        for (ear, taps) in out.iter_mut().enumerate() {
+            taps.clear();
+            taps.resize(self.len, 0.0);
+            for &(el, az, weight) in &corners {
+                let response = &self.responses[el * AZIMUTH_POINTS + az][ear];
+                for (tap, &value) in taps.iter_mut().rev().zip(response) {
+                    *tap += value * weight;
+                }
+            }
+        }
+    }
+}
+
+/// Binaural panner for headphones.
+///
+/// The (mono-summed) input is convolved with a head-related impulse response
+/// pair interpolated from a small built-in HRTF grid, so the source appears at
+/// `azimuth` (degrees clockwise from the front) and `elevation` (-30 to 90
+/// degrees). A position change builds a new filter pair and crossfades to it
+/// over a few milliseconds instead of switching taps mid-signal.
+pub struct Binaural {
+    enabled: bool,
+    hrtf: HrtfSet,
+    azimuth: f32,
+    elevation: f32,
+    /// Two filter slots of time-reversed taps, `[slot][ear]`.
+    filters: [[Vec<f32>; 2]; 2],
+    filter_positions: [(f32, f32); 2],
+    current: usize,
+    fade_remaining: usize,
+    fade_len: usize,
+    /// Input history, written twice so the last `len` samples are contiguous.
+    history: Vec<f32>,
+    write_index: usize,
+    mix: SmoothedParam,
+}
+
+impl Binaural {
+    /// Creates a new Binaural node.
+    ///
+    /// * `sample_rate` - The sample rate in Hz.
+    /// * `azimuth` - Source direction in degrees, clockwise from the front.
+    /// * `elevation` - Source elevation in degrees (-30 to 90).
+    /// * `mix` - The mix amount (0.0 = fully dry, 1.0 = fully wet).
+    pub fn new(sample_rate: f32, azimuth: f32, elevation: f32, mix: f32) -> Self {
+        let hrtf = HrtfSet::new(sample_rate);
+        let len = hrtf.len;
+        let mut binaural = Self {
+            enabled: true,
+            hrtf,
+            azimuth: 0.0,
+            elevation: 0.0,
+            filters: Default::default(),
+            filter_positions: [(0.0, 0.0); 2],
+            current: 0,
+            fade_remaining: 0,
+            fade_len: ((CROSSFADE_MS / 1000.0) * sample_rate).max(1.0) as usize,
+            history: vec![0.0; 2 * len],
+            write_index: 0,
+            mix: SmoothedParam::new(mix.clamp(0.0, 1.0), sample_rate, DEFAULT_SMOOTHING_MS),
+        };
+        binaural.set_position(azimuth, elevation);
+        let position = (binaural.azimuth, binaural.elevation);
+        binaural
+            .hrtf
+            .interpolate(position.0, position.1, &mut binaural.filters[0]);
+        binaural.filter_positions[0] = position;
+        binaural
+    }
+
+    /// Sets the source direction; the filters crossfade to it.
+    pub fn set_position(&mut self, azimuth: f32, elevation: f32) {
+        self.azimuth = azimuth.rem_euclid(360.0);
+        self.elevation = elevation.clamp(
+            ELEVATION_MIN_DEG,
+            ELEVATION_MIN_DEG + (ELEVATION_POINTS - 1) as f32 * ELEVATION_STEP_DEG,
+        );
+    }
+
+    pub fn set_azimuth(&mut self, azimuth: f32) {
+        self.set_position(azimuth, self.elevation);
+    }
+
+    pub fn set_elevation(&mut self, elevation: f32) {
+        self.set_position(self.azimuth, elevation);
+    }
+
+    pub fn azimuth(&self) -> f32 {
+        self.azimuth
+    }
+
+    pub fn elevation(&self) -> f32 {
+        self.elevation
+    }
+
+    /// Sets the mix amount (0.0 = fully dry, 1.0 = fully wet).
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix.set_target(mix.clamp(0.0, 1.0));
+    }
+
+    pub fn mix(&self) -> f32 {
+        self.mix.target()
+    }
+
+    /// Starts a crossfade to the requested position once the previous one has
+    /// finished.
+    fn update_filters(&mut self) {
+        let target = (self.azimuth, self.elevation);
+        if self.fade_remaining > 0 || self.filter_positions[self.current] == target {
+            return;
+        }
+        let next = 1 - self.current;
+        self.hrtf
+            .interpolate(target.0, target.1, &mut self.filters[next]);
+        self.filter_positions[next] = target;
+        self.current = next;
+        self.fade_remaining = self.fade_len;
+    }
+
+    #[inline]
+    fn convolve(taps: &[f32], history: &[f32]) -> f32 {
+        taps.iter()
+            .zip(history)
+            .map(|(tap, sample)| tap * sample)
+            .sum()
+    }
+}
+
+impl AudioNode for Binaural {
+    fn get_ports(&self) -> FxHashMap<PortId, bool> {
+        let mut ports = FxHashMap::default();
+        ports.insert(PortId::AudioInput0, false); // Left (or mono) input
+        ports.insert(PortId::AudioInput1, false); // Optional right input
+        ports.insert(PortId::AudioOutput0, true); // Left output
+        ports.insert(PortId::AudioOutput1, true); // Right output
+        ports
+    }
+
+    fn process<'a>(
+        &mut self,
+        inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+        outputs: &mut FxHashMap<PortId, &mut [f32]>,
+        buffer_size: usize,
+    ) {
+        let left_in = inputs
+            .get(&PortId::AudioInput0)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer);
+        // A mono source feeds both channels so per-voice use works without a stereo pair.
+        let right_in = inputs
+            .get(&PortId::AudioInput1)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer)
+            .or(left_in);
+
+        let outs = outputs.get_disjoint_mut([&PortId::AudioOutput0, &PortId::AudioOutput1]);
+        let [Some(out_left), Some(out_right)] = outs else {
+            panic!("Missing stereo output buffers");
+        };
+        let out_left: &mut [f32] = out_left;
+        let out_right: &mut [f32] = out_right;
+
+        if !self.enabled {
+            // Bypassed: the input passes through untouched.
+            for (output, input) in [(&mut *out_left, left_in), (&mut *out_right, right_in)] {
+                match input {
+                    Some(input) => output[..buffer_size].copy_from_slice(&input[..buffer_size]),
+                    None => output[..buffer_size].fill(0.0),
+                }
+            }
+            return;
+        }
+
+        self.update_filters();
+        let len = self.hrtf.len;
+        let previous = 1 - self.current;
+
+        for i in 0..buffer_size {
+            let l = left_in.and_then(|b| b.get(i)).copied().unwrap_or(0.0);
+            let r = right_in.and_then(|b| b.get(i)).copied().unwrap_or(0.0);
+
+            let mono = 0.5 * (l + r);
+            self.history[self.write_index] = mono;
+            self.history[self.write_index + len] = mono;
+            self.write_index = (self.write_index + 1) % len;
+            let window = &self.history[self.write_index..self.write_index + len];
+
+            let [taps_left, taps_right] = &self.filters[self.current];
+            let mut wet_l = Self::convolve(taps_left, window);
+            let mut wet_r = Self::convolve(taps_right, window);
+            if self.fade_remaining > 0 {
+                let fade_in = 1.0 - self.fade_remaining as f32 / self.fade_len as f32;
+                let [old_left, old_right] = &self.filters[previous];
+                wet_l = wet_l * fade_in + Self::convolve(old_left, window) * (1.0 - fade_in);
+                wet_r = wet_r * fade_in + Self::convolve(old_right, window) * (1.0 - fade_in);
+                self.fade_remaining -= 1;
+            }
+
+            let wet_level = self.mix.next_value();
+            let dry_level = 1.0 - wet_level;
+            out_left[i] = l * dry_level + wet_l * wet_level;
+            out_right[i] = r * dry_level + wet_r * wet_level;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.history.fill(0.0);
+        self.write_index = 0;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn is_active(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.mix.set_time_ms(time_ms);
+    }
+
+    fn set_active(&mut self, active: bool) {
+        self.enabled = active;
+        if active {
+            self.reset();
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Binaural"
+    }
+
+    fn node_type(&self) -> &str {
+        "binaural"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{ModulationTransformation, ModulationType};
+
+    fn run(binaural: &mut Binaural, input: &[f32]) -> (Vec<f32>, Vec<f32>) {
+        let mut inputs = FxHashMap::default();
+        inputs.insert(
+            PortId::AudioInput0,
+            vec![ModulationSource {
+                buffer: input,
+                amount: 1.0,
+                mod_type: ModulationType::Additive,
+                transformation: ModulationTransformation::None,
+            }],
+        );
+        let mut left = vec![0.0; input.len()];
+        let mut right = vec![0.0; input.len()];
+        {
+            let mut outputs = FxHashMap::default();
+            outputs.insert(PortId::AudioOutput0, left.as_mut_slice());
+            outputs.insert(PortId::AudioOutput1, right.as_mut_slice());
+            binaural.process(&inputs, &mut outputs, input.len());
+        }
+        (left, right)
+    }
+
+    fn energy(samples: &[f32]) -> f32 {
+        samples.iter().map(|s| s * s).sum()
+    }
+
+    fn first_arrival(samples: &[f32]) -> usize {
+        samples
+            .iter()
+            .position(|s| s.abs() > 1e-3)
+            .unwrap_or(samples.len())
+    }
+
+    #[test]
+    fn source_on_the_right_is_louder_and_earlier_in_the_right_ear() {
+        let mut binaural = Binaural::new(48_000.0, 90.0, 0.0, 1.0);
+        let mut impulse = vec![0.0; 256];
+        impulse[0] = 1.0;
+
+        let (left, right) = run(&mut binaural, &impulse);
+        assert!(energy(&right) > 2.0 * energy(&left));
+        assert!(first_arrival(&right) + 10 < first_arrival(&left));
+
+        // A frontal source reaches both ears equally.
+        let mut front = Binaural::new(48_000.0, 0.0, 0.0, 1.0);
+        let (left, right) = run(&mut front, &impulse);
+        for (l, r) in left.iter().zip(&right) {
+            assert!((l - r).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn position_changes_crossfade_without_jumps() {
+        let sample_rate = 48_000.0;
+        let mut binaural = Binaural::new(sample_rate, 0.0, 0.0, 1.0);
+        // One continuous sine across both blocks, so only the move can cause a jump.
+        let block = |start: usize| -> Vec<f32> {
+            (start..start + 512)
+                .map(|n| (n as f32 * 0.02).sin() * 0.5)
+                .collect()
+        };
+        let (left, _) = run(&mut binaural, &block(0));
+
+        binaural.set_position(270.0, 30.0);
+        let (faded, _) = run(&mut binaural, &block(512));
+        // The first sample after the change continues from the old filter.
+        assert!((faded[0] - left[511]).abs() < 0.05);
+        let max_step = faded
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).abs())
+            .fold(0.0_f32, f32::max);
+        assert!(max_step < 0.1, "step {}", max_step);
+        assert_eq!(binaural.azimuth(), 270.0);
+        assert_eq!(binaural.elevation(), 30.0);
+    }
+
+    #[test]
+    fn disabled_panner_passes_the_input_through() {
+        let mut binaural = Binaural::new(48_000.0, 90.0, 0.0, 1.0);
+        binaural.set_active(false);
+        let input: Vec<f32> = (0..256).map(|n| (n as f32 * 0.05).sin()).collect();
+        let (left, right) = run(&mut binaural, &input);
+        assert_eq!(left, input);
+        assert_eq!(right, input);
+    }
+}
@@ -5,6 +5,7 @@ use rustc_hash::FxHashMap;
 use crate::{
     graph::ModulationSource,
     traits::{AudioNode, PortId},
+    utils::smoothing::{SmoothedParam, DEFAULT_SMOOTHING_MS},
 };
 
 /// Simple stereo bitcrusher with sample-rate reduction.
@@ -12,26 +13,25 @@ pub struct Bitcrusher {
     enabled: bool,
     bits: u8,
     downsample_factor: usize,
-    mix: f32,
+    mix: SmoothedParam,
     held_left: f32,
     held_right: f32,
     sample_hold_phase: usize,
 }
 
 impl Bitcrusher {
-    pub fn new(bits: u8, downsample_factor: usize, mix: f32) -> Self {
+    pub fn new(sample_rate: f32, bits: u8, downsample_factor: usize, mix: f32) -> Self {
         let mut crusher = Self {
             enabled: true,
             bits: 0,
             downsample_factor: 1,
-            mix: 0.5,
+            mix: SmoothedParam::new(mix.clamp(0.0, 1.0), sample_rate, DEFAULT_SMOOTHING_MS),
             held_left: 0.0,
             held_right: 0.0,
             sample_hold_phase: 0,
         };
         crusher.set_bits(bits);
         crusher.set_downsample_factor(downsample_factor);
-        crusher.set_mix(mix);
         crusher
     }
 
@@ -46,7 +46,19 @@ impl Bitcrusher {
     }
 
     pub fn set_mix(&mut self, mix: f32) {
-        self.mix = mix.clamp(0.0, 1.0);
+        self.mix.set_target(mix.clamp(0.0, 1.0));
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    pub fn downsample_factor(&self) -> usize {
+        self.downsample_factor
+    }
+
+    pub fn mix(&self) -> f32 {
+        self.mix.target()
     }
 
     fn quantize(sample: f32, step: f32) -> f32 {
@@ -73,8 +85,16 @@ impl AudioNode for Bitcrusher {
         outputs: &mut FxHashMap<PortId, &mut [f32]>,
         buffer_size: usize,
     ) {
-        let left_in = inputs.get(&PortId::AudioInput0).unwrap()[0].buffer;
-        let right_in = inputs.get(&PortId::AudioInput1).unwrap()[0].buffer;
+        let left_in = inputs
+            .get(&PortId::AudioInput0)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer);
+        // A mono source feeds both channels so per-voice use works without a stereo pair.
+        let right_in = inputs
+            .get(&PortId::AudioInput1)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer)
+            .or(left_in);
 
         let outs = outputs.get_disjoint_mut([&PortId::AudioOutput0, &PortId::AudioOutput1]);
         let [Some(out_left), Some(out_right)] = outs else {
@@ -87,8 +107,6 @@ impl AudioNode for Bitcrusher {
         let levels = 1u32.checked_shl(bits as u32).unwrap_or(0).max(2) as f32;
         let step = 2.0 / (levels - 1.0);
         let factor = self.downsample_factor.max(1);
-        let dry_gain = 1.0 - self.mix;
-        let wet_gain = self.mix;
 
         let mut phase = if factor == 1 {
             0
@@ -97,13 +115,17 @@ impl AudioNode for Bitcrusher {
         };
 
         for i in 0..buffer_size {
+            let left = left_in.map_or(0.0, |buffer| buffer[i]);
+            let right = right_in.map_or(0.0, |buffer| buffer[i]);
             if phase == 0 {
-                self.held_left = Self::quantize(left_in[i], step);
-                self.held_right = Self::quantize(right_in[i], step);
+                self.held_left = Self::quantize(left, step);
+                self.held_right = Self::quantize(right, step);
             }
 
-            out_left[i] = left_in[i] * dry_gain + self.held_left * wet_gain;
-            out_right[i] = right_in[i] * dry_gain + self.held_right * wet_gain;
+            let wet_gain = self.mix.next_value();
+            let dry_gain = 1.0 - wet_gain;
+            out_left[i] = left * dry_gain + self.held_left * wet_gain;
+            out_right[i] = right * dry_gain + self.held_right * wet_gain;
 
             if factor > 1 {
                 phase += 1;
@@ -138,6 +160,10 @@ impl AudioNode for Bitcrusher {
         self.enabled
     }
 
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.mix.set_time_ms(time_ms);
+    }
+
     fn set_active(&mut self, active: bool) {
         self.enabled = active;
         if !active {
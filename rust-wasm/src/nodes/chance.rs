@@ -0,0 +1,321 @@
+use std::any::Any;
+
+use rustc_hash::FxHashMap;
+
+use crate::graph::ModulationSource;
+use crate::traits::{AudioNode, PortId};
+
+/// What a failed roll does to the signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChanceMode {
+    /// Passed rolls send the signal to output 1, failed rolls to output 2.
+    Route,
+    /// Failed rolls freeze the signal at its value when the gate opened.
+    Hold,
+    /// Failed rolls scale the signal by the attenuation amount.
+    Attenuate,
+}
+
+impl ChanceMode {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => ChanceMode::Hold,
+            2 => ChanceMode::Attenuate,
+            _ => ChanceMode::Route,
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            ChanceMode::Route => 0,
+            ChanceMode::Hold => 1,
+            ChanceMode::Attenuate => 2,
+        }
+    }
+}
+
+/// Whose dice a Chance node rolls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChanceRandomness {
+    /// Every voice rolls its own sequence.
+    PerVoice,
+    /// Every voice rolls the same sequence, so the n-th note of each voice has
+    /// the same outcome.
+    Global,
+}
+
+impl ChanceRandomness {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => ChanceRandomness::Global,
+            _ => ChanceRandomness::PerVoice,
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            ChanceRandomness::PerVoice => 0,
+            ChanceRandomness::Global => 1,
+        }
+    }
+}
+
+/// Probability-based modulation router.
+///
+/// Each rising edge of the gate rolls against `probability`. A passed roll lets
+/// the signal through to `AudioOutput0` until the next gate; a failed one routes
+/// it to `AudioOutput1`, holds it or attenuates it, depending on the mode. The
+/// roll sequence comes from the seed, so a patch plays back the same way after
+/// a reset. The gate input is connected to the GateMixer automatically.
+pub struct Chance {
+    enabled: bool,
+    probability: f32,
+    mode: ChanceMode,
+    attenuation: f32,
+    seed: u32,
+    randomness: ChanceRandomness,
+    voice_index: usize,
+
+    rng_state: u32,
+    gate_high: bool,
+    passed: bool,
+    held: f32,
+}
+
+impl Default for Chance {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Chance {
+    /// Creates a new Chance node that always passes its signal.
+    pub fn new() -> Self {
+        let mut chance = Self {
+            enabled: true,
+            probability: 1.0,
+            mode: ChanceMode::Route,
+            attenuation: 0.0,
+            seed: 1,
+            randomness: ChanceRandomness::PerVoice,
+            voice_index: 0,
+            rng_state: 0,
+            gate_high: false,
+            passed: true,
+            held: 0.0,
+        };
+        chance.reset();
+        chance
+    }
+
+    /// Chance (0.0 to 1.0) that a gate lets the signal through.
+    pub fn set_probability(&mut self, probability: f32) {
+        self.probability = probability.clamp(0.0, 1.0);
+    }
+
+    pub fn set_mode(&mut self, mode: ChanceMode) {
+        self.mode = mode;
+    }
+
+    /// Gain applied on failed rolls in `Attenuate` mode.
+    pub fn set_attenuation(&mut self, attenuation: f32) {
+        self.attenuation = attenuation.clamp(0.0, 1.0);
+    }
+
+    /// Restarts the roll sequence from `seed`.
+    pub fn set_seed(&mut self, seed: u32) {
+        if seed != self.seed {
+            self.seed = seed;
+            self.rng_state = self.initial_state();
+        }
+    }
+
+    pub fn set_randomness(&mut self, randomness: ChanceRandomness) {
+        if randomness != self.randomness {
+            self.randomness = randomness;
+            self.rng_state = self.initial_state();
+        }
+    }
+
+    /// Index of the voice this node runs in, which picks its sequence in
+    /// `PerVoice` mode.
+    pub fn set_voice_index(&mut self, voice_index: usize) {
+        if voice_index != self.voice_index {
+            self.voice_index = voice_index;
+            self.rng_state = self.initial_state();
+        }
+    }
+
+    fn initial_state(&self) -> u32 {
+        let stream = match self.randomness {
+            ChanceRandomness::PerVoice => self.voice_index as u32 + 1,
+            ChanceRandomness::Global => 0,
+        };
+        // Spread nearby seeds and voices apart; xorshift needs a non-zero state.
+        let state = (self.seed ^ stream.wrapping_mul(0x9E37_79B9)).wrapping_mul(0x85EB_CA6B);
+        state.max(1)
+    }
+
+    fn next_random(&mut self) -> f32 {
+        // xorshift32
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng_state = x;
+        (x >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Advances one sample and returns the (passed, failed) outputs.
+    fn tick(&mut self, input: f32, gate: f32) -> (f32, f32) {
+        let high = gate > 0.0;
+        if high && !self.gate_high {
+            self.passed = self.next_random() < self.probability;
+            self.held = input;
+        }
+        self.gate_high = high;
+
+        if self.passed {
+            return (input, 0.0);
+        }
+        match self.mode {
+            ChanceMode::Route => (0.0, input),
+            ChanceMode::Hold => (self.held, 0.0),
+            ChanceMode::Attenuate => (input * self.attenuation, 0.0),
+        }
+    }
+}
+
+fn input_at(sources: Option<&Vec<ModulationSource>>, i: usize) -> f32 {
+    sources.map_or(0.0, |sources| {
+        sources
+            .iter()
+            .map(|src| src.buffer.get(i).copied().unwrap_or(0.0) * src.amount)
+            .sum()
+    })
+}
+
+impl AudioNode for Chance {
+    fn get_ports(&self) -> FxHashMap<PortId, bool> {
+        let mut ports = FxHashMap::default();
+        ports.insert(PortId::AudioInput0, false); // Signal
+        ports.insert(PortId::CombinedGate, false); // Rolls on each rising edge
+        ports.insert(PortId::AudioOutput0, true); // Passed signal
+        ports.insert(PortId::AudioOutput1, true); // Failed signal (Route mode)
+        ports
+    }
+
+    fn process<'a>(
+        &mut self,
+        inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+        outputs: &mut FxHashMap<PortId, &mut [f32]>,
+        buffer_size: usize,
+    ) {
+        let signal = inputs.get(&PortId::AudioInput0);
+        let gate = inputs.get(&PortId::CombinedGate);
+        let [passed_out, failed_out] =
+            outputs.get_disjoint_mut([&PortId::AudioOutput0, &PortId::AudioOutput1]);
+        let (Some(passed_out), Some(failed_out)) = (passed_out, failed_out) else {
+            return;
+        };
+        for i in 0..buffer_size.min(passed_out.len()).min(failed_out.len()) {
+            let input = input_at(signal, i);
+            let (passed, failed) = if self.enabled {
+                self.tick(input, input_at(gate, i))
+            } else {
+                (input, 0.0)
+            };
+            passed_out[i] = passed;
+            failed_out[i] = failed;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.rng_state = self.initial_state();
+        self.gate_high = false;
+        self.passed = true;
+        self.held = 0.0;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn is_active(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_active(&mut self, active: bool) {
+        self.enabled = active;
+    }
+
+    // A bypassed Chance node still has to pass its signal through.
+    fn should_process(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "Chance"
+    }
+
+    fn node_type(&self) -> &str {
+        "chance"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rolls on each of `gates` two-sample gates and returns which passed.
+    fn rolls(chance: &mut Chance, gates: usize) -> Vec<bool> {
+        (0..gates)
+            .map(|_| {
+                let (passed, _) = chance.tick(1.0, 1.0);
+                chance.tick(1.0, 0.0);
+                passed > 0.0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn failed_rolls_route_hold_or_attenuate() {
+        let mut chance = Chance::new();
+        chance.set_probability(0.0);
+        // Everything passes until the first gate.
+        assert_eq!(chance.tick(0.3, 0.0), (0.3, 0.0));
+        assert_eq!(chance.tick(0.4, 1.0), (0.0, 0.4));
+
+        chance.set_mode(ChanceMode::Hold);
+        assert_eq!(chance.tick(0.9, 1.0), (0.4, 0.0));
+
+        chance.set_mode(ChanceMode::Attenuate);
+        chance.set_attenuation(0.5);
+        assert_eq!(chance.tick(0.8, 1.0), (0.4, 0.0));
+    }
+
+    #[test]
+    fn seeds_repeat_and_voices_differ() {
+        let run = |seed: u32, voice: usize, randomness: ChanceRandomness| {
+            let mut chance = Chance::new();
+            chance.set_probability(0.5);
+            chance.set_seed(seed);
+            chance.set_randomness(randomness);
+            chance.set_voice_index(voice);
+            rolls(&mut chance, 64)
+        };
+        let first = run(7, 0, ChanceRandomness::PerVoice);
+        assert_eq!(first, run(7, 0, ChanceRandomness::PerVoice));
+        assert!(first.contains(&true) && first.contains(&false));
+        assert_ne!(first, run(7, 1, ChanceRandomness::PerVoice));
+        assert_ne!(first, run(8, 0, ChanceRandomness::PerVoice));
+        assert_eq!(
+            run(7, 0, ChanceRandomness::Global),
+            run(7, 3, ChanceRandomness::Global)
+        );
+    }
+}
@@ -13,7 +13,8 @@ const MIN_MIX: f32 = 0.0;
 const MAX_MIX: f32 = 1.0;
 
 use crate::graph::ModulationSource; // Assuming these paths are correct for your project
-use crate::traits::{AudioNode, PortId};
+use crate::traits::{AudioNode, PortId, QualityMode};
+use crate::utils::smoothing::smoothing_coefficient;
 
 const SIMD_WIDTH: usize = 4; // web wasm guaranteed supported
 
@@ -227,12 +228,18 @@ fn smooth_parameter(current: f32, target: f32, coefficient: f32) -> f32 {
     current * coefficient + target * (1.0 - coefficient)
 }
 
-const OVERSAMPLE: usize = 4;
+const DEFAULT_OVERSAMPLE: usize = 4;
 const INTERPOLATION_MARGIN: usize = 3;
+const FIR_TAPS: usize = 31;
 const DC_BLOCKER_CUTOFF_HZ: f32 = 10.0;
 
 pub struct Chorus {
     enabled: bool,
+    sample_rate: f32,
+    max_base_delay_ms: f32,
+    oversample: usize,
+    linear_interpolation: bool,
+    smoothing_time_ms: f32,
     internal_sample_rate: f32,
     inv_internal_sample_rate: f32,
     delay_buffer_left: Vec<f32>,
@@ -289,7 +296,7 @@ impl Chorus {
     }
 
     fn ensure_buffer_capacity(&mut self, buffer_size: usize) {
-        let oversampled = buffer_size * OVERSAMPLE;
+        let oversampled = buffer_size * self.oversample;
 
         Self::ensure_len(&mut self.upsample_stage1_left, oversampled);
         Self::ensure_len(&mut self.upsample_stage1_right, oversampled);
@@ -303,6 +310,65 @@ impl Chorus {
         Self::ensure_len(&mut self.scratch_final_right, buffer_size);
     }
 
+    /// Anti-imaging/anti-aliasing FIR coefficients (up, down) for an oversampling
+    /// factor. Each of the two cascaded upsampling stages makes up half of the
+    /// zero-stuffing loss.
+    fn oversampling_coeffs(oversample: usize) -> (Vec<f32>, Vec<f32>) {
+        let normalized_cutoff = 0.5 / oversample as f32;
+        let filter_cutoff = normalized_cutoff * 0.90;
+        let base_coeffs = generate_fir_coeffs(FIR_TAPS, filter_cutoff, blackman_window);
+        let stage_gain = (oversample as f32).sqrt();
+        let up_coeffs = base_coeffs.iter().map(|c| c * stage_gain).collect();
+        (up_coeffs, base_coeffs)
+    }
+
+    fn max_delay_samples_for(max_base_delay_ms: f32, internal_sample_rate: f32) -> usize {
+        let max_modulated_delay_ms = max_base_delay_ms + 20.0;
+        let required_samples_for_delay =
+            (max_modulated_delay_ms / 1000.0 * internal_sample_rate).ceil() as usize;
+        required_samples_for_delay + INTERPOLATION_MARGIN
+    }
+
+    /// Switches the internal oversampling factor (1 disables oversampling).
+    /// Delay-line contents are discarded; parameters carry over.
+    pub fn set_oversample(&mut self, oversample: usize) {
+        let oversample = oversample.max(1);
+        if oversample == self.oversample {
+            return;
+        }
+        let ratio = oversample as f32 / self.oversample as f32;
+        self.oversample = oversample;
+        self.internal_sample_rate = self.sample_rate * oversample as f32;
+        self.inv_internal_sample_rate = 1.0 / self.internal_sample_rate;
+
+        self.max_delay_samples =
+            Self::max_delay_samples_for(self.max_base_delay_ms, self.internal_sample_rate);
+        self.max_safe_read_delay = (self.max_delay_samples - INTERPOLATION_MARGIN) as f32;
+        self.delay_buffer_left = vec![0.0; self.max_delay_samples];
+        self.delay_buffer_right = vec![0.0; self.max_delay_samples];
+
+        // Delay times are stored in internal samples.
+        self.target_base_delay_samples *= ratio;
+        self.target_depth_samples *= ratio;
+        self.param_smooth_coeff =
+            1.0 - smoothing_coefficient(self.internal_sample_rate, self.smoothing_time_ms);
+
+        let (up_coeffs, down_coeffs) = Self::oversampling_coeffs(oversample);
+        self.upsample_filter1_left = FirFilter::new(up_coeffs.clone());
+        self.upsample_filter1_right = FirFilter::new(up_coeffs.clone());
+        self.upsample_filter2_left = FirFilter::new(up_coeffs.clone());
+        self.upsample_filter2_right = FirFilter::new(up_coeffs);
+        self.downsample_filter1_left = FirFilter::new(down_coeffs.clone());
+        self.downsample_filter1_right = FirFilter::new(down_coeffs.clone());
+        self.downsample_filter2_left = FirFilter::new(down_coeffs.clone());
+        self.downsample_filter2_right = FirFilter::new(down_coeffs);
+        self.reset_state();
+    }
+
+    pub fn oversample(&self) -> usize {
+        self.oversample
+    }
+
     pub fn new(
         sample_rate: f32,
         max_base_delay_ms: f32,
@@ -314,13 +380,11 @@ impl Chorus {
         stereo_phase_offset_deg: f32,
     ) -> Self {
         assert!(sample_rate > 0.0, "Sample rate must be positive");
-        let internal_sample_rate = sample_rate * OVERSAMPLE as f32;
+        let internal_sample_rate = sample_rate * DEFAULT_OVERSAMPLE as f32;
         let inv_internal_sample_rate = 1.0 / internal_sample_rate;
 
-        let max_modulated_delay_ms = max_base_delay_ms + 20.0;
-        let required_samples_for_delay =
-            (max_modulated_delay_ms / 1000.0 * internal_sample_rate).ceil() as usize;
-        let max_delay_samples = required_samples_for_delay + INTERPOLATION_MARGIN;
+        let max_delay_samples =
+            Self::max_delay_samples_for(max_base_delay_ms, internal_sample_rate);
         let max_safe_read_delay = (max_delay_samples - INTERPOLATION_MARGIN).max(0) as f32;
 
         let initial_base_delay_samples = (base_delay_ms / 1000.0 * internal_sample_rate).max(0.0);
@@ -332,32 +396,22 @@ impl Chorus {
         let initial_lfo_stereo_phase_offset_rad = stereo_phase_offset_deg.to_radians();
 
         let smoothing_time_ms = 0.1;
-        let param_smooth_coeff = if internal_sample_rate > 0.0 {
-            let smoothing_samples = smoothing_time_ms * 0.001 * internal_sample_rate;
-            if smoothing_samples > 1.0 {
-                (-1.0 / smoothing_samples).exp()
-            } else {
-                0.0
-            }
-        } else {
-            0.0
-        };
+        // Stored as the retention factor (1 - step) used by `smooth_parameter`.
+        let param_smooth_coeff =
+            1.0 - smoothing_coefficient(internal_sample_rate, smoothing_time_ms);
 
-        let num_taps = 31;
-        let normalized_cutoff = 0.5 / OVERSAMPLE as f32;
-        let filter_cutoff = normalized_cutoff * 0.90;
-        let base_coeffs = generate_fir_coeffs(num_taps, filter_cutoff, blackman_window);
-        let mut up_coeffs = base_coeffs.clone();
-        for c in up_coeffs.iter_mut() {
-            *c *= 2.0;
-        }
-        let down_coeffs = base_coeffs;
+        let (up_coeffs, down_coeffs) = Self::oversampling_coeffs(DEFAULT_OVERSAMPLE);
 
         const INITIAL_CAPACITY: usize = 128;
-        let initial_oversampled = INITIAL_CAPACITY * OVERSAMPLE;
+        let initial_oversampled = INITIAL_CAPACITY * DEFAULT_OVERSAMPLE;
 
         Self {
             enabled: true,
+            sample_rate,
+            max_base_delay_ms,
+            oversample: DEFAULT_OVERSAMPLE,
+            linear_interpolation: false,
+            smoothing_time_ms,
             internal_sample_rate,
             inv_internal_sample_rate,
             delay_buffer_left: vec![0.0; max_delay_samples],
@@ -468,6 +522,22 @@ impl Chorus {
             + (-p0 + 3.0 * p1 - 3.0 * p2 + p3) * t3)
     }
 
+    /// Linear interpolation for delay sample estimation (cheaper, duller).
+    #[inline(always)]
+    fn read_linear_interpolated(
+        buffer: &[f32],
+        delay_samples: f32,
+        write_index: usize,
+        max_delay_samples: usize,
+    ) -> f32 {
+        let read_pos_float = (write_index as f32 - delay_samples + max_delay_samples as f32)
+            % max_delay_samples as f32;
+        let index_frac = read_pos_float.fract();
+        let i0 = read_pos_float.floor() as usize % max_delay_samples;
+        let i1 = (i0 + 1) % max_delay_samples;
+        buffer[i0] + (buffer[i1] - buffer[i0]) * index_frac
+    }
+
     #[inline(always)]
     fn calculate_filter_alpha(cutoff: f32, sample_rate: f32) -> f32 {
         // A typical relationship is: alpha = exp(-2π * cutoff / sample_rate)
@@ -572,8 +642,11 @@ impl Chorus {
             return;
         }
 
-        // Smooth parameters (as before)
-        let coeff = self.param_smooth_coeff;
+        // Smooth parameters once per block; the per-sample retention is raised to the
+        // number of oversampled samples in the block so the time constant holds.
+        let coeff = self
+            .param_smooth_coeff
+            .powi((process_len * self.oversample) as i32);
         self.current_base_delay_samples = smooth_parameter(
             self.current_base_delay_samples,
             self.target_base_delay_samples,
@@ -595,7 +668,7 @@ impl Chorus {
         self.current_feedback_filter_cutoff = smooth_parameter(
             self.current_feedback_filter_cutoff,
             self.target_feedback_filter_cutoff,
-            self.param_smooth_coeff,
+            coeff,
         );
         // Update the filter’s alpha for tone shaping.
         self.feedback_filter_l.alpha = Self::calculate_filter_alpha(
@@ -608,34 +681,37 @@ impl Chorus {
         );
 
         // UPSAMPLING (multipass FIR cascaded)
-        let internal_buffer_len = process_len * OVERSAMPLE;
-        {
-            let mut up_idx = 0;
-            let filter1_l = &mut self.upsample_filter1_left;
-            let filter1_r = &mut self.upsample_filter1_right;
-            for i in 0..process_len {
-                self.upsample_stage1_left[up_idx] = filter1_l.process(left_in_slice[i]);
-                self.upsample_stage1_right[up_idx] = filter1_r.process(right_in_slice[i]);
-                up_idx += 1;
-                self.upsample_stage1_left[up_idx] = filter1_l.process(0.0);
-                self.upsample_stage1_right[up_idx] = filter1_r.process(0.0);
-                up_idx += 1;
-                self.upsample_stage1_left[up_idx] = filter1_l.process(0.0);
-                self.upsample_stage1_right[up_idx] = filter1_r.process(0.0);
-                up_idx += 1;
-                self.upsample_stage1_left[up_idx] = filter1_l.process(0.0);
-                self.upsample_stage1_right[up_idx] = filter1_r.process(0.0);
-                up_idx += 1;
+        let oversample = self.oversample;
+        let internal_buffer_len = process_len * oversample;
+        if oversample == 1 {
+            self.upsampled_input_left[..process_len].copy_from_slice(&left_in_slice[..process_len]);
+            self.upsampled_input_right[..process_len]
+                .copy_from_slice(&right_in_slice[..process_len]);
+        } else {
+            {
+                let mut up_idx = 0;
+                let filter1_l = &mut self.upsample_filter1_left;
+                let filter1_r = &mut self.upsample_filter1_right;
+                for i in 0..process_len {
+                    self.upsample_stage1_left[up_idx] = filter1_l.process(left_in_slice[i]);
+                    self.upsample_stage1_right[up_idx] = filter1_r.process(right_in_slice[i]);
+                    up_idx += 1;
+                    for _ in 1..oversample {
+                        self.upsample_stage1_left[up_idx] = filter1_l.process(0.0);
+                        self.upsample_stage1_right[up_idx] = filter1_r.process(0.0);
+                        up_idx += 1;
+                    }
+                }
             }
-        }
 
-        for i in 0..internal_buffer_len {
-            self.upsampled_input_left[i] = self
-                .upsample_filter2_left
-                .process(self.upsample_stage1_left[i]);
-            self.upsampled_input_right[i] = self
-                .upsample_filter2_right
-                .process(self.upsample_stage1_right[i]);
+            for i in 0..internal_buffer_len {
+                self.upsampled_input_left[i] = self
+                    .upsample_filter2_left
+                    .process(self.upsample_stage1_left[i]);
+                self.upsampled_input_right[i] = self
+                    .upsample_filter2_right
+                    .process(self.upsample_stage1_right[i]);
+            }
         }
 
         // CORE CHORUS PROCESSING (in oversampled domain)
@@ -654,6 +730,11 @@ impl Chorus {
             let mut current_write_index = self.write_index;
             let delay_buf_l = &mut self.delay_buffer_left;
             let delay_buf_r = &mut self.delay_buffer_right;
+            let read_delayed = if self.linear_interpolation {
+                Self::read_linear_interpolated
+            } else {
+                Self::read_cubic_interpolated
+            };
             const MIN_DELAY_SAMPLES_CUBIC: f32 = 2.0;
             let min_target_delay = base_delay - depth;
             let effective_base_delay = if min_target_delay < MIN_DELAY_SAMPLES_CUBIC {
@@ -682,13 +763,13 @@ impl Chorus {
                 //     max_delay_samples,
                 // );
                 // Read the delayed values
-                let delayed_left = Self::read_cubic_interpolated(
+                let delayed_left = read_delayed(
                     delay_buf_l,
                     delay_smpls_left,
                     current_write_index,
                     max_delay_samples,
                 );
-                let delayed_right = Self::read_cubic_interpolated(
+                let delayed_right = read_delayed(
                     delay_buf_r,
                     delay_smpls_right,
                     current_write_index,
@@ -751,47 +832,60 @@ impl Chorus {
             self.write_index = current_write_index;
         }
 
-        // DOWNSAMPLING (multipass FIR cascaded)
-        {
-            let downsample_stage = &mut self.scratch_downsample_stage[..internal_buffer_len];
-            let filter1_l = &mut self.downsample_filter1_left;
-            let filter1_r = &mut self.downsample_filter1_right;
-            for i in 0..internal_buffer_len {
-                downsample_stage[i] = filter1_l.process(self.processed_oversampled_left[i]);
-                self.processed_oversampled_right[i] =
-                    filter1_r.process(self.processed_oversampled_right[i]);
-            }
-        }
-        {
-            let downsampled = &mut self.scratch_downsampled[..internal_buffer_len];
-            let filter2_l = &mut self.downsample_filter2_left;
-            let filter2_r = &mut self.downsample_filter2_right;
-            for i in 0..internal_buffer_len {
-                downsampled[i] = filter2_l.process(self.scratch_downsample_stage[i]);
-                self.processed_oversampled_right[i] =
-                    filter2_r.process(self.processed_oversampled_right[i]);
-            }
-        }
-
         // Write final decimated results directly into the preallocated scratch buffers.
         let final_left = &mut self.scratch_final_left;
         let final_right = &mut self.scratch_final_right;
-        let mut out_idx = 0;
-        for i in (0..internal_buffer_len).step_by(OVERSAMPLE) {
-            if out_idx < process_len {
-                final_left[out_idx] = self.scratch_downsampled[i];
-                final_right[out_idx] = self.processed_oversampled_right[i];
-                out_idx += 1;
-            } else {
-                eprintln!("Chorus Error: Output buffer overrun during downsampling!");
-                break;
+        if oversample == 1 {
+            final_left[..process_len]
+                .copy_from_slice(&self.processed_oversampled_left[..process_len]);
+            final_right[..process_len]
+                .copy_from_slice(&self.processed_oversampled_right[..process_len]);
+        } else {
+            // DOWNSAMPLING (multipass FIR cascaded)
+            {
+                let downsample_stage = &mut self.scratch_downsample_stage[..internal_buffer_len];
+                let filter1_l = &mut self.downsample_filter1_left;
+                let filter1_r = &mut self.downsample_filter1_right;
+                for ((stage, &left), right) in downsample_stage
+                    .iter_mut()
+                    .zip(&self.processed_oversampled_left[..internal_buffer_len])
+                    .zip(&mut self.processed_oversampled_right[..internal_buffer_len])
+                {
+                    *stage = filter1_l.process(left);
+                    *right = filter1_r.process(*right);
+                }
+            }
+            {
+                let downsampled = &mut self.scratch_downsampled[..internal_buffer_len];
+                let filter2_l = &mut self.downsample_filter2_left;
+                let filter2_r = &mut self.downsample_filter2_right;
+                for ((left, &stage), right) in downsampled
+                    .iter_mut()
+                    .zip(&self.scratch_downsample_stage[..internal_buffer_len])
+                    .zip(&mut self.processed_oversampled_right[..internal_buffer_len])
+                {
+                    *left = filter2_l.process(stage);
+                    *right = filter2_r.process(*right);
+                }
+            }
+
+            let mut out_idx = 0;
+            for i in (0..internal_buffer_len).step_by(oversample) {
+                if out_idx < process_len {
+                    final_left[out_idx] = self.scratch_downsampled[i];
+                    final_right[out_idx] = self.processed_oversampled_right[i];
+                    out_idx += 1;
+                } else {
+                    eprintln!("Chorus Error: Output buffer overrun during downsampling!");
+                    break;
+                }
             }
+            assert_eq!(
+                out_idx, process_len,
+                "Downsampling mismatch: expected {} samples, got {}",
+                process_len, out_idx
+            );
         }
-        assert_eq!(
-            out_idx, process_len,
-            "Downsampling mismatch: expected {} samples, got {}",
-            process_len, out_idx
-        );
 
         // Apply DC blockers in place on the scratch final buffers.
         for i in 0..process_len {
@@ -864,6 +958,12 @@ impl Chorus {
     fn as_any_internal(&self) -> &dyn Any {
         self
     }
+
+    /// Bytes held by the delay lines.
+    pub fn memory_bytes(&self) -> usize {
+        (self.delay_buffer_left.capacity() + self.delay_buffer_right.capacity())
+            * std::mem::size_of::<f32>()
+    }
 }
 
 impl AudioNode for Chorus {
@@ -898,6 +998,22 @@ impl AudioNode for Chorus {
     fn is_active(&self) -> bool {
         self.is_node_active()
     }
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.smoothing_time_ms = time_ms;
+        self.param_smooth_coeff = 1.0 - smoothing_coefficient(self.internal_sample_rate, time_ms);
+    }
+
+    fn set_quality_mode(&mut self, mode: QualityMode) {
+        // Eco runs the modulated delay at the host rate with linear reads; the
+        // default 4x/cubic path is already transparent, so High keeps it.
+        let (oversample, linear) = match mode {
+            QualityMode::Eco => (1, true),
+            QualityMode::Normal | QualityMode::High => (DEFAULT_OVERSAMPLE, false),
+        };
+        self.linear_interpolation = linear;
+        self.set_oversample(oversample);
+    }
+
     fn set_active(&mut self, active: bool) {
         self.set_node_active(active);
     }
@@ -0,0 +1,188 @@
+use std::any::Any;
+
+use rustc_hash::FxHashMap;
+
+use crate::graph::ModulationSource;
+use crate::traits::{AudioNode, PortId};
+use crate::utils::groove::Groove;
+
+/// Where the transport is for one block: handed to every `Clock` node before
+/// the voices render.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransportClock {
+    /// Beat position at the first sample of the block.
+    pub start_beat: f64,
+    pub beats_per_sample: f64,
+    pub playing: bool,
+}
+
+impl Default for TransportClock {
+    fn default() -> Self {
+        Self {
+            start_beat: 0.0,
+            beats_per_sample: 0.0,
+            playing: false,
+        }
+    }
+}
+
+/// Clock signal from the engine transport.
+///
+/// Outputs a pulse every `division_beats` beats (0.25 = sixteenth notes),
+/// high for `pulse_width` of each division, locked to the transport position
+/// so every voice's clock lines up. Nothing is output while the transport is
+/// stopped. Patch it into the arpeggiator's `ClockInput` to step it, or into
+/// an envelope or gate tool as a gate. Pulses on the sixteenth grid follow the
+/// groove; divisions finer than a sixteenth stay straight.
+pub struct Clock {
+    enabled: bool,
+    division_beats: f64,
+    pulse_width: f64,
+    transport: TransportClock,
+    groove: Groove,
+}
+
+impl Clock {
+    /// Creates a new Clock node.
+    ///
+    /// * `division_beats` - Beats between pulses.
+    /// * `pulse_width` - Fraction of each division the pulse stays high.
+    pub fn new(division_beats: f32, pulse_width: f32) -> Self {
+        let mut clock = Self {
+            enabled: true,
+            division_beats: 1.0,
+            pulse_width: 0.5,
+            transport: TransportClock::default(),
+            groove: Groove::default(),
+        };
+        clock.set_division_beats(division_beats);
+        clock.set_pulse_width(pulse_width);
+        clock
+    }
+
+    pub fn set_division_beats(&mut self, division_beats: f32) {
+        self.division_beats = (division_beats as f64).max(1.0 / 64.0);
+    }
+
+    pub fn set_pulse_width(&mut self, pulse_width: f32) {
+        self.pulse_width = (pulse_width as f64).clamp(0.01, 0.99);
+    }
+
+    /// Sets the transport position for the next block.
+    pub fn set_transport(&mut self, transport: TransportClock) {
+        self.transport = transport;
+    }
+
+    fn pulse_onset(&self, pulse: f64) -> f64 {
+        let beat = pulse * self.division_beats;
+        if self.division_beats >= 0.25 {
+            beat + self.groove.offset_beats(beat)
+        } else {
+            beat
+        }
+    }
+
+    fn is_high(&self, beat: f64) -> bool {
+        let pulse = (beat / self.division_beats).floor();
+        // A grooved pulse starts at most a quarter sixteenth early or three
+        // quarters late, so it is one of these.
+        [pulse + 1.0, pulse, pulse - 1.0]
+            .into_iter()
+            .map(|pulse| self.pulse_onset(pulse))
+            .find(|&onset| onset <= beat)
+            .is_some_and(|onset| beat - onset < self.pulse_width * self.division_beats)
+    }
+}
+
+impl AudioNode for Clock {
+    fn get_ports(&self) -> FxHashMap<PortId, bool> {
+        let mut ports = FxHashMap::default();
+        ports.insert(PortId::AudioOutput0, true); // Clock pulses
+        ports
+    }
+
+    fn process<'a>(
+        &mut self,
+        _inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+        outputs: &mut FxHashMap<PortId, &mut [f32]>,
+        buffer_size: usize,
+    ) {
+        let Some(output) = outputs.get_mut(&PortId::AudioOutput0) else {
+            return;
+        };
+        let TransportClock {
+            start_beat,
+            beats_per_sample,
+            playing,
+        } = self.transport;
+        for (i, out) in output.iter_mut().enumerate().take(buffer_size) {
+            let beat = start_beat + beats_per_sample * i as f64;
+            *out = if playing && self.is_high(beat) {
+                1.0
+            } else {
+                0.0
+            };
+        }
+    }
+
+    fn reset(&mut self) {}
+
+    fn set_groove(&mut self, groove: &Groove) {
+        self.groove = *groove;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn is_active(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_active(&mut self, active: bool) {
+        self.enabled = active;
+    }
+
+    fn name(&self) -> &'static str {
+        "Clock"
+    }
+
+    fn node_type(&self) -> &str {
+        "clock"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pulses_follow_the_transport_position() {
+        let mut clock = Clock::new(0.25, 0.5);
+        // Eight samples per beat, starting half a sixteenth in.
+        clock.set_transport(TransportClock {
+            start_beat: 0.125,
+            beats_per_sample: 1.0 / 8.0,
+            playing: true,
+        });
+        let mut output = vec![0.0; 8];
+        {
+            let mut outputs = FxHashMap::default();
+            outputs.insert(PortId::AudioOutput0, output.as_mut_slice());
+            clock.process(&FxHashMap::default(), &mut outputs, 8);
+        }
+        assert_eq!(output, vec![0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0]);
+
+        clock.set_transport(TransportClock::default());
+        {
+            let mut outputs = FxHashMap::default();
+            outputs.insert(PortId::AudioOutput0, output.as_mut_slice());
+            clock.process(&FxHashMap::default(), &mut outputs, 8);
+        }
+        assert!(output.iter().all(|&s| s == 0.0));
+    }
+}
@@ -3,16 +3,19 @@ use std::any::Any;
 
 use crate::graph::ModulationSource;
 use crate::traits::{AudioNode, PortId};
+use crate::utils::sidechain::sidechain_key;
+use crate::utils::smoothing::{SmoothedParam, DEFAULT_SMOOTHING_MS};
 
-/// Simple stereo compressor with peak detection and wet/dry mix.
+/// Simple stereo compressor with peak detection and wet/dry mix. A signal on
+/// `SidechainInput` replaces the program material as the detector key (ducking).
 pub struct Compressor {
     active: bool,
     threshold_db: f32,
     ratio: f32,
     attack_coeff: f32,
     release_coeff: f32,
-    makeup_gain: f32,
-    mix: f32,
+    makeup_gain: SmoothedParam,
+    mix: SmoothedParam,
     envelope: f32,
     sample_rate: f32,
 }
@@ -33,8 +36,12 @@ impl Compressor {
             ratio: ratio.max(1.0),
             attack_coeff: Self::time_to_coeff(attack_ms, sample_rate),
             release_coeff: Self::time_to_coeff(release_ms, sample_rate),
-            makeup_gain: Self::db_to_linear(makeup_gain_db),
-            mix: mix.clamp(0.0, 1.0),
+            makeup_gain: SmoothedParam::new(
+                Self::db_to_linear(makeup_gain_db),
+                sample_rate,
+                DEFAULT_SMOOTHING_MS,
+            ),
+            mix: SmoothedParam::new(mix.clamp(0.0, 1.0), sample_rate, DEFAULT_SMOOTHING_MS),
             envelope: 0.0,
             sample_rate,
         }
@@ -68,11 +75,12 @@ impl Compressor {
     }
 
     pub fn set_makeup_gain_db(&mut self, makeup_gain_db: f32) {
-        self.makeup_gain = Self::db_to_linear(makeup_gain_db);
+        self.makeup_gain
+            .set_target(Self::db_to_linear(makeup_gain_db));
     }
 
     pub fn set_mix(&mut self, mix: f32) {
-        self.mix = mix.clamp(0.0, 1.0);
+        self.mix.set_target(mix.clamp(0.0, 1.0));
     }
 
     fn compute_gain(&self, level: f32) -> f32 {
@@ -82,12 +90,12 @@ impl Compressor {
 
         let level_db = 20.0 * level.log10();
         if level_db <= self.threshold_db {
-            return self.makeup_gain;
+            return 1.0;
         }
 
         let compressed_db = self.threshold_db + (level_db - self.threshold_db) / self.ratio;
         let gain_db = compressed_db - level_db;
-        Self::db_to_linear(gain_db) * self.makeup_gain
+        Self::db_to_linear(gain_db)
     }
 
     #[inline]
@@ -105,6 +113,7 @@ impl AudioNode for Compressor {
         let mut ports = FxHashMap::default();
         ports.insert(PortId::AudioInput0, false);
         ports.insert(PortId::AudioInput1, false);
+        ports.insert(PortId::SidechainInput, false);
         ports.insert(PortId::AudioOutput0, true);
         ports.insert(PortId::AudioOutput1, true);
         ports
@@ -144,6 +153,8 @@ impl AudioNode for Compressor {
                 &ZERO_BUFFER[..buffer_size.min(ZERO_BUFFER.len())]
             });
 
+        let key = sidechain_key(inputs, buffer_size);
+
         let outs = outputs.get_disjoint_mut([&PortId::AudioOutput0, &PortId::AudioOutput1]);
         let [Some(out_left), Some(out_right)] = outs else {
             return;
@@ -151,15 +162,17 @@ impl AudioNode for Compressor {
         let out_left: &mut [f32] = *out_left;
         let out_right: &mut [f32] = *out_right;
 
-        let mix = self.mix;
-        let dry_mix = 1.0 - mix;
-
         for i in 0..buffer_size {
             let dry_l = left_in.get(i).copied().unwrap_or(0.0);
             let dry_r = right_in.get(i).copied().unwrap_or(0.0);
-            let detector = dry_l.abs().max(dry_r.abs());
+            let detector = match key {
+                Some(key) => key.get(i).copied().unwrap_or(0.0).abs(),
+                None => dry_l.abs().max(dry_r.abs()),
+            };
             self.envelope = self.update_envelope(self.envelope, detector);
-            let gain = self.compute_gain(self.envelope);
+            let gain = self.compute_gain(self.envelope) * self.makeup_gain.next_value();
+            let mix = self.mix.next_value();
+            let dry_mix = 1.0 - mix;
 
             let wet_l = dry_l * gain;
             let wet_r = dry_r * gain;
@@ -185,6 +198,11 @@ impl AudioNode for Compressor {
         self.active
     }
 
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.makeup_gain.set_time_ms(time_ms);
+        self.mix.set_time_ms(time_ms);
+    }
+
     fn set_active(&mut self, active: bool) {
         self.active = active;
         if !active {
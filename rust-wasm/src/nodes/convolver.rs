@@ -1,6 +1,5 @@
 // convolver.rs
 
-use fft_convolver::FFTConvolver;
 use rustc_hash::FxHashMap;
 use std::any::Any;
 use std::borrow::Cow;
@@ -9,7 +8,9 @@ use std::borrow::Cow;
 
 // Import ModulationProcessor and ModulationSource from the graph module.
 use crate::graph::{ModulationProcessor, ModulationSource};
-use crate::traits::{AudioNode, PortId}; // Ensure these paths are correct
+use crate::traits::{AudioNode, PortId, QualityMode}; // Ensure these paths are correct
+use crate::utils::partitioned_convolver::PartitionedConvolver;
+use crate::utils::smoothing::{SmoothedParam, DEFAULT_SMOOTHING_MS};
 
 /// Helper function to ensure a Vec has at least a certain size, filling with a value if resizing,
 /// and truncating if it's too long.
@@ -20,13 +21,16 @@ fn ensure_buffer_size_and_fill(buffer: &mut Vec<f32>, required_size: usize, fill
     buffer.truncate(required_size);
 }
 
-/// A Convolver that uses FFTConvolver for fast convolution processing.
+/// Reverb tail kept in `QualityMode::Eco`; later partitions are skipped.
+const ECO_TAIL_SECONDS: f32 = 1.0;
+
+/// A Convolver that uses zero-latency partitioned FFT convolution.
 /// Assumes only audio inputs are connected, uses self.wet_level for mix.
 pub struct Convolver {
     enabled: bool,
-    convolvers: Vec<FFTConvolver<f32>>,
+    convolvers: Vec<PartitionedConvolver>,
     tail_count: usize,
-    pub wet_level: f32,
+    wet_level: SmoothedParam,
     pub partition_size: usize,
     pub sample_rate: f32,
     fallback_zero_buffer: Vec<f32>,
@@ -42,7 +46,11 @@ impl Convolver {
     }
 
     pub fn set_wet_level(&mut self, wet_level: f32) {
-        self.wet_level = wet_level.clamp(0.0, 1.0);
+        self.wet_level.set_target(wet_level.clamp(0.0, 1.0));
+    }
+
+    pub fn wet_level(&self) -> f32 {
+        self.wet_level.target()
     }
 
     pub fn set_enabled(&mut self, enabled: bool) {
@@ -112,24 +120,14 @@ impl Convolver {
                 *sample *= scale;
             }
 
-            let mut conv = FFTConvolver::<f32>::default();
-            match conv.init(partition_size, &scaled_channel) {
-                Ok(_) => convolvers.push(conv),
-                Err(e) => {
-                    // Panic for critical initialization errors
-                    panic!(
-                        "Unable to initialize convolver {} using IR channel {}: {:?}",
-                        i, ir_channel_index, e
-                    );
-                }
-            }
+            convolvers.push(PartitionedConvolver::new(partition_size, &scaled_channel));
         }
 
         Self {
             enabled: true,
             convolvers,
             tail_count: 0,
-            wet_level: 0.2,
+            wet_level: SmoothedParam::new(0.2, sample_rate, DEFAULT_SMOOTHING_MS),
             partition_size,
             sample_rate,
             fallback_zero_buffer: Vec::new(),
@@ -141,7 +139,7 @@ impl Convolver {
 
     // --- process_block_wet_signal (Cleaned) ---
     fn process_block_wet_signal(
-        convolvers: &mut [FFTConvolver<f32>],
+        convolvers: &mut [PartitionedConvolver],
         tail_count: &mut usize,
         inputs: &[&[f32]],
         output_left: &mut [f32],
@@ -173,8 +171,8 @@ impl Convolver {
         // Perform convolution
         match (input_l_slice_opt, input_r_slice_opt) {
             (Some(in_l), Some(in_r)) => {
-                let _ = convolvers[0].process(&in_l[..buffer_len], output_left);
-                let _ = convolvers[1].process(&in_r[..buffer_len], output_right);
+                convolvers[0].process(&in_l[..buffer_len], output_left);
+                convolvers[1].process(&in_r[..buffer_len], output_right);
             }
             _ => {
                 // No valid input / length mismatch
@@ -209,6 +207,18 @@ impl Convolver {
         self.original_impulse_response = new_convolver.original_impulse_response;
         self.tail_count = 0; // Reset tail count
     }
+
+    /// Bytes held by the impulse response partitions and the stored impulse
+    /// response.
+    pub fn memory_bytes(&self) -> usize {
+        let partitions: usize = self.convolvers.iter().map(|c| c.memory_bytes()).sum();
+        let stored: usize = self
+            .original_impulse_response
+            .iter()
+            .map(|ir| ir.capacity())
+            .sum();
+        partitions + stored * std::mem::size_of::<f32>()
+    }
 }
 
 // ============================================================
@@ -318,11 +328,10 @@ impl AudioNode for Convolver {
             // --- Apply Wet/Dry Mix using self.wet_level ---
             let final_out_l_slice = &mut out_l_buffer[..buffer_size];
             let final_out_r_slice = &mut out_r_buffer[..buffer_size];
-            let mix_factor = self.wet_level; // Use property directly
-
             // Removed pre-mix logs
 
             for i in 0..buffer_size {
+                let mix_factor = self.wet_level.next_value();
                 let wet_l = *self.temp_wet_l.get(i).unwrap_or(&0.0);
                 let wet_r = *self.temp_wet_r.get(i).unwrap_or(&0.0);
                 let dry_l = *dry_l_signal.get(i).unwrap_or(&0.0);
@@ -357,6 +366,22 @@ impl AudioNode for Convolver {
     fn is_active(&self) -> bool {
         self.enabled
     }
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.wet_level.set_time_ms(time_ms);
+    }
+    fn set_quality_mode(&mut self, mode: QualityMode) {
+        let eco_tail_samples = ECO_TAIL_SECONDS * self.sample_rate;
+        for convolver in &mut self.convolvers {
+            let max_partitions = match mode {
+                QualityMode::Eco => {
+                    Some((eco_tail_samples / convolver.block_size() as f32).ceil() as usize)
+                }
+                QualityMode::Normal | QualityMode::High => None,
+            };
+            convolver.set_max_partitions(max_partitions);
+        }
+    }
+
     fn set_active(&mut self, active: bool) {
         if !active && self.enabled {
             self.reset_state();
@@ -5,6 +5,9 @@ use rustc_hash::FxHashMap;
 
 use crate::graph::ModulationSource;
 use crate::traits::{AudioNode, PortId};
+use crate::utils::groove::Groove;
+use crate::utils::sidechain::{sidechain_key, SidechainFollower};
+use crate::utils::smoothing::{SmoothedParam, DEFAULT_SMOOTHING_MS};
 
 pub struct Delay {
     enabled: bool,
@@ -13,8 +16,22 @@ pub struct Delay {
     write_index: usize,
     max_delay_samples: usize,
     delay_samples: usize, // current delay time in samples
-    feedback: f32,
-    mix: f32, // mix amount: 0.0 = fully dry, 1.0 = fully wet
+    delay_ms: f32,        // free-running delay time, used when not synced
+    // Delay time in beats when synced to the tempo; the groove moves echoes
+    // that land on the sixteenth grid.
+    sync_beats: Option<f32>,
+    tempo_bpm: f32,
+    groove: Groove,
+    feedback: SmoothedParam,
+    mix: SmoothedParam,     // mix amount: 0.0 = fully dry, 1.0 = fully wet
+    ducking: SmoothedParam, // how far the sidechain key pulls the echoes down
+    ducker: SidechainFollower,
+    // 0 = normal, 1 = frozen: input muted and the loop recirculates at unity gain.
+    freeze: SmoothedParam,
+    // Offsets from the effect stack's LFOs; the time offset is in ms.
+    time_offset: SmoothedParam,
+    feedback_offset: SmoothedParam,
+    mix_offset: SmoothedParam,
     sample_rate: f32,
 }
 
@@ -43,26 +60,104 @@ impl Delay {
             write_index: 0,
             max_delay_samples,
             delay_samples,
-            feedback,
-            mix: mix.clamp(0.0, 1.0),
+            delay_ms,
+            sync_beats: None,
+            tempo_bpm: 120.0,
+            groove: Groove::default(),
+            feedback: SmoothedParam::new(feedback, sample_rate, DEFAULT_SMOOTHING_MS),
+            mix: SmoothedParam::new(mix.clamp(0.0, 1.0), sample_rate, DEFAULT_SMOOTHING_MS),
+            ducking: SmoothedParam::new(0.0, sample_rate, DEFAULT_SMOOTHING_MS),
+            ducker: SidechainFollower::new(sample_rate, 5.0, 250.0),
+            freeze: SmoothedParam::new(0.0, sample_rate, DEFAULT_SMOOTHING_MS),
+            time_offset: SmoothedParam::new(0.0, sample_rate, DEFAULT_SMOOTHING_MS),
+            feedback_offset: SmoothedParam::new(0.0, sample_rate, DEFAULT_SMOOTHING_MS),
+            mix_offset: SmoothedParam::new(0.0, sample_rate, DEFAULT_SMOOTHING_MS),
             sample_rate,
         }
     }
 
-    /// Sets the delay time in milliseconds.
+    /// Sets the delay time in milliseconds, used while the delay isn't synced.
     pub fn set_delay_ms(&mut self, delay_ms: f32) {
+        self.delay_ms = delay_ms;
+        self.update_delay_time();
+    }
+
+    /// Syncs the delay time to `beats` beats of the tempo (0.75 = dotted
+    /// eighth); `None` returns to the time set in milliseconds.
+    pub fn set_sync_beats(&mut self, beats: Option<f32>) {
+        self.sync_beats = beats.filter(|beats| *beats > 0.0);
+        self.update_delay_time();
+    }
+
+    pub fn sync_beats(&self) -> Option<f32> {
+        self.sync_beats
+    }
+
+    /// Tempo a synced delay follows.
+    pub fn set_tempo(&mut self, bpm: f32) {
+        self.tempo_bpm = bpm.max(1.0);
+        self.update_delay_time();
+    }
+
+    fn update_delay_time(&mut self) {
+        let delay_ms = match self.sync_beats {
+            Some(beats) => {
+                let beats = beats as f64 + self.groove.offset_beats(beats as f64);
+                (beats * 60_000.0 / self.tempo_bpm as f64) as f32
+            }
+            None => self.delay_ms,
+        };
         self.delay_samples =
             (((delay_ms / 1000.0) * self.sample_rate).ceil() as usize).min(self.max_delay_samples);
     }
 
     /// Sets the feedback amount.
     pub fn set_feedback(&mut self, feedback: f32) {
-        self.feedback = feedback;
+        self.feedback.set_target(feedback);
     }
 
     /// Sets the mix amount (0.0 = fully dry, 1.0 = fully wet).
     pub fn set_mix(&mut self, mix: f32) {
-        self.mix = mix.clamp(0.0, 1.0);
+        self.mix.set_target(mix.clamp(0.0, 1.0));
+    }
+
+    /// Sets how much a full-scale sidechain key attenuates the echoes
+    /// (0.0 = no ducking, 1.0 = echoes muted while the key is up).
+    pub fn set_ducking(&mut self, ducking: f32) {
+        self.ducking.set_target(ducking.clamp(0.0, 1.0));
+    }
+
+    /// Holds the current contents of the delay line indefinitely. New input is
+    /// muted and feedback is raised to unity; the loop has no filtering, so the
+    /// held material repeats unchanged rather than building up.
+    pub fn set_freeze(&mut self, frozen: bool) {
+        self.freeze.set_target(if frozen { 1.0 } else { 0.0 });
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.freeze.target() > 0.5
+    }
+
+    pub fn delay_ms(&self) -> f32 {
+        self.delay_samples as f32 * 1000.0 / self.sample_rate
+    }
+
+    pub fn feedback(&self) -> f32 {
+        self.feedback.target()
+    }
+
+    pub fn mix(&self) -> f32 {
+        self.mix.target()
+    }
+
+    pub fn ducking(&self) -> f32 {
+        self.ducking.target()
+    }
+
+    /// Bytes held by the delay lines.
+    pub fn memory_bytes(&self) -> usize {
+        (self.delay_buffer_left.capacity() + self.delay_buffer_right.capacity())
+            * std::mem::size_of::<f32>()
     }
 }
 
@@ -75,6 +170,7 @@ impl AudioNode for Delay {
         // Stereo inputs:
         ports.insert(PortId::AudioInput0, false); // Left input
         ports.insert(PortId::AudioInput1, false); // Right input
+        ports.insert(PortId::SidechainInput, false); // Ducking key
 
         // Stereo outputs:
         ports.insert(PortId::AudioOutput0, true); // Left output
@@ -112,6 +208,8 @@ impl AudioNode for Delay {
                 &ZERO_BUFFER[..buffer_size.min(ZERO_BUFFER.len())]
             });
 
+        let key = sidechain_key(inputs, buffer_size);
+
         // Use get_disjoint_mut to retrieve both outputs at once.
         let outs = outputs.get_disjoint_mut([&PortId::AudioOutput0, &PortId::AudioOutput1]);
         let [Some(out_left), Some(out_right)] = outs else {
@@ -134,7 +232,11 @@ impl AudioNode for Delay {
             let in_right_vec = f32x4::from_array(in_right_arr);
 
             // Calculate the base read index for the delay with wrap-around.
-            let base = (self.write_index + self.max_delay_samples - self.delay_samples)
+            let time_offset = self.time_offset.advance(chunk_len) * 0.001 * self.sample_rate;
+            let delay_samples = (self.delay_samples as f32 + time_offset)
+                .round()
+                .clamp(0.0, self.max_delay_samples as f32) as usize;
+            let base = (self.write_index + self.max_delay_samples - delay_samples)
                 % self.max_delay_samples;
 
             // Load delayed samples from the delay buffers (handling wrap-around).
@@ -169,10 +271,22 @@ impl AudioNode for Delay {
                     )
                 };
 
+            // Feedback and mix glide once per chunk to avoid zipper noise.
+            let freeze = self.freeze.advance(chunk_len);
+            // Crossfade towards (input muted, unity feedback) while freezing.
+            let feedback = (self.feedback.advance(chunk_len)
+                + self.feedback_offset.advance(chunk_len))
+            .clamp(-1.0, 1.0);
+            let feedback = feedback * (1.0 - freeze) + freeze;
+            let mix =
+                (self.mix.advance(chunk_len) + self.mix_offset.advance(chunk_len)).clamp(0.0, 1.0);
+            let ducking = self.ducking.advance(chunk_len);
+
             // Compute new samples: new_sample = input + (delayed * feedback)
-            let fb_vec = f32x4::splat(self.feedback);
-            let new_left_vec = in_left_vec + delayed_left_vec * fb_vec;
-            let new_right_vec = in_right_vec + delayed_right_vec * fb_vec;
+            let fb_vec = f32x4::splat(feedback);
+            let send_vec = f32x4::splat(1.0 - freeze);
+            let new_left_vec = in_left_vec * send_vec + delayed_left_vec * fb_vec;
+            let new_right_vec = in_right_vec * send_vec + delayed_right_vec * fb_vec;
 
             // Write new samples into the delay buffers (handling wrap-around).
             if self.write_index + chunk_len <= self.max_delay_samples {
@@ -195,8 +309,15 @@ impl AudioNode for Delay {
 
             // Calculate mix levels.
             // dry_level = 1.0 - mix, wet_level = mix
-            let dry_level = f32x4::splat(1.0 - self.mix);
-            let wet_level = f32x4::splat(self.mix);
+            let dry_level = f32x4::splat(1.0 - mix);
+            let mut wet_arr = [mix; 4];
+            if let Some(key) = key {
+                for (j, wet) in wet_arr[..chunk_len].iter_mut().enumerate() {
+                    let level = self.ducker.next(key.get(i + j).copied().unwrap_or(0.0));
+                    *wet *= 1.0 - ducking * level;
+                }
+            }
+            let wet_level = f32x4::from_array(wet_arr);
 
             // Mix the dry (original) and wet (delayed) signals.
             let mixed_left_vec = in_left_vec * dry_level + delayed_left_vec * wet_level;
@@ -216,6 +337,7 @@ impl AudioNode for Delay {
         self.delay_buffer_left.fill(0.0);
         self.delay_buffer_right.fill(0.0);
         self.write_index = 0;
+        self.ducker.reset();
     }
 
     fn as_any_mut(&mut self) -> &mut dyn Any {
@@ -230,6 +352,27 @@ impl AudioNode for Delay {
         self.enabled
     }
 
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.feedback.set_time_ms(time_ms);
+        self.mix.set_time_ms(time_ms);
+    }
+
+    fn set_groove(&mut self, groove: &Groove) {
+        self.groove = *groove;
+        self.update_delay_time();
+    }
+
+    fn modulate_parameter(&mut self, parameter: &str, offset: f32) -> bool {
+        let target = match parameter {
+            "time" => &mut self.time_offset,
+            "feedback" => &mut self.feedback_offset,
+            "mix" => &mut self.mix_offset,
+            _ => return false,
+        };
+        target.set_target(offset);
+        true
+    }
+
     fn set_active(&mut self, active: bool) {
         self.enabled = active;
         if active {
@@ -245,3 +388,76 @@ impl AudioNode for Delay {
         "delay"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{ModulationTransformation, ModulationType};
+
+    fn process(delay: &mut Delay, input: &[f32]) -> Vec<f32> {
+        let source = |buffer| ModulationSource {
+            buffer,
+            amount: 1.0,
+            mod_type: ModulationType::Additive,
+            transformation: ModulationTransformation::None,
+        };
+        let mut inputs = FxHashMap::default();
+        inputs.insert(PortId::AudioInput0, vec![source(input)]);
+        inputs.insert(PortId::AudioInput1, vec![source(input)]);
+        let mut left = vec![0.0; input.len()];
+        let mut right = vec![0.0; input.len()];
+        let mut outputs = FxHashMap::default();
+        outputs.insert(PortId::AudioOutput0, left.as_mut_slice());
+        outputs.insert(PortId::AudioOutput1, right.as_mut_slice());
+        delay.process(&inputs, &mut outputs, input.len());
+        left
+    }
+
+    #[test]
+    fn freeze_holds_the_loop_and_ignores_new_input() {
+        // 1 kHz delay of 10 samples, 50% feedback, fully wet.
+        let mut delay = Delay::new(1_000.0, 100.0, 10.0, 0.5, 1.0);
+        let mut impulse = vec![0.0; 10];
+        impulse[0] = 1.0;
+        process(&mut delay, &impulse);
+
+        delay.set_freeze(true);
+        // Let the input fade and feedback reach unity; the echo keeps circulating.
+        for _ in 0..20 {
+            process(&mut delay, &[0.0; 10]);
+        }
+        let held = process(&mut delay, &[0.0; 10]);
+        let level = held.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(level > 0.01);
+
+        // While frozen, new input never reaches the line.
+        let mut echoes = Vec::new();
+        for _ in 0..50 {
+            let out = process(&mut delay, &[0.8; 10]);
+            echoes.push(out.iter().fold(0.0f32, |m, s| m.max(s.abs())));
+        }
+        assert!(
+            echoes.iter().all(|&e| (e - level).abs() < 1e-6),
+            "{:?}",
+            echoes
+        );
+
+        delay.set_freeze(false);
+        assert!(!delay.is_frozen());
+    }
+
+    #[test]
+    fn synced_time_follows_tempo_and_groove() {
+        let mut delay = Delay::new(1_000.0, 2_000.0, 10.0, 0.5, 1.0);
+        delay.set_tempo(120.0);
+        delay.set_sync_beats(Some(0.25));
+        assert_eq!(delay.delay_ms(), 125.0);
+
+        // Full swing lands a sixteenth echo half a sixteenth late.
+        delay.set_groove(&Groove::new(75.0, &[]));
+        assert_eq!(delay.delay_ms(), 188.0);
+
+        delay.set_sync_beats(None);
+        assert_eq!(delay.delay_ms(), 10.0);
+    }
+}
@@ -0,0 +1,402 @@
+use std::any::Any;
+
+use rustc_hash::FxHashMap;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::graph::{ModulationProcessor, ModulationSource};
+use crate::nodes::FilterCollection;
+use crate::traits::{AudioNode, PortId};
+
+const MAX_SPACING_SEMITONES: f32 = 48.0;
+
+/// How the two filters of a `DualFilter` are wired.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DualFilterRouting {
+    Serial = 0,   // Input -> A -> B
+    Parallel = 1, // Input -> A and Input -> B, summed
+    Split = 2,    // Left -> A, right -> B
+}
+
+/// Two `FilterCollection`s behind one node with serial, parallel or split routing.
+///
+/// Cutoff and resonance are set on the container: filter B runs `spacing`
+/// semitones away from filter A. `balance` crossfades from A (0.0) to B (1.0);
+/// in serial mode B is the output of the chain. Split routing sends each
+/// channel through its own filter and ignores `balance`.
+#[derive(Clone)]
+pub struct DualFilter {
+    enabled: bool,
+    filter_a: FilterCollection,
+    filter_b: FilterCollection,
+    routing: DualFilterRouting,
+    balance: f32,
+    spacing_semitones: f32,
+    cutoff: f32,
+    resonance: [f32; 2],
+
+    in_left: Vec<f32>,
+    in_right: Vec<f32>,
+    a_left: Vec<f32>,
+    a_right: Vec<f32>,
+    b_left: Vec<f32>,
+    b_right: Vec<f32>,
+}
+
+impl DualFilter {
+    pub fn new(sample_rate: f32) -> Self {
+        let initial_capacity = 128;
+        let mut dual = Self {
+            enabled: true,
+            filter_a: FilterCollection::new(sample_rate),
+            filter_b: FilterCollection::new(sample_rate),
+            routing: DualFilterRouting::Serial,
+            balance: 1.0,
+            spacing_semitones: 0.0,
+            cutoff: 20000.0,
+            resonance: [0.0; 2],
+            in_left: vec![0.0; initial_capacity],
+            in_right: vec![0.0; initial_capacity],
+            a_left: vec![0.0; initial_capacity],
+            a_right: vec![0.0; initial_capacity],
+            b_left: vec![0.0; initial_capacity],
+            b_right: vec![0.0; initial_capacity],
+        };
+        dual.apply_cutoffs();
+        dual
+    }
+
+    pub fn set_routing(&mut self, routing: DualFilterRouting) {
+        if routing != self.routing {
+            self.routing = routing;
+            self.filter_a.reset();
+            self.filter_b.reset();
+        }
+    }
+
+    /// Sets the A/B crossfade (0.0 = filter A only, 1.0 = filter B only).
+    pub fn set_balance(&mut self, balance: f32) {
+        self.balance = balance.clamp(0.0, 1.0);
+    }
+
+    /// Sets how far filter B's cutoff sits from filter A's, in semitones.
+    pub fn set_spacing(&mut self, semitones: f32) {
+        self.spacing_semitones = semitones.clamp(-MAX_SPACING_SEMITONES, MAX_SPACING_SEMITONES);
+        self.apply_cutoffs();
+    }
+
+    /// Sets the shared base cutoff (filter A; filter B is offset by the spacing).
+    pub fn set_cutoff(&mut self, cutoff: f32) {
+        self.cutoff = cutoff;
+        self.apply_cutoffs();
+    }
+
+    /// Sets the resonance of one filter slot (0 = A, 1 = B).
+    pub fn set_resonance(&mut self, slot: usize, resonance: f32) {
+        if let Some(value) = self.resonance.get_mut(slot) {
+            *value = resonance;
+            self.apply_cutoffs();
+        }
+    }
+
+    /// Access to a filter slot (0 = A, 1 = B) for type, slope, gain and the other
+    /// per-filter settings. Cutoff and resonance are owned by the container.
+    pub fn filter_mut(&mut self, slot: usize) -> Option<&mut FilterCollection> {
+        match slot {
+            0 => Some(&mut self.filter_a),
+            1 => Some(&mut self.filter_b),
+            _ => None,
+        }
+    }
+
+    fn apply_cutoffs(&mut self) {
+        let spacing_ratio = (self.spacing_semitones / 12.0).exp2();
+        self.filter_a.set_params(self.cutoff, self.resonance[0]);
+        self.filter_b
+            .set_params(self.cutoff * spacing_ratio, self.resonance[1]);
+    }
+
+    fn ensure_scratch_buffers(&mut self, size: usize) {
+        for buf in [
+            &mut self.in_left,
+            &mut self.in_right,
+            &mut self.a_left,
+            &mut self.a_right,
+            &mut self.b_left,
+            &mut self.b_right,
+        ] {
+            if buf.len() < size {
+                buf.resize(size.next_power_of_two(), 0.0);
+            }
+        }
+    }
+
+    fn sum_input(
+        inputs: &FxHashMap<PortId, Vec<ModulationSource>>,
+        port: PortId,
+        target: &mut [f32],
+    ) -> bool {
+        target.fill(0.0);
+        let Some(sources) = inputs.get(&port).filter(|sources| !sources.is_empty()) else {
+            return false;
+        };
+        for source in sources {
+            Self::apply_add(source.buffer, target, source.amount, source.transformation);
+        }
+        true
+    }
+}
+
+impl ModulationProcessor for DualFilter {}
+
+impl AudioNode for DualFilter {
+    fn get_ports(&self) -> FxHashMap<PortId, bool> {
+        [
+            (PortId::AudioInput0, false),
+            (PortId::AudioInput1, false),
+            (PortId::CutoffMod, false),
+            (PortId::ResonanceMod, false),
+            (PortId::DriveMod, false),
+            (PortId::Frequency, false),
+            (PortId::GlobalFrequency, false),
+            (PortId::AudioOutput0, true),
+            (PortId::AudioOutput1, true),
+        ]
+        .iter()
+        .cloned()
+        .collect()
+    }
+
+    fn process<'a>(
+        &mut self,
+        inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+        outputs: &mut FxHashMap<PortId, &mut [f32]>,
+        buffer_size: usize,
+    ) {
+        if !self.enabled {
+            for port in [PortId::AudioOutput0, PortId::AudioOutput1] {
+                if let Some(output_buffer) = outputs.get_mut(&port) {
+                    output_buffer[..buffer_size].fill(0.0);
+                }
+            }
+            return;
+        }
+
+        self.ensure_scratch_buffers(buffer_size);
+        let n = buffer_size;
+        Self::sum_input(inputs, PortId::AudioInput0, &mut self.in_left[..n]);
+        let stereo = Self::sum_input(inputs, PortId::AudioInput1, &mut self.in_right[..n]);
+        let in_right = if stereo {
+            &self.in_right[..n]
+        } else {
+            &self.in_left[..n]
+        };
+
+        match self.routing {
+            DualFilterRouting::Serial => {
+                self.filter_a.process_audio(
+                    &self.in_left[..n],
+                    stereo.then_some(in_right),
+                    inputs,
+                    &mut self.a_left[..n],
+                    &mut self.a_right[..n],
+                    n,
+                );
+                self.filter_b.process_audio(
+                    &self.a_left[..n],
+                    stereo.then_some(&self.a_right[..n]),
+                    inputs,
+                    &mut self.b_left[..n],
+                    &mut self.b_right[..n],
+                    n,
+                );
+            }
+            DualFilterRouting::Parallel => {
+                self.filter_a.process_audio(
+                    &self.in_left[..n],
+                    stereo.then_some(in_right),
+                    inputs,
+                    &mut self.a_left[..n],
+                    &mut self.a_right[..n],
+                    n,
+                );
+                self.filter_b.process_audio(
+                    &self.in_left[..n],
+                    stereo.then_some(in_right),
+                    inputs,
+                    &mut self.b_left[..n],
+                    &mut self.b_right[..n],
+                    n,
+                );
+            }
+            DualFilterRouting::Split => {
+                self.filter_a.process_audio(
+                    &self.in_left[..n],
+                    None,
+                    inputs,
+                    &mut self.a_left[..n],
+                    &mut self.a_right[..n],
+                    n,
+                );
+                self.filter_b.process_audio(
+                    in_right,
+                    None,
+                    inputs,
+                    &mut self.b_left[..n],
+                    &mut self.b_right[..n],
+                    n,
+                );
+                if let Some(out) = outputs.get_mut(&PortId::AudioOutput0) {
+                    out[..n].copy_from_slice(&self.a_left[..n]);
+                }
+                if let Some(out) = outputs.get_mut(&PortId::AudioOutput1) {
+                    out[..n].copy_from_slice(&self.b_left[..n]);
+                }
+                return;
+            }
+        }
+
+        let balance = self.balance;
+        for (port, a, b) in [
+            (PortId::AudioOutput0, &self.a_left, &self.b_left),
+            (PortId::AudioOutput1, &self.a_right, &self.b_right),
+        ] {
+            if let Some(out) = outputs.get_mut(&port) {
+                for i in 0..n {
+                    out[i] = a[i] + (b[i] - a[i]) * balance;
+                }
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.filter_a.reset();
+        self.filter_b.reset();
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn is_active(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.filter_a.set_smoothing_time_ms(time_ms);
+        self.filter_b.set_smoothing_time_ms(time_ms);
+    }
+
+    fn set_stereo_input(&mut self, connected: bool) {
+        self.filter_a.set_stereo(connected);
+        self.filter_b.set_stereo(connected);
+    }
+
+    fn set_active(&mut self, active: bool) {
+        if !active && self.enabled {
+            self.reset();
+        }
+        self.enabled = active;
+    }
+
+    fn clone_node(&self) -> Option<Box<dyn AudioNode>> {
+        Some(Box::new(self.clone()))
+    }
+
+    fn name(&self) -> &'static str {
+        "Dual Filter"
+    }
+
+    fn node_type(&self) -> &str {
+        "dual_filter"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biquad::FilterType;
+    use crate::graph::{ModulationTransformation, ModulationType};
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    fn run(node: &mut dyn AudioNode, left: &[f32], right: Option<&[f32]>) -> (Vec<f32>, Vec<f32>) {
+        let source = |buffer| ModulationSource {
+            buffer,
+            amount: 1.0,
+            mod_type: ModulationType::Additive,
+            transformation: ModulationTransformation::None,
+        };
+        let mut inputs = FxHashMap::default();
+        inputs.insert(PortId::AudioInput0, vec![source(left)]);
+        if let Some(right) = right {
+            inputs.insert(PortId::AudioInput1, vec![source(right)]);
+        }
+        let mut out_left = vec![0.0; left.len()];
+        let mut out_right = vec![0.0; left.len()];
+        {
+            let mut outputs = FxHashMap::default();
+            outputs.insert(PortId::AudioOutput0, out_left.as_mut_slice());
+            outputs.insert(PortId::AudioOutput1, out_right.as_mut_slice());
+            node.process(&inputs, &mut outputs, left.len());
+        }
+        (out_left, out_right)
+    }
+
+    fn sine(freq: f32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|n| (2.0 * std::f32::consts::PI * freq * n as f32 / SAMPLE_RATE).sin())
+            .collect()
+    }
+
+    fn peak(buffer: &[f32]) -> f32 {
+        buffer[buffer.len() / 2..]
+            .iter()
+            .fold(0.0f32, |max, &x| max.max(x.abs()))
+    }
+
+    #[test]
+    fn serial_with_zero_balance_matches_filter_a() {
+        let input = sine(3000.0, 1024);
+        let mut dual = DualFilter::new(SAMPLE_RATE);
+        dual.set_cutoff(800.0);
+        dual.set_spacing(-12.0);
+        dual.set_balance(0.0);
+        let mut single = FilterCollection::new(SAMPLE_RATE);
+        single.set_params(800.0, 0.0);
+
+        let (dual_out, _) = run(&mut dual, &input, None);
+        let (single_out, _) = run(&mut single, &input, None);
+        for (a, b) in dual_out.iter().zip(&single_out) {
+            assert!((a - b).abs() < 1e-6);
+        }
+
+        // The full chain adds filter B an octave lower, so it attenuates more.
+        let mut chained = DualFilter::new(SAMPLE_RATE);
+        chained.set_cutoff(800.0);
+        chained.set_spacing(-12.0);
+        let (chained_out, _) = run(&mut chained, &input, None);
+        assert!(peak(&chained_out) < peak(&single_out) * 0.5);
+    }
+
+    #[test]
+    fn split_routes_each_channel_through_its_own_filter() {
+        let input = sine(5000.0, 2048);
+        let mut dual = DualFilter::new(SAMPLE_RATE);
+        dual.set_routing(DualFilterRouting::Split);
+        dual.set_cutoff(300.0);
+        dual.filter_mut(1)
+            .unwrap()
+            .set_filter_type(FilterType::HighPass);
+
+        let (left, right) = run(&mut dual, &input, None);
+        assert!(peak(&left) < 0.05, "lowpass slot should block 5 kHz");
+        assert!(peak(&right) > 0.8, "highpass slot should pass 5 kHz");
+    }
+}
@@ -7,12 +7,22 @@ use wasm_bindgen::prelude::wasm_bindgen;
 
 // Import necessary types
 use crate::graph::{ModulationProcessor, ModulationSource};
+use crate::nodes::DEFAULT_RELEASE_VELOCITY;
 use crate::traits::{AudioNode, PortId};
+use crate::utils::analog_spread::VoiceVariation;
 use crate::utils::curves::get_curved_value;
 use serde::{Deserialize, Serialize};
 
 // Resolution of our lookup tables.
 const CURVE_TABLE_SIZE: usize = 1024;
+/// Octaves of time scaling at full velocity amount between the hardest and
+/// softest hits (velocity 0 with amount 1.0 makes the stage 8x longer).
+const VELOCITY_TIME_OCTAVES: f32 = 3.0;
+/// Octaves of release time scaling at full release velocity amount between
+/// the default and the extreme note-off velocities.
+const RELEASE_VELOCITY_OCTAVES: f32 = 2.0;
+/// Keytracked times are unscaled at middle C.
+pub const KEYTRACK_REFERENCE_HZ: f32 = 261.63;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum EnvelopePhase {
@@ -39,6 +49,34 @@ pub struct EnvelopeConfig {
     #[serde(default)]
     pub attack_smoothing_samples: usize, // Number of samples for smoothing start of attack
     pub active: bool, // Whether the node is active (used by graph)
+    /// Gate input level above which the envelope is held on. Any source on
+    /// the gate port (keyboard gate, LFO square, sequencer gate) re-triggers
+    /// the envelope each time it crosses this level.
+    #[serde(default, rename = "gateThreshold")]
+    pub gate_threshold: f32,
+    /// Velocity -> attack time: positive amounts make softer hits attack
+    /// more slowly, negative ones make them faster.
+    #[serde(default, rename = "velocityAttack")]
+    pub velocity_attack: f32,
+    /// Velocity -> decay time, scaled the same way as the attack.
+    #[serde(default, rename = "velocityDecay")]
+    pub velocity_decay: f32,
+    /// Keytrack -> decay time: at 1.0 the decay halves per octave above
+    /// middle C (and doubles per octave below).
+    #[serde(default, rename = "keyDecay")]
+    pub key_decay: f32,
+    /// Keytrack -> release time, scaled the same way as the decay.
+    #[serde(default, rename = "keyRelease")]
+    pub key_release: f32,
+    /// Release velocity -> release time: positive amounts make slow key
+    /// lifts release longer and fast ones shorter.
+    #[serde(default, rename = "velocityRelease")]
+    pub velocity_release: f32,
+    /// Drone mode: gate-off leaves the envelope sustaining until it is
+    /// released explicitly (see `Envelope::release_drone`), so short trigger
+    /// gates can start notes that ring on indefinitely.
+    #[serde(default)]
+    pub drone: bool,
 }
 
 // Sensible defaults
@@ -54,6 +92,13 @@ impl Default for EnvelopeConfig {
             release_curve: 0.0, // Linear
             attack_smoothing_samples: 0,
             active: true,
+            gate_threshold: 0.0,
+            velocity_attack: 0.0,
+            velocity_decay: 0.0,
+            key_decay: 0.0,
+            key_release: 0.0,
+            velocity_release: 0.0,
+            drone: false,
         }
     }
 }
@@ -84,19 +129,30 @@ impl EnvelopeConfig {
             release_curve,
             attack_smoothing_samples,
             active,
+            ..Default::default()
         }
     }
 }
 
+#[derive(Clone)]
 pub struct Envelope {
     // State
     phase: EnvelopePhase,
-    value: f32,               // Current output value
-    release_level: f32,       // Value when release phase started
-    position: f32,            // Position within the current phase (0.0 to 1.0)
-    last_gate_value: f32,     // Previous gate value to detect changes
-    smoothing_counter: usize, // Remaining samples for attack smoothing
-    pre_attack_value: f32,    // Value before attack started (for smoothing)
+    value: f32,                  // Current output value
+    release_level: f32,          // Value when release phase started
+    position: f32,               // Position within the current phase (0.0 to 1.0)
+    last_gate_value: f32,        // Previous gate value to detect changes
+    smoothing_counter: usize,    // Remaining samples for attack smoothing
+    pre_attack_value: f32,       // Value before attack started (for smoothing)
+    note_velocity: f32,          // Latest GlobalVelocity input (1.0 when unconnected)
+    note_frequency: f32,         // Latest GlobalFrequency input (reference when unconnected)
+    attack_scale: f32,           // Velocity scaling of the attack, latched on each trigger
+    decay_scale: f32,            // Velocity and keytrack scaling of the decay
+    release_scale: f32,          // Keytrack scaling of the release
+    release_velocity: f32,       // Latest ReleaseVelocity input (default when unconnected)
+    release_velocity_scale: f32, // Release velocity scaling, latched on each release
+    time_spread: f32,            // This voice's analog spread factor on all stage times
+    drone_held: bool,            // Gate is off but drone mode is holding the sustain
 
     // Configuration & Timing
     sample_rate: f32,
@@ -132,6 +188,15 @@ impl Envelope {
             last_gate_value: 0.0,
             smoothing_counter: 0,
             pre_attack_value: 0.0,
+            note_velocity: 1.0,
+            note_frequency: KEYTRACK_REFERENCE_HZ,
+            attack_scale: 1.0,
+            decay_scale: 1.0,
+            release_scale: 1.0,
+            release_velocity: DEFAULT_RELEASE_VELOCITY,
+            release_velocity_scale: 1.0,
+            time_spread: 1.0,
+            drone_held: false,
             sample_rate,
             sample_rate_recip: 1.0 / sample_rate,
             // Ensure minimum attack time on initial config
@@ -200,6 +265,100 @@ impl Envelope {
         self.update_lookup_tables();
     }
 
+    pub fn config(&self) -> &EnvelopeConfig {
+        &self.config
+    }
+
+    /// Sets the gate level the envelope triggers on. Bipolar sources such as
+    /// an LFO square swing between -1 and 1, so the default of 0.0 splits
+    /// them at their midpoint.
+    pub fn set_gate_threshold(&mut self, threshold: f32) {
+        self.config.gate_threshold = threshold;
+    }
+
+    pub fn gate_threshold(&self) -> f32 {
+        self.config.gate_threshold
+    }
+
+    /// Sets the velocity and keytrack amounts that scale the stage times.
+    pub fn set_time_scaling(
+        &mut self,
+        velocity_attack: f32,
+        velocity_decay: f32,
+        key_decay: f32,
+        key_release: f32,
+    ) {
+        self.config.velocity_attack = velocity_attack;
+        self.config.velocity_decay = velocity_decay;
+        self.config.key_decay = key_decay;
+        self.config.key_release = key_release;
+    }
+
+    /// Sets how much the note-off velocity scales the release time.
+    pub fn set_release_velocity_amount(&mut self, amount: f32) {
+        self.config.velocity_release = amount;
+    }
+
+    /// Release time multiplier for a note-off `velocity` (0..1) at `amount`.
+    /// The default release velocity leaves the time as it is.
+    pub fn release_velocity_scale(amount: f32, velocity: f32) -> f32 {
+        let slowness = (DEFAULT_RELEASE_VELOCITY - velocity.clamp(0.0, 1.0)) * 2.0;
+        (amount * slowness * RELEASE_VELOCITY_OCTAVES).exp2()
+    }
+
+    /// Turns drone mode on or off. Turning it off releases an envelope it is
+    /// currently holding.
+    pub fn set_drone(&mut self, drone: bool) {
+        self.config.drone = drone;
+        if !drone {
+            self.release_drone();
+        }
+    }
+
+    /// Whether drone mode is holding the envelope after its gate went off.
+    pub fn is_droning(&self) -> bool {
+        self.drone_held
+    }
+
+    /// Releases an envelope held by drone mode. Envelopes whose gate is still
+    /// on are left alone; they release normally at gate-off.
+    pub fn release_drone(&mut self) {
+        if self.drone_held {
+            self.drone_held = false;
+            self.start_release();
+        }
+    }
+
+    fn start_release(&mut self) {
+        // Only start release if not already idle (e.g., from very short note)
+        if self.phase != EnvelopePhase::Idle {
+            self.phase = EnvelopePhase::Release;
+            self.release_level = self.value; // Store current value to release from
+            self.position = 0.0; // Reset position for release phase
+            self.release_velocity_scale =
+                Self::release_velocity_scale(self.config.velocity_release, self.release_velocity);
+        }
+    }
+
+    /// Attack, decay and release time multipliers for a note, including this
+    /// voice's analog spread.
+    pub fn time_scales(&self, velocity: f32, frequency: f32) -> (f32, f32, f32) {
+        let softness = (1.0 - velocity.clamp(0.0, 1.0)) * VELOCITY_TIME_OCTAVES;
+        let octaves_above_reference = if frequency > 0.0 {
+            (frequency / KEYTRACK_REFERENCE_HZ).log2()
+        } else {
+            0.0
+        };
+        let velocity_scale = |amount: f32| (amount * softness).exp2();
+        let key_scale = |amount: f32| (-amount * octaves_above_reference).exp2();
+        let spread = self.time_spread;
+        (
+            velocity_scale(self.config.velocity_attack) * spread,
+            velocity_scale(self.config.velocity_decay) * key_scale(self.config.key_decay) * spread,
+            key_scale(self.config.key_release) * spread,
+        )
+    }
+
     pub fn get_phase(&self) -> EnvelopePhase {
         self.phase
     }
@@ -241,8 +400,9 @@ impl Envelope {
         match self.phase {
             EnvelopePhase::Attack => {
                 // Calculate modulated attack time for this sample
+                let attack_time = self.config.attack * self.attack_scale;
                 let modulated_attack_time =
-                    (self.config.attack + attack_mod_add).max(0.0001) * attack_mod_mul.max(0.0); // Ensure > 0
+                    (attack_time + attack_mod_add).max(0.0001) * attack_mod_mul.max(0.0); // Ensure > 0
 
                 // Calculate position increment based on modulated time
                 let pos_increment = if modulated_attack_time > 1e-9 {
@@ -280,7 +440,7 @@ impl Envelope {
                 }
             }
             EnvelopePhase::Decay => {
-                let decay_time = self.config.decay.max(0.0001); // Ensure > 0
+                let decay_time = (self.config.decay * self.decay_scale).max(0.0001); // Ensure > 0
                 self.position += increment / decay_time;
 
                 // Transition check
@@ -304,7 +464,10 @@ impl Envelope {
                 self.value
             }
             EnvelopePhase::Release => {
-                let release_time = self.config.release.max(0.0001); // Ensure > 0
+                // Ensure > 0
+                let release_time =
+                    (self.config.release * self.release_scale * self.release_velocity_scale)
+                        .max(0.0001);
                 self.position += increment / release_time;
 
                 // Transition check
@@ -338,6 +501,9 @@ impl Envelope {
             // console::log_1(&"Gate ON".into());
             // self.debug_logged_attack = false; // Reset debug flag
             self.pre_attack_value = self.value; // Store value for smoothing
+            self.drone_held = false;
+            (self.attack_scale, self.decay_scale, self.release_scale) =
+                self.time_scales(self.note_velocity, self.note_frequency);
             self.phase = EnvelopePhase::Attack;
             self.position = 0.0; // Reset position for attack phase
                                  // Start smoothing counter if enabled
@@ -350,29 +516,35 @@ impl Envelope {
         } else if !gate_on && self.last_gate_value > 0.0 {
             // Falling edge
             // console::log_1(&"Gate OFF".into());
-            // Only start release if not already idle (e.g., from very short note)
-            if self.phase != EnvelopePhase::Idle {
-                self.phase = EnvelopePhase::Release;
-                self.release_level = self.value; // Store current value to release from
-                self.position = 0.0; // Reset position for release phase
+            if self.config.drone {
+                // Hold the sustain until `release_drone` is called.
+                self.drone_held = self.phase != EnvelopePhase::Idle;
+            } else {
+                self.start_release();
             }
         }
         // Update last_gate_value (store > 0.0 as 1.0 for consistent check)
         self.last_gate_value = if gate_on { 1.0 } else { 0.0 };
     }
 
-    /// Generate a preview buffer of envelope values for visualization.
+    /// Generate a preview buffer of envelope values for visualization, for a
+    /// note of the given velocity (0.0 to 1.0) and frequency in Hz.
     /// NOTE: This still uses a separate simulation and doesn't use the new modulation infra.
     /// It's intended for offline preview, not real-time processing.
-    pub fn preview(&self, preview_duration: f32) -> Vec<f32> {
+    pub fn preview(&self, preview_duration: f32, velocity: f32, frequency: f32) -> Vec<f32> {
         let total_samples = (self.sample_rate * preview_duration).ceil() as usize;
         let mut preview_values = Vec::with_capacity(total_samples);
 
         // Use a temporary envelope instance for simulation
         let mut sim_env = Envelope::new(self.sample_rate, self.config.clone());
+        sim_env.note_velocity = velocity;
+        sim_env.note_frequency = frequency;
         sim_env.trigger(true); // Start with gate on
 
-        let hold_duration = self.config.attack + self.config.decay + 0.5; // Duration before gate off
+        // Duration before gate off
+        let hold_duration = self.config.attack * sim_env.attack_scale
+            + self.config.decay * sim_env.decay_scale
+            + 0.5;
 
         for i in 0..total_samples {
             let t = i as f32 * sim_env.sample_rate_recip;
@@ -396,9 +568,12 @@ impl ModulationProcessor for Envelope {}
 impl AudioNode for Envelope {
     fn get_ports(&self) -> FxHashMap<PortId, bool> {
         [
-            (PortId::CombinedGate, false), // Input for gate signal
-            (PortId::AttackMod, false),    // Input for attack time modulation
-            (PortId::AudioOutput0, true),  // Output envelope value
+            (PortId::CombinedGate, false),    // Input for gate signal
+            (PortId::AttackMod, false),       // Input for attack time modulation
+            (PortId::GlobalVelocity, false),  // Note velocity for time scaling
+            (PortId::GlobalFrequency, false), // Note frequency for keytracking
+            (PortId::ReleaseVelocity, false), // Note-off velocity for release scaling
+            (PortId::AudioOutput0, true),     // Output envelope value
         ]
         .iter()
         .cloned()
@@ -464,11 +639,37 @@ impl AudioNode for Envelope {
             self.scratch_attack_mult[..buffer_size].fill(1.0);
         }
 
-        // --- 3) Main Processing Loop (Sample by Sample) ---
+        // --- 3) Note Velocity, Frequency and Release Velocity for Time Scaling ---
+        // All are read on each sample so a trigger or release latches the current note.
+        let velocity_in = inputs
+            .get(&PortId::GlobalVelocity)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer);
+        let frequency_in = inputs
+            .get(&PortId::GlobalFrequency)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer);
+        let release_velocity_in = inputs
+            .get(&PortId::ReleaseVelocity)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer);
+
+        // --- 4) Main Processing Loop (Sample by Sample) ---
         // Envelope state is inherently sequential, so process sample-by-sample.
         for i in 0..buffer_size {
-            // Determine gate state for this sample (simple threshold)
-            let current_gate_on = self.gate_buffer[i] > 0.0; // Or use a small threshold like 0.01?
+            if let Some(&velocity) = velocity_in.and_then(|b| b.get(i)) {
+                self.note_velocity = velocity;
+            }
+            if let Some(&frequency) = frequency_in.and_then(|b| b.get(i)) {
+                self.note_frequency = frequency;
+            }
+            if let Some(&velocity) = release_velocity_in.and_then(|b| b.get(i)) {
+                self.release_velocity = velocity;
+            }
+
+            // Gate-like sources (keyboard gate, LFO square, sequencer gate) are
+            // all read against the same threshold; `trigger` picks out the edges.
+            let current_gate_on = self.gate_buffer[i] > self.config.gate_threshold;
 
             // Check for gate changes and trigger state transitions
             self.trigger(current_gate_on);
@@ -498,6 +699,7 @@ impl AudioNode for Envelope {
         self.last_gate_value = 0.0;
         self.smoothing_counter = 0;
         self.pre_attack_value = 0.0;
+        self.drone_held = false;
         // Scratch buffers get reset/overwritten at the start of process
     }
 
@@ -521,6 +723,18 @@ impl AudioNode for Envelope {
         }
     }
 
+    fn set_voice_variation(&mut self, variation: &VoiceVariation) {
+        self.time_spread = variation.time_ratio;
+    }
+
+    fn clone_node(&self) -> Option<Box<dyn AudioNode>> {
+        Some(Box::new(self.clone()))
+    }
+
+    fn output_range(&self) -> (f32, f32) {
+        (0.0, 1.0)
+    }
+
     fn name(&self) -> &'static str {
         "Envelope"
     }
@@ -548,6 +762,7 @@ mod tests {
             release_curve: 0.0,
             attack_smoothing_samples: 0,
             active: true,
+            ..Default::default()
         };
         Envelope::new(TEST_SAMPLE_RATE, config)
     }
@@ -775,6 +990,7 @@ mod tests {
             release_curve: 0.0,
             attack_smoothing_samples: 0,
             active: true,
+            ..Default::default()
         };
 
         let env = Envelope::new(TEST_SAMPLE_RATE, config);
@@ -795,6 +1011,7 @@ mod tests {
             release_curve: 0.0,
             attack_smoothing_samples: 0,
             active: true,
+            ..Default::default()
         };
 
         let mut env_exp = Envelope::new(TEST_SAMPLE_RATE, config_exp);
@@ -819,6 +1036,7 @@ mod tests {
             release_curve: 0.0,
             attack_smoothing_samples: 0,
             active: true,
+            ..Default::default()
         };
 
         let mut env_lin = Envelope::new(TEST_SAMPLE_RATE, config_lin);
@@ -951,6 +1169,7 @@ mod tests {
             release_curve: 0.5,
             attack_smoothing_samples: 10,
             active: true,
+            ..Default::default()
         };
 
         env.update_config(new_config.clone());
@@ -961,4 +1180,126 @@ mod tests {
         // Attack should be enforced to minimum
         assert!(env.config.attack >= 0.001);
     }
+
+    #[test]
+    fn gate_sources_retrigger_on_each_crossing_of_the_threshold() {
+        use crate::graph::{ModulationTransformation, ModulationType};
+
+        let mut env = create_test_envelope();
+        env.set_gate_threshold(0.5);
+
+        // An LFO square swinging between -1 and 1, with a level that is
+        // positive but under the threshold in between.
+        let mut phases = Vec::new();
+        for level in [-1.0, 1.0, -1.0, 0.3, 1.0] {
+            let gate = [level; 16];
+            let mut inputs = FxHashMap::default();
+            inputs.insert(
+                PortId::CombinedGate,
+                vec![ModulationSource {
+                    buffer: &gate,
+                    amount: 1.0,
+                    mod_type: ModulationType::Additive,
+                    transformation: ModulationTransformation::None,
+                }],
+            );
+            let mut output = [0.0; 16];
+            let mut outputs = FxHashMap::default();
+            outputs.insert(PortId::AudioOutput0, &mut output[..]);
+            env.process(&inputs, &mut outputs, 16);
+            phases.push(env.get_phase());
+        }
+
+        assert_eq!(
+            phases,
+            vec![
+                EnvelopePhase::Idle,
+                EnvelopePhase::Attack,
+                EnvelopePhase::Release,
+                EnvelopePhase::Release,
+                EnvelopePhase::Attack,
+            ]
+        );
+    }
+
+    #[test]
+    fn velocity_and_keytrack_scale_stage_times() {
+        let mut env = create_test_envelope();
+        env.set_time_scaling(1.0, 0.0, 1.0, -1.0);
+
+        let (attack, decay, release) = env.time_scales(1.0, KEYTRACK_REFERENCE_HZ);
+        assert_eq!((attack, decay, release), (1.0, 1.0, 1.0));
+
+        // Half velocity, one octave up: slower attack, shorter decay, longer release.
+        let (attack, decay, release) = env.time_scales(0.5, KEYTRACK_REFERENCE_HZ * 2.0);
+        assert!((attack - 2.0_f32.powf(1.5)).abs() < 1e-4);
+        assert!((decay - 0.5).abs() < 1e-4);
+        assert!((release - 2.0).abs() < 1e-4);
+
+        // The preview reflects the note: a soft hit takes longer to peak.
+        let peak_index = |values: Vec<f32>| values.iter().position(|&v| v >= 1.0).unwrap();
+        let hard = peak_index(env.preview(1.0, 1.0, KEYTRACK_REFERENCE_HZ));
+        let soft = peak_index(env.preview(1.0, 0.0, KEYTRACK_REFERENCE_HZ));
+        assert!(soft > hard * 7, "hard {} soft {}", hard, soft);
+    }
+
+    #[test]
+    fn drone_mode_holds_sustain_until_released() {
+        let mut env = create_test_envelope();
+        env.set_drone(true);
+
+        env.trigger(true);
+        for _ in 0..(0.3 * TEST_SAMPLE_RATE) as usize {
+            env.process_sample(0.0, 1.0);
+        }
+        assert_eq!(env.phase, EnvelopePhase::Sustain);
+
+        env.trigger(false);
+        assert!(env.is_droning());
+        for _ in 0..TEST_SAMPLE_RATE as usize {
+            env.process_sample(0.0, 1.0);
+        }
+        assert_eq!(env.phase, EnvelopePhase::Sustain);
+
+        env.release_drone();
+        assert!(!env.is_droning());
+        assert_eq!(env.phase, EnvelopePhase::Release);
+    }
+
+    #[test]
+    fn release_velocity_scales_the_release_time() {
+        let release_samples = |amount: f32, velocity: f32| {
+            let mut env = create_test_envelope();
+            env.set_release_velocity_amount(amount);
+            env.release_velocity = velocity;
+            env.trigger(true);
+            for _ in 0..(0.3 * TEST_SAMPLE_RATE) as usize {
+                env.process_sample(0.0, 1.0);
+            }
+            env.trigger(false);
+            let mut samples = 0;
+            while env.phase == EnvelopePhase::Release {
+                env.process_sample(0.0, 1.0);
+                samples += 1;
+            }
+            samples
+        };
+        let plain = release_samples(0.0, 0.0);
+        assert_eq!(release_samples(1.0, DEFAULT_RELEASE_VELOCITY), plain);
+        // A slow key lift at full amount releases 4x longer, a fast one 4x shorter.
+        let slow = release_samples(1.0, 0.0);
+        let fast = release_samples(1.0, 1.0);
+        assert!(
+            (slow as f32 / plain as f32 - 4.0).abs() < 0.01,
+            "{} {}",
+            slow,
+            plain
+        );
+        assert!(
+            (plain as f32 / fast as f32 - 4.0).abs() < 0.01,
+            "{} {}",
+            fast,
+            plain
+        );
+    }
 }
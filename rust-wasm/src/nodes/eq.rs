@@ -1,320 +1,555 @@
-// use std::any::Any;
-// use std::collections::FxHashMap;
-
-// use crate::biquad::{Biquad, CascadedBiquad, Filter, FilterType};
-// use crate::graph::ModulationSource;
-// use crate::traits::{AudioNode, PortId};
-
-// /// Choose between 12 dB/octave (single biquad) and 24 dB/octave (cascaded biquad) slopes.
-// pub enum FilterOrder {
-//     TwelveDb,
-//     TwentyFourDb,
-// }
-
-// /// Helper to create a filter with the specified type, order, and parameters.
-// fn create_filter(
-//     filter_type: FilterType,
-//     order: FilterOrder,
-//     sample_rate: f32,
-//     frequency: f32,
-//     Q: f32,
-//     gain_db: f32,
-// ) -> Box<dyn Filter> {
-//     match order {
-//         FilterOrder::TwelveDb => {
-//             Box::new(Biquad::new(filter_type, sample_rate, frequency, Q, gain_db))
-//         }
-//         FilterOrder::TwentyFourDb => Box::new(CascadedBiquad::new(
-//             filter_type,
-//             sample_rate,
-//             frequency,
-//             Q,
-//             gain_db,
-//         )),
-//     }
-// }
-
-// /// A real three-band equalizer node that processes stereo audio.
-// /// This EQ cascades a low-shelf, a peaking, and a high-shelf filter per channel.
-// pub struct Equalizer {
-//     enabled: bool,
-//     sample_rate: f32,
-//     // Left channel filters.
-//     low_left: Box<dyn Filter>,
-//     mid_left: Box<dyn Filter>,
-//     high_left: Box<dyn Filter>,
-//     // Right channel filters.
-//     low_right: Box<dyn Filter>,
-//     mid_right: Box<dyn Filter>,
-//     high_right: Box<dyn Filter>,
-// }
-
-// impl Equalizer {
-//     /// Creates a new three-band EQ node.
-//     ///
-//     /// * `sample_rate` - The audio sample rate.
-//     /// * `low_gain_db`, `mid_gain_db`, `high_gain_db` - Gains (in dB) for each band.
-//     /// * `order` - Select 12 dB/octave (TwelveDb) or 24 dB/octave (TwentyFourDb) slopes.
-//     pub fn new(
-//         sample_rate: f32,
-//         low_gain_db: f32,
-//         mid_gain_db: f32,
-//         high_gain_db: f32,
-//         order: FilterOrder,
-//     ) -> Self {
-//         // Default center frequencies and Q factors.
-//         let low_freq = 200.0;
-//         let mid_freq = 1000.0;
-//         let high_freq = 5000.0;
-//         let shelf_Q = 0.707;
-//         let mid_Q = 1.0;
-
-//         let low_left = create_filter(
-//             FilterType::LowShelf,
-//             order,
-//             sample_rate,
-//             low_freq,
-//             shelf_Q,
-//             low_gain_db,
-//         );
-//         let mid_left = create_filter(
-//             FilterType::Peaking,
-//             order,
-//             sample_rate,
-//             mid_freq,
-//             mid_Q,
-//             mid_gain_db,
-//         );
-//         let high_left = create_filter(
-//             FilterType::HighShelf,
-//             order,
-//             sample_rate,
-//             high_freq,
-//             shelf_Q,
-//             high_gain_db,
-//         );
-
-//         let low_right = create_filter(
-//             FilterType::LowShelf,
-//             order,
-//             sample_rate,
-//             low_freq,
-//             shelf_Q,
-//             low_gain_db,
-//         );
-//         let mid_right = create_filter(
-//             FilterType::Peaking,
-//             order,
-//             sample_rate,
-//             mid_freq,
-//             mid_Q,
-//             mid_gain_db,
-//         );
-//         let high_right = create_filter(
-//             FilterType::HighShelf,
-//             order,
-//             sample_rate,
-//             high_freq,
-//             shelf_Q,
-//             high_gain_db,
-//         );
-
-//         Self {
-//             enabled: true,
-//             sample_rate,
-//             low_left,
-//             mid_left,
-//             high_left,
-//             low_right,
-//             mid_right,
-//             high_right,
-//         }
-//     }
-
-//     /// Resets all filter states.
-//     pub fn reset_filters(&mut self) {
-//         self.low_left.reset();
-//         self.mid_left.reset();
-//         self.high_left.reset();
-//         self.low_right.reset();
-//         self.mid_right.reset();
-//         self.high_right.reset();
-//     }
-// }
-
-// impl AudioNode for Equalizer {
-//     fn get_ports(&self) -> FxHashMap<PortId, bool> {
-//         let mut ports = FxHashMap::new();
-//         // Stereo audio inputs.
-//         ports.insert(PortId::AudioInput0, false);
-//         ports.insert(PortId::AudioInput1, false);
-//         // Stereo audio outputs.
-//         ports.insert(PortId::AudioOutput0, true);
-//         ports.insert(PortId::AudioOutput1, true);
-//         ports
-//     }
-
-//     fn process<'a>(
-//         &mut self,
-//         inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
-//         outputs: &mut FxHashMap<PortId, &mut [f32]>,
-//         buffer_size: usize,
-//     ) {
-//         // Retrieve input buffers.
-//         let left_in = inputs.get(&PortId::AudioInput0).unwrap()[0]
-//             .buffer
-//             .as_slice();
-//         let right_in = inputs.get(&PortId::AudioInput1).unwrap()[0]
-//             .buffer
-//             .as_slice();
-//         // Retrieve output buffers using nightly's get_many_mut.
-//         let outs = outputs.get_many_mut([&PortId::AudioOutput0, &PortId::AudioOutput1]);
-//         let [Some(out_left), Some(out_right)] = outs else {
-//             panic!("Missing stereo output buffers");
-//         };
-//         let out_left: &mut [f32] = *out_left;
-//         let out_right: &mut [f32] = *out_right;
-
-//         // Process each sample through the cascaded filters.
-//         for i in 0..buffer_size {
-//             // Left channel.
-//             let sample_left = left_in[i];
-//             let low_out_left = self.low_left.process(sample_left);
-//             let mid_out_left = self.mid_left.process(low_out_left);
-//             let eq_sample_left = self.high_left.process(mid_out_left);
-//             // Right channel.
-//             let sample_right = right_in[i];
-//             let low_out_right = self.low_right.process(sample_right);
-//             let mid_out_right = self.mid_right.process(low_out_right);
-//             let eq_sample_right = self.high_right.process(mid_out_right);
-
-//             out_left[i] = eq_sample_left;
-//             out_right[i] = eq_sample_right;
-//         }
-//     }
-
-//     fn reset(&mut self) {
-//         self.reset_filters();
-//     }
-
-//     fn as_any_mut(&mut self) -> &mut dyn Any {
-//         self
-//     }
-
-//     fn as_any(&self) -> &dyn Any {
-//         self
-//     }
-
-//     fn is_active(&self) -> bool {
-//         self.enabled
-//     }
-
-//     fn set_active(&mut self, active: bool) {
-//         self.enabled = active;
-//         if active {
-//             self.reset();
-//         }
-//     }
-
-//     fn node_type(&self) -> &str {
-//         "equalizer"
-//     }
-// }
-
-// /// A notch filter node implemented using a biquad (or cascaded biquad) with FilterType::Notch.
-// pub struct NotchFilter {
-//     enabled: bool,
-//     sample_rate: f32,
-//     // Left and right channel filters.
-//     left: Box<dyn Filter>,
-//     right: Box<dyn Filter>,
-// }
-
-// impl NotchFilter {
-//     /// Creates a new notch filter node.
-//     ///
-//     /// * `sample_rate` - The audio sample rate.
-//     /// * `notch_freq` - The center frequency to notch out.
-//     /// * `Q` - The quality factor of the notch.
-//     /// * `order` - Choose between 12 dB/octave (TwelveDb) or 24 dB/octave (TwentyFourDb).
-//     pub fn new(sample_rate: f32, notch_freq: f32, Q: f32, order: FilterOrder) -> Self {
-//         let left = create_filter(FilterType::Notch, order, sample_rate, notch_freq, Q, 0.0);
-//         let right = create_filter(FilterType::Notch, order, sample_rate, notch_freq, Q, 0.0);
-//         Self {
-//             enabled: true,
-//             sample_rate,
-//             left,
-//             right,
-//         }
-//     }
-
-//     pub fn reset_filters(&mut self) {
-//         self.left.reset();
-//         self.right.reset();
-//     }
-// }
-
-// impl AudioNode for NotchFilter {
-//     fn get_ports(&self) -> FxHashMap<PortId, bool> {
-//         let mut ports = FxHashMap::new();
-//         ports.insert(PortId::AudioInput0, false);
-//         ports.insert(PortId::AudioInput1, false);
-//         ports.insert(PortId::AudioOutput0, true);
-//         ports.insert(PortId::AudioOutput1, true);
-//         ports
-//     }
-
-//     fn process<'a>(
-//         &mut self,
-//         inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
-//         outputs: &mut FxHashMap<PortId, &mut [f32]>,
-//         buffer_size: usize,
-//     ) {
-//         let left_in = inputs.get(&PortId::AudioInput0).unwrap()[0]
-//             .buffer
-//             .as_slice();
-//         let right_in = inputs.get(&PortId::AudioInput1).unwrap()[0]
-//             .buffer
-//             .as_slice();
-//         let outs = outputs.get_many_mut([&PortId::AudioOutput0, &PortId::AudioOutput1]);
-//         let [Some(out_left), Some(out_right)] = outs else {
-//             panic!("Missing stereo output buffers");
-//         };
-//         let out_left: &mut [f32] = *out_left;
-//         let out_right: &mut [f32] = *out_right;
-
-//         for i in 0..buffer_size {
-//             let processed_left = self.left.process(left_in[i]);
-//             let processed_right = self.right.process(right_in[i]);
-//             out_left[i] = processed_left;
-//             out_right[i] = processed_right;
-//         }
-//     }
-
-//     fn reset(&mut self) {
-//         self.reset_filters();
-//     }
-
-//     fn as_any_mut(&mut self) -> &mut dyn Any {
-//         self
-//     }
-
-//     fn as_any(&self) -> &dyn Any {
-//         self
-//     }
-
-//     fn is_active(&self) -> bool {
-//         self.enabled
-//     }
-
-//     fn set_active(&mut self, active: bool) {
-//         self.enabled = active;
-//         if active {
-//             self.reset();
-//         }
-//     }
-
-//     fn node_type(&self) -> &str {
-//         "notch_filter"
-//     }
-// }
+use std::any::Any;
+
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::biquad::{Biquad, FilterType};
+use crate::graph::ModulationSource;
+use crate::traits::{AudioNode, PortId};
+
+pub const MAX_EQ_BANDS: usize = 16;
+const MIN_FREQ_HZ: f32 = 20.0;
+const MAX_FREQ_HZ: f32 = 20_000.0;
+/// Range the bands of `spread_bands` are laid out over.
+const SPREAD_LOW_HZ: f32 = 100.0;
+const SPREAD_HIGH_HZ: f32 = 8_000.0;
+/// Samples between coefficient updates of bands in dynamic mode.
+const DYNAMICS_INTERVAL: usize = 32;
+/// Smallest change in dynamic gain (dB) worth recomputing coefficients for.
+const DYNAMICS_STEP_DB: f32 = 0.05;
+
+/// Shape of one EQ band. Cuts and the notch ignore the band gain.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EqBandType {
+    #[default]
+    Peak = 0,
+    LowShelf = 1,
+    HighShelf = 2,
+    /// 12 dB/oct high-pass.
+    LowCut = 3,
+    /// 12 dB/oct low-pass.
+    HighCut = 4,
+    Notch = 5,
+    BandPass = 6,
+}
+
+impl EqBandType {
+    fn filter_type(self) -> FilterType {
+        match self {
+            EqBandType::Peak => FilterType::Peaking,
+            EqBandType::LowShelf => FilterType::LowShelf,
+            EqBandType::HighShelf => FilterType::HighShelf,
+            EqBandType::LowCut => FilterType::HighPass,
+            EqBandType::HighCut => FilterType::LowPass,
+            EqBandType::Notch => FilterType::Notch,
+            EqBandType::BandPass => FilterType::BandPass,
+        }
+    }
+
+    /// Filter the level detector of a dynamic band listens through, or
+    /// `None` for shapes without a gain to act on.
+    fn detector_type(self) -> Option<FilterType> {
+        match self {
+            EqBandType::Peak => Some(FilterType::BandPass),
+            EqBandType::LowShelf => Some(FilterType::LowPass),
+            EqBandType::HighShelf => Some(FilterType::HighPass),
+            _ => None,
+        }
+    }
+}
+
+/// Dynamic mode of a band: once the signal in the band's range rises above
+/// `threshold_db`, the band gain drops as a compressor with `ratio` would
+/// turn it down, so a resonance that builds up is cut only while it rings.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EqDynamics {
+    /// Level in dBFS, measured through the band's own range.
+    pub threshold_db: f32,
+    pub ratio: f32,
+    pub attack_ms: f32,
+    pub release_ms: f32,
+}
+
+impl Default for EqDynamics {
+    fn default() -> Self {
+        Self {
+            threshold_db: -18.0,
+            ratio: 4.0,
+            attack_ms: 5.0,
+            release_ms: 100.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EqBand {
+    pub band_type: EqBandType,
+    /// Centre or corner frequency in Hz.
+    pub frequency: f32,
+    pub q: f32,
+    pub gain_db: f32,
+    /// Dynamic mode; only peaks and shelves respond to it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dynamics: Option<EqDynamics>,
+}
+
+impl Default for EqBand {
+    /// A flat peak at 1 kHz.
+    fn default() -> Self {
+        Self {
+            band_type: EqBandType::Peak,
+            frequency: 1_000.0,
+            q: 1.0,
+            gain_db: 0.0,
+            dynamics: None,
+        }
+    }
+}
+
+/// Level detector of a band in dynamic mode.
+#[derive(Clone, Copy)]
+struct BandDetector {
+    filter: Biquad,
+    envelope: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    threshold_db: f32,
+    /// Share of the level above the threshold that is taken off the gain.
+    slope: f32,
+    /// Gain reduction (dB) the band's filters were last computed with.
+    applied_db: f32,
+}
+
+impl BandDetector {
+    /// Gain reduction in dB for the current envelope.
+    fn reduction_db(&self) -> f32 {
+        let level_db = 20.0 * self.envelope.max(1e-6).log10();
+        (level_db - self.threshold_db).max(0.0) * self.slope
+    }
+}
+
+/// Parametric equalizer: up to `MAX_EQ_BANDS` biquad bands in series per
+/// channel, each with its own shape, frequency, Q and gain. Peaks and
+/// shelves can run in dynamic mode (see `EqDynamics`).
+pub struct Equalizer {
+    enabled: bool,
+    sample_rate: f32,
+    bands: Vec<EqBand>,
+    /// Left and right filter of each band.
+    filters: Vec<[Biquad; 2]>,
+    /// Level detector of each band in dynamic mode.
+    detectors: Vec<Option<BandDetector>>,
+    /// Samples left until dynamic bands update their coefficients.
+    dynamics_countdown: usize,
+}
+
+impl Equalizer {
+    pub fn new(sample_rate: f32, bands: &[EqBand]) -> Self {
+        let mut eq = Self {
+            enabled: true,
+            sample_rate,
+            bands: Vec::new(),
+            filters: Vec::new(),
+            detectors: Vec::new(),
+            dynamics_countdown: 0,
+        };
+        eq.set_bands(bands);
+        eq
+    }
+
+    /// `count` flat bands spread log-evenly from 100 Hz to 8 kHz: a low
+    /// shelf, peaks, and a high shelf at the top.
+    pub fn spread_bands(count: usize) -> Vec<EqBand> {
+        let count = count.min(MAX_EQ_BANDS);
+        (0..count)
+            .map(|index| {
+                let position = if count > 1 {
+                    index as f32 / (count - 1) as f32
+                } else {
+                    0.5
+                };
+                let band_type = match index {
+                    0 if count > 1 => EqBandType::LowShelf,
+                    i if count > 1 && i == count - 1 => EqBandType::HighShelf,
+                    _ => EqBandType::Peak,
+                };
+                EqBand {
+                    band_type,
+                    frequency: SPREAD_LOW_HZ * (SPREAD_HIGH_HZ / SPREAD_LOW_HZ).powf(position),
+                    q: if band_type == EqBandType::Peak {
+                        1.0
+                    } else {
+                        0.707
+                    },
+                    gain_db: 0.0,
+                    dynamics: None,
+                }
+            })
+            .collect()
+    }
+
+    fn biquad(&self, band: &EqBand) -> Biquad {
+        Biquad::new(
+            band.band_type.filter_type(),
+            self.sample_rate,
+            band.frequency,
+            band.q,
+            band.gain_db,
+        )
+    }
+
+    /// Detector for `band`, or `None` when it is not in dynamic mode.
+    /// `envelope` carries the level over from the band's previous detector.
+    fn detector(&self, band: &EqBand, envelope: f32) -> Option<BandDetector> {
+        let dynamics = band.dynamics?;
+        let detector_type = band.band_type.detector_type()?;
+        let coeff = |ms: f32| (-1.0 / (ms.max(0.1) * 0.001 * self.sample_rate)).exp();
+        Some(BandDetector {
+            filter: Biquad::new(detector_type, self.sample_rate, band.frequency, band.q, 0.0),
+            envelope,
+            attack_coeff: coeff(dynamics.attack_ms),
+            release_coeff: coeff(dynamics.release_ms),
+            threshold_db: dynamics.threshold_db,
+            slope: 1.0 - 1.0 / dynamics.ratio.max(1.0),
+            applied_db: 0.0,
+        })
+    }
+
+    /// Replaces every band, at most `MAX_EQ_BANDS`. Bands that are kept
+    /// carry on from their filter state.
+    pub fn set_bands(&mut self, bands: &[EqBand]) {
+        let bands = &bands[..bands.len().min(MAX_EQ_BANDS)];
+        self.filters.truncate(bands.len());
+        self.detectors.truncate(bands.len());
+        self.bands.clear();
+        for (index, band) in bands.iter().enumerate() {
+            self.bands.push(*band);
+            if index < self.filters.len() {
+                self.update_filters(index);
+            } else {
+                let filter = self.biquad(band);
+                self.filters.push([filter; 2]);
+                self.detectors.push(self.detector(band, 0.0));
+            }
+        }
+    }
+
+    /// Grows or shrinks the EQ to `count` bands; new ones are flat peaks.
+    pub fn set_band_count(&mut self, count: usize) {
+        let mut bands = self.bands.clone();
+        bands.resize(count.min(MAX_EQ_BANDS), EqBand::default());
+        self.set_bands(&bands);
+    }
+
+    pub fn band_count(&self) -> usize {
+        self.bands.len()
+    }
+
+    pub fn bands(&self) -> &[EqBand] {
+        &self.bands
+    }
+
+    /// Sets one band. Returns false when there is no band `index`.
+    pub fn set_band(&mut self, index: usize, band: EqBand) -> bool {
+        let Some(slot) = self.bands.get_mut(index) else {
+            return false;
+        };
+        *slot = band;
+        self.update_filters(index);
+        true
+    }
+
+    /// Turns dynamic mode of one band on (`Some`) or off. Returns false
+    /// when there is no band `index`.
+    pub fn set_band_dynamics(&mut self, index: usize, dynamics: Option<EqDynamics>) -> bool {
+        let Some(slot) = self.bands.get_mut(index) else {
+            return false;
+        };
+        slot.dynamics = dynamics;
+        self.update_filters(index);
+        true
+    }
+
+    fn update_filters(&mut self, index: usize) {
+        let band = self.bands[index];
+        let envelope = self.detectors[index].map_or(0.0, |detector| detector.envelope);
+        self.detectors[index] = self.detector(&band, envelope);
+        for filter in &mut self.filters[index] {
+            filter.filter_type = band.band_type.filter_type();
+            filter.frequency = band.frequency.clamp(10.0, self.sample_rate * 0.4999);
+            filter.q = band.q.max(0.01);
+            filter.gain_db = band.gain_db;
+            filter.update_coefficients();
+        }
+    }
+
+    /// Magnitude response in dB of all bands together, at `points`
+    /// frequencies spaced logarithmically from 20 Hz to 20 kHz (or Nyquist).
+    /// Computed from the filter coefficients, so it is exact and cheap
+    /// enough to redraw while a band is dragged.
+    pub fn response_db(&self, points: usize) -> Vec<f32> {
+        let max_freq = MAX_FREQ_HZ.min(self.sample_rate * 0.5);
+        (0..points)
+            .map(|i| {
+                let position = if points > 1 {
+                    i as f32 / (points - 1) as f32
+                } else {
+                    0.0
+                };
+                let freq = MIN_FREQ_HZ * (max_freq / MIN_FREQ_HZ).powf(position);
+                self.filters
+                    .iter()
+                    .map(|[filter, _]| filter.magnitude_db(freq))
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Moves the gain of each dynamic band to follow its detector.
+    fn update_dynamic_gains(&mut self) {
+        for ((band, filters), detector) in self
+            .bands
+            .iter()
+            .zip(&mut self.filters)
+            .zip(&mut self.detectors)
+        {
+            let Some(detector) = detector else {
+                continue;
+            };
+            let reduction = detector.reduction_db();
+            if (reduction - detector.applied_db).abs() < DYNAMICS_STEP_DB {
+                continue;
+            }
+            detector.applied_db = reduction;
+            for filter in filters {
+                filter.gain_db = band.gain_db - reduction;
+                filter.update_coefficients();
+            }
+        }
+    }
+}
+
+impl AudioNode for Equalizer {
+    fn get_ports(&self) -> FxHashMap<PortId, bool> {
+        let mut ports = FxHashMap::default();
+        ports.insert(PortId::AudioInput0, false); // Left (or mono) input
+        ports.insert(PortId::AudioInput1, false); // Optional right input
+        ports.insert(PortId::AudioOutput0, true); // Left output
+        ports.insert(PortId::AudioOutput1, true); // Right output
+        ports
+    }
+
+    fn process<'a>(
+        &mut self,
+        inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+        outputs: &mut FxHashMap<PortId, &mut [f32]>,
+        buffer_size: usize,
+    ) {
+        let left_in = inputs
+            .get(&PortId::AudioInput0)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer);
+        let right_in = inputs
+            .get(&PortId::AudioInput1)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer)
+            .or(left_in);
+
+        let outs = outputs.get_disjoint_mut([&PortId::AudioOutput0, &PortId::AudioOutput1]);
+        let [Some(out_left), Some(out_right)] = outs else {
+            panic!("Missing stereo output buffers");
+        };
+        let out_left: &mut [f32] = out_left;
+        let out_right: &mut [f32] = out_right;
+
+        for i in 0..buffer_size {
+            if self.dynamics_countdown == 0 {
+                self.dynamics_countdown = DYNAMICS_INTERVAL;
+                self.update_dynamic_gains();
+            }
+            self.dynamics_countdown -= 1;
+
+            let mut left = left_in.and_then(|b| b.get(i)).copied().unwrap_or(0.0);
+            let mut right = right_in.and_then(|b| b.get(i)).copied().unwrap_or(0.0);
+            for ([left_filter, right_filter], detector) in
+                self.filters.iter_mut().zip(&mut self.detectors)
+            {
+                if let Some(detector) = detector {
+                    let level = detector.filter.process(0.5 * (left + right)).abs();
+                    let coeff = if level > detector.envelope {
+                        detector.attack_coeff
+                    } else {
+                        detector.release_coeff
+                    };
+                    detector.envelope = level + coeff * (detector.envelope - level);
+                }
+                left = left_filter.process(left);
+                right = right_filter.process(right);
+            }
+            out_left[i] = left;
+            out_right[i] = right;
+        }
+    }
+
+    fn reset(&mut self) {
+        for index in 0..self.filters.len() {
+            let filter = self.biquad(&self.bands[index]);
+            self.filters[index] = [filter; 2];
+            self.detectors[index] = self.detector(&self.bands[index], 0.0);
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn is_active(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_active(&mut self, active: bool) {
+        self.enabled = active;
+    }
+
+    fn name(&self) -> &'static str {
+        "Equalizer"
+    }
+
+    fn node_type(&self) -> &str {
+        "equalizer"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{ModulationTransformation, ModulationType};
+    use std::f32::consts::PI;
+
+    fn run(eq: &mut Equalizer, input: &[f32]) -> Vec<f32> {
+        let mut inputs = FxHashMap::default();
+        inputs.insert(
+            PortId::AudioInput0,
+            vec![ModulationSource {
+                buffer: input,
+                amount: 1.0,
+                mod_type: ModulationType::Additive,
+                transformation: ModulationTransformation::None,
+            }],
+        );
+        let mut left = vec![0.0; input.len()];
+        let mut right = vec![0.0; input.len()];
+        {
+            let mut outputs = FxHashMap::default();
+            outputs.insert(PortId::AudioOutput0, left.as_mut_slice());
+            outputs.insert(PortId::AudioOutput1, right.as_mut_slice());
+            eq.process(&inputs, &mut outputs, input.len());
+        }
+        left
+    }
+
+    #[test]
+    fn flat_bands_leave_the_signal_alone() {
+        let mut eq = Equalizer::new(48_000.0, &Equalizer::spread_bands(4));
+        assert_eq!(eq.band_count(), 4);
+        assert!(eq.response_db(64).iter().all(|db| db.abs() < 1e-3));
+
+        let input: Vec<f32> = (0..480).map(|n| (n as f32 * 0.37).sin()).collect();
+        let output = run(&mut eq, &input);
+        let max_diff = input
+            .iter()
+            .zip(&output)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0f32, f32::max);
+        assert!(max_diff < 1e-4, "max_diff = {}", max_diff);
+    }
+
+    #[test]
+    fn a_peak_band_boosts_its_centre_by_its_gain() {
+        let mut eq = Equalizer::new(48_000.0, &[]);
+        eq.set_band_count(2);
+        let boost = EqBand {
+            gain_db: 12.0,
+            ..EqBand::default()
+        };
+        assert!(eq.set_band(1, boost));
+        assert!(!eq.set_band(2, boost));
+
+        // The response runs 20 Hz to 20 kHz, so 1 kHz sits at log(50)/log(1000).
+        let points = 1001;
+        let index = ((50.0f32.ln() / 1000.0f32.ln()) * (points - 1) as f32).round() as usize;
+        let response = eq.response_db(points);
+        assert!((response[index] - 12.0).abs() < 0.1, "{}", response[index]);
+        assert!(response[0].abs() < 0.1);
+
+        // A 1 kHz sine comes out 12 dB (about 4x) louder.
+        let input: Vec<f32> = (0..9_600)
+            .map(|n| 0.1 * (2.0 * PI * 1_000.0 * n as f32 / 48_000.0).sin())
+            .collect();
+        let output = run(&mut eq, &input);
+        let peak = output[4_800..]
+            .iter()
+            .fold(0.0f32, |max, x| max.max(x.abs()));
+        assert!((peak / 0.1 - 3.98).abs() < 0.05, "peak = {}", peak);
+    }
+
+    #[test]
+    fn a_dynamic_band_only_cuts_while_its_range_is_loud() {
+        let sine = |amplitude: f32| -> Vec<f32> {
+            (0..24_000)
+                .map(|n| amplitude * (2.0 * PI * 1_000.0 * n as f32 / 48_000.0).sin())
+                .collect()
+        };
+        let tail_peak = |output: &[f32]| {
+            output[19_200..]
+                .iter()
+                .fold(0.0f32, |max, x| max.max(x.abs()))
+        };
+        let dynamic_band = EqBand {
+            dynamics: Some(EqDynamics {
+                threshold_db: -20.0,
+                ratio: 4.0,
+                ..EqDynamics::default()
+            }),
+            ..EqBand::default()
+        };
+
+        // A -40 dB tone stays below the threshold and passes untouched.
+        let mut eq = Equalizer::new(48_000.0, &[dynamic_band]);
+        let quiet = tail_peak(&run(&mut eq, &sine(0.01)));
+        assert!((quiet / 0.01 - 1.0).abs() < 0.02, "quiet = {}", quiet);
+
+        // A 0 dB tone is 20 dB over it: 4:1 takes 15 dB off the band, so it
+        // comes out as through a static -15 dB peak.
+        let mut eq = Equalizer::new(48_000.0, &[dynamic_band]);
+        let loud = tail_peak(&run(&mut eq, &sine(1.0)));
+        let cut = EqBand {
+            gain_db: -15.0,
+            ..EqBand::default()
+        };
+        let expected = tail_peak(&run(&mut Equalizer::new(48_000.0, &[cut]), &sine(1.0)));
+        let (loud_db, expected_db) = (20.0 * loud.log10(), 20.0 * expected.log10());
+        assert!(
+            (loud_db / expected_db - 1.0).abs() < 0.1,
+            "{} dB against {} dB",
+            loud_db,
+            expected_db
+        );
+
+        // Turning the mode off brings the band back to flat.
+        assert!(eq.set_band_dynamics(0, None));
+        let restored = tail_peak(&run(&mut eq, &sine(1.0)));
+        assert!((restored - 1.0).abs() < 0.02, "restored = {}", restored);
+    }
+}
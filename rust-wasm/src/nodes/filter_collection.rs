@@ -7,14 +7,15 @@ use rustc_hash::FxHashMap;
 use rustfft::num_traits::Float;
 use rustfft::{num_complex::Complex, FftPlanner};
 use std::any::Any;
-use std::f32::consts::PI;
 #[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::wasm_bindgen;
 
 // Import necessary items from other modules (adjust paths as needed)
 use crate::biquad::{Biquad, CascadedBiquad, Filter, FilterType};
-use crate::graph::{ModulationProcessor, ModulationSource};
+use crate::graph::{ModulationProcessor, ModulationSource, ModulationTarget, TargetMapping};
 use crate::traits::{AudioNode, PortId};
+use crate::utils::analog_spread::VoiceVariation;
+use crate::utils::smoothing::smoothing_coefficient;
 use serde::de::{self, Visitor};
 use serde::{Deserialize, Deserializer, Serialize};
 use std::fmt;
@@ -142,6 +143,78 @@ fn fast_tanh(x: f32) -> f32 {
 // --- End Tanh LUT ---
 
 const SAFE_NYQUIST_FACTOR: f32 = 0.49;
+// Cutoff/resonance glide; fast enough for envelopes, slow enough to hide knob steps.
+const FILTER_SMOOTHING_MS: f32 = 1.0;
+
+// --- Auto-Gain Calibration ---
+// Output level (dB, relative to the same model at zero resonance) measured with
+// pink noise through a 1 kHz cutoff, sampled at resonance 0.0, 0.125, ..., 1.0.
+// Auto-gain applies the inverse so each model stays near its zero-resonance level.
+const LOWPASS_12_LEVEL_DB: [f32; 9] = [0.0, 0.6, 1.5, 2.4, 3.3, 4.3, 5.1, 5.9, 6.7];
+const LOWPASS_24_LEVEL_DB: [f32; 9] = [0.0, 0.5, 1.5, 2.7, 3.9, 5.2, 6.5, 7.7, 8.8];
+const HIGHPASS_12_LEVEL_DB: [f32; 9] = [0.0, 1.1, 2.6, 3.9, 5.2, 6.4, 7.4, 8.4, 9.2];
+const HIGHPASS_24_LEVEL_DB: [f32; 9] = [0.0, 1.0, 2.5, 4.2, 5.9, 7.4, 8.9, 10.2, 11.4];
+const BANDPASS_12_LEVEL_DB: [f32; 9] = [0.0, -1.6, -3.8, -5.7, -7.3, -8.7, -9.8, -10.8, -11.7];
+const BANDPASS_24_LEVEL_DB: [f32; 9] = [0.0, -1.0, -2.3, -3.3, -4.1, -4.8, -5.4, -5.9, -6.3];
+const NOTCH_12_LEVEL_DB: [f32; 9] = [0.0, 0.4, 0.7, 0.8, 0.9, 0.9, 1.0, 1.0, 1.0];
+const NOTCH_24_LEVEL_DB: [f32; 9] = [0.0, 0.2, 0.4, 0.6, 0.7, 0.7, 0.8, 0.8, 0.9];
+const LADDER_LEVEL_DB: [f32; 9] = [0.0, -0.6, -1.7, -2.8, -3.6, -3.9, -2.7, 6.5, 10.3];
+const COMB_LEVEL_DB: [f32; 9] = [0.0, 0.0, 0.2, 0.4, 0.8, 1.4, 2.4, 4.1, 11.1];
+// Extra ladder level from drive 0.0, 0.5, ..., 4.0, measured at resonance 0.0, 0.5 and 1.0.
+const LADDER_DRIVE_LEVEL_DB: [[f32; 9]; 3] = [
+    [0.0, 13.1, 15.6, 16.1, 16.2, 16.2, 16.2, 16.2, 16.2],
+    [0.0, 13.6, 15.5, 15.9, 16.0, 16.0, 16.0, 16.0, 16.0],
+    [0.0, -0.3, 1.4, 1.8, 2.0, 2.0, 2.0, 2.0, 2.0],
+];
+const MAX_DRIVE: f32 = 4.0;
+const MAX_CUTOFF_MOD_OCTAVES: f32 = 10.0;
+
+/// Linearly interpolates a calibration table sampled evenly over `position` 0.0..=1.0.
+#[inline(always)]
+fn lookup_level_db(table: &[f32], position: f32) -> f32 {
+    let last = table.len() - 1;
+    let index_f = position.clamp(0.0, 1.0) * last as f32;
+    let index = (index_f as usize).min(last - 1);
+    let frac = index_f - index as f32;
+    table[index].mul_add(1.0 - frac, table[index + 1] * frac)
+}
+
+/// Returns the calibrated output level (dB) of a filter model at the given
+/// resonance and drive. Shelving and peaking models are level-neutral.
+fn model_level_db(
+    filter_type: FilterType,
+    slope: FilterSlope,
+    resonance_norm: f32,
+    drive: f32,
+) -> f32 {
+    let db24 = slope == FilterSlope::Db24;
+    let table: &[f32] = match filter_type {
+        FilterType::LowPass if db24 => &LOWPASS_24_LEVEL_DB,
+        FilterType::LowPass => &LOWPASS_12_LEVEL_DB,
+        FilterType::HighPass if db24 => &HIGHPASS_24_LEVEL_DB,
+        FilterType::HighPass => &HIGHPASS_12_LEVEL_DB,
+        FilterType::BandPass if db24 => &BANDPASS_24_LEVEL_DB,
+        FilterType::BandPass => &BANDPASS_12_LEVEL_DB,
+        FilterType::Notch if db24 => &NOTCH_24_LEVEL_DB,
+        FilterType::Notch => &NOTCH_12_LEVEL_DB,
+        FilterType::Ladder => &LADDER_LEVEL_DB,
+        FilterType::Comb => &COMB_LEVEL_DB,
+        _ => return 0.0,
+    };
+    let mut level_db = lookup_level_db(table, resonance_norm);
+
+    if filter_type == FilterType::Ladder && drive > 0.0 {
+        let drive_pos = drive / MAX_DRIVE;
+        let res_pos = resonance_norm.clamp(0.0, 1.0) * 2.0;
+        let row = (res_pos as usize).min(1);
+        let frac = res_pos - row as f32;
+        let low = lookup_level_db(&LADDER_DRIVE_LEVEL_DB[row], drive_pos);
+        let high = lookup_level_db(&LADDER_DRIVE_LEVEL_DB[row + 1], drive_pos);
+        level_db += low.mul_add(1.0 - frac, high * frac);
+    }
+    level_db
+}
+// --- End Auto-Gain Calibration ---
 
 static MAX_COMB_BUFFER_SIZE: Lazy<usize> = Lazy::new(|| {
     let sample_rate = 96000.0;
@@ -149,6 +222,309 @@ static MAX_COMB_BUFFER_SIZE: Lazy<usize> = Lazy::new(|| {
     (sample_rate / min_freq).ceil() as usize + 8
 });
 
+// --- Feedback Precision ---
+// The ladder and comb recirculate their own output, so rounding error, denormals
+// and limit cycles show up there first at extreme settings. Their state is generic
+// over the sample type so a filter can opt into f64; f32 remains the default.
+
+/// Sample type of the ladder/comb feedback paths.
+trait FeedbackSample: Float + Default {
+    fn widen(value: f32) -> Self;
+    fn cast(value: f64) -> Self;
+    fn narrow(self) -> f32;
+    fn wide(self) -> f64;
+    /// Feedback saturator: the tanh LUT in f32, exact tanh in f64.
+    fn saturate(self) -> Self;
+}
+
+impl FeedbackSample for f32 {
+    #[inline(always)]
+    fn widen(value: f32) -> Self {
+        value
+    }
+    #[inline(always)]
+    fn cast(value: f64) -> Self {
+        value as f32
+    }
+    #[inline(always)]
+    fn narrow(self) -> f32 {
+        self
+    }
+    #[inline(always)]
+    fn wide(self) -> f64 {
+        self as f64
+    }
+    #[inline(always)]
+    fn saturate(self) -> Self {
+        fast_tanh(self)
+    }
+}
+
+impl FeedbackSample for f64 {
+    #[inline(always)]
+    fn widen(value: f32) -> Self {
+        value as f64
+    }
+    #[inline(always)]
+    fn cast(value: f64) -> Self {
+        value
+    }
+    #[inline(always)]
+    fn narrow(self) -> f32 {
+        self as f32
+    }
+    #[inline(always)]
+    fn wide(self) -> f64 {
+        self
+    }
+    #[inline(always)]
+    fn saturate(self) -> Self {
+        self.tanh()
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct LadderState<T> {
+    stages: [T; 4],
+    /// Saturated output of the last stage, fed back on the next sample.
+    feedback: T,
+}
+
+impl<T: FeedbackSample> LadderState<T> {
+    fn converted<U: FeedbackSample>(&self) -> LadderState<U> {
+        LadderState {
+            stages: self.stages.map(|s| U::cast(s.wide())),
+            feedback: U::cast(self.feedback.wide()),
+        }
+    }
+
+    #[inline(always)]
+    fn process(
+        &mut self,
+        input: f32,
+        cutoff: f32,
+        resonance_norm: f32,
+        drive: f32,
+        res_comp: f32,
+        sample_rate: f32,
+    ) -> f32 {
+        let lit = T::cast;
+        let sample_rate = T::widen(sample_rate);
+
+        // --- Parameter Calculation ---
+        let effective_cutoff =
+            T::widen(cutoff).clamp(lit(10.0), sample_rate * T::widen(SAFE_NYQUIST_FACTOR));
+        let wc = lit(std::f64::consts::PI) * effective_cutoff / sample_rate;
+        let g = wc.tan(); // TPT pre-warping (FIXED: removed incorrect 0.5 factor)
+
+        // --- Resonance & Compensation (Authentic Moog Tuning) ---
+        let k_resonance = T::widen(resonance_norm).clamp(lit(0.0), lit(1.0));
+        // Moog resonance: 4x feedback at maximum, with exponential curve for musical response
+        // (slightly steeper curve for better control)
+        let k = k_resonance.powf(lit(1.8)) * lit(4.0);
+        // Compensation prevents volume loss at high resonance (authentic Moog behavior)
+        let comp_gain = (lit(1.0) + T::widen(res_comp) * k * lit(0.5)).max(lit(0.0));
+
+        // --- State & Feedback ---
+        let [s0, s1, s2, s3] = self.stages;
+
+        // Feedback comes from the previous sample's output
+        let feedback = k * self.feedback;
+
+        // --- Drive/Saturation (Authentic Moog Character) ---
+        // In authentic Moog: drive adds input stage overdrive and per-stage saturation
+        // At drive=0: nearly linear operation
+        // At drive>0: progressive harmonic distortion
+        let saturating = drive > 0.01;
+        let input_drive = lit(1.0) + T::widen(drive) * lit(2.0); // Input overdrive: 1.0 to 3.0
+        let stage_drive = lit(1.0) + T::widen(drive) * lit(1.5); // Per-stage saturation: 1.0 to 2.5
+
+        // --- Per-Stage Processing (TPT Trapezoidal Integrator with Saturation) ---
+        let g_inv = lit(1.0) / (lit(1.0) + g);
+
+        // Stage 0 - Input stage with feedback and overdrive
+        let input0 = T::widen(input) * comp_gain - feedback;
+        let input0_driven = input0 * input_drive;
+        let input0_sat = if saturating {
+            input0_driven.saturate()
+        } else {
+            input0_driven.clamp(lit(-2.0), lit(2.0)) // Soft clipping when no drive
+        };
+        let v0 = (s0 + g * input0_sat) * g_inv;
+        self.stages[0] = lit(2.0) * v0 - s0;
+
+        // Stages 1-3 - Mostly linear with optional saturation character
+        let stage_input = |v: T| {
+            if saturating {
+                (v * stage_drive).saturate()
+            } else {
+                v
+            }
+        };
+        let v1 = (s1 + g * stage_input(v0)) * g_inv;
+        self.stages[1] = lit(2.0) * v1 - s1;
+
+        let v2 = (s2 + g * stage_input(v1)) * g_inv;
+        self.stages[2] = lit(2.0) * v2 - s2;
+
+        let v3 = (s3 + g * stage_input(v2)) * g_inv;
+        self.stages[3] = lit(2.0) * v3 - s3;
+
+        // --- Store Output for Feedback (with soft saturation for authentic behavior) ---
+        // Authentic Moog uses moderately saturated feedback for self-oscillation at high resonance
+        // tanh(x * 1.2) provides gentle saturation while allowing self-oscillation
+        self.feedback = (v3 * lit(1.2)).saturate();
+
+        // --- Return Filter Output ---
+        v3.narrow()
+    }
+}
+
+#[derive(Clone, Default)]
+struct CombState<T> {
+    buffer: Vec<T>,
+    index: usize,
+    last_output: T,
+    dc_prev: T,
+    dc_state: T,
+}
+
+impl<T: FeedbackSample> CombState<T> {
+    fn with_len(len: usize) -> Self {
+        Self {
+            buffer: vec![T::zero(); len],
+            ..Self::default()
+        }
+    }
+
+    fn converted<U: FeedbackSample>(&self) -> CombState<U> {
+        CombState {
+            buffer: self.buffer.iter().map(|s| U::cast(s.wide())).collect(),
+            index: self.index,
+            last_output: U::cast(self.last_output.wide()),
+            dc_prev: U::cast(self.dc_prev.wide()),
+            dc_state: U::cast(self.dc_state.wide()),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.buffer.fill(T::zero());
+        self.index = 0;
+        self.last_output = T::zero();
+        self.dc_prev = T::zero();
+        self.dc_state = T::zero();
+    }
+
+    #[inline(always)]
+    fn process(
+        &mut self,
+        input: f32,
+        freq: f32,
+        resonance_norm: f32,
+        dampening: f32,
+        sample_rate: f32,
+    ) -> f32 {
+        let lit = T::cast;
+        let sample_rate = T::widen(sample_rate);
+
+        let clamped_freq =
+            T::widen(freq).clamp(lit(10.0), sample_rate * T::widen(SAFE_NYQUIST_FACTOR));
+        let delay_samples = (sample_rate / clamped_freq).max(lit(2.0));
+
+        let clamped_res = T::widen(resonance_norm).clamp(lit(0.0), lit(0.995));
+        let alpha = T::widen(dampening).clamp(lit(0.0), lit(1.0));
+
+        let delay_floor = delay_samples.floor();
+        let delay_frac = delay_samples - delay_floor;
+        let delay_int = delay_floor.wide() as usize;
+
+        let buf_len = self.buffer.len();
+        let read_idx0 = (self.index + buf_len - (delay_int % buf_len)) % buf_len;
+        let read_idx1 = (self.index + buf_len - ((delay_int + 1) % buf_len)) % buf_len;
+
+        let y0 = self.buffer[read_idx0];
+        let y1 = self.buffer[read_idx1];
+        // Manual lerp: y0 * (1.0 - delay_frac) + y1 * delay_frac
+        let delayed_sample = y0.mul_add(lit(1.0) - delay_frac, y1 * delay_frac);
+
+        // Dampening filter in feedback path
+        self.last_output = self.last_output * alpha + delayed_sample * (lit(1.0) - alpha); // Correct one-pole LPF
+        let feedback = self.last_output * clamped_res;
+        let buffer_write_val = T::widen(input) + feedback;
+
+        self.buffer[self.index] = buffer_write_val;
+        self.index = (self.index + 1) % buf_len;
+
+        let comb_output = buffer_write_val;
+
+        // DC blocker
+        let filtered_output = comb_output - self.dc_prev + lit(0.995) * self.dc_state;
+        self.dc_prev = comb_output;
+        self.dc_state = filtered_output;
+
+        filtered_output.narrow()
+    }
+}
+// --- End Feedback Precision ---
+
+/// Most oversampling `set_oversampling_factor` accepts.
+pub const MAX_FILTER_OVERSAMPLING: usize = 4;
+/// Section Qs of an 8th-order Butterworth low-pass.
+const BUTTERWORTH_8_Q: [f32; 4] = [0.5098, 0.6013, 0.9000, 2.5629];
+/// Band edge of the resampling filters, as a fraction of the base sample rate.
+const RESAMPLING_EDGE: f32 = 0.45;
+
+/// Zero-stuffing upsampler and decimator around the ladder/biquad core, with
+/// an 8th-order Butterworth low-pass just below the base Nyquist on each side.
+#[derive(Clone)]
+struct Oversampler {
+    factor: usize,
+    up: [Biquad; 4],
+    down: [Biquad; 4],
+}
+
+impl Oversampler {
+    fn new(factor: usize, sample_rate: f32) -> Self {
+        let rate = sample_rate * factor as f32;
+        let edge = sample_rate * RESAMPLING_EDGE;
+        let section = |q| Biquad::new(FilterType::LowPass, rate, edge, q, 0.0);
+        let sections = BUTTERWORTH_8_Q.map(section);
+        Self {
+            factor,
+            up: sections,
+            down: sections,
+        }
+    }
+
+    /// Spreads one input sample over `sub_samples`, one per oversampled step.
+    #[inline(always)]
+    fn upsample(&mut self, input: f32, sub_samples: &mut [f32]) {
+        for (k, sample) in sub_samples.iter_mut().enumerate() {
+            let gain = if k == 0 { self.factor as f32 } else { 0.0 };
+            *sample = self
+                .up
+                .iter_mut()
+                .fold(input * gain, |x, section| section.process(x));
+        }
+    }
+
+    /// Band-limits `sub_samples` and keeps the last one.
+    #[inline(always)]
+    fn downsample(&mut self, sub_samples: &[f32]) -> f32 {
+        sub_samples.iter().fold(0.0, |_, &sample| {
+            self.down
+                .iter_mut()
+                .fold(sample, |x, section| section.process(x))
+        })
+    }
+
+    fn reset(&mut self) {
+        for section in self.up.iter_mut().chain(self.down.iter_mut()) {
+            Filter::reset(section);
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct FilterCollection {
     sample_rate: f32,
@@ -157,6 +533,10 @@ pub struct FilterCollection {
     base_gain_db: f32,
     base_drive: f32,
     resonance_gain_compensation: f32,
+    auto_gain: bool,
+    cutoff_mod_octaves: f32,
+    /// This voice's analog spread factor on the cutoff.
+    cutoff_spread: f32,
     comb_base_frequency: f32,
     comb_dampening: f32,
     keyboard_tracking_sensitivity: f32,
@@ -170,14 +550,17 @@ pub struct FilterCollection {
 
     biquad: Biquad,
     cascaded: Option<CascadedBiquad>,
-    ladder_stages: [f32; 4],
-    ladder_outputs: [f32; 4],
 
-    comb_buffer: Vec<f32>,
-    comb_buffer_index: usize,
-    comb_last_output: f32,
-    comb_dc_prev: f32,
-    comb_dc_state: f32,
+    /// Run the ladder/comb feedback in f64; the f64 comb buffer is only
+    /// allocated while this is enabled.
+    double_precision_feedback: bool,
+    ladder: LadderState<f32>,
+    ladder_f64: LadderState<f64>,
+    comb: CombState<f32>,
+    comb_f64: CombState<f64>,
+    /// Runs the ladder and biquads at a multiple of the sample rate when set,
+    /// so audio-rate cutoff modulation and drive alias less.
+    oversampler: Option<Oversampler>,
 
     mod_scratch_add: Vec<f32>,
     mod_scratch_mult: Vec<f32>,
@@ -190,6 +573,17 @@ pub struct FilterCollection {
     scratch_freq_mult: Vec<f32>,
     scratch_global_freq_add: Vec<f32>,
     scratch_global_freq_mult: Vec<f32>,
+    scratch_drive_add: Vec<f32>,
+    scratch_drive_mult: Vec<f32>,
+
+    /// Independent filter state for the right channel, built once something is
+    /// wired to `AudioInput1` (see `set_stereo_input`); mono filters never
+    /// allocate or run it.
+    right_channel: Option<Box<FilterCollection>>,
+    /// Whether the right channel ran last block; it starts over when stereo resumes.
+    right_channel_running: bool,
+    /// Whether a parameter changed since the right channel last copied them.
+    right_channel_stale: bool,
 }
 
 // =======================================================================
@@ -213,6 +607,9 @@ impl FilterCollection {
             base_gain_db,
             base_drive: 0.0,
             resonance_gain_compensation: 0.5,
+            auto_gain: false,
+            cutoff_mod_octaves: 0.0,
+            cutoff_spread: 1.0,
             comb_base_frequency,
             comb_dampening: 0.5,
             keyboard_tracking_sensitivity: 0.0,
@@ -221,7 +618,7 @@ impl FilterCollection {
             enabled: true,
             smoothed_cutoff: base_cutoff,
             smoothed_resonance: base_resonance,
-            smoothing_factor: 0.02,
+            smoothing_factor: smoothing_coefficient(sample_rate, FILTER_SMOOTHING_MS),
             biquad: Biquad::new(
                 filter_type,
                 sample_rate,
@@ -230,13 +627,12 @@ impl FilterCollection {
                 base_gain_db,
             ),
             cascaded: None,
-            ladder_stages: [0.0; 4],
-            ladder_outputs: [0.0; 4],
-            comb_buffer: vec![0.0; *MAX_COMB_BUFFER_SIZE],
-            comb_buffer_index: 0,
-            comb_last_output: 0.0,
-            comb_dc_prev: 0.0,
-            comb_dc_state: 0.0,
+            double_precision_feedback: false,
+            ladder: LadderState::default(),
+            ladder_f64: LadderState::default(),
+            comb: CombState::with_len(*MAX_COMB_BUFFER_SIZE),
+            comb_f64: CombState::default(),
+            oversampler: None,
             mod_scratch_add: vec![0.0; initial_capacity],
             mod_scratch_mult: vec![1.0; initial_capacity],
             audio_in_buffer: vec![0.0; initial_capacity],
@@ -248,7 +644,73 @@ impl FilterCollection {
             scratch_freq_mult: vec![1.0; initial_capacity],
             scratch_global_freq_add: vec![440.0; initial_capacity],
             scratch_global_freq_mult: vec![1.0; initial_capacity],
+            scratch_drive_add: vec![0.0; initial_capacity],
+            scratch_drive_mult: vec![1.0; initial_capacity],
+            right_channel: None,
+            right_channel_running: false,
+            right_channel_stale: false,
+        }
+    }
+
+    /// Builds the right-channel filter for a stereo input, or drops it once the
+    /// input is mono again. Call outside the audio callback; the graph does so
+    /// when `AudioInput1` is wired or unwired.
+    pub fn set_stereo(&mut self, stereo: bool) {
+        if stereo == self.right_channel.is_some() {
+            return;
+        }
+        self.right_channel = stereo.then(|| {
+            let mut channel = Box::new(Self::new(self.sample_rate));
+            channel.sync_params_from(self);
+            channel
+        });
+        self.right_channel_running = false;
+        self.right_channel_stale = false;
+    }
+
+    /// Whether the right channel is built, i.e. `AudioInput1` is wired.
+    pub fn is_stereo(&self) -> bool {
+        self.right_channel.is_some()
+    }
+
+    /// The base cutoff with this voice's analog spread applied.
+    fn voice_cutoff(&self) -> f32 {
+        self.base_cutoff * self.cutoff_spread
+    }
+
+    /// Copies the user-facing parameters (not the DSP state) from `src`.
+    fn sync_params_from(&mut self, src: &FilterCollection) {
+        self.base_cutoff = src.base_cutoff;
+        self.base_resonance = src.base_resonance;
+        self.base_gain_db = src.base_gain_db;
+        self.base_drive = src.base_drive;
+        self.resonance_gain_compensation = src.resonance_gain_compensation;
+        self.auto_gain = src.auto_gain;
+        self.cutoff_mod_octaves = src.cutoff_mod_octaves;
+        self.cutoff_spread = src.cutoff_spread;
+        self.comb_base_frequency = src.comb_base_frequency;
+        self.comb_dampening = src.comb_dampening;
+        self.keyboard_tracking_sensitivity = src.keyboard_tracking_sensitivity;
+        self.smoothing_factor = src.smoothing_factor;
+        self.set_double_precision_feedback(src.double_precision_feedback);
+        self.set_oversampling_factor(src.oversampling_factor() as u32);
+        self.set_filter_type(src.filter_type);
+        self.set_filter_slope(src.slope);
+    }
+
+    /// Takes the right-channel filter, synced to this one's parameters. Put it back
+    /// after use; taking and restoring the box doesn't allocate.
+    fn take_right_channel(&mut self) -> Option<Box<FilterCollection>> {
+        let mut channel = self.right_channel.take()?;
+        if self.right_channel_stale {
+            channel.sync_params_from(self);
+            self.right_channel_stale = false;
+        }
+        if !self.right_channel_running {
+            channel.reset();
+            self.right_channel_running = true;
         }
+        Some(channel)
     }
 
     fn ensure_scratch_buffers(&mut self, size: usize) {
@@ -270,6 +732,8 @@ impl FilterCollection {
         resize_if_needed(&mut self.scratch_freq_mult, 1.0);
         resize_if_needed(&mut self.scratch_global_freq_add, 440.0);
         resize_if_needed(&mut self.scratch_global_freq_mult, 1.0);
+        resize_if_needed(&mut self.scratch_drive_add, 0.0);
+        resize_if_needed(&mut self.scratch_drive_mult, 1.0);
     }
 
     pub fn set_params(&mut self, cutoff: f32, resonance: f32) {
@@ -277,26 +741,155 @@ impl FilterCollection {
         let max_safe_cutoff = self.sample_rate * SAFE_NYQUIST_FACTOR;
         self.base_cutoff = cutoff.clamp(10.0, max_safe_cutoff);
         self.base_resonance = resonance.clamp(0.0, 1.0);
+        self.right_channel_stale = true;
     }
 
+    /// The boost or cut of the shelving and peaking types; output gain for the
+    /// others.
     pub fn set_gain_db(&mut self, gain_db: f32) {
-        // (Implementation unchanged)
         self.base_gain_db = gain_db;
+        self.right_channel_stale = true;
     }
 
     pub fn set_drive(&mut self, drive: f32) {
         // (Implementation unchanged)
-        self.base_drive = drive.clamp(0.0, 4.0); // Example range
+        self.base_drive = drive.clamp(0.0, MAX_DRIVE);
+        self.right_channel_stale = true;
     }
 
     pub fn set_resonance_gain_compensation(&mut self, comp: f32) {
         // (Implementation unchanged)
         self.resonance_gain_compensation = comp.clamp(0.0, 1.0);
+        self.right_channel_stale = true;
+    }
+
+    /// Enables calibrated level compensation so resonance and drive changes, or
+    /// switching between filter models, keep a roughly constant output level.
+    pub fn set_auto_gain(&mut self, enabled: bool) {
+        self.auto_gain = enabled;
+        self.right_channel_stale = true;
+    }
+
+    /// Runs the ladder and comb feedback paths in f64, which avoids denormal and
+    /// limit-cycle artifacts at extreme resonance/cutoff settings at some CPU
+    /// cost (see `benches/filter_feedback.rs`). The running state is carried
+    /// over, so switching doesn't click.
+    pub fn set_double_precision_feedback(&mut self, enabled: bool) {
+        if enabled == self.double_precision_feedback {
+            return;
+        }
+        if enabled {
+            self.ladder_f64 = self.ladder.converted();
+            self.comb_f64 = self.comb.converted();
+        } else {
+            self.ladder = self.ladder_f64.converted();
+            self.comb = self.comb_f64.converted();
+            self.comb_f64 = CombState::default();
+        }
+        self.double_precision_feedback = enabled;
+        self.right_channel_stale = true;
+    }
+
+    pub fn double_precision_feedback(&self) -> bool {
+        self.double_precision_feedback
+    }
+
+    /// Runs the ladder and biquad types at 2x or 4x the sample rate, which keeps
+    /// audio-rate cutoff modulation (filter FM) and ladder drive clean. Other
+    /// values round up to the next power of two up to `MAX_FILTER_OVERSAMPLING`;
+    /// 0 or 1 turns oversampling off. The comb always runs at the base rate.
+    pub fn set_oversampling_factor(&mut self, factor: u32) {
+        let factor = (factor.max(1) as usize)
+            .next_power_of_two()
+            .min(MAX_FILTER_OVERSAMPLING);
+        if factor == self.oversampling_factor() {
+            return;
+        }
+        self.oversampler = (factor > 1).then(|| Oversampler::new(factor, self.sample_rate));
+        self.right_channel_stale = true;
+    }
+
+    pub fn oversampling_factor(&self) -> usize {
+        self.oversampler
+            .as_ref()
+            .map_or(1, |oversampler| oversampler.factor)
+    }
+
+    pub fn cutoff(&self) -> f32 {
+        self.base_cutoff
+    }
+
+    pub fn resonance(&self) -> f32 {
+        self.base_resonance
+    }
+
+    pub fn gain_db(&self) -> f32 {
+        self.base_gain_db
+    }
+
+    pub fn auto_gain(&self) -> bool {
+        self.auto_gain
+    }
+
+    pub fn cutoff_mod_octaves(&self) -> f32 {
+        self.cutoff_mod_octaves
+    }
+
+    pub fn keyboard_tracking_sensitivity(&self) -> f32 {
+        self.keyboard_tracking_sensitivity
+    }
+
+    pub fn filter_type(&self) -> FilterType {
+        self.filter_type
+    }
+
+    pub fn filter_slope(&self) -> FilterSlope {
+        self.slope
+    }
+
+    pub fn comb_target_frequency(&self) -> f32 {
+        self.comb_base_frequency
+    }
+
+    pub fn comb_dampening(&self) -> f32 {
+        self.comb_dampening
+    }
+
+    /// Sets how additive CutoffMod is interpreted. At 0.0 the modulation is added
+    /// to the cutoff in Hz; above that a modulation value of +1.0 raises the
+    /// cutoff by `octaves` octaves (and -1.0 lowers it), independent of the base cutoff.
+    pub fn set_cutoff_mod_octaves(&mut self, octaves: f32) {
+        self.cutoff_mod_octaves = octaves.clamp(0.0, MAX_CUTOFF_MOD_OCTAVES);
+        self.right_channel_stale = true;
+    }
+
+    /// Gain (dB) shaped by the biquad itself: the shelf or peak of those types.
+    fn biquad_gain_db(&self) -> f32 {
+        match self.filter_type {
+            FilterType::LowShelf | FilterType::Peaking | FilterType::HighShelf => self.base_gain_db,
+            _ => 0.0,
+        }
+    }
+
+    /// Linear gain applied after the filter, where `gain_db` isn't shaped by it.
+    fn output_gain(&self) -> f32 {
+        10f32.powf((self.base_gain_db - self.biquad_gain_db()) / 20.0)
+    }
+
+    /// Linear gain that cancels the measured level change of the current model.
+    #[inline(always)]
+    fn auto_gain_factor(&self, resonance_norm: f32, drive: f32) -> f32 {
+        if !self.auto_gain {
+            return 1.0;
+        }
+        let level_db = model_level_db(self.filter_type, self.slope, resonance_norm, drive);
+        10f32.powf(-level_db / 20.0)
     }
 
     pub fn set_keyboard_tracking_sensitivity(&mut self, sensitivity: f32) {
         // (Implementation unchanged)
         self.keyboard_tracking_sensitivity = sensitivity.clamp(0.0, 1.0);
+        self.right_channel_stale = true;
     }
 
     pub fn set_filter_type(&mut self, filter_type: FilterType) {
@@ -312,6 +905,7 @@ impl FilterCollection {
             } else {
                 self.cascaded = None;
             }
+            self.right_channel_stale = true;
         }
     }
 
@@ -336,11 +930,21 @@ impl FilterCollection {
                 // Reset ladder/comb state if slope changes (though slope doesn't apply)
                 self.reset_filter_state();
             }
+            self.right_channel_stale = true;
         }
     }
 
     fn setup_cascaded_filter(&mut self) {
-        // (Implementation unchanged)
+        self.setup_cascaded_filter_at(
+            self.sample_rate,
+            self.smoothed_cutoff,
+            self.smoothed_resonance,
+        );
+    }
+
+    /// Builds or retunes the 24 dB cascade for `sample_rate`, which is above the
+    /// base rate while oversampling.
+    fn setup_cascaded_filter_at(&mut self, sr: f32, cutoff: f32, res: f32) {
         if self.slope != FilterSlope::Db24
             || self.filter_type == FilterType::Ladder
             || self.filter_type == FilterType::Comb
@@ -349,11 +953,10 @@ impl FilterCollection {
             return;
         }
 
-        let sr = self.sample_rate;
-        let cutoff = self.smoothed_cutoff; // Use smoothed value
-        let res = self.smoothed_resonance; // Use smoothed value
         let q_overall = normalized_resonance_to_q(res);
         let stage_q = q_overall.sqrt().max(0.501);
+        // Each stage takes half, so the pair shelves twice as steeply to the same gain.
+        let stage_gain_db = self.biquad_gain_db() * 0.5;
 
         if let Some(ref mut cascaded) = self.cascaded {
             cascaded.first.sample_rate = sr;
@@ -362,8 +965,8 @@ impl FilterCollection {
             cascaded.second.frequency = cutoff;
             cascaded.first.q = stage_q;
             cascaded.second.q = stage_q;
-            cascaded.first.gain_db = 0.0;
-            cascaded.second.gain_db = self.base_gain_db;
+            cascaded.first.gain_db = stage_gain_db;
+            cascaded.second.gain_db = stage_gain_db;
             cascaded.first.filter_type = self.filter_type;
             cascaded.second.filter_type = self.filter_type;
             cascaded.first.update_coefficients();
@@ -374,8 +977,8 @@ impl FilterCollection {
                 sr,
                 cutoff,
                 stage_q,
-                0.0,
-                self.base_gain_db,
+                stage_gain_db,
+                stage_gain_db,
             ));
             // Reset state when creating new filter
             if let Some(ref mut c) = self.cascaded {
@@ -388,11 +991,13 @@ impl FilterCollection {
         // (Implementation unchanged)
         let max_safe_freq = self.sample_rate * SAFE_NYQUIST_FACTOR;
         self.comb_base_frequency = freq.clamp(10.0, max_safe_freq);
+        self.right_channel_stale = true;
     }
 
     pub fn set_comb_dampening(&mut self, dampening: f32) {
         // (Implementation unchanged)
         self.comb_dampening = dampening.clamp(0.0, 1.0);
+        self.right_channel_stale = true;
     }
 
     fn reset_filter_state(&mut self) {
@@ -401,13 +1006,13 @@ impl FilterCollection {
         if let Some(ref mut cascaded) = self.cascaded {
             cascaded.reset();
         }
-        self.ladder_outputs.fill(0.0);
-        self.ladder_stages.fill(0.0);
-        self.comb_buffer.fill(0.0);
-        self.comb_buffer_index = 0;
-        self.comb_last_output = 0.0;
-        self.comb_dc_prev = 0.0;
-        self.comb_dc_state = 0.0;
+        self.ladder = LadderState::default();
+        self.ladder_f64 = LadderState::default();
+        self.comb.reset();
+        self.comb_f64.reset();
+        if let Some(oversampler) = self.oversampler.as_mut() {
+            oversampler.reset();
+        }
     }
 
     #[inline(always)]
@@ -420,80 +1025,13 @@ impl FilterCollection {
         res_comp: f32,
         sample_rate: f32,
     ) -> f32 {
-        // --- Parameter Calculation ---
-        let effective_cutoff = cutoff.clamp(10.0, sample_rate * SAFE_NYQUIST_FACTOR);
-        let wc = PI * effective_cutoff / sample_rate;
-        let g = wc.tan(); // TPT pre-warping (FIXED: removed incorrect 0.5 factor)
-
-        // --- Resonance & Compensation (Authentic Moog Tuning) ---
-        let k_resonance = resonance_norm.clamp(0.0, 1.0);
-        // Moog resonance: 4x feedback at maximum, with exponential curve for musical response
-        let k = k_resonance.powf(1.8) * 4.0; // Slightly steeper curve for better control
-                                             // Compensation prevents volume loss at high resonance (authentic Moog behavior)
-        let comp_gain = (1.0 + res_comp * k * 0.5).max(0.0);
-
-        // --- State & Feedback ---
-        let s0 = self.ladder_stages[0];
-        let s1 = self.ladder_stages[1];
-        let s2 = self.ladder_stages[2];
-        let s3 = self.ladder_stages[3];
-
-        // Feedback comes from the previous sample's output
-        let feedback = k * self.ladder_outputs[3];
-
-        // --- Drive/Saturation (Authentic Moog Character) ---
-        // In authentic Moog: drive adds input stage overdrive and per-stage saturation
-        // At drive=0: nearly linear operation
-        // At drive>0: progressive harmonic distortion
-        let input_drive = 1.0 + drive * 2.0; // Input overdrive: 1.0 to 3.0
-        let stage_drive = 1.0 + drive * 1.5; // Per-stage saturation: 1.0 to 2.5
-
-        // --- Per-Stage Processing (TPT Trapezoidal Integrator with Saturation) ---
-        let g_inv = 1.0 / (1.0 + g);
-
-        // Stage 0 - Input stage with feedback and overdrive
-        let input0 = input * comp_gain - feedback;
-        let input0_driven = input0 * input_drive;
-        let input0_sat = if drive > 0.01 {
-            fast_tanh(input0_driven)
-        } else {
-            input0_driven.clamp(-2.0, 2.0) // Soft clipping when no drive
-        };
-        let v0 = (s0 + g * input0_sat) * g_inv;
-        self.ladder_stages[0] = 2.0 * v0 - s0;
-
-        // Stages 1-3 - Mostly linear with optional saturation character
-        let v1_in = if drive > 0.01 {
-            fast_tanh(v0 * stage_drive)
+        if self.double_precision_feedback {
+            self.ladder_f64
+                .process(input, cutoff, resonance_norm, drive, res_comp, sample_rate)
         } else {
-            v0
-        };
-        let v1 = (s1 + g * v1_in) * g_inv;
-        self.ladder_stages[1] = 2.0 * v1 - s1;
-
-        let v2_in = if drive > 0.01 {
-            fast_tanh(v1 * stage_drive)
-        } else {
-            v1
-        };
-        let v2 = (s2 + g * v2_in) * g_inv;
-        self.ladder_stages[2] = 2.0 * v2 - s2;
-
-        let v3_in = if drive > 0.01 {
-            fast_tanh(v2 * stage_drive)
-        } else {
-            v2
-        };
-        let v3 = (s3 + g * v3_in) * g_inv;
-        self.ladder_stages[3] = 2.0 * v3 - s3;
-
-        // --- Store Output for Feedback (with soft saturation for authentic behavior) ---
-        // Authentic Moog uses moderately saturated feedback for self-oscillation at high resonance
-        // tanh(x * 1.2) provides gentle saturation while allowing self-oscillation
-        self.ladder_outputs[3] = fast_tanh(v3 * 1.2);
-
-        // --- Return Filter Output ---
-        v3
+            self.ladder
+                .process(input, cutoff, resonance_norm, drive, res_comp, sample_rate)
+        }
     }
 
     #[inline(always)]
@@ -504,43 +1042,14 @@ impl FilterCollection {
         resonance_norm: f32,
         sample_rate: f32,
     ) -> f32 {
-        // (Implementation unchanged)
-        let clamped_freq = freq.clamp(10.0, sample_rate * SAFE_NYQUIST_FACTOR);
-        let delay_samples = (sample_rate / clamped_freq).max(2.0);
-
-        let clamped_res = resonance_norm.clamp(0.0, 0.995);
-        let clamped_dampening = self.comb_dampening.clamp(0.0, 1.0);
-        let alpha = clamped_dampening; // Use this variable
-
-        let delay_floor = delay_samples.floor();
-        let delay_frac = delay_samples - delay_floor;
-        let delay_int = delay_floor as usize;
-
-        let buf_len = self.comb_buffer.len();
-        let read_idx0 = (self.comb_buffer_index + buf_len - (delay_int % buf_len)) % buf_len;
-        let read_idx1 = (self.comb_buffer_index + buf_len - ((delay_int + 1) % buf_len)) % buf_len;
-
-        let y0 = self.comb_buffer[read_idx0];
-        let y1 = self.comb_buffer[read_idx1];
-        // Manual lerp: y0 * (1.0 - delay_frac) + y1 * delay_frac
-        let delayed_sample = y0.mul_add(1.0 - delay_frac, y1 * delay_frac);
-
-        // Dampening filter in feedback path
-        self.comb_last_output = self.comb_last_output * alpha + delayed_sample * (1.0 - alpha); // Correct one-pole LPF
-        let feedback = self.comb_last_output * clamped_res;
-        let buffer_write_val = input + feedback;
-
-        self.comb_buffer[self.comb_buffer_index] = buffer_write_val;
-        self.comb_buffer_index = (self.comb_buffer_index + 1) % buf_len;
-
-        let comb_output = buffer_write_val;
-
-        // DC blocker
-        let filtered_output = comb_output - self.comb_dc_prev + 0.995 * self.comb_dc_state;
-        self.comb_dc_prev = comb_output;
-        self.comb_dc_state = filtered_output;
-
-        filtered_output
+        let dampening = self.comb_dampening;
+        if self.double_precision_feedback {
+            self.comb_f64
+                .process(input, freq, resonance_norm, dampening, sample_rate)
+        } else {
+            self.comb
+                .process(input, freq, resonance_norm, dampening, sample_rate)
+        }
     }
 
     #[inline(always)]
@@ -551,6 +1060,50 @@ impl FilterCollection {
         resonance_norm: f32,
         sample_rate: f32,
     ) -> f32 {
+        self.prepare_biquad(cutoff, resonance_norm, sample_rate);
+        match (self.slope, self.cascaded.as_mut()) {
+            (FilterSlope::Db24, Some(cascaded)) => cascaded.process(input),
+            _ => self.biquad.process(input),
+        }
+    }
+
+    /// One sample through the ladder or biquad at the oversampled rate, with
+    /// the cutoff swept from `previous_cutoff` to the smoothed one across the
+    /// sub-samples.
+    fn process_oversampled_sample(
+        &mut self,
+        input: f32,
+        previous_cutoff: f32,
+        drive: f32,
+        res_comp: f32,
+    ) -> f32 {
+        let Some(mut oversampler) = self.oversampler.take() else {
+            return input;
+        };
+        let factor = oversampler.factor;
+        let rate = self.sample_rate * factor as f32;
+        let resonance = self.smoothed_resonance;
+        let mut sub_samples = [0.0; MAX_FILTER_OVERSAMPLING];
+        let sub_samples = &mut sub_samples[..factor];
+        oversampler.upsample(input, sub_samples);
+        for (k, sample) in sub_samples.iter_mut().enumerate() {
+            let position = (k + 1) as f32 / factor as f32;
+            let cutoff = previous_cutoff + (self.smoothed_cutoff - previous_cutoff) * position;
+            *sample = match self.filter_type {
+                FilterType::Ladder => {
+                    self.process_ladder_sample(*sample, cutoff, resonance, drive, res_comp, rate)
+                }
+                _ => self.process_biquad_sample(*sample, cutoff, resonance, rate),
+            };
+        }
+        let output = oversampler.downsample(sub_samples);
+        self.oversampler = Some(oversampler);
+        output
+    }
+
+    /// Brings the biquad (or 24 dB cascade) coefficients up to date.
+    #[inline(always)]
+    fn prepare_biquad(&mut self, cutoff: f32, resonance_norm: f32, sample_rate: f32) {
         let safe_cutoff = cutoff.clamp(10.0, sample_rate * SAFE_NYQUIST_FACTOR);
         let safe_resonance = resonance_norm.clamp(0.0, 1.0);
         let q = normalized_resonance_to_q(safe_resonance);
@@ -559,7 +1112,7 @@ impl FilterCollection {
 
         match self.slope {
             FilterSlope::Db12 => {
-                let single_biquad_gain = self.base_gain_db;
+                let single_biquad_gain = self.biquad_gain_db();
                 // Check if biquad parameters need updating
                 let needs_update = self.biquad.sample_rate != sample_rate
                     || (self.biquad.frequency - safe_cutoff).abs() > EPSILON
@@ -579,11 +1132,10 @@ impl FilterCollection {
                     // if self.biquad.filter_type != old_filter_type { self.biquad.reset(); }
                     // --- End FIX ---
                 }
-                self.biquad.process(input)
             }
             FilterSlope::Db24 => {
                 let stage_q = q.sqrt().max(0.501);
-                let second_stage_gain = self.base_gain_db;
+                let second_stage_gain = self.biquad_gain_db() * 0.5;
 
                 // Check if cascaded filter needs setup or update
                 let cascaded_needs_update = match self.cascaded {
@@ -600,14 +1152,11 @@ impl FilterCollection {
                 if cascaded_needs_update {
                     // Setup/Update happens within setup_cascaded_filter call below or implicitly
                     // We can call setup_cascaded_filter here to ensure it's current
-                    self.setup_cascaded_filter();
+                    self.setup_cascaded_filter_at(sample_rate, safe_cutoff, safe_resonance);
                     // Reset should be handled inside setup_cascaded_filter if created new
                 }
 
-                // Process through the cascaded filter
-                if let Some(ref mut cascaded) = self.cascaded {
-                    cascaded.process(input)
-                } else {
+                if self.cascaded.is_none() {
                     // Fallback if cascaded is still None (shouldn't happen with logic above)
                     eprintln!(
                         "Warning: Cascaded filter expected but missing in 24dB mode (fallback)."
@@ -618,10 +1167,9 @@ impl FilterCollection {
                     self.biquad.frequency = safe_cutoff;
                     // Use the overall Q for the single biquad fallback, not stage_q
                     self.biquad.q = q;
-                    self.biquad.gain_db = self.base_gain_db;
+                    self.biquad.gain_db = self.biquad_gain_db();
                     self.biquad.update_coefficients();
                     // --- End FIX ---
-                    self.biquad.process(input) // Process with the single biquad
                 }
             }
         }
@@ -718,7 +1266,8 @@ impl FilterCollection {
         let impulse_comb_freq = temp_filter.comb_base_frequency;
         let impulse_drive = temp_filter.base_drive;
         let impulse_res_comp = temp_filter.resonance_gain_compensation;
-        let output_gain = 10f32.powf(temp_filter.base_gain_db / 20.0);
+        let output_gain = temp_filter.output_gain()
+            * temp_filter.auto_gain_factor(temp_filter.smoothed_resonance, impulse_drive);
 
         for i in 0..length {
             let input = if i == 0 { 1.0 } else { 0.0 };
@@ -784,49 +1333,21 @@ impl FilterCollection {
 // =======================================================================
 impl ModulationProcessor for FilterCollection {}
 
-// =======================================================================
-// AudioNode Implementation
-// =======================================================================
-impl AudioNode for FilterCollection {
-    fn get_ports(&self) -> FxHashMap<PortId, bool> {
-        // (Implementation unchanged)
-        [
-            (PortId::AudioInput0, false),
-            (PortId::CutoffMod, false),
-            (PortId::ResonanceMod, false),
-            (PortId::Frequency, false),
-            (PortId::GlobalFrequency, false),
-            (PortId::AudioOutput0, true),
-        ]
-        .iter()
-        .cloned()
-        .collect()
-    }
-
-    fn process<'a>(
+impl FilterCollection {
+    /// Filters one channel: reads audio from `audio_port`, applies the shared
+    /// modulation inputs and writes the result into `output_buffer`.
+    fn render_channel<'a>(
         &mut self,
+        audio_port: PortId,
         inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
-        outputs: &mut FxHashMap<PortId, &mut [f32]>,
+        output_buffer: &mut [f32],
         buffer_size: usize,
     ) {
-        if !self.enabled {
-            if let Some(output_buffer) = outputs.get_mut(&PortId::AudioOutput0) {
-                output_buffer[..buffer_size].fill(0.0);
-            }
-            return;
-        }
-
         self.ensure_scratch_buffers(buffer_size);
 
-        let output_buffer = match outputs.get_mut(&PortId::AudioOutput0) {
-            Some(buffer) => buffer,
-            None => return,
-        };
-
         // --- 1. Prepare Input Audio Buffer ---
-        // (Implementation unchanged)
         self.audio_in_buffer[..buffer_size].fill(0.0);
-        if let Some(audio_sources) = inputs.get(&PortId::AudioInput0) {
+        if let Some(audio_sources) = inputs.get(&audio_port) {
             for source in audio_sources {
                 Self::apply_add(
                     &source.buffer,
@@ -837,6 +1358,95 @@ impl AudioNode for FilterCollection {
             }
         }
 
+        self.render_prepared(inputs, output_buffer, buffer_size);
+    }
+
+    /// Filters already-mixed audio instead of reading `AudioInput0/1`, using the
+    /// modulation ports from `inputs`. Lets container nodes such as `DualFilter`
+    /// chain filters; `right` of `None` copies the left result (dual mono).
+    pub(crate) fn process_audio<'a>(
+        &mut self,
+        left: &[f32],
+        right: Option<&[f32]>,
+        inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+        output_left: &mut [f32],
+        output_right: &mut [f32],
+        buffer_size: usize,
+    ) {
+        self.ensure_scratch_buffers(buffer_size);
+        self.audio_in_buffer[..buffer_size].copy_from_slice(&left[..buffer_size]);
+        self.render_prepared(inputs, output_left, buffer_size);
+
+        match right.and_then(|right| Some((right, self.take_right_channel()?))) {
+            Some((right, mut channel)) => {
+                channel.ensure_scratch_buffers(buffer_size);
+                channel.audio_in_buffer[..buffer_size].copy_from_slice(&right[..buffer_size]);
+                channel.render_prepared(inputs, output_right, buffer_size);
+                self.right_channel = Some(channel);
+            }
+            None => {
+                self.right_channel_running = false;
+                output_right[..buffer_size].copy_from_slice(&output_left[..buffer_size]);
+            }
+        }
+    }
+
+    /// Cutoff and resonance for a biquad that can run a whole block on fixed
+    /// coefficients: no cutoff/resonance modulation, no key tracking, and the
+    /// parameter smoothing has settled. `None` means per-sample processing.
+    fn settled_biquad_params<'a>(
+        &self,
+        inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+    ) -> Option<(f32, f32)> {
+        if matches!(self.filter_type, FilterType::Ladder | FilterType::Comb)
+            || self.keyboard_tracking_sensitivity != 0.0
+            || self.oversampler.is_some()
+        {
+            return None;
+        }
+        let modulated = |port: PortId| inputs.get(&port).is_some_and(|sources| !sources.is_empty());
+        if modulated(PortId::CutoffMod) || modulated(PortId::ResonanceMod) {
+            return None;
+        }
+
+        let cutoff = self
+            .voice_cutoff()
+            .clamp(10.0, self.sample_rate * SAFE_NYQUIST_FACTOR);
+        let resonance = self.base_resonance.clamp(0.0, 1.0);
+        let settled = (self.smoothed_cutoff - cutoff).abs() <= cutoff * 1e-6
+            && (self.smoothed_resonance - resonance).abs() <= 1e-6;
+        settled.then_some((cutoff, resonance))
+    }
+
+    /// Filters `audio_in_buffer` into `output_buffer`, applying the modulation inputs.
+    #[allow(clippy::needless_range_loop)]
+    fn render_prepared<'a>(
+        &mut self,
+        inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+        output_buffer: &mut [f32],
+        buffer_size: usize,
+    ) {
+        // --- 1. Settled fast path ---
+        // With nothing moving, the coefficients are fixed for the whole block and
+        // the filter can run block-wise (the 24 dB cascade uses the SIMD kernel).
+        if let Some((cutoff, resonance)) = self.settled_biquad_params(inputs) {
+            self.smoothed_cutoff = cutoff;
+            self.smoothed_resonance = resonance;
+            self.prepare_biquad(cutoff, resonance, self.sample_rate);
+
+            let output = &mut output_buffer[..buffer_size];
+            output.copy_from_slice(&self.audio_in_buffer[..buffer_size]);
+            match (self.slope, self.cascaded.as_mut()) {
+                (FilterSlope::Db24, Some(cascaded)) => cascaded.process_block(output),
+                _ => self.biquad.process_block(output),
+            }
+            let gain = self.output_gain() * self.auto_gain_factor(resonance, self.base_drive);
+            for sample in output.iter_mut() {
+                *sample *= gain;
+            }
+            return;
+        }
+
         // --- 2. Prepare Modulation Buffers ---
         // (Implementation unchanged)
         let mut process_mod_input = |port_id: PortId,
@@ -888,25 +1498,38 @@ impl AudioNode for FilterCollection {
             440.0,
             1.0,
         );
-        // Add DriveMod processing here if needed
+        process_mod_input(
+            PortId::DriveMod,
+            &mut self.scratch_drive_add,
+            &mut self.scratch_drive_mult,
+            0.0,
+            1.0,
+        );
 
         // --- 3. Process Audio Samples ---
         let sample_rate = self.sample_rate;
         let base_440_hz = 440.0;
         let smoothing_factor_cutoff = self.smoothing_factor;
         let smoothing_factor_res = self.smoothing_factor;
-        let output_gain = 10f32.powf(self.base_gain_db / 20.0);
+        let output_gain = self.output_gain();
         let max_freq_limit = sample_rate * SAFE_NYQUIST_FACTOR;
 
-        let current_drive = self.base_drive; // Add modulation lookup here if needed
         let current_res_comp = self.resonance_gain_compensation;
+        let base_cutoff = self.voice_cutoff();
 
         for i in 0..buffer_size {
             // --- Calculate Target Parameters ---
-            let target_cutoff_base =
-                (self.base_cutoff + self.scratch_cutoff_add[i]) * self.scratch_cutoff_mult[i];
+            let target_cutoff_base = if self.cutoff_mod_octaves > 0.0 {
+                let octaves = self.scratch_cutoff_add[i] * self.cutoff_mod_octaves;
+                base_cutoff * octaves.exp2() * self.scratch_cutoff_mult[i]
+            } else {
+                (base_cutoff + self.scratch_cutoff_add[i]) * self.scratch_cutoff_mult[i]
+            };
             let target_resonance_norm =
                 (self.base_resonance + self.scratch_res_add[i]) * self.scratch_res_mult[i];
+            let current_drive = ((self.base_drive + self.scratch_drive_add[i])
+                * self.scratch_drive_mult[i])
+                .clamp(0.0, MAX_DRIVE);
 
             // --- Keyboard Tracking ---
             let key_freq = (self.scratch_freq_add[i] * self.scratch_freq_mult[i]).max(10.0);
@@ -929,6 +1552,7 @@ impl AudioNode for FilterCollection {
             let target_resonance_clamped = target_resonance_norm.clamp(0.0, 1.0);
 
             // --- Smooth Parameters (using manual lerp) ---
+            let previous_cutoff = self.smoothed_cutoff;
             let sm_cut = smoothing_factor_cutoff;
             self.smoothed_cutoff = self.smoothed_cutoff * (1.0 - sm_cut) + target_cutoff * sm_cut;
             let sm_res = smoothing_factor_res;
@@ -940,39 +1564,122 @@ impl AudioNode for FilterCollection {
 
             // --- Process Single Sample ---
             let input_sample = self.audio_in_buffer[i];
-            let filter_output = match self.filter_type {
-                FilterType::Ladder => self.process_ladder_sample(
+            let oversampled = self.oversampler.is_some() && self.filter_type != FilterType::Comb;
+            let filter_output = if oversampled {
+                self.process_oversampled_sample(
                     input_sample,
-                    self.smoothed_cutoff,
-                    self.smoothed_resonance,
+                    previous_cutoff,
                     current_drive,
                     current_res_comp,
-                    sample_rate,
-                ),
-                FilterType::Comb => self.process_comb_sample(
-                    input_sample,
-                    target_comb_freq, // Use tracked comb freq
-                    self.smoothed_resonance,
-                    sample_rate,
-                ),
-                _ => self.process_biquad_sample(
-                    input_sample,
-                    self.smoothed_cutoff,
-                    self.smoothed_resonance,
-                    sample_rate,
-                ),
+                )
+            } else {
+                match self.filter_type {
+                    FilterType::Ladder => self.process_ladder_sample(
+                        input_sample,
+                        self.smoothed_cutoff,
+                        self.smoothed_resonance,
+                        current_drive,
+                        current_res_comp,
+                        sample_rate,
+                    ),
+                    FilterType::Comb => self.process_comb_sample(
+                        input_sample,
+                        target_comb_freq, // Use tracked comb freq
+                        self.smoothed_resonance,
+                        sample_rate,
+                    ),
+                    _ => self.process_biquad_sample(
+                        input_sample,
+                        self.smoothed_cutoff,
+                        self.smoothed_resonance,
+                        sample_rate,
+                    ),
+                }
             };
 
             // --- Final Output ---
-            output_buffer[i] = filter_output * output_gain;
+            let auto_gain = self.auto_gain_factor(self.smoothed_resonance, current_drive);
+            output_buffer[i] = filter_output * output_gain * auto_gain;
+        }
+    }
+}
+
+// =======================================================================
+// AudioNode Implementation
+// =======================================================================
+impl AudioNode for FilterCollection {
+    fn get_ports(&self) -> FxHashMap<PortId, bool> {
+        // (Implementation unchanged)
+        [
+            (PortId::AudioInput0, false),
+            (PortId::AudioInput1, false),
+            (PortId::CutoffMod, false),
+            (PortId::ResonanceMod, false),
+            (PortId::DriveMod, false),
+            (PortId::Frequency, false),
+            (PortId::GlobalFrequency, false),
+            (PortId::AudioOutput0, true),
+            (PortId::AudioOutput1, true),
+        ]
+        .iter()
+        .cloned()
+        .collect()
+    }
+
+    fn process<'a>(
+        &mut self,
+        inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+        outputs: &mut FxHashMap<PortId, &mut [f32]>,
+        buffer_size: usize,
+    ) {
+        if !self.enabled {
+            if let Some(output_buffer) = outputs.get_mut(&PortId::AudioOutput0) {
+                output_buffer[..buffer_size].fill(0.0);
+            }
+            if let Some(output_buffer) = outputs.get_mut(&PortId::AudioOutput1) {
+                output_buffer[..buffer_size].fill(0.0);
+            }
+            return;
+        }
+
+        if let Some(output_buffer) = outputs.get_mut(&PortId::AudioOutput0) {
+            self.render_channel(PortId::AudioInput0, inputs, output_buffer, buffer_size);
+        }
+
+        // --- Right channel: filtered separately when stereo, dual-mono otherwise ---
+        let stereo_input = inputs
+            .get(&PortId::AudioInput1)
+            .is_some_and(|sources| !sources.is_empty());
+        if !outputs.contains_key(&PortId::AudioOutput1) {
+            return;
+        }
+        let right = if stereo_input {
+            self.take_right_channel()
+        } else {
+            None
+        };
+        if let Some(mut right) = right {
+            if let Some(output_right) = outputs.get_mut(&PortId::AudioOutput1) {
+                right.render_channel(PortId::AudioInput1, inputs, output_right, buffer_size);
+            }
+            self.right_channel = Some(right);
+            return;
+        }
+        self.right_channel_running = false;
+        if let Some(left) = outputs.get(&PortId::AudioOutput0) {
+            self.audio_in_buffer[..buffer_size].copy_from_slice(&left[..buffer_size]);
+            if let Some(output_right) = outputs.get_mut(&PortId::AudioOutput1) {
+                output_right[..buffer_size].copy_from_slice(&self.audio_in_buffer[..buffer_size]);
+            }
         }
     }
 
     fn reset(&mut self) {
         // (Implementation unchanged)
         self.reset_filter_state();
+        self.right_channel_running = false;
         let max_safe_cutoff = self.sample_rate * SAFE_NYQUIST_FACTOR;
-        self.smoothed_cutoff = self.base_cutoff.clamp(10.0, max_safe_cutoff);
+        self.smoothed_cutoff = self.voice_cutoff().clamp(10.0, max_safe_cutoff);
         self.smoothed_resonance = self.base_resonance.clamp(0.0, 1.0);
         if self.filter_type != FilterType::Ladder
             && self.filter_type != FilterType::Comb
@@ -989,6 +1696,20 @@ impl AudioNode for FilterCollection {
         self.enabled
     }
 
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.smoothing_factor = smoothing_coefficient(self.sample_rate, time_ms);
+        self.right_channel_stale = true;
+    }
+
+    fn set_voice_variation(&mut self, variation: &VoiceVariation) {
+        self.cutoff_spread = variation.cutoff_ratio;
+        self.right_channel_stale = true;
+    }
+
+    fn set_stereo_input(&mut self, connected: bool) {
+        self.set_stereo(connected);
+    }
+
     fn set_active(&mut self, active: bool) {
         // (Implementation unchanged)
         if !active && self.enabled {
@@ -997,6 +1718,44 @@ impl AudioNode for FilterCollection {
         self.enabled = active;
     }
 
+    fn modulation_target(&self, port: PortId) -> Option<ModulationTarget> {
+        match port {
+            PortId::CutoffMod => Some(ModulationTarget {
+                parameter: "cutoff",
+                unit: "Hz",
+                base: self.base_cutoff,
+                min: 10.0,
+                max: self.sample_rate * SAFE_NYQUIST_FACTOR,
+                mapping: if self.cutoff_mod_octaves > 0.0 {
+                    TargetMapping::Octaves(self.cutoff_mod_octaves)
+                } else {
+                    TargetMapping::Linear
+                },
+            }),
+            PortId::ResonanceMod => Some(ModulationTarget {
+                parameter: "resonance",
+                unit: "",
+                base: self.base_resonance,
+                min: 0.0,
+                max: 1.0,
+                mapping: TargetMapping::Linear,
+            }),
+            PortId::DriveMod => Some(ModulationTarget {
+                parameter: "drive",
+                unit: "",
+                base: self.base_drive,
+                min: 0.0,
+                max: MAX_DRIVE,
+                mapping: TargetMapping::Linear,
+            }),
+            _ => None,
+        }
+    }
+
+    fn clone_node(&self) -> Option<Box<dyn AudioNode>> {
+        Some(Box::new(self.clone()))
+    }
+
     fn name(&self) -> &'static str {
         "Filter Collection"
     }
@@ -1024,6 +1783,8 @@ impl AudioNode for FilterCollection {
 mod tests {
     use super::*;
     use crate::graph::{ModulationTransformation, ModulationType};
+    use crate::utils::aliasing::{measure_aliasing, ALIAS_RENDER_LENGTH};
+    use std::f32::consts::PI;
     // Removed the Lerp trait definition - no longer needed
 
     const TEST_SAMPLE_RATE: f32 = 48000.0;
@@ -1523,4 +2284,401 @@ mod tests {
             rms_early
         );
     }
+
+    #[test]
+    fn test_stereo_input_keeps_channels_separate() {
+        let mut fc = FilterCollection::new(TEST_SAMPLE_RATE);
+        fc.set_params(2000.0, 0.0);
+        fc.set_stereo(true);
+
+        let size = 64;
+        let left_in = vec![0.0f32; size];
+        let right_in = vec![1.0f32; size];
+        let mut inputs: FxHashMap<PortId, Vec<ModulationSource>> = FxHashMap::default();
+        for (port, buffer) in [
+            (PortId::AudioInput0, &left_in),
+            (PortId::AudioInput1, &right_in),
+        ] {
+            inputs.insert(
+                port,
+                vec![ModulationSource {
+                    buffer: &buffer[..],
+                    amount: 1.0,
+                    mod_type: ModulationType::Additive,
+                    transformation: ModulationTransformation::None,
+                }],
+            );
+        }
+
+        let mut out_l = vec![0.0f32; size];
+        let mut out_r = vec![0.0f32; size];
+        let mut outputs: FxHashMap<PortId, &mut [f32]> = FxHashMap::default();
+        outputs.insert(PortId::AudioOutput0, &mut out_l);
+        outputs.insert(PortId::AudioOutput1, &mut out_r);
+        fc.process(&inputs, &mut outputs, size);
+
+        assert!(out_l.iter().all(|&s| s.abs() < 1e-6));
+        assert!(out_r[size - 1] > 0.1);
+    }
+
+    #[test]
+    fn test_mono_input_runs_one_channel() {
+        let size = 64;
+        let input: Vec<f32> = (0..size)
+            .map(|i| if i % 8 < 4 { 1.0 } else { -1.0 })
+            .collect();
+        let mut inputs: FxHashMap<PortId, Vec<ModulationSource>> = FxHashMap::default();
+        inputs.insert(
+            PortId::AudioInput0,
+            vec![ModulationSource {
+                buffer: &input[..],
+                amount: 1.0,
+                mod_type: ModulationType::Additive,
+                transformation: ModulationTransformation::None,
+            }],
+        );
+        let render = |fc: &mut FilterCollection, with_right: bool| {
+            let mut out_l = vec![0.0f32; size];
+            let mut out_r = vec![0.0f32; size];
+            let mut outputs: FxHashMap<PortId, &mut [f32]> = FxHashMap::default();
+            outputs.insert(PortId::AudioOutput0, &mut out_l);
+            if with_right {
+                outputs.insert(PortId::AudioOutput1, &mut out_r);
+            }
+            fc.process(&inputs, &mut outputs, size);
+            (out_l, out_r)
+        };
+
+        let mut left_only = FilterCollection::new(TEST_SAMPLE_RATE);
+        left_only.set_params(1000.0, 0.5);
+        let (reference, _) = render(&mut left_only, false);
+        let mut fc = FilterCollection::new(TEST_SAMPLE_RATE);
+        fc.set_params(1000.0, 0.5);
+        let (out_l, out_r) = render(&mut fc, true);
+
+        // No right-channel filter is built or run; the right output is a copy.
+        assert!(!fc.is_stereo());
+        assert_eq!(out_l, reference);
+        assert_eq!(out_r, out_l);
+    }
+
+    fn render_noise_level_db(fc: &mut FilterCollection) -> f32 {
+        let block = 128;
+        let blocks = 375;
+        let mut seed: u32 = 0x1234_5678;
+        let mut pink = [0.0f32; 3];
+        let mut sum_sq = 0.0f32;
+        let mut count = 0usize;
+        for b in 0..blocks {
+            // Pink noise (Paul Kellet's economy filter), matching the calibration signal.
+            let input: Vec<f32> = (0..block)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                    let white = (seed >> 8) as f32 / (1u32 << 24) as f32 * 2.0 - 1.0;
+                    pink[0] = 0.99765 * pink[0] + white * 0.0990460;
+                    pink[1] = 0.96300 * pink[1] + white * 0.2965164;
+                    pink[2] = 0.57000 * pink[2] + white * 1.0526913;
+                    (pink[0] + pink[1] + pink[2] + white * 0.1848) * 0.05
+                })
+                .collect();
+            let mut inputs: FxHashMap<PortId, Vec<ModulationSource>> = FxHashMap::default();
+            inputs.insert(
+                PortId::AudioInput0,
+                vec![ModulationSource {
+                    buffer: &input[..],
+                    amount: 1.0,
+                    mod_type: ModulationType::Additive,
+                    transformation: ModulationTransformation::None,
+                }],
+            );
+            let mut out = vec![0.0f32; block];
+            let mut outputs: FxHashMap<PortId, &mut [f32]> = FxHashMap::default();
+            outputs.insert(PortId::AudioOutput0, &mut out);
+            fc.process(&inputs, &mut outputs, block);
+            // Skip the first half while parameter smoothing settles.
+            if b >= blocks / 2 {
+                sum_sq += out.iter().map(|x| x * x).sum::<f32>();
+                count += block;
+            }
+        }
+        10.0 * (sum_sq / count as f32).max(1e-12).log10()
+    }
+
+    #[test]
+    fn test_auto_gain_flattens_resonance_level() {
+        for (filter_type, slope) in [
+            (FilterType::HighPass, FilterSlope::Db24),
+            (FilterType::BandPass, FilterSlope::Db12),
+        ] {
+            let level_at = |resonance: f32, auto_gain: bool| {
+                let mut fc = FilterCollection::new(TEST_SAMPLE_RATE);
+                fc.set_filter_type(filter_type);
+                fc.set_filter_slope(slope);
+                fc.set_params(1000.0, resonance);
+                fc.set_auto_gain(auto_gain);
+                fc.reset();
+                render_noise_level_db(&mut fc)
+            };
+
+            let raw_change = (level_at(1.0, false) - level_at(0.0, false)).abs();
+            let compensated_change = (level_at(1.0, true) - level_at(0.0, true)).abs();
+            assert!(
+                raw_change > 5.0,
+                "{:?}: expected resonance to change level, got {:.2}dB",
+                filter_type,
+                raw_change
+            );
+            assert!(
+                compensated_change < 2.0,
+                "{:?}: auto-gain left a {:.2}dB level change",
+                filter_type,
+                compensated_change
+            );
+        }
+    }
+
+    #[test]
+    fn test_cutoff_mod_octaves_is_exponential() {
+        let size = 256;
+        let audio = vec![0.0f32; size];
+        let cutoff_mod = vec![1.0f32; size];
+        let settled_cutoff = |base_cutoff: f32| {
+            let mut fc = FilterCollection::new(TEST_SAMPLE_RATE);
+            fc.set_params(base_cutoff, 0.0);
+            fc.set_cutoff_mod_octaves(2.0);
+            fc.reset();
+            let mut inputs: FxHashMap<PortId, Vec<ModulationSource>> = FxHashMap::default();
+            for (port, buffer) in [
+                (PortId::AudioInput0, &audio),
+                (PortId::CutoffMod, &cutoff_mod),
+            ] {
+                inputs.insert(
+                    port,
+                    vec![ModulationSource {
+                        buffer: &buffer[..],
+                        amount: 1.0,
+                        mod_type: ModulationType::Additive,
+                        transformation: ModulationTransformation::None,
+                    }],
+                );
+            }
+            let mut out = vec![0.0f32; size];
+            for _ in 0..8 {
+                let mut outputs: FxHashMap<PortId, &mut [f32]> = FxHashMap::default();
+                outputs.insert(PortId::AudioOutput0, &mut out);
+                fc.process(&inputs, &mut outputs, size);
+            }
+            fc.smoothed_cutoff
+        };
+
+        // +1.0 opens the filter by two octaves whatever the base cutoff is.
+        assert!((settled_cutoff(250.0) - 1000.0).abs() < 1.0);
+        assert!((settled_cutoff(1000.0) - 4000.0).abs() < 4.0);
+    }
+
+    #[test]
+    fn test_drive_mod_adds_to_base_drive() {
+        let size = 512;
+        let audio: Vec<f32> = (0..size)
+            .map(|i| 0.8 * (2.0 * PI * 100.0 * i as f32 / TEST_SAMPLE_RATE).sin())
+            .collect();
+        let drive_mod = vec![1.0f32; size];
+        let render = |base_drive: f32, modulated: bool| {
+            let mut fc = FilterCollection::new(TEST_SAMPLE_RATE);
+            fc.set_filter_type(FilterType::Ladder);
+            fc.set_params(500.0, 0.2);
+            fc.set_drive(base_drive);
+            let mut inputs: FxHashMap<PortId, Vec<ModulationSource>> = FxHashMap::default();
+            let mut sources = vec![(PortId::AudioInput0, &audio)];
+            if modulated {
+                sources.push((PortId::DriveMod, &drive_mod));
+            }
+            for (port, buffer) in sources {
+                inputs.insert(
+                    port,
+                    vec![ModulationSource {
+                        buffer: &buffer[..],
+                        amount: 1.0,
+                        mod_type: ModulationType::Additive,
+                        transformation: ModulationTransformation::None,
+                    }],
+                );
+            }
+            let mut out = vec![0.0f32; size];
+            let mut outputs: FxHashMap<PortId, &mut [f32]> = FxHashMap::default();
+            outputs.insert(PortId::AudioOutput0, &mut out);
+            fc.process(&inputs, &mut outputs, size);
+            drop(outputs);
+            out
+        };
+
+        let modulated = render(0.0, true);
+        let fixed = render(1.0, false);
+        let max_diff = modulated
+            .iter()
+            .zip(&fixed)
+            .fold(0.0f32, |m, (a, b)| m.max((a - b).abs()));
+        assert!(max_diff < 1e-6, "{}", max_diff);
+        assert_ne!(modulated, render(0.0, false));
+    }
+
+    #[test]
+    fn test_oversampling_reduces_filter_fm_aliasing() {
+        // Ladder drive and audio-rate cutoff modulation push partials past Nyquist;
+        // at 1x they fold back between the harmonics of the 3.1 kHz input.
+        let size = ALIAS_RENDER_LENGTH;
+        let tone: Vec<f32> = (0..size)
+            .map(|i| (2.0 * PI * (i as f32 * 3_100.0 / TEST_SAMPLE_RATE).fract()).sin())
+            .collect();
+        let audio: Vec<f32> = tone.iter().map(|s| 0.8 * s).collect();
+        let alias_db = |factor: u32| {
+            let mut fc = FilterCollection::new(TEST_SAMPLE_RATE);
+            fc.set_filter_type(FilterType::Ladder);
+            fc.set_params(8_000.0, 0.3);
+            fc.set_drive(1.0);
+            fc.set_cutoff_mod_octaves(2.0);
+            fc.set_oversampling_factor(factor);
+            let mut inputs: FxHashMap<PortId, Vec<ModulationSource>> = FxHashMap::default();
+            for (port, buffer) in [(PortId::AudioInput0, &audio), (PortId::CutoffMod, &tone)] {
+                inputs.insert(
+                    port,
+                    vec![ModulationSource {
+                        buffer: &buffer[..],
+                        amount: 1.0,
+                        mod_type: ModulationType::Additive,
+                        transformation: ModulationTransformation::None,
+                    }],
+                );
+            }
+            let mut out = vec![0.0f32; size];
+            let mut outputs: FxHashMap<PortId, &mut [f32]> = FxHashMap::default();
+            outputs.insert(PortId::AudioOutput0, &mut out);
+            fc.process(&inputs, &mut outputs, size);
+            drop(outputs);
+            measure_aliasing(&out, TEST_SAMPLE_RATE, 3_100.0).alias_db
+        };
+
+        let plain = alias_db(1);
+        let doubled = alias_db(2);
+        let quadrupled = alias_db(4);
+        assert!(doubled < plain - 6.0, "{} vs {}", doubled, plain);
+        assert!(quadrupled < plain - 10.0, "{} vs {}", quadrupled, plain);
+    }
+
+    #[test]
+    fn test_biquad_types_respond_in_both_slopes() {
+        // Level at 100 Hz, the 1 kHz cutoff and 10 kHz with 6 dB of gain, which
+        // shelves and peaks shape and the other types apply as output gain.
+        let expected: [(FilterType, [Option<f32>; 3]); 6] = [
+            (FilterType::HighPass, [None, Some(0.0), Some(6.0)]),
+            (FilterType::Notch, [Some(6.0), None, Some(6.0)]),
+            (FilterType::AllPass, [Some(6.0), Some(6.0), Some(6.0)]),
+            (FilterType::LowShelf, [Some(6.0), Some(3.0), Some(0.0)]),
+            (FilterType::HighShelf, [Some(0.0), Some(3.0), Some(6.0)]),
+            (FilterType::Peaking, [Some(0.0), Some(6.0), Some(0.0)]),
+        ];
+        let response = |filter_type: FilterType, slope: FilterSlope| {
+            let mut fc = FilterCollection::new(TEST_SAMPLE_RATE);
+            fc.set_filter_type(filter_type);
+            fc.set_filter_slope(slope);
+            fc.set_params(1000.0, 0.0);
+            fc.set_gain_db(6.0);
+            let mag_db = fc.calculate_fft_magnitude(fc.create_impulse_response(FFT_LEN));
+            [100.0, 1000.0, 10_000.0].map(|freq| {
+                get_fft_magnitude_at_freq(&mag_db, freq, TEST_SAMPLE_RATE, FFT_LEN).unwrap()
+            })
+        };
+
+        for slope in [FilterSlope::Db12, FilterSlope::Db24] {
+            for (filter_type, levels) in expected {
+                let measured = response(filter_type, slope);
+                for (level, expected) in measured.iter().zip(levels) {
+                    if let Some(expected) = expected {
+                        assert!(
+                            (level - expected).abs() < 1.0,
+                            "{:?} {:?}: {:?}",
+                            filter_type,
+                            slope,
+                            measured
+                        );
+                    }
+                }
+            }
+            let high_pass = response(FilterType::HighPass, slope);
+            assert!(high_pass[0] < -20.0, "{:?}: {:?}", slope, high_pass);
+            let notch = response(FilterType::Notch, slope);
+            assert!(notch[1] < -20.0, "{:?}: {:?}", slope, notch);
+            let band_pass = response(FilterType::BandPass, slope);
+            assert!(
+                (band_pass[1] - 6.0).abs() < 1.0,
+                "{:?}: {:?}",
+                slope,
+                band_pass
+            );
+            assert!(
+                band_pass[0] < 0.0 && band_pass[2] < 0.0,
+                "{:?}: {:?}",
+                slope,
+                band_pass
+            );
+        }
+    }
+
+    #[test]
+    fn test_oversampling_factor_rounds_to_supported_values() {
+        let mut fc = FilterCollection::new(TEST_SAMPLE_RATE);
+        for (requested, expected) in [(0, 1), (1, 1), (2, 2), (3, 4), (16, 4)] {
+            fc.set_oversampling_factor(requested);
+            assert_eq!(fc.oversampling_factor(), expected);
+        }
+    }
+
+    fn render_feedback_filter(fc: &mut FilterCollection, start: usize, len: usize) -> Vec<f32> {
+        (start..start + len)
+            .map(|i| {
+                let x = 0.5 * (2.0 * PI * 220.0 * i as f32 / TEST_SAMPLE_RATE).sin();
+                match fc.filter_type {
+                    FilterType::Comb => fc.process_comb_sample(x, 220.0, 0.9, TEST_SAMPLE_RATE),
+                    _ => fc.process_ladder_sample(x, 800.0, 0.8, 0.3, 0.5, TEST_SAMPLE_RATE),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_double_precision_feedback_tracks_single() {
+        for filter_type in [FilterType::Ladder, FilterType::Comb] {
+            let mut single = FilterCollection::new(TEST_SAMPLE_RATE);
+            single.set_filter_type(filter_type);
+            let mut double = single.clone();
+            double.set_double_precision_feedback(true);
+
+            let a = render_feedback_filter(&mut single, 0, 4096);
+            let b = render_feedback_filter(&mut double, 0, 4096);
+            let max_diff = a
+                .iter()
+                .zip(b.iter())
+                .fold(0.0f32, |m, (x, y)| m.max((x - y).abs()));
+            assert!(max_diff < 1e-2, "{:?}: {}", filter_type, max_diff);
+        }
+    }
+
+    #[test]
+    fn test_switching_feedback_precision_keeps_state() {
+        for filter_type in [FilterType::Ladder, FilterType::Comb] {
+            let mut reference = FilterCollection::new(TEST_SAMPLE_RATE);
+            reference.set_filter_type(filter_type);
+            render_feedback_filter(&mut reference, 0, 2000);
+            let mut switched = reference.clone();
+            switched.set_double_precision_feedback(true);
+
+            let a = render_feedback_filter(&mut reference, 2000, 16);
+            let b = render_feedback_filter(&mut switched, 2000, 16);
+            // A reset would start from silence; a carried-over state continues the waveform.
+            assert!((a[0] - b[0]).abs() < 1e-3, "{:?}", filter_type);
+
+            switched.set_double_precision_feedback(false);
+            assert!(switched.comb_f64.buffer.is_empty());
+        }
+    }
 }
@@ -0,0 +1,437 @@
+use std::any::Any;
+use std::f32::consts::{PI, TAU};
+
+use rustc_hash::FxHashMap;
+
+use crate::graph::{ModulationProcessor, ModulationSource};
+use crate::traits::{AudioNode, PortId};
+use crate::utils::smoothing::{SmoothedParam, DEFAULT_SMOOTHING_MS};
+use crate::utils::tuning::{detune_ratio, resolve_detune_cents};
+
+/// Cycles of phase shift per unit of `PortId::PhaseMod` input at a mod index
+/// of 1. The input reads as radians: a full-scale modulator at index 1 swings
+/// the phase by ±1 radian. Every oscillator with a `PhaseMod` input uses it.
+pub const PHASE_MOD_SCALE: f32 = 1.0 / TAU;
+
+/// Self-feedback adds `last output * feedback / FEEDBACK_DIVISOR` cycles.
+pub const FEEDBACK_DIVISOR: f32 = PI * 1.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FmWaveform {
+    #[default]
+    Sine,
+    /// Positive half of a sine, silent for the second half of the cycle.
+    HalfSine,
+    /// Rectified sine.
+    AbsSine,
+    Triangle,
+    Square,
+    Saw,
+}
+
+impl FmWaveform {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::HalfSine,
+            2 => Self::AbsSine,
+            3 => Self::Triangle,
+            4 => Self::Square,
+            5 => Self::Saw,
+            _ => Self::Sine,
+        }
+    }
+
+    /// Value at `phase` (0..1).
+    #[inline]
+    fn value(self, phase: f32) -> f32 {
+        match self {
+            Self::Sine => (phase * TAU).sin(),
+            Self::HalfSine => {
+                if phase < 0.5 {
+                    (phase * TAU).sin()
+                } else {
+                    0.0
+                }
+            }
+            Self::AbsSine => (phase * TAU).sin().abs(),
+            Self::Triangle => 1.0 - 4.0 * ((phase + 0.25).rem_euclid(1.0) - 0.5).abs(),
+            Self::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Self::Saw => 2.0 * phase - 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FmOperatorConfig {
+    pub waveform: FmWaveform,
+    /// Run at `fixed_frequency` instead of `ratio` times the note frequency.
+    pub fixed: bool,
+    /// Multiple of the note frequency.
+    pub ratio: f32,
+    /// Frequency in Hz when `fixed`.
+    pub fixed_frequency: f32,
+    /// Coarse tuning in octaves and semitones, added to `detune`. Like it,
+    /// this scales the ratio frequency and the fixed frequency alike.
+    pub detune_oct: f32,
+    pub detune_semi: f32,
+    /// Fine tuning in cents.
+    pub detune: f32,
+    /// Self-modulation amount, as for the oscillators' feedback.
+    pub feedback: f32,
+    /// Output level; a modulator's level sets how hard it drives its carrier.
+    pub level: f32,
+    pub active: bool,
+}
+
+impl Default for FmOperatorConfig {
+    fn default() -> Self {
+        Self {
+            waveform: FmWaveform::Sine,
+            fixed: false,
+            ratio: 1.0,
+            fixed_frequency: 440.0,
+            detune_oct: 0.0,
+            detune_semi: 0.0,
+            detune: 0.0,
+            feedback: 0.0,
+            level: 1.0,
+            active: true,
+        }
+    }
+}
+
+/// A DX-style FM operator: one oscillator whose frequency is a ratio of the
+/// note frequency (or fixed), with self-feedback and an output level.
+///
+/// Chain operators by connecting one's output to another's `PhaseMod` input
+/// (additive); `ModIndex` scales the phase modulation (1 when unconnected),
+/// `GainMod` scales the level (connect the operator's envelope here) and
+/// `FeedbackMod` adds to the feedback. The phase restarts on each note so
+/// every note starts alike.
+pub struct FmOperator {
+    sample_rate: f32,
+    config: FmOperatorConfig,
+    level: SmoothedParam,
+    phase: f32,
+    /// The last two outputs; feedback uses their average, which keeps high
+    /// feedback from breaking into noise.
+    last_outputs: [f32; 2],
+    last_gate: f32,
+    freq_buf: Vec<f32>,
+    base_freq_buf: Vec<f32>,
+    phase_mod_buf: Vec<f32>,
+    mod_index_buf: Vec<f32>,
+    feedback_buf: Vec<f32>,
+    gain_buf: Vec<f32>,
+    gate_buf: Vec<f32>,
+    mod_add: Vec<f32>,
+    mod_mul: Vec<f32>,
+}
+
+impl ModulationProcessor for FmOperator {}
+
+impl FmOperator {
+    pub fn new(sample_rate: f32) -> Self {
+        let config = FmOperatorConfig::default();
+        let cap = 128;
+        Self {
+            sample_rate,
+            config,
+            level: SmoothedParam::new(config.level, sample_rate, DEFAULT_SMOOTHING_MS),
+            phase: 0.0,
+            last_outputs: [0.0; 2],
+            last_gate: 0.0,
+            freq_buf: vec![0.0; cap],
+            base_freq_buf: vec![0.0; cap],
+            phase_mod_buf: vec![0.0; cap],
+            mod_index_buf: vec![1.0; cap],
+            feedback_buf: vec![0.0; cap],
+            gain_buf: vec![1.0; cap],
+            gate_buf: vec![0.0; cap],
+            mod_add: vec![0.0; cap],
+            mod_mul: vec![1.0; cap],
+        }
+    }
+
+    pub fn update(&mut self, config: FmOperatorConfig) {
+        self.config = FmOperatorConfig {
+            ratio: config.ratio.max(0.0),
+            fixed_frequency: config.fixed_frequency.max(0.0),
+            feedback: config.feedback.max(0.0),
+            level: config.level.max(0.0),
+            ..config
+        };
+        self.level.set_target(self.config.level);
+        if !self.config.active {
+            self.reset();
+        }
+    }
+
+    pub fn config(&self) -> &FmOperatorConfig {
+        &self.config
+    }
+
+    fn ensure_buffers(&mut self, size: usize) {
+        for buf in [
+            &mut self.freq_buf,
+            &mut self.base_freq_buf,
+            &mut self.phase_mod_buf,
+            &mut self.mod_index_buf,
+            &mut self.feedback_buf,
+            &mut self.gain_buf,
+            &mut self.gate_buf,
+            &mut self.mod_add,
+            &mut self.mod_mul,
+        ] {
+            if buf.len() < size {
+                buf.resize(size, 0.0);
+            }
+        }
+    }
+
+    /// Combines the modulation on `port` with `base` into `target`.
+    fn modulated(
+        inputs: &FxHashMap<PortId, Vec<ModulationSource>>,
+        port: PortId,
+        base: f32,
+        target: &mut [f32],
+        add: &mut [f32],
+        mul: &mut [f32],
+    ) {
+        let len = target.len();
+        Self::accumulate_modulations_inplace(
+            len,
+            inputs.get(&port).map(|sources| sources.as_slice()),
+            add,
+            mul,
+        );
+        Self::combine_modulation_inplace(target, len, base, add, mul);
+    }
+}
+
+impl AudioNode for FmOperator {
+    fn get_ports(&self) -> FxHashMap<PortId, bool> {
+        let mut ports = FxHashMap::default();
+        ports.insert(PortId::GlobalFrequency, false);
+        ports.insert(PortId::FrequencyMod, false);
+        ports.insert(PortId::PhaseMod, false);
+        ports.insert(PortId::ModIndex, false);
+        ports.insert(PortId::FeedbackMod, false);
+        ports.insert(PortId::GainMod, false);
+        ports.insert(PortId::CombinedGate, false);
+        ports.insert(PortId::AudioOutput0, true);
+        ports
+    }
+
+    fn process<'a>(
+        &mut self,
+        inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+        outputs: &mut FxHashMap<PortId, &mut [f32]>,
+        buffer_size: usize,
+    ) {
+        self.ensure_buffers(buffer_size);
+        let n = buffer_size;
+        let (add, mul) = (&mut self.mod_add[..n], &mut self.mod_mul[..n]);
+
+        let feedback = self.config.feedback;
+        for (port, base, target) in [
+            (PortId::PhaseMod, 0.0, &mut self.phase_mod_buf),
+            (PortId::ModIndex, 1.0, &mut self.mod_index_buf),
+            (PortId::FeedbackMod, feedback, &mut self.feedback_buf),
+            (PortId::GainMod, 1.0, &mut self.gain_buf),
+            (PortId::CombinedGate, 0.0, &mut self.gate_buf),
+        ] {
+            Self::modulated(inputs, port, base, &mut target[..n], add, mul);
+        }
+
+        // Note frequency times the ratio, or the fixed frequency.
+        let note = inputs
+            .get(&PortId::GlobalFrequency)
+            .and_then(|sources| sources.first())
+            .map(|source| source.buffer)
+            .filter(|buffer| !buffer.is_empty());
+        let detune = detune_ratio(resolve_detune_cents(
+            self.config.detune_oct,
+            self.config.detune_semi,
+            self.config.detune,
+            0.0,
+        ));
+        for (i, base) in self.base_freq_buf[..n].iter_mut().enumerate() {
+            *base = if self.config.fixed {
+                self.config.fixed_frequency * detune
+            } else {
+                let note_freq = note.map_or(440.0, |buffer| buffer[i.min(buffer.len() - 1)]);
+                note_freq * self.config.ratio * detune
+            };
+        }
+        Self::accumulate_modulations_inplace(
+            n,
+            inputs
+                .get(&PortId::FrequencyMod)
+                .map(|sources| sources.as_slice()),
+            add,
+            mul,
+        );
+        Self::combine_modulation_inplace_varying_base(
+            &mut self.freq_buf[..n],
+            n,
+            &self.base_freq_buf[..n],
+            add,
+            mul,
+        );
+
+        let Some(output) = outputs.get_mut(&PortId::AudioOutput0) else {
+            return;
+        };
+        if !self.config.active {
+            output[..n].fill(0.0);
+            return;
+        }
+
+        let nyquist = self.sample_rate * 0.5;
+        let sample_rate_recip = 1.0 / self.sample_rate;
+        for (i, out) in output[..n].iter_mut().enumerate() {
+            let gate = self.gate_buf[i];
+            if gate > 0.0 && self.last_gate <= 0.0 {
+                self.phase = 0.0;
+                self.last_outputs = [0.0; 2];
+            }
+            self.last_gate = gate;
+
+            let freq = self.freq_buf[i].clamp(0.0, nyquist);
+            self.phase = (self.phase + freq * sample_rate_recip).rem_euclid(1.0);
+            let feedback =
+                (self.last_outputs[0] + self.last_outputs[1]) * 0.5 * self.feedback_buf[i]
+                    / FEEDBACK_DIVISOR;
+            let phase_mod = self.phase_mod_buf[i] * self.mod_index_buf[i] * PHASE_MOD_SCALE;
+            let sample = self
+                .config
+                .waveform
+                .value((self.phase + phase_mod + feedback).rem_euclid(1.0));
+            self.last_outputs = [sample, self.last_outputs[0]];
+            *out = sample * self.gain_buf[i] * self.level.next_value();
+        }
+    }
+
+    fn reset(&mut self) {
+        self.phase = 0.0;
+        self.last_outputs = [0.0; 2];
+        self.last_gate = 0.0;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn is_active(&self) -> bool {
+        self.config.active
+    }
+
+    fn set_active(&mut self, active: bool) {
+        self.config.active = active;
+        if !active {
+            self.reset();
+        }
+    }
+
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.level.set_time_ms(time_ms);
+    }
+
+    fn name(&self) -> &'static str {
+        "FM Operator"
+    }
+
+    fn node_type(&self) -> &str {
+        "fm_operator"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{ModulationTransformation, ModulationType};
+
+    fn source(buffer: &[f32]) -> ModulationSource<'_> {
+        ModulationSource {
+            buffer,
+            amount: 1.0,
+            mod_type: ModulationType::Additive,
+            transformation: ModulationTransformation::None,
+        }
+    }
+
+    fn render(op: &mut FmOperator, inputs: &FxHashMap<PortId, Vec<ModulationSource>>) -> Vec<f32> {
+        let mut out = vec![0.0; 64];
+        let mut outputs = FxHashMap::default();
+        outputs.insert(PortId::AudioOutput0, out.as_mut_slice());
+        op.process(inputs, &mut outputs, 64);
+        out
+    }
+
+    #[test]
+    fn ratio_tracks_the_note_and_phase_mod_is_in_radians() {
+        // A 6.4 kHz rate makes one cycle of 100 Hz exactly 64 samples.
+        let mut op = FmOperator::new(6_400.0);
+        op.update(FmOperatorConfig {
+            ratio: 2.0,
+            ..Default::default()
+        });
+        let note = vec![50.0; 64];
+        let mut inputs = FxHashMap::default();
+        inputs.insert(PortId::GlobalFrequency, vec![source(&note)]);
+        let out = render(&mut op, &inputs);
+        assert!((out[16] - (TAU * 17.0 / 64.0).sin()).abs() < 1e-4);
+
+        // A constant PI/2 at the PhaseMod input turns the sine into a cosine.
+        let mut op = FmOperator::new(6_400.0);
+        op.update(FmOperatorConfig {
+            ratio: 2.0,
+            ..Default::default()
+        });
+        let offset = vec![PI / 2.0; 64];
+        inputs.insert(PortId::PhaseMod, vec![source(&offset)]);
+        let shifted = render(&mut op, &inputs);
+        assert!((shifted[16] - (TAU * 17.0 / 64.0).cos()).abs() < 1e-4);
+
+        op.update(FmOperatorConfig {
+            active: false,
+            ..Default::default()
+        });
+        assert!(render(&mut op, &inputs).iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn coarse_tuning_applies_in_ratio_and_fixed_mode() {
+        let note = vec![50.0; 64];
+        let mut inputs = FxHashMap::default();
+        inputs.insert(PortId::GlobalFrequency, vec![source(&note)]);
+        // One octave and twelve semitones up: 50 Hz becomes 200 Hz either way.
+        for fixed in [false, true] {
+            let mut op = FmOperator::new(6_400.0);
+            op.update(FmOperatorConfig {
+                fixed,
+                fixed_frequency: 50.0,
+                detune_oct: 1.0,
+                detune_semi: 12.0,
+                ..Default::default()
+            });
+            let out = render(&mut op, &inputs);
+            assert!(
+                (out[16] - (TAU * 34.0 / 64.0).sin()).abs() < 1e-4,
+                "fixed = {}",
+                fixed
+            );
+        }
+    }
+}
@@ -0,0 +1,406 @@
+use std::any::Any;
+use std::f32::consts::PI;
+
+use rustc_hash::FxHashMap;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::graph::{ModulationProcessor, ModulationSource, ModulationTarget, TargetMapping};
+use crate::traits::{AudioNode, PortId};
+use crate::utils::smoothing::{SmoothedParam, DEFAULT_SMOOTHING_MS};
+
+/// The filter coefficients are recomputed every this many samples.
+const CONTROL_INTERVAL: usize = 16;
+/// Formant shift at full `gender`, in octaves either way.
+const MAX_SHIFT_OCTAVES: f32 = 0.4;
+const FORMANTS: usize = 3;
+
+/// Centre frequency (Hz), bandwidth (Hz) and linear gain of the first three
+/// formants of each vowel, sung by a tenor.
+const VOWEL_FORMANTS: [[(f32, f32, f32); FORMANTS]; 5] = [
+    [
+        (650.0, 80.0, 1.0),
+        (1080.0, 90.0, 0.501),
+        (2650.0, 120.0, 0.447),
+    ],
+    [
+        (400.0, 70.0, 1.0),
+        (1700.0, 80.0, 0.200),
+        (2600.0, 100.0, 0.251),
+    ],
+    [
+        (290.0, 40.0, 1.0),
+        (1870.0, 90.0, 0.178),
+        (2800.0, 100.0, 0.126),
+    ],
+    [
+        (400.0, 40.0, 1.0),
+        (800.0, 80.0, 0.316),
+        (2600.0, 100.0, 0.251),
+    ],
+    [
+        (350.0, 40.0, 1.0),
+        (600.0, 60.0, 0.100),
+        (2700.0, 100.0, 0.141),
+    ],
+];
+
+/// Vowel a `FormantFilter` is tuned to.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FormantVowel {
+    #[default]
+    A = 0,
+    E = 1,
+    I = 2,
+    O = 3,
+    U = 4,
+}
+
+impl FormantVowel {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => FormantVowel::E,
+            2 => FormantVowel::I,
+            3 => FormantVowel::O,
+            4 => FormantVowel::U,
+            _ => FormantVowel::A,
+        }
+    }
+}
+
+/// Vowel filter: three parallel band-passes tuned to the formants of a vowel.
+///
+/// `FormantMod` morphs away from the selected vowel along A-E-I-O-U, where
+/// +1.0 covers the whole row. `gender` shifts every formant up (smaller
+/// vocal tract) or down (larger), and `resonance` narrows the bands.
+#[derive(Clone)]
+pub struct FormantFilter {
+    enabled: bool,
+    sample_rate: f32,
+    vowel: FormantVowel,
+    gender: SmoothedParam,
+    resonance: SmoothedParam,
+    mix: SmoothedParam,
+    // SVF state per channel and formant: (ic1eq, ic2eq).
+    state: [[(f32, f32); FORMANTS]; 2],
+    g: [f32; FORMANTS],
+    k: [f32; FORMANTS],
+    gain: [f32; FORMANTS],
+
+    in_left: Vec<f32>,
+    in_right: Vec<f32>,
+    mod_add: Vec<f32>,
+    mod_mult: Vec<f32>,
+}
+
+impl FormantFilter {
+    pub fn new(sample_rate: f32) -> Self {
+        let initial_capacity = 128;
+        let mut filter = Self {
+            enabled: true,
+            sample_rate,
+            vowel: FormantVowel::A,
+            gender: SmoothedParam::new(0.0, sample_rate, DEFAULT_SMOOTHING_MS),
+            resonance: SmoothedParam::new(0.5, sample_rate, DEFAULT_SMOOTHING_MS),
+            mix: SmoothedParam::new(1.0, sample_rate, DEFAULT_SMOOTHING_MS),
+            state: [[(0.0, 0.0); FORMANTS]; 2],
+            g: [0.0; FORMANTS],
+            k: [1.0; FORMANTS],
+            gain: [0.0; FORMANTS],
+            in_left: vec![0.0; initial_capacity],
+            in_right: vec![0.0; initial_capacity],
+            mod_add: vec![0.0; initial_capacity],
+            mod_mult: vec![1.0; initial_capacity],
+        };
+        filter.update_coefficients(filter.position(0.0));
+        filter
+    }
+
+    pub fn set_vowel(&mut self, vowel: FormantVowel) {
+        self.vowel = vowel;
+    }
+
+    pub fn vowel(&self) -> FormantVowel {
+        self.vowel
+    }
+
+    /// Shifts the formants: -1.0 is a larger (lower) voice, 1.0 a smaller one.
+    pub fn set_gender(&mut self, gender: f32) {
+        self.gender.set_target(gender.clamp(-1.0, 1.0));
+    }
+
+    pub fn gender(&self) -> f32 {
+        self.gender.target()
+    }
+
+    /// Band sharpness from 0.0 (twice the natural bandwidth) to 1.0 (half of it).
+    pub fn set_resonance(&mut self, resonance: f32) {
+        self.resonance.set_target(resonance.clamp(0.0, 1.0));
+    }
+
+    pub fn resonance(&self) -> f32 {
+        self.resonance.target()
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix.set_target(mix.clamp(0.0, 1.0));
+    }
+
+    pub fn mix(&self) -> f32 {
+        self.mix.target()
+    }
+
+    /// Position along A-E-I-O-U (0.0 to 4.0) for a `FormantMod` value.
+    fn position(&self, modulation: f32) -> f32 {
+        let last = (VOWEL_FORMANTS.len() - 1) as f32;
+        (self.vowel as u8 as f32 + modulation * last).clamp(0.0, last)
+    }
+
+    fn update_coefficients(&mut self, position: f32) {
+        let lower = position.floor() as usize;
+        let upper = (lower + 1).min(VOWEL_FORMANTS.len() - 1);
+        let t = position - lower as f32;
+        let shift = (self.gender.current() * MAX_SHIFT_OCTAVES).exp2();
+        let narrowing = (1.0 - 2.0 * self.resonance.current()).exp2();
+        let max_frequency = self.sample_rate * 0.45;
+        let pairs = VOWEL_FORMANTS[lower].iter().zip(&VOWEL_FORMANTS[upper]);
+        for (n, (&(f0, b0, a0), &(f1, b1, a1))) in pairs.enumerate() {
+            let frequency = ((f0 + (f1 - f0) * t) * shift).min(max_frequency);
+            let bandwidth = (b0 + (b1 - b0) * t) * shift * narrowing;
+            self.g[n] = (PI * frequency / self.sample_rate).tan();
+            self.k[n] = bandwidth / frequency;
+            self.gain[n] = a0 + (a1 - a0) * t;
+        }
+    }
+
+    /// Sum of the formant band-passes, each normalised to unity gain at its centre.
+    #[inline]
+    fn formants(&mut self, channel: usize, input: f32) -> f32 {
+        let mut out = 0.0;
+        for n in 0..FORMANTS {
+            let (g, k) = (self.g[n], self.k[n]);
+            let (ic1eq, ic2eq) = self.state[channel][n];
+            let a1 = 1.0 / (1.0 + g * (g + k));
+            let v1 = a1 * (ic1eq + g * (input - ic2eq));
+            let v2 = ic2eq + g * v1;
+            self.state[channel][n] = (2.0 * v1 - ic1eq, 2.0 * v2 - ic2eq);
+            out += k * v1 * self.gain[n];
+        }
+        out
+    }
+
+    fn ensure_scratch_buffers(&mut self, size: usize) {
+        for buf in [&mut self.in_left, &mut self.in_right, &mut self.mod_add] {
+            if buf.len() < size {
+                buf.resize(size.next_power_of_two(), 0.0);
+            }
+        }
+        if self.mod_mult.len() < size {
+            self.mod_mult.resize(size.next_power_of_two(), 1.0);
+        }
+    }
+
+    fn sum_input(
+        inputs: &FxHashMap<PortId, Vec<ModulationSource>>,
+        port: PortId,
+        target: &mut [f32],
+    ) -> bool {
+        target.fill(0.0);
+        let Some(sources) = inputs.get(&port).filter(|sources| !sources.is_empty()) else {
+            return false;
+        };
+        for source in sources {
+            Self::apply_add(source.buffer, target, source.amount, source.transformation);
+        }
+        true
+    }
+}
+
+impl ModulationProcessor for FormantFilter {}
+
+impl AudioNode for FormantFilter {
+    fn get_ports(&self) -> FxHashMap<PortId, bool> {
+        [
+            (PortId::AudioInput0, false),
+            (PortId::AudioInput1, false),
+            (PortId::FormantMod, false),
+            (PortId::AudioOutput0, true),
+            (PortId::AudioOutput1, true),
+        ]
+        .iter()
+        .cloned()
+        .collect()
+    }
+
+    fn process<'a>(
+        &mut self,
+        inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+        outputs: &mut FxHashMap<PortId, &mut [f32]>,
+        buffer_size: usize,
+    ) {
+        let n = buffer_size;
+        self.ensure_scratch_buffers(n);
+        Self::sum_input(inputs, PortId::AudioInput0, &mut self.in_left[..n]);
+        let stereo = Self::sum_input(inputs, PortId::AudioInput1, &mut self.in_right[..n]);
+        if !stereo {
+            let (left, right) = (&self.in_left, &mut self.in_right);
+            right[..n].copy_from_slice(&left[..n]);
+        }
+        Self::accumulate_modulations_inplace(
+            n,
+            inputs
+                .get(&PortId::FormantMod)
+                .map(|sources| sources.as_slice()),
+            &mut self.mod_add[..n],
+            &mut self.mod_mult[..n],
+        );
+
+        let outs = outputs.get_disjoint_mut([&PortId::AudioOutput0, &PortId::AudioOutput1]);
+        let [Some(out_left), Some(out_right)] = outs else {
+            panic!("Missing stereo output buffers");
+        };
+        let out_left: &mut [f32] = out_left;
+        let out_right: &mut [f32] = out_right;
+
+        for i in 0..n {
+            let (l, r) = (self.in_left[i], self.in_right[i]);
+            self.gender.next_value();
+            self.resonance.next_value();
+            let mix = self.mix.next_value();
+            if i % CONTROL_INTERVAL == 0 {
+                let position = self.position(self.mod_add[i] * self.mod_mult[i]);
+                self.update_coefficients(position);
+            }
+
+            let wet_l = self.formants(0, l);
+            let wet_r = self.formants(1, r);
+            out_left[i] = l + (wet_l - l) * mix;
+            out_right[i] = r + (wet_r - r) * mix;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.state = [[(0.0, 0.0); FORMANTS]; 2];
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn is_active(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.gender.set_time_ms(time_ms);
+        self.resonance.set_time_ms(time_ms);
+        self.mix.set_time_ms(time_ms);
+    }
+
+    fn set_active(&mut self, active: bool) {
+        if !active && self.enabled {
+            self.reset();
+        }
+        self.enabled = active;
+    }
+
+    fn modulation_target(&self, port: PortId) -> Option<ModulationTarget> {
+        match port {
+            PortId::FormantMod => Some(ModulationTarget {
+                parameter: "vowel",
+                unit: "",
+                base: self.position(0.0) / (VOWEL_FORMANTS.len() - 1) as f32,
+                min: 0.0,
+                max: 1.0,
+                mapping: TargetMapping::Linear,
+            }),
+            _ => None,
+        }
+    }
+
+    fn clone_node(&self) -> Option<Box<dyn AudioNode>> {
+        Some(Box::new(self.clone()))
+    }
+
+    fn name(&self) -> &'static str {
+        "Formant Filter"
+    }
+
+    fn node_type(&self) -> &str {
+        "formant_filter"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{ModulationTransformation, ModulationType};
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    fn run(filter: &mut FormantFilter, input: &[f32], formant_mod: Option<&[f32]>) -> Vec<f32> {
+        let source = |buffer| ModulationSource {
+            buffer,
+            amount: 1.0,
+            mod_type: ModulationType::Additive,
+            transformation: ModulationTransformation::None,
+        };
+        let mut inputs = FxHashMap::default();
+        inputs.insert(PortId::AudioInput0, vec![source(input)]);
+        if let Some(formant_mod) = formant_mod {
+            inputs.insert(PortId::FormantMod, vec![source(formant_mod)]);
+        }
+        let mut left = vec![0.0; input.len()];
+        let mut right = vec![0.0; input.len()];
+        {
+            let mut outputs = FxHashMap::default();
+            outputs.insert(PortId::AudioOutput0, left.as_mut_slice());
+            outputs.insert(PortId::AudioOutput1, right.as_mut_slice());
+            filter.process(&inputs, &mut outputs, input.len());
+        }
+        assert_eq!(left, right);
+        left
+    }
+
+    /// Level of `freq` through the filter, after the bands have settled.
+    fn level(filter: &mut FormantFilter, freq: f32, formant_mod: f32) -> f32 {
+        let input: Vec<f32> = (0..9_600)
+            .map(|n| (2.0 * PI * freq * n as f32 / SAMPLE_RATE).sin())
+            .collect();
+        let modulation = vec![formant_mod; input.len()];
+        filter.reset();
+        let out = run(filter, &input, Some(&modulation));
+        out[4_800..].iter().fold(0.0f32, |max, &x| max.max(x.abs()))
+    }
+
+    #[test]
+    fn vowels_and_formant_mod_move_the_formants() {
+        let mut filter = FormantFilter::new(SAMPLE_RATE);
+        // A has its second formant at 1080 Hz, I at 1870 Hz.
+        assert!(level(&mut filter, 1_080.0, 0.0) > 5.0 * level(&mut filter, 1_870.0, 0.0));
+        filter.set_vowel(FormantVowel::I);
+        let i_level = level(&mut filter, 1_870.0, 0.0);
+        assert!(i_level > 0.15);
+        assert!(level(&mut filter, 1_080.0, 0.0) < 0.05);
+
+        // Half of the row from A lands on I as well.
+        filter.set_vowel(FormantVowel::A);
+        assert!((level(&mut filter, 1_870.0, 0.5) - i_level).abs() < 1e-3);
+    }
+
+    #[test]
+    fn gender_shifts_the_formants() {
+        let mut filter = FormantFilter::new(SAMPLE_RATE);
+        let shifted = 650.0 * MAX_SHIFT_OCTAVES.exp2();
+        let neutral = level(&mut filter, shifted, 0.0);
+        filter.set_gender(1.0);
+        filter.gender.set_immediate(1.0);
+        assert!(level(&mut filter, shifted, 0.0) > 2.0 * neutral);
+    }
+}
@@ -1,5 +1,7 @@
 use crate::graph::ModulationSource;
 use crate::traits::{AudioNode, PortId};
+use crate::utils::sidechain::{sidechain_key, SidechainFollower};
+use crate::utils::smoothing::{SmoothedParam, DEFAULT_SMOOTHING_MS};
 use rustc_hash::FxHashMap;
 use std::any::Any;
 use std::simd::f32x4;
@@ -93,11 +95,11 @@ pub struct Freeverb {
     enabled: bool,
 
     // Parameters
-    room_size: f32, // 0..1; controls comb filter feedback.
-    damp: f32,      // 0..1; dampening inside comb filters.
-    wet: f32,       // Wet mix level.
-    dry: f32,       // Dry mix level.
-    width: f32,     // Stereo width (0 = narrow, 1 = wide).
+    room_size: f32,     // 0..1; controls comb filter feedback.
+    damp: f32,          // 0..1; dampening inside comb filters.
+    wet: f32,           // Wet mix level.
+    dry: SmoothedParam, // Dry mix level.
+    width: f32,         // Stereo width (0 = narrow, 1 = wide).
 
     // 8 comb filters per channel.
     comb_filters_l: Vec<CombFilter>,
@@ -107,9 +109,17 @@ pub struct Freeverb {
     allpass_filters_l: Vec<AllpassFilter>,
     allpass_filters_r: Vec<AllpassFilter>,
 
-    // Precomputed stereo wet mix coefficients.
-    wet1: f32,
-    wet2: f32,
+    // Precomputed stereo wet mix coefficients (smoothed towards `wet`/`width`).
+    wet1: SmoothedParam,
+    wet2: SmoothedParam,
+
+    // Gated reverb: when enabled, the sidechain key opens and closes the tail.
+    gate_enabled: bool,
+    gate: SidechainFollower,
+
+    // Freeze: combs run lossless (unity feedback, no damping) and the input fades out.
+    frozen: bool,
+    input_gain: SmoothedParam,
 }
 
 impl Freeverb {
@@ -180,38 +190,84 @@ impl Freeverb {
             room_size,
             damp,
             wet,
-            dry,
+            dry: SmoothedParam::new(dry, sample_rate, DEFAULT_SMOOTHING_MS),
             width,
             comb_filters_l,
             comb_filters_r,
             allpass_filters_l,
             allpass_filters_r,
-            wet1,
-            wet2,
+            wet1: SmoothedParam::new(wet1, sample_rate, DEFAULT_SMOOTHING_MS),
+            wet2: SmoothedParam::new(wet2, sample_rate, DEFAULT_SMOOTHING_MS),
+            gate_enabled: false,
+            gate: SidechainFollower::new(sample_rate, 1.0, 60.0),
+            frozen: false,
+            input_gain: SmoothedParam::new(1.0, sample_rate, DEFAULT_SMOOTHING_MS),
         }
     }
 
     /// Adjust the room size (affecting comb filter feedback).
     pub fn set_room_size(&mut self, room_size: f32) {
         self.room_size = room_size.clamp(0.0, 1.0);
-        for comb in self.comb_filters_l.iter_mut() {
-            comb.feedback = self.room_size;
-        }
-        for comb in self.comb_filters_r.iter_mut() {
-            comb.feedback = self.room_size;
-        }
+        self.update_combs();
     }
 
     /// Adjust the damping.
     pub fn set_damp(&mut self, damp: f32) {
         self.damp = damp.clamp(0.0, 1.0);
-        for comb in self.comb_filters_l.iter_mut() {
-            comb.damp1 = self.damp;
-            comb.damp2 = 1.0 - self.damp;
-        }
-        for comb in self.comb_filters_r.iter_mut() {
-            comb.damp1 = self.damp;
-            comb.damp2 = 1.0 - self.damp;
+        self.update_combs();
+    }
+
+    /// Freezes the current tail: the input is faded out and the combs hold
+    /// their contents indefinitely. Damping is bypassed while frozen; with a
+    /// lowpass in a unity-gain loop the tail would collapse towards DC.
+    pub fn set_freeze(&mut self, frozen: bool) {
+        self.frozen = frozen;
+        self.input_gain.set_target(if frozen { 0.0 } else { 1.0 });
+        self.update_combs();
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    pub fn room_size(&self) -> f32 {
+        self.room_size
+    }
+
+    pub fn damp(&self) -> f32 {
+        self.damp
+    }
+
+    pub fn wet(&self) -> f32 {
+        self.wet
+    }
+
+    pub fn dry(&self) -> f32 {
+        self.dry.target()
+    }
+
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    pub fn gate_enabled(&self) -> bool {
+        self.gate_enabled
+    }
+
+    fn update_combs(&mut self) {
+        let (feedback, damp) = if self.frozen {
+            (1.0, 0.0)
+        } else {
+            (self.room_size, self.damp)
+        };
+        for comb in self
+            .comb_filters_l
+            .iter_mut()
+            .chain(self.comb_filters_r.iter_mut())
+        {
+            comb.feedback = feedback;
+            comb.damp1 = damp;
+            comb.damp2 = 1.0 - damp;
         }
     }
 
@@ -223,7 +279,7 @@ impl Freeverb {
 
     /// Adjust the dry mix.
     pub fn set_dry(&mut self, dry: f32) {
-        self.dry = dry.clamp(0.0, 1.0);
+        self.dry.set_target(dry.clamp(0.0, 1.0));
     }
 
     /// Adjust the stereo width.
@@ -232,9 +288,16 @@ impl Freeverb {
         self.update_wet_mix();
     }
 
+    /// Gates the reverb tail with the sidechain key. Without a key connected the
+    /// reverb stays open.
+    pub fn set_gate_enabled(&mut self, enabled: bool) {
+        self.gate_enabled = enabled;
+        self.gate.reset();
+    }
+
     fn update_wet_mix(&mut self) {
-        self.wet1 = self.wet * (self.width / 2.0 + 0.5);
-        self.wet2 = self.wet * (0.5 - self.width / 2.0);
+        self.wet1.set_target(self.wet * (self.width / 2.0 + 0.5));
+        self.wet2.set_target(self.wet * (0.5 - self.width / 2.0));
     }
 
     /// Reset all filters.
@@ -252,6 +315,17 @@ impl Freeverb {
             ap.reset();
         }
     }
+
+    /// Bytes held by the comb and allpass delay lines.
+    pub fn memory_bytes(&self) -> usize {
+        let combs = self.comb_filters_l.iter().chain(&self.comb_filters_r);
+        let allpasses = self.allpass_filters_l.iter().chain(&self.allpass_filters_r);
+        let samples: usize = combs.map(|comb| comb.buffer.capacity()).sum::<usize>()
+            + allpasses
+                .map(|allpass| allpass.buffer.capacity())
+                .sum::<usize>();
+        samples * std::mem::size_of::<f32>()
+    }
 }
 
 impl AudioNode for Freeverb {
@@ -259,6 +333,7 @@ impl AudioNode for Freeverb {
         let mut ports = FxHashMap::default();
         ports.insert(PortId::AudioInput0, false); // Left input
         ports.insert(PortId::AudioInput1, false); // Right input
+        ports.insert(PortId::SidechainInput, false); // Gate key
         ports.insert(PortId::AudioOutput0, true); // Left output
         ports.insert(PortId::AudioOutput1, true); // Right output
         ports
@@ -287,6 +362,11 @@ impl AudioNode for Freeverb {
                 static ZERO_BUFFER: [f32; 1024] = [0.0; 1024];
                 &ZERO_BUFFER[..buffer_size.min(ZERO_BUFFER.len())]
             });
+        let gate_key = if self.gate_enabled {
+            sidechain_key(inputs, buffer_size)
+        } else {
+            None
+        };
         let outs = outputs.get_disjoint_mut([&PortId::AudioOutput0, &PortId::AudioOutput1]);
         let [Some(out_left), Some(out_right)] = outs else {
             panic!("Missing stereo output buffers");
@@ -309,12 +389,14 @@ impl AudioNode for Freeverb {
             let mut temp_reverb_l = [0.0f32; 4];
             let mut temp_reverb_r = [0.0f32; 4];
 
+            let send = fixed_gain * self.input_gain.advance(block_size);
+
             // Process per sample in the current block.
             for j in 0..block_size {
                 let idx = i + j;
                 // Apply fixed gain to each channel separately.
-                let input_l = left_in[idx] * fixed_gain;
-                let input_r = right_in[idx] * fixed_gain;
+                let input_l = left_in[idx] * send;
+                let input_r = right_in[idx] * send;
 
                 // --- Comb Filter Processing for Left Channel ---
                 let c0 = self.comb_filters_l[0].process(input_l);
@@ -356,20 +438,28 @@ impl AudioNode for Freeverb {
                 let phase_shift = 0.02;
                 let new_reverb_r = reverb_r + reverb_l * phase_shift;
                 let new_reverb_l = reverb_l - new_reverb_r * phase_shift;
-                temp_reverb_l[j] = new_reverb_l;
-                temp_reverb_r[j] = new_reverb_r;
+                let gate = match gate_key {
+                    Some(key) => self.gate.next(key.get(idx).copied().unwrap_or(0.0)),
+                    None => 1.0,
+                };
+                temp_reverb_l[j] = new_reverb_l * gate;
+                temp_reverb_r[j] = new_reverb_r * gate;
             }
 
             // --- Final Mixing of Dry and Wet Signals ---
+            // Levels glide once per block to avoid zipper noise.
+            let dry = self.dry.advance(block_size);
+            let wet1 = self.wet1.advance(block_size);
+            let wet2 = self.wet2.advance(block_size);
             if block_size == simd_block {
                 let in_left_block = f32x4::from_slice(&left_in[i..i + simd_block]);
                 let in_right_block = f32x4::from_slice(&right_in[i..i + simd_block]);
-                let dry_left = in_left_block * f32x4::splat(self.dry);
-                let dry_right = in_right_block * f32x4::splat(self.dry);
+                let dry_left = in_left_block * f32x4::splat(dry);
+                let dry_right = in_right_block * f32x4::splat(dry);
                 let reverb_l_vec = f32x4::from_array(temp_reverb_l);
                 let reverb_r_vec = f32x4::from_array(temp_reverb_r);
-                let wet1_vec = f32x4::splat(self.wet1);
-                let wet2_vec = f32x4::splat(self.wet2);
+                let wet1_vec = f32x4::splat(wet1);
+                let wet2_vec = f32x4::splat(wet2);
 
                 let mixed_left = dry_left + wet1_vec * reverb_l_vec + wet2_vec * reverb_r_vec;
                 let mixed_right = dry_right + wet1_vec * reverb_r_vec + wet2_vec * reverb_l_vec;
@@ -378,12 +468,10 @@ impl AudioNode for Freeverb {
             } else {
                 for j in 0..block_size {
                     let idx = i + j;
-                    let dry_l = left_in[idx] * self.dry;
-                    let dry_r = right_in[idx] * self.dry;
-                    out_left[idx] =
-                        dry_l + self.wet1 * temp_reverb_l[j] + self.wet2 * temp_reverb_r[j];
-                    out_right[idx] =
-                        dry_r + self.wet1 * temp_reverb_r[j] + self.wet2 * temp_reverb_l[j];
+                    let dry_l = left_in[idx] * dry;
+                    let dry_r = right_in[idx] * dry;
+                    out_left[idx] = dry_l + wet1 * temp_reverb_l[j] + wet2 * temp_reverb_r[j];
+                    out_right[idx] = dry_r + wet1 * temp_reverb_r[j] + wet2 * temp_reverb_l[j];
                 }
             }
             i += block_size;
@@ -392,6 +480,7 @@ impl AudioNode for Freeverb {
 
     fn reset(&mut self) {
         self.reset_filters();
+        self.gate.reset();
     }
 
     fn as_any_mut(&mut self) -> &mut dyn Any {
@@ -406,6 +495,13 @@ impl AudioNode for Freeverb {
         self.enabled
     }
 
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.dry.set_time_ms(time_ms);
+        self.wet1.set_time_ms(time_ms);
+        self.wet2.set_time_ms(time_ms);
+        self.input_gain.set_time_ms(time_ms);
+    }
+
     fn set_active(&mut self, active: bool) {
         self.enabled = active;
         if active {
@@ -0,0 +1,335 @@
+use std::any::Any;
+
+use rustc_hash::FxHashMap;
+
+use crate::graph::ModulationSource;
+use crate::traits::{AudioNode, PortId};
+
+/// Longest trigger delay, in seconds.
+pub const MAX_GATE_DELAY_SECONDS: f32 = 4.0;
+/// Most sub-triggers one incoming trigger can be multiplied into.
+pub const MAX_GATE_MULTIPLICATION: u32 = 8;
+
+#[derive(Clone, Copy)]
+struct GateEvent {
+    time: u64,
+    pulse: u64,
+    on: bool,
+}
+
+/// Reshapes the gate it receives before it reaches envelopes and samplers.
+///
+/// Each rising edge of the incoming gate is a trigger. Triggers can be thinned
+/// out by clock division (every Nth passes) and probability, delayed, and
+/// multiplied into evenly spaced ratchets across the time to the next trigger
+/// (measured from the previous two). Output gates are either a fixed length or
+/// follow the length of the incoming gate. The gate input is connected to the
+/// GateMixer automatically; connecting the output to a node's gate replaces
+/// that node's own GateMixer link (see `AudioGraph::add_connection`).
+pub struct GateTool {
+    enabled: bool,
+    sample_rate: f32,
+    length_samples: u64,
+    delay_samples: u64,
+    division: u32,
+    multiplication: u32,
+    probability: f32,
+
+    clock: u64,
+    input_high: bool,
+    last_rise: Option<u64>,
+    period: Option<u64>,
+    trigger_count: u32,
+    /// Pulse that ends with the incoming gate (follow mode only).
+    following_pulse: Option<u64>,
+    events: Vec<GateEvent>,
+    next_pulse: u64,
+    current_pulse: Option<u64>,
+    rng_state: u32,
+}
+
+impl GateTool {
+    /// Creates a new GateTool node that passes triggers through unchanged.
+    ///
+    /// * `sample_rate` - The sample rate in Hz.
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            enabled: true,
+            sample_rate,
+            length_samples: 0,
+            delay_samples: 0,
+            division: 1,
+            multiplication: 1,
+            probability: 1.0,
+            clock: 0,
+            input_high: false,
+            last_rise: None,
+            period: None,
+            trigger_count: 0,
+            following_pulse: None,
+            events: Vec::with_capacity(2 * MAX_GATE_MULTIPLICATION as usize + 2),
+            next_pulse: 0,
+            current_pulse: None,
+            rng_state: 0x9E37_79B9,
+        }
+    }
+
+    fn ms_to_samples(&self, ms: f32) -> u64 {
+        (ms.max(0.0) * 0.001 * self.sample_rate).round() as u64
+    }
+
+    /// Fixed output gate length; 0 follows the length of the incoming gate.
+    pub fn set_length_ms(&mut self, length_ms: f32) {
+        self.length_samples = self.ms_to_samples(length_ms);
+    }
+
+    pub fn set_delay_ms(&mut self, delay_ms: f32) {
+        let max_ms = MAX_GATE_DELAY_SECONDS * 1000.0;
+        self.delay_samples = self.ms_to_samples(delay_ms.min(max_ms));
+    }
+
+    /// Passes every `division`th trigger.
+    pub fn set_division(&mut self, division: u32) {
+        self.division = division.max(1);
+    }
+
+    /// Splits each trigger into `multiplication` evenly spaced triggers.
+    pub fn set_multiplication(&mut self, multiplication: u32) {
+        self.multiplication = multiplication.clamp(1, MAX_GATE_MULTIPLICATION);
+    }
+
+    /// Chance (0.0 to 1.0) that a trigger passes.
+    pub fn set_probability(&mut self, probability: f32) {
+        self.probability = probability.clamp(0.0, 1.0);
+    }
+
+    fn next_random(&mut self) -> f32 {
+        // xorshift32
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng_state = x;
+        (x >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    fn schedule(&mut self, time: u64, pulse: u64, on: bool) {
+        self.events.push(GateEvent { time, pulse, on });
+    }
+
+    fn on_rise(&mut self) {
+        let now = self.clock;
+        if let Some(last) = self.last_rise {
+            self.period = Some(now - last);
+        }
+        self.last_rise = Some(now);
+
+        let count = self.trigger_count;
+        self.trigger_count = (count + 1) % self.division;
+        if count != 0 || (self.probability < 1.0 && self.next_random() >= self.probability) {
+            self.following_pulse = None;
+            return;
+        }
+
+        // Ratchets need a tempo, so they start from the second trigger.
+        let (ratchets, spacing) = match self.period {
+            Some(period) if self.multiplication > 1 => (
+                self.multiplication as u64,
+                period / self.multiplication as u64,
+            ),
+            _ => (1, 0),
+        };
+        let start = now + self.delay_samples;
+        self.following_pulse = None;
+        for k in 0..ratchets {
+            let pulse = self.next_pulse;
+            self.next_pulse += 1;
+            let on_at = start + k * spacing;
+            self.schedule(on_at, pulse, true);
+            if self.length_samples > 0 {
+                self.schedule(on_at + self.length_samples, pulse, false);
+            } else if k == 0 {
+                self.following_pulse = Some(pulse);
+            } else {
+                self.schedule(on_at + (spacing / 2).max(1), pulse, false);
+            }
+        }
+    }
+
+    fn on_fall(&mut self) {
+        if let Some(pulse) = self.following_pulse.take() {
+            self.schedule(self.clock + self.delay_samples, pulse, false);
+        }
+    }
+
+    /// Advances one sample and returns the output gate.
+    fn tick(&mut self, input: f32) -> f32 {
+        let high = input > 0.0;
+        if high && !self.input_high {
+            self.on_rise();
+        } else if !high && self.input_high {
+            self.on_fall();
+        }
+        self.input_high = high;
+
+        let now = self.clock;
+        let mut retrigger = false;
+        let mut i = 0;
+        while i < self.events.len() {
+            let event = self.events[i];
+            if event.time > now {
+                i += 1;
+                continue;
+            }
+            self.events.swap_remove(i);
+            if event.on {
+                // Drop the gate for this sample so downstream edge detection
+                // sees a new trigger even if the previous pulse is still high.
+                retrigger |= self.current_pulse.is_some();
+                self.current_pulse = Some(event.pulse);
+            } else if self.current_pulse == Some(event.pulse) {
+                self.current_pulse = None;
+            }
+        }
+        self.clock += 1;
+
+        if self.current_pulse.is_some() && !retrigger {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+impl AudioNode for GateTool {
+    fn get_ports(&self) -> FxHashMap<PortId, bool> {
+        let mut ports = FxHashMap::default();
+        ports.insert(PortId::CombinedGate, false); // Incoming gate
+        ports.insert(PortId::AudioOutput0, true); // Reshaped gate
+        ports
+    }
+
+    fn process<'a>(
+        &mut self,
+        inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+        outputs: &mut FxHashMap<PortId, &mut [f32]>,
+        buffer_size: usize,
+    ) {
+        let Some(output) = outputs.get_mut(&PortId::AudioOutput0) else {
+            return;
+        };
+        let sources = inputs.get(&PortId::CombinedGate);
+        for (i, out) in output.iter_mut().enumerate().take(buffer_size) {
+            let input: f32 = sources.map_or(0.0, |sources| {
+                sources
+                    .iter()
+                    .map(|src| src.buffer.get(i).copied().unwrap_or(0.0) * src.amount)
+                    .sum()
+            });
+            *out = if self.enabled {
+                self.tick(input)
+            } else if input > 0.0 {
+                1.0
+            } else {
+                0.0
+            };
+        }
+    }
+
+    fn reset(&mut self) {
+        self.clock = 0;
+        self.input_high = false;
+        self.last_rise = None;
+        self.period = None;
+        self.trigger_count = 0;
+        self.following_pulse = None;
+        self.events.clear();
+        self.current_pulse = None;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn is_active(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_active(&mut self, active: bool) {
+        if active != self.enabled {
+            self.reset();
+        }
+        self.enabled = active;
+    }
+
+    // A bypassed gate tool still has to pass its gate through.
+    fn should_process(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "Gate Tool"
+    }
+
+    fn node_type(&self) -> &str {
+        "gate_tool"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(tool: &mut GateTool, input: &[f32]) -> Vec<f32> {
+        input.iter().map(|&g| tool.tick(g)).collect()
+    }
+
+    fn rising_edges(gate: &[f32]) -> Vec<usize> {
+        (0..gate.len())
+            .filter(|&i| gate[i] > 0.0 && (i == 0 || gate[i - 1] <= 0.0))
+            .collect()
+    }
+
+    /// Four 2-sample triggers, 10 samples apart.
+    fn clock() -> Vec<f32> {
+        (0..40)
+            .map(|i| if i % 10 < 2 { 1.0 } else { 0.0 })
+            .collect()
+    }
+
+    #[test]
+    fn delays_and_lengthens_gates() {
+        let mut tool = GateTool::new(1_000.0);
+        tool.set_delay_ms(3.0);
+        tool.set_length_ms(5.0);
+        let out = run(&mut tool, &clock());
+        assert_eq!(rising_edges(&out), vec![3, 13, 23, 33]);
+        assert_eq!(&out[3..9], &[1.0, 1.0, 1.0, 1.0, 1.0, 0.0]);
+
+        // Following the input keeps its length.
+        tool.set_length_ms(0.0);
+        tool.reset();
+        let out = run(&mut tool, &clock());
+        assert_eq!(&out[..6], &[0.0, 0.0, 0.0, 1.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn divides_and_multiplies_the_clock() {
+        let mut tool = GateTool::new(1_000.0);
+        tool.set_division(2);
+        assert_eq!(rising_edges(&run(&mut tool, &clock())), vec![0, 20]);
+
+        let mut tool = GateTool::new(1_000.0);
+        tool.set_multiplication(2);
+        tool.set_length_ms(2.0);
+        // Ratchets begin once the period between triggers is known.
+        assert_eq!(
+            rising_edges(&run(&mut tool, &clock())),
+            vec![0, 10, 15, 20, 25, 30, 35]
+        );
+    }
+}
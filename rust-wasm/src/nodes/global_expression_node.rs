@@ -0,0 +1,135 @@
+use std::any::Any;
+
+use rustc_hash::FxHashMap;
+
+use crate::graph::ModulationSource;
+use crate::{AudioNode, PortId};
+
+/// Release velocity of notes whose note-off carries none, MIDI's 64. Release
+/// velocity amounts are centred on it, so such notes are left unscaled.
+pub const DEFAULT_RELEASE_VELOCITY: f32 = 0.5;
+
+/// Which per-voice note-expression dimension a `GlobalExpressionNode` carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpressionKind {
+    /// Polyphonic pressure / aftertouch (0..1).
+    Pressure,
+    /// Timbre / slide (MPE CC74 style, 0..1).
+    Timbre,
+    /// Note-off velocity (0..1), held from the note-off until the next one.
+    ReleaseVelocity,
+}
+
+/// GlobalExpressionNode exposes a host-supplied per-voice expression value
+/// (pressure, timbre or release velocity) as a modulation source on `AudioOutput0`.
+/// Like the velocity node it is fed from outside the graph every block.
+pub struct GlobalExpressionNode {
+    kind: ExpressionKind,
+    values: Vec<f32>,
+}
+
+impl GlobalExpressionNode {
+    pub fn new(kind: ExpressionKind, buffer_size: usize) -> Self {
+        Self {
+            kind,
+            values: vec![0.0; buffer_size],
+        }
+    }
+
+    pub fn kind(&self) -> ExpressionKind {
+        self.kind
+    }
+
+    /// Updates the expression buffer. A single value fills the whole block;
+    /// shorter buffers are padded with their last value.
+    pub fn set_values(&mut self, values: &[f32]) {
+        if values.is_empty() {
+            return;
+        }
+
+        if values.len() == 1 {
+            self.values.fill(values[0]);
+            return;
+        }
+
+        let copy_len = values.len().min(self.values.len());
+        self.values[..copy_len].copy_from_slice(&values[..copy_len]);
+        if copy_len < self.values.len() {
+            let last = values[copy_len - 1];
+            self.values[copy_len..].fill(last);
+        }
+    }
+}
+
+impl AudioNode for GlobalExpressionNode {
+    fn get_ports(&self) -> FxHashMap<PortId, bool> {
+        let mut ports = FxHashMap::default();
+        ports.insert(PortId::AudioOutput0, true);
+        ports
+    }
+
+    fn process<'a>(
+        &mut self,
+        _inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+        outputs: &mut FxHashMap<PortId, &mut [f32]>,
+        buffer_size: usize,
+    ) {
+        if self.values.len() < buffer_size {
+            let last = self.values.last().copied().unwrap_or(0.0);
+            self.values.resize(buffer_size, last);
+        }
+
+        if let Some(output) = outputs.get_mut(&PortId::AudioOutput0) {
+            let len = buffer_size.min(output.len());
+            output[..len].copy_from_slice(&self.values[..len]);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.values.fill(0.0);
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn is_active(&self) -> bool {
+        true
+    }
+    fn set_active(&mut self, _active: bool) {}
+    fn name(&self) -> &'static str {
+        match self.kind {
+            ExpressionKind::Pressure => "Global Pressure",
+            ExpressionKind::Timbre => "Global Timbre",
+            ExpressionKind::ReleaseVelocity => "Global Release Velocity",
+        }
+    }
+    fn node_type(&self) -> &str {
+        match self.kind {
+            ExpressionKind::Pressure => "global_pressure",
+            ExpressionKind::Timbre => "global_timbre",
+            ExpressionKind::ReleaseVelocity => "global_release_velocity",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outputs_last_value_for_whole_block() {
+        let mut node = GlobalExpressionNode::new(ExpressionKind::Pressure, 8);
+        node.set_values(&[0.25, 0.75]);
+
+        let mut buf = vec![0.0f32; 8];
+        let inputs = FxHashMap::default();
+        let mut outs: FxHashMap<PortId, &mut [f32]> = FxHashMap::default();
+        outs.insert(PortId::AudioOutput0, &mut buf);
+        node.process(&inputs, &mut outs, 8);
+
+        assert_eq!(buf[0], 0.25);
+        assert!(buf[1..].iter().all(|&v| v == 0.75));
+    }
+}
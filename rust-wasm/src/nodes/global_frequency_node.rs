@@ -6,8 +6,16 @@ use rustc_hash::FxHashMap;
 
 // Import necessary types
 use crate::graph::{ModulationProcessor, ModulationSource};
+use crate::utils::analog_spread::VoiceVariation;
+use crate::utils::smoothing::smoothing_coefficient;
 use crate::{AudioNode, PortId};
 
+/// Time constant of the glide between tuning changes, unless the graph's
+/// smoothing time replaces it.
+const TUNING_SMOOTHING_MS: f32 = 10.0;
+/// Rate assumed until the engine sets the real one.
+const DEFAULT_SAMPLE_RATE: f32 = 48_000.0;
+
 /// GlobalFrequencyNode encapsulates the base frequency buffer and applies a detune factor.
 /// The base frequency can be updated externally.
 /// The detune parameter (in cents) is modulated via the PortId::DetuneMod input.
@@ -16,6 +24,20 @@ pub struct GlobalFrequencyNode {
     base_frequency: Vec<f32>,
     /// Base detune value in cents.
     detune: f32,
+    /// Engine-level transpose in semitones.
+    transpose: f32,
+    /// Engine-level fine tune in cents.
+    fine_tune: f32,
+    /// Per-voice detune offset in cents (e.g. analog-style voice spread).
+    voice_detune: f32,
+    /// This voice's analog spread pitch offset in cents.
+    spread_cents: f32,
+    /// Smoothed tuning offset in cents, chasing transpose/fine/voice detune.
+    current_offset_cents: f32,
+    sample_rate: f32,
+    smoothing_ms: f32,
+    /// Per-sample one-pole coefficient for `current_offset_cents`.
+    tuning_coeff: f32,
 
     // === Scratch Buffers ===
     mod_scratch_add: Vec<f32>,
@@ -23,6 +45,7 @@ pub struct GlobalFrequencyNode {
     // Final modulation results per sample
     scratch_detune_add_semitones: Vec<f32>,
     scratch_detune_mult: Vec<f32>,
+    scratch_offset_cents: Vec<f32>,
 }
 
 impl GlobalFrequencyNode {
@@ -31,11 +54,20 @@ impl GlobalFrequencyNode {
         Self {
             base_frequency: vec![initial_freq; buffer_size],
             detune: 0.0,
+            transpose: 0.0,
+            fine_tune: 0.0,
+            voice_detune: 0.0,
+            spread_cents: 0.0,
+            current_offset_cents: 0.0,
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            smoothing_ms: TUNING_SMOOTHING_MS,
+            tuning_coeff: smoothing_coefficient(DEFAULT_SAMPLE_RATE, TUNING_SMOOTHING_MS),
             // Initialize scratch buffers
             mod_scratch_add: vec![0.0; buffer_size],
             mod_scratch_mult: vec![1.0; buffer_size],
             scratch_detune_add_semitones: vec![0.0; buffer_size],
             scratch_detune_mult: vec![1.0; buffer_size],
+            scratch_offset_cents: vec![0.0; buffer_size],
         }
     }
 
@@ -57,6 +89,13 @@ impl GlobalFrequencyNode {
         resize_if_needed(&mut self.mod_scratch_mult, 1.0);
         resize_if_needed(&mut self.scratch_detune_add_semitones, 0.0);
         resize_if_needed(&mut self.scratch_detune_mult, 1.0);
+        resize_if_needed(&mut self.scratch_offset_cents, 0.0);
+    }
+
+    /// Sets the rate the tuning glide is timed against.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate.max(1.0);
+        self.tuning_coeff = smoothing_coefficient(self.sample_rate, self.smoothing_ms);
     }
 
     /// Sets the base (static) detune parameter in cents.
@@ -64,6 +103,36 @@ impl GlobalFrequencyNode {
         self.detune = detune;
     }
 
+    /// Sets the master tuning: transpose in semitones and fine tune in cents.
+    /// Changes are smoothed over a few milliseconds to avoid clicks.
+    pub fn set_master_tuning(&mut self, transpose: f32, fine_tune: f32) {
+        self.transpose = transpose;
+        self.fine_tune = fine_tune;
+    }
+
+    /// Sets this voice's detune offset in cents.
+    pub fn set_voice_detune(&mut self, cents: f32) {
+        self.voice_detune = cents;
+    }
+
+    fn target_offset_cents(&self) -> f32 {
+        self.transpose * 100.0 + self.fine_tune + self.voice_detune + self.spread_cents
+    }
+
+    /// Fills `scratch_offset_cents` with the smoothed tuning offset for this block.
+    fn fill_tuning_offsets(&mut self, buffer_size: usize) {
+        let target = self.target_offset_cents();
+        if (target - self.current_offset_cents).abs() < 1e-3 {
+            self.current_offset_cents = target;
+            self.scratch_offset_cents[..buffer_size].fill(target);
+            return;
+        }
+        for slot in &mut self.scratch_offset_cents[..buffer_size] {
+            self.current_offset_cents += (target - self.current_offset_cents) * self.tuning_coeff;
+            *slot = self.current_offset_cents;
+        }
+    }
+
     /// Updates the base frequency buffer (e.g., from host).
     pub fn set_base_frequency(&mut self, freq: &[f32]) {
         let current_len = self.base_frequency.len();
@@ -134,6 +203,8 @@ impl AudioNode for GlobalFrequencyNode {
             self.scratch_detune_mult[..buffer_size].fill(1.0);
         }
 
+        self.fill_tuning_offsets(buffer_size);
+
         // --- 2) Calculate Output Frequency (SIMD) ---
         const LANES: usize = 4; // Use f32x4
         type Vf32 = Simd<f32, LANES>;
@@ -152,10 +223,13 @@ impl AudioNode for GlobalFrequencyNode {
             let mod_add_semitones_simd =
                 Vf32::from_slice(&self.scratch_detune_add_semitones[offset..offset + LANES]);
             let mod_mult_simd = Vf32::from_slice(&self.scratch_detune_mult[offset..offset + LANES]);
+            let tuning_cents_simd =
+                Vf32::from_slice(&self.scratch_offset_cents[offset..offset + LANES]);
 
             // Calculate total detune in cents
             let mod_add_cents_simd = mod_add_semitones_simd * cents_per_semitone_simd;
-            let total_detune_cents_simd = static_detune_cents_simd + mod_add_cents_simd;
+            let total_detune_cents_simd =
+                static_detune_cents_simd + mod_add_cents_simd + tuning_cents_simd;
 
             // Calculate detune factor: 2^(total_cents / 1200)
             let exp_arg_simd = total_detune_cents_simd * inv_cents_per_octave_simd;
@@ -176,7 +250,8 @@ impl AudioNode for GlobalFrequencyNode {
             let mod_add_semitones = self.scratch_detune_add_semitones[i];
             let mod_mult = self.scratch_detune_mult[i];
 
-            let total_detune_cents = self.detune + mod_add_semitones * 100.0;
+            let total_detune_cents =
+                self.detune + mod_add_semitones * 100.0 + self.scratch_offset_cents[i];
             let detune_factor = 2.0f32.powf(total_detune_cents / 1200.0);
             let final_factor = detune_factor * mod_mult;
 
@@ -185,8 +260,9 @@ impl AudioNode for GlobalFrequencyNode {
     }
 
     fn reset(&mut self) {
-        // No dynamic state directly within this node (base_frequency is managed externally).
-        // Scratch buffers are overwritten each process call.
+        // base_frequency is managed externally and scratch buffers are overwritten
+        // each process call; only the tuning smoother carries state.
+        self.current_offset_cents = self.target_offset_cents();
     }
 
     fn as_any_mut(&mut self) -> &mut dyn Any {
@@ -205,6 +281,15 @@ impl AudioNode for GlobalFrequencyNode {
         // Activation state typically doesn't apply or is ignored.
     }
 
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.smoothing_ms = time_ms;
+        self.tuning_coeff = smoothing_coefficient(self.sample_rate, self.smoothing_ms);
+    }
+
+    fn set_voice_variation(&mut self, variation: &VoiceVariation) {
+        self.spread_cents = variation.pitch_cents;
+    }
+
     fn name(&self) -> &'static str {
         "Global Frequency"
     }
@@ -216,3 +301,61 @@ impl AudioNode for GlobalFrequencyNode {
 
 // Implement the modulation trait to use its helpers
 impl ModulationProcessor for GlobalFrequencyNode {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(node: &mut GlobalFrequencyNode, size: usize) -> Vec<f32> {
+        let mut out = vec![0.0; size];
+        let inputs = FxHashMap::default();
+        let mut outputs: FxHashMap<PortId, &mut [f32]> = FxHashMap::default();
+        outputs.insert(PortId::GlobalFrequency, &mut out);
+        node.process(&inputs, &mut outputs, size);
+        out
+    }
+
+    #[test]
+    fn master_tuning_transposes_and_smooths() {
+        let mut node = GlobalFrequencyNode::new(440.0, 64);
+        node.set_master_tuning(12.0, 0.0);
+
+        // The first block glides instead of jumping straight to the octave.
+        let first = render(&mut node, 64);
+        assert!(first[0] > 440.0 && first[0] < 450.0);
+
+        node.reset();
+        let settled = render(&mut node, 64);
+        assert!(settled.iter().all(|&f| (f - 880.0).abs() < 1e-2));
+
+        node.set_voice_detune(-1200.0);
+        node.reset();
+        let detuned = render(&mut node, 64);
+        assert!((detuned[63] - 440.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn tuning_glide_follows_sample_rate_and_smoothing_time() {
+        // Samples an octave jump takes to get 63% of the way (one time constant).
+        fn time_constant(node: &mut GlobalFrequencyNode) -> usize {
+            node.set_master_tuning(12.0, 0.0);
+            let out = render(node, 4096);
+            out.iter()
+                .position(|&f| f > 440.0 * 2.0_f32.powf(0.632))
+                .unwrap()
+        }
+
+        let mut node = GlobalFrequencyNode::new(440.0, 64);
+        node.set_sample_rate(48_000.0);
+        assert!(time_constant(&mut node).abs_diff(480) <= 2);
+
+        let mut node = GlobalFrequencyNode::new(440.0, 64);
+        node.set_sample_rate(96_000.0);
+        assert!(time_constant(&mut node).abs_diff(960) <= 2);
+
+        let mut node = GlobalFrequencyNode::new(440.0, 64);
+        node.set_sample_rate(48_000.0);
+        node.set_smoothing_time_ms(5.0);
+        assert!(time_constant(&mut node).abs_diff(240) <= 2);
+    }
+}
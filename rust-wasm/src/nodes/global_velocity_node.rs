@@ -77,6 +77,15 @@ impl GlobalVelocityNode {
         }
     }
 
+    /// Fills the velocity buffer with a linear ramp from `start` towards `end`,
+    /// so per-block velocity changes don't step at block boundaries.
+    pub fn set_velocity_ramp(&mut self, start: f32, end: f32) {
+        let len = self.base_velocity.len().max(1) as f32;
+        for (i, v) in self.base_velocity.iter_mut().enumerate() {
+            *v = start + (end - start) * (i as f32 / len);
+        }
+    }
+
     pub fn set_sensitivity(&mut self, sensitivity: f32) {
         self.sensitivity = sensitivity;
     }
@@ -204,6 +213,9 @@ impl AudioNode for GlobalVelocityNode {
         true
     }
     fn set_active(&mut self, _active: bool) {}
+    fn output_range(&self) -> (f32, f32) {
+        (0.0, 1.0)
+    }
     fn name(&self) -> &'static str {
         "Global Velocity"
     }
@@ -7,7 +7,7 @@ use rustc_hash::FxHashMap;
 use wasm_bindgen::prelude::wasm_bindgen;
 
 use crate::graph::{ModulationProcessor, ModulationSource};
-use crate::traits::{AudioNode, PortId};
+use crate::traits::{AudioNode, PortId, QualityMode};
 use serde::{Deserialize, Serialize};
 
 // --- Enums, LfoTables, Constants remain the same ---
@@ -18,6 +18,10 @@ pub enum LfoWaveform {
     Square,
     Saw,
     InverseSaw,
+    /// Holds a new random value for each cycle.
+    SampleAndHold,
+    /// Glides from one random value to the next over each cycle.
+    SmoothRandom,
 }
 impl LfoWaveform {
     #[inline(always)]
@@ -27,6 +31,32 @@ impl LfoWaveform {
             _ => 0.0,
         }
     }
+
+    /// Index used by the patch format and `update_lfos`.
+    pub fn to_u8(self) -> u8 {
+        match self {
+            LfoWaveform::Sine => 0,
+            LfoWaveform::Triangle => 1,
+            LfoWaveform::Square => 2,
+            LfoWaveform::Saw => 3,
+            LfoWaveform::InverseSaw => 4,
+            LfoWaveform::SampleAndHold => 5,
+            LfoWaveform::SmoothRandom => 6,
+        }
+    }
+
+    /// Inverse of `to_u8`; unknown values fall back to sine.
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => LfoWaveform::Triangle,
+            2 => LfoWaveform::Square,
+            3 => LfoWaveform::Saw,
+            4 => LfoWaveform::InverseSaw,
+            5 => LfoWaveform::SampleAndHold,
+            6 => LfoWaveform::SmoothRandom,
+            _ => LfoWaveform::Sine,
+        }
+    }
 }
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LfoRetriggerMode {
@@ -45,6 +75,15 @@ impl LfoRetriggerMode {
             _ => LfoRetriggerMode::FreeRunning,
         }
     }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            LfoRetriggerMode::FreeRunning => 0,
+            LfoRetriggerMode::StartOnGate => 1,
+            LfoRetriggerMode::Retrigger => 2,
+            LfoRetriggerMode::OneShot => 3,
+        }
+    }
 }
 #[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -91,19 +130,128 @@ impl LfoTables {
             inverse_saw,
         }
     }
+    /// `None` for the random waveforms, which `RandomSteps` generates instead.
     #[inline(always)]
-    fn get_table(&self, waveform: LfoWaveform) -> &[f32] {
+    fn get_table(&self, waveform: LfoWaveform) -> Option<&[f32]> {
         match waveform {
-            LfoWaveform::Sine => &self.sine,
-            LfoWaveform::Triangle => &self.triangle,
-            LfoWaveform::Square => &self.square,
-            LfoWaveform::Saw => &self.saw,
-            LfoWaveform::InverseSaw => &self.inverse_saw,
+            LfoWaveform::Sine => Some(&self.sine),
+            LfoWaveform::Triangle => Some(&self.triangle),
+            LfoWaveform::Square => Some(&self.square),
+            LfoWaveform::Saw => Some(&self.saw),
+            LfoWaveform::InverseSaw => Some(&self.inverse_saw),
+            LfoWaveform::SampleAndHold | LfoWaveform::SmoothRandom => None,
         }
     }
 }
 
+#[inline(always)]
+fn table_lookup(table: &[f32], phase: f32) -> f32 {
+    let table_index_f = phase * TABLE_SIZE_F32;
+    let index1 = (table_index_f as usize) & TABLE_MASK;
+    let index2 = (index1 + 1) & TABLE_MASK;
+    let fraction = table_index_f - table_index_f.floor();
+
+    // Linear interpolation
+    let sample1 = table[index1];
+    let sample2 = table[index2];
+    sample1 + (sample2 - sample1) * fraction
+}
+
+/// Per-step generator for the random waveforms, which can't live in the static
+/// tables. A new bipolar value is drawn each time a full cycle has elapsed;
+/// `SmoothRandom` glides to it along an S-curve, so its slope is limited and
+/// never jumps at a step. The sequence comes from the seed, so a voice replays
+/// the same values after a reset.
+#[derive(Debug, Clone, Copy)]
+pub struct RandomSteps {
+    seed: u32,
+    rng_state: u32,
+    from: f32,
+    to: f32,
+    /// Progress through the current step, 0..1.
+    position: f32,
+}
+
+impl RandomSteps {
+    pub fn new(seed: u32) -> Self {
+        let mut steps = Self {
+            seed,
+            rng_state: 1,
+            from: 0.0,
+            to: 0.0,
+            position: 0.0,
+        };
+        steps.restart();
+        steps
+    }
+
+    /// Restarts the sequence from `seed`.
+    pub fn set_seed(&mut self, seed: u32) {
+        if seed != self.seed {
+            self.seed = seed;
+            self.restart();
+        }
+    }
+
+    /// Goes back to the first value of the seed's sequence.
+    pub fn restart(&mut self) {
+        // Spread nearby seeds apart; xorshift needs a non-zero state.
+        self.rng_state = self
+            .seed
+            .wrapping_mul(0x9E37_79B9)
+            .wrapping_add(0x85EB_CA6B)
+            .max(1);
+        self.to = self.next_random();
+        self.from = self.to;
+        self.position = 0.0;
+    }
+
+    /// Starts a new step from the current value, as on a retrigger.
+    pub fn next_step(&mut self) {
+        self.from = self.value(LfoWaveform::SmoothRandom);
+        self.to = self.next_random();
+        self.position = 0.0;
+    }
+
+    /// Moves on by `cycles` LFO cycles, drawing a new value per completed cycle.
+    pub fn advance(&mut self, cycles: f32) {
+        self.position += cycles.max(0.0);
+        if self.position >= 1.0 {
+            // Only the last two values matter, however many cycles went by.
+            let steps = (self.position as u32).min(2);
+            self.position = self.position.fract();
+            for _ in 0..steps {
+                self.from = self.to;
+                self.to = self.next_random();
+            }
+        }
+    }
+
+    /// Bipolar value (-1.0 to 1.0) of a random waveform at the current position.
+    pub fn value(&self, waveform: LfoWaveform) -> f32 {
+        match waveform {
+            LfoWaveform::SmoothRandom => {
+                let t = self.position.clamp(0.0, 1.0);
+                let eased = t * t * (3.0 - 2.0 * t);
+                self.from + (self.to - self.from) * eased
+            }
+            _ => self.to,
+        }
+    }
+
+    fn next_random(&mut self) -> f32 {
+        // xorshift32
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng_state = x;
+        (x >> 8) as f32 / (1u32 << 23) as f32 - 1.0
+    }
+}
+
 // --- LFO Node Struct ---
+#[derive(Clone)]
 pub struct Lfo {
     // Parameters
     sample_rate: f32,
@@ -127,6 +275,13 @@ pub struct Lfo {
     oneshot_held_value: f32, // Value held after OneShot completes
     // NEW state: Tracks if the initial run from 0.0 up to loop_end has completed
     has_reached_loop_end_once: bool,
+    // Random waveforms: stepped once per cycle of phase travel, seeded per voice.
+    random: RandomSteps,
+    // Control-rate decimation: the table is read every `control_rate_divider`
+    // samples and held in between. The phase still advances every sample.
+    control_rate_divider: usize,
+    control_counter: usize,
+    held_lookup: f32,
 
     // === Scratch Buffers ===
     mod_scratch_add: Vec<f32>,
@@ -162,6 +317,10 @@ impl Lfo {
             is_running: true, // Start running in FreeRunning mode
             oneshot_held_value: 0.0,
             has_reached_loop_end_once: false, // Start before the loop point is hit
+            random: RandomSteps::new(0),
+            control_rate_divider: 1,
+            control_counter: 0,
+            held_lookup: 0.0,
             // Initialize scratch buffers
             mod_scratch_add: vec![0.0; initial_capacity],
             mod_scratch_mult: vec![1.0; initial_capacity],
@@ -207,6 +366,39 @@ impl Lfo {
     pub fn set_use_normalized(&mut self, use_normalized: bool) {
         self.use_normalized = use_normalized;
     }
+    /// Index of the voice this LFO runs in, which picks its random sequence so
+    /// voices don't move in lockstep.
+    pub fn set_voice_index(&mut self, voice_index: usize) {
+        self.random.set_seed(voice_index as u32);
+    }
+
+    pub fn frequency(&self) -> f32 {
+        self.base_frequency
+    }
+    pub fn gain(&self) -> f32 {
+        self.base_gain
+    }
+    pub fn waveform(&self) -> LfoWaveform {
+        self.waveform
+    }
+    pub fn phase_offset(&self) -> f32 {
+        self.phase_offset
+    }
+    pub fn use_absolute(&self) -> bool {
+        self.use_absolute
+    }
+    pub fn use_normalized(&self) -> bool {
+        self.use_normalized
+    }
+    pub fn loop_mode(&self) -> LfoLoopMode {
+        self.loop_mode
+    }
+    pub fn loop_start(&self) -> f32 {
+        self.loop_start
+    }
+    pub fn loop_end(&self) -> f32 {
+        self.loop_end
+    }
 
     pub fn set_retrigger_mode(&mut self, mode: LfoRetriggerMode) {
         if mode != self.retrigger_mode {
@@ -296,6 +488,10 @@ impl Lfo {
         self.phase = 0.0; // ALWAYS reset phase to 0.0
         self.direction = 1.0; // Always start going forwards
         self.has_reached_loop_end_once = false; // Reset the loop detection flag
+        self.control_counter = 0; // Next lookup reads the table immediately
+        if should_run_now {
+            self.random.next_step(); // Each trigger draws a fresh random value
+        }
         self.is_running = match self.retrigger_mode {
             LfoRetriggerMode::FreeRunning => true, // Always running
             LfoRetriggerMode::Retrigger | LfoRetriggerMode::OneShot => should_run_now, // Run only if triggered now
@@ -313,31 +509,39 @@ impl Lfo {
         // This setup ensures `is_running` is correct initially.
     }
 
+    /// Table lookup honouring the control-rate divider.
+    #[inline(always)]
+    fn lookup_sample_at_control_rate(&mut self, phase_value: f32) -> f32 {
+        if self.control_counter == 0 {
+            self.held_lookup = self.lookup_sample_at_phase(phase_value);
+        }
+        self.control_counter += 1;
+        if self.control_counter >= self.control_rate_divider {
+            self.control_counter = 0;
+        }
+        self.held_lookup
+    }
+
     #[inline(always)]
     fn lookup_sample_at_phase(&self, phase_value: f32) -> f32 {
-        // ... (lookup remains the same) ...
         let tables = LFO_TABLES.get().expect("LFO tables not initialized");
-        let table = tables.get_table(self.waveform);
+        let mut sample = match tables.get_table(self.waveform) {
+            Some(table) => {
+                let extra_phase = if self.use_normalized {
+                    self.waveform.normalized_phase_offset()
+                } else {
+                    0.0
+                };
 
-        let extra_phase = if self.use_normalized {
-            self.waveform.normalized_phase_offset()
-        } else {
-            0.0
+                // Apply phase offset and wrap phase to [0.0, 1.0) for table lookup
+                let effective_phase =
+                    (phase_value + self.phase_offset + extra_phase).rem_euclid(1.0);
+                table_lookup(table, effective_phase)
+            }
+            // Random waveforms follow phase travel rather than the phase itself.
+            None => self.random.value(self.waveform),
         };
 
-        // Apply phase offset and wrap phase to [0.0, 1.0) for table lookup
-        let effective_phase = (phase_value + self.phase_offset + extra_phase).rem_euclid(1.0);
-
-        let table_index_f = effective_phase * TABLE_SIZE_F32;
-        let index1 = (table_index_f as usize) & TABLE_MASK;
-        let index2 = (index1 + 1) & TABLE_MASK;
-        let fraction = table_index_f - table_index_f.floor();
-
-        // Linear interpolation
-        let sample1 = table[index1];
-        let sample2 = table[index2];
-        let mut sample = sample1 + (sample2 - sample1) * fraction;
-
         if self.use_absolute {
             sample = sample.abs();
         }
@@ -354,6 +558,7 @@ impl Lfo {
         if !self.is_running {
             return;
         }
+        self.random.advance(phase_increment);
 
         let loop_width = (self.loop_end - self.loop_start).max(f32::EPSILON);
 
@@ -437,6 +642,7 @@ impl Lfo {
             return;
         }
         let total_phase_increment = (self.base_frequency * buffer_size as f32) / self.sample_rate;
+        self.random.advance(total_phase_increment);
 
         match self.loop_mode {
             LfoLoopMode::Off => {
@@ -517,6 +723,7 @@ impl Lfo {
         // Ensure tables are initialized
         let tables = LFO_TABLES.get_or_init(LfoTables::new);
         let table = tables.get_table(waveform);
+        let mut random = RandomSteps::new(0);
         let mut buffer = vec![0.0; buffer_size];
         if buffer_size == 0 {
             return buffer;
@@ -535,14 +742,10 @@ impl Lfo {
         for i in 0..buffer_size {
             // Use the standard lookup function which handles phase offset and wrapping
             let effective_phase = (current_phase + extra_phase_offset).rem_euclid(1.0);
-            let table_index_f = effective_phase * TABLE_SIZE_F32;
-            let index1 = (table_index_f as usize) & TABLE_MASK;
-            let index2 = (index1 + 1) & TABLE_MASK;
-            let fraction = table_index_f - table_index_f.floor();
-
-            let sample1 = table[index1];
-            let sample2 = table[index2];
-            let mut sample = sample1 + (sample2 - sample1) * fraction;
+            let mut sample = match table {
+                Some(table) => table_lookup(table, effective_phase),
+                None => random.value(waveform),
+            };
 
             if use_absolute {
                 sample = sample.abs();
@@ -554,6 +757,7 @@ impl Lfo {
             buffer[i] = sample;
 
             current_phase += phase_increment;
+            random.advance(phase_increment);
             // No need to wrap current_phase here, rem_euclid handles it
         }
         buffer
@@ -708,7 +912,7 @@ impl AudioNode for Lfo {
 
             if should_advance_phase {
                 // LFO is running/advancing this sample.
-                output_sample = self.lookup_sample_at_phase(current_phase_value);
+                output_sample = self.lookup_sample_at_control_rate(current_phase_value);
 
                 // Advance the phase state for the *next* sample.
                 // This also handles OneShot stopping by setting self.is_running = false internally.
@@ -746,6 +950,7 @@ impl AudioNode for Lfo {
         // Reset state based on mode, assume gate is low initially
         self.reset_state_for_mode(false); // Pass false, requires trigger if not FreeRunning
         self.last_gate = 0.0;
+        self.random.restart();
     }
 
     fn as_any_mut(&mut self) -> &mut dyn Any {
@@ -778,6 +983,28 @@ impl AudioNode for Lfo {
         }
     }
 
+    fn set_quality_mode(&mut self, mode: QualityMode) {
+        // In Eco the waveform is read at 1/16 of the audio rate (3 kHz at 48 kHz),
+        // still far above any LFO rate.
+        self.control_rate_divider = match mode {
+            QualityMode::Eco => 16,
+            QualityMode::Normal | QualityMode::High => 1,
+        };
+        self.control_counter = 0;
+    }
+
+    fn clone_node(&self) -> Option<Box<dyn AudioNode>> {
+        Some(Box::new(self.clone()))
+    }
+
+    fn output_range(&self) -> (f32, f32) {
+        if self.use_absolute || self.use_normalized {
+            (0.0, 1.0)
+        } else {
+            (-1.0, 1.0)
+        }
+    }
+
     fn name(&self) -> &'static str {
         "LFO"
     }
@@ -786,3 +1013,30 @@ impl AudioNode for Lfo {
         "lfo"
     }
 } // End impl AudioNode for Lfo
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_waveforms_step_once_per_cycle() {
+        let mut steps = RandomSteps::new(3);
+        let first = steps.value(LfoWaveform::SampleAndHold);
+        steps.advance(0.5);
+        assert_eq!(steps.value(LfoWaveform::SampleAndHold), first);
+        steps.advance(0.5);
+        let second = steps.value(LfoWaveform::SampleAndHold);
+        assert_ne!(second, first);
+        assert!((-1.0..=1.0).contains(&second));
+
+        // The smooth variant starts from the previous value and lands on the new one.
+        assert_eq!(steps.value(LfoWaveform::SmoothRandom), first);
+        steps.advance(0.999);
+        assert!((steps.value(LfoWaveform::SmoothRandom) - second).abs() < 1e-3);
+
+        let mut replay = RandomSteps::new(3);
+        replay.advance(1.0);
+        assert_eq!(replay.value(LfoWaveform::SampleAndHold), second);
+        assert_ne!(RandomSteps::new(4).value(LfoWaveform::SampleAndHold), first);
+    }
+}
@@ -429,6 +429,9 @@ impl AudioNode for Limiter {
     fn set_active(&mut self, active: bool) {
         self.set_node_active(active);
     }
+    fn latency_samples(&self) -> usize {
+        self.lookahead_read_delay
+    }
     fn name(&self) -> &'static str {
         "Limiter"
     }
@@ -0,0 +1,427 @@
+use std::any::Any;
+
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::graph::ModulationSource;
+use crate::traits::{AudioNode, PortId};
+use crate::utils::smoothing::{SmoothedParam, DEFAULT_SMOOTHING_MS};
+
+/// Longest loop the buffer holds.
+pub const MAX_LOOP_SECONDS: f32 = 30.0;
+
+/// Transport commands for `Looper::command`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LooperCommand {
+    /// Starts a new loop, discarding the old one.
+    Record = 0,
+    /// Plays the loop (ends a recording first).
+    Play = 1,
+    /// Plays the loop while layering the input on top of it.
+    Overdub = 2,
+    /// Stops playback, keeping the loop.
+    Stop = 3,
+    /// Discards the loop.
+    Clear = 4,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LooperState {
+    #[default]
+    Empty = 0,
+    Recording = 1,
+    Playing = 2,
+    Overdubbing = 3,
+    Stopped = 4,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LooperSpeed {
+    Half = 0,
+    #[default]
+    Normal = 1,
+    Double = 2,
+}
+
+impl LooperSpeed {
+    fn factor(self) -> f32 {
+        match self {
+            LooperSpeed::Half => 0.5,
+            LooperSpeed::Normal => 1.0,
+            LooperSpeed::Double => 2.0,
+        }
+    }
+}
+
+/// Performance looper.
+///
+/// Records its stereo input into a loop buffer and plays it back on top of
+/// the input, with overdub, reverse and half/double speed playback. Set a
+/// tempo and the loop length snaps to a whole number of beats: the recording
+/// stops by itself once the loop is full, and `sync_to_beat` realigns the
+/// playhead with the host transport. Placed in the effect stack it records the
+/// effect bus at its position in the chain.
+pub struct Looper {
+    enabled: bool,
+    sample_rate: f32,
+    state: LooperState,
+    left: Vec<f32>,
+    right: Vec<f32>,
+    /// Recorded loop length in samples; 0 while empty.
+    length: usize,
+    record_pos: usize,
+    playhead: f32,
+    speed: LooperSpeed,
+    reverse: bool,
+    /// Level existing material keeps on every overdub pass.
+    feedback: f32,
+    tempo_bpm: Option<f32>,
+    loop_beats: f32,
+    level: SmoothedParam,
+}
+
+impl Looper {
+    /// Creates a new Looper node. The loop buffer is allocated on the first
+    /// recording.
+    ///
+    /// * `sample_rate` - The sample rate in Hz.
+    /// * `level` - Playback level of the loop (0.0 to 1.0).
+    pub fn new(sample_rate: f32, level: f32) -> Self {
+        Self {
+            enabled: true,
+            sample_rate,
+            state: LooperState::Empty,
+            left: Vec::new(),
+            right: Vec::new(),
+            length: 0,
+            record_pos: 0,
+            playhead: 0.0,
+            speed: LooperSpeed::Normal,
+            reverse: false,
+            feedback: 1.0,
+            tempo_bpm: None,
+            loop_beats: 4.0,
+            level: SmoothedParam::new(level.clamp(0.0, 1.0), sample_rate, DEFAULT_SMOOTHING_MS),
+        }
+    }
+
+    pub fn command(&mut self, command: LooperCommand) {
+        match command {
+            LooperCommand::Record => {
+                let capacity = (MAX_LOOP_SECONDS * self.sample_rate) as usize;
+                if self.left.len() != capacity {
+                    self.left = vec![0.0; capacity];
+                    self.right = vec![0.0; capacity];
+                }
+                self.length = 0;
+                self.record_pos = 0;
+                self.state = LooperState::Recording;
+            }
+            LooperCommand::Play | LooperCommand::Overdub | LooperCommand::Stop => {
+                if self.state == LooperState::Recording {
+                    self.finish_recording();
+                }
+                if self.length == 0 {
+                    self.state = LooperState::Empty;
+                    return;
+                }
+                self.state = match command {
+                    LooperCommand::Play => LooperState::Playing,
+                    LooperCommand::Overdub => LooperState::Overdubbing,
+                    _ => LooperState::Stopped,
+                };
+            }
+            LooperCommand::Clear => {
+                self.length = 0;
+                self.state = LooperState::Empty;
+            }
+        }
+    }
+
+    fn finish_recording(&mut self) {
+        self.length = self.record_pos;
+        self.playhead = if self.reverse {
+            self.length as f32
+        } else {
+            0.0
+        };
+        self.state = if self.length > 0 {
+            LooperState::Playing
+        } else {
+            LooperState::Empty
+        };
+    }
+
+    pub fn state(&self) -> LooperState {
+        self.state
+    }
+
+    /// Recorded loop length in seconds.
+    pub fn loop_seconds(&self) -> f32 {
+        self.length as f32 / self.sample_rate
+    }
+
+    pub fn set_speed(&mut self, speed: LooperSpeed) {
+        self.speed = speed;
+    }
+
+    pub fn speed(&self) -> LooperSpeed {
+        self.speed
+    }
+
+    pub fn set_reverse(&mut self, reverse: bool) {
+        self.reverse = reverse;
+    }
+
+    pub fn reverse(&self) -> bool {
+        self.reverse
+    }
+
+    /// Sets how much of the existing loop survives each overdub pass (0.0 to 1.0).
+    pub fn set_feedback(&mut self, feedback: f32) {
+        self.feedback = feedback.clamp(0.0, 1.0);
+    }
+
+    pub fn feedback(&self) -> f32 {
+        self.feedback
+    }
+
+    /// Sets the loop playback level (0.0 to 1.0).
+    pub fn set_level(&mut self, level: f32) {
+        self.level.set_target(level.clamp(0.0, 1.0));
+    }
+
+    pub fn level(&self) -> f32 {
+        self.level.target()
+    }
+
+    /// Syncs new recordings to `beats` beats at `bpm`; a tempo of 0 returns
+    /// to free-length loops.
+    pub fn set_tempo(&mut self, bpm: f32, beats: f32) {
+        self.tempo_bpm = (bpm > 0.0).then_some(bpm);
+        self.loop_beats = beats.max(0.0);
+    }
+
+    /// Loop length in samples the next synced recording stops at.
+    fn synced_length(&self) -> Option<usize> {
+        let bpm = self.tempo_bpm?;
+        let samples = (self.loop_beats * 60.0 / bpm * self.sample_rate).round() as usize;
+        (samples > 0).then_some(samples)
+    }
+
+    /// Moves the playhead to where the host transport (`beat_position` in
+    /// beats since the start of the song) says it should be. Only synced
+    /// loops follow the transport.
+    pub fn sync_to_beat(&mut self, beat_position: f64) {
+        let Some(bpm) = self.tempo_bpm else {
+            return;
+        };
+        if self.length == 0 {
+            return;
+        }
+        let samples_per_beat = 60.0 / bpm as f64 * self.sample_rate as f64;
+        let position = beat_position * samples_per_beat * self.speed.factor() as f64;
+        let phase = position.rem_euclid(self.length as f64) as f32;
+        self.playhead = if self.reverse {
+            self.length as f32 - phase
+        } else {
+            phase
+        };
+    }
+
+    #[inline]
+    fn read(&self, buffer: &[f32]) -> f32 {
+        let index = self.playhead.floor();
+        let frac = self.playhead - index;
+        let i0 = (index as usize) % self.length;
+        let i1 = (i0 + 1) % self.length;
+        buffer[i0] + (buffer[i1] - buffer[i0]) * frac
+    }
+}
+
+impl AudioNode for Looper {
+    fn get_ports(&self) -> FxHashMap<PortId, bool> {
+        let mut ports = FxHashMap::default();
+        ports.insert(PortId::AudioInput0, false); // Left (or mono) input
+        ports.insert(PortId::AudioInput1, false); // Optional right input
+        ports.insert(PortId::AudioOutput0, true); // Left output
+        ports.insert(PortId::AudioOutput1, true); // Right output
+        ports
+    }
+
+    fn process<'a>(
+        &mut self,
+        inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+        outputs: &mut FxHashMap<PortId, &mut [f32]>,
+        buffer_size: usize,
+    ) {
+        let left_in = inputs
+            .get(&PortId::AudioInput0)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer);
+        let right_in = inputs
+            .get(&PortId::AudioInput1)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer)
+            .or(left_in);
+
+        let outs = outputs.get_disjoint_mut([&PortId::AudioOutput0, &PortId::AudioOutput1]);
+        let [Some(out_left), Some(out_right)] = outs else {
+            panic!("Missing stereo output buffers");
+        };
+        let out_left: &mut [f32] = out_left;
+        let out_right: &mut [f32] = out_right;
+
+        let step = self.speed.factor() * if self.reverse { -1.0 } else { 1.0 };
+        let synced_length = self.synced_length();
+
+        for i in 0..buffer_size {
+            let l = left_in.and_then(|b| b.get(i)).copied().unwrap_or(0.0);
+            let r = right_in.and_then(|b| b.get(i)).copied().unwrap_or(0.0);
+            let level = self.level.next_value();
+
+            let (loop_l, loop_r) = match self.state {
+                LooperState::Recording => {
+                    self.left[self.record_pos] = l;
+                    self.right[self.record_pos] = r;
+                    self.record_pos += 1;
+                    let full = synced_length
+                        .unwrap_or(self.left.len())
+                        .min(self.left.len());
+                    if self.record_pos >= full {
+                        self.finish_recording();
+                    }
+                    (0.0, 0.0)
+                }
+                LooperState::Playing | LooperState::Overdubbing => {
+                    let loop_l = self.read(&self.left);
+                    let loop_r = self.read(&self.right);
+                    if self.state == LooperState::Overdubbing {
+                        let index = (self.playhead as usize).min(self.length - 1);
+                        self.left[index] = self.left[index] * self.feedback + l;
+                        self.right[index] = self.right[index] * self.feedback + r;
+                    }
+                    self.playhead = (self.playhead + step).rem_euclid(self.length as f32);
+                    (loop_l, loop_r)
+                }
+                LooperState::Empty | LooperState::Stopped => (0.0, 0.0),
+            };
+
+            out_left[i] = l + loop_l * level;
+            out_right[i] = r + loop_r * level;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.playhead = 0.0;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn is_active(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.level.set_time_ms(time_ms);
+    }
+
+    fn set_active(&mut self, active: bool) {
+        self.enabled = active;
+    }
+
+    fn name(&self) -> &'static str {
+        "Looper"
+    }
+
+    fn node_type(&self) -> &str {
+        "looper"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{ModulationTransformation, ModulationType};
+
+    fn run(looper: &mut Looper, input: &[f32]) -> Vec<f32> {
+        let mut inputs = FxHashMap::default();
+        inputs.insert(
+            PortId::AudioInput0,
+            vec![ModulationSource {
+                buffer: input,
+                amount: 1.0,
+                mod_type: ModulationType::Additive,
+                transformation: ModulationTransformation::None,
+            }],
+        );
+        let mut left = vec![0.0; input.len()];
+        let mut right = vec![0.0; input.len()];
+        {
+            let mut outputs = FxHashMap::default();
+            outputs.insert(PortId::AudioOutput0, left.as_mut_slice());
+            outputs.insert(PortId::AudioOutput1, right.as_mut_slice());
+            looper.process(&inputs, &mut outputs, input.len());
+        }
+        left
+    }
+
+    #[test]
+    fn records_overdubs_and_plays_back_in_reverse() {
+        let mut looper = Looper::new(1_000.0, 1.0);
+        let ramp: Vec<f32> = (0..8).map(|n| n as f32).collect();
+        let silence = vec![0.0; 8];
+
+        looper.command(LooperCommand::Record);
+        assert_eq!(run(&mut looper, &ramp), ramp);
+        looper.command(LooperCommand::Play);
+        assert_eq!(looper.state(), LooperState::Playing);
+        assert_eq!(run(&mut looper, &silence), ramp);
+
+        looper.command(LooperCommand::Overdub);
+        run(&mut looper, &vec![1.0; 8]);
+        looper.command(LooperCommand::Play);
+        let layered: Vec<f32> = ramp.iter().map(|s| s + 1.0).collect();
+        assert_eq!(run(&mut looper, &silence), layered);
+
+        looper.set_reverse(true);
+        let reversed = run(&mut looper, &silence);
+        assert_eq!(reversed[1..], [8.0, 7.0, 6.0, 5.0, 4.0, 3.0, 2.0]);
+
+        looper.command(LooperCommand::Stop);
+        assert_eq!(run(&mut looper, &silence), silence);
+    }
+
+    #[test]
+    fn tempo_synced_recording_stops_after_the_loop_length() {
+        // 2 beats at 120 bpm = 1 s = 100 samples at 100 Hz.
+        let mut looper = Looper::new(100.0, 1.0);
+        looper.set_tempo(120.0, 2.0);
+        looper.command(LooperCommand::Record);
+        run(&mut looper, &vec![0.5; 150]);
+        assert_eq!(looper.state(), LooperState::Playing);
+        assert!((looper.loop_seconds() - 1.0).abs() < 1e-6);
+
+        // Half speed stretches the loop over twice the time.
+        looper.set_speed(LooperSpeed::Half);
+        looper.sync_to_beat(4.0);
+        let out = run(&mut looper, &vec![0.0; 200]);
+        assert!(out.iter().all(|&s| (s - 0.5).abs() < 1e-6));
+    }
+}
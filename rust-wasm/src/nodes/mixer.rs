@@ -5,11 +5,13 @@ use std::simd::{Simd, StdFloat}; // Import Simd explicitly, StdFloat for sqrt
 use rustc_hash::FxHashMap;
 
 // Import necessary types
-use crate::graph::{ModulationProcessor, ModulationSource};
+use crate::graph::{ModulationProcessor, ModulationSource, ModulationTarget, TargetMapping};
 use crate::traits::{AudioNode, PortId};
 
 /// A simple stereo mixer node with gain and panning control.
 /// It takes a mono audio input and applies gain and panning to produce stereo output.
+/// When `AudioInput1` is connected the input is treated as stereo (left/right) and
+/// the pan law is applied per channel instead of collapsing to mono.
 pub struct Mixer {
     enabled: bool, // From AudioNode trait
     // base_gain: f32,
@@ -19,6 +21,7 @@ pub struct Mixer {
     mod_scratch_add: Vec<f32>,
     mod_scratch_mult: Vec<f32>,
     audio_in_buffer: Vec<f32>,
+    audio_in_right_buffer: Vec<f32>,
     scratch_gain_add: Vec<f32>,
     scratch_gain_mult: Vec<f32>,
     scratch_pan_add: Vec<f32>,
@@ -39,6 +42,7 @@ impl Mixer {
             mod_scratch_add: vec![0.0; initial_capacity],
             mod_scratch_mult: vec![1.0; initial_capacity],
             audio_in_buffer: vec![0.0; initial_capacity],
+            audio_in_right_buffer: vec![0.0; initial_capacity],
             scratch_gain_add: vec![0.0; initial_capacity],
             scratch_gain_mult: vec![1.0; initial_capacity],
             scratch_pan_add: vec![0.0; initial_capacity],
@@ -60,6 +64,7 @@ impl Mixer {
         resize_if_needed(&mut self.mod_scratch_add, 0.0);
         resize_if_needed(&mut self.mod_scratch_mult, 1.0);
         resize_if_needed(&mut self.audio_in_buffer, 0.0);
+        resize_if_needed(&mut self.audio_in_right_buffer, 0.0);
         resize_if_needed(&mut self.scratch_gain_add, 0.0);
         resize_if_needed(&mut self.scratch_gain_mult, 1.0);
         resize_if_needed(&mut self.scratch_pan_add, 0.0);
@@ -80,7 +85,8 @@ impl ModulationProcessor for Mixer {}
 impl AudioNode for Mixer {
     fn get_ports(&self) -> FxHashMap<PortId, bool> {
         [
-            (PortId::AudioInput0, false), // Mono audio input
+            (PortId::AudioInput0, false), // Mono (or left) audio input
+            (PortId::AudioInput1, false), // Optional right audio input
             (PortId::GainMod, false),     // Modulation for gain
             (PortId::StereoPan, false),   // Modulation for pan
             (PortId::AudioOutput0, true), // Left audio output
@@ -133,6 +139,27 @@ impl AudioNode for Mixer {
             }
         }
 
+        // Right channel: a separate input when connected, otherwise dual-mono.
+        let stereo_input = inputs
+            .get(&PortId::AudioInput1)
+            .is_some_and(|sources| !sources.is_empty());
+        if stereo_input {
+            self.audio_in_right_buffer[..buffer_size].fill(0.0);
+            if let Some(audio_sources) = inputs.get(&PortId::AudioInput1) {
+                for source in audio_sources {
+                    Self::apply_add(
+                        source.buffer,
+                        &mut self.audio_in_right_buffer[..buffer_size],
+                        source.amount,
+                        source.transformation,
+                    );
+                }
+            }
+        } else {
+            self.audio_in_right_buffer[..buffer_size]
+                .copy_from_slice(&self.audio_in_buffer[..buffer_size]);
+        }
+
         let mut process_mod_input = |port_id: PortId,
                                      target_add: &mut [f32],
                                      target_mult: &mut [f32],
@@ -184,13 +211,17 @@ impl AudioNode for Mixer {
 
             // --- Load/Calculate values (same as before) ---
             let audio_in_simd = Vf32::from_slice(&self.audio_in_buffer[offset..offset + LANES]);
+            let audio_in_r_simd =
+                Vf32::from_slice(&self.audio_in_right_buffer[offset..offset + LANES]);
             let gain_add_simd = Vf32::from_slice(&self.scratch_gain_add[offset..offset + LANES]);
             let gain_mult_simd = Vf32::from_slice(&self.scratch_gain_mult[offset..offset + LANES]);
             let pan_add_simd = Vf32::from_slice(&self.scratch_pan_add[offset..offset + LANES]);
             let pan_mult_simd = Vf32::from_slice(&self.scratch_pan_mult[offset..offset + LANES]);
 
             let effective_gain_simd = (Vf32::splat(base_gain) + gain_add_simd) * gain_mult_simd;
-            let gain_applied_input = audio_in_simd * effective_gain_simd.simd_max(zero_simd);
+            let clamped_gain_simd = effective_gain_simd.simd_max(zero_simd);
+            let gain_applied_input = audio_in_simd * clamped_gain_simd;
+            let gain_applied_input_r = audio_in_r_simd * clamped_gain_simd;
 
             // Treat StereoPan as 0..1 where 0 = left, 0.5 = center, 1 = right.
             let effective_pan_simd = (Vf32::splat(base_pan) + pan_add_simd) * pan_mult_simd;
@@ -199,7 +230,7 @@ impl AudioNode for Mixer {
             let gain_l_simd = (one_simd - clamped_pan_simd).sqrt();
 
             let output_l_simd = gain_applied_input * gain_l_simd;
-            let output_r_simd = gain_applied_input * gain_r_simd;
+            let output_r_simd = gain_applied_input_r * gain_r_simd;
 
             // --- Store results into temporary buffers ---
             output_l_simd.copy_to_slice(&mut self.temp_out_l[offset..offset + LANES]);
@@ -210,6 +241,7 @@ impl AudioNode for Mixer {
         let remainder_start = chunks * LANES;
         for i in remainder_start..buffer_size {
             let audio_in = self.audio_in_buffer[i];
+            let audio_in_r = self.audio_in_right_buffer[i];
             let gain_add = self.scratch_gain_add[i];
             let gain_mult = self.scratch_gain_mult[i];
             let pan_add = self.scratch_pan_add[i];
@@ -217,6 +249,7 @@ impl AudioNode for Mixer {
 
             let effective_gain = (base_gain + gain_add) * gain_mult;
             let gain_applied_input = audio_in * effective_gain.max(0.0);
+            let gain_applied_input_r = audio_in_r * effective_gain.max(0.0);
 
             // StereoPan interpreted as 0..1: 0 = left, 0.5 = center, 1 = right.
             let effective_pan = (base_pan + pan_add) * pan_mult;
@@ -226,7 +259,7 @@ impl AudioNode for Mixer {
 
             // Write to temporary buffers
             self.temp_out_l[i] = gain_applied_input * gain_l;
-            self.temp_out_r[i] = gain_applied_input * gain_r;
+            self.temp_out_r[i] = gain_applied_input_r * gain_r;
         } // End of scalar loop
 
         // --- 4) Copy Temporary Buffers to Actual Outputs ---
@@ -259,6 +292,29 @@ impl AudioNode for Mixer {
         self.enabled = active;
     }
 
+    fn modulation_target(&self, port: PortId) -> Option<ModulationTarget> {
+        match port {
+            PortId::GainMod => Some(ModulationTarget {
+                parameter: "gain",
+                unit: "",
+                base: 1.0,
+                min: 0.0,
+                max: f32::INFINITY,
+                mapping: TargetMapping::Linear,
+            }),
+            // 0 is hard left, 0.5 center and 1 hard right.
+            PortId::StereoPan => Some(ModulationTarget {
+                parameter: "pan",
+                unit: "",
+                base: 0.0,
+                min: 0.0,
+                max: 1.0,
+                mapping: TargetMapping::Linear,
+            }),
+            _ => None,
+        }
+    }
+
     fn name(&self) -> &'static str {
         "Mixer"
     }
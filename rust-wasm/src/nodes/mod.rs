@@ -1,47 +1,80 @@
 pub mod analog_oscillator;
 pub mod arpeggiator;
+pub mod auto_wah;
+pub mod binaural;
 pub mod bitcrusher;
+pub mod chance;
 pub mod chorus;
+pub mod clock;
 pub mod compressor;
 pub mod convolver;
 pub mod delay;
+pub mod dual_filter;
 pub mod envelope;
 pub mod eq;
+pub mod exciter;
 pub mod filter_collection;
+pub mod fm_operator;
+pub mod formant_filter;
 pub mod freeverb;
 pub mod gate_mixer;
+pub mod gate_tool;
 pub mod glide;
+pub mod global_expression_node;
 pub mod global_frequency_node;
 pub mod global_velocity_node;
 pub mod lfo;
 pub mod limiter;
+pub mod looper;
 pub mod mixer;
 pub mod morph_wavetable;
+pub mod mseg;
+pub mod multiband;
+pub mod noise_gate;
 pub mod noise_generator;
+pub mod parallel;
 pub mod sampler;
 pub mod saturation;
+pub mod stereo_enhancer;
 pub mod wavetable;
 pub mod wavetable_oscillator;
 
 pub use analog_oscillator::*;
 pub use arpeggiator::*;
+pub use auto_wah::*;
+pub use binaural::*;
 pub use bitcrusher::*;
+pub use chance::*;
 pub use chorus::*;
+pub use clock::*;
 pub use compressor::*;
 pub use convolver::*;
 pub use delay::*;
+pub use dual_filter::*;
 pub use envelope::*;
+pub use eq::*;
+pub use exciter::*;
 pub use filter_collection::*;
+pub use fm_operator::*;
+pub use formant_filter::*;
 pub use freeverb::*;
 pub use gate_mixer::*;
+pub use gate_tool::*;
 pub use glide::*;
+pub use global_expression_node::*;
 pub use global_frequency_node::*;
 pub use global_velocity_node::*;
 pub use lfo::*;
 pub use limiter::*;
+pub use looper::*;
 pub use mixer::*;
+pub use mseg::*;
+pub use multiband::*;
+pub use noise_gate::*;
 pub use noise_generator::*;
+pub use parallel::*;
 pub use sampler::*;
 pub use saturation::*;
+pub use stereo_enhancer::*;
 pub use wavetable::*;
 pub use wavetable_oscillator::*;
@@ -1,6 +1,8 @@
 // morph_wavetable.rs
 
 use rustc_hash::FxHashMap;
+use rustfft::{num_complex::Complex, FftPlanner};
+use serde::Serialize;
 use std::rc::Rc;
 #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
 use web_sys::console;
@@ -13,9 +15,27 @@ fn log_console(message: &str) {
 #[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
 fn log_console(_message: &str) {}
 
-use super::{Waveform, WavetableBank};
+use super::{
+    finite_or_zero, generate_mipmapped_bank_dynamic, SampleData, Waveform, Wavetable, WavetableBank,
+};
 // Import the FFT-based mipmapping types (adjust the module path as needed)
 
+/// Longest wavetable cycle accepted, in samples.
+pub const MAX_WAVETABLE_BASE_SIZE: usize = 1 << 16;
+/// WaveEdit banks are 64 cycles of 256 samples, with nothing in the file to
+/// say so.
+const WAVEEDIT_CYCLES: usize = 64;
+const WAVEEDIT_CYCLE_SIZE: usize = 256;
+/// `.wt` header: magic, cycle size (u32), cycle count (u16), flags (u16).
+const WT_MAGIC: &[u8; 4] = b"vawt";
+const WT_HEADER_SIZE: usize = 12;
+const WT_MAX_CYCLE_SIZE: usize = 4096;
+const WT_MAX_CYCLES: usize = 512;
+const WT_IS_SAMPLE: u16 = 0x1;
+const WT_INT16: u16 = 0x4;
+/// Without it, 16-bit data spans ±16384.
+const WT_INT16_FULL_RANGE: u16 = 0x8;
+
 /// Cubic interpolation helper. Assumes the samples slice is cyclic.
 pub fn cubic_interp(samples: &[f32], pos: f32) -> f32 {
     let n = samples.len();
@@ -37,11 +57,13 @@ pub fn cubic_interp(samples: &[f32], pos: f32) -> f32 {
 }
 
 /// A mipmapped wavetable that contains a bank of band-limited tables.
+#[derive(Clone)]
 pub struct MipmappedWavetable {
     pub bank: WavetableBank,
 }
 
 /// A collection of mipmapped wavetables used for morphing between different waveforms.
+#[derive(Clone)]
 pub struct WavetableMorphCollection {
     pub wavetables: Vec<MipmappedWavetable>,
 }
@@ -126,11 +148,29 @@ impl WavetableMorphCollection {
         let sample2 = cubic_interp(&table2.samples, pos2);
         sample1 + mix * (sample2 - sample1)
     }
+
+    /// Bytes held by the tables of every wavetable in the collection.
+    pub fn memory_bytes(&self) -> usize {
+        self.wavetables
+            .iter()
+            .map(|wavetable| wavetable.bank.memory_bytes())
+            .sum()
+    }
 }
 
 /// A bank of wavetable morph collections, keyed by a name.
 pub struct WavetableSynthBank {
     pub collections: FxHashMap<String, Rc<WavetableMorphCollection>>,
+    /// Rate the mip levels of edited frames are built for.
+    sample_rate: f32,
+}
+
+/// The harmonics of one wavetable frame, from DC up to Nyquist: the peak
+/// amplitude of harmonic `k` and its phase in radians (cosine phase).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FrameSpectrum {
+    pub magnitudes: Vec<f32>,
+    pub phases: Vec<f32>,
 }
 
 impl WavetableSynthBank {
@@ -138,7 +178,10 @@ impl WavetableSynthBank {
         let collection = WavetableMorphCollection::generate_test_collection(sample_rate);
         let mut collections = FxHashMap::default();
         collections.insert("default".to_string(), Rc::new(collection));
-        Self { collections }
+        Self {
+            collections,
+            sample_rate,
+        }
     }
 
     pub fn clear(&mut self) {
@@ -154,10 +197,114 @@ impl WavetableSynthBank {
         self.collections.insert(name.into(), Rc::new(collection));
     }
 
+    /// Remove a collection; returns whether the bank held it.
+    pub fn remove_collection(&mut self, name: &str) -> bool {
+        self.collections.remove(name).is_some()
+    }
+
+    /// Names of the collections in the bank, sorted.
+    pub fn collection_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.collections.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
     /// Retrieve a collection by name.
     pub fn get_collection(&self, name: &str) -> Option<Rc<WavetableMorphCollection>> {
         self.collections.get(name).cloned()
     }
+
+    /// The harmonics of frame `frame` of collection `name`, read from its
+    /// widest mip level.
+    pub fn get_frame_spectrum(&self, name: &str, frame: usize) -> Result<FrameSpectrum, String> {
+        let table = self.frame_table(name, frame)?;
+        let size = table.table_size;
+        let mut spectrum: Vec<Complex<f32>> = table.samples[..size]
+            .iter()
+            .map(|&sample| Complex::new(sample, 0.0))
+            .collect();
+        FftPlanner::new()
+            .plan_fft_forward(size)
+            .process(&mut spectrum);
+        let (magnitudes, phases) = spectrum[..=size / 2]
+            .iter()
+            .enumerate()
+            .map(|(k, bin)| (bin.norm() * harmonic_scale(k, size), bin.arg()))
+            .unzip();
+        Ok(FrameSpectrum { magnitudes, phases })
+    }
+
+    /// Replaces frame `frame` of collection `name` with the harmonics given,
+    /// in the form `get_frame_spectrum` returns, and rebuilds its mip levels.
+    /// Harmonics past the end of the arrays are silent, and DC is dropped as
+    /// for every table. Oscillators playing the collection pick the new
+    /// frame up on their next block.
+    pub fn set_frame_spectrum(
+        &mut self,
+        name: &str,
+        frame: usize,
+        magnitudes: &[f32],
+        phases: &[f32],
+    ) -> Result<(), String> {
+        let size = self.frame_table(name, frame)?.table_size;
+        if magnitudes.len() != phases.len() {
+            return Err(format!(
+                "Got {} magnitudes but {} phases",
+                magnitudes.len(),
+                phases.len()
+            ));
+        }
+        if magnitudes.len() > size / 2 + 1 {
+            return Err(format!(
+                "Frame holds {} harmonics, got {}",
+                size / 2 + 1,
+                magnitudes.len()
+            ));
+        }
+
+        let mut spectrum = vec![Complex::new(0.0, 0.0); size];
+        for (k, (&magnitude, &phase)) in magnitudes.iter().zip(phases).enumerate() {
+            let bin = Complex::from_polar(
+                finite_or_zero(magnitude) / harmonic_scale(k, size),
+                finite_or_zero(phase),
+            );
+            spectrum[k] = bin;
+            if k > 0 && k < size - k {
+                spectrum[size - k] = bin.conj();
+            }
+        }
+        FftPlanner::new()
+            .plan_fft_inverse(size)
+            .process(&mut spectrum);
+        let samples = spectrum.iter().map(|bin| bin.re / size as f32).collect();
+        let bank = generate_mipmapped_bank_dynamic(samples, size, self.sample_rate)
+            .map_err(|e| e.to_string())?;
+
+        if let Some(collection) = self.collections.get_mut(name) {
+            Rc::make_mut(collection).wavetables[frame] = MipmappedWavetable { bank };
+        }
+        Ok(())
+    }
+
+    fn frame_table(&self, name: &str, frame: usize) -> Result<&Wavetable, String> {
+        self.collections
+            .get(name)
+            .ok_or_else(|| format!("Wavetable {} not found", name))?
+            .wavetables
+            .get(frame)
+            .and_then(|wavetable| wavetable.bank.tables.first())
+            .ok_or_else(|| format!("Wavetable {} has no frame {}", name, frame))
+    }
+}
+
+/// Harmonic amplitude per unit of FFT bin magnitude. A real cycle splits
+/// every harmonic but DC and Nyquist between two mirrored bins.
+fn harmonic_scale(k: usize, size: usize) -> f32 {
+    if k == 0 || 2 * k == size {
+        1.0 / size as f32
+    } else {
+        2.0 / size as f32
+    }
 }
 
 /// Helper function to generate a mipmapped bank for a given waveform.
@@ -174,3 +321,215 @@ pub fn generate_mipmapped_bank(
     ));
     MipmappedWavetable { bank }
 }
+
+/// A decoded wavetable file, its cycles back to back.
+pub struct WavetableFile {
+    pub samples: Vec<f32>,
+    pub cycle_size: usize,
+    /// `None` for `.wt` files, which don't store one.
+    pub sample_rate: Option<f32>,
+}
+
+pub fn is_wt_file(data: &[u8]) -> bool {
+    data.starts_with(WT_MAGIC)
+}
+
+/// Reads a wavetable in any of the layouts libraries ship in:
+/// - `.wt` files, whose header gives the cycle size, cycle count and sample
+///   format;
+/// - WAVs from Serum, whose `clm ` chunk gives the cycle size;
+/// - other WAVs, cut into cycles of `base_size`, or read as a WaveEdit bank
+///   when `base_size` is 0.
+///
+/// A cycle size the file declares wins over `base_size`.
+pub fn read_wavetable_file(data: &[u8], base_size: usize) -> Result<WavetableFile, String> {
+    if is_wt_file(data) {
+        let (samples, cycle_size) = parse_wt(data)?;
+        return Ok(WavetableFile {
+            samples,
+            cycle_size,
+            sample_rate: None,
+        });
+    }
+    let wav = SampleData::from_wav_bytes(data)?;
+    let cycle_size = wavetable_cycle_size(wav_cycle_size(data), base_size, wav.samples.len())?;
+    Ok(WavetableFile {
+        samples: wav.samples,
+        cycle_size,
+        sample_rate: Some(wav.sample_rate),
+    })
+}
+
+/// The cycle size for `total` samples of WAV data: the one the file declares,
+/// else `base_size`, else WaveEdit's when the length matches a bank.
+pub fn wavetable_cycle_size(
+    declared: Option<usize>,
+    base_size: usize,
+    total: usize,
+) -> Result<usize, String> {
+    let cycle_size = match declared {
+        Some(size) => size,
+        None if base_size > 0 => base_size,
+        None if total == WAVEEDIT_CYCLES * WAVEEDIT_CYCLE_SIZE => WAVEEDIT_CYCLE_SIZE,
+        None => return Err("The file doesn't declare a cycle size; pass one".to_string()),
+    };
+    if cycle_size == 0 || cycle_size > MAX_WAVETABLE_BASE_SIZE {
+        return Err(format!(
+            "Wavetable cycle size must be between 1 and {}",
+            MAX_WAVETABLE_BASE_SIZE
+        ));
+    }
+    if total < cycle_size {
+        return Err("Wavetable holds no complete cycle".to_string());
+    }
+    Ok(cycle_size)
+}
+
+/// The cycle size a Serum WAV declares in its `clm ` chunk (`<!>2048 ...`).
+pub fn wav_cycle_size(data: &[u8]) -> Option<usize> {
+    if data.get(..4)? != b"RIFF" || data.get(8..12)? != b"WAVE" {
+        return None;
+    }
+    let mut chunks = &data[12..];
+    while chunks.len() >= 8 {
+        let size = u32::from_le_bytes([chunks[4], chunks[5], chunks[6], chunks[7]]) as usize;
+        let body = &chunks[8..];
+        if &chunks[..4] == b"clm " {
+            let text = body.get(..size)?.strip_prefix(b"<!>")?;
+            let digits = text.iter().take_while(|b| b.is_ascii_digit()).count();
+            return std::str::from_utf8(&text[..digits]).ok()?.parse().ok();
+        }
+        // Chunks are padded to an even length.
+        chunks = body.get(size.saturating_add(size % 2)..)?;
+    }
+    None
+}
+
+/// Decodes a `.wt` file into its samples and cycle size.
+fn parse_wt(data: &[u8]) -> Result<(Vec<f32>, usize), String> {
+    let header = data
+        .get(..WT_HEADER_SIZE)
+        .ok_or_else(|| "Truncated .wt header".to_string())?;
+    let cycle_size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+    let cycles = u16::from_le_bytes([header[8], header[9]]) as usize;
+    let flags = u16::from_le_bytes([header[10], header[11]]);
+    if flags & WT_IS_SAMPLE != 0 {
+        return Err("The .wt file holds a sample, not a wavetable".to_string());
+    }
+    if !(1..=WT_MAX_CYCLE_SIZE).contains(&cycle_size) || !(1..=WT_MAX_CYCLES).contains(&cycles) {
+        return Err(format!(
+            "Unsupported .wt layout: {} cycles of {} samples",
+            cycles, cycle_size
+        ));
+    }
+    let bytes_per_sample = if flags & WT_INT16 != 0 { 2 } else { 4 };
+    let body = data[WT_HEADER_SIZE..]
+        .get(..cycle_size * cycles * bytes_per_sample)
+        .ok_or_else(|| "The .wt file is shorter than its header says".to_string())?;
+    let samples = if flags & WT_INT16 != 0 {
+        let scale = if flags & WT_INT16_FULL_RANGE != 0 {
+            32_768.0
+        } else {
+            16_384.0
+        };
+        body.chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / scale)
+            .collect()
+    } else {
+        body.chunks_exact(4)
+            .map(|b| finite_or_zero(f32::from_le_bytes([b[0], b[1], b[2], b[3]])))
+            .collect()
+    };
+    Ok((samples, cycle_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn wav(samples: usize) -> Vec<u8> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 44_100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+        for i in 0..samples {
+            writer.write_sample((i % 256) as i16 * 64).unwrap();
+        }
+        writer.finalize().unwrap();
+        cursor.into_inner()
+    }
+
+    #[test]
+    fn reads_wt_serum_and_waveedit_layouts() {
+        let mut wt = b"vawt".to_vec();
+        wt.extend(4u32.to_le_bytes());
+        wt.extend(2u16.to_le_bytes());
+        wt.extend(WT_INT16.to_le_bytes());
+        for sample in [0i16, 8_192, 16_384, -16_384, 0, 0, 0, 0] {
+            wt.extend(sample.to_le_bytes());
+        }
+        let file = read_wavetable_file(&wt, 2048).unwrap();
+        assert_eq!(file.cycle_size, 4);
+        assert_eq!(file.samples[..4], [0.0, 0.5, 1.0, -1.0]);
+        assert_eq!(file.sample_rate, None);
+        assert!(read_wavetable_file(&wt[..20], 0).is_err());
+
+        // Serum's chunk sits after the audio; the RIFF size grows with it.
+        let mut serum = wav(4 * 512);
+        serum.extend(b"clm ");
+        serum.extend(13u32.to_le_bytes());
+        serum.extend(b"<!>512 010000\0");
+        let riff_size = serum.len() as u32 - 8;
+        serum[4..8].copy_from_slice(&riff_size.to_le_bytes());
+        let file = read_wavetable_file(&serum, 2048).unwrap();
+        assert_eq!((file.cycle_size, file.samples.len()), (512, 2048));
+
+        let waveedit = wav(WAVEEDIT_CYCLES * WAVEEDIT_CYCLE_SIZE);
+        assert_eq!(read_wavetable_file(&waveedit, 0).unwrap().cycle_size, 256);
+        assert_eq!(
+            read_wavetable_file(&waveedit, 1024).unwrap().cycle_size,
+            1024
+        );
+        assert!(read_wavetable_file(&wav(1000), 0).is_err());
+    }
+
+    #[test]
+    fn frame_spectra_round_trip_through_the_bank() {
+        let mut bank = WavetableSynthBank::new(48_000.0);
+        let sine = bank.get_frame_spectrum("default", 0).unwrap();
+        assert_eq!(sine.magnitudes.len(), 1025);
+        assert!((sine.magnitudes[1] - 1.0).abs() < 1e-3);
+        assert!((sine.phases[1] + std::f32::consts::FRAC_PI_2).abs() < 1e-3);
+        assert!(sine.magnitudes[2..]
+            .iter()
+            .all(|magnitude| *magnitude < 1e-3));
+
+        let playing = bank.get_collection("default").unwrap();
+        bank.set_frame_spectrum("default", 0, &[0.0, 1.0, 0.5], &[0.0, 0.0, 1.0])
+            .unwrap();
+        let edited = bank.get_frame_spectrum("default", 0).unwrap();
+        assert!((edited.magnitudes[2] - 0.5).abs() < 1e-3);
+        assert!((edited.phases[2] - 1.0).abs() < 1e-3);
+        let peak = bank
+            .get_collection("default")
+            .unwrap()
+            .lookup_sample(0.0, 0.0, 100.0);
+        assert!((peak - (1.0 + 0.5 * 1f32.cos())).abs() < 1e-3);
+        // Whoever was reading the old frame keeps it.
+        assert!(playing.lookup_sample(0.0, 0.0, 100.0).abs() < 1e-3);
+
+        assert!(bank
+            .set_frame_spectrum("default", 0, &[0.0, 1.0], &[0.0])
+            .is_err());
+        assert!(bank
+            .set_frame_spectrum("default", 0, &[0.0; 2000], &[0.0; 2000])
+            .is_err());
+        assert!(bank.get_frame_spectrum("default", 4).is_err());
+        assert!(bank.get_frame_spectrum("missing", 0).is_err());
+    }
+}
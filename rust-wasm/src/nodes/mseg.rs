@@ -0,0 +1,351 @@
+use std::any::Any;
+
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::graph::ModulationSource;
+use crate::traits::{AudioNode, PortId};
+use crate::utils::curves::get_curved_value;
+
+/// Points an MSEG keeps; longer lists are truncated.
+pub const MAX_MSEG_POINTS: usize = 64;
+
+/// One breakpoint of a multi-stage envelope.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MsegPoint {
+    /// Seconds the segment leading to this point takes.
+    pub time: f32,
+    /// Level reached at the point (-1.0 to 1.0).
+    pub level: f32,
+    /// Shape of the segment leading to this point, as for the ADSR curves
+    /// (0 = linear, positive = exponential, negative = logarithmic).
+    #[serde(default)]
+    pub curve: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MsegConfig {
+    pub points: Vec<MsegPoint>,
+    /// Point held while the gate is on; the segments after it play on
+    /// release. Without one the envelope runs through every point once.
+    #[serde(default)]
+    pub sustain_point: Option<usize>,
+    /// While the gate is on, reaching the sustain point (or the last point
+    /// when there is none) jumps back to the segment after this point.
+    #[serde(default)]
+    pub loop_start: Option<usize>,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+impl Default for MsegConfig {
+    /// A plain attack-decay-release shape.
+    fn default() -> Self {
+        Self {
+            points: vec![
+                MsegPoint {
+                    time: 0.01,
+                    level: 1.0,
+                    curve: 0.0,
+                },
+                MsegPoint {
+                    time: 0.2,
+                    level: 0.6,
+                    curve: 0.0,
+                },
+                MsegPoint {
+                    time: 0.3,
+                    level: 0.0,
+                    curve: 0.0,
+                },
+            ],
+            sustain_point: Some(1),
+            loop_start: None,
+            active: true,
+        }
+    }
+}
+
+impl MsegConfig {
+    /// Clamps times and levels and drops sustain or loop points that don't
+    /// exist.
+    fn sanitized(mut self) -> Self {
+        self.points.truncate(MAX_MSEG_POINTS);
+        for point in &mut self.points {
+            point.time = point.time.max(0.0);
+            point.level = point.level.clamp(-1.0, 1.0);
+        }
+        let len = self.points.len();
+        self.sustain_point = self.sustain_point.filter(|&i| i < len);
+        let loop_end = self.loop_end();
+        self.loop_start = self
+            .loop_start
+            .filter(|&i| loop_end.is_some_and(|end| i < end));
+        self
+    }
+
+    /// Point the loop jumps back from.
+    fn loop_end(&self) -> Option<usize> {
+        self.sustain_point.or(self.points.len().checked_sub(1))
+    }
+
+    /// Seconds from the trigger to the sustain point, or to the end of the loop.
+    fn hold_time(&self) -> f32 {
+        let end = self.loop_end().map_or(0, |end| end + 1);
+        self.points[..end].iter().map(|point| point.time).sum()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MsegStage {
+    Idle,
+    /// Moving towards the point at this index.
+    Segment(usize),
+    /// Holding the sustain point.
+    Sustain(usize),
+}
+
+/// Multi-stage envelope generator.
+///
+/// Plays an arbitrary list of (time, level, curve) segments from the moment
+/// the gate opens, starting from wherever the output currently is. The
+/// sustain point is held until the gate closes, and a loop start makes the
+/// segments between it and the sustain point repeat while the gate is on. The
+/// gate input is connected to the GateMixer automatically.
+pub struct Mseg {
+    sample_rate: f32,
+    config: MsegConfig,
+    stage: MsegStage,
+    position: f32,
+    start_level: f32,
+    value: f32,
+    gate_high: bool,
+}
+
+impl Mseg {
+    pub fn new(sample_rate: f32, config: MsegConfig) -> Self {
+        Self {
+            sample_rate,
+            config: config.sanitized(),
+            stage: MsegStage::Idle,
+            position: 0.0,
+            start_level: 0.0,
+            value: 0.0,
+            gate_high: false,
+        }
+    }
+
+    pub fn update_config(&mut self, config: MsegConfig) {
+        self.config = config.sanitized();
+        let len = self.config.points.len();
+        match self.stage {
+            MsegStage::Segment(i) | MsegStage::Sustain(i) if i >= len => {
+                self.stage = MsegStage::Idle;
+            }
+            MsegStage::Sustain(i) if self.config.sustain_point != Some(i) => {
+                self.finish_segment(i);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn config(&self) -> &MsegConfig {
+        &self.config
+    }
+
+    fn start_segment(&mut self, index: usize) {
+        self.stage = if index < self.config.points.len() {
+            MsegStage::Segment(index)
+        } else {
+            MsegStage::Idle
+        };
+        self.position = 0.0;
+        self.start_level = self.value;
+    }
+
+    fn finish_segment(&mut self, index: usize) {
+        if self.gate_high && self.config.loop_end() == Some(index) {
+            if let Some(loop_start) = self.config.loop_start {
+                self.start_segment(loop_start + 1);
+                return;
+            }
+        }
+        if self.gate_high && self.config.sustain_point == Some(index) {
+            self.stage = MsegStage::Sustain(index);
+        } else {
+            self.start_segment(index + 1);
+        }
+    }
+
+    fn release(&mut self) {
+        let Some(sustain) = self.config.sustain_point else {
+            return;
+        };
+        match self.stage {
+            MsegStage::Sustain(_) => self.start_segment(sustain + 1),
+            MsegStage::Segment(i) if i <= sustain => self.start_segment(sustain + 1),
+            _ => {}
+        }
+    }
+
+    /// Advances one sample with the gate at `gate_on`.
+    fn tick(&mut self, gate_on: bool) -> f32 {
+        if gate_on && !self.gate_high {
+            self.gate_high = true;
+            self.start_segment(0);
+        } else if !gate_on && self.gate_high {
+            self.gate_high = false;
+            self.release();
+        }
+
+        if let MsegStage::Segment(index) = self.stage {
+            let point = self.config.points[index];
+            let samples = point.time * self.sample_rate;
+            self.position += if samples > 1.0 { samples.recip() } else { 1.0 };
+            if self.position >= 1.0 {
+                self.value = point.level;
+                self.finish_segment(index);
+            } else {
+                let shape = get_curved_value(self.position, point.curve);
+                self.value = self.start_level + (point.level - self.start_level) * shape;
+            }
+        }
+        self.value
+    }
+
+    /// Envelope values for visualization: the gate stays on until half a
+    /// second after the sustain point (or one pass of the loop) is reached.
+    pub fn preview(&self, preview_duration: f32) -> Vec<f32> {
+        let total_samples = (self.sample_rate * preview_duration).ceil() as usize;
+        let gate_samples = ((self.config.hold_time() + 0.5) * self.sample_rate) as usize;
+        let mut sim = Mseg::new(self.sample_rate, self.config.clone());
+        (0..total_samples)
+            .map(|i| sim.tick(i < gate_samples))
+            .collect()
+    }
+}
+
+fn input_at(sources: Option<&Vec<ModulationSource>>, i: usize) -> f32 {
+    sources.map_or(0.0, |sources| {
+        sources
+            .iter()
+            .map(|src| src.buffer.get(i).copied().unwrap_or(0.0) * src.amount)
+            .sum()
+    })
+}
+
+impl AudioNode for Mseg {
+    fn get_ports(&self) -> FxHashMap<PortId, bool> {
+        let mut ports = FxHashMap::default();
+        ports.insert(PortId::CombinedGate, false);
+        ports.insert(PortId::AudioOutput0, true); // Envelope value
+        ports
+    }
+
+    fn process<'a>(
+        &mut self,
+        inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+        outputs: &mut FxHashMap<PortId, &mut [f32]>,
+        buffer_size: usize,
+    ) {
+        let Some(output) = outputs.get_mut(&PortId::AudioOutput0) else {
+            return;
+        };
+        if !self.config.active {
+            output[..buffer_size].fill(0.0);
+            return;
+        }
+        let gate = inputs.get(&PortId::CombinedGate);
+        for (i, out) in output.iter_mut().enumerate().take(buffer_size) {
+            *out = self.tick(input_at(gate, i) > 0.0);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.stage = MsegStage::Idle;
+        self.position = 0.0;
+        self.start_level = 0.0;
+        self.value = 0.0;
+        self.gate_high = false;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn is_active(&self) -> bool {
+        self.config.active
+    }
+
+    fn set_active(&mut self, active: bool) {
+        self.config.active = active;
+        if !active {
+            self.reset();
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "MSEG"
+    }
+
+    fn node_type(&self) -> &str {
+        "mseg"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(time: f32, level: f32) -> MsegPoint {
+        MsegPoint {
+            time,
+            level,
+            curve: 0.0,
+        }
+    }
+
+    #[test]
+    fn holds_the_sustain_point_until_release() {
+        // Ten samples per second keeps the segments short.
+        let mut mseg = Mseg::new(10.0, MsegConfig::default());
+        mseg.update_config(MsegConfig {
+            points: vec![point(0.2, 1.0), point(0.2, 0.5), point(0.2, 0.0)],
+            ..MsegConfig::default()
+        });
+        let on: Vec<f32> = (0..6).map(|_| mseg.tick(true)).collect();
+        assert_eq!(on, vec![0.5, 1.0, 0.75, 0.5, 0.5, 0.5]);
+        let off: Vec<f32> = (0..3).map(|_| mseg.tick(false)).collect();
+        assert_eq!(off, vec![0.25, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn loops_while_the_gate_is_held() {
+        let config = MsegConfig {
+            points: vec![point(0.0, 1.0), point(0.1, 0.0), point(0.1, 1.0)],
+            sustain_point: None,
+            loop_start: Some(0),
+            active: true,
+        };
+        let mut mseg = Mseg::new(10.0, config.clone());
+        let on: Vec<f32> = (0..5).map(|_| mseg.tick(true)).collect();
+        assert_eq!(on, vec![1.0, 0.0, 1.0, 0.0, 1.0]);
+        // Without the gate the last pass runs out and holds the final level.
+        assert_eq!(mseg.tick(false), 0.0);
+        assert_eq!(mseg.tick(false), 1.0);
+        assert_eq!(mseg.tick(false), 1.0);
+
+        let preview = Mseg::new(10.0, config).preview(1.0);
+        assert_eq!(preview.len(), 10);
+        assert_eq!(&preview[..3], &[1.0, 0.0, 1.0]);
+    }
+}
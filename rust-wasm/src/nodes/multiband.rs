@@ -0,0 +1,345 @@
+use std::any::Any;
+use std::f32::consts::{PI, SQRT_2};
+
+use rustc_hash::FxHashMap;
+
+use crate::graph::ModulationSource;
+use crate::traits::{AudioNode, PortId};
+use crate::utils::smoothing::{SmoothedParam, DEFAULT_SMOOTHING_MS};
+
+pub const MIN_BANDS: usize = 2;
+pub const MAX_BANDS: usize = 4;
+
+/// Butterworth 2-pole state-variable filter; two in series give the 4th-order
+/// Linkwitz-Riley slopes of the crossover.
+#[derive(Clone, Copy, Default)]
+struct Svf {
+    ic1eq: f32,
+    ic2eq: f32,
+}
+
+impl Svf {
+    /// Returns (low-pass, band-pass, high-pass).
+    #[inline]
+    fn tick(&mut self, input: f32, g: f32) -> (f32, f32, f32) {
+        let a1 = 1.0 / (1.0 + g * (g + SQRT_2));
+        let v1 = a1 * (self.ic1eq + g * (input - self.ic2eq));
+        let v2 = self.ic2eq + g * v1;
+        self.ic1eq = 2.0 * v1 - self.ic1eq;
+        self.ic2eq = 2.0 * v2 - self.ic2eq;
+        (v2, v1, input - SQRT_2 * v1 - v2)
+    }
+}
+
+/// Filter state of one crossover point for one channel.
+#[derive(Clone, Copy, Default)]
+struct CrossoverState {
+    low: [Svf; 2],
+    high: [Svf; 2],
+    /// Allpass per lower band, matching the phase of the bands split off here.
+    allpass: [Svf; MAX_BANDS],
+}
+
+struct Band {
+    gain: SmoothedParam,
+    level: f32,
+    muted: bool,
+    soloed: bool,
+    left: Vec<f32>,
+    right: Vec<f32>,
+}
+
+/// Crossover container for multiband processing.
+///
+/// Splits the stereo input into 2-4 bands with Linkwitz-Riley crossovers
+/// (lower bands are allpass-compensated, so the bands sum back flat) and mixes
+/// them again with per-band gain, mute and solo. On its own it works as a
+/// band-level mixer; inside the effect stack other effects can be assigned to
+/// a band and run on that band only (see `EffectStack::set_effect_band`).
+pub struct Multiband {
+    enabled: bool,
+    sample_rate: f32,
+    crossovers: Vec<f32>,
+    coefficients: Vec<f32>,
+    state: [Vec<CrossoverState>; 2],
+    bands: Vec<Band>,
+}
+
+impl Multiband {
+    /// Creates a new Multiband node.
+    ///
+    /// * `sample_rate` - The sample rate in Hz.
+    /// * `crossovers` - 1 to 3 crossover frequencies in Hz (2 to 4 bands).
+    pub fn new(sample_rate: f32, crossovers: &[f32]) -> Self {
+        let mut multiband = Self {
+            enabled: true,
+            sample_rate,
+            crossovers: Vec::new(),
+            coefficients: Vec::new(),
+            state: [Vec::new(), Vec::new()],
+            bands: Vec::new(),
+        };
+        multiband.set_crossovers(crossovers);
+        multiband
+    }
+
+    /// Sets the crossover frequencies, which also sets the band count (one
+    /// more than the crossovers). Frequencies are sorted; extra ones are
+    /// ignored and at least one is kept.
+    pub fn set_crossovers(&mut self, crossovers: &[f32]) {
+        let mut sorted: Vec<f32> = crossovers
+            .iter()
+            .map(|f| f.clamp(20.0, self.sample_rate * 0.45))
+            .take(MAX_BANDS - 1)
+            .collect();
+        if sorted.is_empty() {
+            sorted.push(1_000.0);
+        }
+        sorted.sort_by(|a, b| a.total_cmp(b));
+
+        self.coefficients = sorted
+            .iter()
+            .map(|f| (PI * f / self.sample_rate).tan())
+            .collect();
+        if sorted.len() != self.crossovers.len() {
+            self.state = [
+                vec![CrossoverState::default(); sorted.len()],
+                vec![CrossoverState::default(); sorted.len()],
+            ];
+            let sample_rate = self.sample_rate;
+            self.bands.resize_with(sorted.len() + 1, || Band {
+                gain: SmoothedParam::new(1.0, sample_rate, DEFAULT_SMOOTHING_MS),
+                level: 1.0,
+                muted: false,
+                soloed: false,
+                left: Vec::new(),
+                right: Vec::new(),
+            });
+        }
+        self.crossovers = sorted;
+        self.update_gains();
+    }
+
+    pub fn crossovers(&self) -> &[f32] {
+        &self.crossovers
+    }
+
+    pub fn band_count(&self) -> usize {
+        self.bands.len()
+    }
+
+    /// Sets a band's linear gain, mute and solo. Soloing any band silences the
+    /// bands that aren't soloed.
+    pub fn set_band(&mut self, band: usize, gain: f32, muted: bool, soloed: bool) {
+        if let Some(b) = self.bands.get_mut(band) {
+            b.level = gain.max(0.0);
+            b.muted = muted;
+            b.soloed = soloed;
+            self.update_gains();
+        }
+    }
+
+    /// (gain, muted, soloed) of a band.
+    pub fn band(&self, band: usize) -> Option<(f32, bool, bool)> {
+        self.bands.get(band).map(|b| (b.level, b.muted, b.soloed))
+    }
+
+    fn update_gains(&mut self) {
+        let any_solo = self.bands.iter().any(|b| b.soloed);
+        for band in &mut self.bands {
+            let audible = !band.muted && (!any_solo || band.soloed);
+            band.gain.set_target(if audible { band.level } else { 0.0 });
+        }
+    }
+
+    /// Splits a block into the band buffers.
+    pub fn split(&mut self, left: &[f32], right: &[f32], len: usize) {
+        for band in &mut self.bands {
+            band.left.resize(len, 0.0);
+            band.right.resize(len, 0.0);
+        }
+        let crossover_count = self.crossovers.len();
+        for (channel, input) in [left, right].into_iter().enumerate() {
+            for i in 0..len {
+                let mut rest = input.get(i).copied().unwrap_or(0.0);
+                for k in 0..crossover_count {
+                    let g = self.coefficients[k];
+                    let state = &mut self.state[channel][k];
+                    let (low, _, _) = state.low[0].tick(rest, g);
+                    let (low, _, _) = state.low[1].tick(low, g);
+                    let (_, _, high) = state.high[0].tick(rest, g);
+                    let (_, _, high) = state.high[1].tick(high, g);
+                    for (j, band) in self.bands.iter_mut().enumerate().take(k) {
+                        let buffer = if channel == 0 {
+                            &mut band.left
+                        } else {
+                            &mut band.right
+                        };
+                        let (_, bp, _) = state.allpass[j].tick(buffer[i], g);
+                        buffer[i] -= 2.0 * SQRT_2 * bp;
+                    }
+                    let band = &mut self.bands[k];
+                    let buffer = if channel == 0 {
+                        &mut band.left
+                    } else {
+                        &mut band.right
+                    };
+                    buffer[i] = low;
+                    rest = high;
+                }
+                let band = &mut self.bands[crossover_count];
+                let buffer = if channel == 0 {
+                    &mut band.left
+                } else {
+                    &mut band.right
+                };
+                buffer[i] = rest;
+            }
+        }
+    }
+
+    /// Band buffers from the last `split`, for processing in place.
+    pub fn band_buffers_mut(&mut self, band: usize) -> Option<(&mut [f32], &mut [f32])> {
+        self.bands
+            .get_mut(band)
+            .map(|b| (b.left.as_mut_slice(), b.right.as_mut_slice()))
+    }
+
+    /// Sums the band buffers into the outputs with the band gains applied.
+    pub fn mix(&mut self, out_left: &mut [f32], out_right: &mut [f32], len: usize) {
+        out_left[..len].fill(0.0);
+        out_right[..len].fill(0.0);
+        for band in &mut self.bands {
+            for i in 0..len {
+                let gain = band.gain.next_value();
+                out_left[i] += band.left[i] * gain;
+                out_right[i] += band.right[i] * gain;
+            }
+        }
+    }
+}
+
+impl AudioNode for Multiband {
+    fn get_ports(&self) -> FxHashMap<PortId, bool> {
+        let mut ports = FxHashMap::default();
+        ports.insert(PortId::AudioInput0, false); // Left (or mono) input
+        ports.insert(PortId::AudioInput1, false); // Optional right input
+        ports.insert(PortId::AudioOutput0, true); // Left output
+        ports.insert(PortId::AudioOutput1, true); // Right output
+        ports
+    }
+
+    fn process<'a>(
+        &mut self,
+        inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+        outputs: &mut FxHashMap<PortId, &mut [f32]>,
+        buffer_size: usize,
+    ) {
+        let left_in = inputs
+            .get(&PortId::AudioInput0)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer)
+            .unwrap_or(&[]);
+        let right_in = inputs
+            .get(&PortId::AudioInput1)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer)
+            .unwrap_or(left_in);
+
+        let outs = outputs.get_disjoint_mut([&PortId::AudioOutput0, &PortId::AudioOutput1]);
+        let [Some(out_left), Some(out_right)] = outs else {
+            panic!("Missing stereo output buffers");
+        };
+
+        self.split(left_in, right_in, buffer_size);
+        self.mix(out_left, out_right, buffer_size);
+    }
+
+    fn reset(&mut self) {
+        for channel in &mut self.state {
+            channel.fill(CrossoverState::default());
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn is_active(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        for band in &mut self.bands {
+            band.gain.set_time_ms(time_ms);
+        }
+    }
+
+    fn set_active(&mut self, active: bool) {
+        self.enabled = active;
+    }
+
+    fn name(&self) -> &'static str {
+        "Multiband"
+    }
+
+    fn node_type(&self) -> &str {
+        "multiband"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn impulse_response(multiband: &mut Multiband, len: usize) -> Vec<f32> {
+        let mut input = vec![0.0; len];
+        input[0] = 1.0;
+        let mut left = vec![0.0; len];
+        let mut right = vec![0.0; len];
+        multiband.split(&input, &input, len);
+        multiband.mix(&mut left, &mut right, len);
+        left
+    }
+
+    fn magnitude_at(response: &[f32], freq: f32, sample_rate: f32) -> f32 {
+        let (mut re, mut im) = (0.0, 0.0);
+        for (n, &s) in response.iter().enumerate() {
+            let phase = 2.0 * PI * freq * n as f32 / sample_rate;
+            re += s * phase.cos();
+            im -= s * phase.sin();
+        }
+        (re * re + im * im).sqrt()
+    }
+
+    #[test]
+    fn bands_sum_back_to_a_flat_response() {
+        let mut multiband = Multiband::new(48_000.0, &[4_000.0, 200.0, 1_500.0]);
+        assert_eq!(multiband.crossovers(), &[200.0, 1_500.0, 4_000.0]);
+        assert_eq!(multiband.band_count(), 4);
+
+        let response = impulse_response(&mut multiband, 8_192);
+        for freq in [50.0, 200.0, 800.0, 1_500.0, 3_000.0, 10_000.0] {
+            let magnitude = magnitude_at(&response, freq, 48_000.0);
+            assert!((magnitude - 1.0).abs() < 0.02, "{} Hz: {}", freq, magnitude);
+        }
+    }
+
+    #[test]
+    fn solo_and_mute_pick_bands() {
+        let mut multiband = Multiband::new(48_000.0, &[1_000.0]);
+        multiband.set_smoothing_time_ms(0.0);
+        multiband.set_band(1, 1.0, false, true);
+        let response = impulse_response(&mut multiband, 8_192);
+        assert!(magnitude_at(&response, 100.0, 48_000.0) < 0.05);
+        assert!((magnitude_at(&response, 8_000.0, 48_000.0) - 1.0).abs() < 0.02);
+
+        multiband.set_band(1, 1.0, true, true);
+        let response = impulse_response(&mut multiband, 8_192);
+        assert!(response.iter().all(|s| s.abs() < 1e-6));
+    }
+}
@@ -0,0 +1,296 @@
+use rustc_hash::FxHashMap;
+use std::any::Any;
+
+use crate::graph::ModulationSource;
+use crate::traits::{AudioNode, PortId};
+use crate::utils::sidechain::sidechain_key;
+
+/// Release of the level detector; short enough to follow note tails.
+const DETECTOR_RELEASE_MS: f32 = 5.0;
+
+/// Stereo noise gate with hysteresis and hold.
+///
+/// The gate opens when the detected level rises above `threshold_db` and only
+/// closes again once it has fallen `hysteresis_db` below that and stayed there
+/// for `hold_ms`, so material hovering around the threshold doesn't chatter.
+/// While closed the signal is attenuated by `range_db` rather than muted
+/// outright. A signal on `SidechainInput` replaces the program material as the
+/// detector key.
+pub struct NoiseGate {
+    active: bool,
+    sample_rate: f32,
+    threshold_db: f32,
+    hysteresis_db: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    detector_coeff: f32,
+    hold_samples: usize,
+    range_gain: f32,
+    detector: f32,
+    open: bool,
+    hold_counter: usize,
+    gain: f32,
+}
+
+impl NoiseGate {
+    /// Creates a new NoiseGate node.
+    ///
+    /// * `sample_rate` - The sample rate in Hz.
+    /// * `threshold_db` - Level that opens the gate.
+    /// * `hysteresis_db` - How far below the threshold the level must fall to close it.
+    /// * `attack_ms` - Fade-in time when the gate opens.
+    /// * `hold_ms` - Minimum time the gate stays open after the level drops.
+    /// * `release_ms` - Fade-out time when the gate closes.
+    /// * `range_db` - Attenuation while closed (e.g. -80 dB).
+    pub fn new(
+        sample_rate: f32,
+        threshold_db: f32,
+        hysteresis_db: f32,
+        attack_ms: f32,
+        hold_ms: f32,
+        release_ms: f32,
+        range_db: f32,
+    ) -> Self {
+        let mut gate = Self {
+            active: true,
+            sample_rate,
+            threshold_db,
+            hysteresis_db: hysteresis_db.max(0.0),
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            detector_coeff: Self::time_to_coeff(DETECTOR_RELEASE_MS, sample_rate),
+            hold_samples: 0,
+            range_gain: 0.0,
+            detector: 0.0,
+            open: false,
+            hold_counter: 0,
+            gain: 0.0,
+        };
+        gate.set_attack_ms(attack_ms);
+        gate.set_hold_ms(hold_ms);
+        gate.set_release_ms(release_ms);
+        gate.set_range_db(range_db);
+        gate.gain = gate.range_gain;
+        gate
+    }
+
+    #[inline]
+    fn time_to_coeff(time_ms: f32, sample_rate: f32) -> f32 {
+        let clamped = time_ms.max(0.01);
+        (-1.0 / (clamped * 0.001 * sample_rate)).exp()
+    }
+
+    #[inline]
+    fn db_to_linear(db: f32) -> f32 {
+        10.0_f32.powf(db * 0.05)
+    }
+
+    pub fn set_threshold_db(&mut self, threshold_db: f32) {
+        self.threshold_db = threshold_db;
+    }
+
+    pub fn set_hysteresis_db(&mut self, hysteresis_db: f32) {
+        self.hysteresis_db = hysteresis_db.max(0.0);
+    }
+
+    pub fn set_attack_ms(&mut self, attack_ms: f32) {
+        self.attack_coeff = Self::time_to_coeff(attack_ms, self.sample_rate);
+    }
+
+    pub fn set_hold_ms(&mut self, hold_ms: f32) {
+        self.hold_samples = (hold_ms.max(0.0) * 0.001 * self.sample_rate) as usize;
+    }
+
+    pub fn set_release_ms(&mut self, release_ms: f32) {
+        self.release_coeff = Self::time_to_coeff(release_ms, self.sample_rate);
+    }
+
+    /// Attenuation while closed; 0 dB or above leaves the gate without effect.
+    pub fn set_range_db(&mut self, range_db: f32) {
+        self.range_gain = Self::db_to_linear(range_db.min(0.0));
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    fn update_state(&mut self, level: f32) {
+        let open_level = Self::db_to_linear(self.threshold_db);
+        let close_level = Self::db_to_linear(self.threshold_db - self.hysteresis_db);
+        if level >= open_level {
+            self.open = true;
+            self.hold_counter = self.hold_samples;
+        } else if self.open && level < close_level {
+            if self.hold_counter == 0 {
+                self.open = false;
+            } else {
+                self.hold_counter -= 1;
+            }
+        }
+    }
+}
+
+impl AudioNode for NoiseGate {
+    fn get_ports(&self) -> FxHashMap<PortId, bool> {
+        let mut ports = FxHashMap::default();
+        ports.insert(PortId::AudioInput0, false);
+        ports.insert(PortId::AudioInput1, false);
+        ports.insert(PortId::SidechainInput, false);
+        ports.insert(PortId::AudioOutput0, true);
+        ports.insert(PortId::AudioOutput1, true);
+        ports
+    }
+
+    fn process<'a>(
+        &mut self,
+        inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+        outputs: &mut FxHashMap<PortId, &mut [f32]>,
+        buffer_size: usize,
+    ) {
+        let left_in = inputs
+            .get(&PortId::AudioInput0)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer);
+        let right_in = inputs
+            .get(&PortId::AudioInput1)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer)
+            .or(left_in);
+
+        let key = sidechain_key(inputs, buffer_size);
+
+        let outs = outputs.get_disjoint_mut([&PortId::AudioOutput0, &PortId::AudioOutput1]);
+        let [Some(out_left), Some(out_right)] = outs else {
+            return;
+        };
+        let out_left: &mut [f32] = out_left;
+        let out_right: &mut [f32] = out_right;
+
+        for i in 0..buffer_size {
+            let dry_l = left_in.and_then(|b| b.get(i)).copied().unwrap_or(0.0);
+            let dry_r = right_in.and_then(|b| b.get(i)).copied().unwrap_or(0.0);
+            let input_level = match key {
+                Some(key) => key.get(i).copied().unwrap_or(0.0).abs(),
+                None => dry_l.abs().max(dry_r.abs()),
+            };
+            self.detector = if input_level > self.detector {
+                input_level
+            } else {
+                input_level + self.detector_coeff * (self.detector - input_level)
+            };
+            self.update_state(self.detector);
+
+            let (target, coeff) = if self.open {
+                (1.0, self.attack_coeff)
+            } else {
+                (self.range_gain, self.release_coeff)
+            };
+            self.gain = target + coeff * (self.gain - target);
+
+            out_left[i] = dry_l * self.gain;
+            out_right[i] = dry_r * self.gain;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.detector = 0.0;
+        self.open = false;
+        self.hold_counter = 0;
+        self.gain = self.range_gain;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn set_active(&mut self, active: bool) {
+        self.active = active;
+        if !active {
+            self.reset();
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Noise Gate"
+    }
+
+    fn node_type(&self) -> &str {
+        "noise_gate"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{ModulationTransformation, ModulationType};
+
+    fn source(buffer: &[f32]) -> Vec<ModulationSource<'_>> {
+        vec![ModulationSource {
+            buffer,
+            amount: 1.0,
+            mod_type: ModulationType::Additive,
+            transformation: ModulationTransformation::None,
+        }]
+    }
+
+    fn run(gate: &mut NoiseGate, input: &[f32], key: Option<&[f32]>) -> Vec<f32> {
+        let mut inputs = FxHashMap::default();
+        inputs.insert(PortId::AudioInput0, source(input));
+        if let Some(key) = key {
+            inputs.insert(PortId::SidechainInput, source(key));
+        }
+        let mut left = vec![0.0; input.len()];
+        let mut right = vec![0.0; input.len()];
+        {
+            let mut outputs = FxHashMap::default();
+            outputs.insert(PortId::AudioOutput0, left.as_mut_slice());
+            outputs.insert(PortId::AudioOutput1, right.as_mut_slice());
+            gate.process(&inputs, &mut outputs, input.len());
+        }
+        left
+    }
+
+    #[test]
+    fn opens_above_threshold_and_holds_through_the_hysteresis_band() {
+        // -20 dB opens, -30 dB closes, 10 ms hold at 1 kHz.
+        let mut gate = NoiseGate::new(1_000.0, -20.0, 10.0, 0.01, 10.0, 0.01, -80.0);
+
+        let quiet = run(&mut gate, &[0.01; 20], None);
+        assert!(!gate.is_open());
+        assert!(quiet.iter().all(|s| s.abs() < 1e-5));
+
+        run(&mut gate, &[0.5; 20], None);
+        assert!(gate.is_open());
+        // -26 dB sits inside the hysteresis band: still open.
+        let band = run(&mut gate, &[0.05; 50], None);
+        assert!(gate.is_open());
+        assert!((band[49] - 0.05).abs() < 1e-4);
+
+        // Below the close level the gate waits out the hold, then closes.
+        let tail = run(&mut gate, &[0.01; 50], None);
+        assert!((tail[0] - 0.01).abs() < 1e-4);
+        assert!(!gate.is_open());
+        assert!(tail[49].abs() < 1e-5);
+    }
+
+    #[test]
+    fn sidechain_key_drives_the_gate() {
+        let mut gate = NoiseGate::new(1_000.0, -20.0, 6.0, 0.01, 0.0, 0.01, -80.0);
+        let program = [0.001; 32];
+        let out = run(&mut gate, &program, Some(&[1.0; 32]));
+        assert!(gate.is_open());
+        assert!((out[31] - 0.001).abs() < 1e-6);
+
+        let out = run(&mut gate, &[0.9; 64], Some(&[0.0; 64]));
+        assert!(!gate.is_open());
+        assert!(out[63].abs() < 1e-3);
+    }
+}
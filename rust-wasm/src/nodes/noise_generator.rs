@@ -1,5 +1,6 @@
 use crate::graph::{ModulationProcessor, ModulationSource};
 use crate::traits::{AudioNode, PortId};
+use crate::utils::smoothing::{SmoothedParam, DEFAULT_SMOOTHING_MS};
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 use std::any::Any;
@@ -53,7 +54,7 @@ const BROWN_OUTPUT_SCALE: f32 = 4.57;
 pub struct NoiseGenerator {
     sample_rate: f32,
     enabled: bool,
-    base_gain: f32,
+    base_gain: SmoothedParam,
     noise_type: NoiseType,
     base_cutoff_normalized: f32,
     dc_offset: f32,
@@ -108,7 +109,7 @@ impl NoiseGenerator {
         NoiseGenerator {
             sample_rate,
             enabled: true,
-            base_gain: 1.0,
+            base_gain: SmoothedParam::new(1.0, sample_rate, DEFAULT_SMOOTHING_MS),
             noise_type: NoiseType::White,
             base_cutoff_normalized: norm,
             dc_offset: 0.0,
@@ -148,7 +149,7 @@ impl NoiseGenerator {
         let old = self.noise_type;
         self.noise_type = upd.noise_type;
         self.base_cutoff_normalized = Self::hz_to_normalized(upd.cutoff, self.sample_rate);
-        self.base_gain = upd.gain.max(0.0);
+        self.base_gain.set_target(upd.gain.max(0.0));
         self.enabled = upd.enabled;
         if old != self.noise_type {
             self.reset_noise_state();
@@ -418,7 +419,7 @@ impl NoiseGenerator {
 
         // Combine gain:
         for i in 0..buffer_size {
-            let base = self.base_gain + self.scratch_gain_add[i];
+            let base = self.base_gain.next_value() + self.scratch_gain_add[i];
             let vca = self.scratch_gain_vca[i];
             self.scratch_gain_vca[i] = base * vca;
         }
@@ -553,6 +554,10 @@ impl AudioNode for NoiseGenerator {
     fn is_active(&self) -> bool {
         self.enabled
     }
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.base_gain.set_time_ms(time_ms);
+    }
+
     fn set_active(&mut self, a: bool) {
         self.enabled = a;
     }
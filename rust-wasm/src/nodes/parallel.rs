@@ -0,0 +1,228 @@
+use std::any::Any;
+
+use rustc_hash::FxHashMap;
+
+use crate::graph::ModulationSource;
+use crate::traits::{AudioNode, PortId};
+use crate::utils::smoothing::{SmoothedParam, DEFAULT_SMOOTHING_MS};
+
+/// Number of parallel chains.
+pub const PARALLEL_CHAINS: usize = 2;
+/// Longest latency difference between the chains that can be compensated.
+const MAX_COMPENSATION_SAMPLES: usize = 8_192;
+
+struct Chain {
+    left: Vec<f32>,
+    right: Vec<f32>,
+    latency: usize,
+    /// Compensation delay lines, written and read at `write_index`.
+    delay_left: Vec<f32>,
+    delay_right: Vec<f32>,
+}
+
+impl Chain {
+    fn new() -> Self {
+        Self {
+            left: Vec::new(),
+            right: Vec::new(),
+            latency: 0,
+            delay_left: vec![0.0; MAX_COMPENSATION_SAMPLES],
+            delay_right: vec![0.0; MAX_COMPENSATION_SAMPLES],
+        }
+    }
+}
+
+/// Parallel effect container.
+///
+/// Feeds the same input to two chains (A and B) and crossfades their outputs
+/// with `blend`. An empty chain passes the input through, so an effect on B
+/// alone gives a wet/dry split and a compressor on B gives classic parallel
+/// compression. The chain with less latency is delayed to match the other,
+/// keeping the two phase-aligned when they are summed. Effects are assigned
+/// to a chain in the effect stack (see `EffectStack::set_effect_band`).
+pub struct Parallel {
+    enabled: bool,
+    blend: SmoothedParam,
+    chains: [Chain; PARALLEL_CHAINS],
+    write_index: usize,
+}
+
+impl Parallel {
+    /// Creates a new Parallel node.
+    ///
+    /// * `sample_rate` - The sample rate in Hz.
+    /// * `blend` - Balance between the chains (0.0 = chain A only, 1.0 = chain B only).
+    pub fn new(sample_rate: f32, blend: f32) -> Self {
+        let mut parallel = Self {
+            enabled: true,
+            blend: SmoothedParam::new(0.5, sample_rate, DEFAULT_SMOOTHING_MS),
+            chains: [Chain::new(), Chain::new()],
+            write_index: 0,
+        };
+        parallel.set_blend(blend);
+        parallel.blend.set_immediate(parallel.blend.target());
+        parallel
+    }
+
+    pub fn set_blend(&mut self, blend: f32) {
+        self.blend.set_target(blend.clamp(0.0, 1.0));
+    }
+
+    pub fn blend(&self) -> f32 {
+        self.blend.target()
+    }
+
+    /// Records the latency of the effects running on a chain; the other chain
+    /// is delayed by the difference.
+    pub fn set_chain_latency(&mut self, chain: usize, samples: usize) {
+        if let Some(c) = self.chains.get_mut(chain) {
+            c.latency = samples;
+        }
+    }
+
+    /// Copies a block into both chain buffers.
+    pub fn split(&mut self, left: &[f32], right: &[f32], len: usize) {
+        for chain in &mut self.chains {
+            chain.left.clear();
+            chain.right.clear();
+            chain
+                .left
+                .extend((0..len).map(|i| left.get(i).copied().unwrap_or(0.0)));
+            chain
+                .right
+                .extend((0..len).map(|i| right.get(i).copied().unwrap_or(0.0)));
+        }
+    }
+
+    /// Chain buffers from the last `split`, for processing in place.
+    pub fn chain_buffers_mut(&mut self, chain: usize) -> Option<(&mut [f32], &mut [f32])> {
+        self.chains
+            .get_mut(chain)
+            .map(|c| (c.left.as_mut_slice(), c.right.as_mut_slice()))
+    }
+
+    /// Blends the latency-aligned chains into the outputs.
+    pub fn mix(&mut self, out_left: &mut [f32], out_right: &mut [f32], len: usize) {
+        let max_latency = self.latency_samples();
+        let delays: [usize; PARALLEL_CHAINS] = std::array::from_fn(|k| {
+            (max_latency - self.chains[k].latency).min(MAX_COMPENSATION_SAMPLES - 1)
+        });
+        for i in 0..len {
+            let blend = self.blend.next_value();
+            let mut aligned = [(0.0, 0.0); PARALLEL_CHAINS];
+            for (k, chain) in self.chains.iter_mut().enumerate() {
+                chain.delay_left[self.write_index] = chain.left[i];
+                chain.delay_right[self.write_index] = chain.right[i];
+                let read = (self.write_index + MAX_COMPENSATION_SAMPLES - delays[k])
+                    % MAX_COMPENSATION_SAMPLES;
+                aligned[k] = (chain.delay_left[read], chain.delay_right[read]);
+            }
+            self.write_index = (self.write_index + 1) % MAX_COMPENSATION_SAMPLES;
+            out_left[i] = aligned[0].0 + (aligned[1].0 - aligned[0].0) * blend;
+            out_right[i] = aligned[0].1 + (aligned[1].1 - aligned[0].1) * blend;
+        }
+    }
+}
+
+impl AudioNode for Parallel {
+    fn get_ports(&self) -> FxHashMap<PortId, bool> {
+        let mut ports = FxHashMap::default();
+        ports.insert(PortId::AudioInput0, false); // Left (or mono) input
+        ports.insert(PortId::AudioInput1, false); // Optional right input
+        ports.insert(PortId::AudioOutput0, true); // Left output
+        ports.insert(PortId::AudioOutput1, true); // Right output
+        ports
+    }
+
+    fn process<'a>(
+        &mut self,
+        inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+        outputs: &mut FxHashMap<PortId, &mut [f32]>,
+        buffer_size: usize,
+    ) {
+        let left_in = inputs
+            .get(&PortId::AudioInput0)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer)
+            .unwrap_or(&[]);
+        let right_in = inputs
+            .get(&PortId::AudioInput1)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer)
+            .unwrap_or(left_in);
+
+        let outs = outputs.get_disjoint_mut([&PortId::AudioOutput0, &PortId::AudioOutput1]);
+        let [Some(out_left), Some(out_right)] = outs else {
+            panic!("Missing stereo output buffers");
+        };
+
+        self.split(left_in, right_in, buffer_size);
+        self.mix(out_left, out_right, buffer_size);
+    }
+
+    fn reset(&mut self) {
+        for chain in &mut self.chains {
+            chain.delay_left.fill(0.0);
+            chain.delay_right.fill(0.0);
+        }
+        self.write_index = 0;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn is_active(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.blend.set_time_ms(time_ms);
+    }
+
+    fn latency_samples(&self) -> usize {
+        self.chains.iter().map(|c| c.latency).max().unwrap_or(0)
+    }
+
+    fn set_active(&mut self, active: bool) {
+        self.enabled = active;
+    }
+
+    fn name(&self) -> &'static str {
+        "Parallel"
+    }
+
+    fn node_type(&self) -> &str {
+        "parallel"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shorter_chain_is_delayed_to_line_up_with_the_longer_one() {
+        let mut parallel = Parallel::new(48_000.0, 0.5);
+        parallel.set_chain_latency(1, 3);
+        assert_eq!(parallel.latency_samples(), 3);
+
+        let mut input = vec![0.0; 8];
+        input[0] = 1.0;
+        parallel.split(&input, &input, 8);
+        // Stand-in for a chain with three samples of latency.
+        let (left, right) = parallel.chain_buffers_mut(1).unwrap();
+        left.rotate_right(3);
+        right.rotate_right(3);
+
+        let mut out_left = vec![0.0; 8];
+        let mut out_right = vec![0.0; 8];
+        parallel.mix(&mut out_left, &mut out_right, 8);
+        assert_eq!(out_left, vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(out_left, out_right);
+    }
+}
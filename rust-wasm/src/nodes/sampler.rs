@@ -1,14 +1,18 @@
 use crate::graph::{ModulationSource, ModulationType};
-use crate::traits::{AudioNode, PortId};
+use crate::nodes::{Envelope, DEFAULT_RELEASE_VELOCITY};
+use crate::traits::{AudioNode, PortId, QualityMode};
+use crate::utils::smoothing::{SmoothedParam, DEFAULT_SMOOTHING_MS};
 use rustc_hash::FxHashMap;
 use std::any::Any;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
+pub mod sfz;
+
 /// Sample loop mode
 #[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -27,6 +31,60 @@ pub enum SamplerTriggerMode {
     OneShot = 2,     // Plays once per gate trigger, ignores gate until complete
 }
 
+/// How the note pitch reaches the sample
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SamplerPlaybackMode {
+    Repitch = 0, // Speed follows pitch, like a tape
+    Stretch = 1, // Granular: pitch follows the note, length stays as recorded
+}
+
+/// Largest WAV file accepted, in samples over all channels (about 12 minutes
+/// of 48 kHz stereo), so a forged header can't make decoding allocate
+/// gigabytes.
+pub const MAX_WAV_SAMPLES: usize = 1 << 26;
+const WAV_SAMPLE_RATES: std::ops::RangeInclusive<u32> = 1_000..=768_000;
+
+/// Rejects WAV files the engine can't or shouldn't decode: unsupported
+/// sample formats, no channels, absurd sample rates and oversized files.
+pub fn check_wav_spec(spec: &hound::WavSpec, samples: u32) -> Result<(), String> {
+    match (spec.bits_per_sample, spec.sample_format) {
+        (32, hound::SampleFormat::Float)
+        | (16, hound::SampleFormat::Int)
+        | (24, hound::SampleFormat::Int)
+        | (32, hound::SampleFormat::Int) => {}
+        (bits, sample_format) => {
+            return Err(format!(
+                "Unsupported WAV format: bits_per_sample={} sample_format={:?}",
+                bits, sample_format
+            ))
+        }
+    }
+    if spec.channels == 0 {
+        return Err("WAV file has no channels".to_string());
+    }
+    if !WAV_SAMPLE_RATES.contains(&spec.sample_rate) {
+        return Err(format!("Unsupported WAV sample rate: {}", spec.sample_rate));
+    }
+    if samples as usize > MAX_WAV_SAMPLES {
+        return Err(format!(
+            "WAV file too long: {} samples, limit {}",
+            samples, MAX_WAV_SAMPLES
+        ));
+    }
+    Ok(())
+}
+
+/// Decoded samples with NaN and infinities silenced.
+#[inline]
+pub fn finite_or_zero(sample: f32) -> f32 {
+    if sample.is_finite() {
+        sample
+    } else {
+        0.0
+    }
+}
+
 /// Shared sample data structure
 #[derive(Clone)]
 pub struct SampleData {
@@ -38,6 +96,110 @@ pub struct SampleData {
     pub sample_rate: f32,
     /// Original root note (MIDI note number, default 60 = C4)
     pub root_note: f32,
+    /// Multisample zones. When any are loaded, each gate picks the zone
+    /// matching the note and velocity; the sample above is played only when
+    /// none match.
+    pub zones: Vec<SampleZone>,
+}
+
+/// Where a zone sits on the keyboard. Ranges are inclusive MIDI values.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ZoneMapping {
+    pub key_low: u8,
+    pub key_high: u8,
+    pub velocity_low: u8,
+    pub velocity_high: u8,
+    /// The note the sample was recorded at. Fractional values tune the zone:
+    /// 59.9 plays 10 cents sharp.
+    pub root_note: f32,
+    /// Zones in the same non-zero group take turns on the notes they share
+    /// (round robin). Group 0 is no round robin.
+    #[serde(default)]
+    pub round_robin_group: u32,
+}
+
+impl Default for ZoneMapping {
+    fn default() -> Self {
+        Self {
+            key_low: 0,
+            key_high: 127,
+            velocity_low: 0,
+            velocity_high: 127,
+            root_note: 60.0,
+            round_robin_group: 0,
+        }
+    }
+}
+
+impl ZoneMapping {
+    pub fn contains(&self, key: u8, velocity: u8) -> bool {
+        (self.key_low..=self.key_high).contains(&key)
+            && (self.velocity_low..=self.velocity_high).contains(&velocity)
+    }
+}
+
+/// Playback settings a zone brings along, e.g. from an SFZ region. The
+/// sampler's own settings apply where these are `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ZonePlayback {
+    /// Loop mode with start and end points in frames.
+    pub looping: Option<(SamplerLoopMode, f32, f32)>,
+    pub amp_envelope: Option<AmpEnvelope>,
+}
+
+/// Linear ADSR applied to a zone's output. Times in seconds, sustain 0..1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AmpEnvelope {
+    pub attack: f32,
+    pub decay: f32,
+    pub sustain: f32,
+    pub release: f32,
+}
+
+/// One sample of a multisampled instrument.
+#[derive(Clone)]
+pub struct SampleZone {
+    mapping: ZoneMapping,
+    playback: ZonePlayback,
+    sample: SampleData,
+    /// Times picked, for round robin.
+    plays: Cell<u32>,
+}
+
+impl SampleZone {
+    pub fn new(mut sample: SampleData, mapping: ZoneMapping) -> Self {
+        sample.root_note = mapping.root_note;
+        sample.zones.clear();
+        Self {
+            mapping,
+            playback: ZonePlayback::default(),
+            sample,
+            plays: Cell::new(0),
+        }
+    }
+
+    pub fn with_playback(mut self, playback: ZonePlayback) -> Self {
+        self.playback = playback;
+        self
+    }
+
+    pub fn mapping(&self) -> ZoneMapping {
+        self.mapping
+    }
+
+    pub fn set_mapping(&mut self, mapping: ZoneMapping) {
+        self.sample.root_note = mapping.root_note;
+        self.mapping = mapping;
+    }
+
+    pub fn playback(&self) -> ZonePlayback {
+        self.playback
+    }
+
+    pub fn sample(&self) -> &SampleData {
+        &self.sample
+    }
 }
 
 impl SampleData {
@@ -47,9 +209,82 @@ impl SampleData {
             channels: 1,
             sample_rate: 44100.0,
             root_note: 60.0, // Middle C
+            zones: Vec::new(),
         }
     }
 
+    /// Adds a zone and returns its index.
+    pub fn add_zone(&mut self, sample: SampleData, mapping: ZoneMapping) -> usize {
+        self.zones.push(SampleZone::new(sample, mapping));
+        self.zones.len() - 1
+    }
+
+    /// The zone to play for a note: the first one covering it, or, when that
+    /// zone is in a round-robin group, whichever zone of the group covering
+    /// the note has been picked least.
+    pub fn select_zone(&self, key: u8, velocity: u8) -> Option<usize> {
+        let first = self
+            .zones
+            .iter()
+            .position(|zone| zone.mapping.contains(key, velocity))?;
+        let group = self.zones[first].mapping.round_robin_group;
+        let index = if group == 0 {
+            first
+        } else {
+            (first..self.zones.len())
+                .filter(|&i| {
+                    let mapping = &self.zones[i].mapping;
+                    mapping.round_robin_group == group && mapping.contains(key, velocity)
+                })
+                .min_by_key(|&i| self.zones[i].plays.get())
+                .unwrap_or(first)
+        };
+        let plays = &self.zones[index].plays;
+        plays.set(plays.get().wrapping_add(1));
+        Some(index)
+    }
+
+    /// The zone's sample, or this one when `zone` is `None` or out of range.
+    fn source(&self, zone: Option<usize>) -> &SampleData {
+        zone.and_then(|index| self.zones.get(index))
+            .map_or(self, |zone| &zone.sample)
+    }
+
+    /// Decodes a WAV file (16/24/32-bit integer or 32-bit float). Malformed
+    /// and oversized files are errors (see `check_wav_spec`).
+    pub fn from_wav_bytes(data: &[u8]) -> Result<Self, String> {
+        let mut reader = hound::WavReader::new(data).map_err(|e| e.to_string())?;
+        let spec = reader.spec();
+        check_wav_spec(&spec, reader.len())?;
+        let format = (spec.bits_per_sample, spec.sample_format);
+        let samples: Result<Vec<f32>, hound::Error> = match format {
+            (32, hound::SampleFormat::Float) => reader
+                .samples::<f32>()
+                .map(|s| s.map(finite_or_zero))
+                .collect(),
+            (16, hound::SampleFormat::Int) => reader
+                .samples::<i16>()
+                .map(|s| s.map(|s| s as f32 / i16::MAX as f32))
+                .collect(),
+            (24, hound::SampleFormat::Int) => reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| (s << 8 >> 8) as f32 / 8_388_607.0))
+                .collect(),
+            // 32-bit integer, the last format `check_wav_spec` lets through.
+            _ => reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 / i32::MAX as f32))
+                .collect(),
+        };
+        let mut sample_data = Self::new();
+        sample_data.load_from_wav(
+            samples.map_err(|e| e.to_string())?,
+            spec.channels as usize,
+            spec.sample_rate as f32,
+        );
+        Ok(sample_data)
+    }
+
     pub fn load_from_wav(&mut self, samples: Vec<f32>, channels: usize, sample_rate: f32) {
         self.samples = samples;
         self.channels = channels;
@@ -103,6 +338,44 @@ impl SampleData {
             (left, right)
         }
     }
+
+    #[inline]
+    fn frame(&self, index: usize) -> (f32, f32) {
+        if self.channels == 1 {
+            let value = self.samples[index];
+            (value, value)
+        } else {
+            (self.samples[index * 2], self.samples[index * 2 + 1])
+        }
+    }
+
+    /// 4-point Hermite interpolated sample at a frame position. Neighbours past
+    /// either end of the sample are clamped to the edge frames.
+    #[inline]
+    fn get_sample_hermite(&self, position: f32) -> (f32, f32) {
+        if self.samples.is_empty() {
+            return (0.0, 0.0);
+        }
+
+        let last = self.len() - 1;
+        let position = position.clamp(0.0, last as f32);
+        let index = position.floor() as usize;
+        let frac = position - index as f32;
+
+        let (l0, r0) = self.frame(index.saturating_sub(1));
+        let (l1, r1) = self.frame(index);
+        let (l2, r2) = self.frame((index + 1).min(last));
+        let (l3, r3) = self.frame((index + 2).min(last));
+        (hermite(l0, l1, l2, l3, frac), hermite(r0, r1, r2, r3, frac))
+    }
+}
+
+#[inline(always)]
+fn hermite(y0: f32, y1: f32, y2: f32, y3: f32, t: f32) -> f32 {
+    let c1 = 0.5 * (y2 - y0);
+    let c2 = y0 - 2.5 * y1 + 2.0 * y2 - 0.5 * y3;
+    let c3 = 0.5 * (y3 - y0) + 1.5 * (y1 - y2);
+    ((c3 * t + c2) * t + c1) * t + y1
 }
 
 impl Default for SampleData {
@@ -111,7 +384,26 @@ impl Default for SampleData {
     }
 }
 
-const SAMPLER_OVERSAMPLE_FACTOR: usize = 2;
+const DEFAULT_OVERSAMPLE_FACTOR: usize = 2;
+pub const DEFAULT_GRAIN_SIZE_MS: f32 = 50.0;
+pub const DEFAULT_STRETCH_QUALITY: u8 = 4;
+/// Overlapping grains at the highest stretch quality.
+const MAX_STRETCH_GRAINS: u8 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AmpStage {
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+/// A Hann-windowed slice of the sample in stretch playback.
+#[derive(Debug, Clone, Copy, Default)]
+struct Grain {
+    start: f32, // Sample frame the grain started reading at
+    age: f32,   // Output samples since the grain started
+}
 
 /// Sampler node - plays back audio samples with pitch control and looping
 pub struct Sampler {
@@ -119,13 +411,17 @@ pub struct Sampler {
     sample_data: Rc<RefCell<SampleData>>,
 
     // Parameters
-    sample_rate: f32,    // Engine sample rate
-    base_frequency: f32, // Base frequency (440 Hz = A4)
-    base_gain: f32,      // Output gain
+    sample_rate: f32,         // Engine sample rate
+    base_frequency: f32,      // Base frequency (440 Hz = A4)
+    base_gain: SmoothedParam, // Output gain
     trigger_mode: SamplerTriggerMode,
     loop_mode: SamplerLoopMode,
     loop_start: f32, // Loop start point (in frames)
     loop_end: f32,   // Loop end point (in frames)
+    loop_crossfade_ms: f32,
+    playback_mode: SamplerPlaybackMode,
+    grain_size_ms: f32,
+    release_velocity_amount: f32, // Release velocity -> zone amp release time
     active: bool,
 
     // State
@@ -134,6 +430,16 @@ pub struct Sampler {
     last_gate: f32,         // Previous gate value
     is_playing: bool,       // Whether currently playing
     oneshot_complete: bool, // For OneShot mode
+    zone: Option<usize>,    // Multisample zone picked at the last trigger
+    amp_stage: AmpStage,    // Zone amp envelope, when the zone has one
+    amp_level: f32,
+    release_step: f32,
+    release_velocity: f32, // Latest ReleaseVelocity input
+    grains: Vec<Grain>,    // Stretch grains; their count is the stretch quality
+
+    // Quality
+    oversample_factor: usize, // Playhead sub-steps averaged per output sample
+    hermite_interpolation: bool,
 
     // Scratch buffers for modulation
     mod_scratch_add: Vec<f32>,
@@ -151,17 +457,29 @@ impl Sampler {
             sample_data: Rc::new(RefCell::new(SampleData::new())),
             sample_rate,
             base_frequency: 440.0, // A4
-            base_gain: 1.0,
+            base_gain: SmoothedParam::new(1.0, sample_rate, DEFAULT_SMOOTHING_MS),
             trigger_mode: SamplerTriggerMode::Gate,
             loop_mode: SamplerLoopMode::Off,
             loop_start: 0.0,
             loop_end: 0.0,
+            loop_crossfade_ms: 0.0,
+            playback_mode: SamplerPlaybackMode::Repitch,
+            grain_size_ms: DEFAULT_GRAIN_SIZE_MS,
+            release_velocity_amount: 0.0,
             active: true,
             playhead: 0.0,
             direction: 1.0,
             last_gate: 0.0,
             is_playing: false,
             oneshot_complete: false,
+            zone: None,
+            amp_stage: AmpStage::Attack,
+            amp_level: 0.0,
+            release_step: 0.0,
+            release_velocity: DEFAULT_RELEASE_VELOCITY,
+            grains: vec![Grain::default(); DEFAULT_STRETCH_QUALITY as usize],
+            oversample_factor: DEFAULT_OVERSAMPLE_FACTOR,
+            hermite_interpolation: false,
             mod_scratch_add: vec![0.0; 128],
             mod_scratch_mult: vec![1.0; 128],
             gate_buffer: vec![0.0; 128],
@@ -185,6 +503,7 @@ impl Sampler {
         self.direction = 1.0;
         self.is_playing = false;
         self.oneshot_complete = false;
+        self.zone = None;
 
         // Update loop_end to sample length if not set
         let sample_len = self.sample_data.borrow().len() as f32;
@@ -198,7 +517,7 @@ impl Sampler {
     }
 
     pub fn set_base_gain(&mut self, gain: f32) {
-        self.base_gain = gain.clamp(0.0, 10.0);
+        self.base_gain.set_target(gain.clamp(0.0, 10.0));
     }
 
     pub fn set_loop_mode(&mut self, mode: SamplerLoopMode) {
@@ -213,6 +532,13 @@ impl Sampler {
         self.loop_end = end;
     }
 
+    /// Crossfade at the loop boundary so loops that don't meet cleanly don't
+    /// click. Loop mode needs as much audio before the loop start as the
+    /// fade is long; shorter lead-ins shorten the fade.
+    pub fn set_loop_crossfade(&mut self, crossfade_ms: f32) {
+        self.loop_crossfade_ms = crossfade_ms.clamp(0.0, 1_000.0);
+    }
+
     pub fn set_trigger_mode(&mut self, mode: SamplerTriggerMode) {
         self.trigger_mode = mode;
     }
@@ -221,8 +547,161 @@ impl Sampler {
         self.sample_data.borrow_mut().root_note = note;
     }
 
-    fn step_playhead(&mut self, step: f32, loop_start: f32, loop_end: f32, sample_len: f32) {
-        match self.loop_mode {
+    pub fn set_playback_mode(&mut self, mode: SamplerPlaybackMode) {
+        self.playback_mode = mode;
+    }
+
+    /// How much the note-off velocity scales the release of zone amp
+    /// envelopes, in the same units as the envelope node's amount.
+    pub fn set_release_velocity_amount(&mut self, amount: f32) {
+        self.release_velocity_amount = amount;
+    }
+
+    /// Grain length of stretch playback. Short grains follow transients
+    /// closely, long ones keep low notes and pads smooth.
+    pub fn set_grain_size(&mut self, grain_size_ms: f32) {
+        self.grain_size_ms = grain_size_ms.clamp(5.0, 500.0);
+    }
+
+    /// Overlapping grains in stretch playback, 2 to 8. More overlap smooths
+    /// out the grain flutter at the cost of a sample read per grain.
+    pub fn set_stretch_quality(&mut self, quality: u8) {
+        let count = quality.clamp(2, MAX_STRETCH_GRAINS) as usize;
+        self.grains.resize(count, Grain::default());
+        self.restart_grains();
+    }
+
+    /// Spreads the grains evenly over a grain length, all reading from the
+    /// playhead, so the windows sum to a constant from the first sample.
+    fn restart_grains(&mut self) {
+        let grain_len = self.grain_len();
+        let count = self.grains.len() as f32;
+        for (index, grain) in self.grains.iter_mut().enumerate() {
+            grain.start = self.playhead;
+            grain.age = grain_len * index as f32 / count;
+        }
+    }
+
+    fn grain_len(&self) -> f32 {
+        (self.grain_size_ms * 0.001 * self.sample_rate).max(1.0)
+    }
+
+    /// One frame of stretch playback. Each grain reads the sample at
+    /// `pitch_rate` from where the playhead was when it started, while the
+    /// playhead itself moves at `time_rate`, so the sample keeps its length
+    /// whatever note plays it.
+    fn next_stretch_frame(
+        &mut self,
+        source: &SampleData,
+        pitch_rate: f32,
+        time_rate: f32,
+        loop_region: (SamplerLoopMode, f32, f32),
+        sample_len: f32,
+    ) -> (f32, f32) {
+        let (loop_mode, loop_start, loop_end) = loop_region;
+        let grain_len = self.grain_len();
+        let (mut left, mut right) = (0.0, 0.0);
+        for index in 0..self.grains.len() {
+            let grain = &mut self.grains[index];
+            if grain.age >= grain_len {
+                grain.age %= grain_len;
+                grain.start = self.playhead;
+            }
+            let phase = grain.age / grain_len;
+            let window = 0.5 - 0.5 * (std::f32::consts::TAU * phase).cos();
+            let mut position = grain.start + grain.age * pitch_rate;
+            grain.age += 1.0;
+            if loop_mode == SamplerLoopMode::Loop && position >= loop_end {
+                position = loop_start + (position - loop_end) % (loop_end - loop_start);
+            }
+            // Grains running past the end of a one-shot fade out to silence.
+            if position >= sample_len {
+                continue;
+            }
+            let (l, r) = if loop_mode == SamplerLoopMode::Loop {
+                self.read_looped(source, position, loop_region)
+            } else {
+                self.read(source, position)
+            };
+            left += l * window;
+            right += r * window;
+        }
+        let step = time_rate * self.direction;
+        self.step_playhead(step, loop_mode, loop_start, loop_end, sample_len);
+
+        // Evenly spaced Hann windows sum to half the grain count.
+        let normalize = 2.0 / self.grains.len() as f32;
+        (left * normalize, right * normalize)
+    }
+
+    #[inline]
+    fn read(&self, source: &SampleData, position: f32) -> (f32, f32) {
+        if self.hermite_interpolation {
+            source.get_sample_hermite(position)
+        } else {
+            source.get_sample_interpolated(position)
+        }
+    }
+
+    /// The sample at `position`, blended across the loop boundary over the
+    /// loop crossfade.
+    fn read_looped(
+        &self,
+        source: &SampleData,
+        position: f32,
+        loop_region: (SamplerLoopMode, f32, f32),
+    ) -> (f32, f32) {
+        let (loop_mode, loop_start, loop_end) = loop_region;
+        let width = loop_end - loop_start;
+        let crossfade = self.loop_crossfade_ms * 0.001 * source.sample_rate;
+        let (blend, other) = match loop_mode {
+            SamplerLoopMode::Off => (0.0, position),
+            SamplerLoopMode::Loop => {
+                // The loop end fades into the audio leading up to the loop
+                // start, so the jump back lands where the fade left off.
+                let length = crossfade.min(width).min(loop_start);
+                if length < 1.0 {
+                    return self.read(source, position);
+                }
+                let into_fade = position - (loop_end - length);
+                (into_fade / length, position - width)
+            }
+            SamplerLoopMode::PingPong => {
+                // Near either turn, blend in the audio mirrored about it; at
+                // the turn both halves meet, so the waveform turns smoothly.
+                let length = crossfade.min(width * 0.5);
+                if length < 1.0 {
+                    return self.read(source, position);
+                }
+                let (distance, mirror) = if position - loop_start < loop_end - position {
+                    (position - loop_start, 2.0 * loop_start - position)
+                } else {
+                    (loop_end - position, 2.0 * loop_end - position)
+                };
+                (0.5 * (1.0 - distance / length), mirror)
+            }
+        };
+        let (left, right) = self.read(source, position);
+        if blend <= 0.0 {
+            return (left, right);
+        }
+        let blend = blend.min(1.0);
+        let (other_left, other_right) = self.read(source, other);
+        (
+            left + (other_left - left) * blend,
+            right + (other_right - right) * blend,
+        )
+    }
+
+    fn step_playhead(
+        &mut self,
+        step: f32,
+        loop_mode: SamplerLoopMode,
+        loop_start: f32,
+        loop_end: f32,
+        sample_len: f32,
+    ) {
+        match loop_mode {
             SamplerLoopMode::Off => {
                 // One-shot playback - play once and stop at the end
                 self.playhead += step;
@@ -261,6 +740,47 @@ impl Sampler {
         }
     }
 
+    /// Advances the zone amp envelope by one sample and returns its level.
+    /// Playback stops once the release has run out.
+    fn next_amp_level(&mut self, envelope: &AmpEnvelope, gate_open: bool) -> f32 {
+        let sample_rate = self.sample_rate;
+        let rate = |seconds: f32| 1.0 / (seconds * sample_rate).max(1.0);
+        if !gate_open && self.amp_stage != AmpStage::Release {
+            self.amp_stage = AmpStage::Release;
+            let release = envelope.release
+                * Envelope::release_velocity_scale(
+                    self.release_velocity_amount,
+                    self.release_velocity,
+                );
+            self.release_step = self.amp_level * rate(release);
+        }
+        match self.amp_stage {
+            AmpStage::Attack => {
+                self.amp_level += rate(envelope.attack);
+                if self.amp_level >= 1.0 {
+                    self.amp_level = 1.0;
+                    self.amp_stage = AmpStage::Decay;
+                }
+            }
+            AmpStage::Decay => {
+                self.amp_level -= (1.0 - envelope.sustain) * rate(envelope.decay);
+                if self.amp_level <= envelope.sustain {
+                    self.amp_level = envelope.sustain;
+                    self.amp_stage = AmpStage::Sustain;
+                }
+            }
+            AmpStage::Sustain => self.amp_level = envelope.sustain,
+            AmpStage::Release => {
+                self.amp_level -= self.release_step;
+                if self.amp_level <= 0.0 {
+                    self.amp_level = 0.0;
+                    self.is_playing = false;
+                }
+            }
+        }
+        self.amp_level
+    }
+
     fn ensure_scratch_buffers(&mut self, size: usize) {
         let resize_if_needed = |buf: &mut Vec<f32>, default_val: f32| {
             if buf.len() < size {
@@ -316,6 +836,8 @@ impl AudioNode for Sampler {
         ports.insert(PortId::AudioOutput1, true); // Right output
         ports.insert(PortId::GlobalGate, false); // Gate input
         ports.insert(PortId::GlobalFrequency, false); // Note pitch from voice
+        ports.insert(PortId::GlobalVelocity, false); // Note velocity, for zone selection
+        ports.insert(PortId::ReleaseVelocity, false); // Note-off velocity, for zone amp release
         ports.insert(PortId::FrequencyMod, false); // Frequency modulation
         ports.insert(PortId::GainMod, false); // Gain modulation
         ports.insert(PortId::StereoPan, false); // Stereo pan modulation (0..1 via macros)
@@ -374,16 +896,14 @@ impl AudioNode for Sampler {
 
         // Calculate playback rate based on frequency
         // Frequency is in Hz, need to convert to playback rate
-        let sample_len = {
-            let sample_data = self.sample_data.borrow();
-            sample_data.len() as f32
-        };
+        let shared = Rc::clone(&self.sample_data);
+        let data = shared.borrow();
 
         // Get output buffers - must split to get two mutable references
         let has_left = outputs.contains_key(&PortId::AudioOutput0);
         let has_right = outputs.contains_key(&PortId::AudioOutput1);
 
-        if sample_len <= 0.0 {
+        if data.is_empty() && data.zones.is_empty() {
             // No sample loaded, output silence
             if has_left {
                 if let Some(buf) = outputs.get_mut(&PortId::AudioOutput0) {
@@ -404,16 +924,29 @@ impl AudioNode for Sampler {
 
         // Calculate the base playback rate
         // Convert MIDI note to frequency: freq = 440 * 2^((note - 69) / 12)
-        let (root_freq, sample_rate_ratio) = {
-            let sample_data = self.sample_data.borrow();
-            let rf = 440.0 * 2.0_f32.powf((sample_data.root_note - 69.0) / 12.0);
-            let srr = sample_data.sample_rate / self.sample_rate;
-            (rf, srr)
+        let engine_rate = self.sample_rate;
+        let playback = |source: &SampleData| {
+            let root_freq = 440.0 * 2.0_f32.powf((source.root_note - 69.0) / 12.0);
+            let sample_rate_ratio = source.sample_rate / engine_rate;
+            (source.len() as f32, root_freq, sample_rate_ratio)
+        };
+        let zone_playback = |zone: Option<usize>| {
+            zone.and_then(|index| data.zones.get(index))
+                .map_or(ZonePlayback::default(), SampleZone::playback)
         };
+        let mut source = data.source(self.zone);
+        let (mut sample_len, mut root_freq, mut sample_rate_ratio) = playback(source);
+        let mut zone_settings = zone_playback(self.zone);
 
         let global_freq_source = inputs
             .get(&PortId::GlobalFrequency)
             .and_then(|sources| sources.first());
+        let velocity_source = inputs
+            .get(&PortId::GlobalVelocity)
+            .and_then(|sources| sources.first());
+        let release_velocity_source = inputs
+            .get(&PortId::ReleaseVelocity)
+            .and_then(|sources| sources.first());
 
         // Process samples directly to output
         let tuning_ratio = if self.base_frequency <= 0.0 {
@@ -424,76 +957,108 @@ impl AudioNode for Sampler {
 
         for i in 0..buffer_size {
             let gate = self.gate_buffer[i];
+            if let Some(&velocity) = release_velocity_source.and_then(|src| src.buffer.get(i)) {
+                self.release_velocity = velocity;
+            }
+
+            // Calculate frequency for this sample
+            let base_pitch = global_freq_source
+                .and_then(|src| src.buffer.get(i).copied())
+                .unwrap_or(440.0);
 
             // Handle gate triggers
             let gate_rising = gate > 0.5 && self.last_gate <= 0.5;
-            match self.trigger_mode {
+            let triggered = match self.trigger_mode {
                 SamplerTriggerMode::FreeRunning => {
                     self.is_playing = true;
+                    false
                 }
-                SamplerTriggerMode::Gate => {
-                    if gate_rising {
-                        // Start at requested sample offset (if provided), otherwise at 0.
-                        if has_offset_mod {
-                            let offset_norm = offset_add[i].clamp(0.0, 1.0);
-                            self.playhead = offset_norm * (sample_len - 1.0);
-                        } else {
-                            self.playhead = 0.0;
-                        }
-                        self.direction = 1.0;
-                        self.is_playing = true;
-                    }
+                SamplerTriggerMode::Gate => gate_rising,
+                SamplerTriggerMode::OneShot => gate_rising && !self.is_playing,
+            };
+            if triggered {
+                if !data.zones.is_empty() {
+                    let key = 69.0 + 12.0 * (base_pitch.max(1.0) / 440.0).log2();
+                    let velocity = velocity_source
+                        .and_then(|src| src.buffer.get(i).copied())
+                        .unwrap_or(1.0);
+                    self.zone = data.select_zone(
+                        key.round().clamp(0.0, 127.0) as u8,
+                        (velocity * 127.0).round().clamp(0.0, 127.0) as u8,
+                    );
+                    source = data.source(self.zone);
+                    (sample_len, root_freq, sample_rate_ratio) = playback(source);
+                    zone_settings = zone_playback(self.zone);
                 }
-                SamplerTriggerMode::OneShot => {
-                    if gate_rising && !self.is_playing {
-                        if has_offset_mod {
-                            let offset_norm = offset_add[i].clamp(0.0, 1.0);
-                            self.playhead = offset_norm * (sample_len - 1.0);
-                        } else {
-                            self.playhead = 0.0;
-                        }
-                        self.direction = 1.0;
-                        self.is_playing = true;
-                        self.oneshot_complete = false;
-                    }
+                // Start at requested sample offset (if provided), otherwise at 0.
+                if has_offset_mod {
+                    let offset_norm = offset_add[i].clamp(0.0, 1.0);
+                    self.playhead = offset_norm * (sample_len - 1.0).max(0.0);
+                } else {
+                    self.playhead = 0.0;
                 }
+                self.direction = 1.0;
+                self.is_playing = true;
+                self.oneshot_complete = false;
+                self.amp_level = 0.0;
+                self.amp_stage = AmpStage::Attack;
+                self.restart_grains();
             }
             self.last_gate = gate;
-
-            // Calculate frequency for this sample
-            let base_pitch = global_freq_source
-                .and_then(|src| src.buffer.get(i).copied())
-                .unwrap_or(440.0);
             let freq = ((base_pitch + freq_add[i]) * freq_mult[i]) * tuning_ratio;
             let playback_rate = (freq / root_freq) * sample_rate_ratio;
 
             // Calculate gain for this sample
-            let gain = (self.base_gain + gain_add[i]) * gain_mult[i];
-
-            // Get sample value at current playhead with simple 2x oversampling
-            let (mut left, mut right) = if self.is_playing {
-                let loop_start = self.loop_start.clamp(0.0, sample_len - 1.0);
-                let loop_end = self.loop_end.clamp(loop_start + 1.0, sample_len);
-                let step = (playback_rate * self.direction) / SAMPLER_OVERSAMPLE_FACTOR as f32;
-
-                let mut acc_left = 0.0;
-                let mut acc_right = 0.0;
-
-                for _ in 0..SAMPLER_OVERSAMPLE_FACTOR {
-                    let (l, r) = {
-                        let sample_data = self.sample_data.borrow();
-                        sample_data.get_sample_interpolated(self.playhead)
-                    };
-                    acc_left += l * gain;
-                    acc_right += r * gain;
-                    self.step_playhead(step, loop_start, loop_end, sample_len);
-                    if !self.is_playing {
-                        break;
+            let mut gain = (self.base_gain.next_value() + gain_add[i]) * gain_mult[i];
+            if let Some(envelope) = zone_settings.amp_envelope {
+                // One-shots play out regardless of the gate.
+                let gate_open = gate > 0.5 || self.trigger_mode == SamplerTriggerMode::OneShot;
+                gain *= self.next_amp_level(&envelope, gate_open);
+            }
+
+            // Get sample value at current playhead with simple oversampling
+            let (mut left, mut right) = if self.is_playing && sample_len > 0.0 {
+                let (loop_mode, loop_start, loop_end) = match zone_settings.looping {
+                    Some(looping) => looping,
+                    // Zones may be loaded without a base sample to set the loop end.
+                    None if self.loop_end <= 0.0 => (self.loop_mode, self.loop_start, sample_len),
+                    None => (self.loop_mode, self.loop_start, self.loop_end),
+                };
+                let loop_start = loop_start.clamp(0.0, sample_len - 1.0);
+                let loop_end = loop_end.clamp(loop_start + 1.0, sample_len);
+                let loop_region = (loop_mode, loop_start, loop_end);
+                match self.playback_mode {
+                    SamplerPlaybackMode::Stretch => {
+                        let (l, r) = self.next_stretch_frame(
+                            source,
+                            playback_rate,
+                            sample_rate_ratio,
+                            loop_region,
+                            sample_len,
+                        );
+                        (l * gain, r * gain)
                     }
-                }
+                    SamplerPlaybackMode::Repitch => {
+                        let oversample_factor = self.oversample_factor;
+                        let step = (playback_rate * self.direction) / oversample_factor as f32;
+
+                        let mut acc_left = 0.0;
+                        let mut acc_right = 0.0;
+
+                        for _ in 0..oversample_factor {
+                            let (l, r) = self.read_looped(source, self.playhead, loop_region);
+                            acc_left += l * gain;
+                            acc_right += r * gain;
+                            self.step_playhead(step, loop_mode, loop_start, loop_end, sample_len);
+                            if !self.is_playing {
+                                break;
+                            }
+                        }
 
-                let factor = 1.0 / SAMPLER_OVERSAMPLE_FACTOR as f32;
-                (acc_left * factor, acc_right * factor)
+                        let factor = 1.0 / oversample_factor as f32;
+                        (acc_left * factor, acc_right * factor)
+                    }
+                }
             } else {
                 (0.0, 0.0)
             };
@@ -534,6 +1099,8 @@ impl AudioNode for Sampler {
         self.last_gate = 0.0;
         self.is_playing = false;
         self.oneshot_complete = false;
+        self.zone = None;
+        self.amp_level = 0.0;
     }
 
     fn as_any_mut(&mut self) -> &mut dyn Any {
@@ -548,6 +1115,20 @@ impl AudioNode for Sampler {
         self.active
     }
 
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.base_gain.set_time_ms(time_ms);
+    }
+
+    fn set_quality_mode(&mut self, mode: QualityMode) {
+        let (oversample_factor, hermite) = match mode {
+            QualityMode::Eco => (1, false),
+            QualityMode::Normal => (DEFAULT_OVERSAMPLE_FACTOR, false),
+            QualityMode::High => (4, true),
+        };
+        self.oversample_factor = oversample_factor;
+        self.hermite_interpolation = hermite;
+    }
+
     fn set_active(&mut self, active: bool) {
         self.active = active;
     }
@@ -564,7 +1145,7 @@ impl AudioNode for Sampler {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::graph::ModulationSource;
+    use crate::graph::{ModulationSource, ModulationTransformation};
 
     #[test]
     fn sampler_with_default_sample_produces_output() {
@@ -604,4 +1185,222 @@ mod tests {
             "Right channel is entirely silent"
         );
     }
+
+    #[test]
+    fn hermite_interpolation_passes_through_frames_and_curves_between_them() {
+        let mut data = SampleData::new();
+        data.load_from_wav(vec![0.0, 1.0, 0.0, -1.0, 0.0], 1, 48_000.0);
+
+        for (index, expected) in [0.0, 1.0, 0.0, -1.0].iter().enumerate() {
+            assert_eq!(data.get_sample_hermite(index as f32).0, *expected);
+        }
+        // Linear would give 0.5 halfway up the ramp; the cubic bulges past it.
+        let (left, right) = data.get_sample_hermite(0.5);
+        assert_eq!(left, right);
+        assert!(left > 0.5 && left < 1.0, "{}", left);
+    }
+
+    #[test]
+    fn every_quality_mode_plays_the_sample() {
+        let sample_rate = 48_000.0;
+        let inputs: FxHashMap<PortId, Vec<ModulationSource<'static>>> = FxHashMap::default();
+        for mode in [QualityMode::Eco, QualityMode::Normal, QualityMode::High] {
+            let mut sampler = Sampler::new(sample_rate);
+            let sample_data = Rc::new(RefCell::new(SampleData::new()));
+            sample_data
+                .borrow_mut()
+                .load_from_wav(vec![0.5; 256], 1, sample_rate);
+            sample_data.borrow_mut().root_note = 69.0;
+            sampler.set_sample_data(sample_data);
+            sampler.set_quality_mode(mode);
+
+            let mut left = vec![0.0_f32; 64];
+            let mut right = vec![0.0_f32; 64];
+            let mut outputs: FxHashMap<PortId, &mut [f32]> = FxHashMap::default();
+            outputs.insert(PortId::AudioOutput0, &mut left[..]);
+            outputs.insert(PortId::AudioOutput1, &mut right[..]);
+            sampler.process(&inputs, &mut outputs, 64);
+
+            assert!(
+                left.iter().all(|&v| (v - 0.5).abs() < 1e-4),
+                "{:?} changed a constant sample",
+                mode
+            );
+        }
+    }
+
+    #[test]
+    fn zones_follow_key_velocity_and_round_robin() {
+        let constant = |value: f32| {
+            let mut sample = SampleData::new();
+            sample.load_from_wav(vec![value; 256], 1, 48_000.0);
+            sample
+        };
+        let mut data = SampleData::new();
+        let low = ZoneMapping {
+            key_high: 59,
+            root_note: 48.0,
+            ..ZoneMapping::default()
+        };
+        let high = ZoneMapping {
+            key_low: 60,
+            root_note: 72.0,
+            round_robin_group: 1,
+            ..ZoneMapping::default()
+        };
+        data.add_zone(constant(0.25), low);
+        data.add_zone(constant(0.5), high);
+        data.add_zone(constant(0.75), high);
+        let soft_only = ZoneMapping {
+            velocity_high: 40,
+            ..low
+        };
+        data.zones[0].set_mapping(soft_only);
+        assert_eq!(data.zones[0].sample().root_note, 48.0);
+
+        assert_eq!(data.select_zone(40, 30), Some(0));
+        assert_eq!(data.select_zone(40, 100), None);
+        let picks: Vec<_> = (0..4).map(|_| data.select_zone(72, 100)).collect();
+        assert_eq!(picks, [Some(1), Some(2), Some(1), Some(2)]);
+
+        // A note on the keyboard plays the zone covering it.
+        let mut sampler = Sampler::new(48_000.0);
+        sampler.set_sample_data(Rc::new(RefCell::new(data)));
+        let pitch = vec![440.0 * 2.0_f32.powf((72.0 - 69.0) / 12.0); 64];
+        let mut inputs: FxHashMap<PortId, Vec<ModulationSource>> = FxHashMap::default();
+        inputs.insert(
+            PortId::GlobalFrequency,
+            vec![ModulationSource {
+                buffer: &pitch,
+                amount: 1.0,
+                mod_type: ModulationType::Additive,
+                transformation: ModulationTransformation::None,
+            }],
+        );
+        let mut left = vec![0.0_f32; 64];
+        let mut outputs: FxHashMap<PortId, &mut [f32]> = FxHashMap::default();
+        outputs.insert(PortId::AudioOutput0, &mut left[..]);
+        sampler.process(&inputs, &mut outputs, 64);
+        assert!(
+            left[1..].iter().all(|&v| (v - 0.5).abs() < 1e-4),
+            "{:?}",
+            &left[..4]
+        );
+    }
+
+    #[test]
+    fn stretch_keeps_the_sample_length_an_octave_up() {
+        let sample_rate = 48_000.0;
+        let pitch = vec![880.0; 128];
+        let mut inputs: FxHashMap<PortId, Vec<ModulationSource>> = FxHashMap::default();
+        inputs.insert(
+            PortId::GlobalFrequency,
+            vec![ModulationSource {
+                buffer: &pitch,
+                amount: 1.0,
+                mod_type: ModulationType::Additive,
+                transformation: ModulationTransformation::None,
+            }],
+        );
+        let render = |mode: SamplerPlaybackMode| {
+            let mut sampler = Sampler::new(sample_rate);
+            let sample_data = Rc::new(RefCell::new(SampleData::new()));
+            sample_data
+                .borrow_mut()
+                .load_from_wav(vec![0.5; 4_800], 1, sample_rate);
+            sample_data.borrow_mut().root_note = 69.0;
+            sampler.set_sample_data(sample_data);
+            sampler.set_playback_mode(mode);
+            let mut rendered = Vec::new();
+            for _ in 0..60 {
+                let mut left = vec![0.0_f32; 128];
+                let mut outputs: FxHashMap<PortId, &mut [f32]> = FxHashMap::default();
+                outputs.insert(PortId::AudioOutput0, &mut left[..]);
+                sampler.process(&inputs, &mut outputs, 128);
+                rendered.extend(left);
+            }
+            rendered
+        };
+        let audible = |rendered: &[f32]| rendered.iter().filter(|v| v.abs() > 1e-3).count();
+
+        let repitched = render(SamplerPlaybackMode::Repitch);
+        assert!(
+            audible(&repitched).abs_diff(2_400) < 10,
+            "{}",
+            audible(&repitched)
+        );
+        // Grains fade out as they run into the end, then playback stops on time.
+        let stretched = render(SamplerPlaybackMode::Stretch);
+        assert!(
+            (3_600..=4_800).contains(&audible(&stretched)),
+            "{}",
+            audible(&stretched)
+        );
+        // Overlapping grains add back up to the sample's level.
+        assert!(stretched[600..2_000]
+            .iter()
+            .all(|&v| (v - 0.5).abs() < 1e-3));
+    }
+
+    #[test]
+    fn loop_crossfade_smooths_the_jump_back() {
+        let sample_rate = 48_000.0;
+        let inputs: FxHashMap<PortId, Vec<ModulationSource<'static>>> = FxHashMap::default();
+        let largest_step = |crossfade_frames: f32| {
+            let mut sampler = Sampler::new(sample_rate);
+            let sample_data = Rc::new(RefCell::new(SampleData::new()));
+            let ramp = (0..1_000).map(|i| i as f32 / 1_000.0).collect();
+            sample_data.borrow_mut().load_from_wav(ramp, 1, sample_rate);
+            sample_data.borrow_mut().root_note = 69.0;
+            sampler.set_sample_data(sample_data);
+            sampler.set_loop_mode(SamplerLoopMode::Loop);
+            sampler.set_loop_start(500.0);
+            sampler.set_loop_end(900.0);
+            sampler.set_loop_crossfade(crossfade_frames * 1_000.0 / sample_rate);
+
+            let mut rendered = Vec::new();
+            for _ in 0..16 {
+                let mut left = vec![0.0_f32; 128];
+                let mut outputs: FxHashMap<PortId, &mut [f32]> = FxHashMap::default();
+                outputs.insert(PortId::AudioOutput0, &mut left[..]);
+                sampler.process(&inputs, &mut outputs, 128);
+                rendered.extend(left);
+            }
+            // The loop is 400 frames long, so any 400 samples after the attack in
+            // the first block span exactly one jump from the loop end to its start.
+            rendered[128..528]
+                .windows(2)
+                .map(|pair| (pair[1] - pair[0]).abs())
+                .fold(0.0, f32::max)
+        };
+        assert!(largest_step(0.0) > 0.3);
+        assert!(largest_step(100.0) < 0.01);
+    }
+
+    #[test]
+    fn malformed_wav_files_are_errors() {
+        let wav = |sample_rate: u32| {
+            let mut bytes = Vec::new();
+            let spec = hound::WavSpec {
+                channels: 1,
+                sample_rate,
+                bits_per_sample: 32,
+                sample_format: hound::SampleFormat::Float,
+            };
+            let cursor = std::io::Cursor::new(&mut bytes);
+            let mut writer = hound::WavWriter::new(cursor, spec).unwrap();
+            for sample in [0.5, f32::NAN, f32::INFINITY, -0.5] {
+                writer.write_sample(sample).unwrap();
+            }
+            writer.finalize().unwrap();
+            bytes
+        };
+
+        let decoded = SampleData::from_wav_bytes(&wav(48_000)).unwrap();
+        assert_eq!(decoded.samples, [0.5, 0.0, 0.0, -0.5]);
+        assert!(SampleData::from_wav_bytes(&wav(10)).is_err());
+        let truncated = wav(48_000);
+        assert!(SampleData::from_wav_bytes(&truncated[..truncated.len() - 6]).is_err());
+        assert!(SampleData::from_wav_bytes(b"RIFF").is_err());
+    }
 }
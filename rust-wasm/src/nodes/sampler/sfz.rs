@@ -0,0 +1,335 @@
+// SFZ instrument import. Covers the subset most instruments lean on:
+// `<region>` headers with defaults inherited from `<control>`, `<global>`,
+// `<master>` and `<group>`; `sample` (with `default_path`), key and velocity
+// ranges, `pitch_keycenter`, `transpose` and `tune`, loop settings, `ampeg_*`
+// and `seq_length` round robins. Other headers and opcodes are ignored.
+
+use rustc_hash::FxHashMap;
+
+use super::{AmpEnvelope, SampleData, SampleZone, SamplerLoopMode, ZoneMapping, ZonePlayback};
+
+/// Header levels, outermost first. A header clears the opcodes of its own
+/// level and every level inside it.
+const LEVELS: [&str; 5] = ["control", "global", "master", "group", "region"];
+const REGION: usize = 4;
+const AMPEG: [&str; 4] = [
+    "ampeg_attack",
+    "ampeg_decay",
+    "ampeg_sustain",
+    "ampeg_release",
+];
+
+type Opcodes = FxHashMap<String, String>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SfzRegion {
+    /// Sample path as written, after `default_path`.
+    pub sample: String,
+    pub mapping: ZoneMapping,
+    pub playback: ZonePlayback,
+}
+
+enum Token {
+    Header(String),
+    Opcode(String, String),
+}
+
+pub fn parse_sfz(text: &str) -> Result<Vec<SfzRegion>, String> {
+    let mut scopes: [Opcodes; 5] = Default::default();
+    let mut current = None;
+    let mut regions = Vec::new();
+    for token in tokenize(text)? {
+        match token {
+            Token::Header(name) => {
+                if current == Some(REGION) {
+                    regions.extend(region(&scopes)?);
+                }
+                current = LEVELS.iter().position(|level| *level == name);
+                if let Some(level) = current {
+                    for scope in &mut scopes[level..] {
+                        scope.clear();
+                    }
+                }
+            }
+            Token::Opcode(name, value) => {
+                if let Some(level) = current {
+                    scopes[level].insert(name, value);
+                }
+            }
+        }
+    }
+    if current == Some(REGION) {
+        regions.extend(region(&scopes)?);
+    }
+    Ok(regions)
+}
+
+/// Parses `text` and builds a zone per region. `resolve_sample` gets each
+/// region's sample path and returns the WAV file; regions sharing a sample
+/// share one decode.
+pub fn load_sfz(
+    text: &str,
+    mut resolve_sample: impl FnMut(&str) -> Result<Vec<u8>, String>,
+) -> Result<Vec<SampleZone>, String> {
+    let mut decoded: FxHashMap<String, SampleData> = FxHashMap::default();
+    parse_sfz(text)?
+        .into_iter()
+        .map(|region| {
+            if !decoded.contains_key(&region.sample) {
+                let sample = SampleData::from_wav_bytes(&resolve_sample(&region.sample)?)
+                    .map_err(|e| format!("{}: {}", region.sample, e))?;
+                decoded.insert(region.sample.clone(), sample);
+            }
+            let sample = decoded[&region.sample].clone();
+            Ok(SampleZone::new(sample, region.mapping).with_playback(region.playback))
+        })
+        .collect()
+}
+
+/// The region the innermost scopes describe; `None` when it names no sample.
+fn region(scopes: &[Opcodes]) -> Result<Option<SfzRegion>, String> {
+    let get = |name: &str| {
+        scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .map(String::as_str)
+    };
+    let number = |name: &str| {
+        get(name)
+            .map(|value| {
+                value
+                    .parse::<f32>()
+                    .map_err(|_| format!("Invalid {} value: {}", name, value))
+            })
+            .transpose()
+    };
+    let key = |name: &str| get(name).map(|value| parse_key(name, value)).transpose();
+    let velocity = |name: &str| {
+        number(name).map(|value| value.map(|velocity| velocity.clamp(0.0, 127.0) as u8))
+    };
+
+    let Some(sample) = get("sample") else {
+        return Ok(None);
+    };
+    let sample =
+        format!("{}{}", get("default_path").unwrap_or_default(), sample).replace('\\', "/");
+
+    let single_key = key("key")?;
+    let key_center = key("pitch_keycenter")?.or(single_key).unwrap_or(60);
+    let root_note = key_center as f32
+        - number("transpose")?.unwrap_or(0.0)
+        - number("tune")?.unwrap_or(0.0) / 100.0;
+    let seq_length = number("seq_length")?.unwrap_or(1.0) as u32;
+    let mapping = ZoneMapping {
+        key_low: key("lokey")?.or(single_key).unwrap_or(0),
+        key_high: key("hikey")?.or(single_key).unwrap_or(127),
+        velocity_low: velocity("lovel")?.unwrap_or(0),
+        velocity_high: velocity("hivel")?.unwrap_or(127),
+        root_note,
+        // Regions of the same sequence length covering the same notes take turns.
+        round_robin_group: if seq_length > 1 { seq_length } else { 0 },
+    };
+
+    let loop_start = number("loop_start")?.unwrap_or(0.0);
+    // SFZ loop ends are inclusive; the sampler clamps to the sample length.
+    let loop_end = number("loop_end")?.map_or(f32::MAX, |end| end + 1.0);
+    let looping = match get("loop_mode") {
+        Some("loop_continuous" | "loop_sustain") => {
+            Some((SamplerLoopMode::Loop, loop_start, loop_end))
+        }
+        Some("no_loop" | "one_shot") => Some((SamplerLoopMode::Off, 0.0, f32::MAX)),
+        _ => None,
+    };
+    let amp_envelope = if AMPEG.iter().any(|name| get(name).is_some()) {
+        Some(AmpEnvelope {
+            attack: number("ampeg_attack")?.unwrap_or(0.0).max(0.0),
+            decay: number("ampeg_decay")?.unwrap_or(0.0).max(0.0),
+            sustain: (number("ampeg_sustain")?.unwrap_or(100.0) / 100.0).clamp(0.0, 1.0),
+            release: number("ampeg_release")?.unwrap_or(0.001).max(0.0),
+        })
+    } else {
+        None
+    };
+
+    Ok(Some(SfzRegion {
+        sample,
+        mapping,
+        playback: ZonePlayback {
+            looping,
+            amp_envelope,
+        },
+    }))
+}
+
+/// A MIDI note number or note name such as `c4` (60), `f#3` or `eb5`.
+fn parse_key(name: &str, value: &str) -> Result<u8, String> {
+    let invalid = || format!("Invalid {} value: {}", name, value);
+    let key = match value.parse::<i32>() {
+        Ok(number) => number,
+        Err(_) => note_number(value).ok_or_else(invalid)?,
+    };
+    if (0..=127).contains(&key) {
+        Ok(key as u8)
+    } else {
+        Err(invalid())
+    }
+}
+
+fn note_number(value: &str) -> Option<i32> {
+    let lower = value.to_ascii_lowercase();
+    let mut chars = lower.chars();
+    let semitone = match chars.next() {
+        Some('c') => 0,
+        Some('d') => 2,
+        Some('e') => 4,
+        Some('f') => 5,
+        Some('g') => 7,
+        Some('a') => 9,
+        Some('b') => 11,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let (accidental, octave) = match rest.strip_prefix('#') {
+        Some(octave) => (1, octave),
+        None => match rest.strip_prefix('b') {
+            Some(octave) => (-1, octave),
+            None => (0, rest),
+        },
+    };
+    let octave: i32 = octave.parse().ok()?;
+    Some((octave + 1) * 12 + semitone + accidental)
+}
+
+/// Splits SFZ text into headers and opcodes. Opcode values run up to the
+/// next opcode or header, so sample paths may contain spaces.
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    for line in text.lines() {
+        let line = line.split("//").next().unwrap_or_default().trim();
+        // Preprocessor lines (#define, #include) aren't supported.
+        if line.starts_with('#') {
+            continue;
+        }
+        let mut rest = line;
+        while !rest.is_empty() {
+            if let Some(header) = rest.strip_prefix('<') {
+                let end = header
+                    .find('>')
+                    .ok_or_else(|| format!("Unclosed header: {}", rest))?;
+                tokens.push(Token::Header(header[..end].trim().to_string()));
+                rest = header[end + 1..].trim_start();
+                continue;
+            }
+            let equals = rest
+                .find('=')
+                .ok_or_else(|| format!("Expected an opcode: {}", rest))?;
+            let value = &rest[equals + 1..];
+            let end = value_end(value);
+            tokens.push(Token::Opcode(
+                rest[..equals].trim().to_string(),
+                value[..end].trim().to_string(),
+            ));
+            rest = value[end..].trim_start();
+        }
+    }
+    Ok(tokens)
+}
+
+/// Where an opcode value ends: at the next header, or at the whitespace
+/// before the next `name=`.
+fn value_end(value: &str) -> usize {
+    for (i, c) in value.char_indices() {
+        if c == '<' {
+            return i;
+        }
+        if c.is_whitespace() {
+            let next = value[i..].trim_start();
+            let word = next
+                .split(|c: char| c.is_whitespace() || c == '<')
+                .next()
+                .unwrap_or_default();
+            if word.contains('=') {
+                return i;
+            }
+        }
+    }
+    value.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const PIANO: &str = "
+        // Two velocity layers, the loud one with round robins.
+        <control> default_path=Piano Samples\\
+        <group> lovel=1 hivel=63 ampeg_release=0.5
+        <region> sample=C4 soft.wav lokey=b3 hikey=c#4 pitch_keycenter=c4 tune=-10
+        <group> lovel=64 seq_length=2 loop_mode=loop_continuous loop_start=10 loop_end=99
+        <region> sample=C4 loud a.wav key=60 transpose=1
+        <region>sample=C4 loud b.wav key=60
+        <curve> sample=ignored.wav
+    ";
+
+    #[test]
+    fn regions_inherit_group_and_control_opcodes() {
+        let regions = parse_sfz(PIANO).unwrap();
+        assert_eq!(regions.len(), 3);
+
+        let soft = &regions[0];
+        assert_eq!(soft.sample, "Piano Samples/C4 soft.wav");
+        assert_eq!((soft.mapping.key_low, soft.mapping.key_high), (59, 61));
+        assert_eq!(
+            (soft.mapping.velocity_low, soft.mapping.velocity_high),
+            (1, 63)
+        );
+        assert!((soft.mapping.root_note - 60.1).abs() < 1e-4);
+        assert_eq!(soft.playback.looping, None);
+        let envelope = soft.playback.amp_envelope.unwrap();
+        assert_eq!((envelope.sustain, envelope.release), (1.0, 0.5));
+
+        let loud = &regions[1];
+        assert_eq!((loud.mapping.key_low, loud.mapping.key_high), (60, 60));
+        assert_eq!(loud.mapping.velocity_low, 64);
+        assert_eq!(loud.mapping.root_note, 59.0);
+        assert_eq!(loud.mapping.round_robin_group, 2);
+        assert_eq!(
+            loud.playback.looping,
+            Some((SamplerLoopMode::Loop, 10.0, 100.0))
+        );
+        assert_eq!(loud.playback.amp_envelope, None);
+        assert_eq!(regions[2].sample, "Piano Samples/C4 loud b.wav");
+    }
+
+    #[test]
+    fn shared_samples_are_resolved_once() {
+        let mut wav = Vec::new();
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 44_100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::new(Cursor::new(&mut wav), spec).unwrap();
+        for _ in 0..64 {
+            writer.write_sample(i16::MAX / 2).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let mut requests = Vec::new();
+        let sfz = "<region> sample=a.wav hikey=59 <region> sample=a.wav lokey=60";
+        let zones = load_sfz(sfz, |path| {
+            requests.push(path.to_string());
+            Ok(wav.clone())
+        })
+        .unwrap();
+        assert_eq!(requests, ["a.wav"]);
+        assert_eq!(zones.len(), 2);
+        assert_eq!(zones[1].sample().len(), 64);
+
+        let missing = load_sfz(sfz, |path| Err(format!("{} not found", path)));
+        assert_eq!(missing.err().as_deref(), Some("a.wav not found"));
+    }
+}
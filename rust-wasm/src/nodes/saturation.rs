@@ -1,39 +1,202 @@
 use std::any::Any;
+use std::f32::consts::PI;
 use std::simd::f32x4;
 
 use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::wasm_bindgen;
 
 use crate::graph::ModulationSource;
 use crate::traits::{AudioNode, PortId};
+use crate::utils::smoothing::{smoothing_coefficient, SmoothedParam, DEFAULT_SMOOTHING_MS};
 
-/// A saturation node implementing soft clipping using tanh.
+/// Pivot of the pre/post tilt EQ: lows and highs are shelved in opposite directions around it.
+const TILT_PIVOT_HZ: f32 = 800.0;
+const MAX_TILT_DB: f32 = 12.0;
+const TUBE_BIAS: f32 = 0.25;
+/// Level of the diode's soft reverse knee relative to the forward direction.
+const DIODE_REVERSE: f32 = 0.6;
+const DC_BLOCK_COEFF: f32 = 0.995;
+/// Time constant of the level followers used by auto-gain.
+const AUTO_GAIN_MS: f32 = 300.0;
+const AUTO_GAIN_RANGE: (f32, f32) = (0.1, 4.0);
+
+/// Transfer curve of the saturator.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SaturationCharacter {
+    /// Symmetric tanh soft clipping.
+    #[default]
+    Soft = 0,
+    /// Gentler arctangent knee that keeps more of the transients.
+    Tape = 1,
+    /// Biased tanh; asymmetric, so it adds even harmonics.
+    Tube = 2,
+    /// Exponential forward knee with a lower reverse ceiling; the most aggressive.
+    Diode = 3,
+}
+
+impl SaturationCharacter {
+    #[inline(always)]
+    fn curve(self, v: f32) -> f32 {
+        match self {
+            SaturationCharacter::Soft => v.tanh(),
+            SaturationCharacter::Tape => v.atan(),
+            SaturationCharacter::Tube => (v + TUBE_BIAS).tanh() - TUBE_BIAS.tanh(),
+            SaturationCharacter::Diode => {
+                if v >= 0.0 {
+                    1.0 - (-v).exp()
+                } else {
+                    -DIODE_REVERSE * (1.0 - (v / DIODE_REVERSE).exp())
+                }
+            }
+        }
+    }
+
+    /// Asymmetric curves leave a DC offset that has to be removed after the shaper.
+    fn is_asymmetric(self) -> bool {
+        matches!(self, SaturationCharacter::Tube | SaturationCharacter::Diode)
+    }
+}
+
+/// Low/high shelf gains for a tilt of `tilt_db` (positive = brighter).
+fn tilt_gains(tilt_db: f32) -> (f32, f32) {
+    let high = 10f32.powf(tilt_db / 40.0);
+    (1.0 / high, high)
+}
+
+#[derive(Clone, Copy, Default)]
+struct ChannelState {
+    pre_tilt_low: f32,
+    post_tilt_low: f32,
+    dc_prev_in: f32,
+    dc_prev_out: f32,
+}
+
+impl ChannelState {
+    #[inline(always)]
+    fn tilt(low_state: &mut f32, x: f32, coeff: f32, (low_gain, high_gain): (f32, f32)) -> f32 {
+        *low_state += (x - *low_state) * coeff;
+        *low_state * low_gain + (x - *low_state) * high_gain
+    }
+
+    #[inline(always)]
+    fn dc_block(&mut self, x: f32) -> f32 {
+        let y = x - self.dc_prev_in + DC_BLOCK_COEFF * self.dc_prev_out;
+        self.dc_prev_in = x;
+        self.dc_prev_out = y;
+        y
+    }
+}
+
+/// A saturation node with selectable transfer curve, pre/post tilt EQ and
+/// optional auto-gain. With the default settings it is plain tanh soft clipping.
+#[derive(Clone)]
 pub struct Saturation {
     enabled: bool,
-    drive: f32, // Determines the amount of saturation. Higher values result in more saturation.
-    mix: f32,   // Mix amount: 0.0 = fully dry, 1.0 = fully saturated (wet)
+    drive: SmoothedParam, // Determines the amount of saturation. Higher values result in more saturation.
+    mix: SmoothedParam,   // Mix amount: 0.0 = fully dry, 1.0 = fully saturated (wet)
+    character: SaturationCharacter,
+    pre_tilt_db: SmoothedParam,
+    post_tilt_db: SmoothedParam,
+    tilt_coeff: f32,
+    auto_gain: bool,
+    auto_gain_coeff: f32,
+    input_power: f32,
+    output_power: f32,
+    channels: [ChannelState; 2],
 }
 
 impl Saturation {
     /// Creates a new Saturation node.
     ///
+    /// * `sample_rate` - The sample rate in Hz (used for parameter smoothing).
     /// * `drive` - The drive amount for the saturation effect.
     /// * `mix` - The mix amount (0.0 = fully dry, 1.0 = fully saturated).
-    pub fn new(drive: f32, mix: f32) -> Self {
+    pub fn new(sample_rate: f32, drive: f32, mix: f32) -> Self {
         Self {
             enabled: true,
-            drive,
-            mix: mix.clamp(0.0, 1.0),
+            drive: SmoothedParam::new(drive, sample_rate, DEFAULT_SMOOTHING_MS),
+            mix: SmoothedParam::new(mix.clamp(0.0, 1.0), sample_rate, DEFAULT_SMOOTHING_MS),
+            character: SaturationCharacter::default(),
+            pre_tilt_db: SmoothedParam::new(0.0, sample_rate, DEFAULT_SMOOTHING_MS),
+            post_tilt_db: SmoothedParam::new(0.0, sample_rate, DEFAULT_SMOOTHING_MS),
+            tilt_coeff: 1.0 - (-2.0 * PI * TILT_PIVOT_HZ / sample_rate).exp(),
+            auto_gain: false,
+            auto_gain_coeff: smoothing_coefficient(sample_rate, AUTO_GAIN_MS),
+            input_power: 0.0,
+            output_power: 0.0,
+            channels: [ChannelState::default(); 2],
         }
     }
 
     /// Sets the drive amount.
     pub fn set_drive(&mut self, drive: f32) {
-        self.drive = drive;
+        self.drive.set_target(drive);
     }
 
     /// Sets the mix amount (0.0 = fully dry, 1.0 = fully saturated).
     pub fn set_mix(&mut self, mix: f32) {
-        self.mix = mix.clamp(0.0, 1.0);
+        self.mix.set_target(mix.clamp(0.0, 1.0));
+    }
+
+    pub fn set_character(&mut self, character: SaturationCharacter) {
+        self.character = character;
+    }
+
+    /// Tilt EQ before the shaper in dB (+/-12); positive values drive the highs harder.
+    pub fn set_pre_tilt(&mut self, tilt_db: f32) {
+        self.pre_tilt_db
+            .set_target(tilt_db.clamp(-MAX_TILT_DB, MAX_TILT_DB));
+    }
+
+    /// Tilt EQ after the shaper in dB (+/-12); negative values tame the added harmonics.
+    pub fn set_post_tilt(&mut self, tilt_db: f32) {
+        self.post_tilt_db
+            .set_target(tilt_db.clamp(-MAX_TILT_DB, MAX_TILT_DB));
+    }
+
+    /// Matches the wet level to the input level, so drive and character can be
+    /// changed without jumping in loudness.
+    pub fn set_auto_gain(&mut self, enabled: bool) {
+        self.auto_gain = enabled;
+    }
+
+    pub fn drive(&self) -> f32 {
+        self.drive.target()
+    }
+
+    pub fn mix(&self) -> f32 {
+        self.mix.target()
+    }
+
+    pub fn character(&self) -> SaturationCharacter {
+        self.character
+    }
+
+    pub fn pre_tilt(&self) -> f32 {
+        self.pre_tilt_db.target()
+    }
+
+    pub fn post_tilt(&self) -> f32 {
+        self.post_tilt_db.target()
+    }
+
+    pub fn auto_gain(&self) -> bool {
+        self.auto_gain
+    }
+
+    /// Gain applied to the wet signal so its level follows the input's.
+    fn auto_gain_factor(&self) -> f32 {
+        if !self.auto_gain || self.output_power <= 1e-9 {
+            return 1.0;
+        }
+        (self.input_power / self.output_power)
+            .sqrt()
+            .clamp(AUTO_GAIN_RANGE.0, AUTO_GAIN_RANGE.1)
     }
 }
 
@@ -58,8 +221,16 @@ impl AudioNode for Saturation {
         buffer_size: usize,
     ) {
         // Extract the input buffers from the first modulation source for each channel.
-        let left_in = inputs.get(&PortId::AudioInput0).unwrap()[0].buffer;
-        let right_in = inputs.get(&PortId::AudioInput1).unwrap()[0].buffer;
+        let left_in = inputs
+            .get(&PortId::AudioInput0)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer);
+        // A mono source feeds both channels so per-voice use works without a stereo pair.
+        let right_in = inputs
+            .get(&PortId::AudioInput1)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer)
+            .or(left_in);
 
         // Retrieve output buffers using nightly's get_disjoint_mut.
         let outs = outputs.get_disjoint_mut([&PortId::AudioOutput0, &PortId::AudioOutput1]);
@@ -69,42 +240,69 @@ impl AudioNode for Saturation {
         let out_left: &mut [f32] = *out_left;
         let out_right: &mut [f32] = *out_right;
 
-        // Avoid division by zero: if drive is nearly zero, clamp it.
-        let drive = if self.drive.abs() < 0.0001 {
-            0.0001
-        } else {
-            self.drive
-        };
-        // Normalization factor to keep the output within -1.0 to 1.0.
-        let norm = drive.tanh();
-
-        // Precompute dry/wet levels.
-        let dry_level = f32x4::splat(1.0 - self.mix);
-        let wet_level = f32x4::splat(self.mix);
-
         // Process the buffer in chunks of 4 samples (SIMD width).
         let mut i = 0;
         while i < buffer_size {
             let chunk_len = (buffer_size - i).min(4);
 
+            // Drive and mix glide once per chunk to avoid zipper noise.
+            let drive = self.drive.advance(chunk_len);
+            // Avoid division by zero: if drive is nearly zero, clamp it.
+            let drive = if drive.abs() < 0.0001 { 0.0001 } else { drive };
+            // Normalization factor to keep the output within -1.0 to 1.0.
+            let character = self.character;
+            let norm = character.curve(drive);
+            let pre_tilt = tilt_gains(self.pre_tilt_db.advance(chunk_len));
+            let post_tilt = tilt_gains(self.post_tilt_db.advance(chunk_len));
+            let tilt_coeff = self.tilt_coeff;
+
+            let mix = self.mix.advance(chunk_len);
+            let dry_level = f32x4::splat(1.0 - mix);
+            let wet_level = f32x4::splat(mix);
+
             // Load up to 4 samples from left and right inputs.
             let mut in_left_arr = [0.0; 4];
             let mut in_right_arr = [0.0; 4];
-            in_left_arr[..chunk_len].copy_from_slice(&left_in[i..i + chunk_len]);
-            in_right_arr[..chunk_len].copy_from_slice(&right_in[i..i + chunk_len]);
+            if let Some(left_in) = left_in {
+                in_left_arr[..chunk_len].copy_from_slice(&left_in[i..i + chunk_len]);
+            }
+            if let Some(right_in) = right_in {
+                in_right_arr[..chunk_len].copy_from_slice(&right_in[i..i + chunk_len]);
+            }
             let in_left_vec = f32x4::from_array(in_left_arr);
             let in_right_vec = f32x4::from_array(in_right_arr);
 
-            // Apply soft clipping per sample.
-            let mut sat_left_arr = [0.0; 4];
-            let mut sat_right_arr = [0.0; 4];
-            for j in 0..chunk_len {
-                // Soft saturation: y = tanh(x * drive) / tanh(drive)
-                sat_left_arr[j] = ((in_left_arr[j] * drive).tanh()) / norm;
-                sat_right_arr[j] = ((in_right_arr[j] * drive).tanh()) / norm;
+            // Tilt, shape and de-tilt per sample.
+            let mut sat_arr = [[0.0; 4]; 2];
+            let mut input_energy = 0.0;
+            let mut output_energy = 0.0;
+            for (ch, input) in [&in_left_arr, &in_right_arr].into_iter().enumerate() {
+                let state = &mut self.channels[ch];
+                for j in 0..chunk_len {
+                    let x = input[j];
+                    let tilted =
+                        ChannelState::tilt(&mut state.pre_tilt_low, x, tilt_coeff, pre_tilt);
+                    // Saturation: y = curve(x * drive) / curve(drive)
+                    let mut y = character.curve(tilted * drive) / norm;
+                    if character.is_asymmetric() {
+                        y = state.dc_block(y);
+                    }
+                    y = ChannelState::tilt(&mut state.post_tilt_low, y, tilt_coeff, post_tilt);
+                    input_energy += x * x;
+                    output_energy += y * y;
+                    sat_arr[ch][j] = y;
+                }
             }
-            let sat_left_vec = f32x4::from_array(sat_left_arr);
-            let sat_right_vec = f32x4::from_array(sat_right_arr);
+
+            if self.auto_gain {
+                let follow = 1.0 - (1.0 - self.auto_gain_coeff).powi(chunk_len as i32);
+                let samples = (2 * chunk_len) as f32;
+                self.input_power += (input_energy / samples - self.input_power) * follow;
+                self.output_power += (output_energy / samples - self.output_power) * follow;
+            }
+            let makeup = f32x4::splat(self.auto_gain_factor());
+            let sat_left_vec = f32x4::from_array(sat_arr[0]) * makeup;
+            let sat_right_vec = f32x4::from_array(sat_arr[1]) * makeup;
 
             // Mix the dry (original) and wet (saturated) signals.
             let mixed_left_vec = in_left_vec * dry_level + sat_left_vec * wet_level;
@@ -119,7 +317,9 @@ impl AudioNode for Saturation {
     }
 
     fn reset(&mut self) {
-        // No internal buffers to reset.
+        self.channels = [ChannelState::default(); 2];
+        self.input_power = 0.0;
+        self.output_power = 0.0;
     }
 
     fn as_any_mut(&mut self) -> &mut dyn Any {
@@ -134,6 +334,13 @@ impl AudioNode for Saturation {
         self.enabled
     }
 
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.drive.set_time_ms(time_ms);
+        self.mix.set_time_ms(time_ms);
+        self.pre_tilt_db.set_time_ms(time_ms);
+        self.post_tilt_db.set_time_ms(time_ms);
+    }
+
     fn set_active(&mut self, active: bool) {
         self.enabled = active;
         if active {
@@ -141,6 +348,10 @@ impl AudioNode for Saturation {
         }
     }
 
+    fn clone_node(&self) -> Option<Box<dyn AudioNode>> {
+        Some(Box::new(self.clone()))
+    }
+
     fn name(&self) -> &'static str {
         "Saturation"
     }
@@ -149,3 +360,100 @@ impl AudioNode for Saturation {
         "saturation"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{ModulationTransformation, ModulationType};
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    fn run(saturation: &mut Saturation, input: &[f32]) -> Vec<f32> {
+        let source = |buffer| {
+            vec![ModulationSource {
+                buffer,
+                amount: 1.0,
+                mod_type: ModulationType::Additive,
+                transformation: ModulationTransformation::None,
+            }]
+        };
+        let mut inputs = FxHashMap::default();
+        inputs.insert(PortId::AudioInput0, source(input));
+        inputs.insert(PortId::AudioInput1, source(input));
+        let mut left = vec![0.0; input.len()];
+        let mut right = vec![0.0; input.len()];
+        let mut outputs = FxHashMap::default();
+        outputs.insert(PortId::AudioOutput0, left.as_mut_slice());
+        outputs.insert(PortId::AudioOutput1, right.as_mut_slice());
+        saturation.process(&inputs, &mut outputs, input.len());
+        left
+    }
+
+    fn sine(len: usize, amplitude: f32) -> Vec<f32> {
+        (0..len)
+            .map(|i| amplitude * (2.0 * PI * 220.0 * i as f32 / SAMPLE_RATE).sin())
+            .collect()
+    }
+
+    fn rms(signal: &[f32]) -> f32 {
+        (signal.iter().map(|s| s * s).sum::<f32>() / signal.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn default_settings_are_plain_tanh() {
+        let mut saturation = Saturation::new(SAMPLE_RATE, 3.0, 1.0);
+        let input = sine(256, 0.8);
+        let output = run(&mut saturation, &input);
+        for (x, y) in input.iter().zip(output.iter()) {
+            let expected = (x * 3.0).tanh() / 3.0f32.tanh();
+            assert!((y - expected).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn asymmetric_characters_are_dc_free() {
+        for character in [SaturationCharacter::Tube, SaturationCharacter::Diode] {
+            let mut saturation = Saturation::new(SAMPLE_RATE, 4.0, 1.0);
+            saturation.set_character(character);
+            let output = run(&mut saturation, &sine(SAMPLE_RATE as usize, 0.9));
+            // Average over the last 100 ms (whole cycles of 220 Hz are not needed at this length).
+            let tail = &output[output.len() - 4800..];
+            let dc = tail.iter().sum::<f32>() / tail.len() as f32;
+            assert!(dc.abs() < 0.01, "{:?}: dc {}", character, dc);
+        }
+    }
+
+    #[test]
+    fn auto_gain_follows_input_level() {
+        let input = sine(SAMPLE_RATE as usize, 0.1);
+        let mut loud = Saturation::new(SAMPLE_RATE, 8.0, 1.0);
+        let uncompensated = run(&mut loud, &input);
+        let mut matched = Saturation::new(SAMPLE_RATE, 8.0, 1.0);
+        matched.set_auto_gain(true);
+        let compensated = run(&mut matched, &input);
+
+        let tail = input.len() - 4800;
+        let input_rms = rms(&input[tail..]);
+        // A quiet signal gets ~8/tanh(8) times louder without compensation.
+        assert!(rms(&uncompensated[tail..]) > 4.0 * input_rms);
+        assert!((rms(&compensated[tail..]) / input_rms - 1.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn negative_post_tilt_darkens_the_harmonics() {
+        let input = sine(SAMPLE_RATE as usize / 4, 0.9);
+        let mut flat = Saturation::new(SAMPLE_RATE, 4.0, 1.0);
+        let mut tilted = Saturation::new(SAMPLE_RATE, 4.0, 1.0);
+        tilted.set_post_tilt(-12.0);
+        tilted.post_tilt_db.set_immediate(-12.0);
+
+        // Share of energy in the first difference, a crude high-pass.
+        let brightness = |signal: &[f32]| {
+            let high: f32 = signal.windows(2).map(|w| (w[1] - w[0]).powi(2)).sum();
+            high / signal.iter().map(|s| s * s).sum::<f32>()
+        };
+        let flat_out = run(&mut flat, &input);
+        let tilted_out = run(&mut tilted, &input);
+        assert!(brightness(&tilted_out) < 0.8 * brightness(&flat_out));
+    }
+}
@@ -0,0 +1,257 @@
+use std::any::Any;
+
+use rustc_hash::FxHashMap;
+
+use crate::graph::ModulationSource;
+use crate::traits::{AudioNode, PortId};
+use crate::utils::smoothing::{SmoothedParam, DEFAULT_SMOOTHING_MS};
+
+// Haas-style micro delays stay below the echo threshold.
+const MAX_DELAY_MS: f32 = 40.0;
+const MAX_WIDTH: f32 = 2.0;
+
+/// A cheap Haas/micro-delay stereo widener.
+///
+/// Each channel gets its own short delay, and the delayed pair is re-encoded as
+/// mid/side so `width` scales only the side component. `comb_compensation`
+/// crossfades the mid channel back to the undelayed signal, which keeps the mono
+/// sum free of the comb filtering a plain Haas delay introduces.
+pub struct StereoEnhancer {
+    enabled: bool,
+    sample_rate: f32,
+    buffer_left: Vec<f32>,
+    buffer_right: Vec<f32>,
+    write_index: usize,
+    delay_samples_left: usize,
+    delay_samples_right: usize,
+    width: SmoothedParam,
+    comb_compensation: SmoothedParam,
+    mix: SmoothedParam,
+}
+
+impl StereoEnhancer {
+    /// Creates a new StereoEnhancer node.
+    ///
+    /// * `sample_rate` - The sample rate in Hz.
+    /// * `delay_left_ms` - Micro delay applied to the left channel (0-40 ms).
+    /// * `delay_right_ms` - Micro delay applied to the right channel (0-40 ms).
+    /// * `width` - Side gain (0.0 = mono, 1.0 = unchanged, 2.0 = extra wide).
+    /// * `comb_compensation` - 0.0 = classic Haas, 1.0 = mono-compatible.
+    /// * `mix` - The mix amount (0.0 = fully dry, 1.0 = fully wet).
+    pub fn new(
+        sample_rate: f32,
+        delay_left_ms: f32,
+        delay_right_ms: f32,
+        width: f32,
+        comb_compensation: f32,
+        mix: f32,
+    ) -> Self {
+        let capacity = ((MAX_DELAY_MS / 1000.0) * sample_rate).ceil() as usize + 1;
+        let mut enhancer = Self {
+            enabled: true,
+            sample_rate,
+            buffer_left: vec![0.0; capacity],
+            buffer_right: vec![0.0; capacity],
+            write_index: 0,
+            delay_samples_left: 0,
+            delay_samples_right: 0,
+            width: SmoothedParam::new(
+                width.clamp(0.0, MAX_WIDTH),
+                sample_rate,
+                DEFAULT_SMOOTHING_MS,
+            ),
+            comb_compensation: SmoothedParam::new(
+                comb_compensation.clamp(0.0, 1.0),
+                sample_rate,
+                DEFAULT_SMOOTHING_MS,
+            ),
+            mix: SmoothedParam::new(mix.clamp(0.0, 1.0), sample_rate, DEFAULT_SMOOTHING_MS),
+        };
+        enhancer.set_delay_left_ms(delay_left_ms);
+        enhancer.set_delay_right_ms(delay_right_ms);
+        enhancer
+    }
+
+    fn ms_to_samples(&self, ms: f32) -> usize {
+        let ms = ms.clamp(0.0, MAX_DELAY_MS);
+        (((ms / 1000.0) * self.sample_rate).round() as usize).min(self.buffer_left.len() - 1)
+    }
+
+    /// Sets the left channel delay in milliseconds.
+    pub fn set_delay_left_ms(&mut self, delay_ms: f32) {
+        self.delay_samples_left = self.ms_to_samples(delay_ms);
+    }
+
+    /// Sets the right channel delay in milliseconds.
+    pub fn set_delay_right_ms(&mut self, delay_ms: f32) {
+        self.delay_samples_right = self.ms_to_samples(delay_ms);
+    }
+
+    /// Sets the stereo width (side gain, 0.0 to 2.0).
+    pub fn set_width(&mut self, width: f32) {
+        self.width.set_target(width.clamp(0.0, MAX_WIDTH));
+    }
+
+    /// Sets how much of the undelayed mid signal is restored (0.0 to 1.0).
+    pub fn set_comb_compensation(&mut self, amount: f32) {
+        self.comb_compensation.set_target(amount.clamp(0.0, 1.0));
+    }
+
+    /// Sets the mix amount (0.0 = fully dry, 1.0 = fully wet).
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix.set_target(mix.clamp(0.0, 1.0));
+    }
+
+    #[inline]
+    fn read(buffer: &[f32], write_index: usize, delay: usize) -> f32 {
+        let len = buffer.len();
+        buffer[(write_index + len - delay) % len]
+    }
+}
+
+impl AudioNode for StereoEnhancer {
+    fn get_ports(&self) -> FxHashMap<PortId, bool> {
+        let mut ports = FxHashMap::default();
+        ports.insert(PortId::AudioInput0, false); // Left (or mono) input
+        ports.insert(PortId::AudioInput1, false); // Optional right input
+        ports.insert(PortId::AudioOutput0, true); // Left output
+        ports.insert(PortId::AudioOutput1, true); // Right output
+        ports
+    }
+
+    fn process<'a>(
+        &mut self,
+        inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+        outputs: &mut FxHashMap<PortId, &mut [f32]>,
+        buffer_size: usize,
+    ) {
+        let left_in = inputs
+            .get(&PortId::AudioInput0)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer);
+        // A mono source feeds both channels so per-voice use works without a stereo pair.
+        let right_in = inputs
+            .get(&PortId::AudioInput1)
+            .and_then(|sources| sources.first())
+            .map(|src| src.buffer)
+            .or(left_in);
+
+        let outs = outputs.get_disjoint_mut([&PortId::AudioOutput0, &PortId::AudioOutput1]);
+        let [Some(out_left), Some(out_right)] = outs else {
+            panic!("Missing stereo output buffers");
+        };
+        let out_left: &mut [f32] = out_left;
+        let out_right: &mut [f32] = out_right;
+
+        let len = self.buffer_left.len();
+
+        for i in 0..buffer_size {
+            let l = left_in.and_then(|b| b.get(i)).copied().unwrap_or(0.0);
+            let r = right_in.and_then(|b| b.get(i)).copied().unwrap_or(0.0);
+
+            self.buffer_left[self.write_index] = l;
+            self.buffer_right[self.write_index] = r;
+            let delayed_l =
+                Self::read(&self.buffer_left, self.write_index, self.delay_samples_left);
+            let delayed_r = Self::read(
+                &self.buffer_right,
+                self.write_index,
+                self.delay_samples_right,
+            );
+            self.write_index = (self.write_index + 1) % len;
+
+            let dry_mid = 0.5 * (l + r);
+            let delayed_mid = 0.5 * (delayed_l + delayed_r);
+            let mid = delayed_mid + (dry_mid - delayed_mid) * self.comb_compensation.next_value();
+            let side = 0.5 * (delayed_l - delayed_r) * self.width.next_value();
+
+            let wet_level = self.mix.next_value();
+            let dry_level = 1.0 - wet_level;
+
+            out_left[i] = l * dry_level + (mid + side) * wet_level;
+            out_right[i] = r * dry_level + (mid - side) * wet_level;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.buffer_left.fill(0.0);
+        self.buffer_right.fill(0.0);
+        self.write_index = 0;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn is_active(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.width.set_time_ms(time_ms);
+        self.comb_compensation.set_time_ms(time_ms);
+        self.mix.set_time_ms(time_ms);
+    }
+
+    fn set_active(&mut self, active: bool) {
+        self.enabled = active;
+        if active {
+            self.reset();
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Stereo Enhancer"
+    }
+
+    fn node_type(&self) -> &str {
+        "stereo_enhancer"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{ModulationTransformation, ModulationType};
+
+    fn run(enhancer: &mut StereoEnhancer, input: &[f32]) -> (Vec<f32>, Vec<f32>) {
+        let mut inputs = FxHashMap::default();
+        inputs.insert(
+            PortId::AudioInput0,
+            vec![ModulationSource {
+                buffer: input,
+                amount: 1.0,
+                mod_type: ModulationType::Additive,
+                transformation: ModulationTransformation::None,
+            }],
+        );
+        let mut left = vec![0.0; input.len()];
+        let mut right = vec![0.0; input.len()];
+        {
+            let mut outputs = FxHashMap::default();
+            outputs.insert(PortId::AudioOutput0, left.as_mut_slice());
+            outputs.insert(PortId::AudioOutput1, right.as_mut_slice());
+            enhancer.process(&inputs, &mut outputs, input.len());
+        }
+        (left, right)
+    }
+
+    #[test]
+    fn compensated_haas_keeps_mono_sum_and_adds_width() {
+        let sample_rate = 48_000.0;
+        let mut enhancer = StereoEnhancer::new(sample_rate, 0.0, 10.0, 1.0, 1.0, 1.0);
+        let input: Vec<f32> = (0..1024).map(|n| (n as f32 * 0.05).sin()).collect();
+
+        let (left, right) = run(&mut enhancer, &input);
+
+        for i in 0..input.len() {
+            assert!(((left[i] + right[i]) - 2.0 * input[i]).abs() < 1e-5);
+        }
+        let delay = (0.010 * sample_rate) as usize;
+        assert!((delay..input.len()).any(|i| (left[i] - right[i]).abs() > 0.1));
+    }
+}
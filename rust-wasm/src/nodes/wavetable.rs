@@ -102,7 +102,13 @@ impl<'de> Deserialize<'de> for Waveform {
     }
 }
 
+/// Shortest cycle `WavetableBank::from_cycle` accepts.
+pub const MIN_CYCLE_LEN: usize = 16;
+/// Longest cycle `WavetableBank::from_cycle` accepts.
+pub const MAX_CYCLE_LEN: usize = 8192;
+
 /// A single wavetable: time–domain samples plus the “top frequency” (Hz) that table can safely cover.
+#[derive(Clone)]
 pub struct Wavetable {
     pub samples: Vec<f32>,
     pub table_size: usize,
@@ -110,6 +116,7 @@ pub struct Wavetable {
 }
 
 /// A bank of wavetables, each covering a different frequency range.
+#[derive(Clone)]
 pub struct WavetableBank {
     pub tables: Vec<Wavetable>,
 }
@@ -335,6 +342,23 @@ impl WavetableBank {
         generate_mipmapped_bank_dynamic(base_samples, max_table_size, sample_rate)
     }
 
+    /// Builds a bank from one cycle of a user waveform, such as a measured
+    /// analog saw. The cycle length must be a power of two from
+    /// `MIN_CYCLE_LEN` to `MAX_CYCLE_LEN` samples; DC is removed.
+    pub fn from_cycle(cycle: &[f32], sample_rate: f32) -> Result<Self, String> {
+        let len = cycle.len();
+        if !len.is_power_of_two() || !(MIN_CYCLE_LEN..=MAX_CYCLE_LEN).contains(&len) {
+            return Err(format!(
+                "A waveform cycle needs a power-of-two length from {} to {} samples, got {}",
+                MIN_CYCLE_LEN, MAX_CYCLE_LEN, len
+            ));
+        }
+        if cycle.iter().any(|sample| !sample.is_finite()) {
+            return Err("The waveform cycle contains non-finite samples".to_string());
+        }
+        generate_mipmapped_bank_dynamic(cycle.to_vec(), len, sample_rate).map_err(|e| e.to_string())
+    }
+
     /// Select the first table whose `top_freq_hz` is greater than or equal to the given frequency.
     /// If no such table exists, the last (lowest–quality) table is returned.
     pub fn select_table(&self, frequency: f32) -> &Wavetable {
@@ -345,4 +369,14 @@ impl WavetableBank {
         }
         self.tables.last().unwrap()
     }
+
+    /// Bytes held by the tables of every mip level.
+    pub fn memory_bytes(&self) -> usize {
+        let samples: usize = self
+            .tables
+            .iter()
+            .map(|table| table.samples.capacity())
+            .sum();
+        samples * std::mem::size_of::<f32>()
+    }
 }
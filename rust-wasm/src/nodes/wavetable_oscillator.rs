@@ -2,7 +2,6 @@ use rustc_hash::FxHashMap;
 use rustfft::num_traits::Float;
 use std::any::Any;
 use std::cell::RefCell;
-use std::f32::consts::PI;
 use std::rc::Rc;
 use std::simd::Simd;
 #[cfg(feature = "wasm")]
@@ -10,8 +9,11 @@ use wasm_bindgen::prelude::*;
 #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
 use web_sys::console;
 
+use super::fm_operator::{FEEDBACK_DIVISOR, PHASE_MOD_SCALE};
 use super::morph_wavetable::{WavetableMorphCollection, WavetableSynthBank};
 use crate::graph::{ModulationProcessor, ModulationSource};
+use crate::utils::smoothing::smoothing_coefficient;
+use crate::utils::tuning::resolve_detune_cents;
 use crate::{AudioNode, PortId};
 use serde::{Deserialize, Serialize};
 
@@ -149,12 +151,7 @@ impl WavetableOscillator {
         let max_spread_cents = 100.0;
 
         let smoothing_time_ms = 1.0;
-        let smoothing_time_samples = sample_rate * (smoothing_time_ms / 1000.0);
-        let smoothing_coeff = if smoothing_time_samples > 0.0 {
-            1.0 - (-1.0 / smoothing_time_samples).exp()
-        } else {
-            1.0
-        };
+        let smoothing_coeff = smoothing_coefficient(sample_rate, smoothing_time_ms);
 
         let mut osc = Self {
             smoothing_coeff,
@@ -186,8 +183,8 @@ impl WavetableOscillator {
             collection_name: "default".to_string(),
             wavetable_bank: bank,
 
-            two_pi_recip: 1.0 / (PI * 2.0),
-            feedback_divisor: PI * 1.5,
+            two_pi_recip: PHASE_MOD_SCALE,
+            feedback_divisor: FEEDBACK_DIVISOR,
             cent_ratio: 2.0_f32.powf(1.0 / 1200.0),
             semitone_ratio: 2.0_f32.powf(1.0 / 12.0),
             sample_rate_recip: 1.0 / sample_rate,
@@ -235,15 +232,35 @@ impl WavetableOscillator {
         resize_if_needed(&mut self.global_freq_buffer, self.frequency);
     }
 
+    /// Sets the output gain without smoothing, for freshly created oscillators.
+    pub fn set_gain(&mut self, gain: f32) {
+        self.target_gain = gain;
+        self.smoothed_gain = gain;
+    }
+
+    pub fn gain(&self) -> f32 {
+        self.target_gain
+    }
+
     pub fn set_current_wavetable(&mut self, collection_name: &str) {
         self.collection_name = collection_name.to_string();
     }
 
+    /// Name of the wavetable bank collection this oscillator plays.
+    pub fn current_wavetable(&self) -> &str {
+        &self.collection_name
+    }
+
     pub fn update_params(&mut self, params: &WavetableOscillatorStateUpdate) {
         self.target_gain = params.gain;
         self.target_feedback_amount = params.feedback_amount;
         self.target_phase_mod_amount = params.phase_mod_amount;
-        self.target_detune = params.detune;
+        self.target_detune = resolve_detune_cents(
+            params.detune_oct,
+            params.detune_semi,
+            params.detune_cents,
+            params.detune,
+        );
 
         let max_spread_cents = 100.0;
         self.target_spread = params.spread.clamp(0.0, max_spread_cents);
@@ -835,6 +852,10 @@ impl AudioNode for WavetableOscillator {
     fn is_active(&self) -> bool {
         self.is_active()
     }
+    fn set_smoothing_time_ms(&mut self, time_ms: f32) {
+        self.smoothing_coeff = smoothing_coefficient(1.0 / self.sample_rate_recip, time_ms);
+    }
+
     fn set_active(&mut self, active: bool) {
         self.set_active(active)
     }
@@ -5,7 +5,9 @@ use std::any::Any;
 #[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
-use crate::graph::ModulationSource;
+use crate::graph::{ModulationSource, ModulationTarget};
+use crate::utils::analog_spread::VoiceVariation;
+use crate::utils::groove::Groove;
 
 #[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -23,6 +25,8 @@ pub enum PortId {
     GlobalVelocity,
     Frequency,
     FrequencyMod,
+    /// Phase modulation in radians, scaled by `ModIndex` (see
+    /// `nodes::fm_operator::PHASE_MOD_SCALE`).
     PhaseMod,
     ModIndex,
     CutoffMod,
@@ -38,6 +42,16 @@ pub enum PortId {
     ArpGate,
     CombinedGate,
     SampleOffset,
+    /// Key signal routed from a voice node into an effect's detector.
+    SidechainInput,
+    /// Clock pulses that step a sequencer.
+    ClockInput,
+    /// Vowel morph of a formant filter.
+    FormantMod,
+    /// Saturation drive of a filter.
+    DriveMod,
+    /// Note-off velocity of the voice's last release.
+    ReleaseVelocity,
 }
 
 impl Default for PortId {
@@ -75,6 +89,8 @@ impl PortId {
                 | PortId::CutoffMod
                 | PortId::ResonanceMod
                 | PortId::EnvelopeMod
+                | PortId::FormantMod
+                | PortId::DriveMod
         )
     }
 
@@ -148,11 +164,30 @@ impl PortId {
             25 => PortId::ArpGate,
             26 => PortId::CombinedGate,
             27 => PortId::SampleOffset,
+            28 => PortId::SidechainInput,
+            29 => PortId::ClockInput,
+            30 => PortId::FormantMod,
+            31 => PortId::DriveMod,
+            32 => PortId::ReleaseVelocity,
             _ => PortId::AudioInput0, // Default or error case
         }
     }
 }
 
+/// Engine-wide fidelity/CPU trade-off. Each node maps it onto its own knobs
+/// (oversampling factor, interpolation order, control-rate decimation).
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum QualityMode {
+    /// Lowest CPU: no oversampling, linear interpolation, decimated control signals.
+    Eco = 0,
+    #[default]
+    Normal = 1,
+    /// More oversampling and higher-order interpolation where it is audible.
+    High = 2,
+}
+
 pub trait AudioNode: Any {
     fn get_ports(&self) -> FxHashMap<PortId, bool>;
 
@@ -175,6 +210,58 @@ pub trait AudioNode: Any {
     // Optional method to handle state changes
     fn on_active_changed(&mut self) {}
 
+    // Time constant used to de-zipper user-facing parameters. Oscillators,
+    // filters, effects, FM operator level and master tuning follow it;
+    // envelopes, LFOs, MSEGs, glide, the arpeggiator, clock/gate/chance nodes,
+    // the mixer, EQ, limiter and noise gate have no smoothed parameters and
+    // ignore it
+    fn set_smoothing_time_ms(&mut self, _time_ms: f32) {}
+
+    // Adopt the engine-wide quality mode; nodes without quality knobs ignore it
+    fn set_quality_mode(&mut self, _mode: QualityMode) {}
+
+    // Adopt a groove template; nodes that aren't tempo-synced ignore it
+    fn set_groove(&mut self, _groove: &Groove) {}
+
+    // Adopt this voice's analog spread offsets; nodes without pitch, cutoff
+    // or envelope times ignore them
+    fn set_voice_variation(&mut self, _variation: &VoiceVariation) {}
+
+    // Whether anything feeds `AudioInput1`; nodes with per-channel state
+    // build their right channel here instead of while processing, others
+    // ignore it
+    fn set_stereo_input(&mut self, _connected: bool) {}
+
+    // Delay the node adds to its signal path in samples (e.g. lookahead), used
+    // to line up parallel paths
+    fn latency_samples(&self) -> usize {
+        0
+    }
+
+    // Offsets a named parameter by `offset` (in that parameter's units) on top
+    // of its set value, for the effect stack's LFOs; returns false if the node
+    // doesn't expose the parameter
+    fn modulate_parameter(&mut self, _parameter: &str, _offset: f32) -> bool {
+        false
+    }
+
+    // Lowest and highest value the node outputs, for modulation depth readouts
+    fn output_range(&self) -> (f32, f32) {
+        (-1.0, 1.0)
+    }
+
+    // The parameter a modulation input drives, for depth readouts; None for
+    // inputs the node doesn't describe
+    fn modulation_target(&self, _port: PortId) -> Option<ModulationTarget> {
+        None
+    }
+
+    // Independent copy of the node, for offline analysis such as response
+    // previews; None for nodes that can't be copied
+    fn clone_node(&self) -> Option<Box<dyn AudioNode>> {
+        None
+    }
+
     // Helper to determine if node should be processed
     fn should_process(&self) -> bool {
         self.is_active()
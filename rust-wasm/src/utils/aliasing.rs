@@ -0,0 +1,189 @@
+// src/utils/aliasing.rs
+//
+// Alias measurement for oscillators: render a steady note, take a windowed
+// spectrum and compare the energy sitting between the harmonics (partials
+// above Nyquist folded back down) with the energy on them.
+
+use rustc_hash::FxHashMap;
+use rustfft::{num_complex::Complex, FftPlanner};
+use serde::Serialize;
+use std::f32::consts::PI;
+
+use crate::graph::{ModulationSource, ModulationTransformation, ModulationType};
+use crate::traits::{AudioNode, PortId};
+
+const FFT_SIZE: usize = 8192;
+const BLOCK_SIZE: usize = 128;
+/// Bins either side of a harmonic counted as part of it; wider than the
+/// Blackman-Harris main lobe.
+const GUARD_BINS: usize = 6;
+const SILENCE_DB: f32 = -200.0;
+
+/// Samples `render_tone` produces: enough to settle and fill one FFT.
+pub const ALIAS_RENDER_LENGTH: usize = FFT_SIZE * 2;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AliasReport {
+    pub fundamental_hz: f32,
+    /// Energy between the harmonics relative to the energy on them, in dB.
+    pub alias_db: f32,
+    /// Strongest bin between the harmonics relative to the strongest harmonic, in dB.
+    pub worst_alias_db: f32,
+    pub worst_alias_hz: f32,
+}
+
+fn to_db(ratio: f32) -> f32 {
+    if ratio > 0.0 {
+        (10.0 * ratio.log10()).max(SILENCE_DB)
+    } else {
+        SILENCE_DB
+    }
+}
+
+/// Power spectrum of the last `FFT_SIZE` samples under a 4-term Blackman-Harris window.
+fn power_spectrum(samples: &[f32]) -> Vec<f32> {
+    let start = samples.len().saturating_sub(FFT_SIZE);
+    let mut spectrum: Vec<Complex<f32>> = (0..FFT_SIZE)
+        .map(|i| {
+            let x = 2.0 * PI * i as f32 / FFT_SIZE as f32;
+            let window =
+                0.35875 - 0.48829 * x.cos() + 0.14128 * (2.0 * x).cos() - 0.01168 * (3.0 * x).cos();
+            let sample = samples.get(start + i).copied().unwrap_or(0.0);
+            Complex::new(sample * window, 0.0)
+        })
+        .collect();
+    FftPlanner::<f32>::new()
+        .plan_fft_forward(FFT_SIZE)
+        .process(&mut spectrum);
+    spectrum[..FFT_SIZE / 2]
+        .iter()
+        .map(|bin| bin.norm_sqr())
+        .collect()
+}
+
+/// Splits the spectrum of a steady tone at `fundamental_hz` into harmonic and
+/// inharmonic energy. Anything off the harmonic series below Nyquist is
+/// counted as aliasing, so feed it a clean render of a single note.
+pub fn measure_aliasing(samples: &[f32], sample_rate: f32, fundamental_hz: f32) -> AliasReport {
+    let power = power_spectrum(samples);
+    let bin_hz = sample_rate / FFT_SIZE as f32;
+    let mut harmonic = vec![false; power.len()];
+    if fundamental_hz > 0.0 {
+        let mut freq = fundamental_hz;
+        while freq < sample_rate * 0.5 {
+            let centre = (freq / bin_hz).round() as usize;
+            let end = (centre + GUARD_BINS + 1).min(power.len());
+            harmonic[centre.saturating_sub(GUARD_BINS).min(end)..end].fill(true);
+            freq += fundamental_hz;
+        }
+    }
+
+    let (mut harmonic_energy, mut alias_energy) = (0.0f32, 0.0f32);
+    let (mut loudest_harmonic, mut worst_alias, mut worst_bin) = (0.0f32, 0.0f32, 0);
+    // DC and the lowest bins belong to neither.
+    let bins = power.iter().zip(&harmonic).enumerate().skip(GUARD_BINS + 1);
+    for (bin, (&p, &on_harmonic)) in bins {
+        if on_harmonic {
+            harmonic_energy += p;
+            loudest_harmonic = loudest_harmonic.max(p);
+        } else {
+            alias_energy += p;
+            if p > worst_alias {
+                worst_alias = p;
+                worst_bin = bin;
+            }
+        }
+    }
+
+    let ratio = |value: f32, reference: f32| {
+        if reference > 0.0 {
+            to_db(value / reference)
+        } else {
+            SILENCE_DB
+        }
+    };
+    AliasReport {
+        fundamental_hz,
+        alias_db: ratio(alias_energy, harmonic_energy),
+        worst_alias_db: ratio(worst_alias, loudest_harmonic),
+        worst_alias_hz: worst_bin as f32 * bin_hz,
+    }
+}
+
+/// Renders `node` holding a note at `frequency`, fed through `GlobalFrequency`
+/// with the gate open, for `ALIAS_RENDER_LENGTH` samples of its left output.
+pub fn render_tone(node: &mut dyn AudioNode, frequency: f32) -> Vec<f32> {
+    let pitch = vec![frequency; BLOCK_SIZE];
+    let gate = vec![1.0; BLOCK_SIZE];
+    let source = |buffer| {
+        vec![ModulationSource {
+            buffer,
+            amount: 1.0,
+            mod_type: ModulationType::Additive,
+            transformation: ModulationTransformation::None,
+        }]
+    };
+    let mut inputs = FxHashMap::default();
+    inputs.insert(PortId::GlobalFrequency, source(pitch.as_slice()));
+    inputs.insert(PortId::GlobalGate, source(gate.as_slice()));
+
+    let mut output = vec![0.0; ALIAS_RENDER_LENGTH];
+    let mut right = vec![0.0; BLOCK_SIZE];
+    for block in output.chunks_mut(BLOCK_SIZE) {
+        let len = block.len();
+        let mut outputs = FxHashMap::default();
+        outputs.insert(PortId::AudioOutput0, block);
+        outputs.insert(PortId::AudioOutput1, &mut right[..len]);
+        node.process(&inputs, &mut outputs, len);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(partials: &[(f64, f32)], sample_rate: f64) -> Vec<f32> {
+        (0..ALIAS_RENDER_LENGTH)
+            .map(|i| {
+                let t = i as f64 / sample_rate;
+                partials
+                    .iter()
+                    .map(|&(freq, level)| level * (std::f64::consts::TAU * freq * t).sin() as f32)
+                    .sum()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn harmonic_tones_measure_clean() {
+        let sample_rate = 48_000.0;
+        let report = measure_aliasing(
+            &tone(
+                &[(1_000.0, 1.0), (2_000.0, 0.5), (3_000.0, 0.33)],
+                sample_rate,
+            ),
+            sample_rate as f32,
+            1_000.0,
+        );
+        assert!(report.alias_db < -80.0, "{:?}", report);
+    }
+
+    #[test]
+    fn finds_a_folded_partial() {
+        let sample_rate = 48_000.0;
+        // A 7th harmonic of 4.1 kHz folds back from 28.7 kHz to 19.3 kHz.
+        let report = measure_aliasing(
+            &tone(&[(4_100.0, 1.0), (19_300.0, 0.1)], sample_rate),
+            sample_rate as f32,
+            4_100.0,
+        );
+        assert!((report.alias_db + 20.0).abs() < 1.0, "{:?}", report);
+        assert!(
+            (report.worst_alias_hz - 19_300.0).abs() < 10.0,
+            "{:?}",
+            report
+        );
+    }
+}
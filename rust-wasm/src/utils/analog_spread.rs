@@ -0,0 +1,98 @@
+// src/utils/analog_spread.rs
+//
+// Voice-to-voice variation of analog polysynths. Each voice gets small fixed
+// offsets to pitch, filter cutoff and envelope times, drawn from the patch's
+// seed and the voice index, so a patch has the same "character" every time it
+// loads. The engine's analog spread (0‥1) scales the offsets up to the
+// deviations below; graphs hand them to their nodes (see
+// `AudioNode::set_voice_variation`).
+
+/// Largest pitch offset, in cents.
+pub const MAX_PITCH_SPREAD_CENTS: f32 = 8.0;
+/// Largest cutoff offset, in octaves.
+pub const MAX_CUTOFF_SPREAD_OCTAVES: f32 = 0.15;
+/// Largest envelope time offset, as a fraction of the time.
+pub const MAX_TIME_SPREAD: f32 = 0.15;
+
+/// One voice's offsets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VoiceVariation {
+    pub pitch_cents: f32,
+    /// Factor on filter cutoffs.
+    pub cutoff_ratio: f32,
+    /// Factor on envelope attack, decay and release times.
+    pub time_ratio: f32,
+}
+
+impl Default for VoiceVariation {
+    fn default() -> Self {
+        Self {
+            pitch_cents: 0.0,
+            cutoff_ratio: 1.0,
+            time_ratio: 1.0,
+        }
+    }
+}
+
+impl VoiceVariation {
+    pub fn for_voice(seed: u32, voice_index: usize, spread: f32) -> Self {
+        let spread = spread.clamp(0.0, 1.0);
+        let mut state = ((seed as u64) << 32) ^ voice_index as u64;
+        let mut next = || bipolar(splitmix64(&mut state));
+        Self {
+            pitch_cents: next() * MAX_PITCH_SPREAD_CENTS * spread,
+            cutoff_ratio: (next() * MAX_CUTOFF_SPREAD_OCTAVES * spread).exp2(),
+            time_ratio: 1.0 + next() * MAX_TIME_SPREAD * spread,
+        }
+    }
+}
+
+/// A patch's spread seed, from its id. FNV-1a rather than std's hasher so the
+/// seed is the same on every platform and release.
+pub fn patch_seed(patch_id: &str) -> u32 {
+    patch_id.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Maps random bits onto -1‥1.
+fn bipolar(bits: u64) -> f32 {
+    (bits >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets_are_fixed_per_voice_and_scale_with_spread() {
+        let seed = patch_seed("warm-pad");
+        assert_eq!(seed, patch_seed("warm-pad"));
+        assert_ne!(seed, patch_seed("bright-pad"));
+        assert_eq!(
+            VoiceVariation::for_voice(seed, 3, 0.0),
+            VoiceVariation::default()
+        );
+
+        let voices: Vec<VoiceVariation> = (0..8)
+            .map(|voice| VoiceVariation::for_voice(seed, voice, 1.0))
+            .collect();
+        assert_eq!(voices[3], VoiceVariation::for_voice(seed, 3, 1.0));
+        assert_ne!(voices[0], voices[1]);
+        for voice in &voices {
+            assert!(voice.pitch_cents.abs() <= MAX_PITCH_SPREAD_CENTS);
+            assert!(voice.cutoff_ratio.log2().abs() <= MAX_CUTOFF_SPREAD_OCTAVES + 1e-6);
+            assert!((voice.time_ratio - 1.0).abs() <= MAX_TIME_SPREAD + 1e-6);
+        }
+        let half = VoiceVariation::for_voice(seed, 0, 0.5);
+        assert!((half.pitch_cents - voices[0].pitch_cents / 2.0).abs() < 1e-4);
+    }
+}
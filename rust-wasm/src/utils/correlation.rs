@@ -0,0 +1,165 @@
+// src/utils/correlation.rs
+//
+// Stereo phase checks: a running correlation meter for the master bus and a
+// mono compatibility analysis that compares a render's level with the level of
+// its mono fold-down, so wide chorus or widener settings that cancel in mono
+// show up before a mono playback system finds them.
+
+use serde::Serialize;
+
+// Floor used when converting to dB so silent renders don't produce -inf/NaN.
+const SILENCE_DB: f32 = -200.0;
+/// Averaging time of the correlation meter.
+const CORRELATION_TIME_MS: f32 = 300.0;
+/// Below this smoothed power the meter treats the bus as silent.
+const SILENCE_POWER: f32 = 1e-10;
+
+fn to_db(power: f32) -> f32 {
+    if power > 0.0 {
+        (10.0 * power.log10()).max(SILENCE_DB)
+    } else {
+        SILENCE_DB
+    }
+}
+
+/// Smoothed correlation between left and right: 1.0 for mono, 0.0 for
+/// unrelated channels, -1.0 for a channel inverted against the other.
+#[derive(Debug, Clone, Copy)]
+pub struct CorrelationMeter {
+    coefficient: f32,
+    left_right: f32,
+    left_left: f32,
+    right_right: f32,
+}
+
+impl CorrelationMeter {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            coefficient: Self::coefficient(sample_rate),
+            left_right: 0.0,
+            left_left: 0.0,
+            right_right: 0.0,
+        }
+    }
+
+    fn coefficient(sample_rate: f32) -> f32 {
+        let samples = (CORRELATION_TIME_MS * 0.001 * sample_rate).max(1.0);
+        1.0 - (-1.0 / samples).exp()
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.coefficient = Self::coefficient(sample_rate);
+        self.reset();
+    }
+
+    pub fn process(&mut self, left: &[f32], right: &[f32]) {
+        let k = self.coefficient;
+        for (&l, &r) in left.iter().zip(right) {
+            self.left_right += (l * r - self.left_right) * k;
+            self.left_left += (l * l - self.left_left) * k;
+            self.right_right += (r * r - self.right_right) * k;
+        }
+    }
+
+    /// The current correlation; 0.0 while the bus is silent.
+    pub fn correlation(&self) -> f32 {
+        let power = (self.left_left * self.right_right).sqrt();
+        if power > SILENCE_POWER {
+            (self.left_right / power).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.left_right = 0.0;
+        self.left_left = 0.0;
+        self.right_right = 0.0;
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonoCompatibilityReport {
+    /// Mean power of the two channels, in dB.
+    pub stereo_db: f32,
+    /// Power of the mono fold-down `(L + R) / 2`, in dB.
+    pub mono_db: f32,
+    /// Level lost by summing to mono. About 0 dB for mono material, 3 dB for
+    /// unrelated channels, more where the channels cancel.
+    pub loss_db: f32,
+    /// Correlation over the whole render.
+    pub correlation: f32,
+}
+
+/// Compares the level of a stereo render with its mono fold-down.
+pub fn analyze_mono_compatibility(left: &[f32], right: &[f32]) -> MonoCompatibilityReport {
+    let (mut left_right, mut left_left, mut right_right) = (0.0f64, 0.0f64, 0.0f64);
+    let mut mono = 0.0f64;
+    for (&l, &r) in left.iter().zip(right) {
+        let (l, r) = (l as f64, r as f64);
+        left_right += l * r;
+        left_left += l * l;
+        right_right += r * r;
+        mono += (0.5 * (l + r)).powi(2);
+    }
+    let len = left.len().min(right.len()).max(1) as f64;
+    let stereo_db = to_db(((left_left + right_right) * 0.5 / len) as f32);
+    let mono_db = to_db((mono / len) as f32);
+    let power = (left_left * right_right).sqrt();
+    let correlation = if power > 0.0 {
+        (left_right / power).clamp(-1.0, 1.0) as f32
+    } else {
+        0.0
+    };
+    MonoCompatibilityReport {
+        stereo_db,
+        mono_db,
+        loss_db: stereo_db - mono_db,
+        correlation,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(len: usize, freq: f32) -> Vec<f32> {
+        (0..len)
+            .map(|i| (std::f32::consts::TAU * freq * i as f32 / 48_000.0).sin())
+            .collect()
+    }
+
+    #[test]
+    fn mono_material_keeps_its_level_and_inverted_material_cancels() {
+        let signal = sine(48_000, 440.0);
+        let report = analyze_mono_compatibility(&signal, &signal);
+        assert!(report.loss_db.abs() < 1e-3, "{:?}", report);
+        assert!((report.correlation - 1.0).abs() < 1e-6);
+
+        let inverted: Vec<f32> = signal.iter().map(|x| -x).collect();
+        let report = analyze_mono_compatibility(&signal, &inverted);
+        assert!(report.loss_db > 100.0, "{:?}", report);
+        assert!((report.correlation + 1.0).abs() < 1e-6);
+
+        // Unrelated channels lose half their power.
+        let report = analyze_mono_compatibility(&signal, &sine(48_000, 1_000.0));
+        assert!((report.loss_db - 3.01).abs() < 0.05, "{:?}", report);
+    }
+
+    #[test]
+    fn meter_follows_the_channel_relationship() {
+        let mut meter = CorrelationMeter::new(48_000.0);
+        assert_eq!(meter.correlation(), 0.0);
+        let signal = sine(48_000, 440.0);
+        meter.process(&signal, &signal);
+        assert!(meter.correlation() > 0.99);
+
+        // A few time constants on, the old material has left the average.
+        let inverted: Vec<f32> = signal.iter().map(|x| -x).collect();
+        for _ in 0..3 {
+            meter.process(&signal, &inverted);
+        }
+        assert!(meter.correlation() < -0.99);
+    }
+}
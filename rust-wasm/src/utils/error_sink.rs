@@ -0,0 +1,61 @@
+// src/utils/error_sink.rs
+//
+// Where the DSP core reports errors it recovers from, such as refused graph
+// connections or an impulse response that failed to generate. Errors go to
+// the browser console on wasm and to stderr elsewhere, unless the host
+// installs its own sink (an embedded logger, a UART, a test collector).
+
+use std::sync::OnceLock;
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+use web_sys::console;
+
+static ERROR_SINK: OnceLock<fn(&str)> = OnceLock::new();
+
+/// Sends every error the DSP core reports to `sink` from now on. Install it
+/// once at startup; returns false if a sink was already installed.
+pub fn set_error_sink(sink: fn(&str)) -> bool {
+    ERROR_SINK.set(sink).is_ok()
+}
+
+/// Reports `message` through the installed sink, or the platform default.
+pub fn report_error(message: &str) {
+    match ERROR_SINK.get() {
+        Some(sink) => sink(message),
+        None => default_sink(message),
+    }
+}
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+fn default_sink(message: &str) {
+    console::error_1(&message.into());
+}
+
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+fn default_sink(message: &str) {
+    eprintln!("{message}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    static REPORTED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    fn collect(message: &str) {
+        REPORTED.lock().unwrap().push(message.to_string());
+    }
+
+    #[test]
+    fn installed_sink_receives_reports() {
+        assert!(set_error_sink(collect));
+        assert!(!set_error_sink(collect));
+        report_error("connection refused");
+        assert!(REPORTED
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|message| message == "connection refused"));
+    }
+}
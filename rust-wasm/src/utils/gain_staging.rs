@@ -0,0 +1,188 @@
+// src/utils/gain_staging.rs
+//
+// Level bookkeeping for the gain staging analyzer: peak/RMS meters for each
+// stage of a patch (voice nodes, master effects, the final output) and the
+// report that flags stages running over full scale or throwing away a lot of
+// level.
+
+use serde::Serialize;
+
+// Floor used when converting to dB so silent stages don't produce -inf/NaN.
+const SILENCE_DB: f32 = -200.0;
+/// Peaks above this are reported as clipping.
+pub const CLIP_THRESHOLD_DB: f32 = 0.0;
+/// Stages whose output RMS is this far below their input are reported as
+/// attenuating excessively.
+pub const ATTENUATION_THRESHOLD_DB: f32 = -24.0;
+
+fn to_db(value: f32) -> f32 {
+    if value > 0.0 {
+        (20.0 * value.log10()).max(SILENCE_DB)
+    } else {
+        SILENCE_DB
+    }
+}
+
+/// Accumulates peak and RMS over every channel and block it is fed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LevelMeter {
+    peak: f32,
+    sum_squares: f64,
+    count: usize,
+}
+
+impl LevelMeter {
+    pub fn add(&mut self, samples: &[f32]) {
+        for &s in samples {
+            self.peak = self.peak.max(s.abs());
+            self.sum_squares += (s as f64) * (s as f64);
+        }
+        self.count += samples.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+
+    pub fn rms(&self) -> f32 {
+        if self.count == 0 {
+            0.0
+        } else {
+            (self.sum_squares / self.count as f64).sqrt() as f32
+        }
+    }
+
+    pub fn reading(&self) -> LevelReading {
+        LevelReading {
+            peak_db: to_db(self.peak()),
+            rms_db: to_db(self.rms()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LevelReading {
+    pub peak_db: f32,
+    pub rms_db: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StageKind {
+    /// A node in the voice graph (levels of all sounding voices combined).
+    Voice,
+    /// A master effect.
+    Effect,
+    /// The engine output.
+    Master,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageLevels {
+    /// Node id as used in patches (effect ids include the effect offset).
+    pub id: String,
+    pub name: String,
+    pub kind: StageKind,
+    /// Sum of the audio feeding the stage; `None` for sources and the master.
+    pub input: Option<LevelReading>,
+    pub output: LevelReading,
+    /// Output RMS relative to input RMS.
+    pub gain_db: Option<f32>,
+    pub clipping: bool,
+    pub attenuating: bool,
+}
+
+impl StageLevels {
+    pub fn new(
+        id: String,
+        name: String,
+        kind: StageKind,
+        input: Option<&LevelMeter>,
+        output: &LevelMeter,
+    ) -> Self {
+        let input = input
+            .filter(|meter| !meter.is_empty())
+            .map(LevelMeter::reading);
+        let output_reading = output.reading();
+        // A silent input can't be attenuated, and its gain is meaningless.
+        let gain_db = input
+            .filter(|reading| reading.rms_db > SILENCE_DB)
+            .map(|reading| output_reading.rms_db - reading.rms_db);
+        Self {
+            id,
+            name,
+            kind,
+            input,
+            output: output_reading,
+            gain_db,
+            clipping: output_reading.peak_db > CLIP_THRESHOLD_DB,
+            attenuating: gain_db.is_some_and(|gain| gain < ATTENUATION_THRESHOLD_DB),
+        }
+    }
+}
+
+/// Per-stage levels of a patch playing a reference phrase, in signal-flow
+/// order: voice nodes, then the master effects, then the output.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GainStagingReport {
+    pub stages: Vec<StageLevels>,
+}
+
+impl GainStagingReport {
+    /// Stages that clip or attenuate excessively.
+    pub fn problems(&self) -> impl Iterator<Item = &StageLevels> {
+        self.stages
+            .iter()
+            .filter(|stage| stage.clipping || stage.attenuating)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meter(samples: &[f32]) -> LevelMeter {
+        let mut meter = LevelMeter::default();
+        meter.add(samples);
+        meter
+    }
+
+    #[test]
+    fn stages_flag_clipping_and_heavy_attenuation() {
+        let input = meter(&[0.5, -0.5, 0.5, -0.5]);
+
+        let hot = StageLevels::new(
+            "a".into(),
+            "Hot".into(),
+            StageKind::Voice,
+            Some(&input),
+            &meter(&[2.0, -2.0, 2.0, -2.0]),
+        );
+        assert!(hot.clipping && !hot.attenuating);
+        assert!((hot.gain_db.unwrap() - 12.04).abs() < 0.01);
+
+        let quiet = StageLevels::new(
+            "b".into(),
+            "Quiet".into(),
+            StageKind::Effect,
+            Some(&input),
+            &meter(&[0.01, -0.01, 0.01, -0.01]),
+        );
+        assert!(!quiet.clipping && quiet.attenuating);
+
+        let source = StageLevels::new("c".into(), "Osc".into(), StageKind::Voice, None, &input);
+        assert!(source.input.is_none() && source.gain_db.is_none());
+
+        let report = GainStagingReport {
+            stages: vec![hot, quiet, source],
+        };
+        assert_eq!(report.problems().count(), 2);
+    }
+}
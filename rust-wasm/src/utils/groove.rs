@@ -0,0 +1,110 @@
+// src/utils/groove.rs
+//
+// Groove templates for tempo-synced modules: swing plus a timing offset for
+// each sixteenth of a bar. The clock node, the arpeggiator and synced delays
+// take a groove from their graph or effect stack (see
+// `AudioNode::set_groove`), which can be set engine-wide or per node.
+
+/// Sixteenths in a groove template (one bar of 4/4).
+pub const GROOVE_STEPS: usize = 16;
+/// Straight timing: the second sixteenth lands halfway through the eighth.
+pub const STRAIGHT_SWING_PERCENT: f32 = 50.0;
+/// Hardest swing: the second sixteenth lands three quarters through the eighth.
+pub const MAX_SWING_PERCENT: f32 = 75.0;
+/// Largest per-step micro offset, as a fraction of a sixteenth.
+pub const MAX_GROOVE_OFFSET: f32 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Groove {
+    swing_percent: f32,
+    offsets: [f32; GROOVE_STEPS],
+}
+
+impl Default for Groove {
+    fn default() -> Self {
+        Self {
+            swing_percent: STRAIGHT_SWING_PERCENT,
+            offsets: [0.0; GROOVE_STEPS],
+        }
+    }
+}
+
+impl Groove {
+    /// * `swing_percent` - Where the second sixteenth of each eighth lands (50 to 75).
+    /// * `offsets` - Micro offset of each sixteenth in the bar, as a fraction of a
+    ///   sixteenth; missing steps are 0.
+    pub fn new(swing_percent: f32, offsets: &[f32]) -> Self {
+        let mut groove = Self::default();
+        groove.set_swing_percent(swing_percent);
+        groove.set_offsets(offsets);
+        groove
+    }
+
+    pub fn set_swing_percent(&mut self, swing_percent: f32) {
+        self.swing_percent = swing_percent.clamp(STRAIGHT_SWING_PERCENT, MAX_SWING_PERCENT);
+    }
+
+    pub fn swing_percent(&self) -> f32 {
+        self.swing_percent
+    }
+
+    pub fn set_offsets(&mut self, offsets: &[f32]) {
+        for (i, slot) in self.offsets.iter_mut().enumerate() {
+            let offset = offsets.get(i).copied().unwrap_or(0.0);
+            *slot = offset.clamp(-MAX_GROOVE_OFFSET, MAX_GROOVE_OFFSET);
+        }
+    }
+
+    pub fn offsets(&self) -> &[f32; GROOVE_STEPS] {
+        &self.offsets
+    }
+
+    pub fn is_straight(&self) -> bool {
+        self.swing_percent == STRAIGHT_SWING_PERCENT && self.offsets.iter().all(|&o| o == 0.0)
+    }
+
+    /// How late sixteenth `step` (counted from the bar) lands, as a fraction of
+    /// a sixteenth. Ranges from -0.25 to 0.75.
+    pub fn step_offset(&self, step: usize) -> f32 {
+        let swing = if step % 2 == 1 {
+            (self.swing_percent - STRAIGHT_SWING_PERCENT) / STRAIGHT_SWING_PERCENT
+        } else {
+            0.0
+        };
+        swing + self.offsets[step % GROOVE_STEPS]
+    }
+
+    /// How late an event at `beat` lands, in beats. Only events on the
+    /// sixteenth grid move.
+    pub fn offset_beats(&self, beat: f64) -> f64 {
+        let sixteenths = beat * 4.0;
+        let step = sixteenths.round();
+        if (sixteenths - step).abs() > 1e-6 {
+            return 0.0;
+        }
+        self.step_offset(step.rem_euclid(GROOVE_STEPS as f64) as usize) as f64 * 0.25
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swing_and_offsets_move_sixteenths() {
+        let mut offsets = [0.0; GROOVE_STEPS];
+        offsets[4] = -0.1;
+        let groove = Groove::new(75.0, &offsets);
+        assert_eq!(groove.step_offset(0), 0.0);
+        assert_eq!(groove.step_offset(1), 0.5);
+        assert_eq!(groove.step_offset(4), -0.1);
+        // Step 17 is the second sixteenth of the next bar.
+        assert_eq!(groove.step_offset(17), 0.5);
+
+        assert!((groove.offset_beats(0.25) - 0.125).abs() < 1e-9);
+        assert!((groove.offset_beats(1.0) + 0.025).abs() < 1e-6);
+        // Triplets are off the grid and stay put.
+        assert_eq!(groove.offset_beats(1.0 / 3.0), 0.0);
+        assert!(Groove::default().is_straight());
+    }
+}
@@ -0,0 +1,334 @@
+// src/utils/midi_file.rs
+//
+// Standard MIDI File (SMF) import. Reads format 0 and 1 files (format 2 tracks
+// are merged as if they were format 1), pairs note-ons with their note-offs and
+// converts tick times to seconds through the file's tempo map, so the notes can
+// be handed to the offline renderer as `RenderNote`s.
+
+use super::null_test::RenderNote;
+
+/// Tempo until the first Set Tempo event: 120 BPM.
+const DEFAULT_MICROS_PER_QUARTER: u32 = 500_000;
+
+/// A note read from a MIDI file, timed in ticks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MidiNote {
+    pub channel: u8,
+    pub note: u8,
+    /// Note-on velocity, 1 to 127.
+    pub velocity: u8,
+    pub start_tick: u64,
+    pub end_tick: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Timing {
+    TicksPerQuarter(u16),
+    /// Fixed seconds per tick from an SMPTE division; tempo events don't apply.
+    Smpte {
+        seconds_per_tick: f64,
+    },
+}
+
+/// Tempo changes of a file, converting ticks to seconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TempoMap {
+    timing: Timing,
+    /// (tick, microseconds per quarter note), sorted by tick.
+    changes: Vec<(u64, u32)>,
+}
+
+impl TempoMap {
+    /// Seconds from the start of the file to `tick`.
+    pub fn seconds_at(&self, tick: u64) -> f64 {
+        let ticks_per_quarter = match self.timing {
+            Timing::Smpte { seconds_per_tick } => return tick as f64 * seconds_per_tick,
+            Timing::TicksPerQuarter(ticks) => f64::from(ticks.max(1)),
+        };
+        let mut seconds = 0.0;
+        let mut last_tick = 0;
+        let mut micros_per_quarter = DEFAULT_MICROS_PER_QUARTER;
+        for &(change_tick, micros) in &self.changes {
+            if change_tick >= tick {
+                break;
+            }
+            seconds += (change_tick - last_tick) as f64 * f64::from(micros_per_quarter)
+                / ticks_per_quarter
+                / 1e6;
+            last_tick = change_tick;
+            micros_per_quarter = micros;
+        }
+        seconds
+            + (tick - last_tick) as f64 * f64::from(micros_per_quarter) / ticks_per_quarter / 1e6
+    }
+
+    /// Tempo in BPM at `tick`.
+    pub fn bpm_at(&self, tick: u64) -> f64 {
+        let micros = self
+            .changes
+            .iter()
+            .take_while(|&&(change_tick, _)| change_tick <= tick)
+            .last()
+            .map_or(DEFAULT_MICROS_PER_QUARTER, |&(_, micros)| micros);
+        60e6 / f64::from(micros.max(1))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MidiFile {
+    pub tempo_map: TempoMap,
+    /// Notes of every track, ordered by start.
+    pub notes: Vec<MidiNote>,
+    /// Tick of the last event in any track.
+    pub end_tick: u64,
+}
+
+impl MidiFile {
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader { bytes, pos: 0 };
+        let (id, header) = reader.chunk()?;
+        if id != *b"MThd" || header.len() < 6 {
+            return Err("Not a MIDI file: missing MThd header".to_string());
+        }
+        let track_count = u16::from_be_bytes([header[2], header[3]]);
+        let division = u16::from_be_bytes([header[4], header[5]]);
+        let timing = if division & 0x8000 != 0 {
+            let frames_per_second = f64::from(-((division >> 8) as u8 as i8));
+            let ticks_per_frame = f64::from(division & 0xFF);
+            if frames_per_second <= 0.0 || ticks_per_frame <= 0.0 {
+                return Err("Invalid SMPTE division".to_string());
+            }
+            Timing::Smpte {
+                seconds_per_tick: 1.0 / (frames_per_second * ticks_per_frame),
+            }
+        } else {
+            Timing::TicksPerQuarter(division)
+        };
+
+        let mut file = MidiFile {
+            tempo_map: TempoMap {
+                timing,
+                changes: Vec::new(),
+            },
+            notes: Vec::new(),
+            end_tick: 0,
+        };
+        let mut tracks_read = 0;
+        while tracks_read < track_count && !reader.is_empty() {
+            let (id, data) = reader.chunk()?;
+            // Unknown chunk types are skipped, as the spec asks.
+            if id == *b"MTrk" {
+                file.read_track(data)?;
+                tracks_read += 1;
+            }
+        }
+        file.tempo_map.changes.sort_by_key(|&(tick, _)| tick);
+        file.notes.sort_by_key(|note| (note.start_tick, note.note));
+        Ok(file)
+    }
+
+    fn read_track(&mut self, data: &[u8]) -> Result<(), String> {
+        let mut reader = Reader {
+            bytes: data,
+            pos: 0,
+        };
+        let mut tick = 0u64;
+        let mut running_status = None;
+        // Open notes per (channel, note), oldest first.
+        let mut open: Vec<(u8, u8, u8, u64)> = Vec::new();
+
+        while !reader.is_empty() {
+            tick += u64::from(reader.var_len()?);
+            let mut status = reader.byte()?;
+            match status {
+                0xFF => {
+                    let kind = reader.byte()?;
+                    let len = reader.var_len()? as usize;
+                    let payload = reader.take(len)?;
+                    if kind == 0x51 && payload.len() == 3 {
+                        let micros = u32::from_be_bytes([0, payload[0], payload[1], payload[2]]);
+                        self.tempo_map.changes.push((tick, micros));
+                    } else if kind == 0x2F {
+                        break;
+                    }
+                    continue;
+                }
+                0xF0 | 0xF7 => {
+                    let len = reader.var_len()? as usize;
+                    reader.take(len)?;
+                    running_status = None;
+                    continue;
+                }
+                _ => {}
+            }
+
+            let first_data = if status & 0x80 == 0 {
+                // Running status: this byte is already the first data byte.
+                let data = status;
+                status = running_status.ok_or("Data byte without a running status")?;
+                data
+            } else {
+                running_status = Some(status);
+                reader.byte()?
+            };
+            let channel = status & 0x0F;
+            match status & 0xF0 {
+                0x80 | 0x90 => {
+                    let velocity = reader.byte()?;
+                    let note = first_data & 0x7F;
+                    if status & 0xF0 == 0x90 && velocity > 0 {
+                        open.push((channel, note, velocity, tick));
+                    } else if let Some(index) = open
+                        .iter()
+                        .position(|&(c, n, _, _)| c == channel && n == note)
+                    {
+                        let (_, _, velocity, start_tick) = open.remove(index);
+                        self.push_note(channel, note, velocity, start_tick, tick);
+                    }
+                }
+                0xA0 | 0xB0 | 0xE0 => {
+                    reader.byte()?;
+                }
+                0xC0 | 0xD0 => {}
+                _ => return Err(format!("Unsupported MIDI status byte {:#04x}", status)),
+            }
+        }
+
+        // Notes still held when the track ends stop there.
+        for (channel, note, velocity, start_tick) in open {
+            self.push_note(channel, note, velocity, start_tick, tick);
+        }
+        self.end_tick = self.end_tick.max(tick);
+        Ok(())
+    }
+
+    fn push_note(&mut self, channel: u8, note: u8, velocity: u8, start_tick: u64, end_tick: u64) {
+        self.notes.push(MidiNote {
+            channel,
+            note,
+            velocity,
+            start_tick,
+            end_tick,
+        });
+    }
+
+    /// Length of the file in seconds.
+    pub fn duration_seconds(&self) -> f64 {
+        self.tempo_map.seconds_at(self.end_tick)
+    }
+
+    /// The notes as an offline render sequence at `sample_rate`.
+    pub fn render_notes(&self, sample_rate: f32) -> Vec<RenderNote> {
+        let to_samples = |tick| (self.tempo_map.seconds_at(tick) * f64::from(sample_rate)) as usize;
+        self.notes
+            .iter()
+            .map(|note| {
+                let start = to_samples(note.start_tick);
+                let end = to_samples(note.end_tick).max(start + 1);
+                RenderNote::new(
+                    440.0 * 2f32.powf((f32::from(note.note) - 69.0) / 12.0),
+                    f32::from(note.velocity) / 127.0,
+                    start,
+                    end - start,
+                )
+            })
+            .collect()
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or("Unexpected end of MIDI data")?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Variable-length quantity: 7 bits per byte, at most four bytes.
+    fn var_len(&mut self) -> Result<u32, String> {
+        let mut value = 0u32;
+        for _ in 0..4 {
+            let byte = self.byte()?;
+            value = (value << 7) | u32::from(byte & 0x7F);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("Variable-length quantity longer than four bytes".to_string())
+    }
+
+    fn chunk(&mut self) -> Result<([u8; 4], &'a [u8]), String> {
+        let id = self.take(4)?;
+        let len = self.take(4)?;
+        let len = u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize;
+        let data = self.take(len)?;
+        Ok(([id[0], id[1], id[2], id[3]], data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_vec();
+        bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    #[test]
+    fn reads_notes_through_the_tempo_map() {
+        // Format 1 at 96 ticks per quarter: a tempo track switching from
+        // 120 to 60 BPM after one beat, and a note track using running status.
+        let tempo_track = [
+            0x00, 0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20, // 120 BPM
+            0x60, 0xFF, 0x51, 0x03, 0x0F, 0x42, 0x40, // 60 BPM at tick 96
+            0x00, 0xFF, 0x2F, 0x00,
+        ];
+        let note_track = [
+            0x00, 0x90, 60, 100, // C4 on at 0
+            0x81, 0x40, 60, 0, // off at 192 (velocity 0, running status)
+            0x00, 0x91, 64, 127, // E4 on at 192 on channel 2, never released
+            0x60, 0xFF, 0x2F, 0x00, // end at 288
+        ];
+        let mut bytes = chunk(b"MThd", &[0, 1, 0, 2, 0, 96]);
+        bytes.extend(chunk(b"MTrk", &tempo_track));
+        bytes.extend(chunk(b"MTrk", &note_track));
+
+        let file = MidiFile::parse(&bytes).unwrap();
+        assert_eq!(file.notes.len(), 2);
+        assert_eq!(file.notes[0].end_tick, 192);
+        assert_eq!(file.notes[1].channel, 1);
+        assert_eq!(file.notes[1].end_tick, 288);
+        // One beat at 120 BPM, then two at 60.
+        assert!((file.tempo_map.seconds_at(192) - 1.5).abs() < 1e-9);
+        assert!((file.duration_seconds() - 2.5).abs() < 1e-9);
+        assert_eq!(file.tempo_map.bpm_at(100), 60.0);
+
+        let notes = file.render_notes(1000.0);
+        assert_eq!(notes[0].start_sample, 0);
+        assert_eq!(notes[0].length_samples, 1500);
+        assert_eq!(notes[1].start_sample, 1500);
+        assert!((notes[1].frequency - 329.628).abs() < 1e-2);
+
+        assert!(MidiFile::parse(b"RIFF").is_err());
+    }
+}
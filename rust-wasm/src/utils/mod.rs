@@ -1,2 +1,15 @@
+pub mod aliasing;
+pub mod analog_spread;
 pub mod buffer_ops;
+pub mod correlation;
 pub mod curves;
+pub mod error_sink;
+pub mod gain_staging;
+pub mod groove;
+pub mod midi_file;
+pub mod null_test;
+pub mod partitioned_convolver;
+pub mod sidechain;
+pub mod simd_kernels;
+pub mod smoothing;
+pub mod tuning;
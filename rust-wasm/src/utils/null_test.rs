@@ -0,0 +1,275 @@
+// src/utils/null_test.rs
+//
+// A/B render comparison: subtracts two renders of the same material and reports
+// how deep the null is, plus a per-octave spectral difference so small level or
+// tone changes show up even when the waveforms drift apart in phase.
+
+use rustfft::{num_complex::Complex, FftPlanner};
+use serde::Serialize;
+use std::f32::consts::PI;
+
+const SPECTRUM_FFT_SIZE: usize = 2048;
+const SPECTRUM_HOP: usize = SPECTRUM_FFT_SIZE / 2;
+const LOWEST_BAND_HZ: f32 = 31.25;
+// Floor used when converting to dB so silent renders don't produce -inf/NaN.
+const SILENCE_DB: f32 = -200.0;
+
+/// A note in an offline test sequence, timed in samples.
+#[derive(Debug, Clone, Copy)]
+pub struct RenderNote {
+    pub frequency: f32,
+    pub velocity: f32,
+    pub start_sample: usize,
+    pub length_samples: usize,
+}
+
+impl RenderNote {
+    pub fn new(frequency: f32, velocity: f32, start_sample: usize, length_samples: usize) -> Self {
+        Self {
+            frequency,
+            velocity,
+            start_sample,
+            length_samples,
+        }
+    }
+
+    fn end_sample(&self) -> usize {
+        self.start_sample.saturating_add(self.length_samples)
+    }
+
+    /// Whether the note's gate is open anywhere in `[start, end)`.
+    pub fn overlaps(&self, start: usize, end: usize) -> bool {
+        self.start_sample < end && self.end_sample() > start
+    }
+
+    /// The part of `[start, end)` where the gate is open, relative to `start`.
+    pub fn gate_range(&self, start: usize, end: usize) -> std::ops::Range<usize> {
+        let from = self.start_sample.clamp(start, end) - start;
+        let to = self.end_sample().clamp(start, end) - start;
+        from..to
+    }
+}
+
+/// Result of comparing a reference render against a candidate render.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NullTestReport {
+    pub reference_rms: f32,
+    pub candidate_rms: f32,
+    /// RMS of `candidate - reference`.
+    pub residual_rms: f32,
+    pub residual_peak: f32,
+    /// Residual level relative to the reference in dB (more negative = deeper null).
+    pub null_depth_db: f32,
+    /// Octave band centre frequencies used for the spectral comparison.
+    pub band_centers_hz: Vec<f32>,
+    /// Candidate minus reference band energy per octave, in dB.
+    pub band_diff_db: Vec<f32>,
+    pub max_band_diff_db: f32,
+}
+
+impl NullTestReport {
+    /// True when the residual is at least `min_null_depth_db` below the reference
+    /// and no octave band moved by more than `band_tolerance_db`.
+    pub fn is_transparent(&self, min_null_depth_db: f32, band_tolerance_db: f32) -> bool {
+        self.null_depth_db <= -min_null_depth_db.abs()
+            && self.max_band_diff_db <= band_tolerance_db.abs()
+    }
+}
+
+fn to_db(value: f32) -> f32 {
+    if value > 0.0 {
+        (20.0 * value.log10()).max(SILENCE_DB)
+    } else {
+        SILENCE_DB
+    }
+}
+
+fn rms(samples: impl Iterator<Item = f32>) -> f32 {
+    let (sum, count) = samples.fold((0.0f64, 0usize), |(sum, count), s| {
+        (sum + (s as f64) * (s as f64), count + 1)
+    });
+    if count == 0 {
+        0.0
+    } else {
+        (sum / count as f64).sqrt() as f32
+    }
+}
+
+/// Welch-averaged power spectrum (Hann window, 50% overlap) of a mono signal.
+fn average_power_spectrum(signal: &[f32], planner: &mut FftPlanner<f32>) -> Vec<f32> {
+    let fft = planner.plan_fft_forward(SPECTRUM_FFT_SIZE);
+    let window: Vec<f32> = (0..SPECTRUM_FFT_SIZE)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / SPECTRUM_FFT_SIZE as f32).cos())
+        .collect();
+    let bins = SPECTRUM_FFT_SIZE / 2 + 1;
+    let mut power = vec![0.0f32; bins];
+    let mut frame = vec![Complex::new(0.0f32, 0.0); SPECTRUM_FFT_SIZE];
+    let mut frames = 0usize;
+
+    let mut start = 0;
+    loop {
+        for (i, slot) in frame.iter_mut().enumerate() {
+            // Short signals are zero padded into a single frame.
+            let sample = signal.get(start + i).copied().unwrap_or(0.0);
+            *slot = Complex::new(sample * window[i], 0.0);
+        }
+        fft.process(&mut frame);
+        for (p, c) in power.iter_mut().zip(frame.iter()) {
+            *p += c.norm_sqr();
+        }
+        frames += 1;
+
+        start += SPECTRUM_HOP;
+        if start + SPECTRUM_FFT_SIZE > signal.len() {
+            break;
+        }
+    }
+
+    for p in &mut power {
+        *p /= frames as f32;
+    }
+    power
+}
+
+/// Octave band centres from `LOWEST_BAND_HZ` up to (but not past) Nyquist.
+fn octave_bands(sample_rate: f32) -> Vec<f32> {
+    let nyquist = sample_rate * 0.5;
+    let mut bands = Vec::new();
+    let mut centre = LOWEST_BAND_HZ;
+    while centre * std::f32::consts::SQRT_2 <= nyquist {
+        bands.push(centre);
+        centre *= 2.0;
+    }
+    bands
+}
+
+fn band_energy(power: &[f32], sample_rate: f32, centre: f32) -> f32 {
+    let bin_hz = sample_rate / SPECTRUM_FFT_SIZE as f32;
+    let lo = ((centre / std::f32::consts::SQRT_2) / bin_hz).floor() as usize;
+    let hi = ((centre * std::f32::consts::SQRT_2) / bin_hz).ceil() as usize;
+    power[lo.min(power.len())..hi.min(power.len())].iter().sum()
+}
+
+/// Compares two stereo renders sample by sample. The shorter length of the two is
+/// used, so renders should start at the same sample and be equally long.
+pub fn compare_renders(
+    reference_left: &[f32],
+    reference_right: &[f32],
+    candidate_left: &[f32],
+    candidate_right: &[f32],
+    sample_rate: f32,
+) -> NullTestReport {
+    let len = reference_left
+        .len()
+        .min(reference_right.len())
+        .min(candidate_left.len())
+        .min(candidate_right.len());
+    let reference = || {
+        reference_left[..len]
+            .iter()
+            .chain(reference_right[..len].iter())
+            .copied()
+    };
+    let candidate = || {
+        candidate_left[..len]
+            .iter()
+            .chain(candidate_right[..len].iter())
+            .copied()
+    };
+
+    let reference_rms = rms(reference());
+    let candidate_rms = rms(candidate());
+    let residual = || reference().zip(candidate()).map(|(r, c)| c - r);
+    let residual_rms = rms(residual());
+    let residual_peak = residual().fold(0.0f32, |peak, s| peak.max(s.abs()));
+
+    let null_depth_db = if residual_rms == 0.0 {
+        SILENCE_DB
+    } else if reference_rms == 0.0 {
+        // Anything against a silent reference counts as a complete failure to null.
+        0.0
+    } else {
+        to_db(residual_rms / reference_rms)
+    };
+
+    let mono = |left: &[f32], right: &[f32]| -> Vec<f32> {
+        left[..len]
+            .iter()
+            .zip(right[..len].iter())
+            .map(|(l, r)| 0.5 * (l + r))
+            .collect()
+    };
+    let mut planner = FftPlanner::new();
+    let reference_power =
+        average_power_spectrum(&mono(reference_left, reference_right), &mut planner);
+    let candidate_power =
+        average_power_spectrum(&mono(candidate_left, candidate_right), &mut planner);
+
+    let band_centers_hz = octave_bands(sample_rate);
+    let band_diff_db: Vec<f32> = band_centers_hz
+        .iter()
+        .map(|&centre| {
+            let reference = band_energy(&reference_power, sample_rate, centre);
+            let candidate = band_energy(&candidate_power, sample_rate, centre);
+            // Energies are powers, so halve the amplitude dB conversion.
+            0.5 * (to_db(candidate) - to_db(reference))
+        })
+        .collect();
+    let max_band_diff_db = band_diff_db.iter().fold(0.0f32, |m, d| m.max(d.abs()));
+
+    NullTestReport {
+        reference_rms,
+        candidate_rms,
+        residual_rms,
+        residual_peak,
+        null_depth_db,
+        band_centers_hz,
+        band_diff_db,
+        max_band_diff_db,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(freq: f32, amplitude: f32, sample_rate: f32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| amplitude * (2.0 * PI * freq * i as f32 / sample_rate).sin())
+            .collect()
+    }
+
+    #[test]
+    fn identical_renders_null_completely() {
+        let sample_rate = 48_000.0;
+        let signal = sine(440.0, 0.5, sample_rate, 8192);
+        let report = compare_renders(&signal, &signal, &signal, &signal, sample_rate);
+
+        assert_eq!(report.residual_rms, 0.0);
+        assert!(report.max_band_diff_db.abs() < 1e-3);
+        assert!(report.is_transparent(90.0, 0.1));
+    }
+
+    #[test]
+    fn level_change_shows_in_residual_and_spectrum() {
+        let sample_rate = 48_000.0;
+        let reference = sine(1000.0, 0.5, sample_rate, 8192);
+        // -6 dB leaves a residual of half the reference.
+        let candidate: Vec<f32> = reference.iter().map(|s| s * 0.5).collect();
+        let report = compare_renders(&reference, &reference, &candidate, &candidate, sample_rate);
+
+        assert!(
+            (report.null_depth_db + 6.02).abs() < 0.05,
+            "{}",
+            report.null_depth_db
+        );
+        let band = report
+            .band_centers_hz
+            .iter()
+            .position(|&c| c == 1000.0)
+            .expect("1 kHz octave band");
+        assert!((report.band_diff_db[band] + 6.02).abs() < 0.1);
+        assert!(!report.is_transparent(60.0, 0.5));
+    }
+}
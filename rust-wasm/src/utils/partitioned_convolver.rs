@@ -0,0 +1,321 @@
+// src/utils/partitioned_convolver.rs
+//
+// Zero-latency uniformly partitioned FFT convolution. The impulse response is
+// split into blocks of `block_size`, each transformed once; the input is
+// transformed block by block and convolved in the frequency domain. Partial
+// blocks are handled by re-transforming the incomplete input block on every
+// call, so output is available for any host buffer size without added latency.
+//
+// Spectra are kept in split real/imaginary form so the multiply-accumulate over
+// all partitions runs through the SIMD kernel.
+
+use realfft::num_complex::Complex;
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+use std::sync::Arc;
+
+use super::simd_kernels::complex_multiply_accumulate;
+
+#[derive(Clone)]
+struct SplitSpectrum {
+    re: Vec<f32>,
+    im: Vec<f32>,
+}
+
+impl SplitSpectrum {
+    fn zeros(bins: usize) -> Self {
+        Self {
+            re: vec![0.0; bins],
+            im: vec![0.0; bins],
+        }
+    }
+
+    fn clear(&mut self) {
+        self.re.fill(0.0);
+        self.im.fill(0.0);
+    }
+
+    fn copy_from(&mut self, other: &SplitSpectrum) {
+        self.re.copy_from_slice(&other.re);
+        self.im.copy_from_slice(&other.im);
+    }
+
+    fn load(&mut self, spectrum: &[Complex<f32>], scale: f32) {
+        for (i, c) in spectrum.iter().enumerate() {
+            self.re[i] = c.re * scale;
+            self.im[i] = c.im * scale;
+        }
+    }
+
+    fn multiply_accumulate(&mut self, a: &SplitSpectrum, b: &SplitSpectrum) {
+        complex_multiply_accumulate(&mut self.re, &mut self.im, &a.re, &a.im, &b.re, &b.im);
+    }
+}
+
+pub struct PartitionedConvolver {
+    block_size: usize,
+    forward: Arc<dyn RealToComplex<f32>>,
+    inverse: Arc<dyn ComplexToReal<f32>>,
+    ir_segments: Vec<SplitSpectrum>,
+    // Partitions actually convolved; fewer than `ir_segments` truncates the tail.
+    active_segments: usize,
+    input_segments: Vec<SplitSpectrum>,
+    current: usize,
+    pre_multiplied: SplitSpectrum,
+    conv: SplitSpectrum,
+    overlap: Vec<f32>,
+    input_buffer: Vec<f32>,
+    input_fill: usize,
+    time_buffer: Vec<f32>,
+    spectrum_buffer: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+}
+
+impl PartitionedConvolver {
+    /// `block_size` is rounded up to a power of two. Trailing silence in the
+    /// impulse response is trimmed; an all-silent response outputs silence.
+    pub fn new(block_size: usize, impulse_response: &[f32]) -> Self {
+        let block_size = block_size.max(1).next_power_of_two();
+        let segment_size = 2 * block_size;
+        let bins = block_size + 1;
+
+        let mut planner = RealFftPlanner::<f32>::new();
+        let forward = planner.plan_fft_forward(segment_size);
+        let inverse = planner.plan_fft_inverse(segment_size);
+        let scratch_len = forward.get_scratch_len().max(inverse.get_scratch_len());
+
+        let ir_len = impulse_response
+            .iter()
+            .rposition(|s| *s != 0.0)
+            .map_or(0, |last| last + 1);
+        let segment_count = ir_len.div_ceil(block_size);
+
+        let mut time_buffer = vec![0.0; segment_size];
+        let mut spectrum_buffer = vec![Complex::new(0.0, 0.0); bins];
+        let mut scratch = vec![Complex::new(0.0, 0.0); scratch_len];
+        // The inverse transform is unnormalised; fold 1/N into the IR instead of
+        // scaling every output block.
+        let scale = 1.0 / segment_size as f32;
+        let ir_segments = impulse_response[..ir_len]
+            .chunks(block_size)
+            .map(|chunk| {
+                time_buffer.fill(0.0);
+                time_buffer[..chunk.len()].copy_from_slice(chunk);
+                forward
+                    .process_with_scratch(&mut time_buffer, &mut spectrum_buffer, &mut scratch)
+                    .expect("forward FFT buffer sizes");
+                let mut segment = SplitSpectrum::zeros(bins);
+                segment.load(&spectrum_buffer, scale);
+                segment
+            })
+            .collect();
+
+        Self {
+            block_size,
+            forward,
+            inverse,
+            active_segments: segment_count,
+            ir_segments,
+            input_segments: vec![SplitSpectrum::zeros(bins); segment_count],
+            current: 0,
+            pre_multiplied: SplitSpectrum::zeros(bins),
+            conv: SplitSpectrum::zeros(bins),
+            overlap: vec![0.0; block_size],
+            input_buffer: vec![0.0; block_size],
+            input_fill: 0,
+            time_buffer,
+            spectrum_buffer,
+            scratch,
+        }
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Limits convolution to the first `max_partitions` blocks of the impulse
+    /// response, trading tail length for CPU. `None` uses the whole response.
+    pub fn set_max_partitions(&mut self, max_partitions: Option<usize>) {
+        let segment_count = self.ir_segments.len();
+        self.active_segments = max_partitions.map_or(segment_count, |max| max.min(segment_count));
+    }
+
+    /// Clears the convolution history without touching the impulse response.
+    pub fn reset(&mut self) {
+        for segment in &mut self.input_segments {
+            segment.clear();
+        }
+        self.pre_multiplied.clear();
+        self.overlap.fill(0.0);
+        self.input_buffer.fill(0.0);
+        self.input_fill = 0;
+        self.current = 0;
+    }
+
+    /// Convolves `input` into `output` (overwriting it). Both slices are
+    /// processed up to the shorter length.
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        let len = input.len().min(output.len());
+        let segment_count = self.ir_segments.len();
+        if segment_count == 0 {
+            output[..len].fill(0.0);
+            return;
+        }
+
+        let mut processed = 0;
+        while processed < len {
+            let block_start = self.input_fill == 0;
+            let position = self.input_fill;
+            let count = (len - processed).min(self.block_size - position);
+
+            self.input_buffer[position..position + count]
+                .copy_from_slice(&input[processed..processed + count]);
+
+            // Transform the (possibly partial) current input block.
+            self.time_buffer[..self.block_size].copy_from_slice(&self.input_buffer);
+            self.time_buffer[self.block_size..].fill(0.0);
+            self.forward
+                .process_with_scratch(
+                    &mut self.time_buffer,
+                    &mut self.spectrum_buffer,
+                    &mut self.scratch,
+                )
+                .expect("forward FFT buffer sizes");
+            self.input_segments[self.current].load(&self.spectrum_buffer, 1.0);
+
+            // Older input blocks only change once per block, so their products
+            // with the IR tail are accumulated once and reused for partial calls.
+            if block_start {
+                self.pre_multiplied.clear();
+                for i in 1..self.active_segments {
+                    let audio_index = (self.current + i) % segment_count;
+                    self.pre_multiplied.multiply_accumulate(
+                        &self.ir_segments[i],
+                        &self.input_segments[audio_index],
+                    );
+                }
+            }
+            self.conv.copy_from(&self.pre_multiplied);
+            self.conv
+                .multiply_accumulate(&self.ir_segments[0], &self.input_segments[self.current]);
+
+            for (i, c) in self.spectrum_buffer.iter_mut().enumerate() {
+                *c = Complex::new(self.conv.re[i], self.conv.im[i]);
+            }
+            // A real signal has purely real DC and Nyquist bins; clear any rounding.
+            self.spectrum_buffer[0].im = 0.0;
+            self.spectrum_buffer[self.block_size].im = 0.0;
+            self.inverse
+                .process_with_scratch(
+                    &mut self.spectrum_buffer,
+                    &mut self.time_buffer,
+                    &mut self.scratch,
+                )
+                .expect("inverse FFT buffer sizes");
+
+            for i in 0..count {
+                output[processed + i] = self.time_buffer[position + i] + self.overlap[position + i];
+            }
+
+            self.input_fill += count;
+            if self.input_fill == self.block_size {
+                self.input_buffer.fill(0.0);
+                self.input_fill = 0;
+                self.overlap
+                    .copy_from_slice(&self.time_buffer[self.block_size..]);
+                self.current = if self.current > 0 {
+                    self.current - 1
+                } else {
+                    segment_count - 1
+                };
+            }
+            processed += count;
+        }
+    }
+
+    /// Bytes held by the impulse response spectra and the input history.
+    pub fn memory_bytes(&self) -> usize {
+        let spectra: usize = self
+            .ir_segments
+            .iter()
+            .chain(&self.input_segments)
+            .map(|segment| segment.re.capacity() + segment.im.capacity())
+            .sum();
+        spectra * std::mem::size_of::<f32>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::simd_kernels::{set_simd_enabled, SIMD_AVAILABLE};
+
+    fn direct_convolution(input: &[f32], ir: &[f32]) -> Vec<f32> {
+        (0..input.len())
+            .map(|n| (0..ir.len().min(n + 1)).map(|k| ir[k] * input[n - k]).sum())
+            .collect()
+    }
+
+    fn render(convolver: &mut PartitionedConvolver, input: &[f32], chunk: usize) -> Vec<f32> {
+        let mut output = vec![0.0; input.len()];
+        for (inp, out) in input.chunks(chunk).zip(output.chunks_mut(chunk)) {
+            convolver.process(inp, out);
+        }
+        output
+    }
+
+    #[test]
+    fn matches_direct_convolution_for_odd_host_buffers() {
+        let ir: Vec<f32> = (0..300)
+            .map(|i| (i as f32 * 0.21).sin() * (-(i as f32) / 80.0).exp())
+            .collect();
+        let input: Vec<f32> = (0..1000)
+            .map(|i| ((i * 7919) % 97) as f32 / 48.0 - 1.0)
+            .collect();
+        let expected = direct_convolution(&input, &ir);
+
+        // 37-sample host buffers straddle the 64-sample partitions.
+        let mut convolver = PartitionedConvolver::new(64, &ir);
+        let output = render(&mut convolver, &input, 37);
+        for (o, e) in output.iter().zip(expected.iter()) {
+            assert!((o - e).abs() < 1e-4, "{} vs {}", o, e);
+        }
+    }
+
+    #[test]
+    fn simd_and_scalar_paths_render_the_same() {
+        let ir: Vec<f32> = (0..500)
+            .map(|i| ((i * 31) % 17) as f32 / 17.0 - 0.5)
+            .collect();
+        let input: Vec<f32> = (0..800).map(|i| (i as f32 * 0.05).sin()).collect();
+
+        set_simd_enabled(false);
+        let scalar = render(&mut PartitionedConvolver::new(128, &ir), &input, 128);
+        set_simd_enabled(true);
+        let simd = render(&mut PartitionedConvolver::new(128, &ir), &input, 128);
+        set_simd_enabled(SIMD_AVAILABLE);
+
+        for (a, b) in scalar.iter().zip(simd.iter()) {
+            assert!((a - b).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn max_partitions_truncates_the_tail() {
+        let ir = vec![0.25; 256];
+        let input: Vec<f32> = (0..512).map(|i| if i == 0 { 1.0 } else { 0.0 }).collect();
+        let mut convolver = PartitionedConvolver::new(64, &ir);
+        convolver.set_max_partitions(Some(2));
+        let output = render(&mut convolver, &input, 64);
+
+        assert!(output[..128].iter().all(|&s| (s - 0.25).abs() < 1e-4));
+        assert!(output[128..].iter().all(|&s| s.abs() < 1e-4));
+    }
+
+    #[test]
+    fn silent_impulse_response_outputs_silence() {
+        let mut convolver = PartitionedConvolver::new(32, &[0.0; 100]);
+        let mut output = vec![1.0; 50];
+        convolver.process(&[0.5; 50], &mut output);
+        assert!(output.iter().all(|&s| s == 0.0));
+    }
+}
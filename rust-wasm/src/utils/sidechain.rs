@@ -0,0 +1,61 @@
+// src/utils/sidechain.rs
+//
+// Helpers for effects that react to a key signal routed in from the voice
+// domain (see `EffectStack::set_effect_sidechain`). The key arrives on
+// `PortId::SidechainInput` and may be anything a voice node outputs: audio, an
+// envelope or a gate.
+
+use rustc_hash::FxHashMap;
+
+use crate::graph::ModulationSource;
+use crate::traits::PortId;
+
+/// The key signal connected to `PortId::SidechainInput`, if any.
+pub fn sidechain_key<'a>(
+    inputs: &FxHashMap<PortId, Vec<ModulationSource<'a>>>,
+    buffer_size: usize,
+) -> Option<&'a [f32]> {
+    inputs
+        .get(&PortId::SidechainInput)
+        .and_then(|sources| sources.first())
+        .map(|src| &src.buffer[..buffer_size.min(src.buffer.len())])
+}
+
+/// Peak follower that turns a key signal into a smooth 0..1 control level.
+pub struct SidechainFollower {
+    attack_coeff: f32,
+    release_coeff: f32,
+    level: f32,
+}
+
+impl SidechainFollower {
+    pub fn new(sample_rate: f32, attack_ms: f32, release_ms: f32) -> Self {
+        Self {
+            attack_coeff: Self::time_to_coeff(attack_ms, sample_rate),
+            release_coeff: Self::time_to_coeff(release_ms, sample_rate),
+            level: 0.0,
+        }
+    }
+
+    #[inline]
+    fn time_to_coeff(time_ms: f32, sample_rate: f32) -> f32 {
+        (-1.0 / (time_ms.max(0.01) * 0.001 * sample_rate)).exp()
+    }
+
+    /// Feeds one key sample and returns the follower level, clamped to 0..1.
+    #[inline]
+    pub fn next(&mut self, key: f32) -> f32 {
+        let input = key.abs().min(1.0);
+        let coeff = if input > self.level {
+            self.attack_coeff
+        } else {
+            self.release_coeff
+        };
+        self.level = input + coeff * (self.level - input);
+        self.level
+    }
+
+    pub fn reset(&mut self) {
+        self.level = 0.0;
+    }
+}
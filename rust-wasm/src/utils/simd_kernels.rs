@@ -0,0 +1,137 @@
+// src/utils/simd_kernels.rs
+//
+// Fixed-width (4 x f32) kernels for the hot loops of the effect chain. The
+// vectors map directly onto wasm simd128 / SSE / NEON registers; every kernel
+// has a scalar twin that produces the same results, selected at runtime so the
+// host can turn SIMD off (unsupported browser, A/B profiling) without a rebuild.
+
+use std::simd::f32x4;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether this build was compiled with a native 128-bit vector unit. Without
+/// one the portable SIMD types are lowered to scalar code and gain nothing.
+pub const SIMD_AVAILABLE: bool = cfg!(any(
+    all(target_arch = "wasm32", target_feature = "simd128"),
+    target_arch = "x86_64",
+    target_arch = "aarch64"
+));
+
+static SIMD_ENABLED: AtomicBool = AtomicBool::new(SIMD_AVAILABLE);
+
+/// True when the SIMD kernels are in use.
+#[inline]
+pub fn simd_enabled() -> bool {
+    SIMD_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Selects the SIMD or scalar kernels. Requests for SIMD are ignored when the
+/// build has no vector unit. Returns the setting actually in effect.
+pub fn set_simd_enabled(enabled: bool) -> bool {
+    let enabled = enabled && SIMD_AVAILABLE;
+    SIMD_ENABLED.store(enabled, Ordering::Relaxed);
+    enabled
+}
+
+/// `acc += a * b` over complex spectra stored as separate real/imaginary arrays.
+/// All slices are processed up to the length of `acc_re`.
+#[inline]
+pub fn complex_multiply_accumulate(
+    acc_re: &mut [f32],
+    acc_im: &mut [f32],
+    a_re: &[f32],
+    a_im: &[f32],
+    b_re: &[f32],
+    b_im: &[f32],
+) {
+    if simd_enabled() {
+        complex_multiply_accumulate_simd(acc_re, acc_im, a_re, a_im, b_re, b_im);
+    } else {
+        complex_multiply_accumulate_scalar(acc_re, acc_im, a_re, a_im, b_re, b_im, 0);
+    }
+}
+
+fn complex_multiply_accumulate_scalar(
+    acc_re: &mut [f32],
+    acc_im: &mut [f32],
+    a_re: &[f32],
+    a_im: &[f32],
+    b_re: &[f32],
+    b_im: &[f32],
+    start: usize,
+) {
+    let len = acc_re.len();
+    for i in start..len {
+        acc_re[i] += a_re[i] * b_re[i] - a_im[i] * b_im[i];
+        acc_im[i] += a_re[i] * b_im[i] + a_im[i] * b_re[i];
+    }
+}
+
+fn complex_multiply_accumulate_simd(
+    acc_re: &mut [f32],
+    acc_im: &mut [f32],
+    a_re: &[f32],
+    a_im: &[f32],
+    b_re: &[f32],
+    b_im: &[f32],
+) {
+    let len = acc_re.len();
+    assert!(acc_im.len() >= len && a_re.len() >= len && a_im.len() >= len);
+    assert!(b_re.len() >= len && b_im.len() >= len);
+
+    let chunks = len / 4;
+    for i in 0..chunks {
+        let offset = i * 4;
+        let range = offset..offset + 4;
+        let ar = f32x4::from_slice(&a_re[range.clone()]);
+        let ai = f32x4::from_slice(&a_im[range.clone()]);
+        let br = f32x4::from_slice(&b_re[range.clone()]);
+        let bi = f32x4::from_slice(&b_im[range.clone()]);
+        let re = f32x4::from_slice(&acc_re[range.clone()]) + (ar * br - ai * bi);
+        let im = f32x4::from_slice(&acc_im[range.clone()]) + (ar * bi + ai * br);
+        re.copy_to_slice(&mut acc_re[range.clone()]);
+        im.copy_to_slice(&mut acc_im[range]);
+    }
+
+    complex_multiply_accumulate_scalar(acc_re, acc_im, a_re, a_im, b_re, b_im, chunks * 4);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simd_and_scalar_complex_mac_agree() {
+        // 13 bins exercises both the vector body and the scalar remainder.
+        let len = 13;
+        let a_re: Vec<f32> = (0..len).map(|i| (i as f32 * 0.37).sin()).collect();
+        let a_im: Vec<f32> = (0..len).map(|i| (i as f32 * 0.91).cos()).collect();
+        let b_re: Vec<f32> = (0..len).map(|i| 0.5 - i as f32 * 0.05).collect();
+        let b_im: Vec<f32> = (0..len).map(|i| (i as f32 * 1.3).sin() * 0.25).collect();
+
+        let mut simd_re = vec![0.1; len];
+        let mut simd_im = vec![-0.2; len];
+        let mut scalar_re = simd_re.clone();
+        let mut scalar_im = simd_im.clone();
+
+        complex_multiply_accumulate_simd(&mut simd_re, &mut simd_im, &a_re, &a_im, &b_re, &b_im);
+        complex_multiply_accumulate_scalar(
+            &mut scalar_re,
+            &mut scalar_im,
+            &a_re,
+            &a_im,
+            &b_re,
+            &b_im,
+            0,
+        );
+
+        for i in 0..len {
+            assert!((simd_re[i] - scalar_re[i]).abs() < 1e-6);
+            assert!((simd_im[i] - scalar_im[i]).abs() < 1e-6);
+        }
+        // (1 + 2i)(3 - i) = 5 + 5i
+        let mut re = [0.0];
+        let mut im = [0.0];
+        complex_multiply_accumulate_scalar(&mut re, &mut im, &[1.0], &[2.0], &[3.0], &[-1.0], 0);
+        assert_eq!((re[0], im[0]), (5.0, 5.0));
+    }
+}
@@ -0,0 +1,123 @@
+// src/utils/smoothing.rs
+
+/// Default time constant for user-facing parameters (mix, feedback, gain...).
+/// Long enough to hide the steps between UI updates, short enough to feel immediate.
+pub const DEFAULT_SMOOTHING_MS: f32 = 5.0;
+
+/// One-pole smoothing coefficient: the fraction of the remaining distance covered
+/// per sample for a time constant of `time_ms`. Zero (or negative) time disables
+/// smoothing and returns 1.0.
+pub fn smoothing_coefficient(sample_rate: f32, time_ms: f32) -> f32 {
+    let samples = sample_rate * (time_ms / 1000.0);
+    if samples > 0.0 {
+        1.0 - (-1.0 / samples).exp()
+    } else {
+        1.0
+    }
+}
+
+/// A parameter that glides towards its target with a one-pole low-pass, so
+/// `set_*` calls from the UI don't produce zipper noise.
+#[derive(Clone, Debug)]
+pub struct SmoothedParam {
+    current: f32,
+    target: f32,
+    coeff: f32,
+    sample_rate: f32,
+}
+
+impl SmoothedParam {
+    pub fn new(value: f32, sample_rate: f32, time_ms: f32) -> Self {
+        Self {
+            current: value,
+            target: value,
+            coeff: smoothing_coefficient(sample_rate, time_ms),
+            sample_rate,
+        }
+    }
+
+    /// Changes the time constant without disturbing the current value.
+    pub fn set_time_ms(&mut self, time_ms: f32) {
+        self.coeff = smoothing_coefficient(self.sample_rate, time_ms);
+    }
+
+    pub fn set_target(&mut self, target: f32) {
+        self.target = target;
+    }
+
+    /// Jumps straight to `value` (use when the node is reset or not yet audible).
+    pub fn set_immediate(&mut self, value: f32) {
+        self.current = value;
+        self.target = value;
+    }
+
+    pub fn target(&self) -> f32 {
+        self.target
+    }
+
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+
+    /// Advances one sample and returns the smoothed value.
+    #[inline(always)]
+    pub fn next_value(&mut self) -> f32 {
+        self.current += (self.target - self.current) * self.coeff;
+        self.current
+    }
+
+    /// Advances `samples` samples at once, for nodes that update parameters per chunk.
+    #[inline]
+    pub fn advance(&mut self, samples: usize) -> f32 {
+        if self.current != self.target {
+            let remaining = (1.0 - self.coeff).powi(samples as i32);
+            self.current = self.target + (self.current - self.target) * remaining;
+            if (self.current - self.target).abs() < 1e-6 {
+                self.current = self.target;
+            }
+        }
+        self.current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reaches_target_after_a_few_time_constants() {
+        let sample_rate = 48_000.0;
+        let mut param = SmoothedParam::new(0.0, sample_rate, 10.0);
+        param.set_target(1.0);
+
+        let first = param.next_value();
+        assert!(first > 0.0 && first < 0.01, "no jump on the first sample");
+
+        // Five time constants (50 ms) leave less than 1% of the step.
+        for _ in 1..(0.05 * sample_rate) as usize {
+            param.next_value();
+        }
+        assert!((param.current() - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn block_advance_matches_per_sample_steps() {
+        let mut per_sample = SmoothedParam::new(0.0, 48_000.0, 2.0);
+        let mut per_block = per_sample.clone();
+        per_sample.set_target(1.0);
+        per_block.set_target(1.0);
+
+        for _ in 0..64 {
+            per_sample.next_value();
+        }
+        per_block.advance(64);
+        assert!((per_sample.current() - per_block.current()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn zero_time_is_immediate() {
+        let mut param = SmoothedParam::new(0.0, 48_000.0, 0.0);
+        param.set_target(0.5);
+        assert_eq!(param.next_value(), 0.5);
+    }
+}
@@ -0,0 +1,35 @@
+// src/utils/tuning.rs
+//
+// Static tuning shared by the analog, wavetable and FM oscillators: coarse
+// (octave/semitone) and fine (cent) fields add up to one detune in cents, which
+// scales whatever frequency the oscillator runs at, the note frequency, an FM
+// operator's ratio of it, or a fixed frequency alike.
+
+/// Total static detune in cents. The octave, semitone and cent fields take
+/// precedence; `legacy_cents` is the single `detune` field of older patches and
+/// is only used when none of them is set.
+pub fn resolve_detune_cents(octaves: f32, semitones: f32, cents: f32, legacy_cents: f32) -> f32 {
+    if octaves != 0.0 || semitones != 0.0 || cents != 0.0 {
+        octaves * 1200.0 + semitones * 100.0 + cents
+    } else {
+        legacy_cents
+    }
+}
+
+/// Frequency multiplier of a detune in cents.
+pub fn detune_ratio(cents: f32) -> f32 {
+    (cents / 1200.0).exp2()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coarse_and_fine_fields_replace_the_legacy_detune() {
+        assert_eq!(resolve_detune_cents(0.0, 0.0, 0.0, 7.0), 7.0);
+        assert_eq!(resolve_detune_cents(1.0, -2.0, 5.0, 7.0), 1005.0);
+        assert!((detune_ratio(1200.0) - 2.0).abs() < 1e-6);
+        assert!((detune_ratio(-700.0) - 2f32.powf(-7.0 / 12.0)).abs() < 1e-6);
+    }
+}
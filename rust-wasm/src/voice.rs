@@ -1,7 +1,9 @@
 use crate::{
     graph::{ModulationTransformation, ModulationType},
-    nodes::{EnvelopePhase, Lfo, LfoRetriggerMode},
-    AudioGraph, AudioNode, Envelope, MacroManager, ModulationTarget, NodeId, PortId,
+    macros::MacroSourceValues,
+    nodes::{EnvelopePhase, Lfo, LfoRetriggerMode, DEFAULT_RELEASE_VELOCITY},
+    AudioGraph, AudioNode, Envelope, MacroManager, MacroMapping, MacroSource, ModulationTarget,
+    NodeId, PortId,
 };
 
 #[derive(Debug)]
@@ -15,7 +17,22 @@ pub struct Voice {
     pub current_gate: f32,
     pub current_frequency: f32,
     pub current_velocity: f32,
+    /// Velocity reached at the end of the next block; `None` holds `current_velocity`.
+    pub velocity_ramp_target: Option<f32>,
+    pub current_pressure: f32,
+    pub current_timbre: f32,
+    /// Velocity of the voice's last note-off.
+    pub current_release_velocity: f32,
+    pub current_mod_wheel: f32,
     pub active: bool,
+    // Whether the graph ran in the last block; node buffers are stale otherwise.
+    rendered: bool,
+    // Samples rendered since the gate last rose.
+    age_samples: u64,
+    gate_high: bool,
+    // Random macro source value of the current note, and the generator state.
+    note_random: f32,
+    random_state: u32,
     macro_manager: MacroManager,
 }
 
@@ -35,7 +52,17 @@ impl Voice {
             current_gate: 0.0,
             current_frequency: 440.0,
             current_velocity: 1.0,
+            velocity_ramp_target: None,
+            current_pressure: 0.0,
+            current_timbre: 0.0,
+            current_release_velocity: DEFAULT_RELEASE_VELOCITY,
+            current_mod_wheel: 0.0,
             active: false,
+            rendered: false,
+            age_samples: 0,
+            gate_high: false,
+            note_random: 0.0,
+            random_state: 0x9e37_79b9 ^ id as u32,
             macro_manager,
         }
     }
@@ -50,12 +77,30 @@ impl Voice {
         // Reset state
         self.current_gate = 0.0;
         self.current_frequency = 440.0;
+        self.velocity_ramp_target = None;
+        self.current_pressure = 0.0;
+        self.current_timbre = 0.0;
+        self.current_release_velocity = DEFAULT_RELEASE_VELOCITY;
         self.active = false;
+        self.rendered = false;
+        self.age_samples = 0;
+        self.gate_high = false;
 
-        // Clear macro manager
+        // Clear macro manager; the routes pointed into the cleared graph.
+        self.macro_manager.clear_routes(&mut self.graph.buffer_pool);
         self.macro_manager.clear(&mut self.graph.buffer_pool);
     }
 
+    /// Output of a node in this voice for the last block, or `None` if the voice
+    /// was silent and skipped processing.
+    pub fn node_output(&self, node_id: NodeId, port: PortId) -> Option<&[f32]> {
+        if self.rendered {
+            self.graph.node_output(node_id, port)
+        } else {
+            None
+        }
+    }
+
     pub fn set_output_node(&mut self, node: NodeId) {
         self.output_node = node;
         self.graph.set_output_node(node);
@@ -80,7 +125,8 @@ impl Voice {
 
         let gate_active = self.current_gate > 0.0;
         let has_active_envelopes = self.has_active_envelopes();
-        let has_audio_output = self.has_significant_audio_output();
+        // Node buffers are stale when the voice skipped processing.
+        let has_audio_output = self.rendered && self.has_significant_audio_output();
 
         self.active = gate_active || has_active_envelopes || has_audio_output;
     }
@@ -89,7 +135,7 @@ impl Voice {
     fn has_active_envelopes(&self) -> bool {
         self.graph.nodes.iter().any(|(_id, node)| {
             if let Some(env) = node.as_any().downcast_ref::<Envelope>() {
-                env.is_active() && env.get_phase() == EnvelopePhase::Release
+                env.is_active() && (env.get_phase() == EnvelopePhase::Release || env.is_droning())
             } else {
                 false
             }
@@ -98,12 +144,17 @@ impl Voice {
 
     // Check if the voice is still producing significant audio output
     fn has_significant_audio_output(&self) -> bool {
-        // This is a new method to detect if the voice is still producing sound
-        // even after envelopes have completed
+        // -80dB threshold (very quiet but still audible)
+        const SILENCE_THRESHOLD: f32 = 0.0001; // approximately -80dB
 
-        // If we don't have an output node, we can't check
-        if self.output_node == NodeId::default() {
-            return false;
+        self.output_rms() > SILENCE_THRESHOLD
+    }
+
+    /// RMS of the left output over the last rendered block (0 when the voice
+    /// was skipped or has no output node).
+    pub fn output_rms(&self) -> f32 {
+        if !self.rendered || self.output_node == NodeId::default() {
+            return 0.0;
         }
 
         // Get the output buffer indices
@@ -112,25 +163,28 @@ impl Voice {
             .node_buffers
             .get(&(self.output_node, PortId::AudioOutput0))
         {
-            // Analyze the buffer to see if it contains significant audio
             let output_buffer = self.graph.buffer_pool.copy_out(left_buffer_idx);
-
-            // Check if the output has any significant audio content
-            // Using RMS to detect if there's still meaningful audio
             let mut sum_squared = 0.0;
             for &sample in output_buffer {
                 sum_squared += sample * sample;
             }
 
-            let rms = (sum_squared / output_buffer.len() as f32).sqrt();
+            return (sum_squared / output_buffer.len() as f32).sqrt();
+        }
 
-            // -80dB threshold (very quiet but still audible)
-            const SILENCE_THRESHOLD: f32 = 0.0001; // approximately -80dB
+        0.0
+    }
 
-            return rms > SILENCE_THRESHOLD;
+    /// Silences the voice immediately: every node is reset (envelopes back to
+    /// idle, delay lines cleared) and the voice stops rendering until its next
+    /// gate.
+    pub fn cull(&mut self) {
+        for node in self.graph.nodes.values_mut() {
+            node.reset();
         }
-
-        false
+        self.active = false;
+        self.rendered = false;
+        self.gate_high = false;
     }
 
     //this doesn't quite work yet, dont use
@@ -156,6 +210,31 @@ impl Voice {
         self.current_frequency
     }
 
+    /// Samples rendered since the voice's gate last rose.
+    pub fn age_samples(&self) -> u64 {
+        self.age_samples
+    }
+
+    /// Where the voice is in its envelopes: the least advanced phase of any
+    /// running envelope, so a voice only counts as releasing once all of them
+    /// are. `Idle` when no envelope is running.
+    pub fn envelope_phase(&self) -> EnvelopePhase {
+        let rank = |phase: EnvelopePhase| match phase {
+            EnvelopePhase::Attack => 0,
+            EnvelopePhase::Decay => 1,
+            EnvelopePhase::Sustain => 2,
+            EnvelopePhase::Release => 3,
+            EnvelopePhase::Idle => 4,
+        };
+        self.graph
+            .nodes
+            .values()
+            .filter_map(|node| node.as_any().downcast_ref::<Envelope>())
+            .map(Envelope::get_phase)
+            .min_by_key(|&phase| rank(phase))
+            .unwrap_or(EnvelopePhase::Idle)
+    }
+
     pub fn add_macro_modulation(
         &mut self,
         macro_index: usize,
@@ -165,8 +244,7 @@ impl Voice {
         modulation_type: ModulationType,
         modulation_transform: ModulationTransformation,
     ) -> Result<(), String> {
-        // First add the modulation target to the macro manager to get the buffer
-        self.macro_manager.add_modulation(
+        self.add_macro_mapping(
             macro_index,
             ModulationTarget {
                 node_id: target_node,
@@ -174,14 +252,32 @@ impl Voice {
                 amount,
                 modulation_type,
                 modulation_transform,
+                mapping: MacroMapping::default(),
+                mapped_buffer_idx: None,
             },
-        )?;
+        )
+    }
 
-        // Get the buffer index that the macro manager is using
-        let buffer_idx = self
-            .macro_manager
-            .get_macro_buffer_idx(macro_index)
-            .ok_or_else(|| "Failed to get macro buffer index".to_string())?;
+    /// Routes a macro to `target` through the target's range mapping. A macro can
+    /// drive any number of targets, each with its own mapping.
+    pub fn add_macro_mapping(
+        &mut self,
+        macro_index: usize,
+        target: ModulationTarget,
+    ) -> Result<(), String> {
+        let ModulationTarget {
+            node_id: target_node,
+            port_id: target_port,
+            amount,
+            modulation_type,
+            modulation_transform,
+            ..
+        } = target;
+        // The macro manager hands back the buffer this target reads: the macro
+        // buffer itself, or a mapped copy of it.
+        let buffer_idx =
+            self.macro_manager
+                .add_modulation(macro_index, target, &mut self.graph.buffer_pool)?;
 
         // Since the macro modulation isn't coming from a regular node,
         // we supply a reserved NodeId (for example, NodeId(usize::MAX)) as the source.
@@ -201,21 +297,65 @@ impl Voice {
         Ok(())
     }
 
+    /// Replaces the mapping of an existing macro route, keeping its amount,
+    /// modulation type and transform. Returns false if there is no such route.
+    pub fn set_macro_mapping(
+        &mut self,
+        macro_index: usize,
+        target_node: NodeId,
+        target_port: PortId,
+        mapping: MacroMapping,
+    ) -> Result<bool, String> {
+        let Some(existing) = self
+            .macro_manager
+            .targets(macro_index)
+            .iter()
+            .find(|t| t.node_id == target_node && t.port_id == target_port)
+            .cloned()
+        else {
+            return Ok(false);
+        };
+        self.remove_macro_route(macro_index, target_node, target_port)?;
+        self.add_macro_mapping(
+            macro_index,
+            ModulationTarget {
+                mapping,
+                mapped_buffer_idx: None,
+                ..existing
+            },
+        )?;
+        Ok(true)
+    }
+
     pub fn remove_macro_route(
         &mut self,
         macro_index: usize,
         target_node: NodeId,
         target_port: PortId,
     ) -> Result<bool, String> {
-        self.macro_manager
-            .remove_target(macro_index, target_node, target_port)?;
+        let buffers = self.macro_manager.remove_target(
+            macro_index,
+            target_node,
+            target_port,
+            &mut self.graph.buffer_pool,
+        )?;
 
-        if let Some(buffer_idx) = self.macro_manager.get_macro_buffer_idx(macro_index) {
+        for buffer_idx in &buffers {
             self.graph
-                .remove_macro_connection(buffer_idx, target_node, target_port);
+                .remove_macro_connection(*buffer_idx, target_node, target_port);
         }
 
-        Ok(true)
+        Ok(!buffers.is_empty())
+    }
+
+    /// Every macro route in this voice as `(macro_index, target)`.
+    pub fn macro_routes(&self) -> impl Iterator<Item = (usize, &ModulationTarget)> {
+        (0..self.macro_manager.num_macros()).flat_map(move |macro_index| {
+            self.macro_manager
+                .targets(macro_index)
+                .iter()
+                .map(move |target| (macro_index, target))
+        })
     }
 
     pub fn clear_macros(&mut self) {
@@ -227,6 +367,29 @@ impl Voice {
             .update_macro(macro_index, values, &mut self.graph.buffer_pool)
     }
 
+    /// Drives a macro from the voice's own performance data. See
+    /// `MacroManager::set_source`.
+    pub fn set_macro_source(
+        &mut self,
+        macro_index: usize,
+        source: Option<MacroSource>,
+        amount: f32,
+    ) -> Result<(), String> {
+        self.macro_manager.set_source(macro_index, source, amount)
+    }
+
+    /// Sparse macro update, expanded at the start of the next block. See
+    /// `MacroManager::set_macro_sparse`.
+    pub fn set_macro_sparse(
+        &mut self,
+        macro_index: usize,
+        value: f32,
+        ramp_target: Option<f32>,
+    ) -> Result<(), String> {
+        self.macro_manager
+            .set_macro_sparse(macro_index, value, ramp_target)
+    }
+
     pub fn process_audio(
         &mut self,
         gate_buffer: &[f32],
@@ -238,8 +401,23 @@ impl Voice {
         // has_significant_audio_output to have valid output buffers to analyze
 
         let gate_present = gate_buffer.iter().any(|&g| g > 0.0);
+        let gate_high = gate_present || self.current_gate > 0.0;
+        if gate_high && !self.gate_high {
+            self.note_random = self.next_random();
+        }
+        self.macro_manager.drive_sources(&MacroSourceValues {
+            velocity: self.current_velocity,
+            aftertouch: self.current_pressure,
+            mod_wheel: self.current_mod_wheel,
+            random: self.note_random,
+        });
+        self.macro_manager
+            .expand_sparse_updates(&mut self.graph.buffer_pool);
+        self.macro_manager
+            .update_mapped_buffers(&mut self.graph.buffer_pool);
 
-        if self.is_active() || gate_present {
+        self.rendered = self.is_active() || gate_present;
+        if self.rendered {
             // Normal processing path for active voices
             let single_gate = [self.current_gate];
             let gate_slice = if gate_buffer.is_empty() {
@@ -258,7 +436,14 @@ impl Voice {
             };
 
             self.graph.set_frequency(frequency_slice);
-            self.graph.set_velocity(&[self.current_velocity]);
+            match self.velocity_ramp_target {
+                Some(target) => self.graph.set_velocity_ramp(self.current_velocity, target),
+                None => self.graph.set_velocity(&[self.current_velocity]),
+            }
+            self.graph.set_pressure(&[self.current_pressure]);
+            self.graph.set_timbre(&[self.current_timbre]);
+            self.graph
+                .set_release_velocity(&[self.current_release_velocity]);
             self.graph.process_audio_with_macros(
                 Some(&self.macro_manager),
                 output_left,
@@ -274,6 +459,12 @@ impl Voice {
             output_right.fill(0.0);
         }
 
+        if gate_high && !self.gate_high {
+            self.age_samples = 0;
+        }
+        self.gate_high = gate_high;
+        self.age_samples += output_left.len() as u64;
+
         // Update the active state for the next cycle
         self.update_active_state();
 
@@ -289,6 +480,16 @@ impl Voice {
         // }
     }
 
+    /// Next value of the voice's xorshift generator, in 0..1.
+    fn next_random(&mut self) -> f32 {
+        let mut x = self.random_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.random_state = x;
+        (x >> 8) as f32 / (1u32 << 24) as f32
+    }
+
     fn has_free_running_lfos(&self) -> bool {
         self.graph.nodes.iter().any(|(_id, node)| {
             if let Some(lfo) = node.as_any().downcast_ref::<Lfo>() {
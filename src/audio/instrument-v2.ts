@@ -14,7 +14,7 @@
 import { createStandardAudioWorklet } from './audio-processor-loader';
 import type OscillatorState from './models/OscillatorState';
 import { type NoiseState, type NoiseUpdate } from './types/noise';
-import type { Patch } from './types/preset-types';
+import type { MacroRouteMapping, Patch } from './types/preset-types';
 import type {
   ChorusState,
   ConvolverState,
@@ -331,7 +331,7 @@ export default class InstrumentV2 {
     }
   }
 
-  public connectMacroRoute(payload: { macroIndex: number; targetId: string; targetPort: PortId; amount: number; modulationType: WasmModulationType; modulationTransformation: ModulationTransformation }): void {
+  public connectMacroRoute(payload: { macroIndex: number; targetId: string; targetPort: PortId; amount: number; modulationType: WasmModulationType; modulationTransformation: ModulationTransformation } & MacroRouteMapping): void {
     if (!this.workletNode) {
       return;
     }
@@ -343,6 +343,10 @@ export default class InstrumentV2 {
       amount: payload.amount,
       modulationType: payload.modulationType,
       modulationTransformation: payload.modulationTransformation,
+      min: payload.min,
+      max: payload.max,
+      curve: payload.curve,
+      polarity: payload.polarity,
     });
   }
 
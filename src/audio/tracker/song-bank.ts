@@ -1607,6 +1607,10 @@ export class TrackerSongBank {
           amount,
           modulationType,
           modulationTransformation,
+          min: route.min,
+          max: route.max,
+          curve: route.curve,
+          polarity: route.polarity,
         });
       });
     }
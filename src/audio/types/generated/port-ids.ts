@@ -29,4 +29,9 @@ export enum PortId {
   ArpGate = 25,
   CombinedGate = 26,
   SampleOffset = 27,
+  SidechainInput = 28,
+  ClockInput = 29,
+  FormantMod = 30,
+  DriveMod = 31,
+  ReleaseVelocity = 32,
 }
@@ -141,7 +141,21 @@ export interface SynthState {
   instrumentGain?: number;
 }
 
-export interface MacroRouteState {
+/** How a macro knob (0-1) maps onto one target. Omitted fields use the identity mapping. */
+export type MacroPolarity = 'unipolar' | 'bipolar';
+
+export interface MacroRouteMapping {
+  /** Value sent at the bottom of the knob's travel (default 0) */
+  min?: number;
+  /** Value sent at the top of the knob's travel (default 1) */
+  max?: number;
+  /** Response curve; 0 is linear, positive exponential, negative logarithmic */
+  curve?: number;
+  /** Bipolar mappings centre the range on the knob's midpoint */
+  polarity?: MacroPolarity;
+}
+
+export interface MacroRouteState extends MacroRouteMapping {
   macroIndex: number;
   targetId: string;
   targetPort: number;
@@ -229,6 +229,11 @@ export const PORT_LABELS: Record<PortId, string> = {
   [PortId.ArpGate]: 'Arpeggio gate',
   [PortId.CombinedGate]: 'Combined gate',
   [PortId.SampleOffset]: 'Sample Offset',
+  [PortId.SidechainInput]: 'Sidechain',
+  [PortId.ClockInput]: 'Clock',
+  [PortId.FormantMod]: 'Formant',
+  [PortId.DriveMod]: 'Drive',
+  [PortId.ReleaseVelocity]: 'Release Velocity',
 };
 
 export interface ModulationTargetOption {
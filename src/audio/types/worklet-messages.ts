@@ -26,6 +26,7 @@ import type {
 } from './synth-layout';
 import type { WasmModulationType, ModulationTransformation } from 'app/public/wasm/audio_processor';
 import type { PortId } from './generated/port-ids';
+import type { MacroRouteMapping } from './preset-types';
 
 // ============================================================================
 // Base Message Types
@@ -229,7 +230,7 @@ export interface RemoveConnectionMessage extends BaseMessage {
   targetPort: number;
 }
 
-export interface ConnectMacroMessage extends BaseMessage {
+export interface ConnectMacroMessage extends BaseMessage, MacroRouteMapping {
   type: 'connectMacro';
   macroIndex: number;
   targetId: string;
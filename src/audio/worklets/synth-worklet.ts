@@ -23,6 +23,7 @@ import {
   convertRawModulationType,
 } from '../types/synth-layout';
 import { type NoiseUpdate } from '../types/noise.js';
+import type { MacroRouteMapping } from '../types/preset-types';
 import {
   AnalogOscillatorStateUpdate,
   AudioEngine,
@@ -335,7 +336,9 @@ class SynthAudioProcessor extends AudioWorkletProcessor {
 
     try {
       const cpu = this.audioEngine.get_cpu_usage();
-      this.port.postMessage({ type: 'cpuUsage', cpu });
+      // Overload protection actions (quality drops, culled voices) since the last poll.
+      const diagnostics = this.audioEngine.take_diagnostics();
+      this.port.postMessage({ type: 'cpuUsage', cpu, diagnostics });
     } catch (error) {
       // Silently skip if there's a borrow conflict (happens during audio processing)
       // This is expected and not an error condition
@@ -347,7 +350,7 @@ class SynthAudioProcessor extends AudioWorkletProcessor {
     this.handleRequestSync();
   }
 
-  private handleConnectMacro(data: { macroIndex: number; targetId: string; targetPort: PortId; amount: number; modulationType: WasmModulationType; modulationTransformation: ModulationTransformation }) {
+  private handleConnectMacro(data: { macroIndex: number; targetId: string; targetPort: PortId; amount: number; modulationType: WasmModulationType; modulationTransformation: ModulationTransformation } & MacroRouteMapping) {
     if (!this.audioEngine) return;
     // Always wire macros across the active voice count; voiceLayouts can be a single
     // canonical layout, so fall back to the configured voice count instead of length.
@@ -381,6 +384,28 @@ class SynthAudioProcessor extends AudioWorkletProcessor {
         }
       }
     }
+
+    const hasMapping =
+      data.min !== undefined ||
+      data.max !== undefined ||
+      data.curve !== undefined ||
+      data.polarity !== undefined;
+    if (hasMapping && data.amount > 0) {
+      try {
+        this.audioEngine.set_macro_mapping(
+          undefined,
+          data.macroIndex,
+          data.targetId,
+          data.targetPort,
+          data.min ?? 0,
+          data.max ?? 1,
+          data.curve ?? 0,
+          data.polarity === 'bipolar',
+        );
+      } catch (err) {
+        console.error('Failed to set macro mapping:', err);
+      }
+    }
   }
 
   private handleCreateNode(data: { node?: VoiceNodeType; nodeType?: VoiceNodeType }) {
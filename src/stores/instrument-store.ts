@@ -6,6 +6,7 @@ import InstrumentV2 from 'src/audio/instrument-v2';
 import { AudioSyncManager } from 'src/audio/sync-manager';
 import type { PortId } from 'app/public/wasm/audio_processor';
 import type { ModulationTransformation, WasmModulationType } from 'app/public/wasm/audio_processor';
+import type { MacroRouteMapping } from 'src/audio/types/preset-types';
 
 interface InstrumentStoreState {
   audioSystem: AudioSystem | null;
@@ -27,7 +28,7 @@ interface InstrumentStoreActions {
   waitForInstrumentReady(timeoutMs?: number): Promise<boolean>;
   setMacro(macroIndex: number, value: number): void;
   applyMacrosToInstrument(): void;
-  connectMacroRoute(payload: { macroIndex: number; targetId: string; targetPort: PortId; amount: number; modulationType: WasmModulationType; modulationTransformation: ModulationTransformation }): void;
+  connectMacroRoute(payload: { macroIndex: number; targetId: string; targetPort: PortId; amount: number; modulationType: WasmModulationType; modulationTransformation: ModulationTransformation } & MacroRouteMapping): void;
   setMacros(values: number[]): void;
   setInstrumentGain(gain: number): void;
   /** Swap currentInstrument to an external instrument (e.g., from song bank for live editing) */
@@ -121,7 +122,7 @@ export const useInstrumentStore = defineStore<'instrumentStore', InstrumentStore
       });
     },
 
-    connectMacroRoute(payload: { macroIndex: number; targetId: string; targetPort: PortId; amount: number; modulationType: WasmModulationType; modulationTransformation: ModulationTransformation }) {
+    connectMacroRoute(payload: { macroIndex: number; targetId: string; targetPort: PortId; amount: number; modulationType: WasmModulationType; modulationTransformation: ModulationTransformation } & MacroRouteMapping) {
       if (!this.currentInstrument) return;
       this.currentInstrument.connectMacroRoute(payload);
     },
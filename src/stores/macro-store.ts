@@ -3,8 +3,9 @@ import { uid } from 'quasar';
 import { useInstrumentStore } from './instrument-store';
 import { ModulationTransformation, WasmModulationType, type PortId } from 'app/public/wasm/audio_processor';
 import type { NodeConnection } from 'src/audio/types/synth-layout';
+import type { MacroRouteMapping } from 'src/audio/types/preset-types';
 
-export interface MacroRoute {
+export interface MacroRoute extends MacroRouteMapping {
   id: string;
   macroIndex: number;
   targetId: string;
@@ -32,6 +33,10 @@ export const useMacroStore = defineStore('macroStore', {
         amount: route.amount,
         modulationType: route.modulationType,
         modulationTransformation: route.modulationTransformation,
+        min: route.min,
+        max: route.max,
+        curve: route.curve,
+        polarity: route.polarity,
       };
       this.routes.push(newRoute);
       this.applyRoute(newRoute);
@@ -60,6 +65,10 @@ export const useMacroStore = defineStore('macroStore', {
         amount: route.amount,
         modulationType: route.modulationType,
         modulationTransformation: route.modulationTransformation,
+        min: route.min,
+        max: route.max,
+        curve: route.curve,
+        polarity: route.polarity,
       });
     },
     reapplyAllRoutes() {
@@ -70,7 +79,7 @@ export const useMacroStore = defineStore('macroStore', {
     reset() {
       this.routes = [];
     },
-    setFromPatch(macros?: { values?: number[]; routes?: { macroIndex: number; targetId: string; targetPort: number; amount: number; modulationType?: WasmModulationType; modulationTransformation?: ModulationTransformation }[] }) {
+    setFromPatch(macros?: { values?: number[]; routes?: ({ macroIndex: number; targetId: string; targetPort: number; amount: number; modulationType?: WasmModulationType; modulationTransformation?: ModulationTransformation } & MacroRouteMapping)[] }) {
       const instrumentStore = useInstrumentStore();
       if (macros?.values) {
         instrumentStore.setMacros(macros.values);
@@ -84,6 +93,10 @@ export const useMacroStore = defineStore('macroStore', {
         amount: route.amount,
         modulationType: route.modulationType ?? WasmModulationType.VCA,
         modulationTransformation: route.modulationTransformation ?? ModulationTransformation.None,
+        min: route.min,
+        max: route.max,
+        curve: route.curve,
+        polarity: route.polarity,
       }));
 
       this.reapplyAllRoutes();
@@ -3,6 +3,7 @@ import { nextTick } from 'vue';
 import type {
   AudioAsset,
   Bank,
+  MacroRouteMapping,
   Patch,
   PatchMetadata,
 } from 'src/audio/types/preset-types';
@@ -294,14 +295,18 @@ export const usePatchStore = defineStore('patchStore', {
                   modulationType: route.modulationType as WasmModulationType | undefined,
                   modulationTransformation:
                     route.modulationTransformation as ModulationTransformation | undefined,
-                })) as {
+                  min: route.min,
+                  max: route.max,
+                  curve: route.curve,
+                  polarity: route.polarity,
+                })) as ({
                   macroIndex: number;
                   targetId: string;
                   targetPort: number;
                   amount: number;
                   modulationType?: WasmModulationType;
                   modulationTransformation?: ModulationTransformation;
-                }[],
+                } & MacroRouteMapping)[],
               }
             : undefined,
         );
@@ -599,6 +604,10 @@ export const usePatchStore = defineStore('patchStore', {
             amount: route.amount,
             modulationType: route.modulationType,
             modulationTransformation: route.modulationTransformation,
+            min: route.min,
+            max: route.max,
+            curve: route.curve,
+            polarity: route.polarity,
           })),
         };
 
@@ -785,6 +794,10 @@ export const usePatchStore = defineStore('patchStore', {
             amount: route.amount,
             modulationType: route.modulationType,
             modulationTransformation: route.modulationTransformation,
+            min: route.min,
+            max: route.max,
+            curve: route.curve,
+            polarity: route.polarity,
           })),
         };
 
@@ -1048,6 +1061,10 @@ export const usePatchStore = defineStore('patchStore', {
           amount: route.amount,
           modulationType: route.modulationType,
           modulationTransformation: route.modulationTransformation,
+          min: route.min,
+          max: route.max,
+          curve: route.curve,
+          polarity: route.polarity,
         })),
       };
 